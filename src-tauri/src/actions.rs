@@ -1,21 +1,58 @@
+use crate::app_overrides;
 #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
 use crate::apple_intelligence;
 use crate::audio_feedback::{play_feedback_sound, play_feedback_sound_blocking, SoundType};
+use crate::cancellation::{self, Interrupted};
+use crate::chain::{self, ChainInput};
+#[cfg(feature = "llama-cpp")]
+use crate::llama_cpp;
+use crate::llm_client::LlmClient;
+use crate::llm_error::LlmError;
+use crate::llm_types::{
+    format_structured_output, parse_structured_output, ChatCompletionRequest,
+    ChatCompletionRequestBuilder, ChatRequestMessage, ChatRole, ContentPart, InputAudio,
+    ResponseFormat, ToolChoice, Usage,
+};
 use crate::managers::audio::AudioRecordingManager;
+use crate::managers::conversation::ConversationManager;
 use crate::managers::history::HistoryManager;
 use crate::managers::transcription::TranscriptionManager;
-use crate::settings::{get_settings, AppSettings, APPLE_INTELLIGENCE_PROVIDER_ID};
+use crate::managers::usage::UsageManager;
+use crate::modes::BUILTIN_MODES;
+use crate::notifications::{self, NotificationKind};
+use crate::output::{self, ActionOutput};
+use crate::pipeline::{self, PipelineState};
+use crate::preview;
+use crate::profiles;
+use crate::progress::{self, OperationKind};
+use crate::retry::{self, send_with_retry, RetryPolicy};
+use crate::scripting::{self, HookContext, HookResult};
+use crate::selection;
+use crate::settings::{
+    get_settings, ActionConfig, AppSettings, ConcurrencyPolicy, LongTranscriptStrategy,
+    OutputTarget, PasteMethod, PostProcessProvider, APPLE_INTELLIGENCE_PROVIDER_ID,
+    LLAMA_CPP_PROVIDER_ID,
+};
+use crate::shell_command;
 use crate::shortcut;
-use crate::tray::{change_tray_icon, TrayIconState};
-use crate::utils::{self, show_recording_overlay, show_transcribing_overlay};
+use crate::shutdown;
+use crate::snippets;
+use crate::streaming::StreamingInjection;
+use crate::template;
+use crate::throttle;
+use crate::token_budget;
+use crate::tools;
+use crate::utils;
+use crate::voice_command::{self, VoiceIntent, VOICE_COMMAND_ACTION_ID};
 use ferrous_opencc::{config::BuiltinConfig, OpenCC};
-use log::{debug, error};
+use log::{debug, error, warn};
 use once_cell::sync::Lazy;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tauri::AppHandle;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 // Shortcut Action Trait
 pub trait ShortcutAction: Send + Sync {
@@ -27,16 +64,22 @@ pub trait ShortcutAction: Send + Sync {
 struct TranscribeAction;
 
 /// Online provider configuration for audio transcription
+#[derive(Clone)]
 struct OnlineTranscriptionProvider {
     provider_id: String,
     base_url: String,
     model: String,
+    /// Key used for the current attempt
     api_key: String,
+    /// Main key first, then fallbacks
+    api_keys: Vec<String>,
+    /// Client with the user's proxy and CA settings
+    http_client: reqwest::Client,
 }
 
 /// Convert f32 audio samples to WAV format in memory
 /// Shared by both OpenAI-compatible and Gemini transcription flows
-fn convert_samples_to_wav(audio_samples: &[f32]) -> Result<Vec<u8>, String> {
+pub fn convert_samples_to_wav(audio_samples: &[f32]) -> Result<Vec<u8>, String> {
     use hound::{WavSpec, WavWriter};
     use std::io::Cursor;
 
@@ -72,10 +115,18 @@ async fn transcribe_online(
     audio_samples: Vec<f32>,
     language: Option<String>,
     translate_to_english: bool,
-) -> Result<String, String> {
+    retry_policy: RetryPolicy,
+) -> Result<String, LlmError> {
     // Use different API flow for Gemini (chat completions with audio)
     if provider.provider_id == "gemini" {
-        return transcribe_online_gemini(provider, audio_samples, language, translate_to_english).await;
+        return transcribe_online_gemini(
+            provider,
+            audio_samples,
+            language,
+            translate_to_english,
+            retry_policy,
+        )
+        .await;
     }
     
     // Standard OpenAI-compatible /audio/transcriptions flow for OpenAI and Groq
@@ -116,72 +167,76 @@ async fn transcribe_online(
     };
     info!("[Cloud Transcription] Sending request to: {} (translate: {}, whisper: {})", endpoint, translate_to_english, is_whisper_model);
 
-    // Create multipart form
-    let form = reqwest::multipart::Form::new()
-        .text("model", provider.model.clone())
-        .part(
-            "file",
-            reqwest::multipart::Part::bytes(wav_data)
-                .file_name("audio.wav")
-                .mime_str("audio/wav")
-                .map_err(|e| {
-                    error!("[Cloud Transcription] Failed to set MIME type: {}", e);
-                    format!("Failed to set MIME type: {}", e)
-                })?,
-        );
+    // Collect the text fields of the multipart form
+    let mut form_fields: Vec<(&str, String)> = vec![("model", provider.model.clone())];
 
     // Add language if specified and not "auto" (not used for translations endpoint)
-    let form = if !use_translations_endpoint {
+    if !use_translations_endpoint {
         if let Some(ref lang) = language {
             if lang != "auto" {
                 info!("[Cloud Transcription] Using language: {}", lang);
-                form.text("language", lang.clone())
-            } else {
-                form
+                form_fields.push(("language", lang.clone()));
             }
-        } else {
-            form
         }
-    } else {
-        form
-    };
+    }
 
     // Detect if this is a GPT-4o transcribe model (uses "instructions" instead of "prompt")
     let is_gpt4o_transcribe = provider.model.to_lowercase().contains("gpt-4o");
     
     // For GPT-4o transcribe models with translation enabled, use the "instructions" field
     // For other non-Whisper models, use the "prompt" field
-    let form = if translate_to_english && !is_whisper_model {
+    if translate_to_english && !is_whisper_model {
         if is_gpt4o_transcribe {
             info!("[Cloud Transcription] Adding translation instructions for GPT-4o transcribe model");
-            form.text("instructions", "Transcribe this audio and translate it to English. Output only the translated English text.")
+            form_fields.push(("instructions", "Transcribe this audio and translate it to English. Output only the translated English text.".to_string()));
         } else {
             info!("[Cloud Transcription] Adding translation prompt for non-Whisper model");
-            form.text("prompt", "Please transcribe this audio and translate it to English.")
+            form_fields.push((
+                "prompt",
+                "Please transcribe this audio and translate it to English.".to_string(),
+            ));
         }
-    } else {
-        form
-    };
+    }
 
-    // Create HTTP client with authorization header
-    let client = reqwest::Client::new();
+    // Authorization is added per request below
+    let client = provider.http_client.clone();
     info!("[Cloud Transcription] Sending POST request...");
-    
-    let response = client
-        .post(&endpoint)
-        .bearer_auth(&provider.api_key)
-        .multipart(form)
-        .send()
-        .await
-        .map_err(|e| {
-            error!("[Cloud Transcription] Network error - failed to send request: {}", e);
-            format!("Failed to send transcription request: {}", e)
-        })?;
+
+    // Multipart bodies can't be cloned, so the form is rebuilt for every attempt
+    let response = send_with_retry(&retry_policy, "Cloud Transcription", || {
+        let file_part = reqwest::multipart::Part::bytes(wav_data.clone())
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| {
+                error!("[Cloud Transcription] Failed to set MIME type: {}", e);
+                format!("Failed to set MIME type: {}", e)
+            })?;
+        let form = form_fields
+            .iter()
+            .fold(reqwest::multipart::Form::new(), |form, (name, value)| {
+                form.text(*name, value.clone())
+            })
+            .part("file", file_part);
+
+        Ok(client
+            .post(&endpoint)
+            .bearer_auth(&provider.api_key)
+            .multipart(form))
+    })
+    .await
+    .map_err(|e| {
+        error!(
+            "[Cloud Transcription] Network error - failed to send request: {}",
+            e
+        );
+        e
+    })?;
 
     let status = response.status();
     info!("[Cloud Transcription] Received response with status: {}", status);
 
     if !status.is_success() {
+        let retry_after = retry::retry_after(&response);
         let error_text = response
             .text()
             .await
@@ -190,28 +245,28 @@ async fn transcribe_online(
             "[Cloud Transcription] API ERROR - Status: {}, Provider: {}, Model: {}, Response: {}",
             status, base_url, provider.model, error_text
         );
-        return Err(format!(
-            "Transcription request failed ({}): {}",
-            status, error_text
-        ));
+        return Err(LlmError::from_response(status, retry_after, &error_text));
     }
 
     // Parse the response - OpenAI returns { "text": "..." }
-    let response_text = response
-        .text()
-        .await
-        .map_err(|e| {
-            error!("[Cloud Transcription] Failed to read response body: {}", e);
-            format!("Failed to read response: {}", e)
-        })?;
+    let response_text = response.text().await.map_err(|e| {
+        error!("[Cloud Transcription] Failed to read response body: {}", e);
+        LlmError::from(e)
+    })?;
 
-    debug!("[Cloud Transcription] Raw response: {}", response_text);
+    debug!(
+        target: crate::logging::TRANSCRIPT_TARGET,
+        "[Cloud Transcription] Raw response: {}", response_text
+    );
 
-    let parsed: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| {
-            error!("[Cloud Transcription] Failed to parse JSON response: {}. Raw: {}", e, response_text);
-            format!("Failed to parse response: {}", e)
-        })?;
+    let parsed: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+        error!(
+            target: crate::logging::TRANSCRIPT_TARGET,
+            "[Cloud Transcription] Failed to parse JSON response: {}. Raw: {}",
+            e, response_text
+        );
+        LlmError::parse(format!("Failed to parse response: {}", e))
+    })?;
 
     let text = parsed
         .get("text")
@@ -233,7 +288,8 @@ async fn transcribe_online_gemini(
     audio_samples: Vec<f32>,
     language: Option<String>,
     translate_to_english: bool,
-) -> Result<String, String> {
+    retry_policy: RetryPolicy,
+) -> Result<String, LlmError> {
     use log::info;
     use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
@@ -281,47 +337,45 @@ async fn transcribe_online_gemini(
     };
 
     // Build request body with multimodal content (text + audio)
-    let request_body = serde_json::json!({
-        "model": provider.model,
-        "messages": [{
-            "role": "user",
-            "content": [
-                {
-                    "type": "text",
-                    "text": transcription_prompt
+    let request_body = ChatCompletionRequest::builder(provider.model.clone())
+        .message(ChatRequestMessage::parts(
+            ChatRole::User,
+            vec![
+                ContentPart::Text {
+                    text: transcription_prompt,
                 },
-                {
-                    "type": "input_audio",
-                    "input_audio": {
-                        "data": audio_base64,
-                        "format": "wav"
-                    }
-                }
-            ]
-        }],
-        "max_tokens": 4096
-    });
+                ContentPart::InputAudio {
+                    input_audio: InputAudio {
+                        data: audio_base64,
+                        format: "wav".to_string(),
+                    },
+                },
+            ],
+        ))
+        .max_tokens(4096)
+        .build()?;
 
-    // Create HTTP client and send request
-    let client = reqwest::Client::new();
+    let client = provider.http_client.clone();
     info!("[Cloud Transcription - Gemini] Sending POST request...");
     
-    let response = client
-        .post(&endpoint)
-        .header("Content-Type", "application/json")
-        .bearer_auth(&provider.api_key)
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| {
-            error!("[Cloud Transcription - Gemini] Network error: {}", e);
-            format!("Failed to send transcription request: {}", e)
-        })?;
+    let response = send_with_retry(&retry_policy, "Cloud Transcription - Gemini", || {
+        Ok(client
+            .post(&endpoint)
+            .header("Content-Type", "application/json")
+            .bearer_auth(&provider.api_key)
+            .json(&request_body))
+    })
+    .await
+    .map_err(|e| {
+        error!("[Cloud Transcription - Gemini] Network error: {}", e);
+        e
+    })?;
 
     let status = response.status();
     info!("[Cloud Transcription - Gemini] Received response with status: {}", status);
 
     if !status.is_success() {
+        let retry_after = retry::retry_after(&response);
         let error_text = response
             .text()
             .await
@@ -330,28 +384,31 @@ async fn transcribe_online_gemini(
             "[Cloud Transcription - Gemini] API ERROR - Status: {}, Model: {}, Response: {}",
             status, provider.model, error_text
         );
-        return Err(format!(
-            "Gemini transcription failed ({}): {}",
-            status, error_text
-        ));
+        return Err(LlmError::from_response(status, retry_after, &error_text));
     }
 
     // Parse the chat completion response
-    let response_text = response
-        .text()
-        .await
-        .map_err(|e| {
-            error!("[Cloud Transcription - Gemini] Failed to read response body: {}", e);
-            format!("Failed to read response: {}", e)
-        })?;
+    let response_text = response.text().await.map_err(|e| {
+        error!(
+            "[Cloud Transcription - Gemini] Failed to read response body: {}",
+            e
+        );
+        LlmError::from(e)
+    })?;
 
-    debug!("[Cloud Transcription - Gemini] Raw response: {}", response_text);
+    debug!(
+        target: crate::logging::TRANSCRIPT_TARGET,
+        "[Cloud Transcription - Gemini] Raw response: {}", response_text
+    );
 
-    let parsed: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| {
-            error!("[Cloud Transcription - Gemini] Failed to parse JSON: {}. Raw: {}", e, response_text);
-            format!("Failed to parse response: {}", e)
-        })?;
+    let parsed: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+        error!(
+            target: crate::logging::TRANSCRIPT_TARGET,
+            "[Cloud Transcription - Gemini] Failed to parse JSON: {}. Raw: {}",
+            e, response_text
+        );
+        LlmError::parse(format!("Failed to parse response: {}", e))
+    })?;
 
     // Extract text from chat completion response: choices[0].message.content
     let text = parsed
@@ -372,16 +429,62 @@ async fn transcribe_online_gemini(
     Ok(text)
 }
 
+/// Transcribe online, moving on to the next key when one is rejected or rate limited
+async fn transcribe_online_with_fallback(
+    provider: OnlineTranscriptionProvider,
+    audio_samples: Vec<f32>,
+    language: Option<String>,
+    translate_to_english: bool,
+    retry_policy: RetryPolicy,
+) -> Result<String, LlmError> {
+    let keys = crate::api_keys::ordered_keys(&provider.provider_id, &provider.api_keys);
+    let last = keys.len() - 1;
+    let mut result = Err(LlmError::from("No API key configured".to_string()));
+    for (index, api_key) in keys.into_iter().enumerate() {
+        let attempt = OnlineTranscriptionProvider {
+            api_key: api_key.clone(),
+            ..provider.clone()
+        };
+        result = transcribe_online(
+            attempt,
+            audio_samples.clone(),
+            language.clone(),
+            translate_to_english,
+            retry_policy,
+        )
+        .await;
+        crate::api_keys::record_result(
+            &provider.provider_id,
+            &api_key,
+            result.as_ref().map(|_| ()),
+        );
+
+        match &result {
+            Err(e) if index < last && crate::api_keys::is_key_error(e) => {
+                debug!("Retrying transcription with the next API key");
+            }
+            _ => break,
+        }
+    }
+    result
+}
+
+/// Base URL of an online transcription provider
+pub(crate) fn online_provider_base_url(provider_id: &str) -> Option<&'static str> {
+    match provider_id {
+        "openai" => Some("https://api.openai.com/v1"),
+        "groq" => Some("https://api.groq.com/openai/v1"),
+        "gemini" => Some("https://generativelanguage.googleapis.com/v1beta/openai"),
+        _ => None,
+    }
+}
+
 /// Get the online provider configuration from settings
 fn get_online_transcription_provider(settings: &AppSettings) -> Option<OnlineTranscriptionProvider> {
     let provider_id = &settings.online_provider_id;
-    let api_key = settings
-        .online_provider_api_keys
-        .get(provider_id)
-        .cloned()
-        .unwrap_or_default();
+    let api_keys = settings.online_provider_keys(provider_id);
 
-    if api_key.trim().is_empty() {
+    if api_keys.is_empty() {
         error!(
             "Online transcription skipped: no API key for provider '{}'",
             provider_id
@@ -403,34 +506,478 @@ fn get_online_transcription_provider(settings: &AppSettings) -> Option<OnlineTra
             }
         });
 
-    // Provider base URLs
-    let base_url = match provider_id.as_str() {
-        "openai" => "https://api.openai.com/v1".to_string(),
-        "groq" => "https://api.groq.com/openai/v1".to_string(),
-        "gemini" => "https://generativelanguage.googleapis.com/v1beta/openai".to_string(),
-        _ => {
+    let base_url = match online_provider_base_url(provider_id) {
+        Some(base_url) => base_url.to_string(),
+        None => {
             error!("Unknown online provider: {}", provider_id);
             return None;
         }
     };
 
+    let http_client = match crate::http::shared_client(settings) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Online transcription skipped: {}", e);
+            return None;
+        }
+    };
+
     Some(OnlineTranscriptionProvider {
         provider_id: provider_id.clone(),
         base_url,
         model,
-        api_key,
+        api_key: api_keys[0].clone(),
+        api_keys,
+        http_client,
+    })
+}
+
+/// Transcribe `samples` with the online provider or the local model, per
+/// `settings`
+pub async fn transcribe_audio(
+    settings: &AppSettings,
+    tm: &TranscriptionManager,
+    samples: Vec<f32>,
+) -> Result<String, LlmError> {
+    if settings.use_online_provider {
+        debug!("Using online provider for transcription");
+        let provider = get_online_transcription_provider(settings)
+            .ok_or_else(|| LlmError::from("Online provider not configured properly".to_string()))?;
+        let language = if settings.selected_language == "auto" {
+            None
+        } else {
+            Some(settings.selected_language.clone())
+        };
+        let retry_policy = RetryPolicy::with_max_attempts(settings.network_max_attempts);
+        transcribe_online_with_fallback(
+            provider,
+            samples,
+            language,
+            settings.translate_to_english,
+            retry_policy,
+        )
+        .await
+    } else {
+        debug!("Using local model for transcription");
+        tm.transcribe(samples, &settings.selected_language)
+            .map_err(|e| LlmError::from(e.to_string()))
+    }
+}
+
+/// Text produced by LLM post-processing and the prompt template that produced it
+struct PostProcessOutput {
+    text: String,
+    prompt: String,
+    /// Parsed response when the action requested structured output
+    structured: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Serialize)]
+struct StructuredOutputEvent {
+    binding_id: String,
+    run_id: Option<String>,
+    data: serde_json::Value,
+}
+
+#[derive(Clone, Serialize)]
+struct LlmErrorEvent {
+    binding_id: String,
+    run_id: Option<String>,
+    /// "transcription" or "post_process"
+    stage: String,
+    error: LlmError,
+    user_message: String,
+    remediation: String,
+}
+
+#[derive(Clone, Serialize)]
+struct VoiceCommandEvent {
+    transcript: String,
+    intent: Option<VoiceIntent>,
+    success: bool,
+    message: String,
+}
+
+#[derive(Clone, Serialize)]
+struct PipelineProgressEvent {
+    binding_id: String,
+    run_id: Option<String>,
+    step_index: usize,
+    step_count: usize,
+    step_name: String,
+    output: String,
+}
+
+/// Fill the post-processing prompt with the transcription, the target
+/// language and the selected text
+fn render_prompt(prompt: &str, transcription: &str, language: &str, selection: &str) -> String {
+    template::render(prompt, "${", "}", |name| match name {
+        "output" => Some(transcription.to_string()),
+        "language" => Some(language.to_string()),
+        "selection" => Some(selection.to_string()),
+        _ => None,
     })
 }
 
+/// Fill a pipeline step template with the previous output, the original
+/// transcription, the results of earlier named steps and the selected text
+fn render_step_template(
+    prompt_template: &str,
+    previous: &str,
+    transcription: &str,
+    step_outputs: &HashMap<String, String>,
+    language: &str,
+    selection: &str,
+) -> String {
+    template::render(prompt_template, "${", "}", |name| match name {
+        "output" => Some(previous.to_string()),
+        "transcription" => Some(transcription.to_string()),
+        "language" => Some(language.to_string()),
+        "selection" => Some(selection.to_string()),
+        _ => name
+            .strip_prefix("step.")
+            .and_then(|step| step_outputs.get(step))
+            .cloned(),
+    })
+}
+
+fn create_post_process_client(
+    settings: &AppSettings,
+    provider: &PostProcessProvider,
+) -> Option<LlmClient> {
+    let api_key = settings
+        .post_process_api_keys
+        .get(&provider.id)
+        .cloned()
+        .unwrap_or_default();
+
+    let (service_tier, extra_body) = settings.provider_request_fields(&provider.id);
+
+    let client = crate::http::shared_client(settings)
+        .map(|http_client| crate::llm_client::create_client(provider, api_key, http_client));
+    match client {
+        Ok(client) => Some(
+            client
+                .with_gemini_safety_threshold(settings.gemini_safety_threshold)
+                .with_retry_policy(RetryPolicy::with_max_attempts(
+                    settings.network_max_attempts,
+                ))
+                .with_provider_fields(service_tier, extra_body)
+                .with_fallback_api_keys(
+                    settings
+                        .post_process_fallback_api_keys
+                        .get(&provider.id)
+                        .cloned()
+                        .unwrap_or_default(),
+                ),
+        ),
+        Err(e) => {
+            error!("Failed to create LLM client: {}", e);
+            None
+        }
+    }
+}
+
+/// Apply an action's sampling overrides to a request
+fn apply_sampling(
+    mut request: ChatCompletionRequestBuilder,
+    config: &ActionConfig,
+) -> ChatCompletionRequestBuilder {
+    if let Some(temperature) = config.temperature {
+        request = request.temperature(temperature);
+    }
+    if let Some(top_p) = config.top_p {
+        request = request.top_p(top_p);
+    }
+    if let Some(max_tokens) = config.max_tokens {
+        request = request.max_tokens(max_tokens);
+    }
+    request.stop(config.stop.clone())
+}
+
+/// Let the frontend show what went wrong and how to fix it
+fn emit_llm_error(app: &AppHandle, binding_id: &str, stage: &str, error: &LlmError) {
+    let event = LlmErrorEvent {
+        binding_id: binding_id.to_string(),
+        run_id: pipeline::run_id(),
+        stage: stage.to_string(),
+        error: error.clone(),
+        user_message: error.user_message(),
+        remediation: error.remediation().to_string(),
+    };
+    if let Err(e) = app.emit("llm-error", event) {
+        error!("Failed to emit LLM error: {}", e);
+    }
+
+    let title = if stage == "transcription" {
+        "Transcription failed"
+    } else {
+        "Post-processing failed"
+    };
+    notifications::notify(
+        app,
+        NotificationKind::ProviderError,
+        title,
+        &error.user_message(),
+    );
+}
+
+#[derive(Clone, Serialize)]
+struct BudgetExceededEvent {
+    spent_usd: f64,
+    budget_usd: f64,
+}
+
+fn record_llm_failure(app: &AppHandle, provider_id: &str, model: &str) {
+    if let Err(e) = app
+        .state::<Arc<UsageManager>>()
+        .record_error(provider_id, model)
+    {
+        error!("Failed to record LLM error: {}", e);
+    }
+}
+
+fn record_llm_usage(app: &AppHandle, provider_id: &str, model: &str, usage: &Usage) {
+    let usage_manager = app.state::<Arc<UsageManager>>();
+    if let Err(e) = usage_manager.record(provider_id, model, usage) {
+        error!("Failed to record LLM usage: {}", e);
+    }
+
+    let settings = get_settings(app);
+    let budget_usd = match settings.monthly_llm_budget_usd {
+        Some(budget_usd) => budget_usd,
+        None => return,
+    };
+    match usage_manager.month_to_date_cost(&settings.custom_model_prices) {
+        Ok(spent_usd) if usage_manager.should_warn_budget(spent_usd, budget_usd) => {
+            warn!(
+                "Estimated LLM spend this month (${:.2}) exceeds the budget of ${:.2}",
+                spent_usd, budget_usd
+            );
+            let event = BudgetExceededEvent {
+                spent_usd,
+                budget_usd,
+            };
+            if let Err(e) = app.emit("llm-budget-exceeded", event) {
+                error!("Failed to emit budget warning: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to compute LLM spend: {}", e),
+    }
+}
+
+const CONVERSATION_CONTEXT_INSTRUCTION: &str = "The earlier messages are previous dictations from the same session and your outputs for them. If the new dictation corrects or refers to the previous output (for example \"actually make that Tuesday\"), return the complete updated version of that output. Otherwise process the new dictation on its own.";
+
+const CONDENSE_CHUNK_PROMPT: &str = "Condense the following part of a longer transcript. Keep every fact, name, number, decision and instruction, and keep the speaker's wording where possible. Output only the condensed text.\n\nTranscript part:\n";
+
+/// Shorten a transcript that would not fit into the model's context window next to
+/// `overhead_tokens` of prompt. Depending on the setting the transcript is either
+/// truncated or summarized chunk by chunk (map-reduce) before the real request.
+async fn fit_transcription_to_budget(
+    app: &AppHandle,
+    settings: &AppSettings,
+    client: &LlmClient,
+    provider_id: &str,
+    model: &str,
+    overhead_tokens: usize,
+    transcription: &str,
+) -> String {
+    let budget = token_budget::transcript_budget(model, overhead_tokens);
+    let estimated = token_budget::estimate_tokens(transcription);
+    if estimated <= budget {
+        return transcription.to_string();
+    }
+
+    if settings.long_transcript_strategy == LongTranscriptStrategy::Truncate {
+        warn!(
+            "Transcript (~{} tokens) exceeds the budget of {} tokens for model '{}', truncating",
+            estimated, budget, model
+        );
+        return token_budget::truncate_to_tokens(transcription, budget);
+    }
+
+    // Each condense request carries its own instruction, so leave room for it
+    let chunk_budget = token_budget::transcript_budget(
+        model,
+        token_budget::estimate_tokens(CONDENSE_CHUNK_PROMPT),
+    );
+    let chunks = token_budget::chunk_text(transcription, chunk_budget);
+    warn!(
+        "Transcript (~{} tokens) exceeds the budget of {} tokens for model '{}', summarizing {} chunk(s)",
+        estimated,
+        budget,
+        model,
+        chunks.len()
+    );
+
+    let mut summaries = Vec::with_capacity(chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        let request = ChatCompletionRequest::builder(model)
+            .user(format!("{}{}", CONDENSE_CHUNK_PROMPT, chunk))
+            .build();
+        let result = match request {
+            Ok(request) => client.send_chat_request(&request).await,
+            Err(e) => Err(LlmError::from(e)),
+        };
+        match result {
+            Ok(output) if !output.content.trim().is_empty() => {
+                if let Some(usage) = &output.usage {
+                    record_llm_usage(app, provider_id, model, usage);
+                }
+                summaries.push(output.content.trim().to_string());
+            }
+            Ok(_) => {
+                warn!("Summary of chunk {} was empty, keeping it as-is", index + 1);
+                summaries.push(chunk.clone());
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to summarize chunk {}: {}. Keeping it as-is",
+                    index + 1,
+                    e
+                );
+                summaries.push(chunk.clone());
+            }
+        }
+    }
+
+    // The reduced text can still be too long, e.g. when summaries failed
+    token_budget::truncate_to_tokens(&summaries.join("\n\n"), budget)
+}
+
+/// Run an action's LLM steps in order, feeding each result into the next
+async fn run_llm_pipeline(
+    app: &AppHandle,
+    settings: &AppSettings,
+    provider: &PostProcessProvider,
+    model: &str,
+    binding_id: &str,
+    action_config: &ActionConfig,
+    transcription: &str,
+) -> Option<PostProcessOutput> {
+    let steps = &action_config.steps;
+    let client = create_post_process_client(settings, provider)?;
+    let selection = selection::captured();
+    let overhead_tokens = steps
+        .iter()
+        .map(|step| {
+            token_budget::estimate_tokens(&step.prompt_template)
+                + step
+                    .system_prompt
+                    .as_deref()
+                    .map_or(0, token_budget::estimate_tokens)
+        })
+        .max()
+        .unwrap_or(0)
+        + token_budget::estimate_tokens(&selection);
+    let transcription = fit_transcription_to_budget(
+        app,
+        settings,
+        &client,
+        &provider.id,
+        model,
+        overhead_tokens,
+        transcription,
+    )
+    .await;
+    let transcription = transcription.as_str();
+    let mut step_outputs: HashMap<String, String> = HashMap::new();
+    let mut current = transcription.to_string();
+
+    for (index, step) in steps.iter().enumerate() {
+        debug!(
+            "Running pipeline step {}/{} '{}' for action '{}'",
+            index + 1,
+            steps.len(),
+            step.name,
+            binding_id
+        );
+
+        let prompt = render_step_template(
+            &step.prompt_template,
+            &current,
+            transcription,
+            &step_outputs,
+            &settings.translate_target_language,
+            &selection,
+        );
+        let mut request = apply_sampling(ChatCompletionRequest::builder(model), action_config);
+        if let Some(system_prompt) = step.system_prompt.as_ref().filter(|p| !p.trim().is_empty()) {
+            request = request.system(system_prompt.clone());
+        }
+
+        let result = match request.user(prompt).build() {
+            Ok(request) => client.send_chat_request(&request).await,
+            Err(e) => Err(LlmError::from(e)),
+        };
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                error!(
+                    "Pipeline step '{}' failed: {}. Falling back to original transcription.",
+                    step.name, e
+                );
+                emit_llm_error(app, binding_id, "post_process", &e);
+                record_llm_failure(app, &provider.id, model);
+                return None;
+            }
+        };
+
+        if let Some(usage) = &output.usage {
+            record_llm_usage(app, &provider.id, model, usage);
+        }
+        if output.content.trim().is_empty() {
+            error!("Pipeline step '{}' returned empty content", step.name);
+            return None;
+        }
+
+        let event = PipelineProgressEvent {
+            binding_id: binding_id.to_string(),
+            run_id: pipeline::run_id(),
+            step_index: index,
+            step_count: steps.len(),
+            step_name: step.name.clone(),
+            output: output.content.clone(),
+        };
+        if let Err(e) = app.emit("pipeline-progress", event) {
+            error!("Failed to emit pipeline progress: {}", e);
+        }
+
+        step_outputs.insert(step.name.clone(), output.content.clone());
+        current = output.content;
+    }
+
+    let step_names: Vec<&str> = steps.iter().map(|step| step.name.as_str()).collect();
+    Some(PostProcessOutput {
+        text: current,
+        prompt: format!("Pipeline: {}", step_names.join(" → ")),
+        structured: None,
+    })
+}
 
 async fn maybe_post_process_transcription(
+    app: &AppHandle,
     settings: &AppSettings,
+    binding_id: &str,
     transcription: &str,
-) -> Option<String> {
+    streaming: Option<&StreamingInjection>,
+) -> Option<PostProcessOutput> {
     if !settings.post_process_enabled {
         return None;
     }
 
+    // User configuration first, then the built-in mode defaults for this action
+    let action_config = settings.action_config(binding_id);
+    let action_config = action_config.as_ref();
+    if action_config.is_some_and(|config| config.skip_post_process) {
+        debug!(
+            "Post-processing skipped because action '{}' is configured as raw",
+            binding_id
+        );
+        return None;
+    }
+
     let provider = match settings.active_post_process_provider().cloned() {
         Some(provider) => provider,
         None => {
@@ -453,28 +1000,64 @@ async fn maybe_post_process_transcription(
         return None;
     }
 
-    let selected_prompt_id = match &settings.post_process_selected_prompt_id {
-        Some(id) => id.clone(),
-        None => {
-            debug!("Post-processing skipped because no prompt is selected");
+    pipeline::advance(
+        app,
+        PipelineState::Transcribing,
+        PipelineState::PostProcessing,
+    );
+
+    // Multi-step pipelines replace the single prompt
+    if let Some(config) = action_config.filter(|config| !config.steps.is_empty()) {
+        if provider.id == APPLE_INTELLIGENCE_PROVIDER_ID || provider.id == LLAMA_CPP_PROVIDER_ID {
+            error!(
+                "Multi-step pipelines are not supported with {}",
+                provider.label
+            );
             return None;
         }
-    };
+        return run_llm_pipeline(
+            app,
+            settings,
+            &provider,
+            &model,
+            binding_id,
+            config,
+            transcription,
+        )
+        .await;
+    }
 
-    let prompt = match settings
-        .post_process_prompts
-        .iter()
-        .find(|prompt| prompt.id == selected_prompt_id)
-    {
-        Some(prompt) => prompt.prompt.clone(),
+    // Action-specific templates take precedence over the globally selected prompt
+    let prompt = match action_config.and_then(|config| config.prompt_template.clone()) {
+        Some(template) => template,
         None => {
-            debug!(
-                "Post-processing skipped because prompt '{}' was not found",
-                selected_prompt_id
-            );
-            return None;
+            let selected_prompt_id = match &settings.post_process_selected_prompt_id {
+                Some(id) => id.clone(),
+                None => {
+                    debug!("Post-processing skipped because no prompt is selected");
+                    return None;
+                }
+            };
+
+            match settings
+                .post_process_prompts
+                .iter()
+                .find(|prompt| prompt.id == selected_prompt_id)
+            {
+                Some(prompt) => prompt.prompt.clone(),
+                None => {
+                    debug!(
+                        "Post-processing skipped because prompt '{}' was not found",
+                        selected_prompt_id
+                    );
+                    return None;
+                }
+            }
         }
     };
+    let system_prompt = action_config
+        .and_then(|config| config.system_prompt.clone())
+        .filter(|system_prompt| !system_prompt.trim().is_empty());
 
     if prompt.trim().is_empty() {
         debug!("Post-processing skipped because the selected prompt is empty");
@@ -486,9 +1069,7 @@ async fn maybe_post_process_transcription(
         provider.id, model
     );
 
-    // Replace ${output} variable in the prompt with the actual text
-    let processed_prompt = prompt.replace("${output}", transcription);
-    debug!("Processed prompt length: {} chars", processed_prompt.len());
+    let selection = selection::captured();
 
     if provider.id == APPLE_INTELLIGENCE_PROVIDER_ID {
         #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
@@ -498,8 +1079,19 @@ async fn maybe_post_process_transcription(
                 return None;
             }
 
+            let processed_prompt = render_prompt(
+                &prompt,
+                transcription,
+                &settings.translate_target_language,
+                &selection,
+            );
+            // The on-device model takes a single prompt, so prepend any system prompt
+            let apple_prompt = match &system_prompt {
+                Some(system_prompt) => format!("{}\n\n{}", system_prompt, processed_prompt),
+                None => processed_prompt,
+            };
             let token_limit = model.trim().parse::<i32>().unwrap_or(0);
-            return match apple_intelligence::process_text(&processed_prompt, token_limit) {
+            return match apple_intelligence::process_text(&apple_prompt, token_limit) {
                 Ok(result) => {
                     if result.trim().is_empty() {
                         debug!("Apple Intelligence returned an empty response");
@@ -509,7 +1101,11 @@ async fn maybe_post_process_transcription(
                             "Apple Intelligence post-processing succeeded. Output length: {} chars",
                             result.len()
                         );
-                        Some(result)
+                        Some(PostProcessOutput {
+                            text: result,
+                            prompt,
+                            structured: None,
+                        })
                     }
                 }
                 Err(err) => {
@@ -526,35 +1122,238 @@ async fn maybe_post_process_transcription(
         }
     }
 
-    let api_key = settings
-        .post_process_api_keys
-        .get(&provider.id)
-        .cloned()
-        .unwrap_or_default();
+    if provider.id == LLAMA_CPP_PROVIDER_ID {
+        #[cfg(feature = "llama-cpp")]
+        {
+            let model_path = match settings.llama_cpp_model_path.clone() {
+                Some(path) => path,
+                None => {
+                    debug!("Local llama.cpp provider selected but no GGUF model is configured");
+                    return None;
+                }
+            };
+            let gpu_layers = settings.llama_cpp_gpu_layers;
+            let params = llama_cpp::GenerationParams {
+                max_tokens: action_config
+                    .and_then(|config| config.max_tokens)
+                    .unwrap_or(llama_cpp::DEFAULT_MAX_TOKENS),
+                temperature: action_config.and_then(|config| config.temperature),
+                top_p: action_config.and_then(|config| config.top_p),
+                stop: action_config
+                    .map(|config| config.stop.clone())
+                    .unwrap_or_default(),
+            };
+            let local_prompt = render_prompt(
+                &prompt,
+                transcription,
+                &settings.translate_target_language,
+                &selection,
+            );
+            let local_system_prompt = system_prompt.clone();
+
+            // Inference is CPU/GPU bound and takes seconds, keep it off the async workers
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                llama_cpp::process_text(
+                    &model_path,
+                    gpu_layers,
+                    local_system_prompt.as_deref(),
+                    &local_prompt,
+                    &params,
+                )
+            })
+            .await
+            .map_err(|e| format!("Local inference task failed: {}", e))
+            .and_then(|result| result);
+
+            return match result {
+                Ok(result) if !result.is_empty() => {
+                    debug!(
+                        "llama.cpp post-processing succeeded. Output length: {} chars",
+                        result.len()
+                    );
+                    Some(PostProcessOutput {
+                        text: result,
+                        prompt,
+                        structured: None,
+                    })
+                }
+                Ok(_) => {
+                    debug!("llama.cpp returned an empty response");
+                    None
+                }
+                Err(err) => {
+                    error!("llama.cpp post-processing failed: {}", err);
+                    None
+                }
+            };
+        }
 
-    // Create OpenAI-compatible client
-    let client = match crate::llm_client::create_client(&provider, api_key) {
-        Ok(client) => client,
+        #[cfg(not(feature = "llama-cpp"))]
+        {
+            debug!("Local llama.cpp provider selected but this build doesn't include it");
+            return None;
+        }
+    }
+
+    let client = create_post_process_client(settings, &provider)?;
+
+    // Earlier dictations of this session, replayed as chat history
+    let context = if settings.conversation_context_enabled {
+        app.state::<Arc<ConversationManager>>().context(
+            settings.conversation_context_turns as usize,
+            Duration::from_secs(u64::from(settings.conversation_context_window_minutes) * 60),
+        )
+    } else {
+        Vec::new()
+    };
+
+    let context_tokens: usize = context
+        .iter()
+        .map(|turn| {
+            token_budget::estimate_tokens(&turn.prompt)
+                + token_budget::estimate_tokens(&turn.output)
+        })
+        .sum();
+    let overhead_tokens = token_budget::estimate_tokens(&prompt)
+        + token_budget::estimate_tokens(&selection)
+        + system_prompt
+            .as_deref()
+            .map_or(0, token_budget::estimate_tokens)
+        + context_tokens;
+    let transcription = fit_transcription_to_budget(
+        app,
+        settings,
+        &client,
+        &provider.id,
+        &model,
+        overhead_tokens,
+        transcription,
+    )
+    .await;
+    let processed_prompt = render_prompt(
+        &prompt,
+        &transcription,
+        &settings.translate_target_language,
+        &selection,
+    );
+    debug!("Processed prompt length: {} chars", processed_prompt.len());
+
+    let output_schema = match action_config.and_then(|config| config.output_schema.as_deref()) {
+        Some(schema) => match serde_json::from_str::<serde_json::Value>(schema) {
+            Ok(schema) => Some(schema),
+            Err(e) => {
+                error!(
+                    "Ignoring invalid output schema for action '{}': {}",
+                    binding_id, e
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut request = ChatCompletionRequest::builder(model.clone());
+    if let Some(config) = action_config {
+        request = apply_sampling(request, config);
+    }
+    if let Some(system_prompt) = &system_prompt {
+        request = request.system(system_prompt.clone());
+    }
+    if let Some(schema) = &output_schema {
+        let strict = action_config.is_some_and(|config| config.output_schema_strict);
+        request = request.response_format(
+            ResponseFormat::json_schema("structured_output", schema.clone()).strict(strict),
+        );
+    }
+    if !context.is_empty() {
+        debug!(
+            "Including {} earlier dictation(s) as context",
+            context.len()
+        );
+        request = request.system(CONVERSATION_CONTEXT_INSTRUCTION);
+        for turn in &context {
+            request = request
+                .user(turn.prompt.clone())
+                .message(ChatRequestMessage::text(
+                    ChatRole::Assistant,
+                    turn.output.clone(),
+                ));
+        }
+    }
+    let tools_enabled = action_config.is_some_and(|config| config.tools_enabled);
+    if tools_enabled {
+        request = request
+            .tools(tools::TOOL_REGISTRY.definitions())
+            .tool_choice(ToolChoice::auto());
+    }
+    let request = match request.user(processed_prompt.clone()).build() {
+        Ok(request) => request,
         Err(e) => {
-            error!("Failed to create LLM client: {}", e);
+            error!("Failed to build LLM request: {}", e);
             return None;
         }
     };
 
     // Send the chat completion request using our custom client
-    match client.chat_completion(&model, &processed_prompt).await {
-        Ok(content) => {
+    let result = match streaming {
+        _ if tools_enabled => {
+            tools::complete_with_tools(&client, app, request, &tools::TOOL_REGISTRY).await
+        }
+        // Structured output is JSON for the app, not text to type
+        Some(session) if output_schema.is_none() => {
+            client
+                .stream_chat_request(&request, &mut |text: &str| {
+                    session.update(text);
+                    pipeline::partial(app, text);
+                })
+                .await
+        }
+        _ => client.send_chat_request(&request).await,
+    };
+
+    match result {
+        Ok(output) => {
+            if let Some(usage) = &output.usage {
+                record_llm_usage(app, &provider.id, &model, usage);
+            }
+
+            let content = output.content;
             if content.trim().is_empty() {
                 error!("LLM API response has empty content");
-                None
+                return None;
+            }
+
+            let structured = if output_schema.is_some() {
+                match parse_structured_output(&content) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        error!("{}. Falling back to original transcription.", e);
+                        emit_llm_error(app, binding_id, "post_process", &LlmError::parse(e));
+                        record_llm_failure(app, &provider.id, &model);
+                        return None;
+                    }
+                }
             } else {
-                debug!(
-                    "LLM post-processing succeeded for provider '{}'. Output length: {} chars",
-                    provider.id,
-                    content.len()
-                );
-                Some(content)
+                None
+            };
+
+            debug!(
+                "LLM post-processing succeeded for provider '{}'. Output length: {} chars",
+                provider.id,
+                content.len()
+            );
+            if settings.conversation_context_enabled {
+                app.state::<Arc<ConversationManager>>()
+                    .record(processed_prompt, content.clone());
             }
+            Some(PostProcessOutput {
+                text: structured
+                    .as_ref()
+                    .map(format_structured_output)
+                    .unwrap_or(content),
+                prompt,
+                structured,
+            })
         }
         Err(e) => {
             error!(
@@ -562,11 +1361,121 @@ async fn maybe_post_process_transcription(
                 provider.id,
                 e
             );
+            emit_llm_error(app, binding_id, "post_process", &e);
+            record_llm_failure(app, &provider.id, &model);
             None
         }
     }
 }
 
+/// Post-process an earlier transcript with action `binding_id`, optionally
+/// with another saved prompt than the selected one. Runs even when
+/// post-processing is turned off, and leaves the conversation context alone.
+pub async fn reprocess_transcription(
+    app: &AppHandle,
+    binding_id: &str,
+    prompt_id: Option<String>,
+    transcription: &str,
+) -> Result<(String, String), String> {
+    let mut settings = get_settings(app);
+    if let Some(prompt_id) = prompt_id {
+        if !settings
+            .post_process_prompts
+            .iter()
+            .any(|prompt| prompt.id == prompt_id)
+        {
+            return Err(format!("Prompt '{}' not found", prompt_id));
+        }
+        settings.post_process_selected_prompt_id = Some(prompt_id);
+    }
+    settings.post_process_enabled = true;
+    settings.conversation_context_enabled = false;
+
+    match maybe_post_process_transcription(app, &settings, binding_id, transcription, None).await {
+        Some(output) => Ok((output.text, output.prompt)),
+        None => Err("Post-processing failed or isn't configured for this action".to_string()),
+    }
+}
+
+/// Classify a spoken command with the LLM and run the resulting app action
+async fn run_voice_command(app: &AppHandle, settings: &AppSettings, transcript: &str) {
+    let result = match classify_voice_command(app, settings, transcript).await {
+        Ok(intent) => {
+            let outcome = voice_command::execute_intent(app, &intent).await;
+            (Some(intent), outcome)
+        }
+        Err(e) => (None, Err(e)),
+    };
+
+    let event = match result {
+        (intent, Ok(message)) => {
+            debug!("Voice command succeeded: {}", message);
+            VoiceCommandEvent {
+                transcript: transcript.to_string(),
+                intent,
+                success: true,
+                message,
+            }
+        }
+        (intent, Err(message)) => {
+            error!("Voice command '{}' failed: {}", transcript, message);
+            VoiceCommandEvent {
+                transcript: transcript.to_string(),
+                intent,
+                success: false,
+                message,
+            }
+        }
+    };
+    if let Err(e) = app.emit("voice-command", event) {
+        error!("Failed to emit voice command result: {}", e);
+    }
+}
+
+async fn classify_voice_command(
+    app: &AppHandle,
+    settings: &AppSettings,
+    transcript: &str,
+) -> Result<VoiceIntent, String> {
+    let provider = settings
+        .active_post_process_provider()
+        .cloned()
+        .ok_or_else(|| "Voice commands need a post-processing provider".to_string())?;
+    if provider.id == APPLE_INTELLIGENCE_PROVIDER_ID || provider.id == LLAMA_CPP_PROVIDER_ID {
+        return Err(format!(
+            "Voice commands are not supported with {}",
+            provider.label
+        ));
+    }
+    let model = settings
+        .post_process_models
+        .get(&provider.id)
+        .cloned()
+        .filter(|model| !model.trim().is_empty())
+        .ok_or_else(|| format!("No model configured for provider '{}'", provider.id))?;
+
+    let client = create_post_process_client(settings, &provider)
+        .ok_or_else(|| "Failed to create LLM client".to_string())?;
+    let mode_names: Vec<String> = settings
+        .post_process_prompts
+        .iter()
+        .map(|prompt| prompt.name.clone())
+        .collect();
+    let request = voice_command::intent_request(&model, transcript, &mode_names)?;
+
+    let output = client.send_chat_request(&request).await.map_err(|e| {
+        emit_llm_error(app, VOICE_COMMAND_ACTION_ID, "post_process", &e);
+        record_llm_failure(app, &provider.id, &model);
+        e.to_string()
+    })?;
+    if let Some(usage) = &output.usage {
+        record_llm_usage(app, &provider.id, &model, usage);
+    }
+
+    let value = parse_structured_output(&output.content)?;
+    voice_command::parse_intent(&value)
+}
+
 async fn maybe_convert_chinese_variant(
     settings: &AppSettings,
     transcription: &str,
@@ -624,10 +1533,13 @@ impl ShortcutAction for TranscribeAction {
         } else {
             debug!("Using online provider for transcription, skipping local model load");
         }
+        // Connect while the user speaks, an idle connection may have closed
+        crate::warmup::preconnect(app);
 
         let binding_id = binding_id.to_string();
-        change_tray_icon(app, TrayIconState::Recording);
-        show_recording_overlay(app);
+        // Before anything can take the focus from the app with the selection
+        selection::capture(&settings, &binding_id);
+        pipeline::start(app, &binding_id);
 
         let rm = app.state::<Arc<AudioRecordingManager>>();
 
@@ -687,9 +1599,6 @@ impl ShortcutAction for TranscribeAction {
     }
 
     fn stop(&self, app: &AppHandle, binding_id: &str, _shortcut_str: &str) {
-        // Unregister the cancel shortcut when transcription stops
-        shortcut::unregister_cancel_shortcut(app);
-
         let stop_time = Instant::now();
         debug!("TranscribeAction::stop called for binding: {}", binding_id);
 
@@ -698,8 +1607,7 @@ impl ShortcutAction for TranscribeAction {
         let tm = Arc::clone(&app.state::<Arc<TranscriptionManager>>());
         let hm = Arc::clone(&app.state::<Arc<HistoryManager>>());
 
-        change_tray_icon(app, TrayIconState::Transcribing);
-        show_transcribing_overlay(app);
+        pipeline::transition(app, PipelineState::Transcribing);
 
         // Unmute before playing audio feedback so the stop sound is audible
         rm.remove_mute();
@@ -709,7 +1617,18 @@ impl ShortcutAction for TranscribeAction {
 
         let binding_id = binding_id.to_string(); // Clone binding_id for the async task
 
-        tauri::async_runtime::spawn(async move {
+        // The cancel shortcut stays registered until the invocation ends
+        let invocation = cancellation::begin(&binding_id);
+        let tracked = invocation.clone();
+        // Taken now; a new dictation may start while this one is processed
+        let run_id = pipeline::run_id();
+        let settings = get_settings(app);
+        let timeout = cancellation::timeout_for(&settings, &binding_id);
+        let queued = settings
+            .action_config(&binding_id)
+            .is_some_and(|config| config.concurrency == ConcurrencyPolicy::Queue);
+
+        let work = async move {
             let binding_id = binding_id.clone(); // Clone for the inner async task
             debug!(
                 "Starting async transcription task for binding: {}",
@@ -724,46 +1643,59 @@ impl ShortcutAction for TranscribeAction {
                     samples.len()
                 );
 
-                let settings = get_settings(&ah);
-                
+                // Language, prompt and paste method can depend on the app being dictated into
+                let settings = app_overrides::settings_for_focused_app(get_settings(&ah));
+                let focused_app = app_overrides::focused_process_name()
+                    .map(|name| app_overrides::normalize_process_name(&name));
+
                 let transcription_time = Instant::now();
                 let samples_clone = samples.clone(); // Clone for history saving
                 
-                // Use either online or local transcription based on settings
-                let transcription_result: Result<String, String> = if settings.use_online_provider {
-                    // Online transcription
-                    debug!("Using online provider for transcription");
-                    if let Some(provider) = get_online_transcription_provider(&settings) {
-                        let language = if settings.selected_language == "auto" {
-                            None
-                        } else {
-                            Some(settings.selected_language.clone())
-                        };
-                        let translate = settings.translate_to_english;
-                        transcribe_online(provider, samples, language, translate)
-                            .await
-                            .map_err(|e| format!("Online transcription failed: {}", e))
-                    } else {
-                        Err("Online provider not configured properly".to_string())
-                    }
-                } else {
-                    // Local transcription
-                    debug!("Using local model for transcription");
-                    tm.transcribe(samples).map_err(|e| e.to_string())
-                };
+                let transcription_result = transcribe_audio(&settings, &tm, samples).await;
 
                 match transcription_result {
                     Ok(transcription) => {
                         debug!(
+                            target: crate::logging::TRANSCRIPT_TARGET,
                             "Transcription completed in {:?}: '{}'",
                             transcription_time.elapsed(),
                             transcription
                         );
-                        if !transcription.is_empty() {
+                        let hook_context = HookContext {
+                            binding_id: &binding_id,
+                            app: focused_app.as_deref(),
+                        };
+                        let transcription =
+                            scripting::on_transcript(&settings, &hook_context, transcription);
+                        if !transcription.is_empty() && binding_id == VOICE_COMMAND_ACTION_ID {
+                            // Commands drive the app instead of being pasted or saved
+                            pipeline::transition(&ah, PipelineState::PostProcessing);
+                            run_voice_command(&ah, &settings, &transcription).await;
+                            pipeline::finish(&ah);
+                        } else if !transcription.is_empty() {
                             let mut final_text = transcription.clone();
                             let mut post_processed_text: Option<String> = None;
                             let mut post_process_prompt: Option<String> = None;
 
+                            let mut targets = output::targets_for(&settings, &binding_id);
+                            let action_config = settings.action_config(&binding_id);
+                            let confirm = action_config
+                                .as_ref()
+                                .is_some_and(|config| config.confirm_before_inject);
+                            let (shell_command, chain) = action_config
+                                .map(|config| (config.shell_command, config.chain))
+                                .unwrap_or_default();
+                            // Streaming types into the focused app, so only when that's
+                            // the sole destination and nothing confirms or rewrites it first
+                            let streaming = (settings.streaming_injection
+                                && targets == [OutputTarget::Inject]
+                                && !confirm
+                                && shell_command.is_none()
+                                && chain.is_empty()
+                                && settings.paste_method != PasteMethod::None
+                                && !scripting::has_hook(&settings, "before_inject"))
+                                .then(|| StreamingInjection::new(&ah));
+
                             // First, check if Chinese variant conversion is needed
                             if let Some(converted_text) =
                                 maybe_convert_chinese_variant(&settings, &transcription).await
@@ -772,20 +1704,102 @@ impl ShortcutAction for TranscribeAction {
                                 post_processed_text = Some(converted_text);
                             }
                             // Then apply regular post-processing if enabled
-                            else if let Some(processed_text) =
-                                maybe_post_process_transcription(&settings, &transcription).await
+                            else if let Some(processed) = maybe_post_process_transcription(
+                                &ah,
+                                &settings,
+                                &binding_id,
+                                &transcription,
+                                streaming.as_ref(),
+                            )
+                            .await
                             {
-                                final_text = processed_text.clone();
-                                post_processed_text = Some(processed_text);
-
-                                // Get the prompt that was used
-                                if let Some(prompt_id) = &settings.post_process_selected_prompt_id {
-                                    if let Some(prompt) = settings
-                                        .post_process_prompts
-                                        .iter()
-                                        .find(|p| &p.id == prompt_id)
-                                    {
-                                        post_process_prompt = Some(prompt.prompt.clone());
+                                final_text = processed.text.clone();
+                                post_processed_text = Some(processed.text);
+                                post_process_prompt = Some(processed.prompt);
+
+                                if let Some(data) = processed.structured {
+                                    let event = StructuredOutputEvent {
+                                        binding_id: binding_id.clone(),
+                                        run_id: run_id.clone(),
+                                        data,
+                                    };
+                                    if let Err(e) = ah.emit("structured-output", event) {
+                                        error!("Failed to emit structured output: {}", e);
+                                    }
+                                }
+                            }
+
+                            // After post-processing, so the snippet text arrives verbatim
+                            let expanded =
+                                snippets::expand_snippets(&final_text, &settings.snippets);
+                            if expanded != final_text {
+                                debug!(
+                                    target: crate::logging::TRANSCRIPT_TARGET,
+                                    "Expanded snippets in '{}'", final_text
+                                );
+                                final_text = expanded.clone();
+                                post_processed_text = Some(expanded);
+                            }
+
+                            if let Some(command) = &shell_command {
+                                let result = shell_command::run(
+                                    command,
+                                    &binding_id,
+                                    &transcription,
+                                    &final_text,
+                                )
+                                .await;
+                                match result {
+                                    Ok(output) if command.capture_output => {
+                                        final_text = output.clone();
+                                        post_processed_text = Some(output);
+                                    }
+                                    Ok(_) => debug!("Ran the command of '{}'", binding_id),
+                                    Err(e) => {
+                                        error!("Command of '{}' failed: {}", binding_id, e)
+                                    }
+                                }
+                            }
+
+                            // A stopped chain keeps the dictation in the history only
+                            let mut chain_error = None;
+                            if !chain.is_empty() {
+                                let input = ChainInput {
+                                    binding_id: &binding_id,
+                                    run_id: run_id.as_deref(),
+                                    transcription: &transcription,
+                                    text: final_text.clone(),
+                                };
+                                match chain::run(&ah, &settings, input, &chain).await {
+                                    Ok(text) if text != final_text => {
+                                        final_text = text.clone();
+                                        post_processed_text = Some(text);
+                                    }
+                                    Ok(_) => {}
+                                    Err(e) => {
+                                        error!("Chain stopped: {}", e);
+                                        chain_error = Some(e);
+                                    }
+                                }
+                            }
+
+                            if chain_error.is_none() && targets.contains(&OutputTarget::Inject) {
+                                match scripting::before_inject(
+                                    &settings,
+                                    &hook_context,
+                                    &final_text,
+                                ) {
+                                    HookResult::Keep => {}
+                                    HookResult::Replace(text) => {
+                                        final_text = text.clone();
+                                        post_processed_text = Some(text);
+                                    }
+                                    HookResult::Veto => {
+                                        debug!(
+                                            target: crate::logging::TRANSCRIPT_TARGET,
+                                            "before_inject vetoed injecting '{}'", final_text
+                                        );
+                                        targets.retain(|target| *target != OutputTarget::Inject);
                                     }
                                 }
                             }
@@ -793,6 +1807,9 @@ impl ShortcutAction for TranscribeAction {
                             // Save to history with post-processed text and prompt
                             let hm_clone = Arc::clone(&hm);
                             let transcription_for_history = transcription.clone();
+                            let history_action_id = binding_id.clone();
+                            let history_app = focused_app.clone();
+                            let history_run_id = run_id.clone();
                             tauri::async_runtime::spawn(async move {
                                 if let Err(e) = hm_clone
                                     .save_transcription(
@@ -800,6 +1817,9 @@ impl ShortcutAction for TranscribeAction {
                                         transcription_for_history,
                                         post_processed_text,
                                         post_process_prompt,
+                                        history_action_id,
+                                        history_app,
+                                        history_run_id,
                                     )
                                     .await
                                 {
@@ -807,41 +1827,148 @@ impl ShortcutAction for TranscribeAction {
                                 }
                             });
 
+                            if let Some(e) = chain_error {
+                                pipeline::fail(&ah, &e);
+                                return;
+                            }
+
+                            let action_output = ActionOutput {
+                                binding_id: binding_id.clone(),
+                                text: final_text.clone(),
+                                transcription: transcription.clone(),
+                                timestamp: chrono::Utc::now().timestamp(),
+                                run_id: run_id.clone(),
+                            };
+                            if let Err(e) = ah.emit("transcript-final", &action_output) {
+                                error!("Failed to emit final transcript: {}", e);
+                            }
+
+                            if let Some(session) = &streaming {
+                                pipeline::transition(&ah, PipelineState::Injecting);
+                                session.finish(&final_text);
+                                pipeline::finish(&ah);
+                                return;
+                            }
+
+                            output::deliver(&ah, &settings, &action_output, &targets);
+                            if !targets.contains(&OutputTarget::Inject) {
+                                pipeline::finish(&ah);
+                                return;
+                            }
+
+                            let keep_on_clipboard = targets.contains(&OutputTarget::Clipboard);
+                            if confirm {
+                                if let Err(e) = preview::request(
+                                    &ah,
+                                    &binding_id,
+                                    final_text,
+                                    keep_on_clipboard,
+                                    settings.paste_method,
+                                ) {
+                                    error!("Failed to show preview: {}", e);
+                                }
+                                pipeline::finish(&ah);
+                                return;
+                            }
+
+                            notifications::notify_if_focus_moved(
+                                &ah,
+                                focused_app.as_deref(),
+                                &final_text,
+                            );
+
                             // Paste the final text (either processed or original)
+                            pipeline::transition(&ah, PipelineState::Injecting);
                             let ah_clone = ah.clone();
                             let paste_time = Instant::now();
+                            let paste_method = settings.paste_method;
                             ah.run_on_main_thread(move || {
-                                match utils::paste(final_text, ah_clone.clone()) {
-                                    Ok(()) => debug!(
-                                        "Text pasted successfully in {:?}",
-                                        paste_time.elapsed()
-                                    ),
-                                    Err(e) => error!("Failed to paste transcription: {}", e),
+                                // Cancelled while waiting for the main thread
+                                if invocation.is_cancelled() {
+                                    return;
+                                }
+                                match utils::paste_text(
+                                    final_text,
+                                    ah_clone.clone(),
+                                    keep_on_clipboard,
+                                    paste_method,
+                                ) {
+                                    Ok(()) => {
+                                        debug!(
+                                            "Text pasted successfully in {:?}",
+                                            paste_time.elapsed()
+                                        );
+                                        pipeline::finish(&ah_clone);
+                                    }
+                                    Err(e) => {
+                                        error!("Failed to paste transcription: {}", e);
+                                        notifications::notify(
+                                            &ah_clone,
+                                            NotificationKind::PermissionProblem,
+                                            "Couldn't paste the text",
+                                            &format!(
+                                                "Check that Babbl may control the keyboard. {}",
+                                                e
+                                            ),
+                                        );
+                                        pipeline::fail(&ah_clone, &e);
+                                    }
                                 }
-                                // Hide the overlay after transcription is complete
-                                utils::hide_recording_overlay(&ah_clone);
-                                change_tray_icon(&ah_clone, TrayIconState::Idle);
                             })
                             .unwrap_or_else(|e| {
                                 error!("Failed to run paste on main thread: {:?}", e);
-                                utils::hide_recording_overlay(&ah);
-                                change_tray_icon(&ah, TrayIconState::Idle);
+                                pipeline::fail(&ah, &e.to_string());
                             });
                         } else {
-                            utils::hide_recording_overlay(&ah);
-                            change_tray_icon(&ah, TrayIconState::Idle);
+                            pipeline::finish(&ah);
                         }
                     }
                     Err(err) => {
                         error!("Transcription error: {}", err);
-                        utils::hide_recording_overlay(&ah);
-                        change_tray_icon(&ah, TrayIconState::Idle);
+                        // Local model failures aren't provider errors
+                        if settings.use_online_provider {
+                            emit_llm_error(&ah, &binding_id, "transcription", &err);
+                        }
+                        pipeline::fail(&ah, &err.to_string());
                     }
                 }
             } else {
                 debug!("No samples retrieved from recording stop");
-                utils::hide_recording_overlay(&ah);
-                change_tray_icon(&ah, TrayIconState::Idle);
+                pipeline::finish(&ah);
+            }
+        };
+
+        let ah = app.clone();
+        tauri::async_runtime::spawn(async move {
+            // Queued runs deliver one after the other
+            let _turn = if queued {
+                Some(tracked.turn().await)
+            } else {
+                None
+            };
+            match tracked.run(timeout, work).await {
+                Ok(()) => {}
+                Err(Interrupted::Cancelled) => debug!("'{}' was cancelled", tracked.binding_id()),
+                Err(Interrupted::TimedOut(timeout)) => {
+                    // Chunked local transcriptions run on their own thread
+                    progress::cancel_all(OperationKind::Transcription, None);
+                    let message = format!(
+                        "'{}' took longer than {} seconds",
+                        tracked.binding_id(),
+                        timeout.as_secs()
+                    );
+                    error!("{}", message);
+                    notifications::notify(
+                        &ah,
+                        NotificationKind::ProviderError,
+                        "Action timed out",
+                        &message,
+                    );
+                    pipeline::fail(&ah, &message);
+                }
+            }
+            if cancellation::end(&tracked) && pipeline::current() != PipelineState::Recording {
+                shortcut::unregister_cancel_shortcut(&ah);
             }
         });
 
@@ -865,6 +1992,55 @@ impl ShortcutAction for CancelAction {
     }
 }
 
+// Undo Last Injection Action
+struct UndoInjectionAction;
+
+impl ShortcutAction for UndoInjectionAction {
+    fn start(&self, app: &AppHandle, _binding_id: &str, _shortcut_str: &str) {
+        // Key events go through the main thread like pasting does
+        let ah = app.clone();
+        let result = app.run_on_main_thread(move || {
+            if let Err(e) = utils::undo_last_injection(&ah) {
+                warn!("Undo of last injection failed: {}", e);
+            }
+        });
+        if let Err(e) = result {
+            error!("Failed to run undo on main thread: {:?}", e);
+        }
+    }
+
+    fn stop(&self, _app: &AppHandle, _binding_id: &str, _shortcut_str: &str) {
+        // One-shot action, triggered on release only
+    }
+}
+
+// Cycle Profile Action
+struct CycleProfileAction;
+
+impl ShortcutAction for CycleProfileAction {
+    fn start(&self, app: &AppHandle, _binding_id: &str, _shortcut_str: &str) {
+        let settings = get_settings(app);
+        let next =
+            profiles::next_profile_id(&settings.profiles, settings.active_profile_id.as_deref());
+        match next {
+            Some(id) => {
+                // Shortcuts can't be re-registered from inside a shortcut handler
+                let ah = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = profiles::switch_profile(&ah, &id) {
+                        warn!("Failed to switch profile: {}", e);
+                    }
+                });
+            }
+            None => debug!("No profiles to switch between"),
+        }
+    }
+
+    fn stop(&self, _app: &AppHandle, _binding_id: &str, _shortcut_str: &str) {
+        // One-shot action, triggered on release only
+    }
+}
+
 // Test Action
 struct TestAction;
 
@@ -888,8 +2064,9 @@ impl ShortcutAction for TestAction {
     }
 }
 
-// Static Action Map
-pub static ACTION_MAP: Lazy<HashMap<String, Arc<dyn ShortcutAction>>> = Lazy::new(|| {
+/// Built-in actions; look actions up with [`action_for`], which also knows
+/// the ones defined at runtime
+static BUILTIN_ACTIONS: Lazy<HashMap<String, Arc<dyn ShortcutAction>>> = Lazy::new(|| {
     let mut map = HashMap::new();
     map.insert(
         "transcribe".to_string(),
@@ -903,5 +2080,120 @@ pub static ACTION_MAP: Lazy<HashMap<String, Arc<dyn ShortcutAction>>> = Lazy::ne
         "test".to_string(),
         Arc::new(TestAction) as Arc<dyn ShortcutAction>,
     );
+    map.insert(
+        utils::UNDO_INJECTION_ACTION_ID.to_string(),
+        Arc::new(UndoInjectionAction) as Arc<dyn ShortcutAction>,
+    );
+    map.insert(
+        profiles::CYCLE_PROFILE_ACTION_ID.to_string(),
+        Arc::new(CycleProfileAction) as Arc<dyn ShortcutAction>,
+    );
+    map.insert(
+        VOICE_COMMAND_ACTION_ID.to_string(),
+        Arc::new(TranscribeAction) as Arc<dyn ShortcutAction>,
+    );
+    for mode in BUILTIN_MODES {
+        map.insert(
+            mode.id.to_string(),
+            Arc::new(TranscribeAction) as Arc<dyn ShortcutAction>,
+        );
+    }
     map
 });
+
+/// Prefix of the ids of actions defined in the settings. They dictate like
+/// the transcribe action, with their own `ActionConfig`.
+pub const CUSTOM_ACTION_PREFIX: &str = "custom_";
+
+/// Whether a new run of `binding_id` may start while an earlier one is still
+/// processing, per the action's concurrency policy. `Restart` cancels the
+/// earlier run here. Nothing starts while Babbl is quitting, or right after
+/// another dictation started.
+pub fn admit(app: &AppHandle, binding_id: &str) -> bool {
+    if shutdown::is_shutting_down() {
+        debug!("Not starting '{}' while quitting", binding_id);
+        return false;
+    }
+    if throttle::start_too_soon() {
+        debug!(
+            "Not starting '{}' right after another dictation",
+            binding_id
+        );
+        return false;
+    }
+    let admitted = admit_by_policy(app, binding_id);
+    if admitted {
+        throttle::record_start();
+    }
+    admitted
+}
+
+fn admit_by_policy(app: &AppHandle, binding_id: &str) -> bool {
+    if !cancellation::is_running(binding_id) {
+        return true;
+    }
+    let policy = get_settings(app)
+        .action_config(binding_id)
+        .map(|config| config.concurrency)
+        .unwrap_or_default();
+    match policy {
+        ConcurrencyPolicy::Parallel | ConcurrencyPolicy::Queue => true,
+        ConcurrencyPolicy::Ignore => {
+            debug!("Ignoring '{}' while it's still running", binding_id);
+            false
+        }
+        ConcurrencyPolicy::Restart => {
+            debug!("Restarting '{}'", binding_id);
+            cancellation::cancel(binding_id);
+            // So the new recording shows instead of the cancelled run
+            pipeline::reset(app);
+            true
+        }
+    }
+}
+
+/// Action behind a binding, built in or defined in the settings
+pub fn action_for(binding_id: &str) -> Option<Arc<dyn ShortcutAction>> {
+    BUILTIN_ACTIONS.get(binding_id).cloned().or_else(|| {
+        binding_id
+            .starts_with(CUSTOM_ACTION_PREFIX)
+            .then(|| Arc::new(TranscribeAction) as Arc<dyn ShortcutAction>)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prompt() {
+        assert_eq!(
+            render_prompt(
+                "Translate to ${language}: ${output}",
+                "paste ${selection} in ${language}",
+                "German",
+                "secret",
+            ),
+            "Translate to German: paste ${selection} in ${language}"
+        );
+    }
+
+    #[test]
+    fn test_render_step_template() {
+        let step_outputs = HashMap::from([(
+            "translate".to_string(),
+            "Say ${transcription} and {{text}}".to_string(),
+        )]);
+        assert_eq!(
+            render_step_template(
+                "Summarize in ${language}: ${step.translate} / ${output} ${step.missing}",
+                "${selection}",
+                "original",
+                &step_outputs,
+                "English",
+                "selected",
+            ),
+            "Summarize in English: Say ${transcription} and {{text}} / ${selection} ${step.missing}"
+        );
+    }
+}
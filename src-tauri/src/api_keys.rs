@@ -0,0 +1,222 @@
+use crate::llm_error::LlmError;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a rate limited key is skipped when the provider gives no `Retry-After`
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// How long a rejected or exhausted key is skipped; it's retried afterwards
+/// in case the account was topped up or the key re-enabled
+const REJECTED_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Default)]
+struct KeyState {
+    requests: u64,
+    failures: u64,
+    rate_limited: u64,
+    rejected: u64,
+    cooldown_until: Option<Instant>,
+}
+
+/// Usage and health of the keys seen this session, keyed by provider id and key
+static KEY_STATES: Lazy<Mutex<HashMap<(String, String), KeyState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Per-key usage since the app started
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct ApiKeyUsage {
+    pub provider_id: String,
+    /// Last characters of the key, enough to tell keys apart
+    pub key_hint: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub rate_limited: u64,
+    /// Authentication or quota failures
+    pub rejected: u64,
+    /// Seconds until the key is tried first again, if it's cooling down
+    pub cooldown_secs: Option<u64>,
+}
+
+/// Whether `error` is tied to the key, so another key may succeed
+pub fn is_key_error(error: &LlmError) -> bool {
+    matches!(
+        error,
+        LlmError::Auth { .. } | LlmError::RateLimited { .. } | LlmError::Quota { .. }
+    )
+}
+
+/// Non-empty keys without duplicates, in configured order
+pub fn dedup_keys(keys: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for key in keys {
+        let key = key.trim().to_string();
+        if !key.is_empty() && !unique.contains(&key) {
+            unique.push(key);
+        }
+    }
+    unique
+}
+
+/// Keys in the order they should be tried: configured order, with keys that
+/// are cooling down moved to the back (soonest available first). Never empty,
+/// so providers without a key still get one attempt.
+pub fn ordered_keys(provider_id: &str, keys: &[String]) -> Vec<String> {
+    let states = KEY_STATES.lock().unwrap();
+    order_keys(keys, Instant::now(), |key| {
+        states
+            .get(&(provider_id.to_string(), key.to_string()))
+            .and_then(|state| state.cooldown_until)
+    })
+}
+
+fn order_keys(
+    keys: &[String],
+    now: Instant,
+    cooldown_until: impl Fn(&str) -> Option<Instant>,
+) -> Vec<String> {
+    if keys.is_empty() {
+        return vec![String::new()];
+    }
+
+    let mut ordered: Vec<(Option<Instant>, &String)> = keys
+        .iter()
+        .map(|key| (cooldown_until(key).filter(|until| *until > now), key))
+        .collect();
+    // Stable, so available keys keep their configured order
+    ordered.sort_by_key(|(until, _)| (until.is_some(), *until));
+    ordered.into_iter().map(|(_, key)| key.clone()).collect()
+}
+
+/// Record the outcome of a request made with `key`
+pub fn record_result(provider_id: &str, key: &str, result: Result<(), &LlmError>) {
+    let mut states = KEY_STATES.lock().unwrap();
+    let state = states
+        .entry((provider_id.to_string(), key.to_string()))
+        .or_default();
+    state.requests += 1;
+
+    let error = match result {
+        Ok(()) => {
+            state.cooldown_until = None;
+            return;
+        }
+        Err(error) => error,
+    };
+    state.failures += 1;
+
+    let cooldown = match error {
+        LlmError::RateLimited {
+            retry_after_secs, ..
+        } => {
+            state.rate_limited += 1;
+            retry_after_secs
+                .map(Duration::from_secs)
+                .unwrap_or(RATE_LIMIT_COOLDOWN)
+        }
+        LlmError::Auth { .. } | LlmError::Quota { .. } => {
+            state.rejected += 1;
+            REJECTED_COOLDOWN
+        }
+        _ => return,
+    };
+    warn!(
+        "API key {} of provider '{}' failed ({}), skipping it for {:?}",
+        mask_key(key),
+        provider_id,
+        error.user_message(),
+        cooldown
+    );
+    state.cooldown_until = Some(Instant::now() + cooldown);
+}
+
+/// Usage of every key seen this session, optionally for one provider
+pub fn key_usage(provider_id: Option<&str>) -> Vec<ApiKeyUsage> {
+    let now = Instant::now();
+    let states = KEY_STATES.lock().unwrap();
+    let mut usage: Vec<ApiKeyUsage> = states
+        .iter()
+        .filter(|((provider, key), _)| {
+            !key.is_empty() && provider_id.is_none_or(|id| id == provider)
+        })
+        .map(|((provider, key), state)| ApiKeyUsage {
+            provider_id: provider.clone(),
+            key_hint: mask_key(key),
+            requests: state.requests,
+            failures: state.failures,
+            rate_limited: state.rate_limited,
+            rejected: state.rejected,
+            cooldown_secs: state
+                .cooldown_until
+                .filter(|until| *until > now)
+                .map(|until| (until - now).as_secs()),
+        })
+        .collect();
+    usage.sort_by(|a, b| {
+        a.provider_id
+            .cmp(&b.provider_id)
+            .then(a.key_hint.cmp(&b.key_hint))
+    });
+    usage
+}
+
+/// Forget the health of a provider's keys, e.g. after they were edited
+pub fn reset(provider_id: &str) {
+    debug!("Resetting API key state of provider '{}'", provider_id);
+    KEY_STATES
+        .lock()
+        .unwrap()
+        .retain(|(provider, _), _| provider != provider_id);
+}
+
+pub fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "…".to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("…{}", tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooling_keys_move_to_the_back() {
+        let keys: Vec<String> = ["a", "b", "c"].iter().map(|k| k.to_string()).collect();
+        let now = Instant::now();
+        let soon = now + Duration::from_secs(10);
+        let later = now + Duration::from_secs(60);
+
+        let ordered = order_keys(&keys, now, |key| match key {
+            "a" => Some(later),
+            "b" => Some(soon),
+            _ => None,
+        });
+        assert_eq!(ordered, vec!["c", "b", "a"]);
+
+        // Expired cooldowns no longer count
+        let ordered = order_keys(&keys, now, |key| (key == "a").then_some(now));
+        assert_eq!(ordered, vec!["a", "b", "c"]);
+
+        assert_eq!(order_keys(&[], now, |_| None), vec![String::new()]);
+    }
+
+    #[test]
+    fn test_dedup_and_mask_keys() {
+        let keys = dedup_keys(vec![
+            "gsk_primary_key_1234".to_string(),
+            " ".to_string(),
+            "gsk_primary_key_1234".to_string(),
+            "gsk_backup_key_5678".to_string(),
+        ]);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(mask_key(&keys[1]), "…5678");
+        assert_eq!(mask_key("short"), "…");
+    }
+}
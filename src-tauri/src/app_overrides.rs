@@ -0,0 +1,192 @@
+use crate::settings::{AppOverride, AppSettings};
+use log::debug;
+
+/// Lowercase executable name without directory or `.exe`, so "Code.exe",
+/// "/usr/share/code/code" and "code" all match the same override
+pub fn normalize_process_name(name: &str) -> String {
+    let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name).trim();
+    let lower = file_name.to_lowercase();
+    match lower.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => lower,
+    }
+}
+
+pub fn find_override<'a>(settings: &'a AppSettings, process_name: &str) -> Option<&'a AppOverride> {
+    let process_name = normalize_process_name(process_name);
+    settings
+        .app_overrides
+        .iter()
+        .find(|(name, _)| normalize_process_name(name) == process_name)
+        .map(|(_, app_override)| app_override)
+}
+
+/// Apply the override for `process_name` to `settings`, if there is one.
+/// Returns whether one matched.
+pub fn apply(settings: &mut AppSettings, process_name: &str) -> bool {
+    let app_override = match find_override(settings, process_name) {
+        Some(app_override) => app_override.clone(),
+        None => return false,
+    };
+    debug!("Applying settings override for '{}'", process_name);
+
+    if let Some(language) = app_override.selected_language {
+        settings.selected_language = language;
+    }
+    if let Some(enabled) = app_override.post_process_enabled {
+        settings.post_process_enabled = enabled;
+    }
+    if let Some(prompt_id) = app_override.post_process_prompt_id {
+        settings.post_process_selected_prompt_id = Some(prompt_id);
+    }
+    if let Some(paste_method) = app_override.paste_method {
+        settings.paste_method = paste_method;
+    }
+    true
+}
+
+/// Settings for dictating into the focused app: the stored settings with the
+/// app's override applied
+pub fn settings_for_focused_app(mut settings: AppSettings) -> AppSettings {
+    if settings.app_overrides.is_empty() {
+        return settings;
+    }
+    match focused_process_name() {
+        Some(process_name) => {
+            if !apply(&mut settings, &process_name) {
+                debug!("No settings override for focused app '{}'", process_name);
+            }
+        }
+        None => debug!("Couldn't determine the focused app"),
+    }
+    settings
+}
+
+/// Process name of the app owning the focused window
+#[cfg(target_os = "windows")]
+pub fn focused_process_name() -> Option<String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.is_invalid() {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(window, Some(&mut pid as *mut u32));
+        if pid == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+        Some(normalize_process_name(&String::from_utf16_lossy(
+            &buffer[..len as usize],
+        )))
+    }
+}
+
+/// Process name of the frontmost app, from Launch Services
+#[cfg(target_os = "macos")]
+pub fn focused_process_name() -> Option<String> {
+    let front = command_output("lsappinfo", &["front"])?;
+    let info = command_output(
+        "lsappinfo",
+        &["info", "-only", "executablepath", front.trim()],
+    )?;
+    // Printed as "LSExecutablePath"="/Applications/Safari.app/Contents/MacOS/Safari"
+    let path = info.split_once('=')?.1.trim().trim_matches('"');
+    Some(normalize_process_name(path))
+}
+
+/// Process name of the focused window's owner. X11 goes through xdotool;
+/// Wayland has no common protocol for it, so only Hyprland and Sway are asked.
+#[cfg(target_os = "linux")]
+pub fn focused_process_name() -> Option<String> {
+    let pid = if crate::utils::is_wayland() {
+        focused_wayland_pid()?
+    } else {
+        command_output("xdotool", &["getactivewindow", "getwindowpid"])?
+            .trim()
+            .parse()
+            .ok()?
+    };
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    Some(normalize_process_name(&comm))
+}
+
+#[cfg(target_os = "linux")]
+fn focused_wayland_pid() -> Option<u64> {
+    if std::env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok() {
+        let output = command_output("hyprctl", &["activewindow", "-j"])?;
+        let window: serde_json::Value = serde_json::from_str(&output).ok()?;
+        return window.get("pid")?.as_u64();
+    }
+    if std::env::var("SWAYSOCK").is_ok() {
+        let output = command_output("swaymsg", &["-t", "get_tree"])?;
+        let tree: serde_json::Value = serde_json::from_str(&output).ok()?;
+        return focused_sway_node(&tree)?.get("pid")?.as_u64();
+    }
+    None
+}
+
+/// The focused window somewhere below `node` in Sway's layout tree
+#[cfg(target_os = "linux")]
+fn focused_sway_node(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    if node.get("focused").and_then(|focused| focused.as_bool()) == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node.get(*key)?.as_array())
+        .flatten()
+        .find_map(focused_sway_node)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub fn focused_process_name() -> Option<String> {
+    None
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_process_name() {
+        assert_eq!(normalize_process_name("Code.exe"), "code");
+        assert_eq!(
+            normalize_process_name(r"C:\Program Files\Mozilla Thunderbird\thunderbird.exe"),
+            "thunderbird"
+        );
+        assert_eq!(normalize_process_name("/usr/share/code/code\n"), "code");
+        assert_eq!(normalize_process_name("Mail"), "mail");
+    }
+}
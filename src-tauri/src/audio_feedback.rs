@@ -2,7 +2,7 @@ use crate::settings::SoundTheme;
 use crate::settings::{self, AppSettings};
 use cpal::traits::{DeviceTrait, HostTrait};
 use log::{debug, error, warn};
-use rodio::OutputStreamBuilder;
+use rodio::{OutputStream, OutputStreamBuilder};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
@@ -20,8 +20,15 @@ fn resolve_sound_path(
     sound_type: SoundType,
 ) -> Option<PathBuf> {
     let sound_file = get_sound_path(settings, sound_type);
-    let base_dir = get_sound_base_dir(settings);
-    app.path().resolve(&sound_file, base_dir).ok()
+    match settings.sound_theme {
+        SoundTheme::Custom => crate::portable::app_data_dir(app)
+            .ok()
+            .map(|dir| dir.join(sound_file)),
+        _ => app
+            .path()
+            .resolve(&sound_file, tauri::path::BaseDirectory::Resource)
+            .ok(),
+    }
 }
 
 fn get_sound_path(settings: &AppSettings, sound_type: SoundType) -> String {
@@ -33,13 +40,6 @@ fn get_sound_path(settings: &AppSettings, sound_type: SoundType) -> String {
     }
 }
 
-fn get_sound_base_dir(settings: &AppSettings) -> tauri::path::BaseDirectory {
-    match settings.sound_theme {
-        SoundTheme::Custom => tauri::path::BaseDirectory::AppData,
-        _ => tauri::path::BaseDirectory::Resource,
-    }
-}
-
 pub fn play_feedback_sound(app: &AppHandle, sound_type: SoundType) {
     let settings = settings::get_settings(app);
     if !settings.audio_feedback {
@@ -89,11 +89,11 @@ fn play_sound_at_path(app: &AppHandle, path: &Path) -> Result<(), Box<dyn std::e
     play_audio_file(path, selected_device, volume)
 }
 
-fn play_audio_file(
-    path: &std::path::Path,
+/// Output stream on the device picked in the settings, falling back to the
+/// default device
+pub fn open_output_stream(
     selected_device: Option<String>,
-    volume: f32,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<OutputStream, Box<dyn std::error::Error>> {
     let stream_builder = if let Some(device_name) = selected_device {
         if device_name == "Default" {
             debug!("Using default device");
@@ -123,7 +123,15 @@ fn play_audio_file(
         OutputStreamBuilder::from_default_device()?
     };
 
-    let stream_handle = stream_builder.open_stream()?;
+    Ok(stream_builder.open_stream()?)
+}
+
+fn play_audio_file(
+    path: &std::path::Path,
+    selected_device: Option<String>,
+    volume: f32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stream_handle = open_output_stream(selected_device)?;
     let mixer = stream_handle.mixer();
 
     let file = File::open(path)?;
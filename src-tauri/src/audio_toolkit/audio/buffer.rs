@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const SAMPLE_BYTES: usize = std::mem::size_of::<f32>();
+
+/// Numbers the spill files of this process
+static NEXT_SPILL: AtomicUsize = AtomicUsize::new(0);
+
+/// How much of the recording is held in memory and how much was spilled to
+/// disk, in bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferUsage {
+    pub memory_bytes: usize,
+    pub spilled_bytes: usize,
+}
+
+/// Counters a buffer keeps up to date for other threads to read
+#[derive(Default)]
+pub struct SharedUsage {
+    memory_bytes: AtomicUsize,
+    spilled_bytes: AtomicUsize,
+}
+
+impl SharedUsage {
+    pub fn get(&self) -> BufferUsage {
+        BufferUsage {
+            memory_bytes: self.memory_bytes.load(Ordering::Relaxed),
+            spilled_bytes: self.spilled_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Samples of a recording, held in memory up to a limit; beyond it they're
+/// moved to a temporary file, to be read back once the recording stops
+pub struct SampleBuffer {
+    memory: Vec<f32>,
+    limit_bytes: usize,
+    spill: Option<(PathBuf, BufWriter<File>)>,
+    spilled: usize,
+    usage: Arc<SharedUsage>,
+}
+
+impl SampleBuffer {
+    pub fn new(usage: Arc<SharedUsage>) -> Self {
+        Self {
+            memory: Vec::new(),
+            limit_bytes: usize::MAX,
+            spill: None,
+            spilled: 0,
+            usage,
+        }
+    }
+
+    /// Empty the buffer for a new recording held in at most `limit_bytes`
+    pub fn reset(&mut self, limit_bytes: usize) {
+        self.clear();
+        self.limit_bytes = limit_bytes;
+    }
+
+    pub fn extend_from_slice(&mut self, samples: &[f32]) {
+        let over = (self.memory.len() + samples.len()) * SAMPLE_BYTES > self.limit_bytes;
+        if over && !self.memory.is_empty() {
+            if let Err(e) = self.spill() {
+                log::warn!(
+                    "Failed to spill the recording to disk, keeping it in memory: {}",
+                    e
+                );
+                self.limit_bytes = usize::MAX;
+            }
+        }
+        self.memory.extend_from_slice(samples);
+        self.update_usage();
+    }
+
+    /// Move the samples in memory to the spill file
+    fn spill(&mut self) -> std::io::Result<()> {
+        if self.spill.is_none() {
+            let path = std::env::temp_dir().join(format!(
+                "babbl-recording-{}-{}.f32",
+                std::process::id(),
+                NEXT_SPILL.fetch_add(1, Ordering::Relaxed)
+            ));
+            let file = File::create(&path)?;
+            log::debug!("Spilling the recording to {:?}", path);
+            self.spill = Some((path, BufWriter::new(file)));
+        }
+        if let Some((_, writer)) = &mut self.spill {
+            for sample in &self.memory {
+                writer.write_all(&sample.to_le_bytes())?;
+            }
+        }
+        self.spilled += self.memory.len();
+        self.memory.clear();
+        // Give the memory back instead of keeping the grown capacity
+        self.memory.shrink_to(self.limit_bytes / SAMPLE_BYTES);
+        Ok(())
+    }
+
+    /// All samples recorded, leaving the buffer empty
+    pub fn take(&mut self) -> Vec<f32> {
+        let mut samples = Vec::new();
+        if let Some((path, writer)) = self.spill.take() {
+            match read_spill(writer, &path, self.spilled) {
+                Ok(spilled) => samples = spilled,
+                Err(e) => log::error!("Failed to read back the spilled recording: {}", e),
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+        samples.append(&mut self.memory);
+        self.clear();
+        samples
+    }
+
+    pub fn clear(&mut self) {
+        if let Some((path, writer)) = self.spill.take() {
+            drop(writer);
+            let _ = std::fs::remove_file(&path);
+        }
+        self.memory = Vec::new();
+        self.spilled = 0;
+        self.update_usage();
+    }
+
+    fn update_usage(&self) {
+        let usage = &self.usage;
+        usage
+            .memory_bytes
+            .store(self.memory.len() * SAMPLE_BYTES, Ordering::Relaxed);
+        usage
+            .spilled_bytes
+            .store(self.spilled * SAMPLE_BYTES, Ordering::Relaxed);
+    }
+}
+
+impl Drop for SampleBuffer {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+fn read_spill(writer: BufWriter<File>, path: &Path, samples: usize) -> std::io::Result<Vec<f32>> {
+    writer.into_inner().map_err(|e| e.into_error())?;
+    let mut bytes = Vec::with_capacity(samples * SAMPLE_BYTES);
+    File::open(path)?.read_to_end(&mut bytes)?;
+    let mut spilled = Vec::with_capacity(samples);
+    spilled.extend(
+        bytes
+            .chunks_exact(SAMPLE_BYTES)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+    );
+    Ok(spilled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spills_beyond_limit() {
+        let usage = Arc::new(SharedUsage::default());
+        let mut buffer = SampleBuffer::new(usage.clone());
+        buffer.reset(100 * SAMPLE_BYTES);
+        let samples: Vec<f32> = (0..250).map(|i| i as f32 / 250.0).collect();
+        for chunk in samples.chunks(30) {
+            buffer.extend_from_slice(chunk);
+        }
+        let spilled = usage.get().spilled_bytes;
+        assert!(spilled > 0);
+        assert!(usage.get().memory_bytes <= 100 * SAMPLE_BYTES);
+        let path = buffer.spill.as_ref().unwrap().0.clone();
+
+        assert_eq!(buffer.take(), samples);
+        assert!(!path.exists());
+        assert_eq!(usage.get().spilled_bytes, 0);
+    }
+}
@@ -0,0 +1,173 @@
+use rustfft::{num_complex::Complex32, FftPlanner};
+
+/// Coefficients kept per frame
+pub const MFCC_COEFFICIENTS: usize = 13;
+
+/// Mel-frequency cepstral coefficients of one frame
+pub type MfccFrame = [f32; MFCC_COEFFICIENTS];
+
+/// 25 ms frames every 10 ms at 16 kHz
+const FRAME_LEN: usize = 400;
+const HOP: usize = 160;
+const FFT_LEN: usize = 512;
+
+const MEL_BANDS: usize = 26;
+
+const PRE_EMPHASIS: f32 = 0.97;
+
+/// Frames this much quieter than the loudest, in natural log of power, are
+/// the silence around the speech; about 30 dB
+const SILENCE_BELOW_PEAK: f32 = 6.9;
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular filters spaced evenly on the mel scale, as weights per FFT bin
+fn mel_filters(sample_rate: u32) -> Vec<Vec<f32>> {
+    let max_mel = hz_to_mel(sample_rate as f32 / 2.0);
+    let edges: Vec<f32> = (0..MEL_BANDS + 2)
+        .map(|i| {
+            let hz = mel_to_hz(max_mel * i as f32 / (MEL_BANDS + 1) as f32);
+            hz * FFT_LEN as f32 / sample_rate as f32
+        })
+        .collect();
+
+    edges
+        .windows(3)
+        .map(|edge| {
+            let (low, center, high) = (edge[0], edge[1], edge[2]);
+            (0..=FFT_LEN / 2)
+                .map(|bin| {
+                    let bin = bin as f32;
+                    if bin <= low || bin >= high {
+                        0.0
+                    } else if bin <= center {
+                        (bin - low) / (center - low)
+                    } else {
+                        (high - bin) / (high - center)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// MFCCs of `samples`, with the silence before and after the speech left out
+/// and each coefficient's mean taken away, so the microphone and the
+/// loudness matter little. Empty when the audio is shorter than a frame.
+pub fn mfcc(samples: &[f32], sample_rate: u32) -> Vec<MfccFrame> {
+    if samples.len() < FRAME_LEN {
+        return Vec::new();
+    }
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(FFT_LEN);
+    let filters = mel_filters(sample_rate);
+    let window: Vec<f32> = (0..FRAME_LEN)
+        .map(|i| {
+            0.54 - 0.46 * (2.0 * std::f32::consts::PI * i as f32 / (FRAME_LEN - 1) as f32).cos()
+        })
+        .collect();
+
+    let emphasized: Vec<f32> = std::iter::once(samples[0])
+        .chain(
+            samples
+                .windows(2)
+                .map(|pair| pair[1] - PRE_EMPHASIS * pair[0]),
+        )
+        .collect();
+
+    let mut buffer = vec![Complex32::new(0.0, 0.0); FFT_LEN];
+    let mut frames: Vec<(f32, MfccFrame)> = Vec::new();
+    for frame in emphasized.windows(FRAME_LEN).step_by(HOP) {
+        let energy: f32 = frame.iter().map(|sample| sample * sample).sum();
+        for (value, (sample, weight)) in buffer.iter_mut().zip(frame.iter().zip(&window)) {
+            *value = Complex32::new(sample * weight, 0.0);
+        }
+        buffer[FRAME_LEN..].fill(Complex32::new(0.0, 0.0));
+        fft.process(&mut buffer);
+
+        let log_bands: Vec<f32> = filters
+            .iter()
+            .map(|filter| {
+                let band: f32 = filter
+                    .iter()
+                    .zip(&buffer)
+                    .map(|(weight, bin)| weight * bin.norm_sqr())
+                    .sum();
+                (band + 1e-10).ln()
+            })
+            .collect();
+        // DCT-II of the log mel energies
+        let mut coefficients = [0.0; MFCC_COEFFICIENTS];
+        for (k, coefficient) in coefficients.iter_mut().enumerate() {
+            *coefficient = log_bands
+                .iter()
+                .enumerate()
+                .map(|(m, band)| {
+                    band * (std::f32::consts::PI * k as f32 * (m as f32 + 0.5) / MEL_BANDS as f32)
+                        .cos()
+                })
+                .sum();
+        }
+        frames.push(((energy + 1e-10).ln(), coefficients));
+    }
+
+    let peak = frames
+        .iter()
+        .map(|(energy, _)| *energy)
+        .fold(f32::MIN, f32::max);
+    let is_speech = |(energy, _): &(f32, MfccFrame)| *energy >= peak - SILENCE_BELOW_PEAK;
+    let first = frames.iter().position(is_speech);
+    let last = frames.iter().rposition(is_speech);
+    let speech: Vec<MfccFrame> = match (first, last) {
+        (Some(first), Some(last)) => frames[first..=last]
+            .iter()
+            .map(|(_, coefficients)| *coefficients)
+            .collect(),
+        _ => return Vec::new(),
+    };
+
+    let mut mean = [0.0; MFCC_COEFFICIENTS];
+    for frame in &speech {
+        for (sum, coefficient) in mean.iter_mut().zip(frame) {
+            *sum += coefficient / speech.len() as f32;
+        }
+    }
+    speech
+        .into_iter()
+        .map(|mut frame| {
+            for (coefficient, mean) in frame.iter_mut().zip(&mean) {
+                *coefficient -= mean;
+            }
+            frame
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(hz: f32, secs: f32) -> Vec<f32> {
+        (0..(16000.0 * secs) as usize)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * hz * i as f32 / 16000.0).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_mfcc() {
+        assert!(mfcc(&tone(440.0, 0.01), 16000).is_empty());
+
+        // The silence around the tone is left out
+        let mut samples = vec![0.0; 8000];
+        samples.extend(tone(440.0, 0.5));
+        samples.extend(vec![0.0; 8000]);
+        let frames = mfcc(&samples, 16000);
+        assert!((45..=55).contains(&frames.len()), "{}", frames.len());
+        assert!(frames.iter().flatten().all(|value| value.is_finite()));
+    }
+}
@@ -1,12 +1,20 @@
 // Re-export all audio components
+mod buffer;
 mod device;
+mod mfcc;
 mod recorder;
 mod resampler;
+mod spool;
 mod utils;
 mod visualizer;
+mod waveform;
 
+pub use buffer::{BufferUsage, SampleBuffer, SharedUsage};
 pub use device::{list_input_devices, list_output_devices, CpalDeviceInfo};
+pub use mfcc::{mfcc, MfccFrame};
 pub use recorder::AudioRecorder;
 pub use resampler::FrameResampler;
-pub use utils::save_wav_file;
+pub use spool::AudioSpool;
+pub use utils::{read_wav_file, save_wav_file};
 pub use visualizer::AudioVisualiser;
+pub use waveform::WaveformDownsampler;
@@ -1,7 +1,11 @@
 use std::{
     io::Error,
-    sync::{mpsc, Arc, Mutex},
-    time::Duration,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use cpal::{
@@ -10,15 +14,45 @@ use cpal::{
 };
 
 use crate::audio_toolkit::{
-    audio::{AudioVisualiser, FrameResampler},
+    audio::{
+        AudioSpool, AudioVisualiser, BufferUsage, FrameResampler, SampleBuffer, SharedUsage,
+        WaveformDownsampler,
+    },
     constants,
     vad::{self, VadFrame},
     VoiceActivityDetector,
 };
 
+/// Waveform frames per second
+const WAVEFORM_FPS: u32 = 30;
+/// Peaks per waveform frame
+const WAVEFORM_POINTS: usize = 64;
+
+type SamplesCallback = Arc<dyn Fn(Vec<f32>) + Send + Sync + 'static>;
+
+/// Gets what's said while not recording, one utterance at a time
+#[derive(Clone)]
+struct UtteranceListener {
+    listening: Arc<AtomicBool>,
+    max_samples: usize,
+    cb: SamplesCallback,
+}
+
+/// Callbacks moved into the worker thread
+#[derive(Clone)]
+struct Callbacks {
+    level: Option<SamplesCallback>,
+    waveform: Option<SamplesCallback>,
+    utterance: Option<UtteranceListener>,
+}
+
 enum Cmd {
-    Start,
+    /// With the file to spool the recording to, if any, and the bytes of it
+    /// to hold in memory
+    Start(Option<PathBuf>, usize),
     Stop(mpsc::Sender<Vec<f32>>),
+    /// What's recorded so far, while recording on
+    Take(mpsc::Sender<Vec<f32>>),
     Shutdown,
 }
 
@@ -27,7 +61,15 @@ pub struct AudioRecorder {
     cmd_tx: Option<mpsc::Sender<Cmd>>,
     worker_handle: Option<std::thread::JoinHandle<()>>,
     vad: Option<Arc<Mutex<Box<dyn vad::VoiceActivityDetector>>>>,
-    level_cb: Option<Arc<dyn Fn(Vec<f32>) + Send + Sync + 'static>>,
+    level_cb: Option<SamplesCallback>,
+    waveform_cb: Option<SamplesCallback>,
+    utterance: Option<UtteranceListener>,
+    memory_limit: usize,
+    usage: Arc<SharedUsage>,
+    /// When the VAD last heard speech in the current recording
+    speech_at: Arc<Mutex<Instant>>,
+    /// Set when the stream's device goes away
+    stream_failed: Arc<AtomicBool>,
 }
 
 impl AudioRecorder {
@@ -38,6 +80,12 @@ impl AudioRecorder {
             worker_handle: None,
             vad: None,
             level_cb: None,
+            waveform_cb: None,
+            utterance: None,
+            memory_limit: usize::MAX,
+            usage: Arc::new(SharedUsage::default()),
+            speech_at: Arc::new(Mutex::new(Instant::now())),
+            stream_failed: Arc::new(AtomicBool::new(false)),
         })
     }
 
@@ -54,6 +102,36 @@ impl AudioRecorder {
         self
     }
 
+    /// Called with `WAVEFORM_POINTS` peak amplitudes, `WAVEFORM_FPS` times a
+    /// second while recording
+    pub fn with_waveform_callback<F>(mut self, cb: F) -> Self
+    where
+        F: Fn(Vec<f32>) + Send + Sync + 'static,
+    {
+        self.waveform_cb = Some(Arc::new(cb));
+        self
+    }
+
+    /// Called with each utterance the VAD picks up while not recording, as
+    /// long as `listening` is set. Utterances longer than `max_samples` are
+    /// dropped.
+    pub fn with_utterance_callback<F>(
+        mut self,
+        listening: Arc<AtomicBool>,
+        max_samples: usize,
+        cb: F,
+    ) -> Self
+    where
+        F: Fn(Vec<f32>) + Send + Sync + 'static,
+    {
+        self.utterance = Some(UtteranceListener {
+            listening,
+            max_samples,
+            cb: Arc::new(cb),
+        });
+        self
+    }
+
     pub fn open(&mut self, device: Option<Device>) -> Result<(), Box<dyn std::error::Error>> {
         if self.worker_handle.is_some() {
             return Ok(()); // already open
@@ -72,8 +150,16 @@ impl AudioRecorder {
 
         let thread_device = device.clone();
         let vad = self.vad.clone();
-        // Move the optional level callback into the worker thread
-        let level_cb = self.level_cb.clone();
+        // Move the optional callbacks into the worker thread
+        let callbacks = Callbacks {
+            level: self.level_cb.clone(),
+            waveform: self.waveform_cb.clone(),
+            utterance: self.utterance.clone(),
+        };
+        let usage = self.usage.clone();
+        let speech_at = self.speech_at.clone();
+        self.stream_failed.store(false, Ordering::Relaxed);
+        let stream_failed = self.stream_failed.clone();
 
         let worker = std::thread::spawn(move || {
             let config = AudioRecorder::get_preferred_config(&thread_device)
@@ -91,33 +177,61 @@ impl AudioRecorder {
             );
 
             let stream = match config.sample_format() {
-                cpal::SampleFormat::U8 => {
-                    AudioRecorder::build_stream::<u8>(&thread_device, &config, sample_tx, channels)
-                        .unwrap()
-                }
-                cpal::SampleFormat::I8 => {
-                    AudioRecorder::build_stream::<i8>(&thread_device, &config, sample_tx, channels)
-                        .unwrap()
-                }
-                cpal::SampleFormat::I16 => {
-                    AudioRecorder::build_stream::<i16>(&thread_device, &config, sample_tx, channels)
-                        .unwrap()
-                }
-                cpal::SampleFormat::I32 => {
-                    AudioRecorder::build_stream::<i32>(&thread_device, &config, sample_tx, channels)
-                        .unwrap()
-                }
-                cpal::SampleFormat::F32 => {
-                    AudioRecorder::build_stream::<f32>(&thread_device, &config, sample_tx, channels)
-                        .unwrap()
-                }
+                cpal::SampleFormat::U8 => AudioRecorder::build_stream::<u8>(
+                    &thread_device,
+                    &config,
+                    sample_tx,
+                    channels,
+                    stream_failed,
+                )
+                .unwrap(),
+                cpal::SampleFormat::I8 => AudioRecorder::build_stream::<i8>(
+                    &thread_device,
+                    &config,
+                    sample_tx,
+                    channels,
+                    stream_failed,
+                )
+                .unwrap(),
+                cpal::SampleFormat::I16 => AudioRecorder::build_stream::<i16>(
+                    &thread_device,
+                    &config,
+                    sample_tx,
+                    channels,
+                    stream_failed,
+                )
+                .unwrap(),
+                cpal::SampleFormat::I32 => AudioRecorder::build_stream::<i32>(
+                    &thread_device,
+                    &config,
+                    sample_tx,
+                    channels,
+                    stream_failed,
+                )
+                .unwrap(),
+                cpal::SampleFormat::F32 => AudioRecorder::build_stream::<f32>(
+                    &thread_device,
+                    &config,
+                    sample_tx,
+                    channels,
+                    stream_failed,
+                )
+                .unwrap(),
                 _ => panic!("unsupported sample format"),
             };
 
             stream.play().expect("failed to start stream");
 
             // keep the stream alive while we process samples
-            run_consumer(sample_rate, vad, sample_rx, cmd_rx, level_cb);
+            run_consumer(
+                sample_rate,
+                vad,
+                sample_rx,
+                cmd_rx,
+                callbacks,
+                usage,
+                speech_at,
+            );
             // stream is dropped here, after run_consumer returns
         });
 
@@ -128,13 +242,57 @@ impl AudioRecorder {
         Ok(())
     }
 
+    /// Whether the stream opened by `open` is still delivering samples: its
+    /// worker hasn't stopped and the stream hasn't reported an error
+    pub fn is_alive(&self) -> bool {
+        self.worker_handle
+            .as_ref()
+            .is_some_and(|worker| !worker.is_finished())
+            && !self.stream_failed.load(Ordering::Relaxed)
+    }
+
+    /// Bytes of the next recordings held in memory; the rest is spilled to
+    /// a temporary file until the recording stops
+    pub fn set_memory_limit(&mut self, limit_bytes: usize) {
+        self.memory_limit = limit_bytes;
+    }
+
+    /// How much of the current recording is in memory and on disk
+    pub fn buffer_usage(&self) -> BufferUsage {
+        self.usage.get()
+    }
+
     pub fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(tx) = &self.cmd_tx {
-            tx.send(Cmd::Start)?;
+            tx.send(Cmd::Start(None, self.memory_limit))?;
         }
         Ok(())
     }
 
+    /// Start recording and also write what's recorded to `spool` as it comes
+    /// in, until the recording stops
+    pub fn start_spooled(&self, spool: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(tx) = &self.cmd_tx {
+            tx.send(Cmd::Start(Some(spool), self.memory_limit))?;
+        }
+        Ok(())
+    }
+
+    /// What's been recorded since the recording started or this was last
+    /// called, without stopping it
+    pub fn take(&self) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let (resp_tx, resp_rx) = mpsc::channel();
+        if let Some(tx) = &self.cmd_tx {
+            tx.send(Cmd::Take(resp_tx))?;
+        }
+        Ok(resp_rx.recv()?)
+    }
+
+    /// Time since the VAD last heard speech in the current recording
+    pub fn silent_for(&self) -> Duration {
+        self.speech_at.lock().unwrap().elapsed()
+    }
+
     pub fn stop(&self) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
         let (resp_tx, resp_rx) = mpsc::channel();
         if let Some(tx) = &self.cmd_tx {
@@ -159,6 +317,7 @@ impl AudioRecorder {
         config: &cpal::SupportedStreamConfig,
         sample_tx: mpsc::Sender<Vec<f32>>,
         channels: usize,
+        stream_failed: Arc<AtomicBool>,
     ) -> Result<cpal::Stream, cpal::BuildStreamError>
     where
         T: Sample + SizedSample + Send + 'static,
@@ -195,7 +354,12 @@ impl AudioRecorder {
         device.build_input_stream(
             &config.clone().into(),
             stream_cb,
-            |err| log::error!("Stream error: {}", err),
+            move |err| {
+                log::error!("Stream error: {}", err);
+                if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+                    stream_failed.store(true, Ordering::Relaxed);
+                }
+            },
             None,
         )
     }
@@ -244,7 +408,9 @@ fn run_consumer(
     vad: Option<Arc<Mutex<Box<dyn vad::VoiceActivityDetector>>>>,
     sample_rx: mpsc::Receiver<Vec<f32>>,
     cmd_rx: mpsc::Receiver<Cmd>,
-    level_cb: Option<Arc<dyn Fn(Vec<f32>) + Send + Sync + 'static>>,
+    callbacks: Callbacks,
+    usage: Arc<SharedUsage>,
+    speech_at: Arc<Mutex<Instant>>,
 ) {
     let mut frame_resampler = FrameResampler::new(
         in_sample_rate as usize,
@@ -252,8 +418,15 @@ fn run_consumer(
         Duration::from_millis(30),
     );
 
-    let mut processed_samples = Vec::<f32>::new();
+    let mut processed_samples = SampleBuffer::new(usage);
+    // Samples of the latest frames, before they're spooled and buffered
+    let mut fresh = Vec::<f32>::new();
     let mut recording = false;
+    let mut spool: Option<AudioSpool> = None;
+    // What's being said while not recording, and whether it already ran
+    // longer than the listener wants
+    let mut utterance = Vec::<f32>::new();
+    let mut overlong = false;
 
     // ---------- spectrum visualisation setup ---------------------------- //
     const BUCKETS: usize = 16;
@@ -265,12 +438,14 @@ fn run_consumer(
         400.0,  // vocal_min_hz
         4000.0, // vocal_max_hz
     );
+    let mut waveform = WaveformDownsampler::new(in_sample_rate, WAVEFORM_FPS, WAVEFORM_POINTS);
 
     fn handle_frame(
         samples: &[f32],
         recording: bool,
         vad: &Option<Arc<Mutex<Box<dyn vad::VoiceActivityDetector>>>>,
         out_buf: &mut Vec<f32>,
+        speech_at: &Mutex<Instant>,
     ) {
         if !recording {
             return;
@@ -279,14 +454,68 @@ fn run_consumer(
         if let Some(vad_arc) = vad {
             let mut det = vad_arc.lock().unwrap();
             match det.push_frame(samples).unwrap_or(VadFrame::Speech(samples)) {
-                VadFrame::Speech(buf) => out_buf.extend_from_slice(buf),
+                VadFrame::Speech(buf) => {
+                    out_buf.extend_from_slice(buf);
+                    *speech_at.lock().unwrap() = Instant::now();
+                }
                 VadFrame::Noise => {}
             }
         } else {
             out_buf.extend_from_slice(samples);
+            *speech_at.lock().unwrap() = Instant::now();
+        }
+    }
+
+    /// Collect `samples` into `utterance` while not recording, handing it
+    /// to the listener once the speech ends
+    fn listen_frame(
+        samples: &[f32],
+        vad: &Option<Arc<Mutex<Box<dyn vad::VoiceActivityDetector>>>>,
+        listener: &UtteranceListener,
+        utterance: &mut Vec<f32>,
+        overlong: &mut bool,
+    ) {
+        // Without a VAD there's no telling where utterances end
+        let vad_arc = match vad {
+            Some(vad_arc) => vad_arc,
+            None => return,
+        };
+        let mut det = vad_arc.lock().unwrap();
+        match det.push_frame(samples) {
+            Ok(VadFrame::Speech(buf)) => {
+                if *overlong {
+                    return;
+                }
+                utterance.extend_from_slice(buf);
+                if utterance.len() > listener.max_samples {
+                    utterance.clear();
+                    *overlong = true;
+                }
+            }
+            Ok(VadFrame::Noise) => {
+                *overlong = false;
+                if !utterance.is_empty() {
+                    (listener.cb)(std::mem::take(utterance));
+                }
+            }
+            Err(e) => log::debug!("VAD failed while listening: {}", e),
         }
     }
 
+    fn keep(spool: &mut Option<AudioSpool>, buffer: &mut SampleBuffer, fresh: &mut Vec<f32>) {
+        if fresh.is_empty() {
+            return;
+        }
+        if let Some(writer) = spool {
+            if let Err(e) = writer.write(fresh) {
+                log::warn!("Failed to spool audio to {:?}: {}", writer.path(), e);
+                *spool = None;
+            }
+        }
+        buffer.extend_from_slice(fresh);
+        fresh.clear();
+    }
+
     loop {
         let raw = match sample_rx.recv() {
             Ok(s) => s,
@@ -295,23 +524,53 @@ fn run_consumer(
 
         // ---------- spectrum processing ---------------------------------- //
         if let Some(buckets) = visualizer.feed(&raw) {
-            if let Some(cb) = &level_cb {
+            if let Some(cb) = &callbacks.level {
                 cb(buckets);
             }
         }
 
+        // ---------- waveform, only while recording ------------------------ //
+        if recording {
+            if let Some(cb) = &callbacks.waveform {
+                waveform.feed(&raw, &mut |frame| cb(frame));
+            }
+        }
+
+        // ---------- utterances, only while not recording ------------------ //
+        let listener = callbacks
+            .utterance
+            .as_ref()
+            .filter(|listener| !recording && listener.listening.load(Ordering::Relaxed));
+        if listener.is_none() {
+            utterance.clear();
+            overlong = false;
+        }
+
         // ---------- existing pipeline ------------------------------------ //
         frame_resampler.push(&raw, &mut |frame: &[f32]| {
-            handle_frame(frame, recording, &vad, &mut processed_samples)
+            handle_frame(frame, recording, &vad, &mut fresh, &speech_at);
+            if let Some(listener) = listener {
+                listen_frame(frame, &vad, listener, &mut utterance, &mut overlong);
+            }
         });
+        keep(&mut spool, &mut processed_samples, &mut fresh);
 
         // non-blocking check for a command
         while let Ok(cmd) = cmd_rx.try_recv() {
             match cmd {
-                Cmd::Start => {
-                    processed_samples.clear();
+                Cmd::Start(spool_path, memory_limit) => {
+                    processed_samples.reset(memory_limit);
+                    spool = spool_path.and_then(|path| match AudioSpool::create(&path) {
+                        Ok(spool) => Some(spool),
+                        Err(e) => {
+                            log::warn!("Failed to create audio spool {:?}: {}", path, e);
+                            None
+                        }
+                    });
                     recording = true;
+                    *speech_at.lock().unwrap() = Instant::now();
                     visualizer.reset(); // Reset visualization buffer
+                    waveform.reset();
                     if let Some(v) = &vad {
                         v.lock().unwrap().reset();
                     }
@@ -321,10 +580,21 @@ fn run_consumer(
 
                     frame_resampler.finish(&mut |frame: &[f32]| {
                         // we still want to process the last few frames
-                        handle_frame(frame, true, &vad, &mut processed_samples)
+                        handle_frame(frame, true, &vad, &mut fresh, &speech_at)
                     });
+                    keep(&mut spool, &mut processed_samples, &mut fresh);
+                    if let Some(Err(e)) = spool.take().map(AudioSpool::finish) {
+                        log::warn!("Failed to finish audio spool: {}", e);
+                    }
 
-                    let _ = reply_tx.send(std::mem::take(&mut processed_samples));
+                    let _ = reply_tx.send(processed_samples.take());
+                    // Listening starts over, not in the recording's speech
+                    if let Some(v) = &vad {
+                        v.lock().unwrap().reset();
+                    }
+                }
+                Cmd::Take(reply_tx) => {
+                    let _ = reply_tx.send(processed_samples.take());
                 }
                 Cmd::Shutdown => return,
             }
@@ -0,0 +1,75 @@
+use crate::audio_toolkit::constants::WHISPER_SAMPLE_RATE;
+use hound::{WavSpec, WavWriter};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Samples written between header updates, one second of audio
+const FLUSH_EVERY: usize = WHISPER_SAMPLE_RATE as usize;
+
+/// WAV file a recording is written to as it comes in. The header is kept up
+/// to date every second, so the file stays readable if the process dies.
+pub struct AudioSpool {
+    path: PathBuf,
+    writer: WavWriter<BufWriter<File>>,
+    unflushed: usize,
+}
+
+impl AudioSpool {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, hound::Error> {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: WHISPER_SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            writer: WavWriter::create(path.as_ref(), spec)?,
+            unflushed: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), hound::Error> {
+        for sample in samples {
+            self.writer
+                .write_sample((sample * i16::MAX as f32) as i16)?;
+        }
+        self.unflushed += samples.len();
+        if self.unflushed >= FLUSH_EVERY {
+            self.writer.flush()?;
+            self.unflushed = 0;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), hound::Error> {
+        self.writer.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_toolkit::read_wav_file;
+
+    #[test]
+    fn test_spool_survives_crash() {
+        let path = std::env::temp_dir().join(format!("babbl-spool-{}.wav", std::process::id()));
+        let mut spool = AudioSpool::create(&path).unwrap();
+        spool.write(&vec![0.5; FLUSH_EVERY]).unwrap();
+        // Not flushed yet, so lost in a crash
+        spool.write(&[0.5; 10]).unwrap();
+        // A crash skips the finalizing drop
+        std::mem::forget(spool);
+
+        let samples = read_wav_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(samples.len(), FLUSH_EVERY);
+        assert!((samples[0] - 0.5).abs() < 0.001);
+    }
+}
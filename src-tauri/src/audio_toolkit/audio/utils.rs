@@ -1,7 +1,10 @@
+use super::FrameResampler;
+use crate::audio_toolkit::constants::WHISPER_SAMPLE_RATE;
 use anyhow::Result;
-use hound::{WavSpec, WavWriter};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
 use log::debug;
 use std::path::Path;
+use std::time::Duration;
 
 /// Save audio samples as a WAV file
 pub async fn save_wav_file<P: AsRef<Path>>(file_path: P, samples: &[f32]) -> Result<()> {
@@ -24,3 +27,61 @@ pub async fn save_wav_file<P: AsRef<Path>>(file_path: P, samples: &[f32]) -> Res
     debug!("Saved WAV file: {:?}", file_path.as_ref());
     Ok(())
 }
+
+/// Average the channels of interleaved samples
+fn to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Read a WAV file as mono samples at the rate the models expect
+pub fn read_wav_file<P: AsRef<Path>>(file_path: P) -> Result<Vec<f32>> {
+    let mut reader = WavReader::open(file_path.as_ref())?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    let mono = to_mono(&samples, spec.channels as usize);
+    debug!(
+        "Read {} samples at {} Hz from {:?}",
+        mono.len(),
+        spec.sample_rate,
+        file_path.as_ref()
+    );
+    if spec.sample_rate == WHISPER_SAMPLE_RATE {
+        return Ok(mono);
+    }
+
+    let mut resampler = FrameResampler::new(
+        spec.sample_rate as usize,
+        WHISPER_SAMPLE_RATE as usize,
+        Duration::from_millis(30),
+    );
+    let mut resampled = Vec::new();
+    resampler.push(&mono, |frame| resampled.extend_from_slice(frame));
+    resampler.finish(|frame| resampled.extend_from_slice(frame));
+    Ok(resampled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_mono() {
+        assert_eq!(to_mono(&[0.5, -0.5], 1), vec![0.5, -0.5]);
+        assert_eq!(to_mono(&[0.25, 0.75, -1.0, 0.0], 2), vec![0.5, -0.5]);
+    }
+}
@@ -0,0 +1,62 @@
+/// Cuts the input into frames of `sample_rate / fps` samples and reduces each
+/// to `points` peak amplitudes, for drawing a live waveform
+pub struct WaveformDownsampler {
+    frame_len: usize,
+    points: usize,
+    buffer: Vec<f32>,
+}
+
+impl WaveformDownsampler {
+    pub fn new(sample_rate: u32, fps: u32, points: usize) -> Self {
+        let frame_len = (sample_rate / fps.max(1)).max(points as u32) as usize;
+        Self {
+            frame_len,
+            points,
+            buffer: Vec::with_capacity(frame_len * 2),
+        }
+    }
+
+    /// Add samples; calls `emit` once per completed frame
+    pub fn feed(&mut self, samples: &[f32], emit: &mut impl FnMut(Vec<f32>)) {
+        self.buffer.extend_from_slice(samples);
+
+        while self.buffer.len() >= self.frame_len {
+            let frame: Vec<f32> = self.buffer[..self.frame_len]
+                .chunks(self.frame_len.div_ceil(self.points))
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+                        .min(1.0)
+                })
+                .collect();
+            self.buffer.drain(..self.frame_len);
+            emit(frame);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed() {
+        // 100 samples per frame, 4 points of 25 samples each
+        let mut downsampler = WaveformDownsampler::new(3000, 30, 4);
+        let mut frames = Vec::new();
+
+        let mut samples = vec![0.1; 150];
+        samples[30] = -0.8;
+        downsampler.feed(&samples, &mut |frame| frames.push(frame));
+        assert_eq!(frames, vec![vec![0.1, 0.8, 0.1, 0.1]]);
+
+        // The leftover 50 samples complete the next frame
+        downsampler.feed(&[2.0; 50], &mut |frame| frames.push(frame));
+        assert_eq!(frames[1], vec![0.1, 0.1, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,79 @@
+//! Starting at login. The entry is registered through the autostart plugin
+//! (the `Run` registry key on Windows, a launch agent on macOS) with
+//! `--autostart`, so a start at login can stay in the tray and wait for the
+//! audio devices before opening the microphone.
+
+use crate::settings::AppSettings;
+use log::debug;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_autostart::ManagerExt;
+
+/// Command line flag the login entry starts Babbl with
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+/// Longest wait after a start at login
+pub const MAX_DELAY_SECS: u32 = 120;
+
+static AUTOSTARTED: Lazy<bool> = Lazy::new(|| std::env::args().any(|arg| arg == AUTOSTART_ARG));
+
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct AutostartStatus {
+    /// Whether the login entry is registered with the OS
+    pub registered: bool,
+    pub enabled: bool,
+    pub minimized: bool,
+    pub delay_secs: u32,
+}
+
+/// Whether this launch came from the login entry
+pub fn is_autostarted() -> bool {
+    *AUTOSTARTED
+}
+
+/// Arguments the login entry starts Babbl with
+pub fn launch_args() -> Vec<&'static str> {
+    let mut args = crate::portable::launch_args();
+    args.push(AUTOSTART_ARG);
+    args
+}
+
+/// Whether Babbl starts in the tray, without showing the main window
+pub fn starts_hidden(settings: &AppSettings) -> bool {
+    settings.start_hidden || (is_autostarted() && settings.autostart_minimized)
+}
+
+/// How long to wait before opening the microphone at startup
+pub fn microphone_delay(settings: &AppSettings) -> Duration {
+    if is_autostarted() {
+        Duration::from_secs(settings.autostart_delay_secs.min(MAX_DELAY_SECS) as u64)
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// Register or remove the login entry per `settings`. Registering again
+/// also updates the arguments of an entry from an older version.
+pub fn sync(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let manager = app.autolaunch();
+    let result = if settings.autostart_enabled {
+        manager.enable()
+    } else {
+        manager.disable()
+    };
+    result.map_err(|e| format!("Failed to update autostart: {}", e))?;
+    debug!("Autostart enabled: {}", settings.autostart_enabled);
+    Ok(())
+}
+
+pub fn status(app: &AppHandle, settings: &AppSettings) -> AutostartStatus {
+    AutostartStatus {
+        registered: app.autolaunch().is_enabled().unwrap_or(false),
+        enabled: settings.autostart_enabled,
+        minimized: settings.autostart_minimized,
+        delay_secs: settings.autostart_delay_secs,
+    }
+}
@@ -0,0 +1,397 @@
+//! Latency benchmark: a sample goes through every configured transcription
+//! and post-processing provider, timing each stage, so users can compare
+//! their setups with numbers instead of impressions. Online requests can't be
+//! taken apart, so their upload is estimated from a round trip to the
+//! provider and the rest of the request counts as inference.
+
+use crate::actions;
+use crate::audio_toolkit::audio::{SampleBuffer, SharedUsage};
+use crate::audio_toolkit::{constants::WHISPER_SAMPLE_RATE, read_wav_file};
+use crate::clipboard;
+use crate::http;
+use crate::llm_types::ChatCompletionRequest;
+use crate::managers::transcription::TranscriptionManager;
+use crate::pipeline;
+use crate::retry::RetryPolicy;
+use crate::settings::{
+    self, AppSettings, PostProcessProvider, APPLE_INTELLIGENCE_PROVIDER_ID, LLAMA_CPP_PROVIDER_ID,
+};
+use log::{debug, info};
+use serde::Serialize;
+use specta::Type;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Online transcription providers, tried when they have a key
+const ONLINE_STT_PROVIDERS: &[&str] = &["openai", "groq", "gemini"];
+
+/// Length of the synthetic sample
+const SYNTHETIC_SECONDS: usize = 5;
+
+/// Frames the sample is buffered in, like the recorder's
+const FRAME_SAMPLES: usize = WHISPER_SAMPLE_RATE as usize * 30 / 1000;
+
+/// Post-processed when no transcription came back with text
+const FALLBACK_TRANSCRIPT: &str =
+    "so um I think we should uh meet on tuesday to go over the the budget numbers";
+
+const CLEANUP_PROMPT: &str = "Clean up this dictated text: fix punctuation and remove filler \
+words. Reply with the cleaned text only.";
+
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct ProviderTiming {
+    pub provider_id: String,
+    pub model: String,
+    /// Estimated from a round trip to the provider; None for local models
+    pub upload_ms: Option<u64>,
+    /// Transcription, or post-processing, without the upload
+    pub inference_ms: Option<u64>,
+    pub total_ms: u64,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct BenchmarkReport {
+    pub audio_ms: u64,
+    /// Whether the sample was generated rather than read from a file
+    pub synthetic_audio: bool,
+    /// Getting the recording out of its buffer once it stops
+    pub capture_flush_ms: u64,
+    pub transcription: Vec<ProviderTiming>,
+    pub post_processing: Vec<ProviderTiming>,
+    /// Putting the text on the clipboard; the keystroke and the target app
+    /// aren't included
+    pub inject_ms: Option<u64>,
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// A voice-like signal: a few harmonics of a wavering pitch, swelling and
+/// fading at about the rate of syllables
+fn synthetic_audio(seconds: usize) -> Vec<f32> {
+    let rate = WHISPER_SAMPLE_RATE as f32;
+    (0..seconds * WHISPER_SAMPLE_RATE as usize)
+        .map(|i| {
+            let t = i as f32 / rate;
+            let pitch = 140.0 + 20.0 * (2.0 * std::f32::consts::PI * 0.7 * t).sin();
+            let phase = 2.0 * std::f32::consts::PI * pitch * t;
+            let voice = (1..=4)
+                .map(|harmonic| (phase * harmonic as f32).sin() / harmonic as f32)
+                .sum::<f32>();
+            let envelope = (std::f32::consts::PI * 4.0 * t).sin().abs();
+            0.2 * envelope * voice
+        })
+        .collect()
+}
+
+/// Buffer `samples` as a recording would be, then time getting them out
+fn time_capture_flush(samples: &[f32], settings: &AppSettings) -> Duration {
+    let mut buffer = SampleBuffer::new(Arc::new(SharedUsage::default()));
+    buffer.reset(settings.recording_memory_limit_mb as usize * 1024 * 1024);
+    for frame in samples.chunks(FRAME_SAMPLES) {
+        buffer.extend_from_slice(frame);
+    }
+    let started = Instant::now();
+    let _ = buffer.take();
+    started.elapsed()
+}
+
+/// Split a run into upload and inference, given the round trip of an
+/// online one
+fn timing(
+    provider_id: &str,
+    model: String,
+    round_trip: Option<Duration>,
+    elapsed: Duration,
+    result: Result<String, String>,
+) -> ProviderTiming {
+    let upload = round_trip.unwrap_or_default().min(elapsed);
+    let (output, error) = match result {
+        Ok(output) => (Some(output), None),
+        Err(e) => (None, Some(e)),
+    };
+    ProviderTiming {
+        provider_id: provider_id.to_string(),
+        model,
+        upload_ms: round_trip.map(|_| millis(upload)),
+        inference_ms: output.as_ref().map(|_| millis(elapsed - upload)),
+        total_ms: millis(elapsed),
+        output,
+        error,
+    }
+}
+
+/// Round trip to `url` over a warm connection, like a dictation's
+async fn measure_round_trip(settings: &AppSettings, url: &str) -> Option<Duration> {
+    let client = http::shared_client(settings).ok()?;
+    http::preconnect(&client, url).await;
+    http::round_trip(&client, url).await.ok()
+}
+
+async fn time_local_transcription(
+    app: &AppHandle,
+    settings: &AppSettings,
+    samples: &[f32],
+) -> ProviderTiming {
+    let tm = app.state::<Arc<TranscriptionManager>>().inner().clone();
+    let model = settings.selected_model.clone();
+    let language = settings.selected_language.clone();
+    let samples = samples.to_vec();
+    let model_id = model.clone();
+    // Loading the model isn't part of a dictation once it's warm
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        if tm.get_current_model().as_deref() != Some(model_id.as_str()) || !tm.is_model_loaded() {
+            tm.load_model(&model_id).map_err(|e| e.to_string())?;
+        }
+        let started = Instant::now();
+        let text = tm
+            .transcribe(samples, &language)
+            .map_err(|e| e.to_string())?;
+        Ok::<_, String>((text, started.elapsed()))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+
+    match result {
+        Ok((text, elapsed)) => timing("local", model, None, elapsed, Ok(text)),
+        Err(e) => timing("local", model, None, Duration::ZERO, Err(e)),
+    }
+}
+
+async fn time_online_transcription(
+    app: &AppHandle,
+    settings: &AppSettings,
+    provider_id: &str,
+    samples: &[f32],
+) -> ProviderTiming {
+    let mut settings = settings.clone();
+    settings.use_online_provider = true;
+    settings.online_provider_id = provider_id.to_string();
+    settings.network_max_attempts = 1;
+    let model = settings
+        .online_provider_models
+        .get(provider_id)
+        .cloned()
+        .unwrap_or_default();
+    let round_trip = match actions::online_provider_base_url(provider_id) {
+        Some(url) => measure_round_trip(&settings, url).await,
+        None => None,
+    };
+
+    let tm = app.state::<Arc<TranscriptionManager>>();
+    let started = Instant::now();
+    let result = actions::transcribe_audio(&settings, &tm, samples.to_vec())
+        .await
+        .map_err(|e| e.to_string());
+    timing(provider_id, model, round_trip, started.elapsed(), result)
+}
+
+#[cfg(feature = "llama-cpp")]
+async fn time_llama_cpp(settings: &AppSettings, model_path: String, text: &str) -> ProviderTiming {
+    let gpu_layers = settings.llama_cpp_gpu_layers;
+    let prompt = text.to_string();
+    let model = model_path.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        crate::llama_cpp::preload(&model_path, gpu_layers)?;
+        let params = crate::llama_cpp::GenerationParams {
+            max_tokens: crate::llama_cpp::DEFAULT_MAX_TOKENS,
+            temperature: None,
+            top_p: None,
+            stop: Vec::new(),
+        };
+        let started = Instant::now();
+        let text = crate::llama_cpp::process_text(
+            &model_path,
+            gpu_layers,
+            Some(CLEANUP_PROMPT),
+            &prompt,
+            &params,
+        )?;
+        Ok::<_, String>((text, started.elapsed()))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+
+    match result {
+        Ok((text, elapsed)) => timing(LLAMA_CPP_PROVIDER_ID, model, None, elapsed, Ok(text)),
+        Err(e) => timing(LLAMA_CPP_PROVIDER_ID, model, None, Duration::ZERO, Err(e)),
+    }
+}
+
+async fn time_online_llm(
+    settings: &AppSettings,
+    provider: &PostProcessProvider,
+    model: String,
+    text: &str,
+) -> ProviderTiming {
+    let api_key = settings
+        .post_process_api_keys
+        .get(&provider.id)
+        .cloned()
+        .unwrap_or_default();
+    let (service_tier, extra_body) = settings.provider_request_fields(&provider.id);
+    let client = match http::shared_client(settings) {
+        Ok(http_client) => crate::llm_client::create_client(provider, api_key, http_client)
+            .with_gemini_safety_threshold(settings.gemini_safety_threshold)
+            .with_retry_policy(RetryPolicy::with_max_attempts(1))
+            .with_provider_fields(service_tier, extra_body),
+        Err(e) => return timing(&provider.id, model, None, Duration::ZERO, Err(e)),
+    };
+    let request = match ChatCompletionRequest::builder(model.clone())
+        .system(CLEANUP_PROMPT)
+        .user(text)
+        .build()
+    {
+        Ok(request) => request,
+        Err(e) => return timing(&provider.id, model, None, Duration::ZERO, Err(e)),
+    };
+
+    let round_trip = measure_round_trip(settings, &provider.base_url).await;
+    let started = Instant::now();
+    let result = client
+        .send_chat_request(&request)
+        .await
+        .map(|output| output.content)
+        .map_err(|e| e.to_string());
+    timing(&provider.id, model, round_trip, started.elapsed(), result)
+}
+
+/// Post-processing providers with a model and, online, a key
+fn configured_llm_providers(settings: &AppSettings) -> Vec<(PostProcessProvider, String)> {
+    settings
+        .post_process_providers
+        .iter()
+        .filter(|provider| provider.id != APPLE_INTELLIGENCE_PROVIDER_ID)
+        .filter_map(|provider| {
+            if provider.id == LLAMA_CPP_PROVIDER_ID {
+                let model_path = settings.llama_cpp_model_path.clone()?;
+                return Some((provider.clone(), model_path));
+            }
+            let model = settings
+                .post_process_models
+                .get(&provider.id)
+                .filter(|model| !model.trim().is_empty())?;
+            let has_key = settings
+                .post_process_api_keys
+                .get(&provider.id)
+                .is_some_and(|key| !key.trim().is_empty());
+            let keyless = matches!(provider.id.as_str(), "custom" | "ollama");
+            (has_key || keyless).then(|| (provider.clone(), model.clone()))
+        })
+        .collect()
+}
+
+/// Run the benchmark with the WAV file at `sample_path`, or synthetic audio
+pub async fn run(app: &AppHandle, sample_path: Option<String>) -> Result<BenchmarkReport, String> {
+    if pipeline::is_busy() {
+        return Err("Finish the current dictation before running the benchmark".to_string());
+    }
+    let settings = settings::get_settings(app);
+    let (samples, synthetic_audio) = match &sample_path {
+        Some(path) => (
+            read_wav_file(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?,
+            false,
+        ),
+        None => (synthetic_audio(SYNTHETIC_SECONDS), true),
+    };
+    info!(
+        "Running the benchmark on {} samples{}",
+        samples.len(),
+        if synthetic_audio {
+            " of synthetic audio"
+        } else {
+            ""
+        }
+    );
+
+    let capture_flush = time_capture_flush(&samples, &settings);
+
+    let mut transcription = Vec::new();
+    if !settings.selected_model.is_empty() {
+        transcription.push(time_local_transcription(app, &settings, &samples).await);
+    }
+    for provider_id in ONLINE_STT_PROVIDERS {
+        if !settings.online_provider_keys(provider_id).is_empty() {
+            transcription
+                .push(time_online_transcription(app, &settings, provider_id, &samples).await);
+        }
+    }
+
+    let text = transcription
+        .iter()
+        .filter_map(|timing| timing.output.clone())
+        .find(|output| !output.trim().is_empty())
+        .unwrap_or_else(|| FALLBACK_TRANSCRIPT.to_string());
+
+    let mut post_processing = Vec::new();
+    for (provider, model) in configured_llm_providers(&settings) {
+        debug!("Benchmarking post-processing with '{}'", provider.id);
+        if provider.id == LLAMA_CPP_PROVIDER_ID {
+            #[cfg(feature = "llama-cpp")]
+            post_processing.push(time_llama_cpp(&settings, model, &text).await);
+            continue;
+        }
+        post_processing.push(time_online_llm(&settings, &provider, model, &text).await);
+    }
+
+    let inject_ms = match clipboard::time_clipboard_write(app, &text) {
+        Ok(elapsed) => Some(millis(elapsed)),
+        Err(e) => {
+            debug!("Couldn't time the clipboard: {}", e);
+            None
+        }
+    };
+
+    Ok(BenchmarkReport {
+        audio_ms: samples.len() as u64 * 1000 / u64::from(WHISPER_SAMPLE_RATE),
+        synthetic_audio,
+        capture_flush_ms: millis(capture_flush),
+        transcription,
+        post_processing,
+        inject_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_audio() {
+        let samples = synthetic_audio(2);
+        assert_eq!(samples.len(), 2 * WHISPER_SAMPLE_RATE as usize);
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+        assert!(samples.iter().any(|sample| sample.abs() > 0.1));
+    }
+
+    #[test]
+    fn test_timing() {
+        let online = timing(
+            "groq",
+            "whisper-large-v3-turbo".to_string(),
+            Some(Duration::from_millis(80)),
+            Duration::from_millis(500),
+            Ok("Hello".to_string()),
+        );
+        assert_eq!(online.upload_ms, Some(80));
+        assert_eq!(online.inference_ms, Some(420));
+        assert_eq!(online.total_ms, 500);
+
+        let failed = timing(
+            "groq",
+            String::new(),
+            None,
+            Duration::from_millis(30),
+            Err("401".to_string()),
+        );
+        assert_eq!(failed.upload_ms, None);
+        assert_eq!(failed.inference_ms, None);
+        assert_eq!(failed.error.as_deref(), Some("401"));
+    }
+}
@@ -0,0 +1,232 @@
+//! Command line companion that drives a running Babbl through its local
+//! control API. The port and token are read from Babbl's settings unless
+//! given with `--port`/`--token` or `BABBL_API_PORT`/`BABBL_API_TOKEN`.
+
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const APP_IDENTIFIER: &str = "com.babbl.app";
+const SETTINGS_FILE: &str = "settings_store.json";
+const DEFAULT_PORT: u16 = 47_810;
+
+const USAGE: &str =
+    "Usage: babbl-cli [--port <port>] [--token <token>] [--data-dir <dir>] <command>
+
+Commands:
+  status                 Show what Babbl is doing
+  start [action]         Start dictating, with the transcribe action by default
+  stop [action]          Stop dictating and process the recording
+  cancel                 Cancel the current dictation
+  transcribe <file.wav>  Print the transcription of a WAV file
+  last [--copy]          Print the last transcript, or copy it to the clipboard";
+
+struct Connection {
+    port: u16,
+    token: String,
+}
+
+/// Babbl's data directory, as Tauri picks it per platform
+fn default_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Application Support")
+    } else {
+        match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+        }
+    };
+    Some(base.join(APP_IDENTIFIER))
+}
+
+/// Control API settings from the settings store in `data_dir`
+fn stored_settings(data_dir: Option<PathBuf>) -> Value {
+    data_dir
+        .or_else(default_data_dir)
+        .and_then(|dir| std::fs::read_to_string(dir.join(SETTINGS_FILE)).ok())
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+        .map(|store| store["settings"].clone())
+        .unwrap_or_default()
+}
+
+/// Status code and JSON body of a raw HTTP response
+fn parse_response(response: &str) -> Result<(u16, Value), String> {
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "Malformed response from Babbl".to_string())?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| "Malformed response from Babbl".to_string())?;
+    Ok((status, serde_json::from_str(body).unwrap_or_default()))
+}
+
+fn request(
+    connection: &Connection,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+) -> Result<Value, String> {
+    let mut stream = TcpStream::connect(("127.0.0.1", connection.port)).map_err(|e| {
+        format!(
+            "Couldn't reach Babbl on port {} ({}). Is it running with the control API enabled?",
+            connection.port, e
+        )
+    })?;
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nAuthorization: Bearer {}\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        connection.port,
+        connection.token,
+        body.len(),
+        body
+    )
+    .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    let (status, value) = parse_response(&response)?;
+    if status != 200 {
+        let error = value["error"].as_str().unwrap_or("Request failed");
+        return Err(format!("{} (HTTP {})", error, status));
+    }
+    Ok(value)
+}
+
+fn run(connection: &Connection, command: &str, args: &[String]) -> Result<(), String> {
+    let action = |args: &[String]| json!({ "binding_id": args.first() });
+    match command {
+        "status" => {
+            let status = request(connection, "GET", "/v1/status", None)?;
+            println!("{}", status["state"].as_str().unwrap_or("unknown"));
+        }
+        "start" | "stop" => {
+            let path = format!("/v1/{}", command);
+            let result = request(connection, "POST", &path, Some(action(args)))?;
+            if result["changed"] == json!(false) {
+                let state = if command == "start" {
+                    "dictating"
+                } else {
+                    "stopped"
+                };
+                eprintln!("Already {}", state);
+            }
+        }
+        "cancel" => {
+            request(connection, "POST", "/v1/cancel", None)?;
+        }
+        "transcribe" => {
+            let file = args.first().ok_or_else(|| USAGE.to_string())?;
+            // The app resolves the path, which may have another working directory
+            let path =
+                std::fs::canonicalize(file).map_err(|e| format!("Can't open '{}': {}", file, e))?;
+            let body = json!({ "path": path });
+            let result = request(connection, "POST", "/v1/transcribe", Some(body))?;
+            println!("{}", result["text"].as_str().unwrap_or_default());
+        }
+        "last" if args.iter().any(|arg| arg == "--copy") => {
+            request(connection, "POST", "/v1/transcripts/last/copy", None)?;
+        }
+        "last" => {
+            let result = request(connection, "GET", "/v1/transcripts/last", None)?;
+            match result["text"].as_str() {
+                Some(text) => println!("{}", text),
+                None => return Err("There is no transcript yet".to_string()),
+            }
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut port = std::env::var("BABBL_API_PORT").ok();
+    let mut token = std::env::var("BABBL_API_TOKEN").ok();
+    let mut data_dir = None;
+    let mut positional = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => port = args.next(),
+            "--token" => token = args.next(),
+            "--data-dir" => data_dir = args.next().map(PathBuf::from),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let (command, args) = match positional.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let settings = stored_settings(data_dir);
+    let port = match port {
+        Some(port) => match port.parse() {
+            Ok(port) => port,
+            Err(_) => {
+                eprintln!("Invalid port '{}'", port);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => settings["control_api_port"]
+            .as_u64()
+            .and_then(|port| u16::try_from(port).ok())
+            .unwrap_or(DEFAULT_PORT),
+    };
+    let token = token.or_else(|| settings["control_api_token"].as_str().map(str::to_string));
+    let token = match token {
+        Some(token) => token,
+        None => {
+            eprintln!(
+                "No API token found; enable the control API in Babbl's settings or pass --token"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&Connection { port, token }, command, args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let (status, body) =
+            parse_response("HTTP/1.1 200 OK\r\nContent-Length: 13\r\n\r\n{\"text\":\"hi\"}")
+                .unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(body["text"], "hi");
+
+        let (status, body) = parse_response("HTTP/1.1 401 Unauthorized\r\n\r\n").unwrap();
+        assert_eq!(status, 401);
+        assert!(body.is_null());
+
+        assert!(parse_response("garbage").is_err());
+    }
+}
@@ -0,0 +1,407 @@
+//! Muting calls while dictating, so the dictation isn't heard in the
+//! meeting. When a recording starts Babbl mutes the calls that are unmuted
+//! and unmutes exactly those again when it ends.
+//!
+//! - Teams through its local third-party app API, which reports the mute
+//!   state. It has to be turned on in Teams (Privacy > Manage API); the
+//!   first time, Teams asks during a meeting whether to allow Babbl and
+//!   hands out a token that's kept in the settings.
+//! - Zoom on macOS through its Meeting menu, whose item says whether the
+//!   microphone is on. This uses the accessibility access pasting needs.
+//!
+//! Browser calls like Meet have no interface outside the tab and aren't
+//! covered.
+
+use crate::pipeline::PipelineState;
+use crate::settings::{self, AppSettings};
+use crate::settings_validation::SettingsError;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener};
+use tungstenite::{Error, Message, WebSocket};
+
+/// Where Teams serves its third-party app API
+const TEAMS_PORT: u16 = 8124;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the thread waits before looking for Teams messages again
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wait between attempts while Teams isn't running
+const RECONNECT_DELAY: Duration = Duration::from_secs(15);
+
+/// Bumped whenever muting is turned off, which ends its thread
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+static REQUESTS: Lazy<Mutex<Option<Sender<Request>>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallApp {
+    Teams,
+    Zoom,
+}
+
+#[derive(Debug)]
+enum Request {
+    /// A recording started
+    Mute,
+    /// The recording ended
+    Restore,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct MeetingState {
+    #[serde(default)]
+    is_in_meeting: bool,
+    #[serde(default)]
+    is_muted: bool,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct MeetingPermissions {
+    #[serde(default)]
+    can_toggle_mute: bool,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct MeetingUpdate {
+    #[serde(default)]
+    meeting_state: MeetingState,
+    #[serde(default)]
+    meeting_permissions: MeetingPermissions,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct TeamsMessage {
+    #[serde(default)]
+    meeting_update: Option<MeetingUpdate>,
+    /// Sent when Babbl is allowed, and later to replace the token
+    #[serde(default)]
+    token_refresh: Option<String>,
+}
+
+/// Address of the Teams API, identifying Babbl with the saved token
+fn teams_url(token: Option<&str>, version: &str) -> String {
+    format!(
+        "ws://127.0.0.1:{}?token={}&protocol-version=2.0.0&manufacturer=Babbl&device=Babbl\
+         &app=Babbl&app-version={}",
+        TEAMS_PORT,
+        token.unwrap_or_default(),
+        version
+    )
+}
+
+struct Teams {
+    socket: WebSocket<TcpStream>,
+    meeting: MeetingUpdate,
+    paired: bool,
+    next_request_id: u64,
+}
+
+impl Teams {
+    fn connect(app: &AppHandle) -> Result<Self, String> {
+        let token = settings::get_settings(app).call_mute_teams_token;
+        let address = SocketAddr::from(([127, 0, 0, 1], TEAMS_PORT));
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+            .map_err(|e| format!("Teams isn't reachable: {}", e))?;
+        stream
+            .set_read_timeout(Some(CONNECT_TIMEOUT))
+            .map_err(|e| format!("Failed to configure the Teams connection: {}", e))?;
+        let url = teams_url(token.as_deref(), &app.package_info().version.to_string());
+        let (socket, _) = tungstenite::client(url, stream)
+            .map_err(|e| format!("Failed to connect to Teams: {}", e))?;
+        // Reads time out so requests aren't held up by a quiet Teams
+        socket
+            .get_ref()
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .map_err(|e| format!("Failed to configure the Teams connection: {}", e))?;
+        info!("Connected to Teams");
+        Ok(Self {
+            socket,
+            meeting: MeetingUpdate::default(),
+            paired: token.is_some(),
+            next_request_id: 0,
+        })
+    }
+
+    /// Take in what Teams sent since the last call
+    fn poll(&mut self, app: &AppHandle) -> Result<(), String> {
+        loop {
+            let text = match self.socket.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => return Err("Teams closed the connection".to_string()),
+                Ok(_) => continue,
+                Err(Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    return Ok(())
+                }
+                Err(e) => return Err(format!("Lost the connection to Teams: {}", e)),
+            };
+            let message = match serde_json::from_str::<TeamsMessage>(text.as_str()) {
+                Ok(message) => message,
+                Err(e) => {
+                    debug!("Ignoring Teams message: {}", e);
+                    continue;
+                }
+            };
+            if let Some(meeting) = message.meeting_update {
+                self.meeting = meeting;
+            }
+            if let Some(token) = message.token_refresh {
+                info!("Teams allowed Babbl to mute calls");
+                self.paired = true;
+                let mut settings = settings::get_settings(app);
+                settings.call_mute_teams_token = Some(token);
+                if let Err(e) = settings::write_settings(app, settings) {
+                    warn!("Failed to save the Teams token: {}", e);
+                }
+            }
+        }
+    }
+
+    fn send_action(&mut self, action: &str) -> Result<(), String> {
+        self.next_request_id += 1;
+        let text = json!({
+            "action": action,
+            "parameters": {},
+            "requestId": self.next_request_id,
+        })
+        .to_string();
+        self.socket
+            .send(Message::Text(text.into()))
+            .map_err(|e| format!("Failed to send to Teams: {}", e))
+    }
+
+    /// Mute the meeting if it's unmuted; whether it was
+    fn mute(&mut self) -> Result<bool, String> {
+        if !self.paired {
+            // Shows the prompt in Teams; muting works from the next dictation
+            self.send_action("pair")?;
+            return Ok(false);
+        }
+        let state = self.meeting.meeting_state;
+        if !state.is_in_meeting
+            || state.is_muted
+            || !self.meeting.meeting_permissions.can_toggle_mute
+        {
+            return Ok(false);
+        }
+        self.send_action("toggle-mute")?;
+        self.meeting.meeting_state.is_muted = true;
+        Ok(true)
+    }
+
+    /// Unmute the meeting Babbl muted, unless the user left it or unmuted
+    /// it already
+    fn unmute(&mut self) -> Result<(), String> {
+        let state = self.meeting.meeting_state;
+        if state.is_in_meeting && state.is_muted {
+            self.send_action("toggle-mute")?;
+            self.meeting.meeting_state.is_muted = false;
+        }
+        Ok(())
+    }
+}
+
+/// Click the Meeting menu item `item` of Zoom; whether it was there
+#[cfg(target_os = "macos")]
+fn click_zoom_menu_item(item: &str) -> bool {
+    let script = format!(
+        r#"tell application "System Events"
+    if not (exists process "zoom.us") then return false
+    tell process "zoom.us"
+        set meetingMenu to menu 1 of menu bar item "Meeting" of menu bar 1
+        if not (exists menu item "{item}" of meetingMenu) then return false
+        click menu item "{item}" of meetingMenu
+        return true
+    end tell
+end tell"#
+    );
+    match std::process::Command::new("osascript")
+        .args(["-e", &script])
+        .output()
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).trim() == "true",
+        Err(e) => {
+            warn!("Failed to run osascript: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn click_zoom_menu_item(_item: &str) -> bool {
+    false
+}
+
+fn mute(teams: Option<&mut Teams>) -> Vec<CallApp> {
+    let mut muted = Vec::new();
+    if let Some(teams) = teams {
+        match teams.mute() {
+            Ok(true) => muted.push(CallApp::Teams),
+            Ok(false) => {}
+            Err(e) => warn!("{}", e),
+        }
+    }
+    if click_zoom_menu_item("Mute audio") {
+        muted.push(CallApp::Zoom);
+    }
+    if !muted.is_empty() {
+        info!("Muted {:?} for the dictation", muted);
+    }
+    muted
+}
+
+fn restore(teams: Option<&mut Teams>, muted: Vec<CallApp>) {
+    if muted.is_empty() {
+        return;
+    }
+    info!("Unmuting {:?} after the dictation", muted);
+    if muted.contains(&CallApp::Teams) {
+        match teams {
+            Some(teams) => {
+                if let Err(e) = teams.unmute() {
+                    warn!("{}", e);
+                }
+            }
+            None => warn!("Lost Teams before unmuting it"),
+        }
+    }
+    if muted.contains(&CallApp::Zoom) && !click_zoom_menu_item("Unmute audio") {
+        debug!("Zoom was unmuted already or the meeting ended");
+    }
+}
+
+/// Keep up with Teams and mute or restore the calls on request, until
+/// muting is turned off
+fn run(app: AppHandle, requests: Receiver<Request>, generation: u64) {
+    let mut teams: Option<Teams> = None;
+    let mut next_attempt = Instant::now();
+    let mut muted = Vec::new();
+    while GENERATION.load(Ordering::Relaxed) == generation {
+        if teams.is_none() && Instant::now() >= next_attempt {
+            match Teams::connect(&app) {
+                Ok(connected) => teams = Some(connected),
+                Err(e) => {
+                    debug!("{}", e);
+                    next_attempt = Instant::now() + crate::power::scaled(RECONNECT_DELAY);
+                }
+            }
+        }
+        if let Some(connected) = teams.as_mut() {
+            if let Err(e) = connected.poll(&app) {
+                debug!("{}", e);
+                teams = None;
+                next_attempt = Instant::now() + crate::power::scaled(RECONNECT_DELAY);
+            }
+        }
+
+        match requests.recv_timeout(POLL_INTERVAL) {
+            Ok(Request::Mute) => {
+                let newly_muted = mute(teams.as_mut());
+                muted.extend(newly_muted);
+            }
+            Ok(Request::Restore) => restore(teams.as_mut(), std::mem::take(&mut muted)),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    // Turned off in the middle of a dictation
+    restore(teams.as_mut(), muted);
+    debug!("Call muting stopped");
+}
+
+fn send(request: Request) {
+    if let Some(requests) = REQUESTS.lock().unwrap().as_ref() {
+        let _ = requests.send(request);
+    }
+}
+
+/// Mute calls when a recording starts and restore them when it ends; the
+/// listener stays for the app's lifetime and does nothing while muting is
+/// off
+pub fn init(app: &AppHandle) {
+    app.listen_any("pipeline-state", |event| {
+        let states = serde_json::from_str::<serde_json::Value>(event.payload())
+            .ok()
+            .and_then(|event| {
+                let state = serde_json::from_value(event["state"].clone()).ok()?;
+                let previous = serde_json::from_value(event["previous"].clone()).ok()?;
+                Some((state, previous))
+            });
+        match states {
+            Some((PipelineState::Recording, previous)) if previous != PipelineState::Recording => {
+                send(Request::Mute)
+            }
+            Some((state, PipelineState::Recording)) if state != PipelineState::Recording => {
+                send(Request::Restore)
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Start or stop muting per the settings
+pub fn sync(app: &AppHandle) {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    REQUESTS.lock().unwrap().take();
+    if !settings::get_settings(app).call_mute_enabled {
+        return;
+    }
+    let (sender, receiver) = mpsc::channel();
+    *REQUESTS.lock().unwrap() = Some(sender);
+    let generation = GENERATION.load(Ordering::Relaxed);
+    let app = app.clone();
+    std::thread::spawn(move || run(app, receiver, generation));
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    // A new Teams token is picked up on the next connection
+    if previous.call_mute_enabled != next.call_mute_enabled {
+        sync(app);
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_teams_message() {
+        let message: TeamsMessage = serde_json::from_str(
+            r#"{"meetingUpdate":{"meetingState":{"isMuted":false,"isVideoOn":true,
+                "isInMeeting":true},"meetingPermissions":{"canToggleMute":true}}}"#,
+        )
+        .unwrap();
+        let meeting = message.meeting_update.unwrap();
+        assert!(meeting.meeting_state.is_in_meeting);
+        assert!(!meeting.meeting_state.is_muted);
+        assert!(meeting.meeting_permissions.can_toggle_mute);
+
+        let message: TeamsMessage = serde_json::from_str(r#"{"tokenRefresh":"6f1c0c5e"}"#).unwrap();
+        assert_eq!(message.token_refresh.as_deref(), Some("6f1c0c5e"));
+        assert_eq!(message.meeting_update, None);
+
+        assert!(teams_url(None, "1.2.0").starts_with("ws://127.0.0.1:8124?token=&"));
+        assert!(teams_url(Some("abc"), "1.2.0").ends_with("&app-version=1.2.0"));
+    }
+}
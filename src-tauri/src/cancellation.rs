@@ -0,0 +1,198 @@
+//! Cancellation and time limits for action invocations. Each dictation gets
+//! an [`Invocation`] once its recording stops; cancelling it (the cancel
+//! shortcut, the control API) or running past the action's timeout drops
+//! whatever it's waiting on, like a provider request, before anything is
+//! injected.
+
+use crate::settings::AppSettings;
+use futures_util::future::{self, Either};
+use log::debug;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{watch, OwnedMutexGuard};
+
+#[derive(Debug, PartialEq)]
+pub enum Interrupted {
+    Cancelled,
+    TimedOut(Duration),
+}
+
+/// One run of an action, from the end of its recording to the injection
+#[derive(Clone)]
+pub struct Invocation {
+    id: u64,
+    binding_id: String,
+    cancelled: Arc<watch::Sender<bool>>,
+}
+
+/// Longest time limit an action can have
+pub const MAX_ACTION_TIMEOUT_SECS: u32 = 3600;
+
+static RUNNING: Lazy<Mutex<Vec<Invocation>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Taken by queued runs of an action, in the order they asked
+static TURNS: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl Invocation {
+    fn new(binding_id: &str) -> Self {
+        let (cancelled, _) = watch::channel(false);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            binding_id: binding_id.to_string(),
+            cancelled: Arc::new(cancelled),
+        }
+    }
+
+    pub fn binding_id(&self) -> &str {
+        &self.binding_id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    fn cancel(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    async fn until_cancelled(&self) {
+        let mut cancelled = self.cancelled.subscribe();
+        // The sender lives as long as `self`, so this only ends on a cancel
+        let _ = cancelled.wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// Wait until earlier queued runs of the action are done; the turn lasts
+    /// until the guard is dropped
+    pub async fn turn(&self) -> OwnedMutexGuard<()> {
+        let turn = Arc::clone(
+            TURNS
+                .lock()
+                .unwrap()
+                .entry(self.binding_id.clone())
+                .or_default(),
+        );
+        turn.lock_owned().await
+    }
+
+    /// Run `work` until it's done, the invocation is cancelled or `timeout`
+    /// passes; the work is dropped in the latter two cases
+    pub async fn run<F: Future>(
+        &self,
+        timeout: Option<Duration>,
+        work: F,
+    ) -> Result<F::Output, Interrupted> {
+        // E.g. while it waited for its turn
+        if self.is_cancelled() {
+            return Err(Interrupted::Cancelled);
+        }
+        let limited = async {
+            match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, work)
+                    .await
+                    .map_err(|_| Interrupted::TimedOut(timeout)),
+                None => Ok(work.await),
+            }
+        };
+        match future::select(Box::pin(limited), Box::pin(self.until_cancelled())).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(Interrupted::Cancelled),
+        }
+    }
+}
+
+/// Track a new invocation of `binding_id`
+pub fn begin(binding_id: &str) -> Invocation {
+    let invocation = Invocation::new(binding_id);
+    debug!(
+        "Invocation {} of '{}' started",
+        invocation.id, invocation.binding_id
+    );
+    RUNNING.lock().unwrap().push(invocation.clone());
+    invocation
+}
+
+/// Stop tracking `invocation`; returns whether no other one is running
+pub fn end(invocation: &Invocation) -> bool {
+    let mut running = RUNNING.lock().unwrap();
+    running.retain(|other| other.id != invocation.id);
+    running.is_empty()
+}
+
+/// Whether a run of `binding_id` is still processing
+pub fn is_running(binding_id: &str) -> bool {
+    RUNNING
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|invocation| invocation.binding_id == binding_id)
+}
+
+/// Cancel the running invocations of `binding_id`
+pub fn cancel(binding_id: &str) {
+    RUNNING.lock().unwrap().retain(|invocation| {
+        if invocation.binding_id != binding_id {
+            return true;
+        }
+        debug!(
+            "Cancelling invocation {} of '{}'",
+            invocation.id, binding_id
+        );
+        invocation.cancel();
+        false
+    });
+}
+
+/// Cancel every running invocation
+pub fn cancel_all() {
+    for invocation in RUNNING.lock().unwrap().drain(..) {
+        debug!(
+            "Cancelling invocation {} of '{}'",
+            invocation.id, invocation.binding_id
+        );
+        invocation.cancel();
+    }
+}
+
+/// Time limit of an action: its own, else the global one; zero means none
+pub fn timeout_for(settings: &AppSettings, binding_id: &str) -> Option<Duration> {
+    let secs = settings
+        .action_config(binding_id)
+        .and_then(|config| config.timeout_secs)
+        .unwrap_or(settings.action_timeout_secs);
+    (secs > 0).then(|| Duration::from_secs(secs.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        tauri::async_runtime::block_on(async {
+            let invocation = Invocation::new("transcribe");
+            assert_eq!(invocation.run(None, async { 1 }).await, Ok(1));
+
+            let timeout = Duration::from_millis(10);
+            let hung = tokio::time::sleep(Duration::from_secs(60));
+            assert_eq!(
+                invocation.run(Some(timeout), hung).await,
+                Err(Interrupted::TimedOut(timeout))
+            );
+
+            invocation.cancel();
+            assert!(invocation.is_cancelled());
+            let hung = tokio::time::sleep(Duration::from_secs(60));
+            assert_eq!(
+                invocation.run(None, hung).await,
+                Err(Interrupted::Cancelled)
+            );
+        });
+    }
+}
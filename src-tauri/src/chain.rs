@@ -0,0 +1,99 @@
+//! Runs an action's chain: the dictated text passes through each step in
+//! turn, e.g. summarize → copy to clipboard → notify.
+
+use crate::notifications::{self, NotificationKind};
+use crate::output::{self, ActionOutput};
+use crate::settings::{AppSettings, ChainStep, ChainStepKind, OutputTarget, StepErrorPolicy};
+use crate::shell_command;
+use log::{debug, warn};
+use tauri::AppHandle;
+
+/// Text that goes through a chain, with where it came from
+pub struct ChainInput<'a> {
+    pub binding_id: &'a str,
+    pub run_id: Option<&'a str>,
+    pub transcription: &'a str,
+    pub text: String,
+}
+
+/// Run one step on `text`; Ok(None) leaves the text as it was
+async fn run_step(
+    app: &AppHandle,
+    settings: &AppSettings,
+    input: &ChainInput<'_>,
+    step: &ChainStepKind,
+) -> Result<Option<String>, String> {
+    match step {
+        ChainStepKind::Action { action_id } => {
+            let (text, _prompt) =
+                crate::actions::reprocess_transcription(app, action_id, None, &input.text).await?;
+            Ok(Some(text))
+        }
+        ChainStepKind::Shell { command } => {
+            let output =
+                shell_command::run(command, input.binding_id, input.transcription, &input.text)
+                    .await?;
+            Ok(command.capture_output.then_some(output))
+        }
+        ChainStepKind::Output { targets } => {
+            let targets: Vec<OutputTarget> = targets
+                .iter()
+                .copied()
+                .filter(|target| *target != OutputTarget::Inject)
+                .collect();
+            let action_output = ActionOutput {
+                binding_id: input.binding_id.to_string(),
+                text: input.text.clone(),
+                transcription: input.transcription.to_string(),
+                timestamp: chrono::Utc::now().timestamp(),
+                run_id: input.run_id.map(str::to_string),
+            };
+            output::deliver(app, settings, &action_output, &targets);
+            Ok(None)
+        }
+        ChainStepKind::Notify { title } => {
+            notifications::notify(app, NotificationKind::ActionResult, title, &input.text);
+            Ok(None)
+        }
+    }
+}
+
+/// Pass `input.text` through `steps` and return what comes out. A failing
+/// step ends the chain with its error, unless it may be skipped.
+pub async fn run(
+    app: &AppHandle,
+    settings: &AppSettings,
+    mut input: ChainInput<'_>,
+    steps: &[ChainStep],
+) -> Result<String, String> {
+    for (index, step) in steps.iter().enumerate() {
+        match run_step(app, settings, &input, &step.step).await {
+            Ok(Some(text)) => input.text = text,
+            Ok(None) => {}
+            Err(e) => match step.on_error {
+                StepErrorPolicy::Stop => {
+                    return Err(format!(
+                        "Step {} of '{}': {}",
+                        index + 1,
+                        input.binding_id,
+                        e
+                    ))
+                }
+                StepErrorPolicy::Continue => {
+                    warn!(
+                        "Skipping failed step {} of '{}': {}",
+                        index + 1,
+                        input.binding_id,
+                        e
+                    );
+                }
+            },
+        }
+        debug!(
+            "Chain of '{}' finished step {}",
+            input.binding_id,
+            index + 1
+        );
+    }
+    Ok(input.text)
+}
@@ -1,140 +1,263 @@
 use crate::input::{self, EnigoState};
-use crate::settings::{get_settings, ClipboardHandling, PasteMethod};
+use crate::rich_text;
+use crate::selection;
+use crate::settings::{get_settings, ClipboardHandling, PasteMethod, UndoMethod};
+use crate::spacing;
 use enigo::Enigo;
-use log::info;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::image::Image;
 use tauri::{AppHandle, Manager};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 
 #[cfg(target_os = "linux")]
 use crate::utils::is_wayland;
 #[cfg(target_os = "linux")]
-use std::process::Command;
+use crate::wayland;
 
-/// Pastes text using the clipboard: saves current content, writes text, sends paste keystroke, restores clipboard.
-fn paste_via_clipboard(
-    enigo: &mut Enigo,
-    text: &str,
-    app_handle: &AppHandle,
-    paste_method: &PasteMethod,
-) -> Result<(), String> {
-    // Check for Wayland first
-    #[cfg(target_os = "linux")]
-    if try_wayland_send_paste(paste_method)? {
-        return Ok(());
-    }
+/// Binding id of the action that removes the last injected text
+pub const UNDO_INJECTION_ACTION_ID: &str = "undo_last_injection";
 
-    let clipboard = app_handle.clipboard();
-    let clipboard_content = clipboard.read_text().unwrap_or_default();
+/// Injected text older than this is no longer undone; the cursor has likely moved on
+const UNDO_WINDOW: Duration = Duration::from_secs(120);
 
-    clipboard
-        .write_text(text)
-        .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+/// Text most recently pasted or typed into another app
+struct Injection {
+    text: String,
+    at: Instant,
+}
 
-    std::thread::sleep(std::time::Duration::from_millis(50));
+static LAST_INJECTION: Lazy<Mutex<Option<Injection>>> = Lazy::new(|| Mutex::new(None));
 
-    match paste_method {
-        PasteMethod::CtrlV => input::send_paste_ctrl_v(enigo)?,
-        PasteMethod::CtrlShiftV => input::send_paste_ctrl_shift_v(enigo)?,
-        PasteMethod::ShiftInsert => input::send_paste_shift_insert(enigo)?,
-        _ => return Err("Invalid paste method for clipboard paste".into()),
-    }
-
-    std::thread::sleep(std::time::Duration::from_millis(50));
+/// Characters read before the cursor for smart spacing; two tell a sentence
+/// end followed by a space apart from a word
+const CONTEXT_CHARS: usize = 2;
 
-    clipboard
-        .write_text(&clipboard_content)
-        .map_err(|e| format!("Failed to restore clipboard: {}", e))?;
+/// What was on the clipboard before a paste, so it can be put back afterwards
+enum ClipboardSnapshot {
+    Text(String),
+    Image(Image<'static>),
+    Empty,
+}
 
-    Ok(())
+/// A restore waiting for its delay to pass
+struct PendingRestore {
+    id: u64,
+    snapshot: ClipboardSnapshot,
+    pasted: String,
 }
 
-/// Attempts to paste using Wayland-specific tools (`wtype` or `dotool`).
-/// Returns `Ok(true)` if a Wayland tool handled the paste, `Ok(false)` if not applicable,
-/// or `Err` on failure from the underlying tool.
-#[cfg(target_os = "linux")]
-fn try_wayland_send_paste(paste_method: &PasteMethod) -> Result<bool, String> {
-    if is_wayland() {
-        if is_wtype_available() {
-            send_paste_via_wtype(paste_method)?;
-            return Ok(true);
-        } else if is_dotool_available() {
-            send_paste_via_dotool(paste_method)?;
-            return Ok(true);
-        }
+static PENDING_RESTORES: Lazy<Mutex<Vec<PendingRestore>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+static NEXT_RESTORE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What's on the clipboard, `None` when it can't be read. Formats other than
+/// text and images (files, rich text) can't, and putting anything back
+/// would replace them.
+fn snapshot_clipboard(app_handle: &AppHandle) -> Option<ClipboardSnapshot> {
+    let clipboard = app_handle.clipboard();
+    match clipboard.read_text() {
+        Ok(text) if !text.is_empty() => Some(ClipboardSnapshot::Text(text)),
+        text => match clipboard.read_image() {
+            Ok(image) => Some(ClipboardSnapshot::Image(image.to_owned())),
+            Err(_) if text.is_ok() => Some(ClipboardSnapshot::Empty),
+            Err(_) => None,
+        },
     }
+}
+
+/// Puts the snapshot back once `delay` has passed, leaving the clipboard alone
+/// if something other than `pasted` was copied in the meantime.
+/// The delay gives slow apps time to read the pasted text first.
+fn restore_clipboard_later(
+    app_handle: AppHandle,
+    snapshot: ClipboardSnapshot,
+    pasted: String,
+    delay: Duration,
+) {
+    let id = NEXT_RESTORE_ID.fetch_add(1, Ordering::Relaxed);
+    PENDING_RESTORES.lock().unwrap().push(PendingRestore {
+        id,
+        snapshot,
+        pasted,
+    });
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
 
-    Ok(false)
+        let pending = {
+            let mut restores = PENDING_RESTORES.lock().unwrap();
+            let index = restores.iter().position(|restore| restore.id == id);
+            index.map(|index| restores.remove(index))
+        };
+        // Restored early by `restore_pending`
+        if let Some(pending) = pending {
+            restore_unless_changed(&app_handle, pending);
+        }
+    });
 }
 
-/// Check if wtype is available (Wayland text input tool)
-#[cfg(target_os = "linux")]
-fn is_wtype_available() -> bool {
-    Command::new("which")
-        .arg("wtype")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+fn restore_unless_changed(app_handle: &AppHandle, pending: PendingRestore) {
+    match app_handle.clipboard().read_text() {
+        Ok(current) if current == pending.pasted => restore_snapshot(app_handle, pending.snapshot),
+        Ok(_) => debug!("Clipboard changed since the paste, not restoring it"),
+        // Something other than text was copied since
+        Err(e) => debug!("Can't read the clipboard ({}), not restoring it", e),
+    }
 }
 
-/// Check if dotool is available (another Wayland text input tool)
-#[cfg(target_os = "linux")]
-fn is_dotool_available() -> bool {
-    Command::new("which")
-        .arg("dotool")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+/// Put the clipboard back now for the pastes still waiting to, so quitting
+/// doesn't leave a transcript on it
+pub fn restore_pending(app_handle: &AppHandle) {
+    let pending = std::mem::take(&mut *PENDING_RESTORES.lock().unwrap());
+    for restore in pending {
+        restore_unless_changed(app_handle, restore);
+    }
 }
 
-/// Paste using wtype and return a friendly error on failure.
-#[cfg(target_os = "linux")]
-fn send_paste_via_wtype(paste_method: &PasteMethod) -> Result<(), String> {
-    let args: Vec<&str> = match paste_method {
-        PasteMethod::CtrlV => vec!["-M", "ctrl", "-k", "v"],
-        PasteMethod::ShiftInsert => vec!["-M", "shift", "-k", "Insert"],
-        PasteMethod::CtrlShiftV => vec!["-M", "ctrl", "-M", "shift", "-k", "v"],
-        _ => return Err("Unsupported paste method".into()),
+fn restore_snapshot(app_handle: &AppHandle, snapshot: ClipboardSnapshot) {
+    let clipboard = app_handle.clipboard();
+    let result = match snapshot {
+        ClipboardSnapshot::Text(text) => clipboard.write_text(text),
+        ClipboardSnapshot::Image(image) => clipboard.write_image(&image),
+        ClipboardSnapshot::Empty => clipboard.clear(),
     };
+    if let Err(e) = result {
+        warn!("Failed to restore clipboard: {}", e);
+    }
+}
 
-    let output = Command::new("wtype")
-        .args(&args)
-        .output()
-        .map_err(|e| format!("Failed to execute wtype: {}", e))?;
+fn write_clipboard(app_handle: &AppHandle, text: &str, html: Option<&str>) -> Result<(), String> {
+    let clipboard = app_handle.clipboard();
+    let result = match html {
+        Some(html) => clipboard.write_html(html, Some(text)),
+        None => clipboard.write_text(text),
+    };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("wtype failed: {}", stderr));
+    // Compositors without the data-control protocol (GNOME) may refuse the
+    // plugin; wl-copy still reaches them
+    #[cfg(target_os = "linux")]
+    if let Err(e) = &result {
+        if is_wayland() {
+            warn!("Clipboard write failed ({}), retrying with wl-copy", e);
+            if wayland::copy_text(text)? {
+                return Ok(());
+            }
+        }
     }
 
-    Ok(())
+    result.map_err(|e| e.to_string())
 }
 
-/// Paste using dotool and return a friendly error on failure.
-#[cfg(target_os = "linux")]
-fn send_paste_via_dotool(paste_method: &PasteMethod) -> Result<(), String> {
-    let command;
-    match paste_method {
-        PasteMethod::CtrlV => command = "echo key ctrl+v | dotool",
-        PasteMethod::ShiftInsert => command = "echo key shift+insert | dotool",
-        PasteMethod::CtrlShiftV => command = "echo key ctrl+shift+v | dotool",
-        _ => return Err("Unsupported paste method".into()),
+/// Put `text` on the clipboard without pasting it
+pub fn copy_text(app_handle: &AppHandle, text: &str) -> Result<(), String> {
+    write_clipboard(app_handle, text, None)
+}
+
+/// Time putting `text` on the clipboard, the part of a paste that doesn't
+/// depend on the app pasted into, then put the clipboard back
+pub fn time_clipboard_write(app_handle: &AppHandle, text: &str) -> Result<Duration, String> {
+    let snapshot =
+        snapshot_clipboard(app_handle).ok_or("The clipboard can't be read to put it back")?;
+    let started = Instant::now();
+    let result = write_clipboard(app_handle, text, None);
+    let elapsed = started.elapsed();
+    restore_snapshot(app_handle, snapshot);
+    result.map(|_| elapsed)
+}
+
+/// Pastes text using the clipboard: saves current content, writes text, sends paste keystroke,
+/// and restores the saved content after `restore_delay` unless it is `None`.
+/// With `html`, the clipboard holds it as rich text and `text` as the plain alternative.
+fn paste_via_clipboard(
+    enigo: &mut Enigo,
+    text: &str,
+    html: Option<&str>,
+    app_handle: &AppHandle,
+    paste_method: &PasteMethod,
+    restore_delay: Option<Duration>,
+) -> Result<(), String> {
+    let snapshot = restore_delay.and_then(|_| snapshot_clipboard(app_handle));
+    if restore_delay.is_some() && snapshot.is_none() {
+        debug!("Can't read the clipboard, it won't be restored after the paste");
     }
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .output()
-        .map_err(|e| format!("Failed to execute dotool: {}", e))?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("dotool failed: {}", stderr));
+
+    write_clipboard(app_handle, text, html)
+        .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+
+    // Wayland tools only send the keystroke, the text is already on the clipboard
+    #[cfg(target_os = "linux")]
+    let sent = is_wayland() && wayland::send_paste_keys(paste_method)?;
+    #[cfg(not(target_os = "linux"))]
+    let sent = false;
+
+    #[cfg(target_os = "linux")]
+    if !sent && is_wayland() {
+        warn_missing_input_tool();
+    }
+
+    if !sent {
+        match paste_method {
+            PasteMethod::CtrlV => input::send_paste_ctrl_v(enigo)?,
+            PasteMethod::CtrlShiftV => input::send_paste_ctrl_shift_v(enigo)?,
+            PasteMethod::ShiftInsert => input::send_paste_shift_insert(enigo)?,
+            _ => return Err("Invalid paste method for clipboard paste".into()),
+        }
+    }
+
+    if let (Some(snapshot), Some(delay)) = (snapshot, restore_delay) {
+        restore_clipboard_later(app_handle.clone(), snapshot, text.to_string(), delay);
     }
 
     Ok(())
 }
 
+/// Without wtype, dotool or ydotool, key events on Wayland only reach
+/// XWayland windows and native apps receive nothing
+#[cfg(target_os = "linux")]
+fn warn_missing_input_tool() {
+    warn!(
+        "No Wayland input tool found, falling back to X11 key events that only reach \
+         XWayland apps. Install wtype (wlroots compositors), dotool or ydotool (any compositor)."
+    );
+}
+
 pub fn paste(text: String, app_handle: AppHandle) -> Result<(), String> {
+    let paste_method = get_settings(&app_handle).paste_method;
+    paste_text(text, app_handle, false, paste_method)
+}
+
+/// Like [`paste`] with `paste_method` instead of the configured one, e.g. from
+/// an app override. With `keep_on_clipboard` the text stays on the clipboard
+/// afterwards whatever `clipboard_handling` says.
+pub fn paste_text(
+    text: String,
+    app_handle: AppHandle,
+    keep_on_clipboard: bool,
+    paste_method: PasteMethod,
+) -> Result<(), String> {
     let settings = get_settings(&app_handle);
-    let paste_method = settings.paste_method;
+    let copy_to_clipboard =
+        keep_on_clipboard || settings.clipboard_handling == ClipboardHandling::CopyToClipboard;
+
+    // Rich text can only travel through the clipboard. Keystroke methods and
+    // apps that only read plain text get the text without Markdown syntax.
+    let is_markdown = settings.markdown_rich_text && rich_text::looks_like_markdown(&text);
+    let html = (is_markdown
+        && matches!(
+            paste_method,
+            PasteMethod::CtrlV | PasteMethod::CtrlShiftV | PasteMethod::ShiftInsert
+        ))
+    .then(|| rich_text::markdown_to_html(&text));
+    let text = if is_markdown {
+        rich_text::markdown_to_plain_text(&text)
+    } else {
+        text
+    };
 
     // Append trailing space if setting is enabled
     let text = if settings.append_trailing_space {
@@ -154,24 +277,110 @@ pub fn paste(text: String, app_handle: AppHandle) -> Result<(), String> {
         .lock()
         .map_err(|e| format!("Failed to lock Enigo: {}", e))?;
 
+    // Rich text keeps the formatting of the original Markdown
+    let text = if settings.smart_spacing && html.is_none() && paste_method != PasteMethod::None {
+        match selection::text_before_cursor(CONTEXT_CHARS) {
+            Some(before) => spacing::fit_to_context(&text, &before),
+            None => {
+                debug!("The text before the cursor isn't exposed, leaving spacing as is");
+                text
+            }
+        }
+    } else {
+        text
+    };
+
     // Perform the paste operation
     match paste_method {
         PasteMethod::None => {
             info!("PasteMethod::None selected - skipping paste action");
         }
-        PasteMethod::Direct => input::paste_text_direct(&mut enigo, &text)?,
+        PasteMethod::Direct | PasteMethod::Keystrokes => {
+            let interval = (paste_method == PasteMethod::Keystrokes)
+                .then(|| input::keystroke_interval(settings.typing_chars_per_second));
+
+            #[cfg(target_os = "linux")]
+            let typed = is_wayland() && wayland::type_text(&text, interval)?;
+            #[cfg(not(target_os = "linux"))]
+            let typed = false;
+
+            #[cfg(target_os = "linux")]
+            if !typed && is_wayland() {
+                warn_missing_input_tool();
+            }
+
+            if !typed {
+                match interval {
+                    Some(_) => {
+                        input::type_text(&mut enigo, &text, settings.typing_chars_per_second)?
+                    }
+                    None => input::paste_text_direct(&mut enigo, &text)?,
+                }
+            }
+        }
         PasteMethod::CtrlV | PasteMethod::CtrlShiftV | PasteMethod::ShiftInsert => {
-            paste_via_clipboard(&mut enigo, &text, &app_handle, &paste_method)?
+            // Text that should stay on the clipboard must not be replaced by the restore
+            let restore_delay = (settings.restore_clipboard && !copy_to_clipboard)
+                .then(|| Duration::from_millis(settings.clipboard_restore_delay_ms));
+            paste_via_clipboard(
+                &mut enigo,
+                &text,
+                html.as_deref(),
+                &app_handle,
+                &paste_method,
+                restore_delay,
+            )?
         }
     }
 
+    if paste_method != PasteMethod::None {
+        record_injection(&text);
+    }
+
     // After pasting, optionally copy to clipboard based on settings
-    if settings.clipboard_handling == ClipboardHandling::CopyToClipboard {
-        let clipboard = app_handle.clipboard();
-        clipboard
-            .write_text(&text)
+    if copy_to_clipboard {
+        write_clipboard(&app_handle, &text, html.as_deref())
             .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
     }
 
     Ok(())
 }
+
+/// Remember `text` as the latest injection, for [`undo_last_injection`]
+pub fn record_injection(text: &str) {
+    *LAST_INJECTION.lock().unwrap() = Some(Injection {
+        text: text.to_string(),
+        at: Instant::now(),
+    });
+}
+
+/// Removes the most recently injected text from the focused app, once
+pub fn undo_last_injection(app_handle: &AppHandle) -> Result<(), String> {
+    let injection = LAST_INJECTION
+        .lock()
+        .unwrap()
+        .take()
+        .filter(|injection| injection.at.elapsed() <= UNDO_WINDOW)
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+    let undo_method = get_settings(app_handle).undo_method;
+    // CRLF line breaks are removed with a single backspace
+    let count = injection.text.chars().filter(|c| *c != '\r').count();
+    info!(
+        "Undoing last injection ({} chars) via {:?}",
+        count, undo_method
+    );
+
+    let enigo_state = app_handle
+        .try_state::<EnigoState>()
+        .ok_or("Enigo state not initialized")?;
+    let mut enigo = enigo_state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock Enigo: {}", e))?;
+
+    input::release_modifiers(&mut enigo)?;
+    match undo_method {
+        UndoMethod::Backspace => input::send_backspaces(&mut enigo, count),
+        UndoMethod::PlatformUndo => input::send_undo(&mut enigo),
+    }
+}
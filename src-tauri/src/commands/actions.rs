@@ -0,0 +1,407 @@
+use crate::actions::CUSTOM_ACTION_PREFIX;
+use crate::cancellation::MAX_ACTION_TIMEOUT_SECS;
+use crate::settings::{
+    get_settings, write_settings, ActionConfig, AppSettings, ChainStepKind, OutputTarget,
+    ShellCommand, ShortcutBinding,
+};
+use crate::shell_command::MAX_TIMEOUT_SECS;
+use reqwest::header::{HeaderName, HeaderValue};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_action_configs(app: AppHandle) -> Result<HashMap<String, ActionConfig>, String> {
+    Ok(get_settings(&app).action_configs)
+}
+
+fn validate_action_config(config: &ActionConfig) -> Result<(), String> {
+    let mut step_names = std::collections::HashSet::new();
+    for step in &config.steps {
+        if step.name.trim().is_empty() || step.prompt_template.trim().is_empty() {
+            return Err("Pipeline steps need a name and a prompt template".to_string());
+        }
+        if !step_names.insert(step.name.as_str()) {
+            return Err(format!("Duplicate pipeline step name '{}'", step.name));
+        }
+    }
+    if let Some(temperature) = config.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err("Temperature must be between 0 and 2".to_string());
+        }
+    }
+    if let Some(top_p) = config.top_p {
+        if !(0.0..=1.0).contains(&top_p) {
+            return Err("top_p must be between 0 and 1".to_string());
+        }
+    }
+    if config
+        .timeout_secs
+        .is_some_and(|secs| secs > MAX_ACTION_TIMEOUT_SECS)
+    {
+        return Err(format!(
+            "The action timeout must be at most {} seconds",
+            MAX_ACTION_TIMEOUT_SECS
+        ));
+    }
+    if config.max_tokens == Some(0) {
+        return Err("max_tokens must be greater than 0".to_string());
+    }
+    if config.stop.len() > 4 || config.stop.iter().any(|stop| stop.is_empty()) {
+        return Err("Use at most 4 non-empty stop sequences".to_string());
+    }
+    if let Some(schema) = &config.output_schema {
+        let parsed: serde_json::Value = serde_json::from_str(schema)
+            .map_err(|e| format!("Output schema is not valid JSON: {}", e))?;
+        if !parsed.is_object() {
+            return Err("Output schema must be a JSON object".to_string());
+        }
+    }
+    let chain_targets = config.chain.iter().flat_map(|step| -> &[OutputTarget] {
+        match &step.step {
+            ChainStepKind::Output { targets } => targets,
+            _ => &[],
+        }
+    });
+    let targets: Vec<OutputTarget> = config
+        .output_targets
+        .iter()
+        .chain(chain_targets)
+        .copied()
+        .collect();
+    if targets.contains(&OutputTarget::Webhook) {
+        let url = config.webhook_url.as_deref().unwrap_or_default();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err("The webhook target needs an http:// or https:// URL".to_string());
+        }
+    }
+    if targets.contains(&OutputTarget::Obsidian) {
+        let vault = config.obsidian_vault_path.as_deref().unwrap_or_default();
+        let vault = crate::note::resolve_note_path(vault, chrono::Local::now().date_naive());
+        if vault.as_os_str().is_empty() || !vault.is_dir() {
+            return Err("The Obsidian target needs the folder of an existing vault".to_string());
+        }
+    }
+    if targets.contains(&OutputTarget::Notion) {
+        if config
+            .notion_token
+            .as_deref()
+            .is_none_or(|token| token.trim().is_empty())
+        {
+            return Err("The Notion target needs an integration token".to_string());
+        }
+        if config
+            .notion_database_id
+            .as_deref()
+            .and_then(crate::notion::database_id)
+            .is_none()
+        {
+            return Err("The Notion target needs the id or link of a database".to_string());
+        }
+    }
+    for (name, value) in &config.webhook_headers {
+        if HeaderName::from_bytes(name.as_bytes()).is_err() {
+            return Err(format!("'{}' isn't a valid header name", name));
+        }
+        if HeaderValue::from_str(value).is_err() {
+            return Err(format!("The value of header '{}' isn't valid", name));
+        }
+    }
+    if let Some(command) = &config.shell_command {
+        validate_shell_command(command)?;
+    }
+    for step in &config.chain {
+        match &step.step {
+            ChainStepKind::Action { action_id } => {
+                if action_id.trim().is_empty() {
+                    return Err("Chain steps need the action to run".to_string());
+                }
+            }
+            ChainStepKind::Shell { command } => validate_shell_command(command)?,
+            ChainStepKind::Output { targets } => {
+                if targets.is_empty() {
+                    return Err("Output steps need at least one target".to_string());
+                }
+            }
+            ChainStepKind::Notify { title } => {
+                if title.trim().is_empty() {
+                    return Err("Notify steps need a title".to_string());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_shell_command(command: &ShellCommand) -> Result<(), String> {
+    if command.command.trim().is_empty() {
+        return Err("The shell command must not be empty".to_string());
+    }
+    if !(1..=MAX_TIMEOUT_SECS).contains(&command.timeout_secs) {
+        return Err(format!(
+            "The command timeout must be between 1 and {} seconds",
+            MAX_TIMEOUT_SECS
+        ));
+    }
+    if let Some(dir) = command.working_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("Working directory '{}' doesn't exist", dir));
+        }
+    }
+    Ok(())
+}
+
+/// Create or replace the prompt configuration of an action
+#[tauri::command]
+#[specta::specta]
+pub fn set_action_config(
+    app: AppHandle,
+    action_id: String,
+    config: ActionConfig,
+) -> Result<(), String> {
+    if action_id.trim().is_empty() {
+        return Err("Action id must not be empty".to_string());
+    }
+    validate_action_config(&config)?;
+
+    let mut settings = get_settings(&app);
+    settings.action_configs.insert(action_id, config);
+    write_settings(&app, settings)
+}
+
+/// Remove an action's configuration so it falls back to the global prompt
+#[tauri::command]
+#[specta::specta]
+pub fn delete_action_config(app: AppHandle, action_id: String) -> Result<(), String> {
+    let mut settings = get_settings(&app);
+    if settings.action_configs.remove(&action_id).is_none() {
+        return Err(format!("No configuration found for action '{}'", action_id));
+    }
+    write_settings(&app, settings)
+}
+
+/// Id for a custom action called `name`, unique among `taken`
+fn custom_action_id(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    let slug: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let slug = slug.trim_matches('_');
+    let base = format!(
+        "{}{}",
+        CUSTOM_ACTION_PREFIX,
+        if slug.is_empty() { "action" } else { slug }
+    );
+    let mut id = base.clone();
+    let mut n = 2;
+    while taken(&id) {
+        id = format!("{}_{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+/// Define a new action with its own configuration, e.g. one that pipes its
+/// dictation to a shell command. It starts without a shortcut. Returns its id.
+#[tauri::command]
+#[specta::specta]
+pub fn create_custom_action(
+    app: AppHandle,
+    name: String,
+    config: ActionConfig,
+) -> Result<String, String> {
+    if name.trim().is_empty() {
+        return Err("The action needs a name".to_string());
+    }
+    validate_action_config(&config)?;
+
+    let mut settings = get_settings(&app);
+    let id = custom_action_id(&name, |id| settings.bindings.contains_key(id));
+    settings.bindings.insert(
+        id.clone(),
+        ShortcutBinding {
+            id: id.clone(),
+            name: name.trim().to_string(),
+            description: "Custom action".to_string(),
+            default_binding: String::new(),
+            current_binding: String::new(),
+        },
+    );
+    settings.action_configs.insert(id.clone(), config);
+    write_settings(&app, settings)?;
+    Ok(id)
+}
+
+/// Every action configuration, the active one and those of the profiles
+fn all_action_configs(settings: &mut AppSettings) -> impl Iterator<Item = &mut ActionConfig> {
+    settings.action_configs.values_mut().chain(
+        settings
+            .profiles
+            .iter_mut()
+            .flat_map(|profile| profile.action_configs.values_mut()),
+    )
+}
+
+/// Actions whose chain runs `action_id`
+fn chains_using(settings: &AppSettings, action_id: &str) -> Vec<String> {
+    let mut users: Vec<String> = settings
+        .action_configs
+        .iter()
+        .filter(|(_, config)| {
+            config.chain.iter().any(|step| {
+                matches!(&step.step, ChainStepKind::Action { action_id: id } if id == action_id)
+            })
+        })
+        .map(|(id, _)| id.clone())
+        .collect();
+    users.sort();
+    users
+}
+
+/// Give action `from` the id `to`: its shortcut, configuration, profile
+/// entries and the chain steps that run it
+fn move_action_id(settings: &mut AppSettings, from: &str, to: &str) {
+    if let Some(mut binding) = settings.bindings.remove(from) {
+        binding.id = to.to_string();
+        settings.bindings.insert(to.to_string(), binding);
+    }
+    if let Some(config) = settings.action_configs.remove(from) {
+        settings.action_configs.insert(to.to_string(), config);
+    }
+    for profile in &mut settings.profiles {
+        if let Some(shortcut) = profile.bindings.remove(from) {
+            profile.bindings.insert(to.to_string(), shortcut);
+        }
+        if let Some(config) = profile.action_configs.remove(from) {
+            profile.action_configs.insert(to.to_string(), config);
+        }
+    }
+    for config in all_action_configs(settings) {
+        for step in &mut config.chain {
+            if let ChainStepKind::Action { action_id } = &mut step.step {
+                if action_id == from {
+                    *action_id = to.to_string();
+                }
+            }
+        }
+    }
+}
+
+fn check_custom_action(settings: &AppSettings, action_id: &str) -> Result<(), String> {
+    if !action_id.starts_with(CUSTOM_ACTION_PREFIX) {
+        return Err(format!("'{}' is not a custom action", action_id));
+    }
+    if !settings.bindings.contains_key(action_id) {
+        return Err(format!("No custom action '{}'", action_id));
+    }
+    Ok(())
+}
+
+/// Rename a custom action. Its id follows the name, and whatever refers to
+/// the old id is moved along. Returns the new id.
+#[tauri::command]
+#[specta::specta]
+pub fn rename_custom_action(
+    app: AppHandle,
+    action_id: String,
+    name: String,
+) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The action needs a name".to_string());
+    }
+    let mut settings = get_settings(&app);
+    check_custom_action(&settings, &action_id)?;
+
+    let id = custom_action_id(name, |id| {
+        id != action_id && settings.bindings.contains_key(id)
+    });
+    move_action_id(&mut settings, &action_id, &id);
+    if let Some(binding) = settings.bindings.get_mut(&id) {
+        binding.name = name.to_string();
+    }
+    write_settings(&app, settings)?;
+    Ok(id)
+}
+
+/// Remove a custom action with its shortcut and configuration, unless
+/// another action's chain still runs it
+#[tauri::command]
+#[specta::specta]
+pub fn delete_custom_action(app: AppHandle, action_id: String) -> Result<(), String> {
+    let mut settings = get_settings(&app);
+    check_custom_action(&settings, &action_id)?;
+    let users = chains_using(&settings, &action_id);
+    if !users.is_empty() {
+        return Err(format!(
+            "'{}' is still used by the chain of {}",
+            action_id,
+            users.join(", ")
+        ));
+    }
+
+    settings.bindings.remove(&action_id);
+    settings.action_configs.remove(&action_id);
+    for profile in &mut settings.profiles {
+        profile.bindings.remove(&action_id);
+        profile.action_configs.remove(&action_id);
+    }
+    write_settings(&app, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_action_id() {
+        assert_eq!(
+            custom_action_id("Send to Obsidian!", |_| false),
+            "custom_send_to_obsidian"
+        );
+        assert_eq!(custom_action_id("  ", |_| false), "custom_action");
+        assert_eq!(
+            custom_action_id("Log", |id| id == "custom_log" || id == "custom_log_2"),
+            "custom_log_3"
+        );
+    }
+
+    #[test]
+    fn test_move_action_id() {
+        let mut settings = crate::settings::get_default_settings();
+        settings.bindings.insert(
+            "custom_log".to_string(),
+            ShortcutBinding {
+                id: "custom_log".to_string(),
+                name: "Log".to_string(),
+                description: "Custom action".to_string(),
+                default_binding: String::new(),
+                current_binding: String::new(),
+            },
+        );
+        let mut config = ActionConfig::default();
+        config.chain.push(crate::settings::ChainStep {
+            step: ChainStepKind::Action {
+                action_id: "custom_log".to_string(),
+            },
+            on_error: Default::default(),
+        });
+        settings
+            .action_configs
+            .insert("custom_log".to_string(), ActionConfig::default());
+        settings
+            .action_configs
+            .insert("custom_other".to_string(), config);
+        assert_eq!(chains_using(&settings, "custom_log"), ["custom_other"]);
+
+        move_action_id(&mut settings, "custom_log", "custom_journal");
+        assert!(!settings.bindings.contains_key("custom_log"));
+        assert_eq!(settings.bindings["custom_journal"].id, "custom_journal");
+        assert!(settings.action_configs.contains_key("custom_journal"));
+        assert!(chains_using(&settings, "custom_log").is_empty());
+        assert_eq!(chains_using(&settings, "custom_journal"), ["custom_other"]);
+    }
+}
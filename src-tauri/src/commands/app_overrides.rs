@@ -0,0 +1,63 @@
+use crate::app_overrides::normalize_process_name;
+use crate::settings::{get_settings, write_settings, AppOverride};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_app_overrides(app: AppHandle) -> Result<HashMap<String, AppOverride>, String> {
+    Ok(get_settings(&app).app_overrides)
+}
+
+/// Add or replace the override for the app with process `process_name`
+#[tauri::command]
+#[specta::specta]
+pub fn set_app_override(
+    app: AppHandle,
+    process_name: String,
+    app_override: AppOverride,
+) -> Result<(), String> {
+    let process_name = normalize_process_name(&process_name);
+    if process_name.is_empty() {
+        return Err("Process name must not be empty".to_string());
+    }
+    if app_override
+        .selected_language
+        .as_ref()
+        .is_some_and(|language| language.trim().is_empty())
+    {
+        return Err("Language must not be empty".to_string());
+    }
+
+    let mut settings = get_settings(&app);
+    if let Some(prompt_id) = &app_override.post_process_prompt_id {
+        if !settings
+            .post_process_prompts
+            .iter()
+            .any(|prompt| &prompt.id == prompt_id)
+        {
+            return Err(format!("Prompt with id '{}' not found", prompt_id));
+        }
+    }
+    // Entries added by hand may differ in case or suffix
+    settings
+        .app_overrides
+        .retain(|name, _| normalize_process_name(name) != process_name);
+    settings.app_overrides.insert(process_name, app_override);
+    write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_app_override(app: AppHandle, process_name: String) -> Result<(), String> {
+    let process_name = normalize_process_name(&process_name);
+    let mut settings = get_settings(&app);
+    let count = settings.app_overrides.len();
+    settings
+        .app_overrides
+        .retain(|name, _| normalize_process_name(name) != process_name);
+    if settings.app_overrides.len() == count {
+        return Err(format!("No override found for '{}'", process_name));
+    }
+    write_settings(&app, settings)
+}
@@ -1,6 +1,7 @@
 use crate::audio_feedback;
 use crate::audio_toolkit::audio::{list_input_devices, list_output_devices};
-use crate::managers::audio::{AudioRecordingManager, MicrophoneMode};
+use crate::managers::audio::AudioRecordingManager;
+use crate::microphone_permission::{self, MicrophoneAccess};
 use crate::settings::{get_settings, write_settings};
 use log::warn;
 use serde::{Deserialize, Serialize};
@@ -15,12 +16,8 @@ pub struct CustomSounds {
 }
 
 fn custom_sound_exists(app: &AppHandle, sound_type: &str) -> bool {
-    app.path()
-        .resolve(
-            format!("custom_{}.wav", sound_type),
-            tauri::path::BaseDirectory::AppData,
-        )
-        .map_or(false, |path| path.exists())
+    crate::portable::app_data_dir(app)
+        .is_ok_and(|dir| dir.join(format!("custom_{}.wav", sound_type)).exists())
 }
 
 #[tauri::command]
@@ -45,18 +42,7 @@ pub fn update_microphone_mode(app: AppHandle, always_on: bool) -> Result<(), Str
     // Update settings
     let mut settings = get_settings(&app);
     settings.always_on_microphone = always_on;
-    write_settings(&app, settings);
-
-    // Update the audio manager mode
-    let rm = app.state::<Arc<AudioRecordingManager>>();
-    let new_mode = if always_on {
-        MicrophoneMode::AlwaysOn
-    } else {
-        MicrophoneMode::OnDemand
-    };
-
-    rm.update_mode(new_mode)
-        .map_err(|e| format!("Failed to update microphone mode: {}", e))
+    write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -96,14 +82,7 @@ pub fn set_selected_microphone(app: AppHandle, device_name: String) -> Result<()
     } else {
         Some(device_name)
     };
-    write_settings(&app, settings);
-
-    // Update the audio manager to use the new device
-    let rm = app.state::<Arc<AudioRecordingManager>>();
-    rm.update_selected_device()
-        .map_err(|e| format!("Failed to update selected device: {}", e))?;
-
-    Ok(())
+    write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -145,8 +124,7 @@ pub fn set_selected_output_device(app: AppHandle, device_name: String) -> Result
     } else {
         Some(device_name)
     };
-    write_settings(&app, settings);
-    Ok(())
+    write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -181,8 +159,7 @@ pub fn set_clamshell_microphone(app: AppHandle, device_name: String) -> Result<(
     } else {
         Some(device_name)
     };
-    write_settings(&app, settings);
-    Ok(())
+    write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -200,3 +177,16 @@ pub fn is_recording(app: AppHandle) -> bool {
     let audio_manager = app.state::<Arc<AudioRecordingManager>>();
     audio_manager.is_recording()
 }
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_microphone_access() -> MicrophoneAccess {
+    microphone_permission::status()
+}
+
+/// Open the system settings page that turns microphone access back on
+#[tauri::command]
+#[specta::specta]
+pub fn open_microphone_settings(app: AppHandle) -> Result<(), String> {
+    microphone_permission::open_settings(&app)
+}
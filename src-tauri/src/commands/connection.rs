@@ -0,0 +1,154 @@
+use crate::llm_error::LlmError;
+use crate::llm_types::ChatCompletionRequest;
+use crate::retry::RetryPolicy;
+use crate::settings::{get_settings, APPLE_INTELLIGENCE_PROVIDER_ID, LLAMA_CPP_PROVIDER_ID};
+use log::{debug, warn};
+use serde::Serialize;
+use specta::Type;
+use std::time::Instant;
+use tauri::AppHandle;
+
+/// Result of a provider connection test
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct ConnectionCheck {
+    pub ok: bool,
+    pub model: String,
+    /// Round trip of the test request, including the model's answer
+    pub latency_ms: u64,
+    pub error: Option<LlmError>,
+    /// Short explanation and fix for `error`, ready to show
+    pub user_message: Option<String>,
+    pub remediation: Option<String>,
+}
+
+impl ConnectionCheck {
+    fn failed(model: String, latency_ms: u64, error: LlmError) -> Self {
+        Self {
+            ok: false,
+            model,
+            latency_ms,
+            user_message: Some(error.user_message()),
+            remediation: Some(error.remediation().to_string()),
+            error: Some(error),
+        }
+    }
+}
+
+/// Send a minimal chat request with the stored key and model of a provider.
+/// `model` overrides the saved model, e.g. to test a selection before saving it.
+#[tauri::command]
+#[specta::specta]
+pub async fn check_llm_connection(
+    app: AppHandle,
+    provider_id: String,
+    model: Option<String>,
+) -> Result<ConnectionCheck, String> {
+    let settings = get_settings(&app);
+    let provider = settings
+        .post_process_provider(&provider_id)
+        .cloned()
+        .ok_or_else(|| format!("Provider '{}' not found", provider_id))?;
+    let model = model
+        .or_else(|| settings.post_process_models.get(&provider_id).cloned())
+        .unwrap_or_default();
+
+    if provider.id == APPLE_INTELLIGENCE_PROVIDER_ID {
+        #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+        let available = crate::apple_intelligence::check_apple_intelligence_availability();
+        #[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+        let available = false;
+
+        if available {
+            return Ok(ConnectionCheck {
+                ok: true,
+                model,
+                latency_ms: 0,
+                error: None,
+                user_message: None,
+                remediation: None,
+            });
+        }
+        return Ok(ConnectionCheck::failed(
+            model,
+            0,
+            LlmError::from("Apple Intelligence is not available on this device".to_string()),
+        ));
+    }
+
+    if provider.id == LLAMA_CPP_PROVIDER_ID {
+        let model_path = settings.llama_cpp_model_path.clone().unwrap_or_default();
+        let problem = if !cfg!(feature = "llama-cpp") {
+            Some("This build does not include local llama.cpp inference".to_string())
+        } else if model_path.is_empty() {
+            Some("No GGUF model file selected".to_string())
+        } else if !std::path::Path::new(&model_path).is_file() {
+            Some(format!("Model file '{}' does not exist", model_path))
+        } else {
+            None
+        };
+
+        return Ok(match problem {
+            Some(message) => ConnectionCheck::failed(model, 0, LlmError::InvalidModel { message }),
+            None => ConnectionCheck {
+                ok: true,
+                model,
+                latency_ms: 0,
+                error: None,
+                user_message: None,
+                remediation: None,
+            },
+        });
+    }
+
+    if model.trim().is_empty() {
+        return Ok(ConnectionCheck::failed(
+            model,
+            0,
+            LlmError::InvalidModel {
+                message: "No model selected".to_string(),
+            },
+        ));
+    }
+
+    let api_key = settings
+        .post_process_api_keys
+        .get(&provider_id)
+        .cloned()
+        .unwrap_or_default();
+    let (service_tier, extra_body) = settings.provider_request_fields(&provider_id);
+    // A connection test should report the first failure, not retry it
+    let http_client = crate::http::shared_client(&settings)?;
+    let client = crate::llm_client::create_client(&provider, api_key, http_client)
+        .with_gemini_safety_threshold(settings.gemini_safety_threshold)
+        .with_retry_policy(RetryPolicy::with_max_attempts(1))
+        .with_provider_fields(service_tier, extra_body);
+    let request = ChatCompletionRequest::builder(model.clone())
+        .user("Reply with the single word OK.")
+        .build()?;
+
+    debug!(
+        "Testing connection to provider '{}' with model '{}'",
+        provider_id, model
+    );
+    let started = Instant::now();
+    let result = client.send_chat_request(&request).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    Ok(match result {
+        Ok(_) => ConnectionCheck {
+            ok: true,
+            model,
+            latency_ms,
+            error: None,
+            user_message: None,
+            remediation: None,
+        },
+        Err(e) => {
+            warn!(
+                "Connection test for provider '{}' failed: {}",
+                provider_id, e
+            );
+            ConnectionCheck::failed(model, latency_ms, e)
+        }
+    })
+}
@@ -0,0 +1,10 @@
+use crate::managers::conversation::ConversationManager;
+use std::sync::Arc;
+use tauri::State;
+
+/// Forget earlier dictations so the next one starts a new conversation
+#[tauri::command]
+#[specta::specta]
+pub fn reset_conversation(conversation_manager: State<'_, Arc<ConversationManager>>) {
+    conversation_manager.reset();
+}
@@ -1,6 +1,23 @@
-use crate::managers::history::{HistoryEntry, HistoryManager};
+use crate::history_export::{self, ExportFormat, ExportOptions};
+use crate::managers::history::{
+    HistoryCursor, HistoryEntry, HistoryFilter, HistoryManager, HistoryPage, HistoryRange,
+    HistoryRevision, HistorySearchResult,
+};
+use crate::managers::usage::UsageManager;
+use crate::progress::{Operation, OperationKind};
+use crate::recovery::{self, OrphanedRecording};
+use crate::stats::{self, DictationStats};
+use chrono::{DateTime, Local};
+use log::warn;
 use std::sync::Arc;
-use tauri::{AppHandle, State};
+use std::time::Duration;
+use tauri::http::header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use tauri::http::{Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, State};
+
+/// Scheme the frontend plays recordings from, as
+/// `history-audio://localhost/<file name>`
+pub const AUDIO_PROTOCOL: &str = "history-audio";
 
 #[tauri::command]
 #[specta::specta]
@@ -14,6 +31,186 @@ pub async fn get_history_entries(
         .map_err(|e| e.to_string())
 }
 
+/// Entries matching `filter`, newest first, `limit` at a time. Pass the
+/// returned cursor back to get the next page.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_history_page(
+    history_manager: State<'_, Arc<HistoryManager>>,
+    filter: Option<HistoryFilter>,
+    cursor: Option<HistoryCursor>,
+    limit: Option<u32>,
+) -> Result<HistoryPage, String> {
+    history_manager
+        .get_history_page(&filter.unwrap_or_default(), cursor, limit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The newest entry's final text, None while the history is empty
+#[tauri::command]
+#[specta::specta]
+pub async fn get_last_transcript(
+    history_manager: State<'_, Arc<HistoryManager>>,
+) -> Result<Option<String>, String> {
+    let page = history_manager
+        .get_history_page(&HistoryFilter::default(), None, Some(1))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(page.entries.into_iter().next().map(|entry| {
+        entry
+            .post_processed_text
+            .unwrap_or(entry.transcription_text)
+    }))
+}
+
+/// Copy the newest entry's final text to the clipboard
+#[tauri::command]
+#[specta::specta]
+pub async fn copy_last_transcript(
+    app: AppHandle,
+    history_manager: State<'_, Arc<HistoryManager>>,
+) -> Result<(), String> {
+    let text = get_last_transcript(history_manager)
+        .await?
+        .ok_or_else(|| "There is no transcript yet".to_string())?;
+    crate::clipboard::copy_text(&app, &text)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn search_history(
+    history_manager: State<'_, Arc<HistoryManager>>,
+    query: String,
+) -> Result<Vec<HistorySearchResult>, String> {
+    history_manager
+        .search_history(&query)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Write the entries in `range` to `path` as Markdown, CSV or JSON.
+/// Returns how many entries were exported.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_history(
+    app: AppHandle,
+    history_manager: State<'_, Arc<HistoryManager>>,
+    range: HistoryRange,
+    format: ExportFormat,
+    path: String,
+    options: Option<ExportOptions>,
+) -> Result<usize, String> {
+    let mut operation = Operation::start(&app, OperationKind::HistoryExport, Some(&path));
+    operation.report("loading", None);
+    let entries = history_manager
+        .get_entries_between(range.start, range.end)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if operation.is_cancelled() {
+        return Err("Export cancelled".to_string());
+    }
+    operation.report("rendering", Some(33.0));
+    let contents = history_export::render(
+        &entries,
+        format,
+        options.unwrap_or_default(),
+        history_manager.recordings_dir(),
+    )?;
+
+    if operation.is_cancelled() {
+        return Err("Export cancelled".to_string());
+    }
+    operation.report("writing", Some(66.0));
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+    operation.complete();
+    Ok(entries.len())
+}
+
+/// Run an entry's raw transcript through post-processing again, with action
+/// `binding_id` (the default transcribe action when omitted) and optionally
+/// another prompt. The new output replaces the entry's, the old one is kept as
+/// a revision.
+#[tauri::command]
+#[specta::specta]
+pub async fn reprocess_history_entry(
+    app: AppHandle,
+    history_manager: State<'_, Arc<HistoryManager>>,
+    id: i64,
+    binding_id: Option<String>,
+    prompt_id: Option<String>,
+) -> Result<HistoryEntry, String> {
+    let entry = history_manager
+        .get_entry_by_id(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("History entry {} not found", id))?;
+    let binding_id = binding_id.unwrap_or_else(|| "transcribe".to_string());
+
+    let (text, prompt) = crate::actions::reprocess_transcription(
+        &app,
+        &binding_id,
+        prompt_id,
+        &entry.transcription_text,
+    )
+    .await?;
+    history_manager
+        .add_revision(id, text, prompt)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    history_manager
+        .get_entry_by_id(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("History entry {} not found", id))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_history_revisions(
+    history_manager: State<'_, Arc<HistoryManager>>,
+    id: i64,
+) -> Result<Vec<HistoryRevision>, String> {
+    history_manager
+        .get_revisions(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Words, speaking speed, time saved, action use and provider error rates for
+/// the entries in `range`
+#[tauri::command]
+#[specta::specta]
+pub async fn get_stats(
+    history_manager: State<'_, Arc<HistoryManager>>,
+    usage_manager: State<'_, Arc<UsageManager>>,
+    range: HistoryRange,
+) -> Result<DictationStats, String> {
+    let entries = history_manager
+        .get_entries_between(range.start, range.end)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut stats = stats::compute(&entries);
+
+    // Usage counters are kept per local day
+    let local_day = |timestamp: i64| {
+        DateTime::from_timestamp(timestamp, 0)
+            .map(|at| at.with_timezone(&Local).format("%Y-%m-%d").to_string())
+    };
+    let since = range.start.and_then(local_day).unwrap_or_default();
+    let until = range
+        .end
+        .and_then(|end| local_day(end - 1))
+        .unwrap_or_else(|| "9999-12-31".to_string());
+    stats.providers = usage_manager
+        .get_provider_errors(&since, &until)
+        .map_err(|e| e.to_string())?;
+
+    Ok(stats)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn toggle_history_entry_saved(
@@ -40,6 +237,107 @@ pub async fn get_audio_file_path(
         .map(|s| s.to_string())
 }
 
+/// Inclusive byte range a `Range` header asks for out of `len` bytes
+fn byte_range(header: &str, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = header.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let last = len.checked_sub(1)?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => (len.saturating_sub(suffix.parse().ok()?), last),
+        (start, "") => (start.parse().ok()?, last),
+        (start, end) => (start.parse().ok()?, end.parse::<usize>().ok()?.min(last)),
+    };
+    (start <= end).then_some((start, end))
+}
+
+fn empty_response(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+/// A recording for a [`AUDIO_PROTOCOL`] request, decrypted in memory so an
+/// encrypted one is never written out in plain text for playback
+pub fn audio_protocol_response(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let file_name = request.uri().path().trim_start_matches('/');
+    // Only names recordings are saved under, nothing outside their folder
+    let valid = !file_name.starts_with('.')
+        && file_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return empty_response(StatusCode::BAD_REQUEST);
+    }
+    let data = match app.state::<Arc<HistoryManager>>().read_audio(file_name) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Failed to read recording '{}': {}", file_name, e);
+            return empty_response(StatusCode::NOT_FOUND);
+        }
+    };
+
+    let range = request
+        .headers()
+        .get(RANGE)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| byte_range(header, data.len()));
+    let response = Response::builder()
+        .header(CONTENT_TYPE, "audio/wav")
+        .header(ACCEPT_RANGES, "bytes");
+    match range {
+        Some((start, end)) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, data.len()),
+            )
+            .body(data[start..=end].to_vec()),
+        None => response.body(data),
+    }
+    .unwrap_or_default()
+}
+
+/// Play the recording of entry `id` in the app, from `start_ms` into it
+#[tauri::command]
+#[specta::specta]
+pub async fn play_history_audio(
+    app: AppHandle,
+    history_manager: State<'_, Arc<HistoryManager>>,
+    id: i64,
+    start_ms: Option<u64>,
+) -> Result<(), String> {
+    let entry = history_manager
+        .get_entry_by_id(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("History entry {} not found", id))?;
+    if entry.audio_deleted {
+        return Err("The recording was removed by the retention policy".to_string());
+    }
+
+    let start_ms = start_ms.unwrap_or(0);
+    if let Some(duration_ms) = entry.duration_ms {
+        if start_ms as i64 >= duration_ms {
+            return Err(format!(
+                "Position {} ms is past the end of the recording ({} ms)",
+                start_ms, duration_ms
+            ));
+        }
+    }
+
+    let wav = history_manager
+        .read_audio(&entry.file_name)
+        .map_err(|e| format!("Failed to read the recording: {}", e))?;
+    crate::playback::play(&app, id, wav, Duration::from_millis(start_ms));
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_history_audio(app: AppHandle) {
+    crate::playback::stop(&app);
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn delete_history_entry(
@@ -55,27 +353,16 @@ pub async fn delete_history_entry(
 
 #[tauri::command]
 #[specta::specta]
-pub async fn update_history_limit(
-    app: AppHandle,
-    history_manager: State<'_, Arc<HistoryManager>>,
-    limit: usize,
-) -> Result<(), String> {
+pub async fn update_history_limit(app: AppHandle, limit: usize) -> Result<(), String> {
     let mut settings = crate::settings::get_settings(&app);
     settings.history_limit = limit;
-    crate::settings::write_settings(&app, settings);
-
-    history_manager
-        .cleanup_old_entries()
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+    crate::settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
 #[specta::specta]
 pub async fn update_recording_retention_period(
     app: AppHandle,
-    history_manager: State<'_, Arc<HistoryManager>>,
     period: String,
 ) -> Result<(), String> {
     use crate::settings::RecordingRetentionPeriod;
@@ -86,16 +373,117 @@ pub async fn update_recording_retention_period(
         "days3" => RecordingRetentionPeriod::Days3,
         "weeks2" => RecordingRetentionPeriod::Weeks2,
         "months3" => RecordingRetentionPeriod::Months3,
+        "custom_days" => RecordingRetentionPeriod::CustomDays,
         _ => return Err(format!("Invalid retention period: {}", period)),
     };
 
     let mut settings = crate::settings::get_settings(&app);
     settings.recording_retention_period = retention_period;
-    crate::settings::write_settings(&app, settings);
+    crate::settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_history_retention_days(app: AppHandle, days: u32) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings(&app);
+    settings.history_retention_days = days;
+    crate::settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_history_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings(&app);
+    settings.history_enabled = enabled;
+    crate::settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_purge_audio_only(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings(&app);
+    settings.purge_audio_only = enabled;
+    crate::settings::write_settings(&app, settings)
+}
 
+#[tauri::command]
+#[specta::specta]
+pub async fn update_recording_storage_limit(
+    app: AppHandle,
+    limit_mb: Option<u64>,
+) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings(&app);
+    settings.recording_storage_limit_mb = limit_mb;
+    crate::settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_meeting_notes_dir(app: AppHandle, dir: Option<String>) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings(&app);
+    settings.meeting_notes_dir = dir.filter(|dir| !dir.trim().is_empty());
+    crate::settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_meeting_save_to_history(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = crate::settings::get_settings(&app);
+    settings.meeting_save_to_history = enabled;
+    crate::settings::write_settings(&app, settings)
+}
+
+/// Encrypt or decrypt the history database and recordings; the setting only
+/// changes once all of them are
+#[tauri::command]
+#[specta::specta]
+pub async fn update_history_encryption(
+    app: AppHandle,
+    history_manager: State<'_, Arc<HistoryManager>>,
+    enabled: bool,
+) -> Result<(), String> {
     history_manager
-        .cleanup_old_entries()
+        .set_encryption(enabled)
         .map_err(|e| e.to_string())?;
+    let mut settings = crate::settings::get_settings(&app);
+    settings.history_encryption_enabled = enabled;
+    crate::settings::write_settings(&app, settings)
+}
 
-    Ok(())
+/// Recordings left unfinished when Babbl last stopped
+#[tauri::command]
+#[specta::specta]
+pub fn list_orphaned_recordings(app: AppHandle) -> Vec<OrphanedRecording> {
+    recovery::orphans(&app)
+}
+
+/// Transcribe an unfinished recording into the history; returns the text
+#[tauri::command]
+#[specta::specta]
+pub async fn recover_orphaned_recording(
+    app: AppHandle,
+    file_name: String,
+) -> Result<String, String> {
+    recovery::recover(&app, &file_name).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn discard_orphaned_recording(app: AppHandle, file_name: String) -> Result<(), String> {
+    recovery::discard_orphan(&app, &file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(byte_range("bytes=0-", 10), Some((0, 9)));
+        assert_eq!(byte_range("bytes=2-4", 10), Some((2, 4)));
+        assert_eq!(byte_range("bytes=5-100", 10), Some((5, 9)));
+        assert_eq!(byte_range("bytes=-3", 10), Some((7, 9)));
+        assert_eq!(byte_range("bytes=12-", 10), None);
+        assert_eq!(byte_range("bytes=0-", 0), None);
+    }
 }
@@ -0,0 +1,173 @@
+use crate::settings::{get_settings, APPLE_INTELLIGENCE_PROVIDER_ID, LLAMA_CPP_PROVIDER_ID};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// How long a provider's model list is reused before it is fetched again
+const MODEL_LIST_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Largest edit distance for which a "did you mean" suggestion is offered
+const MAX_SUGGESTION_DISTANCE: usize = 4;
+
+struct CachedModelList {
+    fetched_at: Instant,
+    models: Vec<String>,
+}
+
+/// Model lists keyed by provider id and base URL, so editing a custom
+/// provider's URL doesn't serve the old server's models
+static MODEL_LIST_CACHE: Lazy<Mutex<HashMap<String, CachedModelList>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cache_key(app: &AppHandle, provider_id: &str) -> String {
+    let base_url = get_settings(app)
+        .post_process_provider(provider_id)
+        .map(|provider| provider.base_url.clone())
+        .unwrap_or_default();
+    format!("{}|{}", provider_id, base_url)
+}
+
+/// List the models a post-processing provider offers. Results are cached for a
+/// few minutes; pass `refresh` to bypass the cache.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_llm_models(
+    app: AppHandle,
+    provider_id: String,
+    refresh: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let key = cache_key(&app, &provider_id);
+
+    if !refresh.unwrap_or(false) {
+        let cache = MODEL_LIST_CACHE.lock().unwrap();
+        if let Some(cached) = cache.get(&key) {
+            if cached.fetched_at.elapsed() < MODEL_LIST_TTL {
+                debug!("Using cached model list for provider '{}'", provider_id);
+                return Ok(cached.models.clone());
+            }
+        }
+    }
+
+    let mut models = crate::shortcut::fetch_post_process_models(app, provider_id).await?;
+    models.sort();
+    models.dedup();
+
+    MODEL_LIST_CACHE.lock().unwrap().insert(
+        key,
+        CachedModelList {
+            fetched_at: Instant::now(),
+            models: models.clone(),
+        },
+    );
+    Ok(models)
+}
+
+/// Check a model name against the provider's model list before it is saved.
+///
+/// Only definite mismatches are rejected: when the list can't be fetched
+/// (offline, no key yet, endpoint not supported) the name is accepted as-is.
+pub async fn validate_llm_model(
+    app: &AppHandle,
+    provider_id: &str,
+    model: &str,
+) -> Result<(), String> {
+    let model = model.trim();
+    if model.is_empty()
+        || provider_id == APPLE_INTELLIGENCE_PROVIDER_ID
+        || provider_id == LLAMA_CPP_PROVIDER_ID
+    {
+        return Ok(());
+    }
+
+    let models = match list_llm_models(app.clone(), provider_id.to_string(), None).await {
+        Ok(models) => models,
+        Err(e) => {
+            warn!(
+                "Skipping validation of model '{}' for provider '{}': {}",
+                model, provider_id, e
+            );
+            return Ok(());
+        }
+    };
+
+    check_model_name(model, &models)
+}
+
+fn check_model_name(model: &str, models: &[String]) -> Result<(), String> {
+    // Gemini lists models without the "models/" prefix
+    let normalized = model.trim_start_matches("models/");
+    if models.is_empty() || models.iter().any(|m| m == model || m == normalized) {
+        return Ok(());
+    }
+
+    match closest_model(normalized, models) {
+        Some(suggestion) => Err(format!(
+            "Model '{}' is not offered by this provider. Did you mean '{}'?",
+            model, suggestion
+        )),
+        None => Err(format!("Model '{}' is not offered by this provider", model)),
+    }
+}
+
+fn closest_model<'a>(model: &str, models: &'a [String]) -> Option<&'a str> {
+    let model = model.to_lowercase();
+    models
+        .iter()
+        .map(|candidate| (edit_distance(&model, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("llama", "llama"), 0);
+        assert_eq!(edit_distance("lama", "llama"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_check_model_name_suggests_closest() {
+        let models = vec![
+            "llama-3.1-8b-instant".to_string(),
+            "llama-3.3-70b-versatile".to_string(),
+        ];
+        assert!(check_model_name("llama-3.3-70b-versatile", &models).is_ok());
+        assert!(check_model_name("anything", &[]).is_ok());
+
+        let error = check_model_name("lama-3.3-70b-versatile", &models).unwrap_err();
+        assert!(error.contains("Did you mean 'llama-3.3-70b-versatile'"));
+
+        let error = check_model_name("gpt-4o", &models).unwrap_err();
+        assert!(!error.contains("Did you mean"));
+    }
+
+    #[test]
+    fn test_check_model_name_accepts_gemini_prefix() {
+        let models = vec!["gemini-2.5-flash".to_string()];
+        assert!(check_model_name("models/gemini-2.5-flash", &models).is_ok());
+    }
+}
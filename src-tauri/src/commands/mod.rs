@@ -1,11 +1,23 @@
+pub mod actions;
+pub mod app_overrides;
 pub mod audio;
+pub mod connection;
+pub mod conversation;
 pub mod history;
+pub mod llm_models;
 pub mod models;
+pub mod onboarding;
+pub mod preview;
+pub mod profiles;
+pub mod snippets;
 pub mod transcription;
+pub mod usage;
+pub mod widget;
 
 use crate::settings::{get_settings, write_settings, AppSettings, LogLevel};
+use crate::settings_validation::{self, SettingsError};
 use crate::utils::cancel_current_operation;
-use tauri::{AppHandle, Manager};
+use tauri::AppHandle;
 use tauri_plugin_opener::OpenerExt;
 
 #[tauri::command]
@@ -14,12 +26,18 @@ pub fn cancel_operation(app: AppHandle) {
     cancel_current_operation(&app);
 }
 
+/// Stop a model download, long transcription or export by the id its
+/// `operation-progress` events carry
+#[tauri::command]
+#[specta::specta]
+pub fn cancel_progress_operation(operation_id: String) -> Result<(), String> {
+    crate::progress::cancel(&operation_id)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub fn get_app_dir_path(app: AppHandle) -> Result<String, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
+    let app_data_dir = crate::portable::app_data_dir(&app)
         .map_err(|e| format!("Failed to get app data directory: {}", e))?;
 
     Ok(app_data_dir.to_string_lossy().to_string())
@@ -31,18 +49,44 @@ pub fn get_app_settings(app: AppHandle) -> Result<AppSettings, String> {
     Ok(get_settings(&app))
 }
 
+/// Settings fields rejected or flagged by the last validation
+#[tauri::command]
+#[specta::specta]
+pub fn get_settings_errors() -> Result<Vec<SettingsError>, String> {
+    Ok(settings_validation::last_errors())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub fn get_default_settings() -> Result<AppSettings, String> {
     Ok(crate::settings::get_default_settings())
 }
 
+/// Restore the defaults of one settings section, e.g. `shortcuts` or
+/// `output`, keeping all other settings
+#[tauri::command]
+#[specta::specta]
+pub fn reset_settings_section(app: AppHandle, name: String) -> Result<AppSettings, String> {
+    let settings = crate::settings::reset_section(&get_settings(&app), &name)?;
+    write_settings(&app, settings)?;
+    Ok(get_settings(&app))
+}
+
+/// Replace the control API token, locking out clients with the old one
+#[tauri::command]
+#[specta::specta]
+pub fn regenerate_control_api_token(app: AppHandle) -> Result<String, String> {
+    let token = crate::control_api::generate_token();
+    let mut settings = get_settings(&app);
+    settings.control_api_token = Some(token.clone());
+    write_settings(&app, settings)?;
+    Ok(token)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub fn get_log_dir_path(app: AppHandle) -> Result<String, String> {
-    let log_dir = app
-        .path()
-        .app_log_dir()
+    let log_dir = crate::portable::app_log_dir(&app)
         .map_err(|e| format!("Failed to get log directory: {}", e))?;
 
     Ok(log_dir.to_string_lossy().to_string())
@@ -51,27 +95,220 @@ pub fn get_log_dir_path(app: AppHandle) -> Result<String, String> {
 #[specta::specta]
 #[tauri::command]
 pub fn set_log_level(app: AppHandle, level: LogLevel) -> Result<(), String> {
-    let tauri_log_level: tauri_plugin_log::LogLevel = level.into();
-    let log_level: log::Level = tauri_log_level.into();
-    // Update the file log level atomic so the filter picks up the new level
-    crate::FILE_LOG_LEVEL.store(
-        log_level.to_level_filter() as u8,
-        std::sync::atomic::Ordering::Relaxed,
-    );
-
     let mut settings = get_settings(&app);
     settings.log_level = level;
-    write_settings(&app, settings);
+    write_settings(&app, settings)
+}
 
-    Ok(())
+/// Zip the recent logs, settings, system info, audio devices and permission
+/// states into `path` for a bug report, without secrets or transcripts
+#[specta::specta]
+#[tauri::command]
+pub async fn create_diagnostic_bundle(app: AppHandle, path: String) -> Result<(), String> {
+    crate::diagnostics::create_bundle(&app, std::path::Path::new(&path)).await
+}
+
+/// Time each stage of a dictation with every configured provider, on the
+/// WAV file at `sample_path` or on synthetic audio
+#[specta::specta]
+#[tauri::command]
+pub async fn run_benchmark(
+    app: AppHandle,
+    sample_path: Option<String>,
+) -> Result<crate::benchmark::BenchmarkReport, String> {
+    crate::benchmark::run(&app, sample_path).await
+}
+
+/// Panics and unrecoverable errors not dismissed yet, for the error dialog
+#[specta::specta]
+#[tauri::command]
+pub fn get_fatal_errors() -> Vec<crate::fatal::FatalError> {
+    crate::fatal::errors()
+}
+
+/// Open a GitHub issue prefilled with the details of the error with `id`
+#[specta::specta]
+#[tauri::command]
+pub fn report_fatal_error(app: AppHandle, id: u32) -> Result<(), String> {
+    crate::fatal::open_issue(&app, id)
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn dismiss_fatal_errors(app: AppHandle) {
+    crate::fatal::dismiss(&app);
+}
+
+/// Health of the input listener, microphone stream and control API, from
+/// the watchdog's last check
+#[specta::specta]
+#[tauri::command]
+pub fn get_health() -> crate::health::HealthStatus {
+    crate::health::status()
+}
+
+/// The displays, with the overlay corner picked for each
+#[specta::specta]
+#[tauri::command]
+pub fn get_displays(app: AppHandle) -> Result<Vec<crate::overlay::DisplayInfo>, String> {
+    crate::overlay::displays(&app)
+}
+
+/// The app keeping the shortcuts from Babbl with secure keyboard entry
+/// (macOS), if any
+#[specta::specta]
+#[tauri::command]
+pub fn get_secure_input() -> Option<crate::secure_input::SecureInput> {
+    crate::secure_input::current()
+}
+
+/// The settings section a `babbl://settings/...` link asked for before the
+/// window was ready, once
+#[specta::specta]
+#[tauri::command]
+pub fn take_requested_section() -> Option<String> {
+    crate::launch::take_requested_section()
+}
+
+/// Connect to OBS with the saved settings and show a test caption
+#[specta::specta]
+#[tauri::command]
+pub async fn test_obs_connection(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || crate::obs::test_connection(&app))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result)
+}
+
+/// Record and transcribe a meeting until it's stopped
+#[specta::specta]
+#[tauri::command]
+pub fn start_meeting_transcription(
+    app: AppHandle,
+) -> Result<crate::meeting::MeetingSession, String> {
+    crate::meeting::start(&app)
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn stop_meeting_transcription(app: AppHandle) -> Result<(), String> {
+    crate::meeting::stop(&app)
+}
+
+/// The meeting being transcribed, if any
+#[specta::specta]
+#[tauri::command]
+pub fn get_meeting_session() -> Option<crate::meeting::MeetingSession> {
+    crate::meeting::current()
+}
+
+/// Record the wake phrase once, until a pause after it; returns how many
+/// recordings of it there are
+#[specta::specta]
+#[tauri::command]
+pub async fn record_wake_word_sample(app: AppHandle) -> Result<u32, String> {
+    tauri::async_runtime::spawn_blocking(move || crate::wake_word::record_sample(&app))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result)
+        .map(|count| count as u32)
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn clear_wake_word_samples(app: AppHandle) -> Result<(), String> {
+    crate::wake_word::clear_samples(&app)
+}
+
+/// How many recordings of the wake phrase there are; it's listened for
+/// from three on
+#[specta::specta]
+#[tauri::command]
+pub fn get_wake_word_sample_count() -> u32 {
+    crate::wake_word::sample_count() as u32
+}
+
+/// Whether Babbl starts at login, and how
+#[specta::specta]
+#[tauri::command]
+pub fn get_autostart_status(app: AppHandle) -> crate::autostart::AutostartStatus {
+    crate::autostart::status(&app, &get_settings(&app))
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn get_service_status() -> crate::systemd::ServiceStatus {
+    crate::systemd::status()
+}
+
+/// Start Babbl from a systemd user service at login (Linux), in place of the
+/// autostart entry
+#[specta::specta]
+#[tauri::command]
+pub fn install_service(app: AppHandle) -> Result<crate::systemd::ServiceStatus, String> {
+    crate::systemd::install()?;
+    let mut settings = get_settings(&app);
+    if settings.autostart_enabled {
+        settings.autostart_enabled = false;
+        write_settings(&app, settings)?;
+    }
+    Ok(crate::systemd::status())
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn uninstall_service() -> Result<crate::systemd::ServiceStatus, String> {
+    crate::systemd::uninstall()?;
+    Ok(crate::systemd::status())
+}
+
+/// Check the selected update channel, downloading a new version in the
+/// background
+#[specta::specta]
+#[tauri::command]
+pub async fn check_for_update(
+    app: AppHandle,
+) -> Result<Option<crate::updater::UpdateInfo>, String> {
+    crate::updater::check(&app).await
+}
+
+#[specta::specta]
+#[tauri::command]
+pub fn get_pending_update() -> Option<crate::updater::UpdateInfo> {
+    crate::updater::pending()
+}
+
+/// Restart into the downloaded update, after the dictation underway
+#[specta::specta]
+#[tauri::command]
+pub fn restart_to_update(app: AppHandle) -> Result<(), String> {
+    crate::updater::restart_to_update(&app)
+}
+
+/// Log `module` at `level` in the log files, or at the global level again
+/// without one. `module` is one of Babbl's, like `input_hook`, or a crate.
+#[specta::specta]
+#[tauri::command]
+pub fn set_log_module_level(
+    app: AppHandle,
+    module: String,
+    level: Option<LogLevel>,
+) -> Result<(), String> {
+    if !crate::logging::is_module_name(&module) {
+        return Err(format!("'{}' isn't a module name", module));
+    }
+    let mut settings = get_settings(&app);
+    match level {
+        Some(level) => settings.log_module_levels.insert(module, level),
+        None => settings.log_module_levels.remove(&module),
+    };
+    write_settings(&app, settings)
 }
 
 #[specta::specta]
 #[tauri::command]
 pub fn open_recordings_folder(app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
+    let app_data_dir = crate::portable::app_data_dir(&app)
         .map_err(|e| format!("Failed to get app data directory: {}", e))?;
 
     let recordings_dir = app_data_dir.join("recordings");
@@ -87,9 +324,7 @@ pub fn open_recordings_folder(app: AppHandle) -> Result<(), String> {
 #[specta::specta]
 #[tauri::command]
 pub fn open_log_dir(app: AppHandle) -> Result<(), String> {
-    let log_dir = app
-        .path()
-        .app_log_dir()
+    let log_dir = crate::portable::app_log_dir(&app)
         .map_err(|e| format!("Failed to get log directory: {}", e))?;
 
     let path = log_dir.to_string_lossy().as_ref().to_string();
@@ -103,9 +338,7 @@ pub fn open_log_dir(app: AppHandle) -> Result<(), String> {
 #[specta::specta]
 #[tauri::command]
 pub fn open_app_data_dir(app: AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
+    let app_data_dir = crate::portable::app_data_dir(&app)
         .map_err(|e| format!("Failed to get app data directory: {}", e))?;
 
     let path = app_data_dir.to_string_lossy().as_ref().to_string();
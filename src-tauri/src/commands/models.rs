@@ -69,9 +69,7 @@ pub async fn set_active_model(
     // Update settings
     let mut settings = get_settings(&app_handle);
     settings.selected_model = model_id.clone();
-    write_settings(&app_handle, settings);
-
-    Ok(())
+    write_settings(&app_handle, settings)
 }
 
 #[tauri::command]
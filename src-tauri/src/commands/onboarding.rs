@@ -0,0 +1,305 @@
+use crate::audio_toolkit::list_input_devices;
+use crate::settings::{
+    get_settings, AppSettings, APPLE_INTELLIGENCE_PROVIDER_ID, LLAMA_CPP_PROVIDER_ID,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+
+/// Text typed by the injection test; the wizard checks that its focused field
+/// received it
+const INJECTION_TEST_TEXT: &str = "Babbl can type here";
+
+/// Something the app needs before dictation works
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityId {
+    MicrophonePermission,
+    /// Needed to paste or type into other apps (macOS)
+    AccessibilityPermission,
+    /// Needed for mouse button shortcuts (macOS)
+    InputMonitoringPermission,
+    AudioDevice,
+    /// Keys of the providers the settings use
+    ApiKey,
+    /// Whether pasting into a focused field works, see `run_injection_test`
+    Injection,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityStatus {
+    Ready,
+    NeedsAction,
+    /// Not needed on this platform or with these settings
+    NotRequired,
+    /// Can't be checked without the user, e.g. the injection test
+    Unknown,
+}
+
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct Capability {
+    pub id: CapabilityId,
+    pub status: CapabilityStatus,
+    /// What's wrong or what to do, ready to show
+    pub detail: Option<String>,
+    /// `request_capability` can prompt for it
+    pub can_request: bool,
+}
+
+impl Capability {
+    fn new(id: CapabilityId, status: CapabilityStatus) -> Self {
+        Self {
+            id,
+            status,
+            detail: None,
+            can_request: false,
+        }
+    }
+
+    fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    fn requestable(mut self) -> Self {
+        self.can_request = true;
+        self
+    }
+}
+
+fn status_of(granted: bool) -> CapabilityStatus {
+    if granted {
+        CapabilityStatus::Ready
+    } else {
+        CapabilityStatus::NeedsAction
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn microphone_capability() -> Capability {
+    use crate::microphone_permission::{self, MicrophoneAccess};
+
+    let access = microphone_permission::status();
+    let capability = Capability::new(
+        CapabilityId::MicrophonePermission,
+        status_of(access == MicrophoneAccess::Granted),
+    )
+    .requestable();
+    match access.problem() {
+        Some(problem) => capability.with_detail(problem),
+        None => capability,
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn permission_capabilities() -> Vec<Capability> {
+    use tauri_plugin_macos_permissions as permissions;
+
+    vec![
+        microphone_capability(),
+        Capability::new(
+            CapabilityId::AccessibilityPermission,
+            status_of(permissions::check_accessibility_permission().await),
+        )
+        .requestable(),
+        Capability::new(
+            CapabilityId::InputMonitoringPermission,
+            status_of(permissions::check_input_monitoring_permission().await),
+        )
+        .requestable(),
+    ]
+}
+
+#[cfg(not(target_os = "macos"))]
+async fn permission_capabilities() -> Vec<Capability> {
+    // Windows asks on first use and can't be queried; the settings page can
+    // still be opened
+    #[cfg(target_os = "windows")]
+    let microphone = Capability::new(
+        CapabilityId::MicrophonePermission,
+        CapabilityStatus::Unknown,
+    )
+    .requestable();
+    #[cfg(not(target_os = "windows"))]
+    let microphone = Capability::new(
+        CapabilityId::MicrophonePermission,
+        CapabilityStatus::NotRequired,
+    );
+
+    vec![
+        microphone,
+        Capability::new(
+            CapabilityId::AccessibilityPermission,
+            CapabilityStatus::NotRequired,
+        ),
+        Capability::new(
+            CapabilityId::InputMonitoringPermission,
+            CapabilityStatus::NotRequired,
+        ),
+    ]
+}
+
+fn audio_device_capability() -> Capability {
+    match list_input_devices() {
+        Ok(devices) if !devices.is_empty() => {
+            Capability::new(CapabilityId::AudioDevice, CapabilityStatus::Ready)
+        }
+        Ok(_) => Capability::new(CapabilityId::AudioDevice, CapabilityStatus::NeedsAction)
+            .with_detail("No microphone found. Connect one and check again."),
+        Err(e) => Capability::new(CapabilityId::AudioDevice, CapabilityStatus::NeedsAction)
+            .with_detail(format!("Failed to list microphones: {}", e)),
+    }
+}
+
+/// Provider that post-processing sends to, unless it runs on the device
+fn keyed_post_process_provider(settings: &AppSettings) -> Option<&str> {
+    let provider_id = settings.post_process_provider_id.as_str();
+    (settings.post_process_enabled
+        && provider_id != APPLE_INTELLIGENCE_PROVIDER_ID
+        && provider_id != LLAMA_CPP_PROVIDER_ID)
+        .then_some(provider_id)
+}
+
+fn has_key(keys: &HashMap<String, String>, provider_id: &str) -> bool {
+    keys.get(provider_id)
+        .is_some_and(|key| !key.trim().is_empty())
+}
+
+async fn api_key_capability(app: &AppHandle, validate: bool) -> Result<Capability, String> {
+    let settings = get_settings(app);
+    let post_process_provider = keyed_post_process_provider(&settings);
+    if !settings.use_online_provider && post_process_provider.is_none() {
+        return Ok(Capability::new(
+            CapabilityId::ApiKey,
+            CapabilityStatus::NotRequired,
+        ));
+    }
+
+    if settings.use_online_provider
+        && !has_key(
+            &settings.online_provider_api_keys,
+            &settings.online_provider_id,
+        )
+    {
+        return Ok(
+            Capability::new(CapabilityId::ApiKey, CapabilityStatus::NeedsAction).with_detail(
+                format!("Add an API key for '{}'", settings.online_provider_id),
+            ),
+        );
+    }
+
+    if let Some(provider_id) = post_process_provider {
+        // Only a request tells whether a stored key is accepted
+        if validate {
+            let check =
+                super::connection::check_llm_connection(app.clone(), provider_id.to_string(), None)
+                    .await?;
+            if !check.ok {
+                let capability =
+                    Capability::new(CapabilityId::ApiKey, CapabilityStatus::NeedsAction);
+                return Ok(match check.user_message {
+                    Some(message) => capability.with_detail(message),
+                    None => capability,
+                });
+            }
+        } else if !has_key(&settings.post_process_api_keys, provider_id) {
+            return Ok(
+                Capability::new(CapabilityId::ApiKey, CapabilityStatus::NeedsAction)
+                    .with_detail(format!("Add an API key for '{}'", provider_id)),
+            );
+        }
+    }
+
+    Ok(Capability::new(
+        CapabilityId::ApiKey,
+        CapabilityStatus::Ready,
+    ))
+}
+
+/// Status of everything dictation needs, for the first-run wizard. With
+/// `validate_api_key` the post-processing key is checked with a test request.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_setup_status(
+    app: AppHandle,
+    validate_api_key: bool,
+) -> Result<Vec<Capability>, String> {
+    let mut capabilities = permission_capabilities().await;
+    capabilities.push(audio_device_capability());
+    capabilities.push(api_key_capability(&app, validate_api_key).await?);
+    capabilities.push(Capability::new(
+        CapabilityId::Injection,
+        CapabilityStatus::Unknown,
+    ));
+    Ok(capabilities)
+}
+
+/// Show the OS prompt or settings page for a capability
+#[tauri::command]
+#[specta::specta]
+pub async fn request_capability(app: AppHandle, id: CapabilityId) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        use crate::microphone_permission::{self, MicrophoneAccess};
+        use tauri_plugin_macos_permissions as permissions;
+
+        match id {
+            // Once answered, the prompt doesn't show again
+            CapabilityId::MicrophonePermission => match microphone_permission::status() {
+                MicrophoneAccess::NotDetermined => microphone_permission::request(),
+                _ => microphone_permission::open_settings(&app)?,
+            },
+            CapabilityId::AccessibilityPermission => {
+                let _ = permissions::request_accessibility_permission().await;
+            }
+            CapabilityId::InputMonitoringPermission => {
+                let _ = permissions::request_input_monitoring_permission().await;
+            }
+            _ => return Err(format!("{:?} can't be requested", id)),
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        match id {
+            CapabilityId::MicrophonePermission => crate::microphone_permission::open_settings(&app),
+            _ => Err(format!("{:?} can't be requested", id)),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = app;
+        Err(format!("{:?} can't be requested on this platform", id))
+    }
+}
+
+/// Paste a known phrase with the configured paste method. The wizard focuses
+/// one of its own fields first and compares its content with the returned
+/// text.
+#[tauri::command]
+#[specta::specta]
+pub async fn run_injection_test(app: AppHandle) -> Result<String, String> {
+    let paste_method = get_settings(&app).paste_method;
+    let (result_tx, result_rx) = oneshot::channel();
+    let app_clone = app.clone();
+    app.run_on_main_thread(move || {
+        let result = crate::clipboard::paste_text(
+            INJECTION_TEST_TEXT.to_string(),
+            app_clone,
+            false,
+            paste_method,
+        );
+        let _ = result_tx.send(result);
+    })
+    .map_err(|e| format!("Failed to run the injection test: {}", e))?;
+
+    result_rx
+        .await
+        .map_err(|_| "The injection test didn't finish".to_string())??;
+    Ok(INJECTION_TEST_TEXT.to_string())
+}
@@ -0,0 +1,25 @@
+use crate::preview::{self, PendingPreview};
+use tauri::AppHandle;
+
+/// Text waiting in the preview window, for when the window opens after the
+/// `show-preview` event was sent
+#[tauri::command]
+#[specta::specta]
+pub fn get_pending_preview() -> Option<PendingPreview> {
+    preview::pending()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn accept_preview(app: AppHandle, id: u64, text: String) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return preview::discard(&app, id);
+    }
+    preview::accept(&app, id, text)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn discard_preview(app: AppHandle, id: u64) -> Result<(), String> {
+    preview::discard(&app, id)
+}
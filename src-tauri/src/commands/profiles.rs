@@ -0,0 +1,63 @@
+use crate::profiles;
+use crate::settings::{get_settings, write_settings, SettingsProfile};
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_profiles(app: AppHandle) -> Result<Vec<SettingsProfile>, String> {
+    let mut settings = get_settings(&app);
+    // Include edits made since the active profile was switched to
+    profiles::save_active(&mut settings);
+    Ok(settings.profiles)
+}
+
+/// Save the current shortcuts, prompts and providers as a profile, replacing
+/// the one with the same name. The saved profile becomes the active one.
+#[tauri::command]
+#[specta::specta]
+pub fn save_profile(app: AppHandle, name: String) -> Result<SettingsProfile, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+
+    let mut settings = get_settings(&app);
+    let existing = settings
+        .profiles
+        .iter()
+        .position(|profile| profile.name.eq_ignore_ascii_case(&name));
+    let id = match existing {
+        Some(index) => settings.profiles[index].id.clone(),
+        None => format!("profile_{}", chrono::Utc::now().timestamp_millis()),
+    };
+    let profile = profiles::capture(&settings, id, name);
+    match existing {
+        Some(index) => settings.profiles[index] = profile.clone(),
+        None => settings.profiles.push(profile.clone()),
+    }
+    settings.active_profile_id = Some(profile.id.clone());
+    write_settings(&app, settings)?;
+    Ok(profile)
+}
+
+/// Delete a profile; the current settings stay as they are
+#[tauri::command]
+#[specta::specta]
+pub fn delete_profile(app: AppHandle, id: String) -> Result<(), String> {
+    let mut settings = get_settings(&app);
+    let count = settings.profiles.len();
+    settings.profiles.retain(|profile| profile.id != id);
+    if settings.profiles.len() == count {
+        return Err(format!("Profile '{}' not found", id));
+    }
+    if settings.active_profile_id.as_deref() == Some(id.as_str()) {
+        settings.active_profile_id = None;
+    }
+    write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn switch_profile(app: AppHandle, id: String) -> Result<(), String> {
+    profiles::switch_profile(&app, &id)
+}
@@ -0,0 +1,52 @@
+use crate::settings::{get_settings, write_settings, Snippet};
+use crate::snippets::trigger_words;
+use tauri::AppHandle;
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_snippets(app: AppHandle) -> Result<Vec<Snippet>, String> {
+    Ok(get_settings(&app).snippets)
+}
+
+/// Add a snippet, or replace the one with the same trigger phrase
+#[tauri::command]
+#[specta::specta]
+pub fn set_snippet(app: AppHandle, trigger: String, text: String) -> Result<(), String> {
+    let words = trigger_words(&trigger);
+    if words.is_empty() {
+        return Err("Trigger phrase must contain a word".to_string());
+    }
+    if text.trim().is_empty() {
+        return Err("Snippet text must not be empty".to_string());
+    }
+
+    let mut settings = get_settings(&app);
+    let snippet = Snippet {
+        trigger: trigger.trim().to_string(),
+        text,
+    };
+    match settings
+        .snippets
+        .iter_mut()
+        .find(|existing| trigger_words(&existing.trigger) == words)
+    {
+        Some(existing) => *existing = snippet,
+        None => settings.snippets.push(snippet),
+    }
+    write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_snippet(app: AppHandle, trigger: String) -> Result<(), String> {
+    let words = trigger_words(&trigger);
+    let mut settings = get_settings(&app);
+    let count = settings.snippets.len();
+    settings
+        .snippets
+        .retain(|snippet| trigger_words(&snippet.trigger) != words);
+    if settings.snippets.len() == count {
+        return Err(format!("No snippet found for '{}'", trigger));
+    }
+    write_settings(&app, settings)
+}
@@ -1,5 +1,5 @@
 use crate::managers::transcription::TranscriptionManager;
-use crate::settings::{get_settings, write_settings, ModelUnloadTimeout};
+use crate::settings::{get_settings, write_settings, LowPowerMode, ModelUnloadTimeout, WarmUp};
 use serde::Serialize;
 use specta::Type;
 use tauri::{AppHandle, State};
@@ -12,10 +12,31 @@ pub struct ModelLoadStatus {
 
 #[tauri::command]
 #[specta::specta]
-pub fn set_model_unload_timeout(app: AppHandle, timeout: ModelUnloadTimeout) {
+pub fn set_model_unload_timeout(app: AppHandle, timeout: ModelUnloadTimeout) -> Result<(), String> {
     let mut settings = get_settings(&app);
     settings.model_unload_timeout = timeout;
-    write_settings(&app, settings);
+    write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_warm_up(app: AppHandle, warm_up: WarmUp) -> Result<(), String> {
+    let mut settings = get_settings(&app);
+    settings.warm_up = warm_up;
+    write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_low_power_mode(
+    app: AppHandle,
+    mode: LowPowerMode,
+    model: Option<String>,
+) -> Result<(), String> {
+    let mut settings = get_settings(&app);
+    settings.low_power_mode = mode;
+    settings.low_power_model = model.filter(|model| !model.is_empty());
+    write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -0,0 +1,66 @@
+use crate::api_keys::{self, ApiKeyUsage};
+use crate::managers::usage::{CostStats, UsageManager, UsageStats};
+use crate::settings::{get_settings, write_settings, ModelPrice};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_usage_stats(
+    usage_manager: State<'_, Arc<UsageManager>>,
+    days: Option<u32>,
+) -> Result<UsageStats, String> {
+    usage_manager.get_stats(days).map_err(|e| e.to_string())
+}
+
+/// Requests, failures and cooldowns per API key since the app started
+#[tauri::command]
+#[specta::specta]
+pub fn get_api_key_usage(provider_id: Option<String>) -> Vec<ApiKeyUsage> {
+    api_keys::key_usage(provider_id.as_deref())
+}
+
+/// Estimated spend per day, week and provider for the last `days` days
+#[tauri::command]
+#[specta::specta]
+pub fn get_cost_stats(
+    app: AppHandle,
+    usage_manager: State<'_, Arc<UsageManager>>,
+    days: Option<u32>,
+) -> Result<CostStats, String> {
+    let settings = get_settings(&app);
+    usage_manager
+        .get_cost_stats(
+            days,
+            &settings.custom_model_prices,
+            settings.monthly_llm_budget_usd,
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Set the price of a model missing from the built-in table; `None` removes it
+#[tauri::command]
+#[specta::specta]
+pub fn set_custom_model_price(
+    app: AppHandle,
+    model: String,
+    price: Option<ModelPrice>,
+) -> Result<(), String> {
+    if model.trim().is_empty() {
+        return Err("Model must not be empty".to_string());
+    }
+
+    let mut settings = get_settings(&app);
+    match price {
+        Some(price) => {
+            if price.input_per_million < 0.0 || price.output_per_million < 0.0 {
+                return Err("Prices must not be negative".to_string());
+            }
+            settings.custom_model_prices.insert(model, price);
+        }
+        None => {
+            settings.custom_model_prices.remove(&model);
+        }
+    }
+    write_settings(&app, settings)
+}
@@ -0,0 +1,8 @@
+use tauri::AppHandle;
+
+/// The widget's record button
+#[tauri::command]
+#[specta::specta]
+pub fn toggle_widget_recording(app: AppHandle) -> Result<(), String> {
+    crate::widget::toggle_recording(&app)
+}
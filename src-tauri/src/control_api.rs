@@ -0,0 +1,437 @@
+//! Optional REST API on 127.0.0.1 so scripts and home-automation setups can
+//! drive Babbl. Every request needs `Authorization: Bearer <token>` with the
+//! token from the settings.
+//!
+//! - `GET /v1/status`: pipeline state and the memory the recording takes
+//! - `POST /v1/start`, `POST /v1/stop`: dictate with `{"binding_id": ...}`,
+//!   the transcribe action without a body
+//! - `POST /v1/cancel`
+//! - `POST /v1/transcribe`: transcribe the WAV file at `{"path": ...}`
+//! - `GET /v1/transcripts/last`, `POST /v1/transcripts/last/copy`
+//! - `GET /v1/settings` with the secrets blanked, `PATCH /v1/settings` with
+//!   the fields to change
+
+use crate::actions;
+use crate::audio_toolkit::read_wav_file;
+use crate::commands::history::{copy_last_transcript, get_last_transcript};
+use crate::diagnostics;
+use crate::managers::audio::AudioRecordingManager;
+use crate::managers::transcription::TranscriptionManager;
+use crate::pipeline;
+use crate::settings::{self, AppSettings};
+use crate::settings_validation::SettingsError;
+use crate::utils::cancel_current_operation;
+use crate::ManagedToggleState;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const TOKEN_LENGTH: usize = 32;
+
+/// Larger request bodies are cut off, settings changes are far smaller
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+static SERVER: Lazy<Mutex<Option<Arc<Server>>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    Status,
+    Start,
+    Stop,
+    Cancel,
+    Transcribe,
+    LastTranscript,
+    CopyLastTranscript,
+    GetSettings,
+    UpdateSettings,
+}
+
+#[derive(Deserialize, Default)]
+struct RecordingRequest {
+    #[serde(default)]
+    binding_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TranscribeRequest {
+    /// WAV file on this machine
+    path: String,
+}
+
+type ApiResult = Result<Value, (u16, String)>;
+
+fn route(method: &Method, url: &str) -> Option<Route> {
+    let path = url
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    match (method, path) {
+        (Method::Get, "/v1/status") => Some(Route::Status),
+        (Method::Post, "/v1/start") => Some(Route::Start),
+        (Method::Post, "/v1/stop") => Some(Route::Stop),
+        (Method::Post, "/v1/cancel") => Some(Route::Cancel),
+        (Method::Post, "/v1/transcribe") => Some(Route::Transcribe),
+        (Method::Get, "/v1/transcripts/last") => Some(Route::LastTranscript),
+        (Method::Post, "/v1/transcripts/last/copy") => Some(Route::CopyLastTranscript),
+        (Method::Get, "/v1/settings") => Some(Route::GetSettings),
+        (Method::Patch, "/v1/settings") => Some(Route::UpdateSettings),
+        _ => None,
+    }
+}
+
+/// Whether `given` is `token`, compared in constant time
+pub fn token_matches(given: &str, token: &str) -> bool {
+    !token.is_empty()
+        && given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Whether an Authorization header carries `token`
+fn is_authorized(header: Option<&str>, token: &str) -> bool {
+    header
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .is_some_and(|given| token_matches(given.trim(), token))
+}
+
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Settings that run code, decide where requests and transcripts are sent,
+/// or hold secrets; a token for the API isn't enough to change them
+const APP_ONLY_SETTINGS: &[&str] = &[
+    "action_configs",
+    "allow_link_actions",
+    "hook_script_path",
+    "proxy_url",
+    "custom_ca_path",
+    "post_process_providers",
+    "mqtt_host",
+];
+
+fn is_app_only(field: &str) -> bool {
+    field.starts_with("control_api_")
+        || APP_ONLY_SETTINGS.contains(&field)
+        || diagnostics::is_secret_field(field)
+}
+
+/// Settings as JSON with the secrets blanked, as in a diagnostic bundle
+fn redacted(settings: &AppSettings) -> Value {
+    let mut value = serde_json::to_value(settings).unwrap_or_default();
+    diagnostics::redact_secrets(&mut value);
+    value
+}
+
+/// `current` with the top-level fields of `changes` replaced. The API's own
+/// settings and the ones in [`APP_ONLY_SETTINGS`] can only be changed in the
+/// app.
+fn merge_settings(current: &AppSettings, changes: &Value) -> Result<AppSettings, String> {
+    let changes = changes
+        .as_object()
+        .ok_or_else(|| "Expected a JSON object of settings".to_string())?;
+    let mut merged = match serde_json::to_value(current) {
+        Ok(Value::Object(merged)) => merged,
+        _ => return Err("Failed to read the current settings".to_string()),
+    };
+    for (field, value) in changes {
+        if is_app_only(field) {
+            return Err(format!("'{}' can only be changed in the app", field));
+        }
+        if !merged.contains_key(field) {
+            return Err(format!("Unknown setting '{}'", field));
+        }
+        merged.insert(field.clone(), value.clone());
+    }
+    serde_json::from_value(Value::Object(merged)).map_err(|e| e.to_string())
+}
+
+/// Start or stop a dictation with `binding_id` like its shortcut in toggle
+/// mode; Ok(false) when it already was in that state
+pub fn set_recording(app: &AppHandle, binding_id: &str, active: bool) -> Result<bool, String> {
+    let action = actions::action_for(binding_id)
+        .ok_or_else(|| format!("No action defined for '{}'", binding_id))?;
+
+    let toggle_state_manager = app.state::<ManagedToggleState>();
+    let mut states = toggle_state_manager
+        .lock()
+        .map_err(|e| format!("Failed to lock toggle state manager: {}", e))?;
+    let is_currently_active = states
+        .active_toggles
+        .entry(binding_id.to_string())
+        .or_insert(false);
+
+    if *is_currently_active == active {
+        return Ok(false);
+    }
+    if active {
+        if !actions::admit(app, binding_id) {
+            return Err(format!("'{}' is still running", binding_id));
+        }
+        if pipeline::is_busy() {
+            return Err("Another dictation is running".to_string());
+        }
+        action.start(app, binding_id, "control_api");
+    } else {
+        action.stop(app, binding_id, "control_api");
+    }
+    *is_currently_active = active;
+    Ok(true)
+}
+
+fn dispatch(app: &AppHandle, settings: &AppSettings, route: Route, body: &str) -> ApiResult {
+    let bad_request = |e: String| (400, e);
+    match route {
+        Route::Status => {
+            let buffer = app.state::<Arc<AudioRecordingManager>>().buffer_usage();
+            Ok(json!({
+                "state": pipeline::current(),
+                "busy": pipeline::is_busy(),
+                "version": app.package_info().version.to_string(),
+                "recording_buffer": {
+                    "memory_bytes": buffer.memory_bytes,
+                    "spilled_bytes": buffer.spilled_bytes,
+                    "limit_bytes": settings.recording_memory_limit_mb as usize * 1024 * 1024,
+                },
+            }))
+        }
+        Route::Start | Route::Stop => {
+            let request: RecordingRequest = if body.trim().is_empty() {
+                RecordingRequest::default()
+            } else {
+                serde_json::from_str(body).map_err(|e| bad_request(e.to_string()))?
+            };
+            let binding_id = request
+                .binding_id
+                .unwrap_or_else(|| "transcribe".to_string());
+            if !settings.bindings.contains_key(&binding_id) {
+                return Err((404, format!("No action '{}'", binding_id)));
+            }
+            let changed =
+                set_recording(app, &binding_id, route == Route::Start).map_err(|e| (409, e))?;
+            Ok(json!({ "binding_id": binding_id, "changed": changed }))
+        }
+        Route::Cancel => {
+            cancel_current_operation(app);
+            Ok(json!({}))
+        }
+        Route::Transcribe => {
+            let request: TranscribeRequest =
+                serde_json::from_str(body).map_err(|e| bad_request(e.to_string()))?;
+            let samples = read_wav_file(&request.path)
+                .map_err(|e| bad_request(format!("Failed to read '{}': {}", request.path, e)))?;
+            let tm = app.state::<Arc<TranscriptionManager>>();
+            let text =
+                tauri::async_runtime::block_on(actions::transcribe_audio(settings, &tm, samples))
+                    .map_err(|e| (500, e.to_string()))?;
+            Ok(json!({ "text": text }))
+        }
+        Route::LastTranscript => {
+            let text = tauri::async_runtime::block_on(get_last_transcript(app.state()))
+                .map_err(|e| (500, e))?;
+            Ok(json!({ "text": text }))
+        }
+        Route::CopyLastTranscript => {
+            tauri::async_runtime::block_on(copy_last_transcript(app.clone(), app.state()))
+                .map_err(|e| (404, e))?;
+            Ok(json!({}))
+        }
+        Route::GetSettings => Ok(redacted(settings)),
+        Route::UpdateSettings => {
+            let changes: Value =
+                serde_json::from_str(body).map_err(|e| bad_request(e.to_string()))?;
+            let next = merge_settings(settings, &changes).map_err(bad_request)?;
+            settings::write_settings(app, next).map_err(bad_request)?;
+            Ok(redacted(&settings::get_settings(app)))
+        }
+    }
+}
+
+fn respond(request: Request, status: u16, body: Value) {
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("Content-Type header is valid");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        debug!("Failed to answer control API request: {}", e);
+    }
+}
+
+fn handle(app: &AppHandle, mut request: Request) {
+    let settings = settings::get_settings(app);
+    let token = settings.control_api_token.as_deref().unwrap_or_default();
+    let authorization = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .map(|header| header.value.as_str());
+    if !is_authorized(authorization, token) {
+        return respond(request, 401, json!({ "error": "Missing or wrong token" }));
+    }
+
+    let route = match route(request.method(), request.url()) {
+        Some(route) => route,
+        None => return respond(request, 404, json!({ "error": "Not found" })),
+    };
+    let mut body = String::new();
+    if let Err(e) = request
+        .as_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_string(&mut body)
+    {
+        return respond(request, 400, json!({ "error": e.to_string() }));
+    }
+
+    debug!("Control API request: {:?}", route);
+    match dispatch(app, &settings, route, &body) {
+        Ok(value) => respond(request, 200, value),
+        Err((status, error)) => respond(request, status, json!({ "error": error })),
+    }
+}
+
+fn stop() {
+    if let Some(server) = SERVER.lock().unwrap().take() {
+        server.unblock();
+        info!("Control API stopped");
+    }
+}
+
+fn start(app: &AppHandle, port: u16) -> Result<(), String> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to start control API on port {}: {}", port, e))?;
+    let server = Arc::new(server);
+    *SERVER.lock().unwrap() = Some(Arc::clone(&server));
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // Ends once `stop` unblocks the server. Each request gets a thread, a
+        // file transcription mustn't hold up a stop.
+        for request in server.incoming_requests() {
+            let app = app.clone();
+            std::thread::spawn(move || handle(&app, request));
+        }
+    });
+    info!("Control API listening on 127.0.0.1:{}", port);
+    Ok(())
+}
+
+/// Whether the server is listening
+pub fn is_running() -> bool {
+    SERVER.lock().unwrap().is_some()
+}
+
+/// Create the token on first use; the WebSocket server shares it
+pub fn ensure_token(app: &AppHandle) {
+    let mut settings = settings::get_settings(app);
+    if settings.control_api_token.is_none() {
+        settings.control_api_token = Some(generate_token());
+        if let Err(e) = settings::write_settings(app, settings) {
+            warn!("Failed to save the control API token: {}", e);
+        }
+    }
+}
+
+/// Start or stop the server per the settings
+pub fn sync(app: &AppHandle) -> Result<(), String> {
+    stop();
+    let settings = settings::get_settings(app);
+    if !settings.control_api_enabled {
+        return Ok(());
+    }
+    ensure_token(app);
+    start(app, settings.control_api_port)
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.control_api_enabled == next.control_api_enabled
+        && previous.control_api_port == next.control_api_port
+    {
+        return Vec::new();
+    }
+    match sync(app) {
+        Ok(()) => Vec::new(),
+        Err(e) => {
+            warn!("{}", e);
+            vec![SettingsError::new("control_api_port", e)]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route(&Method::Get, "/v1/status"), Some(Route::Status));
+        assert_eq!(route(&Method::Post, "/v1/start/"), Some(Route::Start));
+        assert_eq!(
+            route(&Method::Patch, "/v1/settings?x=1"),
+            Some(Route::UpdateSettings)
+        );
+        assert_eq!(
+            route(&Method::Post, "/v1/transcripts/last/copy"),
+            Some(Route::CopyLastTranscript)
+        );
+        assert_eq!(route(&Method::Get, "/v1/start"), None);
+        assert_eq!(route(&Method::Get, "/status"), None);
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized(Some("Bearer abc123"), "abc123"));
+        assert!(!is_authorized(Some("Bearer abc124"), "abc123"));
+        assert!(!is_authorized(Some("Bearer abc"), "abc123"));
+        assert!(!is_authorized(Some("abc123"), "abc123"));
+        assert!(!is_authorized(None, "abc123"));
+        assert!(!is_authorized(Some("Bearer "), ""));
+    }
+
+    #[test]
+    fn test_merge_settings() {
+        let current = settings::get_default_settings();
+        let merged = merge_settings(&current, &json!({ "smart_spacing": true })).unwrap();
+        assert!(merged.smart_spacing);
+
+        assert!(merge_settings(&current, &json!({ "no_such_setting": 1 })).is_err());
+        assert!(merge_settings(&current, &json!({ "smart_spacing": "yes" })).is_err());
+        assert!(merge_settings(&current, &json!({ "control_api_port": 8080 })).is_err());
+        assert!(merge_settings(&current, &json!([1, 2])).is_err());
+        for field in ["action_configs", "hook_script_path", "proxy_url"] {
+            assert!(merge_settings(&current, &json!({ field: null })).is_err());
+        }
+        assert!(merge_settings(&current, &json!({ "post_process_api_keys": {} })).is_err());
+    }
+
+    #[test]
+    fn test_redacted() {
+        let mut current = settings::get_default_settings();
+        current.control_api_token = Some("abc123".to_string());
+        current
+            .post_process_api_keys
+            .insert("openai".to_string(), "sk-secret".to_string());
+        let value = redacted(&current).to_string();
+        assert!(!value.contains("abc123"));
+        assert!(!value.contains("sk-secret"));
+    }
+}
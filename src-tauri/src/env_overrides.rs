@@ -0,0 +1,221 @@
+use log::info;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const PREFIX: &str = "BABBL_";
+
+/// `BABBL_*` variables, read once at startup
+static ENV: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    let vars: HashMap<String, String> = std::env::vars()
+        .filter(|(name, value)| name.starts_with(PREFIX) && !value.is_empty())
+        .collect();
+    if !vars.is_empty() {
+        let mut names: Vec<&str> = vars.keys().map(String::as_str).collect();
+        names.sort();
+        info!(
+            "Settings overridden from the environment: {}",
+            names.join(", ")
+        );
+    }
+    vars
+});
+
+/// Variable name part for a provider id: "openai" -> "OPENAI"
+fn env_name(provider_id: &str) -> String {
+    provider_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// JSON pointer from path segments, escaped per RFC 6901
+fn pointer(segments: &[&str]) -> String {
+    segments
+        .iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Settings fields the environment overrides, as JSON pointers into the
+/// stored settings and their values:
+///
+/// - `BABBL_<PROVIDER>_API_KEY`: key of a post-processing or online transcription provider
+/// - `BABBL_<PROVIDER>_BASE_URL`: endpoint of a post-processing provider
+/// - `BABBL_LLM_PROVIDER`, `BABBL_LLM_MODEL`, `BABBL_LLM_API_KEY`, `BABBL_LLM_BASE_URL`:
+///   the active post-processing provider
+/// - `BABBL_STT_PROVIDER`, `BABBL_STT_MODEL`, `BABBL_STT_API_KEY`: the online
+///   transcription provider
+fn overrides(settings: &Value, env: &HashMap<String, String>) -> Vec<(String, String)> {
+    let var = |name: &str| env.get(&format!("{}{}", PREFIX, name)).cloned();
+    let mut overrides = Vec::new();
+
+    let llm_provider = var("LLM_PROVIDER").or_else(|| {
+        settings["post_process_provider_id"]
+            .as_str()
+            .map(str::to_string)
+    });
+    if let Some(provider) = var("LLM_PROVIDER") {
+        overrides.push((pointer(&["post_process_provider_id"]), provider));
+    }
+
+    let providers = settings["post_process_providers"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    for (index, provider) in providers.iter().enumerate() {
+        let id = match provider["id"].as_str() {
+            Some(id) => id,
+            None => continue,
+        };
+        let is_active = llm_provider.as_deref() == Some(id);
+        let index = index.to_string();
+        let api_key = var(&format!("{}_API_KEY", env_name(id)))
+            .or_else(|| is_active.then(|| var("LLM_API_KEY")).flatten());
+        if let Some(api_key) = api_key {
+            overrides.push((pointer(&["post_process_api_keys", id]), api_key));
+        }
+        let base_url = var(&format!("{}_BASE_URL", env_name(id)))
+            .or_else(|| is_active.then(|| var("LLM_BASE_URL")).flatten());
+        if let Some(base_url) = base_url {
+            overrides.push((
+                pointer(&["post_process_providers", &index, "base_url"]),
+                base_url,
+            ));
+        }
+    }
+    if let (Some(provider), Some(model)) = (&llm_provider, var("LLM_MODEL")) {
+        overrides.push((pointer(&["post_process_models", provider]), model));
+    }
+
+    let stt_provider =
+        var("STT_PROVIDER").or_else(|| settings["online_provider_id"].as_str().map(str::to_string));
+    if let Some(provider) = var("STT_PROVIDER") {
+        overrides.push((pointer(&["online_provider_id"]), provider));
+    }
+    if let Some(provider) = &stt_provider {
+        let api_key =
+            var(&format!("{}_API_KEY", env_name(provider))).or_else(|| var("STT_API_KEY"));
+        if let Some(api_key) = api_key {
+            overrides.push((pointer(&["online_provider_api_keys", provider]), api_key));
+        }
+        if let Some(model) = var("STT_MODEL") {
+            overrides.push((pointer(&["online_provider_models", provider]), model));
+        }
+    }
+    overrides
+}
+
+/// Set `value` at `pointer`, creating it when the parent object exists
+fn set_pointer(settings: &mut Value, pointer: &str, value: Value) {
+    if let Some(target) = settings.pointer_mut(pointer) {
+        *target = value;
+        return;
+    }
+    let (parent, key) = match pointer.rsplit_once('/') {
+        Some(split) => split,
+        None => return,
+    };
+    if let Some(Value::Object(parent)) = settings.pointer_mut(parent) {
+        let key = key.replace("~1", "/").replace("~0", "~");
+        parent.insert(key, value);
+    }
+}
+
+fn remove_pointer(settings: &mut Value, pointer: &str) {
+    let (parent, key) = match pointer.rsplit_once('/') {
+        Some(split) => split,
+        None => return,
+    };
+    if let Some(Value::Object(parent)) = settings.pointer_mut(parent) {
+        parent.remove(&key.replace("~1", "/").replace("~0", "~"));
+    }
+}
+
+pub(crate) fn apply_with(settings: &mut Value, env: &HashMap<String, String>) {
+    for (pointer, value) in overrides(settings, env) {
+        set_pointer(settings, &pointer, Value::String(value));
+    }
+}
+
+fn restore_with(settings: &mut Value, stored: &Value, env: &HashMap<String, String>) {
+    for (pointer, _) in overrides(settings, env) {
+        match stored.pointer(&pointer) {
+            Some(original) => set_pointer(settings, &pointer, original.clone()),
+            None => remove_pointer(settings, &pointer),
+        }
+    }
+}
+
+/// Apply the environment overrides to stored settings that were just read
+pub fn apply(settings: &mut Value) {
+    if !ENV.is_empty() {
+        apply_with(settings, &ENV);
+    }
+}
+
+/// Put the `stored` values back into overridden fields of settings about to
+/// be written, so values from the environment never reach the disk. Nothing
+/// was overridden when nothing is stored yet.
+pub fn restore(settings: &mut Value, stored: Option<&Value>) {
+    if let (false, Some(stored)) = (ENV.is_empty(), stored) {
+        restore_with(settings, stored, &ENV);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_and_restore() {
+        let env: HashMap<String, String> = [
+            ("BABBL_OPENAI_API_KEY", "sk-env"),
+            ("BABBL_LLM_PROVIDER", "custom"),
+            ("BABBL_LLM_BASE_URL", "http://localhost:8080/v1"),
+            ("BABBL_LLM_MODEL", "local-model"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let stored = json!({
+            "post_process_provider_id": "openai",
+            "post_process_providers": [
+                { "id": "openai", "base_url": "https://api.openai.com/v1" },
+                { "id": "custom", "base_url": "http://localhost:11434/v1" }
+            ],
+            "post_process_api_keys": { "openai": "sk-stored" },
+            "post_process_models": {},
+            "online_provider_id": "groq",
+            "online_provider_api_keys": {}
+        });
+
+        let mut settings = stored.clone();
+        apply_with(&mut settings, &env);
+        assert_eq!(settings["post_process_provider_id"], "custom");
+        assert_eq!(settings["post_process_api_keys"]["openai"], "sk-env");
+        assert_eq!(
+            settings["post_process_providers"][1]["base_url"],
+            "http://localhost:8080/v1"
+        );
+        assert_eq!(
+            settings["post_process_providers"][0]["base_url"],
+            "https://api.openai.com/v1"
+        );
+        assert_eq!(settings["post_process_models"]["custom"], "local-model");
+
+        // A setting changed in the app is kept, overridden ones aren't written
+        settings["online_provider_id"] = json!("openai");
+        restore_with(&mut settings, &stored, &env);
+        let mut expected = stored.clone();
+        expected["online_provider_id"] = json!("openai");
+        assert_eq!(settings, expected);
+    }
+}
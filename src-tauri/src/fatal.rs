@@ -0,0 +1,263 @@
+//! Panics and unrecoverable errors, surfaced to the user. A panic on any
+//! thread, or an error reported with `report`, goes through a channel to a
+//! thread that fails the running dictation, flags the tray and shows the
+//! window with the details and a way to report it, rather than leaving a
+//! dead thread behind an app that looks fine.
+
+use crate::pipeline;
+use crate::show_main_window;
+use log::error;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::any::Any;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use tauri::tray::TrayIcon;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_opener::OpenerExt;
+
+const ISSUES_URL: &str = "https://github.com/avijitbhuin21/Babbl/issues/new";
+
+/// The thread surfacing errors, whose own panics aren't sent back to it
+const DISPATCHER: &str = "fatal-errors";
+
+/// Errors kept for the frontend
+const MAX_ERRORS: usize = 20;
+
+/// Backtrace characters put in an issue, whose URL has to stay short
+const MAX_ISSUE_BACKTRACE: usize = 4000;
+
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum FatalErrorKind {
+    Panic,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct FatalError {
+    pub id: u32,
+    pub kind: FatalErrorKind,
+    pub message: String,
+    /// Source location of a panic
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: Option<String>,
+    pub run_id: Option<String>,
+    /// In milliseconds since the epoch
+    pub occurred_at: i64,
+}
+
+struct Channel {
+    sender: Mutex<Sender<FatalError>>,
+    receiver: Mutex<Option<Receiver<FatalError>>>,
+}
+
+static CHANNEL: Lazy<Channel> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel();
+    Channel {
+        sender: Mutex::new(sender),
+        receiver: Mutex::new(Some(receiver)),
+    }
+});
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+static ERRORS: Lazy<Mutex<Vec<FatalError>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn new_error(kind: FatalErrorKind, message: String, location: Option<String>) -> FatalError {
+    FatalError {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        kind,
+        message,
+        location,
+        thread: std::thread::current().name().map(str::to_string),
+        backtrace: None,
+        // Not waiting on the pipeline, whose lock the panic may have held
+        run_id: None,
+        occurred_at: chrono::Utc::now().timestamp_millis(),
+    }
+}
+
+fn send(error: FatalError) {
+    // Never panic here, it may run inside the panic hook
+    if let Ok(sender) = CHANNEL.sender.lock() {
+        let _ = sender.send(error);
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic".to_string())
+}
+
+/// Catch panics on every thread; errors are queued until `init`
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()));
+        let mut fatal = new_error(
+            FatalErrorKind::Panic,
+            panic_message(info.payload()),
+            location,
+        );
+        error!(
+            "Panic on thread {}: {} at {}",
+            fatal.thread.as_deref().unwrap_or("<unnamed>"),
+            fatal.message,
+            fatal.location.as_deref().unwrap_or("unknown location")
+        );
+        if fatal.thread.as_deref() == Some(DISPATCHER) {
+            return;
+        }
+        fatal.backtrace = Some(std::backtrace::Backtrace::force_capture().to_string());
+        send(fatal);
+    }));
+}
+
+/// Surface an error Babbl can't recover from, like a worker thread stopping
+pub fn report(message: &str) {
+    error!("Unrecoverable error: {}", message);
+    let mut fatal = new_error(FatalErrorKind::Error, message.to_string(), None);
+    fatal.run_id = pipeline::run_id();
+    send(fatal);
+}
+
+fn set_tray_tooltip(app: &AppHandle, tooltip: Option<&str>) {
+    if let Some(tray) = app.try_state::<TrayIcon>() {
+        if let Err(e) = tray.set_tooltip(tooltip) {
+            log::warn!("Failed to set the tray tooltip: {}", e);
+        }
+    }
+}
+
+fn surface(app: &AppHandle, fatal: FatalError) {
+    {
+        let mut errors = ERRORS.lock().unwrap();
+        errors.push(fatal.clone());
+        let excess = errors.len().saturating_sub(MAX_ERRORS);
+        errors.drain(..excess);
+    }
+    // Whatever was running likely died with it
+    if pipeline::is_busy() {
+        pipeline::fail(app, &format!("Internal error: {}", fatal.message));
+    }
+    set_tray_tooltip(app, Some("Babbl ran into an error"));
+    show_main_window(app);
+    if let Err(e) = app.emit("fatal-error", fatal) {
+        log::warn!("Failed to emit fatal error: {}", e);
+    }
+}
+
+/// Start surfacing errors, including any queued before now
+pub fn init(app: &AppHandle) {
+    let receiver = match CHANNEL.receiver.lock().unwrap().take() {
+        Some(receiver) => receiver,
+        None => return,
+    };
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name(DISPATCHER.to_string())
+        .spawn(move || {
+            for fatal in receiver {
+                // A panic surfacing one error mustn't stop the next ones
+                let _ =
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| surface(&app, fatal)));
+            }
+        });
+    if let Err(e) = spawned {
+        error!("Failed to start surfacing errors: {}", e);
+    }
+}
+
+/// Errors since the last dismissal, oldest first
+pub fn errors() -> Vec<FatalError> {
+    ERRORS.lock().unwrap().clone()
+}
+
+pub fn dismiss(app: &AppHandle) {
+    ERRORS.lock().unwrap().clear();
+    set_tray_tooltip(app, None);
+}
+
+/// New-issue link prefilled with `fatal`
+fn issue_url(fatal: &FatalError, version: &str, os: &str) -> Url {
+    let title = format!(
+        "Crash: {}",
+        fatal.message.lines().next().unwrap_or_default()
+    );
+    let mut body = format!(
+        "**What were you doing when it happened?**\n\n\n\n\
+         **Details**\n\n- Version: {}\n- OS: {}\n- Kind: {:?}\n",
+        version, os, fatal.kind
+    );
+    if let Some(location) = &fatal.location {
+        body.push_str(&format!("- Location: `{}`\n", location));
+    }
+    if let Some(thread) = &fatal.thread {
+        body.push_str(&format!("- Thread: `{}`\n", thread));
+    }
+    body.push_str(&format!("\n```\n{}\n```\n", fatal.message));
+    if let Some(backtrace) = &fatal.backtrace {
+        let backtrace: String = backtrace.chars().take(MAX_ISSUE_BACKTRACE).collect();
+        body.push_str(&format!(
+            "\n<details><summary>Backtrace</summary>\n\n```\n{}\n```\n</details>\n",
+            backtrace
+        ));
+    }
+    body.push_str("\nA diagnostic bundle, from the debug settings, helps too.\n");
+
+    let mut url = Url::parse(ISSUES_URL).expect("valid issues URL");
+    url.query_pairs_mut()
+        .append_pair("title", &title)
+        .append_pair("body", &body);
+    url
+}
+
+/// Open a prefilled issue for the error with `id`
+pub fn open_issue(app: &AppHandle, id: u32) -> Result<(), String> {
+    let fatal = errors()
+        .into_iter()
+        .find(|fatal| fatal.id == id)
+        .ok_or_else(|| format!("No error with id {}", id))?;
+    let os = format!(
+        "{} {} ({})",
+        tauri_plugin_os::platform(),
+        tauri_plugin_os::version(),
+        tauri_plugin_os::arch()
+    );
+    let url = issue_url(&fatal, &app.package_info().version.to_string(), &os);
+    app.opener()
+        .open_url(url.as_str(), None::<String>)
+        .map_err(|e| format!("Failed to open the issue page: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_url() {
+        let mut fatal = new_error(
+            FatalErrorKind::Panic,
+            "index out of bounds\nsecond line".to_string(),
+            Some("src/managers/audio.rs:42".to_string()),
+        );
+        fatal.backtrace = Some("x".repeat(MAX_ISSUE_BACKTRACE * 2));
+        let url = issue_url(&fatal, "1.2.3", "linux 6.1 (x86_64)");
+        let query: std::collections::HashMap<_, _> = url.query_pairs().collect();
+        assert_eq!(query["title"], "Crash: index out of bounds");
+        assert!(query["body"].contains("- Version: 1.2.3"));
+        assert!(query["body"].contains("`src/managers/audio.rs:42`"));
+        assert!(query["body"].contains(&"x".repeat(MAX_ISSUE_BACKTRACE)));
+        assert!(!query["body"].contains(&"x".repeat(MAX_ISSUE_BACKTRACE + 1)));
+    }
+}
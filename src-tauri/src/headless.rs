@@ -0,0 +1,54 @@
+//! Headless mode, for low-RAM machines and setups driven by the settings
+//! file and the command line. Started with `--headless`, Babbl runs the
+//! tray, the shortcuts and the local API without loading the main window;
+//! it's only created when asked for, like from the tray, and destroyed again
+//! once closed.
+
+use log::{debug, error};
+use once_cell::sync::Lazy;
+use tauri::{AppHandle, Manager, WebviewWindow, WebviewWindowBuilder};
+
+/// Command line flag that turns on headless mode
+pub const HEADLESS_ARG: &str = "--headless";
+
+pub const MAIN_WINDOW_LABEL: &str = "main";
+
+static HEADLESS: Lazy<bool> = Lazy::new(|| std::env::args().any(|arg| arg == HEADLESS_ARG));
+
+pub fn is_headless() -> bool {
+    *HEADLESS
+}
+
+fn create_main_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|window| window.label == MAIN_WINDOW_LABEL)
+        .ok_or_else(|| "The main window isn't configured".to_string())?;
+    let window = WebviewWindowBuilder::from_config(app, config)
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to create the main window: {}", e))?;
+    debug!("Created the main window");
+    Ok(window)
+}
+
+/// The main window, created when it isn't there yet
+pub fn main_window(app: &AppHandle) -> Option<WebviewWindow> {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) {
+        return Some(window);
+    }
+    match create_main_window(app) {
+        Ok(window) => Some(window),
+        Err(e) => {
+            error!("{}", e);
+            None
+        }
+    }
+}
+
+/// Whether closing `label` should destroy the window instead of hiding it
+pub fn destroys_on_close(label: &str) -> bool {
+    is_headless() && label == MAIN_WINDOW_LABEL
+}
@@ -0,0 +1,232 @@
+//! Watchdog over the long-running parts of Babbl that can die without anyone
+//! noticing: the global input listener, the microphone stream and the local
+//! control API. Each is checked periodically and restarted when it stopped,
+//! up to a few times in a row; the results feed the status indicator in the
+//! UI through `get_health` and the `health-changed` event.
+
+use crate::control_api;
+use crate::input_hook;
+use crate::managers::audio::AudioRecordingManager;
+use crate::settings;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Restarts tried before a component is left down, until it's seen running
+/// again
+const MAX_RESTART_ATTEMPTS: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentState {
+    Running,
+    /// Not meant to run with the current settings
+    Disabled,
+    /// Stopped and restarted, not confirmed running yet
+    Restarting,
+    /// Stopped, and restarting it didn't help
+    Down,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Type)]
+pub struct ComponentHealth {
+    pub state: ComponentState,
+    /// Restarts since launch
+    pub restarts: u32,
+    /// Restarts since it was last seen running
+    pub restart_attempts: u32,
+    pub last_error: Option<String>,
+}
+
+impl Default for ComponentHealth {
+    fn default() -> Self {
+        Self {
+            state: ComponentState::Running,
+            restarts: 0,
+            restart_attempts: 0,
+            last_error: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Type)]
+pub struct HealthStatus {
+    /// Whether nothing is down, for the indicator
+    pub healthy: bool,
+    pub input_listener: ComponentHealth,
+    pub audio_stream: ComponentHealth,
+    pub control_api: ComponentHealth,
+    /// In milliseconds since the epoch, `None` before the first check
+    pub checked_at: Option<i64>,
+}
+
+static STATUS: Lazy<Mutex<HealthStatus>> = Lazy::new(|| {
+    Mutex::new(HealthStatus {
+        healthy: true,
+        ..Default::default()
+    })
+});
+
+/// Next health of a component that's `alive` (`None` when it isn't meant to
+/// run), restarting it with `restart` when it stopped
+fn next_health(
+    name: &str,
+    previous: &ComponentHealth,
+    alive: Option<bool>,
+    restart: impl FnOnce() -> Result<(), String>,
+) -> ComponentHealth {
+    let mut health = previous.clone();
+    match alive {
+        None => {
+            health.state = ComponentState::Disabled;
+            health.restart_attempts = 0;
+        }
+        Some(true) => {
+            health.state = ComponentState::Running;
+            health.restart_attempts = 0;
+        }
+        Some(false) if health.restart_attempts >= MAX_RESTART_ATTEMPTS => {
+            health.state = ComponentState::Down;
+        }
+        Some(false) => {
+            health.restart_attempts += 1;
+            info!(
+                "Restarting the {} (attempt {} of {})",
+                name, health.restart_attempts, MAX_RESTART_ATTEMPTS
+            );
+            match restart() {
+                Ok(()) => {
+                    health.state = ComponentState::Restarting;
+                    health.restarts += 1;
+                }
+                Err(e) => {
+                    warn!("Failed to restart the {}: {}", name, e);
+                    health.state = ComponentState::Down;
+                    health.last_error = Some(e);
+                }
+            }
+        }
+    }
+    health
+}
+
+fn check(app: &AppHandle) -> HealthStatus {
+    let previous = status();
+    let settings = settings::get_settings(app);
+
+    let input_listener = next_health(
+        "input listener",
+        &previous.input_listener,
+        Some(input_hook::is_listener_running()),
+        || {
+            input_hook::restart_listener();
+            Ok(())
+        },
+    );
+
+    let rm = app.state::<Arc<AudioRecordingManager>>();
+    let audio_stream = next_health(
+        "microphone stream",
+        &previous.audio_stream,
+        rm.stream_alive(),
+        || rm.restart_microphone_stream().map_err(|e| e.to_string()),
+    );
+
+    let control_api = next_health(
+        "control API",
+        &previous.control_api,
+        settings.control_api_enabled.then(control_api::is_running),
+        || control_api::sync(app),
+    );
+
+    HealthStatus {
+        healthy: [&input_listener, &audio_stream, &control_api]
+            .iter()
+            .all(|health| health.state != ComponentState::Down),
+        input_listener,
+        audio_stream,
+        control_api,
+        checked_at: Some(chrono::Utc::now().timestamp_millis()),
+    }
+}
+
+/// The result of the last check
+pub fn status() -> HealthStatus {
+    STATUS.lock().unwrap().clone()
+}
+
+/// Check every component now, restarting the stopped ones
+pub fn check_now(app: &AppHandle) -> HealthStatus {
+    let next = check(app);
+    let changed = {
+        let mut status = STATUS.lock().unwrap();
+        let changed = status.input_listener != next.input_listener
+            || status.audio_stream != next.audio_stream
+            || status.control_api != next.control_api;
+        *status = next.clone();
+        changed
+    };
+    if changed {
+        debug!("Health changed: {:?}", next);
+        if let Err(e) = app.emit("health-changed", next.clone()) {
+            warn!("Failed to emit health change: {}", e);
+        }
+    }
+    next
+}
+
+/// Start the watchdog; call once the managers are managed and the servers
+/// started
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("health".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(crate::power::scaled(CHECK_INTERVAL));
+            check_now(&app);
+        });
+    if let Err(e) = spawned {
+        warn!("Failed to start the health watchdog: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_health() {
+        let running = ComponentHealth::default();
+        let ok = || Ok(());
+
+        let restarted = next_health("test", &running, Some(false), ok);
+        assert_eq!(restarted.state, ComponentState::Restarting);
+        assert_eq!(restarted.restarts, 1);
+
+        let mut health = restarted;
+        for _ in 1..MAX_RESTART_ATTEMPTS {
+            health = next_health("test", &health, Some(false), ok);
+        }
+        assert_eq!(health.restart_attempts, MAX_RESTART_ATTEMPTS);
+        let given_up = next_health("test", &health, Some(false), || panic!("restarted"));
+        assert_eq!(given_up.state, ComponentState::Down);
+
+        let recovered = next_health("test", &given_up, Some(true), ok);
+        assert_eq!(recovered.state, ComponentState::Running);
+        assert_eq!(recovered.restart_attempts, 0);
+        assert_eq!(recovered.restarts, MAX_RESTART_ATTEMPTS);
+
+        let failed = next_health("test", &running, Some(false), || Err("busy".into()));
+        assert_eq!(failed.state, ComponentState::Down);
+        assert_eq!(failed.last_error.as_deref(), Some("busy"));
+
+        let disabled = next_health("test", &failed, None, ok);
+        assert_eq!(disabled.state, ComponentState::Disabled);
+    }
+}
@@ -0,0 +1,184 @@
+//! Encryption of the history database and recordings at rest. The secret
+//! lives in the OS keyring; the database key and the recording key are
+//! derived from it. Only builds with the `history-encryption` feature can
+//! encrypt.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Start of an encrypted recording, followed by the nonce and the ciphertext
+const AUDIO_MAGIC: &[u8] = b"BABBLENC1";
+#[cfg(feature = "history-encryption")]
+const NONCE_LEN: usize = 12;
+#[cfg(feature = "history-encryption")]
+const KEYRING_SERVICE: &str = "babbl";
+#[cfg(feature = "history-encryption")]
+const KEYRING_USER: &str = "history-encryption";
+
+/// Only built by `load_or_create_key`, so never without the feature
+#[cfg_attr(not(feature = "history-encryption"), allow(dead_code))]
+#[derive(Clone)]
+pub struct HistoryKey {
+    database: [u8; 32],
+    audio: [u8; 32],
+}
+
+impl HistoryKey {
+    /// Raw key in the form `PRAGMA key` takes
+    pub fn sqlcipher_key(&self) -> String {
+        let hex: String = self
+            .database
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("\"x'{}'\"", hex)
+    }
+}
+
+#[cfg(feature = "history-encryption")]
+fn derive_key(secret: &[u8], label: &str) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    Sha256::new()
+        .chain_update(label.as_bytes())
+        .chain_update(secret)
+        .finalize()
+        .into()
+}
+
+#[cfg(feature = "history-encryption")]
+fn derive_keys(secret: &[u8]) -> HistoryKey {
+    HistoryKey {
+        database: derive_key(secret, "babbl-history-database"),
+        audio: derive_key(secret, "babbl-history-audio"),
+    }
+}
+
+/// Keys from the keyring secret, creating the secret on first use
+#[cfg(feature = "history-encryption")]
+pub fn load_or_create_key() -> Result<HistoryKey, String> {
+    use base64::Engine;
+    use chacha20poly1305::aead::{KeyInit, OsRng};
+    use chacha20poly1305::ChaCha20Poly1305;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| format!("Failed to open the keyring: {}", e))?;
+    let secret = match entry.get_password() {
+        Ok(secret) => engine
+            .decode(secret)
+            .map_err(|e| format!("Invalid history key in the keyring: {}", e))?,
+        Err(keyring::Error::NoEntry) => {
+            let secret = ChaCha20Poly1305::generate_key(&mut OsRng).to_vec();
+            entry
+                .set_password(&engine.encode(&secret))
+                .map_err(|e| format!("Failed to store the history key: {}", e))?;
+            secret
+        }
+        Err(e) => return Err(format!("Failed to read the history key: {}", e)),
+    };
+    Ok(derive_keys(&secret))
+}
+
+#[cfg(not(feature = "history-encryption"))]
+pub fn load_or_create_key() -> Result<HistoryKey, String> {
+    Err("This build doesn't include history encryption".to_string())
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(AUDIO_MAGIC)
+}
+
+/// Whether the recording at `path` is encrypted, reading only its start
+pub fn is_encrypted_file(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(AUDIO_MAGIC.len());
+    fs::File::open(path)
+        .and_then(|file| file.take(AUDIO_MAGIC.len() as u64).read_to_end(&mut header))
+        .is_ok_and(|_| is_encrypted(&header))
+}
+
+#[cfg(feature = "history-encryption")]
+pub fn encrypt(key: &HistoryKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::ChaCha20Poly1305;
+
+    let cipher = ChaCha20Poly1305::new((&key.audio).into());
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Failed to encrypt recording".to_string())?;
+
+    let mut data = AUDIO_MAGIC.to_vec();
+    data.extend_from_slice(&nonce);
+    data.extend(ciphertext);
+    Ok(data)
+}
+
+#[cfg(not(feature = "history-encryption"))]
+pub fn encrypt(_key: &HistoryKey, _plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    Err("This build doesn't include history encryption".to_string())
+}
+
+#[cfg(feature = "history-encryption")]
+pub fn decrypt(key: &HistoryKey, data: &[u8]) -> Result<Vec<u8>, String> {
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+    let body = data
+        .strip_prefix(AUDIO_MAGIC)
+        .filter(|body| body.len() >= NONCE_LEN)
+        .ok_or_else(|| "Recording is not encrypted".to_string())?;
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new((&key.audio).into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt recording; the key may have changed".to_string())
+}
+
+#[cfg(not(feature = "history-encryption"))]
+pub fn decrypt(_key: &HistoryKey, _data: &[u8]) -> Result<Vec<u8>, String> {
+    Err("This build doesn't include history encryption".to_string())
+}
+
+/// Write `data` next to `path` and move it over, so a crash never leaves a
+/// half-written recording
+fn replace_file(path: &Path, data: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, data).map_err(|e| format!("Failed to write '{}': {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace '{}': {}", path.display(), e))
+}
+
+/// Encrypt the recording at `path` in place, unless it already is
+pub fn encrypt_file(key: &HistoryKey, path: &Path) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    if is_encrypted(&data) {
+        return Ok(());
+    }
+    replace_file(path, &encrypt(key, &data)?)
+}
+
+/// Decrypt the recording at `path` in place, unless it isn't encrypted
+pub fn decrypt_file(key: &HistoryKey, path: &Path) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    if !is_encrypted(&data) {
+        return Ok(());
+    }
+    replace_file(path, &decrypt(key, &data)?)
+}
+
+#[cfg(all(test, feature = "history-encryption"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = derive_keys(b"secret");
+        let data = encrypt(&key, b"RIFF....WAVE").unwrap();
+        assert!(is_encrypted(&data));
+        assert_eq!(decrypt(&key, &data).unwrap(), b"RIFF....WAVE");
+
+        let other = derive_keys(b"other secret");
+        assert!(decrypt(&other, &data).is_err());
+        assert!(decrypt(&key, b"RIFF....WAVE").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::managers::history::HistoryEntry;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use specta::Type;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One section per day, for reading
+    Markdown,
+    /// One row per entry, for spreadsheets
+    Csv,
+    /// Array of entries, for scripts
+    Json,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Type)]
+pub struct ExportOptions {
+    /// Add the id, title, saved flag, raw transcript and prompt
+    #[serde(default)]
+    pub include_metadata: bool,
+    /// Add the path of each entry's recording
+    #[serde(default)]
+    pub include_audio: bool,
+}
+
+fn local_time(timestamp: i64) -> DateTime<Local> {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+}
+
+/// Text the user ended up with: the post-processed text when there is one
+fn final_text(entry: &HistoryEntry) -> &str {
+    entry
+        .post_processed_text
+        .as_deref()
+        .filter(|text| !text.trim().is_empty())
+        .unwrap_or(&entry.transcription_text)
+}
+
+fn audio_path(entry: &HistoryEntry, audio_dir: &Path) -> String {
+    audio_dir.join(&entry.file_name).display().to_string()
+}
+
+/// `entries`, oldest first, in `format`
+pub fn render(
+    entries: &[HistoryEntry],
+    format: ExportFormat,
+    options: ExportOptions,
+    audio_dir: &Path,
+) -> Result<String, String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(entries, options, audio_dir)),
+        ExportFormat::Csv => Ok(render_csv(entries, options, audio_dir)),
+        ExportFormat::Json => render_json(entries, options, audio_dir),
+    }
+}
+
+fn render_markdown(entries: &[HistoryEntry], options: ExportOptions, audio_dir: &Path) -> String {
+    let mut out = String::from("# Babbl history\n");
+    let mut current_day = None;
+    for entry in entries {
+        let at = local_time(entry.timestamp);
+        let day = at.date_naive();
+        if current_day != Some(day) {
+            out.push_str(&format!("\n## {}\n", day.format("%A, %B %e, %Y")));
+            current_day = Some(day);
+        }
+        out.push_str(&format!(
+            "\n### {}\n\n{}\n",
+            at.format("%H:%M"),
+            final_text(entry).trim()
+        ));
+
+        let mut details = Vec::new();
+        if options.include_metadata {
+            details.push(format!("- Id: {}", entry.id));
+            if entry.saved {
+                details.push("- Saved".to_string());
+            }
+            if entry.post_processed_text.is_some() {
+                details.push(format!("- Raw: {}", entry.transcription_text.trim()));
+            }
+            if let Some(prompt) = &entry.post_process_prompt {
+                details.push(format!("- Prompt: {}", prompt.trim()));
+            }
+        }
+        if options.include_audio {
+            details.push(format!("- Audio: <{}>", audio_path(entry, audio_dir)));
+        }
+        if !details.is_empty() {
+            out.push_str(&format!("\n{}\n", details.join("\n")));
+        }
+    }
+    out
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_csv(entries: &[HistoryEntry], options: ExportOptions, audio_dir: &Path) -> String {
+    let mut header = vec!["timestamp", "text"];
+    if options.include_metadata {
+        header.extend([
+            "id",
+            "title",
+            "saved",
+            "transcription_text",
+            "post_processed_text",
+            "post_process_prompt",
+        ]);
+    }
+    if options.include_audio {
+        header.push("audio_file");
+    }
+
+    let mut out = format!("{}\r\n", header.join(","));
+    for entry in entries {
+        let mut row = vec![
+            local_time(entry.timestamp).to_rfc3339(),
+            final_text(entry).to_string(),
+        ];
+        if options.include_metadata {
+            row.extend([
+                entry.id.to_string(),
+                entry.title.clone(),
+                entry.saved.to_string(),
+                entry.transcription_text.clone(),
+                entry.post_processed_text.clone().unwrap_or_default(),
+                entry.post_process_prompt.clone().unwrap_or_default(),
+            ]);
+        }
+        if options.include_audio {
+            row.push(audio_path(entry, audio_dir));
+        }
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&format!("{}\r\n", row.join(",")));
+    }
+    out
+}
+
+fn render_json(
+    entries: &[HistoryEntry],
+    options: ExportOptions,
+    audio_dir: &Path,
+) -> Result<String, String> {
+    let entries: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let mut object = Map::new();
+            object.insert(
+                "timestamp".to_string(),
+                json!(local_time(entry.timestamp).to_rfc3339()),
+            );
+            object.insert("text".to_string(), json!(final_text(entry)));
+            if options.include_metadata {
+                object.insert("id".to_string(), json!(entry.id));
+                object.insert("title".to_string(), json!(entry.title));
+                object.insert("saved".to_string(), json!(entry.saved));
+                object.insert(
+                    "transcription_text".to_string(),
+                    json!(entry.transcription_text),
+                );
+                object.insert(
+                    "post_processed_text".to_string(),
+                    json!(entry.post_processed_text),
+                );
+                object.insert(
+                    "post_process_prompt".to_string(),
+                    json!(entry.post_process_prompt),
+                );
+            }
+            if options.include_audio {
+                object.insert(
+                    "audio_file".to_string(),
+                    json!(audio_path(entry, audio_dir)),
+                );
+            }
+            Value::Object(object)
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, timestamp: i64, raw: &str, processed: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            id,
+            file_name: format!("babbl-{}.wav", timestamp),
+            timestamp,
+            saved: false,
+            title: String::new(),
+            transcription_text: raw.to_string(),
+            post_processed_text: processed.map(str::to_string),
+            post_process_prompt: None,
+            action_id: None,
+            duration_ms: None,
+            audio_deleted: false,
+            app_name: None,
+            run_id: None,
+        }
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let entries = [
+            entry(1, 0, "um hello", Some("Hello, \"world\"")),
+            entry(2, 60, "plain", None),
+        ];
+        let options = ExportOptions {
+            include_metadata: false,
+            include_audio: true,
+        };
+        let csv = render(&entries, ExportFormat::Csv, options, Path::new("rec")).unwrap();
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], "timestamp,text,audio_file");
+        assert!(lines[1].contains(",\"Hello, \"\"world\"\"\","));
+        assert!(lines[2].ends_with(&format!(
+            ",plain,{}",
+            audio_path(&entries[1], Path::new("rec"))
+        )));
+    }
+
+    #[test]
+    fn test_render_markdown_groups_by_day() {
+        let day = 24 * 60 * 60;
+        let entries = [
+            entry(1, 10 * day, "first", None),
+            entry(2, 10 * day + 60, "second", None),
+            entry(3, 12 * day, "third", None),
+        ];
+        let markdown = render(
+            &entries,
+            ExportFormat::Markdown,
+            ExportOptions::default(),
+            Path::new("rec"),
+        )
+        .unwrap();
+        assert!(markdown.matches("\n## ").count() >= 2);
+        assert_eq!(markdown.matches("\n### ").count(), 3);
+        assert!(!markdown.contains("- Audio"));
+    }
+}
@@ -1,5 +1,6 @@
 use enigo::{Enigo, Key, Keyboard, Mouse, Settings};
 use std::sync::Mutex;
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 
 /// Wrapper for Enigo to store in Tauri's managed state.
@@ -114,10 +115,213 @@ pub fn send_paste_shift_insert(enigo: &mut Enigo) -> Result<(), String> {
 
 /// Pastes text directly using the enigo text method.
 /// This tries to use system input methods if possible, otherwise simulates keystrokes one by one.
+/// On Windows the text is sent as Unicode key events, see [`send_unicode_text`].
 pub fn paste_text_direct(enigo: &mut Enigo, text: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = enigo;
+        send_unicode_text(text, None)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        enigo
+            .text(text)
+            .map_err(|e| format!("Failed to send text directly: {}", e))?;
+        Ok(())
+    }
+}
+
+/// One key event pair sent for a character
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnicodeKey {
+    /// A UTF-16 code unit; characters outside the BMP take two
+    Unit(u16),
+    Return,
+    Tab,
+}
+
+/// Key events for `text`, grouped per character. Line breaks and tabs are
+/// real key presses, since many apps ignore them as Unicode input.
+#[cfg(any(target_os = "windows", test))]
+fn unicode_keys(text: &str) -> Vec<Vec<UnicodeKey>> {
+    let mut buffer = [0u16; 2];
+    text.chars()
+        .filter(|c| *c != '\r')
+        .map(|c| match c {
+            '\n' => vec![UnicodeKey::Return],
+            '\t' => vec![UnicodeKey::Tab],
+            _ => c
+                .encode_utf16(&mut buffer)
+                .iter()
+                .map(|unit| UnicodeKey::Unit(*unit))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Sends `text` with `SendInput` and `KEYEVENTF_UNICODE`, which types the
+/// characters themselves instead of virtual keys, so Bengali, accents and
+/// emoji arrive intact whatever the keyboard layout. With `interval`, pauses
+/// after each character, otherwise everything goes out in one call.
+#[cfg(target_os = "windows")]
+pub fn send_unicode_text(text: &str, interval: Option<Duration>) -> Result<(), String> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_RETURN, VK_TAB,
+    };
+
+    let key_input = |key: UnicodeKey, up: bool| {
+        let (vk, scan, flags) = match key {
+            UnicodeKey::Unit(unit) => (VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE),
+            UnicodeKey::Return => (VK_RETURN, 0, KEYBD_EVENT_FLAGS(0)),
+            UnicodeKey::Tab => (VK_TAB, 0, KEYBD_EVENT_FLAGS(0)),
+        };
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: vk,
+                    wScan: scan,
+                    dwFlags: if up { flags | KEYEVENTF_KEYUP } else { flags },
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    };
+    let send = |keys: &[UnicodeKey]| -> Result<(), String> {
+        let inputs: Vec<INPUT> = keys
+            .iter()
+            .flat_map(|key| [key_input(*key, false), key_input(*key, true)])
+            .collect();
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        // Fewer events are sent when input is blocked, e.g. by an elevated window
+        if sent as usize != inputs.len() {
+            return Err(format!(
+                "SendInput sent {} of {} key events",
+                sent,
+                inputs.len()
+            ));
+        }
+        Ok(())
+    };
+
+    let characters = unicode_keys(text);
+    match interval {
+        Some(interval) => {
+            for keys in &characters {
+                send(keys)?;
+                std::thread::sleep(interval);
+            }
+            Ok(())
+        }
+        None => send(&characters.concat()),
+    }
+}
+
+/// Releases modifier keys that may still be held from the shortcut that
+/// triggered an action, so the keys sent next aren't combined with them.
+pub fn release_modifiers(enigo: &mut Enigo) -> Result<(), String> {
+    for key in [Key::Control, Key::Shift, Key::Alt, Key::Meta] {
+        enigo
+            .key(key, enigo::Direction::Release)
+            .map_err(|e| format!("Failed to release modifier key: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Sends `count` backspaces
+pub fn send_backspaces(enigo: &mut Enigo, count: usize) -> Result<(), String> {
+    for _ in 0..count {
+        enigo
+            .key(Key::Backspace, enigo::Direction::Click)
+            .map_err(|e| format!("Failed to send backspace: {}", e))?;
+        // Some apps drop keys sent back to back
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    Ok(())
+}
+
+/// Sends the platform undo shortcut (Cmd+Z on macOS, Ctrl+Z elsewhere)
+pub fn send_undo(enigo: &mut Enigo) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let (modifier_key, z_key_code) = (Key::Meta, Key::Other(6)); // kVK_ANSI_Z
+    #[cfg(target_os = "windows")]
+    let (modifier_key, z_key_code) = (Key::Control, Key::Other(0x5A)); // VK_Z
+    #[cfg(target_os = "linux")]
+    let (modifier_key, z_key_code) = (Key::Control, Key::Unicode('z'));
+
     enigo
-        .text(text)
-        .map_err(|e| format!("Failed to send text directly: {}", e))?;
+        .key(modifier_key, enigo::Direction::Press)
+        .map_err(|e| format!("Failed to press modifier key: {}", e))?;
+    enigo
+        .key(z_key_code, enigo::Direction::Click)
+        .map_err(|e| format!("Failed to click Z key: {}", e))?;
+
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    enigo
+        .key(modifier_key, enigo::Direction::Release)
+        .map_err(|e| format!("Failed to release modifier key: {}", e))?;
 
     Ok(())
 }
+
+/// Delay between two typed characters at `chars_per_second`
+pub fn keystroke_interval(chars_per_second: u32) -> Duration {
+    Duration::from_micros(1_000_000 / u64::from(chars_per_second.max(1)))
+}
+
+/// Types text one character at a time, pausing between characters so slow
+/// targets (remote desktops, serial consoles) don't drop input.
+/// Line breaks and tabs are sent as key presses rather than characters.
+pub fn type_text(enigo: &mut Enigo, text: &str, chars_per_second: u32) -> Result<(), String> {
+    let interval = keystroke_interval(chars_per_second);
+    #[cfg(target_os = "windows")]
+    {
+        let _ = enigo;
+        send_unicode_text(text, Some(interval))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut buffer = [0u8; 4];
+        for c in text.chars() {
+            match c {
+                '\r' => continue,
+                '\n' => enigo.key(Key::Return, enigo::Direction::Click),
+                '\t' => enigo.key(Key::Tab, enigo::Direction::Click),
+                _ => enigo.text(c.encode_utf8(&mut buffer)),
+            }
+            .map_err(|e| format!("Failed to type character: {}", e))?;
+            std::thread::sleep(interval);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystroke_interval() {
+        assert_eq!(keystroke_interval(100), Duration::from_millis(10));
+        assert_eq!(keystroke_interval(0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_unicode_keys() {
+        assert_eq!(
+            unicode_keys("é\r\n😀"),
+            vec![
+                vec![UnicodeKey::Unit(0xE9)],
+                vec![UnicodeKey::Return],
+                vec![UnicodeKey::Unit(0xD83D), UnicodeKey::Unit(0xDE00)],
+            ]
+        );
+        assert_eq!(unicode_keys("আ").concat(), vec![UnicodeKey::Unit(0x0986)]);
+    }
+}
@@ -4,15 +4,16 @@
 //! enabling support for mouse button shortcuts that the standard Tauri global-shortcut
 //! plugin cannot handle.
 
-use log::{debug, error, info, warn};
-use once_cell::sync::Lazy;
+use log::{debug, info, warn};
+use once_cell::sync::{Lazy, OnceCell};
 use rdev::{Button, Event, EventType, Key};
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use std::thread;
 use tauri::AppHandle;
 
-use crate::actions::ACTION_MAP;
+use crate::actions::{action_for, admit};
 use crate::settings;
 use crate::ManagedToggleState;
 
@@ -129,17 +130,20 @@ impl InputState {
 /// Global input hook manager
 pub struct InputHookManager {
     state: Arc<RwLock<InputState>>,
-    app_handle: Arc<Mutex<Option<AppHandle>>>,
-    listener_running: Arc<Mutex<bool>>,
+    /// Set once by `init`, then read on every event without locking
+    app_handle: Arc<OnceCell<AppHandle>>,
+    listener_running: Arc<AtomicBool>,
 }
 
+/// Set once the listener's stop was reported; restarts that fail the same
+/// way aren't reported again
+static STOP_REPORTED: AtomicBool = AtomicBool::new(false);
+
 /// Global singleton instance
-static INPUT_HOOK_MANAGER: Lazy<InputHookManager> = Lazy::new(|| {
-    InputHookManager {
-        state: Arc::new(RwLock::new(InputState::new())),
-        app_handle: Arc::new(Mutex::new(None)),
-        listener_running: Arc::new(Mutex::new(false)),
-    }
+static INPUT_HOOK_MANAGER: Lazy<InputHookManager> = Lazy::new(|| InputHookManager {
+    state: Arc::new(RwLock::new(InputState::new())),
+    app_handle: Arc::new(OnceCell::new()),
+    listener_running: Arc::new(AtomicBool::new(false)),
 });
 
 impl InputHookManager {
@@ -150,8 +154,9 @@ impl InputHookManager {
     
     /// Initialize the input hook manager with an app handle
     pub fn init(&self, app: AppHandle) {
-        let mut handle = self.app_handle.lock().unwrap();
-        *handle = Some(app);
+        if self.app_handle.set(app).is_err() {
+            debug!("Input hook manager already initialized");
+        }
         
         // Start the listener if not already running
         self.start_listener();
@@ -159,13 +164,10 @@ impl InputHookManager {
     
     /// Start the global input listener
     fn start_listener(&self) {
-        let mut running = self.listener_running.lock().unwrap();
-        if *running {
+        if self.listener_running.swap(true, Ordering::SeqCst) {
             debug!("Input listener already running");
             return;
         }
-        *running = true;
-        drop(running);
         
         let state = Arc::clone(&self.state);
         let app_handle = Arc::clone(&self.app_handle);
@@ -179,17 +181,28 @@ impl InputHookManager {
             };
             
             if let Err(error) = rdev::listen(callback) {
-                error!("Error in global input listener: {:?}", error);
-                let mut running = listener_running.lock().unwrap();
-                *running = false;
+                if STOP_REPORTED.swap(true, Ordering::Relaxed) {
+                    warn!("The global input listener stopped again: {:?}", error);
+                } else {
+                    crate::fatal::report(&format!(
+                        "The global input listener stopped, mouse and keyboard bindings won't work: {:?}",
+                        error
+                    ));
+                }
+                listener_running.store(false, Ordering::SeqCst);
             }
         });
     }
     
+    /// Whether the listener thread is still listening
+    pub fn is_listener_running(&self) -> bool {
+        self.listener_running.load(Ordering::SeqCst)
+    }
+
     /// Handle an input event from rdev
     fn handle_event(
         state: &Arc<RwLock<InputState>>,
-        app_handle: &Arc<Mutex<Option<AppHandle>>>,
+        app_handle: &Arc<OnceCell<AppHandle>>,
         event: Event,
     ) {
         let element = match event.event_type {
@@ -451,16 +464,13 @@ impl InputHookManager {
     }
     
     /// Trigger a shortcut action
-    fn trigger_shortcut(
-        app_handle: &Arc<Mutex<Option<AppHandle>>>,
-        binding_id: &str,
-        is_press: bool,
-    ) {
-        let app_guard = app_handle.lock().unwrap();
-        if let Some(app) = app_guard.as_ref() {
-            let settings = settings::get_settings(app);
+    fn trigger_shortcut(app_handle: &Arc<OnceCell<AppHandle>>, binding_id: &str, is_press: bool) {
+        if let Some(app) = app_handle.get() {
+            if crate::shortcut::ignore_shortcut(app) {
+                return;
+            }
             
-            if let Some(action) = ACTION_MAP.get(binding_id) {
+            if let Some(action) = action_for(binding_id) {
                 if binding_id == "cancel" {
                     // Cancel action only triggers on press
                     if is_press {
@@ -471,18 +481,42 @@ impl InputHookManager {
                             action.start(app, binding_id, "mouse_shortcut");
                         }
                     }
-                } else if settings.push_to_talk {
+                } else if binding_id == crate::clipboard::UNDO_INJECTION_ACTION_ID {
+                    // One-shot action, triggered on release
+                    if !is_press {
+                        action.start(app, binding_id, "mouse_shortcut");
+                    }
+                } else if settings::get_settings(app).push_to_talk {
                     // Push-to-talk mode: press = start, release = stop
+                    use tauri::Manager;
+                    let toggle_state_manager = app.state::<ManagedToggleState>();
+
+                    let mut states = toggle_state_manager
+                        .lock()
+                        .expect("Failed to lock toggle state manager");
+                    let is_currently_active = states
+                        .active_toggles
+                        .entry(binding_id.to_string())
+                        .or_insert(false);
+
                     if is_press {
                         debug!("Mouse shortcut triggered (press): {}", binding_id);
-                        action.start(app, binding_id, "mouse_shortcut");
-                    } else {
+                        if !*is_currently_active && admit(app, binding_id) {
+                            action.start(app, binding_id, "mouse_shortcut");
+                            *is_currently_active = true;
+                        }
+                    } else if *is_currently_active {
                         debug!("Mouse shortcut triggered (release): {}", binding_id);
                         action.stop(app, binding_id, "mouse_shortcut");
+                        *is_currently_active = false;
                     }
                 } else {
                     // Toggle mode: only trigger on press
                     if is_press {
+                        if crate::throttle::is_bounce(binding_id) {
+                            debug!("Ignoring a bounce of '{}'", binding_id);
+                            return;
+                        }
                         use tauri::Manager;
                         let toggle_state_manager = app.state::<ManagedToggleState>();
                         
@@ -494,7 +528,7 @@ impl InputHookManager {
                         if *is_currently_active {
                             action.stop(app, binding_id, "mouse_shortcut");
                             *is_currently_active = false;
-                        } else {
+                        } else if admit(app, binding_id) {
                             action.start(app, binding_id, "mouse_shortcut");
                             *is_currently_active = true;
                         }
@@ -579,6 +613,16 @@ pub fn init_input_hooks(app: &AppHandle) {
     InputHookManager::instance().init(app.clone());
 }
 
+/// Whether the global input listener is running (checked by health.rs)
+pub fn is_listener_running() -> bool {
+    InputHookManager::instance().is_listener_running()
+}
+
+/// Start the global input listener again after it stopped
+pub fn restart_listener() {
+    InputHookManager::instance().start_listener();
+}
+
 /// Register a mouse shortcut (called from shortcut.rs)
 pub fn register_mouse_shortcut(id: &str, binding: &str) -> Result<(), String> {
     InputHookManager::instance().register_shortcut(id, binding)
@@ -0,0 +1,418 @@
+//! What a launch asks for. Only one Babbl runs at a time: a second launch
+//! hands its arguments to the running one and exits, so it never fights it
+//! over the input hook or the microphone. The arguments may be `--start`,
+//! `--stop`, `--toggle` or `--cancel`, optionally with `--action <id>`, or a
+//! `babbl://` link; a launch without any brings the window to the front.
+//!
+//! Links, for launchers like Raycast or Alfred and for browsers:
+//!
+//! - `babbl://<command>?action=<id>` with `start`, `stop`, `toggle`,
+//!   `record` (same as `toggle`) or `cancel`; the action may also be given
+//!   by its name or without the `mode_` prefix, e.g. `action=email`
+//! - `babbl://settings/<section>` opens the window at a settings section
+//! - `babbl://transcribe?file=<path>` transcribes a WAV file into the
+//!   history and onto the clipboard; only an absolute path on this machine
+//!
+//! Any web page can open a link, so links only start a recording or
+//! transcribe a file once that's allowed in the settings.
+
+use crate::actions;
+use crate::audio_toolkit::read_wav_file;
+use crate::autostart::AUTOSTART_ARG;
+use crate::clipboard;
+use crate::control_api;
+use crate::managers::history::HistoryManager;
+use crate::managers::transcription::TranscriptionManager;
+use crate::notifications::{self, NotificationKind};
+use crate::pipeline::{self, PipelineState};
+use crate::settings::{self, ShortcutBinding};
+use crate::show_main_window;
+use crate::utils::cancel_current_operation;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+pub const URL_SCHEME: &str = "babbl";
+
+/// Section a link asked for before the window could listen
+static REQUESTED_SECTION: Lazy<Mutex<Option<&'static str>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Start,
+    Stop,
+    Toggle,
+    Cancel,
+}
+
+impl Command {
+    fn named(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "start" => Some(Self::Start),
+            "stop" => Some(Self::Stop),
+            "toggle" | "record" => Some(Self::Toggle),
+            "cancel" => Some(Self::Cancel),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Request {
+    /// Recording command and optional action id or name
+    Command(Command, Option<String>),
+    /// Open the window at this settings section
+    Settings(&'static str),
+    /// Transcribe the WAV file at this path
+    Transcribe(String),
+}
+
+/// Sidebar section of the main window a link names; shortcuts and the
+/// microphone are on the general page
+fn settings_section(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().as_str() {
+        "" | "general" | "shortcuts" | "microphone" => Some("general"),
+        "advanced" => Some("advanced"),
+        "postprocessing" | "post-processing" | "prompts" => Some("postprocessing"),
+        "onlineprovider" | "providers" => Some("onlineprovider"),
+        "history" => Some("history"),
+        "debug" => Some("debug"),
+        "about" => Some("about"),
+        _ => None,
+    }
+}
+
+/// Whether `path` is an absolute path to a WAV file on this machine, not on
+/// a network share
+fn is_local_wav(path: &Path) -> bool {
+    let text = path.to_string_lossy();
+    let unc = text.starts_with("\\\\") || text.starts_with("//");
+    path.is_absolute()
+        && !unc
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"))
+}
+
+/// A `file` parameter, given as a path or a `file://` URL, if it's a local
+/// WAV file
+fn file_parameter(url: &Url) -> Option<String> {
+    let (_, file) = url.query_pairs().find(|(key, _)| key == "file")?;
+    let path = match Url::parse(&file).ok().filter(|url| url.scheme() == "file") {
+        Some(url) if matches!(url.host_str(), None | Some("") | Some("localhost")) => {
+            url.to_file_path().ok()?
+        }
+        Some(_) => return None,
+        None => PathBuf::from(file.as_ref()),
+    };
+    is_local_wav(&path).then(|| path.to_string_lossy().to_string())
+}
+
+/// What a `babbl://` link asks for
+fn parse_url(url: &Url) -> Option<Request> {
+    if url.scheme() != URL_SCHEME {
+        return None;
+    }
+    // `babbl://toggle` has a host, `babbl:toggle` only a path
+    let path = url.path().trim_matches('/');
+    let (name, rest) = match url.host_str() {
+        Some(host) => (host, path),
+        None => path.split_once('/').unwrap_or((path, "")),
+    };
+    match name.to_lowercase().as_str() {
+        "settings" => settings_section(rest).map(Request::Settings),
+        "transcribe" => file_parameter(url).map(Request::Transcribe),
+        _ => {
+            let command = Command::named(name)?;
+            let binding_id = url
+                .query_pairs()
+                .find(|(key, _)| key == "action")
+                .map(|(_, value)| value.into_owned());
+            Some(Request::Command(command, binding_id))
+        }
+    }
+}
+
+/// What the arguments of a launch ask for, without the program name
+fn parse(args: &[String]) -> Option<Request> {
+    let mut command = None;
+    let mut binding_id = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--action" {
+            binding_id = args.next().cloned();
+        } else if let Some(name) = arg.strip_prefix("--") {
+            command = Command::named(name).or(command);
+        } else if let Some(request) = Url::parse(arg).ok().as_ref().and_then(parse_url) {
+            match request {
+                Request::Command(linked, action) => {
+                    command = Some(linked);
+                    binding_id = action.or(binding_id);
+                }
+                request => return Some(request),
+            }
+        }
+    }
+    command.map(|command| Request::Command(command, binding_id))
+}
+
+/// The action `name` refers to: its id, its id without the `mode_` prefix
+/// or its name
+fn resolve_action(bindings: &HashMap<String, ShortcutBinding>, name: &str) -> Option<String> {
+    if bindings.contains_key(name) {
+        return Some(name.to_string());
+    }
+    let mode_id = format!("mode_{}", name);
+    if bindings.contains_key(&mode_id) {
+        return Some(mode_id);
+    }
+    bindings
+        .values()
+        .find(|binding| binding.name.eq_ignore_ascii_case(name.trim()))
+        .map(|binding| binding.id.clone())
+}
+
+fn run_command(app: &AppHandle, command: Command, binding_id: Option<String>) {
+    debug!("Launch command: {:?} {:?}", command, binding_id);
+    let active = match command {
+        Command::Start => true,
+        Command::Stop => false,
+        Command::Toggle => pipeline::current() != PipelineState::Recording,
+        Command::Cancel => {
+            cancel_current_operation(app);
+            return;
+        }
+    };
+
+    let name = binding_id.unwrap_or_else(|| "transcribe".to_string());
+    let binding_id = match resolve_action(&settings::get_settings(app).bindings, &name) {
+        Some(binding_id) => binding_id,
+        None => {
+            warn!("Launch command for unknown action '{}'", name);
+            return;
+        }
+    };
+    if let Err(e) = control_api::set_recording(app, &binding_id, active) {
+        warn!("Launch command failed: {}", e);
+    }
+}
+
+fn open_settings(app: &AppHandle, section: &'static str) {
+    debug!("Opening the {} settings from a link", section);
+    // Kept for a window that's still loading and asks once it listens
+    *REQUESTED_SECTION.lock().unwrap() = Some(section);
+    show_main_window(app);
+    if let Err(e) = app.emit("open-section", section) {
+        warn!("Failed to emit open section: {}", e);
+    }
+}
+
+/// The settings section a link asked for, once
+pub fn take_requested_section() -> Option<String> {
+    REQUESTED_SECTION.lock().unwrap().take().map(str::to_string)
+}
+
+async fn transcribe_file(app: &AppHandle, path: &str) -> Result<String, String> {
+    let samples = read_wav_file(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    let settings = settings::get_settings(app);
+    let tm = app.state::<Arc<TranscriptionManager>>();
+    let text = actions::transcribe_audio(&settings, &tm, samples.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    let hm = app.state::<Arc<HistoryManager>>();
+    if let Err(e) = hm
+        .save_transcription(
+            samples,
+            text.clone(),
+            None,
+            None,
+            "transcribe".to_string(),
+            None,
+            None,
+        )
+        .await
+    {
+        warn!("Failed to save the transcription of '{}': {}", path, e);
+    }
+    clipboard::copy_text(app, &text)?;
+    Ok(text)
+}
+
+fn transcribe(app: &AppHandle, path: String) {
+    info!("Transcribing '{}' from a link", path);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let (title, body) = match transcribe_file(&app, &path).await {
+            Ok(text) => ("Transcribed to the clipboard", text),
+            Err(e) => {
+                warn!("{}", e);
+                ("Transcription failed", e)
+            }
+        };
+        notifications::notify(&app, NotificationKind::ActionResult, title, &body);
+    });
+}
+
+/// Whether `request` records or reads a file, which a link may only do once
+/// it's allowed
+fn needs_permission(request: &Request) -> bool {
+    match request {
+        Request::Command(command, _) => matches!(command, Command::Start | Command::Toggle),
+        Request::Settings(_) => false,
+        Request::Transcribe(_) => true,
+    }
+}
+
+/// Whether the arguments of a launch carry a `babbl://` link
+fn has_link(args: &[String]) -> bool {
+    args.iter()
+        .any(|arg| Url::parse(arg).is_ok_and(|url| url.scheme() == URL_SCHEME))
+}
+
+fn run(app: &AppHandle, request: Request, from_link: bool) {
+    if from_link && needs_permission(&request) && !settings::get_settings(app).allow_link_actions {
+        warn!("Ignoring a link to {:?}, links may not record", request);
+        notifications::notify(
+            app,
+            NotificationKind::PermissionProblem,
+            "Link ignored",
+            "Allow links to record and transcribe files in the settings first",
+        );
+        return;
+    }
+    match request {
+        Request::Command(command, binding_id) => run_command(app, command, binding_id),
+        Request::Settings(section) => open_settings(app, section),
+        Request::Transcribe(path) => transcribe(app, path),
+    }
+}
+
+/// Run the command Babbl itself was started with, if any
+pub fn handle_startup(app: &AppHandle) {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(request) = parse(&args) {
+        run(app, request, has_link(&args));
+    }
+}
+
+/// Handle a second launch with `args`, which include the program name
+pub fn handle_second_instance(app: &AppHandle, args: &[String]) {
+    debug!("Another launch handed over its arguments: {:?}", args);
+    let args = args.get(1..).unwrap_or_default();
+    match parse(args) {
+        Some(request) => run(app, request, has_link(args)),
+        // The login entry started Babbl again, which should stay in the tray
+        None if args.iter().any(|arg| arg == AUTOSTART_ARG) => {}
+        None => show_main_window(app),
+    }
+}
+
+/// Handle links the system opened Babbl with
+pub fn handle_urls(app: &AppHandle, urls: &[Url]) {
+    for url in urls {
+        match parse_url(url) {
+            Some(request) => run(app, request, true),
+            None => warn!("Ignoring unknown link '{}'", url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn command(command: Command, binding_id: Option<&str>) -> Option<Request> {
+        Some(Request::Command(command, binding_id.map(str::to_string)))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(&args(&[])), None);
+        assert_eq!(parse(&args(&["--portable"])), None);
+        assert_eq!(parse(&args(&["--headless"])), None);
+        assert_eq!(parse(&args(&["--autostart"])), None);
+        assert_eq!(parse(&args(&["--toggle"])), command(Command::Toggle, None));
+        assert_eq!(
+            parse(&args(&["--portable", "--start", "--action", "mode_email"])),
+            command(Command::Start, Some("mode_email"))
+        );
+        assert_eq!(
+            parse(&args(&["babbl://stop?action=mode_email"])),
+            command(Command::Stop, Some("mode_email"))
+        );
+        assert_eq!(
+            parse(&args(&["babbl:Cancel"])),
+            command(Command::Cancel, None)
+        );
+        assert_eq!(parse(&args(&["babbl://reboot"])), None);
+        assert_eq!(parse(&args(&["https://toggle"])), None);
+    }
+
+    #[test]
+    fn test_parse_links() {
+        assert_eq!(
+            parse(&args(&["babbl://record?action=email"])),
+            command(Command::Toggle, Some("email"))
+        );
+        assert_eq!(
+            parse(&args(&["babbl://settings/shortcuts"])),
+            Some(Request::Settings("general"))
+        );
+        assert_eq!(
+            parse(&args(&["babbl://settings"])),
+            Some(Request::Settings("general"))
+        );
+        assert_eq!(
+            parse(&args(&["babbl:settings/history"])),
+            Some(Request::Settings("history"))
+        );
+        assert_eq!(parse(&args(&["babbl://settings/nowhere"])), None);
+        assert_eq!(
+            parse(&args(&["babbl://transcribe?file=%2Ftmp%2Fmemo%201.wav"])),
+            Some(Request::Transcribe("/tmp/memo 1.wav".to_string()))
+        );
+        assert_eq!(parse(&args(&["babbl://transcribe"])), None);
+        // Only absolute paths to WAV files on this machine
+        for file in [
+            "memo.wav",
+            "%2Ftmp%2Fnotes.txt",
+            "%2F%2Fserver%2Fshare%2Fmemo.wav",
+            "file%3A%2F%2Fserver%2Fshare%2Fmemo.wav",
+        ] {
+            let link = format!("babbl://transcribe?file={}", file);
+            assert_eq!(parse(&args(&[link.as_str()])), None);
+        }
+    }
+
+    #[test]
+    fn test_resolve_action() {
+        let binding = |id: &str, name: &str| ShortcutBinding {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            default_binding: String::new(),
+            current_binding: String::new(),
+        };
+        let bindings: HashMap<String, ShortcutBinding> = [
+            binding("transcribe", "Transcribe"),
+            binding("mode_summarize", "Summarize"),
+            binding("custom_1", "Email Reply"),
+        ]
+        .into_iter()
+        .map(|binding| (binding.id.clone(), binding))
+        .collect();
+
+        let resolve = |name: &str| resolve_action(&bindings, name);
+        assert_eq!(resolve("transcribe").as_deref(), Some("transcribe"));
+        assert_eq!(resolve("summarize").as_deref(), Some("mode_summarize"));
+        assert_eq!(resolve("email reply").as_deref(), Some("custom_1"));
+        assert_eq!(resolve("email"), None);
+    }
+}
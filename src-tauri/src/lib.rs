@@ -1,84 +1,115 @@
 mod actions;
+mod api_keys;
+mod app_overrides;
 #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
 mod apple_intelligence;
 mod audio_feedback;
 pub mod audio_toolkit;
+mod autostart;
+mod benchmark;
+mod call_mute;
+mod cancellation;
+mod chain;
 mod clipboard;
 mod commands;
+mod control_api;
+mod diagnostics;
+mod env_overrides;
+mod fatal;
+mod headless;
+mod health;
 mod helpers;
+mod history_crypto;
+mod history_export;
+mod http;
 mod input;
 mod input_hook;
+mod launch;
+#[cfg(feature = "llama-cpp")]
+mod llama_cpp;
 mod llm_client;
+mod llm_error;
 mod llm_types;
+mod logging;
 mod managers;
+mod meeting;
+mod microphone_permission;
+mod modes;
+mod mqtt;
+mod note;
+mod notifications;
+mod notion;
+mod obs;
+mod obsidian;
+mod output;
 mod overlay;
+mod pipeline;
+mod playback;
+mod portable;
+mod power;
+mod preview;
+mod pricing;
+mod profiles;
+mod progress;
+mod recovery;
+mod retry;
+mod rich_text;
+mod scripting;
+mod secure_input;
+mod selection;
 mod settings;
+mod settings_events;
+mod settings_validation;
+mod settings_watcher;
+mod shell_command;
 mod shortcut;
+mod shutdown;
 mod signal_handle;
+mod snippets;
+mod spacing;
+mod stats;
+mod streaming;
+mod systemd;
+mod template;
+mod throttle;
+mod token_budget;
+mod tools;
 mod tray;
+mod updater;
 mod utils;
+mod voice_command;
+mod wake_word;
+mod warmup;
+#[cfg(target_os = "linux")]
+mod wayland;
+mod websocket;
+mod widget;
 use specta_typescript::{BigIntExportBehavior, Typescript};
 use tauri_specta::{collect_commands, Builder};
 
-use env_filter::Builder as EnvFilterBuilder;
 use managers::audio::AudioRecordingManager;
+use managers::conversation::ConversationManager;
 use managers::history::HistoryManager;
 use managers::model::ModelManager;
 use managers::transcription::TranscriptionManager;
+use managers::usage::UsageManager;
 #[cfg(unix)]
 use signal_hook::consts::SIGUSR2;
 #[cfg(unix)]
 use signal_hook::iterator::Signals;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::image::Image;
 
 use tauri::tray::TrayIconBuilder;
 use tauri::Emitter;
 use tauri::{AppHandle, Manager};
-use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
-use tauri_plugin_log::{Builder as LogBuilder, RotationStrategy, Target, TargetKind};
-
-use crate::settings::get_settings;
-
-// Global atomic to store the file log level filter
-// We use u8 to store the log::LevelFilter as a number
-pub static FILE_LOG_LEVEL: AtomicU8 = AtomicU8::new(log::LevelFilter::Debug as u8);
-
-fn level_filter_from_u8(value: u8) -> log::LevelFilter {
-    match value {
-        0 => log::LevelFilter::Off,
-        1 => log::LevelFilter::Error,
-        2 => log::LevelFilter::Warn,
-        3 => log::LevelFilter::Info,
-        4 => log::LevelFilter::Debug,
-        5 => log::LevelFilter::Trace,
-        _ => log::LevelFilter::Trace,
-    }
-}
+use tauri_plugin_autostart::MacosLauncher;
+use tauri_plugin_deep_link::DeepLinkExt;
 
-fn build_console_filter() -> env_filter::Filter {
-    let mut builder = EnvFilterBuilder::new();
-
-    match std::env::var("RUST_LOG") {
-        Ok(spec) if !spec.trim().is_empty() => {
-            if let Err(err) = builder.try_parse(&spec) {
-                log::warn!(
-                    "Ignoring invalid RUST_LOG value '{}': {}. Falling back to info-level console logging",
-                    spec,
-                    err
-                );
-                builder.filter_level(log::LevelFilter::Info);
-            }
-        }
-        _ => {
-            builder.filter_level(log::LevelFilter::Info);
-        }
-    }
-
-    builder.build()
-}
+use crate::settings::{get_settings, AppSettings};
+use crate::settings_events::SettingsSection;
+use crate::settings_validation::SettingsError;
 
 #[derive(Default)]
 struct ShortcutToggleStates {
@@ -88,8 +119,8 @@ struct ShortcutToggleStates {
 
 type ManagedToggleState = Mutex<ShortcutToggleStates>;
 
-fn show_main_window(app: &AppHandle) {
-    if let Some(main_window) = app.get_webview_window("main") {
+pub(crate) fn show_main_window(app: &AppHandle) {
+    if let Some(main_window) = headless::main_window(app) {
         // First, ensure the window is visible
         if let Err(e) = main_window.show() {
             log::error!("Failed to show window: {}", e);
@@ -115,6 +146,9 @@ fn initialize_core_logic(app_handle: &AppHandle) {
     let enigo_state = input::EnigoState::new().expect("Failed to initialize input state (Enigo)");
     app_handle.manage(enigo_state);
 
+    // Before the managers, which pick lighter behavior in low-power mode
+    power::init(app_handle);
+
     // Initialize the managers
     let recording_manager = Arc::new(
         AudioRecordingManager::new(app_handle).expect("Failed to initialize recording manager"),
@@ -127,16 +161,20 @@ fn initialize_core_logic(app_handle: &AppHandle) {
     );
     let history_manager =
         Arc::new(HistoryManager::new(app_handle).expect("Failed to initialize history manager"));
+    let usage_manager =
+        Arc::new(UsageManager::new(app_handle).expect("Failed to initialize usage manager"));
 
     // Add managers to Tauri's managed state
     app_handle.manage(recording_manager.clone());
     app_handle.manage(model_manager.clone());
     app_handle.manage(transcription_manager.clone());
     app_handle.manage(history_manager.clone());
+    app_handle.manage(usage_manager.clone());
+    app_handle.manage(Arc::new(ConversationManager::default()));
 
     // Initialize the keyboard shortcuts
     shortcut::init_shortcuts(app_handle);
-    
+
     // Initialize the global input hook for mouse button shortcuts
     input_hook::init_input_hooks(app_handle);
 
@@ -150,7 +188,7 @@ fn initialize_core_logic(app_handle: &AppHandle) {
     #[cfg(target_os = "macos")]
     {
         let settings = settings::get_settings(app_handle);
-        if settings.start_hidden {
+        if autostart::starts_hidden(&settings) || headless::is_headless() {
             let _ = app_handle.set_activation_policy(tauri::ActivationPolicy::Accessory);
         }
     }
@@ -192,6 +230,54 @@ fn initialize_core_logic(app_handle: &AppHandle) {
             "quit" => {
                 app.exit(0);
             }
+            "pause_shortcuts" => {
+                shortcut::set_shortcuts_paused(app.clone(), !shortcut::shortcuts_paused());
+            }
+            "meeting" => {
+                let result = if meeting::current().is_some() {
+                    meeting::stop(app)
+                } else {
+                    meeting::start(app).map(|_| ())
+                };
+                if let Err(e) = result {
+                    log::warn!("Failed to start or stop the meeting transcription: {}", e);
+                }
+            }
+            "wake_word" => {
+                let enabled = !settings::get_settings(app).wake_word_enabled;
+                if let Err(e) = shortcut::change_wake_word_enabled_setting(app.clone(), enabled) {
+                    log::warn!("Failed to switch the wake word: {}", e);
+                }
+            }
+            "copy_last_transcript" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    let result =
+                        commands::history::copy_last_transcript(app.clone(), app.state()).await;
+                    if let Err(e) = result {
+                        log::warn!("Failed to copy the last transcript: {}", e);
+                    }
+                });
+            }
+            id if id.starts_with(tray::MICROPHONE_MENU_PREFIX) => {
+                let device_name = id[tray::MICROPHONE_MENU_PREFIX.len()..].to_string();
+                if let Err(e) = commands::audio::set_selected_microphone(app.clone(), device_name) {
+                    log::warn!("Failed to switch microphone: {}", e);
+                }
+                // Check items toggle themselves when clicked, so redraw on failure too
+                tray::update_tray_menu(app, &tray::TrayIconState::Idle);
+            }
+            id if id.starts_with(profiles::TRAY_MENU_PREFIX) => {
+                let profile_id = id[profiles::TRAY_MENU_PREFIX.len()..].to_string();
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = profiles::switch_profile(&app, &profile_id) {
+                        log::warn!("Failed to switch profile: {}", e);
+                    }
+                    // Check items toggle themselves when clicked, so redraw on failure too
+                    tray::update_tray_menu(&app, &tray::TrayIconState::Idle);
+                });
+            }
             _ => {}
         })
         .build(app_handle)
@@ -201,20 +287,90 @@ fn initialize_core_logic(app_handle: &AppHandle) {
     // Initialize tray menu with idle state
     utils::update_tray_menu(app_handle, &utils::TrayIconState::Idle);
 
-    // Get the autostart manager and configure based on user setting
-    let autostart_manager = app_handle.autolaunch();
-    let settings = settings::get_settings(&app_handle);
-
-    if settings.autostart_enabled {
-        // Enable autostart if user has opted in
-        let _ = autostart_manager.enable();
-    } else {
-        // Disable autostart if user has opted out
-        let _ = autostart_manager.disable();
+    // Register or remove the login entry per the settings
+    if let Err(e) = autostart::sync(app_handle, &settings::get_settings(app_handle)) {
+        log::warn!("{}", e);
     }
 
     // Create the recording overlay window (hidden by default)
     utils::create_recording_overlay(app_handle);
+    widget::sync(app_handle);
+    if let Err(e) = control_api::sync(app_handle) {
+        log::warn!("{}", e);
+    }
+    websocket::init(app_handle);
+    if let Err(e) = websocket::sync(app_handle) {
+        log::warn!("{}", e);
+    }
+    mqtt::init(app_handle);
+    mqtt::sync(app_handle);
+    obs::init(app_handle);
+    obs::sync(app_handle);
+    call_mute::init(app_handle);
+    call_mute::sync(app_handle);
+    wake_word::init(app_handle);
+    wake_word::sync(app_handle);
+
+    // Subsystems reconfigure themselves when their settings are saved
+    settings_events::subscribe(SettingsSection::Shortcuts, shortcut::on_settings_changed);
+    settings_events::subscribe(SettingsSection::Audio, managers::audio::on_settings_changed);
+    settings_events::subscribe(SettingsSection::Audio, wake_word::on_settings_changed);
+    settings_events::subscribe(
+        SettingsSection::Providers,
+        managers::transcription::on_settings_changed,
+    );
+    #[cfg(feature = "llama-cpp")]
+    settings_events::subscribe(SettingsSection::Providers, llama_cpp::on_settings_changed);
+    settings_events::subscribe(SettingsSection::Overlay, overlay::on_settings_changed);
+    settings_events::subscribe(SettingsSection::Overlay, widget::on_settings_changed);
+    settings_events::subscribe(
+        SettingsSection::History,
+        managers::history::on_settings_changed,
+    );
+    settings_events::subscribe(
+        SettingsSection::Conversation,
+        managers::conversation::on_settings_changed,
+    );
+    settings_events::subscribe(SettingsSection::Profiles, tray::on_settings_changed);
+    settings_events::subscribe(SettingsSection::Audio, tray::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, tray::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, on_system_settings_changed);
+    settings_events::subscribe(SettingsSection::System, control_api::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, websocket::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, updater::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, mqtt::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, obs::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, call_mute::on_settings_changed);
+    settings_events::subscribe(SettingsSection::System, power::on_settings_changed);
+
+    managers::history::start_maintenance(app_handle);
+    recovery::init(app_handle);
+    warmup::init(app_handle);
+    health::init(app_handle);
+    secure_input::init(app_handle);
+    updater::init(app_handle);
+
+    // Apply hand edits of the settings file while running
+    settings_watcher::start(app_handle);
+}
+
+/// Apply the autostart and log level settings when they change
+fn on_system_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.autostart_enabled != next.autostart_enabled {
+        if let Err(e) = autostart::sync(app, next) {
+            log::warn!("{}", e);
+        }
+    }
+
+    if previous.log_level != next.log_level || previous.log_module_levels != next.log_module_levels
+    {
+        logging::apply(next);
+    }
+    Vec::new()
 }
 
 #[tauri::command]
@@ -231,9 +387,10 @@ fn trigger_update_check(app: AppHandle) -> Result<(), String> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Parse console logging directives from RUST_LOG, falling back to info-level logging
-    // when the variable is unset
-    let console_filter = build_console_filter();
+    if let Some(code) = systemd::run_cli() {
+        std::process::exit(code);
+    }
+    fatal::install_panic_hook();
 
     let specta_builder = Builder::<tauri::Wry>::new().commands(collect_commands![
         shortcut::change_binding,
@@ -244,15 +401,50 @@ pub fn run() {
         shortcut::change_sound_theme_setting,
         shortcut::change_start_hidden_setting,
         shortcut::change_autostart_setting,
+        shortcut::change_autostart_minimized_setting,
+        shortcut::change_autostart_delay_setting,
         shortcut::change_translate_to_english_setting,
         shortcut::change_selected_language_setting,
         shortcut::change_overlay_position_setting,
+        shortcut::change_overlay_display_anchor_setting,
+        shortcut::change_overlay_click_through_setting,
+        shortcut::change_widget_enabled_setting,
+        shortcut::change_allow_link_actions_setting,
+        shortcut::change_control_api_enabled_setting,
+        shortcut::change_control_api_port_setting,
+        shortcut::change_websocket_enabled_setting,
+        shortcut::change_websocket_port_setting,
+        shortcut::change_mqtt_enabled_setting,
+        shortcut::change_mqtt_broker_setting,
+        shortcut::change_mqtt_publish_transcripts_setting,
+        shortcut::change_call_mute_enabled_setting,
+        shortcut::change_obs_enabled_setting,
+        shortcut::change_obs_connection_setting,
+        shortcut::change_obs_clear_after_setting,
+        shortcut::set_shortcuts_paused,
+        shortcut::get_shortcuts_paused,
         shortcut::change_debug_mode_setting,
+        shortcut::change_notification_setting,
         shortcut::change_word_correction_threshold_setting,
         shortcut::change_paste_method_setting,
+        shortcut::change_typing_speed_setting,
+        shortcut::change_undo_method_setting,
+        shortcut::change_output_mode_setting,
+        shortcut::change_note_file_path_setting,
+        shortcut::change_restore_clipboard_setting,
+        shortcut::change_clipboard_restore_delay_setting,
+        shortcut::change_markdown_rich_text_setting,
+        shortcut::change_smart_spacing_setting,
+        shortcut::change_streaming_injection_setting,
         shortcut::change_clipboard_handling_setting,
+        shortcut::change_gemini_safety_threshold_setting,
         shortcut::change_post_process_enabled_setting,
         shortcut::change_post_process_base_url_setting,
+        shortcut::change_post_process_api_version_setting,
+        shortcut::change_post_process_fallback_api_keys_setting,
+        shortcut::change_online_provider_fallback_api_keys_setting,
+        shortcut::change_post_process_service_tier_setting,
+        shortcut::change_post_process_extra_body_setting,
         shortcut::change_post_process_api_key_setting,
         shortcut::change_post_process_model_setting,
         shortcut::set_post_process_provider,
@@ -265,20 +457,66 @@ pub fn run() {
         shortcut::suspend_binding,
         shortcut::resume_binding,
         shortcut::change_mute_while_recording_setting,
+        shortcut::change_recording_memory_limit_setting,
+        shortcut::change_wake_word_enabled_setting,
+        shortcut::change_wake_word_phrase_setting,
+        shortcut::change_wake_word_sensitivity_setting,
         shortcut::change_append_trailing_space_setting,
+        shortcut::change_llama_cpp_model_path_setting,
+        shortcut::change_llama_cpp_gpu_layers_setting,
+        shortcut::change_network_max_attempts_setting,
+        shortcut::change_action_timeout_setting,
+        shortcut::change_proxy_url_setting,
+        shortcut::change_custom_ca_path_setting,
+        shortcut::change_hook_script_path_setting,
+        shortcut::change_conversation_context_enabled_setting,
+        shortcut::change_conversation_context_turns_setting,
+        shortcut::change_conversation_context_window_setting,
+        shortcut::change_monthly_llm_budget_setting,
+        shortcut::change_translate_target_language_setting,
+        shortcut::change_long_transcript_strategy_setting,
         shortcut::change_app_language_setting,
         shortcut::change_update_checks_setting,
+        shortcut::change_update_channel_setting,
         shortcut::change_use_online_provider_setting,
         shortcut::change_online_provider_id_setting,
         shortcut::change_online_provider_api_key_setting,
         shortcut::change_online_provider_model_setting,
         trigger_update_check,
         commands::cancel_operation,
+        commands::cancel_progress_operation,
         commands::get_app_dir_path,
         commands::get_app_settings,
+        commands::get_settings_errors,
         commands::get_default_settings,
+        commands::reset_settings_section,
+        commands::regenerate_control_api_token,
         commands::get_log_dir_path,
         commands::set_log_level,
+        commands::set_log_module_level,
+        commands::create_diagnostic_bundle,
+        commands::run_benchmark,
+        commands::get_fatal_errors,
+        commands::report_fatal_error,
+        commands::dismiss_fatal_errors,
+        commands::get_health,
+        commands::get_displays,
+        commands::get_secure_input,
+        commands::test_obs_connection,
+        commands::take_requested_section,
+        commands::start_meeting_transcription,
+        commands::stop_meeting_transcription,
+        commands::get_meeting_session,
+        commands::record_wake_word_sample,
+        commands::clear_wake_word_samples,
+        commands::get_wake_word_sample_count,
+        commands::get_autostart_status,
+        commands::get_service_status,
+        commands::install_service,
+        commands::uninstall_service,
+        commands::check_for_update,
+        commands::get_pending_update,
+        commands::restart_to_update,
         commands::open_recordings_folder,
         commands::open_log_dir,
         commands::open_app_data_dir,
@@ -294,6 +532,8 @@ pub fn run() {
         commands::models::has_any_models_available,
         commands::models::has_any_models_or_downloads,
         commands::models::get_recommended_first_model,
+        commands::llm_models::list_llm_models,
+        commands::connection::check_llm_connection,
         commands::audio::update_microphone_mode,
         commands::audio::get_microphone_mode,
         commands::audio::get_available_microphones,
@@ -307,15 +547,67 @@ pub fn run() {
         commands::audio::set_clamshell_microphone,
         commands::audio::get_clamshell_microphone,
         commands::audio::is_recording,
+        commands::audio::get_microphone_access,
+        commands::audio::open_microphone_settings,
         commands::transcription::set_model_unload_timeout,
+        commands::transcription::set_warm_up,
+        commands::transcription::set_low_power_mode,
         commands::transcription::get_model_load_status,
         commands::transcription::unload_model_manually,
         commands::history::get_history_entries,
+        commands::history::get_history_page,
+        commands::onboarding::get_setup_status,
+        commands::onboarding::request_capability,
+        commands::onboarding::run_injection_test,
+        commands::history::get_last_transcript,
+        commands::history::copy_last_transcript,
+        commands::history::search_history,
+        commands::history::export_history,
+        commands::history::list_orphaned_recordings,
+        commands::history::recover_orphaned_recording,
+        commands::history::discard_orphaned_recording,
+        commands::history::reprocess_history_entry,
+        commands::history::get_history_revisions,
+        commands::history::get_stats,
         commands::history::toggle_history_entry_saved,
         commands::history::get_audio_file_path,
+        commands::history::play_history_audio,
+        commands::history::stop_history_audio,
         commands::history::delete_history_entry,
         commands::history::update_history_limit,
         commands::history::update_recording_retention_period,
+        commands::history::update_history_retention_days,
+        commands::history::update_history_enabled,
+        commands::history::update_purge_audio_only,
+        commands::history::update_recording_storage_limit,
+        commands::history::update_meeting_notes_dir,
+        commands::history::update_meeting_save_to_history,
+        commands::history::update_history_encryption,
+        commands::usage::get_usage_stats,
+        commands::usage::get_cost_stats,
+        commands::usage::get_api_key_usage,
+        commands::usage::set_custom_model_price,
+        commands::conversation::reset_conversation,
+        commands::actions::get_action_configs,
+        commands::actions::set_action_config,
+        commands::actions::delete_action_config,
+        commands::actions::create_custom_action,
+        commands::actions::rename_custom_action,
+        commands::actions::delete_custom_action,
+        commands::preview::get_pending_preview,
+        commands::preview::accept_preview,
+        commands::preview::discard_preview,
+        commands::widget::toggle_widget_recording,
+        commands::snippets::get_snippets,
+        commands::snippets::set_snippet,
+        commands::snippets::delete_snippet,
+        commands::profiles::get_profiles,
+        commands::profiles::save_profile,
+        commands::profiles::delete_profile,
+        commands::profiles::switch_profile,
+        commands::app_overrides::get_app_overrides,
+        commands::app_overrides::set_app_override,
+        commands::app_overrides::delete_app_override,
         helpers::clamshell::is_laptop,
     ]);
 
@@ -328,29 +620,11 @@ pub fn run() {
         .expect("Failed to export typescript bindings");
 
     #[allow(unused_mut)]
-    let mut builder = tauri::Builder::default().plugin(
-        LogBuilder::new()
-            .level(log::LevelFilter::Trace) // Set to most verbose level globally
-            .max_file_size(500_000)
-            .rotation_strategy(RotationStrategy::KeepOne)
-            .clear_targets()
-            .targets([
-                // Console output respects RUST_LOG environment variable
-                Target::new(TargetKind::Stdout).filter({
-                    let console_filter = console_filter.clone();
-                    move |metadata| console_filter.enabled(metadata)
-                }),
-                // File logs respect the user's settings (stored in FILE_LOG_LEVEL atomic)
-                Target::new(TargetKind::LogDir {
-                    file_name: Some("babbl".into()),
-                })
-                .filter(|metadata| {
-                    let file_level = FILE_LOG_LEVEL.load(Ordering::Relaxed);
-                    metadata.level() <= level_filter_from_u8(file_level)
-                }),
-            ])
-            .build(),
-    );
+    let mut builder = tauri::Builder::default()
+        // First, so a second launch exits before anything else starts
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            launch::handle_second_instance(app, &args);
+        }));
 
     #[cfg(target_os = "macos")]
     {
@@ -358,35 +632,70 @@ pub fn run() {
     }
 
     builder
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            show_main_window(app);
-        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_macos_permissions::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_autostart::init(
             MacosLauncher::LaunchAgent,
-            Some(vec![]),
+            Some(autostart::launch_args()),
         ))
         .manage(Mutex::new(ShortcutToggleStates::default()))
+        .register_asynchronous_uri_scheme_protocol(
+            commands::history::AUDIO_PROTOCOL,
+            |ctx, request, responder| {
+                let app = ctx.app_handle().clone();
+                // Decrypting a long recording shouldn't hold up the webview
+                std::thread::spawn(move || {
+                    responder.respond(commands::history::audio_protocol_response(&app, &request))
+                });
+            },
+        )
         .setup(move |app| {
             let settings = get_settings(&app.handle());
-            let tauri_log_level: tauri_plugin_log::LogLevel = settings.log_level.into();
-            let file_log_level: log::Level = tauri_log_level.into();
-            // Store the file log level in the atomic for the filter to use
-            FILE_LOG_LEVEL.store(file_log_level.to_level_filter() as u8, Ordering::Relaxed);
+            logging::init(app.handle(), &settings);
+            fatal::init(app.handle());
+            if let Some(dir) = portable::data_dir() {
+                log::info!("Running portable, keeping data in {:?}", dir);
+            }
             let app_handle = app.handle().clone();
 
+            if headless::is_headless() {
+                log::info!("Running headless, without the main window");
+            } else {
+                headless::main_window(&app_handle);
+            }
             initialize_core_logic(&app_handle);
 
+            // Linux and Windows start Babbl with the link as its argument,
+            // macOS hands links to the running app
+            #[cfg(any(target_os = "linux", windows))]
+            {
+                if let Err(e) = app.deep_link().register_all() {
+                    log::warn!("Failed to register {}:// links: {}", launch::URL_SCHEME, e);
+                }
+            }
+            #[cfg(target_os = "macos")]
+            {
+                let handle = app_handle.clone();
+                app.deep_link().on_open_url(move |event| {
+                    launch::handle_urls(&handle, &event.urls());
+                });
+                if let Ok(Some(urls)) = app.deep_link().get_current() {
+                    launch::handle_urls(&app_handle, &urls);
+                }
+            }
+            launch::handle_startup(&app_handle);
+
             // Show main window only if not starting hidden
-            if !settings.start_hidden {
+            if !autostart::starts_hidden(&settings) && !headless::is_headless() {
                 if let Some(main_window) = app_handle.get_webview_window("main") {
                     main_window.show().unwrap();
                     main_window.set_focus().unwrap();
@@ -397,8 +706,10 @@ pub fn run() {
         })
         .on_window_event(|window, event| match event {
             tauri::WindowEvent::CloseRequested { api, .. } => {
-                api.prevent_close();
-                let _res = window.hide();
+                if !headless::destroys_on_close(window.label()) {
+                    api.prevent_close();
+                    let _res = window.hide();
+                }
                 #[cfg(target_os = "macos")]
                 {
                     let res = window
@@ -409,14 +720,25 @@ pub fn run() {
                     }
                 }
             }
+            tauri::WindowEvent::Moved(position) if window.label() == "widget" => {
+                widget::on_moved(window, *position);
+            }
+            tauri::WindowEvent::Focused(true) if window.label() == "main" => {
+                notifications::on_main_window_focused(window.app_handle());
+            }
             tauri::WindowEvent::ThemeChanged(theme) => {
                 log::info!("Theme changed to: {:?}", theme);
-                // Update tray icon to match new theme, maintaining idle state
-                utils::change_tray_icon(&window.app_handle(), utils::TrayIconState::Idle);
+                // Update tray icon to match new theme, keeping the pipeline state
+                utils::change_tray_icon(&window.app_handle(), pipeline::tray_icon_state());
             }
             _ => {}
         })
         .invoke_handler(specta_builder.invoke_handler())
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { code, api, .. } = event {
+                shutdown::on_exit_requested(app, code, &api);
+            }
+        });
 }
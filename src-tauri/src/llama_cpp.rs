@@ -0,0 +1,233 @@
+use crate::settings::AppSettings;
+use crate::settings_validation::SettingsError;
+use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::llama_backend::LlamaBackend;
+use llama_cpp_2::llama_batch::LlamaBatch;
+use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::{AddBos, LlamaChatMessage, LlamaModel, Special};
+use llama_cpp_2::sampling::LlamaSampler;
+use log::{debug, info};
+use once_cell::sync::{Lazy, OnceCell};
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::AppHandle;
+
+/// Context size used even when the model was trained on a longer one;
+/// cleanup prompts are short and the KV cache grows with the context.
+const MAX_CONTEXT_TOKENS: u32 = 8192;
+
+/// Tokens evaluated per decode call while reading the prompt
+const BATCH_SIZE: usize = 512;
+
+/// Completion limit when the action doesn't set one
+pub const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+/// llama.cpp may only be initialised once per process
+static BACKEND: OnceCell<LlamaBackend> = OnceCell::new();
+
+struct LoadedModel {
+    path: String,
+    gpu_layers: u32,
+    model: LlamaModel,
+}
+
+/// The last loaded model, kept in memory between dictations. Loading a 3-8B
+/// model takes seconds, so it's only reloaded when the path or layers change.
+static MODEL: Lazy<Mutex<Option<LoadedModel>>> = Lazy::new(|| Mutex::new(None));
+
+/// Sampling parameters of one completion
+#[derive(Debug, Clone)]
+pub struct GenerationParams {
+    pub max_tokens: u32,
+    /// `None` or 0 selects greedy decoding
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub stop: Vec<String>,
+}
+
+fn backend() -> Result<&'static LlamaBackend, String> {
+    BACKEND.get_or_try_init(|| {
+        LlamaBackend::init().map_err(|e| format!("Failed to initialise llama.cpp: {}", e))
+    })
+}
+
+fn build_sampler(params: &GenerationParams) -> LlamaSampler {
+    match params.temperature.filter(|temperature| *temperature > 0.0) {
+        Some(temperature) => LlamaSampler::chain_simple([
+            LlamaSampler::top_p(params.top_p.unwrap_or(1.0), 1),
+            LlamaSampler::temp(temperature),
+            LlamaSampler::dist(rand::random()),
+        ]),
+        None => LlamaSampler::greedy(),
+    }
+}
+
+/// Load the model at `model_path` into `cached`, unless it's there already
+fn load<'a>(
+    cached: &'a mut Option<LoadedModel>,
+    backend: &LlamaBackend,
+    model_path: &str,
+    gpu_layers: u32,
+) -> Result<&'a LlamaModel, String> {
+    let stale = cached
+        .as_ref()
+        .is_none_or(|loaded| loaded.path != model_path || loaded.gpu_layers != gpu_layers);
+    if stale {
+        // Free the previous model before loading the next one
+        *cached = None;
+        info!(
+            "Loading GGUF model '{}' with {} GPU layers",
+            model_path, gpu_layers
+        );
+        let started = Instant::now();
+        let model_params = LlamaModelParams::default().with_n_gpu_layers(gpu_layers);
+        let model = LlamaModel::load_from_file(backend, model_path, &model_params)
+            .map_err(|e| format!("Failed to load model '{}': {}", model_path, e))?;
+        info!("Loaded GGUF model in {:?}", started.elapsed());
+        *cached = Some(LoadedModel {
+            path: model_path.to_string(),
+            gpu_layers,
+            model,
+        });
+    }
+    Ok(&cached.as_ref().unwrap().model)
+}
+
+/// Load the model ahead of the first completion. Blocks; call it from a
+/// blocking task.
+pub fn preload(model_path: &str, gpu_layers: u32) -> Result<(), String> {
+    let backend = backend()?;
+    load(&mut MODEL.lock().unwrap(), backend, model_path, gpu_layers).map(|_| ())
+}
+
+/// Run a chat completion against the GGUF model at `model_path`, offloading
+/// `gpu_layers` layers to the GPU when llama.cpp was built with GPU support.
+/// Blocks for the whole generation; call it from a blocking task.
+pub fn process_text(
+    model_path: &str,
+    gpu_layers: u32,
+    system_prompt: Option<&str>,
+    prompt: &str,
+    params: &GenerationParams,
+) -> Result<String, String> {
+    let backend = backend()?;
+    let mut cached = MODEL.lock().unwrap();
+    let model = load(&mut cached, backend, model_path, gpu_layers)?;
+
+    let mut messages = Vec::new();
+    if let Some(system_prompt) = system_prompt {
+        messages.push(
+            LlamaChatMessage::new("system".to_string(), system_prompt.to_string())
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    messages.push(
+        LlamaChatMessage::new("user".to_string(), prompt.to_string()).map_err(|e| e.to_string())?,
+    );
+    let template = model
+        .chat_template(None)
+        .map_err(|e| format!("Model has no usable chat template: {}", e))?;
+    let formatted = model
+        .apply_chat_template(&template, &messages, true)
+        .map_err(|e| format!("Failed to apply chat template: {}", e))?;
+    let tokens = model
+        .str_to_token(&formatted, AddBos::Always)
+        .map_err(|e| format!("Failed to tokenize prompt: {}", e))?;
+
+    let n_ctx = model.n_ctx_train().clamp(1, MAX_CONTEXT_TOKENS);
+    let prompt_len = tokens.len() as u32;
+    if prompt_len >= n_ctx {
+        return Err(format!(
+            "Prompt is {} tokens but the context holds {}",
+            prompt_len, n_ctx
+        ));
+    }
+    let max_tokens = params.max_tokens.min(n_ctx - prompt_len);
+
+    let context_params = LlamaContextParams::default()
+        .with_n_ctx(NonZeroU32::new(n_ctx))
+        .with_n_batch(BATCH_SIZE as u32);
+    let mut ctx = model
+        .new_context(backend, context_params)
+        .map_err(|e| format!("Failed to create llama.cpp context: {}", e))?;
+
+    let started = Instant::now();
+    let mut batch = LlamaBatch::new(BATCH_SIZE, 1);
+    let last_index = tokens.len() - 1;
+    for (chunk_index, chunk) in tokens.chunks(BATCH_SIZE).enumerate() {
+        batch.clear();
+        for (offset, token) in chunk.iter().enumerate() {
+            let index = chunk_index * BATCH_SIZE + offset;
+            batch
+                .add(*token, index as i32, &[0], index == last_index)
+                .map_err(|e| e.to_string())?;
+        }
+        ctx.decode(&mut batch)
+            .map_err(|e| format!("Failed to evaluate prompt: {}", e))?;
+    }
+
+    let mut sampler = build_sampler(params);
+    let mut output = Vec::new();
+    let mut position = prompt_len as i32;
+    for _ in 0..max_tokens {
+        let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+        sampler.accept(token);
+        if model.is_eog_token(token) {
+            break;
+        }
+        let bytes = model
+            .token_to_bytes(token, Special::Plaintext)
+            .map_err(|e| e.to_string())?;
+        output.extend_from_slice(&bytes);
+
+        // Tokens can split multi-byte characters, so only check whole text
+        let text = String::from_utf8_lossy(&output);
+        if params.stop.iter().any(|stop| text.contains(stop.as_str())) {
+            break;
+        }
+
+        batch.clear();
+        batch
+            .add(token, position, &[0], true)
+            .map_err(|e| e.to_string())?;
+        position += 1;
+        ctx.decode(&mut batch)
+            .map_err(|e| format!("Failed to generate: {}", e))?;
+    }
+
+    let mut text = String::from_utf8_lossy(&output).into_owned();
+    if let Some(cut) = params
+        .stop
+        .iter()
+        .filter_map(|stop| text.find(stop.as_str()))
+        .min()
+    {
+        text.truncate(cut);
+    }
+    debug!(
+        "llama.cpp generated {} tokens in {:?}",
+        position - prompt_len as i32,
+        started.elapsed()
+    );
+    Ok(text.trim().to_string())
+}
+
+/// Drop the cached model, e.g. after the model path was cleared
+pub fn unload() {
+    if MODEL.lock().unwrap().take().is_some() {
+        debug!("Unloaded GGUF model");
+    }
+}
+
+/// Release the memory of the previous model as soon as another one is picked
+pub fn on_settings_changed(
+    _app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.llama_cpp_model_path != next.llama_cpp_model_path {
+        unload();
+    }
+    Vec::new()
+}
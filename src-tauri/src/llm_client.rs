@@ -1,101 +1,466 @@
-use crate::llm_types::ChatCompletionResponse;
-use crate::settings::PostProcessProvider;
-use reqwest::Client;
-use serde::Serialize;
-
-#[derive(Serialize)]
-struct ChatCompletionRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
+use crate::api_keys;
+use crate::llm_error::LlmError;
+use crate::llm_types::{
+    parse_stream_line, AnthropicMessagesRequest, AnthropicMessagesResponse, ChatCompletionRequest,
+    ChatCompletionResponse, GeminiGenerateContentRequest, GeminiGenerateContentResponse,
+    OllamaChatRequest, OllamaChatResponse, ToolCall, Usage,
+};
+use crate::retry::{send_with_retry, RetryPolicy};
+use crate::settings::{GeminiSafetyThreshold, PostProcessProvider};
+use futures_util::StreamExt;
+use log::debug;
+use reqwest::{Client, Response};
+
+/// Azure OpenAI data-plane version used when the provider doesn't set one
+pub const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Content, token usage and requested tool calls of a successful chat completion
+pub struct ChatCompletionOutput {
+    pub content: String,
+    pub usage: Option<Usage>,
+    pub tool_calls: Vec<ToolCall>,
 }
 
-#[derive(Serialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
+/// Wire format spoken by a provider's chat endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiFormat {
+    OpenAi,
+    /// OpenAI wire format behind Azure's deployment URLs and `api-key` header
+    AzureOpenAi,
+    Anthropic,
+    Ollama,
+    Gemini,
 }
 
-/// LLM client for making chat completion requests to OpenAI-compatible APIs
+impl ApiFormat {
+    pub fn for_provider(provider: &PostProcessProvider) -> Self {
+        match provider.id.as_str() {
+            "anthropic" => ApiFormat::Anthropic,
+            "azure" => ApiFormat::AzureOpenAi,
+            "ollama" => ApiFormat::Ollama,
+            "gemini" => ApiFormat::Gemini,
+            _ => ApiFormat::OpenAi,
+        }
+    }
+}
+
+/// LLM client for making chat completion requests to OpenAI-compatible
+/// and Anthropic Messages APIs
 pub struct LlmClient {
     http_client: Client,
     base_url: String,
-    api_key: String,
+    provider_id: String,
+    /// Primary key first, then fallbacks tried when a key is rejected or rate limited
+    api_keys: Vec<String>,
+    api_version: Option<String>,
+    format: ApiFormat,
+    gemini_safety_threshold: GeminiSafetyThreshold,
+    retry_policy: RetryPolicy,
+    service_tier: Option<String>,
+    extra_body: serde_json::Map<String, serde_json::Value>,
 }
 
 impl LlmClient {
-    /// Send a chat completion request and return the response content
-    pub async fn chat_completion(
+    /// Set the blocking threshold applied to Gemini safety categories
+    pub fn with_gemini_safety_threshold(mut self, threshold: GeminiSafetyThreshold) -> Self {
+        self.gemini_safety_threshold = threshold;
+        self
+    }
+
+    /// Set how transient failures (429, 5xx, timeouts) are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the service tier and extra body fields sent with every request that
+    /// doesn't set them itself (OpenAI-compatible providers only)
+    pub fn with_provider_fields(
+        mut self,
+        service_tier: Option<String>,
+        extra_body: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.service_tier = service_tier;
+        self.extra_body = extra_body;
+        self
+    }
+
+    /// Add keys to fall back to when the primary key is rejected or rate limited
+    pub fn with_fallback_api_keys(mut self, keys: Vec<String>) -> Self {
+        self.api_keys = api_keys::dedup_keys(self.api_keys.drain(..).chain(keys));
+        self
+    }
+
+    /// Send a prepared chat completion request and return the response content and usage
+    pub async fn send_chat_request(
         &self,
-        model: &str,
-        user_message: &str,
-    ) -> Result<String, String> {
-        let request = ChatCompletionRequest {
-            model: model.to_string(),
-            messages: vec![ChatMessage {
-                role: "user".to_string(),
-                content: user_message.to_string(),
-            }],
+        request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionOutput, LlmError> {
+        let keys = api_keys::ordered_keys(&self.provider_id, &self.api_keys);
+        let last = keys.len() - 1;
+        for (index, api_key) in keys.iter().enumerate() {
+            let result = match self.format {
+                ApiFormat::OpenAi | ApiFormat::AzureOpenAi => {
+                    self.send_openai_request(request, api_key).await
+                }
+                ApiFormat::Anthropic => self.send_anthropic_request(request, api_key).await,
+                ApiFormat::Ollama => self.send_ollama_request(request, api_key).await,
+                ApiFormat::Gemini => self.send_gemini_request(request, api_key).await,
+            };
+            api_keys::record_result(&self.provider_id, api_key, result.as_ref().map(|_| ()));
+
+            match result {
+                Err(e) if index < last && api_keys::is_key_error(&e) => {
+                    debug!(
+                        "Retrying provider '{}' with its next API key",
+                        self.provider_id
+                    );
+                }
+                result => return result,
+            }
+        }
+        unreachable!("ordered_keys never returns an empty list")
+    }
+
+    /// Like [`Self::send_chat_request`], calling `on_text` with the content
+    /// received so far while the response streams in. Only OpenAI-compatible
+    /// APIs stream; other formats call `on_text` once with the whole response.
+    pub async fn stream_chat_request(
+        &self,
+        request: &ChatCompletionRequest,
+        on_text: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<ChatCompletionOutput, LlmError> {
+        if !matches!(self.format, ApiFormat::OpenAi | ApiFormat::AzureOpenAi) {
+            let output = self.send_chat_request(request).await?;
+            on_text(&output.content);
+            return Ok(output);
+        }
+
+        let mut streamed = request.clone();
+        streamed
+            .extra_body
+            .insert("stream".to_string(), serde_json::Value::Bool(true));
+        streamed.extra_body.insert(
+            "stream_options".to_string(),
+            serde_json::json!({ "include_usage": true }),
+        );
+
+        // Keys are rejected before anything streams, so rotation works as usual
+        let keys = api_keys::ordered_keys(&self.provider_id, &self.api_keys);
+        let last = keys.len() - 1;
+        let mut response = None;
+        for (index, api_key) in keys.iter().enumerate() {
+            let result = self.post_openai_request(&streamed, api_key).await;
+            api_keys::record_result(&self.provider_id, api_key, result.as_ref().map(|_| ()));
+            match result {
+                Err(e) if index < last && api_keys::is_key_error(&e) => {
+                    debug!(
+                        "Retrying provider '{}' with its next API key",
+                        self.provider_id
+                    );
+                }
+                result => {
+                    response = Some(result?);
+                    break;
+                }
+            }
+        }
+        let response = response.expect("ordered_keys never returns an empty list");
+
+        let mut stream = response.bytes_stream();
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut content = String::new();
+        let mut usage = None;
+        while let Some(bytes) = stream.next().await {
+            buffer.extend_from_slice(&bytes?);
+            // Events can be split across network chunks, so only parse whole lines
+            while let Some(newline) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let chunk = match parse_stream_line(&String::from_utf8_lossy(&line)) {
+                    Ok(Some(chunk)) => chunk,
+                    Ok(None) => continue,
+                    Err(e) => return Err(LlmError::parse(e)),
+                };
+                if chunk.usage.is_some() {
+                    usage = chunk.usage;
+                }
+                let before = content.len();
+                for choice in chunk.choices {
+                    content.extend(choice.delta.content);
+                }
+                if content.len() > before {
+                    on_text(&content);
+                }
+            }
+        }
+
+        if content.is_empty() {
+            return Err(LlmError::parse("No content in response"));
+        }
+        Ok(ChatCompletionOutput {
+            content,
+            usage,
+            tool_calls: Vec::new(),
+        })
+    }
+
+    /// POST an OpenAI-style request and check the status
+    async fn post_openai_request(
+        &self,
+        request: &ChatCompletionRequest,
+        api_key: &str,
+    ) -> Result<Response, LlmError> {
+        // Azure addresses the deployment in the path; the model field is ignored
+        let url = match self.format {
+            ApiFormat::AzureOpenAi => {
+                azure_chat_url(&self.base_url, &request.model, self.api_version.as_deref())
+            }
+            _ => format!("{}/chat/completions", self.base_url),
         };
 
-        let url = format!("{}/chat/completions", self.base_url);
-        
-        let response = self
-            .http_client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
+        let merged;
+        let request = if self.service_tier.is_none() && self.extra_body.is_empty() {
+            request
+        } else {
+            merged = request.with_provider_fields(self.service_tier.as_deref(), &self.extra_body);
+            &merged
+        };
+
+        let response = send_with_retry(&self.retry_policy, "LLM request", || {
+            let builder = self
+                .http_client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .json(request);
+            Ok(if self.format == ApiFormat::AzureOpenAi {
+                builder.header("api-key", api_key)
+            } else {
+                builder.header("Authorization", format!("Bearer {}", api_key))
+            })
+        })
+        .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(format!("API request failed with status {}: {}", status, body));
+            return Err(LlmError::from_http_response(response).await);
         }
+        Ok(response)
+    }
 
-        let body = response
-            .text()
-            .await
-            .map_err(|e| format!("Failed to read response body: {}", e))?;
+    async fn send_openai_request(
+        &self,
+        request: &ChatCompletionRequest,
+        api_key: &str,
+    ) -> Result<ChatCompletionOutput, LlmError> {
+        let response = self.post_openai_request(request, api_key).await?;
+        let body = response.text().await?;
 
-        let parsed: ChatCompletionResponse = serde_json::from_str(&body)
-            .map_err(|e| format!("Failed to parse response: {} - body: {}", e, body))?;
+        let parsed: ChatCompletionResponse = serde_json::from_str(&body).map_err(|e| {
+            LlmError::parse(format!("Failed to parse response: {} - body: {}", e, body))
+        })?;
 
-        parsed
+        let message = parsed
             .choices
-            .first()
-            .and_then(|c| c.message.content.clone())
-            .ok_or_else(|| "No content in response".to_string())
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| LlmError::parse("No choices in response"))?;
+
+        // Tool call responses usually carry no text content
+        if message.content.is_none() && message.tool_calls.is_empty() {
+            return Err(LlmError::parse("No content in response"));
+        }
+
+        Ok(ChatCompletionOutput {
+            content: message.content.unwrap_or_default(),
+            usage: parsed.usage,
+            tool_calls: message.tool_calls,
+        })
+    }
+
+    async fn send_anthropic_request(
+        &self,
+        request: &ChatCompletionRequest,
+        api_key: &str,
+    ) -> Result<ChatCompletionOutput, LlmError> {
+        let url = format!("{}/messages", self.base_url);
+        let anthropic_request = AnthropicMessagesRequest::try_from(request)?;
+
+        let response = send_with_retry(&self.retry_policy, "Anthropic request", || {
+            Ok(self
+                .http_client
+                .post(&url)
+                .header("x-api-key", api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .header("Content-Type", "application/json")
+                .json(&anthropic_request))
+        })
+        .await?;
+
+        if !response.status().is_success() {
+            return Err(LlmError::from_http_response(response).await);
+        }
+
+        let body = response.text().await?;
+
+        let parsed: AnthropicMessagesResponse = serde_json::from_str(&body).map_err(|e| {
+            LlmError::parse(format!("Failed to parse response: {} - body: {}", e, body))
+        })?;
+
+        let content = parsed
+            .text()
+            .ok_or_else(|| LlmError::parse("No content in response"))?;
+
+        Ok(ChatCompletionOutput {
+            content,
+            usage: parsed.usage.map(Usage::from),
+            tool_calls: Vec::new(),
+        })
+    }
+
+    async fn send_ollama_request(
+        &self,
+        request: &ChatCompletionRequest,
+        api_key: &str,
+    ) -> Result<ChatCompletionOutput, LlmError> {
+        let url = format!("{}/api/chat", self.base_url);
+        let ollama_request = OllamaChatRequest::try_from(request)?;
+
+        let response = send_with_retry(&self.retry_policy, "Ollama request", || {
+            // Ollama needs no key, but one may be set when it sits behind an auth proxy
+            let mut builder = self.http_client.post(&url).json(&ollama_request);
+            if !api_key.trim().is_empty() {
+                builder = builder.header("Authorization", format!("Bearer {}", api_key));
+            }
+            Ok(builder)
+        })
+        .await?;
+
+        if !response.status().is_success() {
+            return Err(LlmError::from_http_response(response).await);
+        }
+
+        let body = response.text().await?;
+
+        let parsed: OllamaChatResponse = serde_json::from_str(&body).map_err(|e| {
+            LlmError::parse(format!("Failed to parse response: {} - body: {}", e, body))
+        })?;
+
+        let content = parsed
+            .message
+            .content
+            .clone()
+            .ok_or_else(|| LlmError::parse("No content in response"))?;
+
+        Ok(ChatCompletionOutput {
+            content,
+            usage: Some(parsed.usage()),
+            tool_calls: Vec::new(),
+        })
     }
+
+    async fn send_gemini_request(
+        &self,
+        request: &ChatCompletionRequest,
+        api_key: &str,
+    ) -> Result<ChatCompletionOutput, LlmError> {
+        let model = request.model.trim_start_matches("models/");
+        let url = format!("{}/models/{}:generateContent", self.base_url, model);
+        let gemini_request = GeminiGenerateContentRequest::from_chat_request(
+            request,
+            self.gemini_safety_threshold.as_api_str(),
+        )?;
+
+        let response = send_with_retry(&self.retry_policy, "Gemini request", || {
+            Ok(self
+                .http_client
+                .post(&url)
+                .header("x-goog-api-key", api_key)
+                .header("Content-Type", "application/json")
+                .json(&gemini_request))
+        })
+        .await?;
+
+        if !response.status().is_success() {
+            return Err(LlmError::from_http_response(response).await);
+        }
+
+        let body = response.text().await?;
+
+        let parsed: GeminiGenerateContentResponse = serde_json::from_str(&body).map_err(|e| {
+            LlmError::parse(format!("Failed to parse response: {} - body: {}", e, body))
+        })?;
+
+        Ok(ChatCompletionOutput {
+            content: parsed.text()?,
+            usage: parsed.usage_metadata.map(Usage::from),
+            tool_calls: Vec::new(),
+        })
+    }
+}
+
+/// Chat completions URL of an Azure OpenAI deployment. `base_url` is the
+/// resource endpoint; a pasted deployment or chat URL is cut back to it.
+pub fn azure_chat_url(base_url: &str, deployment: &str, api_version: Option<&str>) -> String {
+    let endpoint = match base_url.find("/openai") {
+        Some(index) => &base_url[..index],
+        None => base_url,
+    };
+    let api_version = api_version
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .unwrap_or(AZURE_DEFAULT_API_VERSION);
+    format!(
+        "{}/openai/deployments/{}/chat/completions?api-version={}",
+        endpoint.trim_end_matches('/'),
+        deployment.trim(),
+        api_version
+    )
 }
 
-/// Create an LLM client configured for the given provider
+/// Create an LLM client configured for the given provider. `http_client`
+/// carries the network settings and open connections, see
+/// [`crate::http::shared_client`].
 pub fn create_client(
     provider: &PostProcessProvider,
     api_key: String,
-) -> Result<LlmClient, String> {
+    http_client: Client,
+) -> LlmClient {
     let base_url = provider.base_url.trim_end_matches('/').to_string();
+    let format = ApiFormat::for_provider(provider);
 
-    let mut headers = reqwest::header::HeaderMap::new();
-    
-    // Add provider-specific headers
-    if provider.id == "anthropic" {
-        headers.insert(
-            "anthropic-version",
-            reqwest::header::HeaderValue::from_static("2023-06-01"),
-        );
+    LlmClient {
+        http_client,
+        base_url,
+        provider_id: provider.id.clone(),
+        api_keys: api_keys::dedup_keys([api_key]),
+        api_version: provider.api_version.clone(),
+        format,
+        gemini_safety_threshold: GeminiSafetyThreshold::BlockOnlyHigh,
+        retry_policy: RetryPolicy::default(),
+        service_tier: None,
+        extra_body: serde_json::Map::new(),
     }
+}
 
-    let http_client = reqwest::Client::builder()
-        .default_headers(headers)
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(LlmClient {
-        http_client,
-        base_url,
-        api_key,
-    })
+    #[test]
+    fn test_azure_chat_url() {
+        assert_eq!(
+            azure_chat_url("https://acme.openai.azure.com", "gpt-4o-mini", None),
+            "https://acme.openai.azure.com/openai/deployments/gpt-4o-mini/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(
+            azure_chat_url(
+                "https://acme.openai.azure.com/openai/deployments/old/chat/completions",
+                "cleanup",
+                Some("2025-01-01-preview")
+            ),
+            "https://acme.openai.azure.com/openai/deployments/cleanup/chat/completions?api-version=2025-01-01-preview"
+        );
+    }
 }
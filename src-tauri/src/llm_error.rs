@@ -0,0 +1,246 @@
+use crate::retry;
+use reqwest::{Response, StatusCode};
+use serde::Serialize;
+use specta::Type;
+use std::fmt;
+use std::time::Duration;
+
+/// Classified failure of an LLM or cloud transcription request.
+///
+/// Serialized with a `kind` tag so the frontend can pick a message and a fix
+/// without parsing provider error bodies.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LlmError {
+    /// The API key is missing, wrong or lacks access
+    Auth { message: String },
+    /// Too many requests; `retry_after_secs` is taken from the `Retry-After` header
+    RateLimited {
+        retry_after_secs: Option<u64>,
+        message: String,
+    },
+    /// The account is out of credits or over its billing quota
+    Quota { message: String },
+    /// The provider could not be reached
+    Network { message: String },
+    /// The configured model does not exist or isn't available to this key
+    InvalidModel { message: String },
+    /// The provider refused the prompt or the answer for safety reasons
+    ContentFilter { message: String },
+    /// The response could not be understood
+    Parse { message: String },
+    /// Anything else, e.g. invalid requests or server errors
+    Other { message: String },
+}
+
+impl LlmError {
+    /// Classify a non-success HTTP response from its status and body
+    pub fn from_response(status: StatusCode, retry_after: Option<Duration>, body: &str) -> Self {
+        let message = format!("API request failed with status {}: {}", status, body);
+        let lower = body.to_lowercase();
+
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            return LlmError::Auth { message };
+        }
+        if status == StatusCode::PAYMENT_REQUIRED
+            || lower.contains("insufficient_quota")
+            || lower.contains("billing")
+            || lower.contains("credit balance")
+        {
+            return LlmError::Quota { message };
+        }
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return LlmError::RateLimited {
+                retry_after_secs: retry_after.map(|d| d.as_secs()),
+                message,
+            };
+        }
+        if lower.contains("content_filter")
+            || lower.contains("content policy")
+            || lower.contains("safety")
+        {
+            return LlmError::ContentFilter { message };
+        }
+        if lower.contains("model")
+            && (status == StatusCode::NOT_FOUND
+                || lower.contains("not found")
+                || lower.contains("does not exist")
+                || lower.contains("model_not_found"))
+        {
+            return LlmError::InvalidModel { message };
+        }
+        // Some providers (e.g. Gemini) report bad keys as 400
+        if lower.contains("api key") || lower.contains("api_key_invalid") {
+            return LlmError::Auth { message };
+        }
+
+        LlmError::Other { message }
+    }
+
+    /// Classify a failed response, consuming its body
+    pub async fn from_http_response(response: Response) -> Self {
+        let status = response.status();
+        let retry_after = retry::retry_after(&response);
+        let body = response.text().await.unwrap_or_default();
+        Self::from_response(status, retry_after, &body)
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        LlmError::Parse {
+            message: message.into(),
+        }
+    }
+
+    pub fn content_filter(message: impl Into<String>) -> Self {
+        LlmError::ContentFilter {
+            message: message.into(),
+        }
+    }
+
+    /// Detailed message, including the provider's response where available
+    pub fn message(&self) -> &str {
+        match self {
+            LlmError::Auth { message }
+            | LlmError::RateLimited { message, .. }
+            | LlmError::Quota { message }
+            | LlmError::Network { message }
+            | LlmError::InvalidModel { message }
+            | LlmError::ContentFilter { message }
+            | LlmError::Parse { message }
+            | LlmError::Other { message } => message,
+        }
+    }
+
+    /// Short explanation shown to the user
+    pub fn user_message(&self) -> String {
+        match self {
+            LlmError::Auth { .. } => "The provider rejected your API key.".to_string(),
+            LlmError::RateLimited {
+                retry_after_secs: Some(seconds),
+                ..
+            } => format!(
+                "The provider is rate limiting requests. Try again in {} seconds.",
+                seconds
+            ),
+            LlmError::RateLimited { .. } => "The provider is rate limiting requests.".to_string(),
+            LlmError::Quota { .. } => "Your provider account is out of quota.".to_string(),
+            LlmError::Network { .. } => "Could not reach the provider.".to_string(),
+            LlmError::InvalidModel { .. } => "The selected model is not available.".to_string(),
+            LlmError::ContentFilter { .. } => {
+                "The provider blocked this request with its content filter.".to_string()
+            }
+            LlmError::Parse { .. } => {
+                "The provider returned a response Babbl could not read.".to_string()
+            }
+            LlmError::Other { .. } => "The request to the provider failed.".to_string(),
+        }
+    }
+
+    /// Suggested next step for the user
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            LlmError::Auth { .. } => "Check the API key for this provider in Settings.",
+            LlmError::RateLimited { .. } => {
+                "Wait a moment, or raise the rate limit of your provider account."
+            }
+            LlmError::Quota { .. } => "Add credits or check billing on your provider account.",
+            LlmError::Network { .. } => {
+                "Check your internet connection, proxy and the provider's base URL."
+            }
+            LlmError::InvalidModel { .. } => "Pick a different model in Settings.",
+            LlmError::ContentFilter { .. } => {
+                "Rephrase the dictation, or lower the safety threshold if the provider supports it."
+            }
+            LlmError::Parse { .. } => {
+                "Try again, or choose a model that follows the requested output format."
+            }
+            LlmError::Other { .. } => "See the logs for the provider's full response.",
+        }
+    }
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            return LlmError::parse(format!("Failed to read response body: {}", error));
+        }
+        LlmError::Network {
+            message: format!("HTTP request failed: {}", error),
+        }
+    }
+}
+
+/// Local failures such as invalid requests that never reached the provider
+impl From<String> for LlmError {
+    fn from(message: String) -> Self {
+        LlmError::Other { message }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_responses() {
+        assert!(matches!(
+            LlmError::from_response(StatusCode::UNAUTHORIZED, None, "bad key"),
+            LlmError::Auth { .. }
+        ));
+        assert_eq!(
+            LlmError::from_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                Some(Duration::from_secs(12)),
+                "slow down"
+            ),
+            LlmError::RateLimited {
+                retry_after_secs: Some(12),
+                message: "API request failed with status 429 Too Many Requests: slow down"
+                    .to_string(),
+            }
+        );
+        assert!(matches!(
+            LlmError::from_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                None,
+                r#"{"error":{"code":"insufficient_quota"}}"#
+            ),
+            LlmError::Quota { .. }
+        ));
+        assert!(matches!(
+            LlmError::from_response(
+                StatusCode::NOT_FOUND,
+                None,
+                "The model `gpt-9` does not exist"
+            ),
+            LlmError::InvalidModel { .. }
+        ));
+        assert!(matches!(
+            LlmError::from_response(
+                StatusCode::BAD_REQUEST,
+                None,
+                "API key not valid. Please pass a valid API key."
+            ),
+            LlmError::Auth { .. }
+        ));
+        assert!(matches!(
+            LlmError::from_response(StatusCode::INTERNAL_SERVER_ERROR, None, "oops"),
+            LlmError::Other { .. }
+        ));
+    }
+
+    #[test]
+    fn test_serializes_with_kind_tag() {
+        let json = serde_json::to_value(LlmError::parse("bad json")).unwrap();
+        assert_eq!(json["kind"], "parse");
+        assert_eq!(json["message"], "bad json");
+    }
+}
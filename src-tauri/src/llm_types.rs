@@ -1,4 +1,6 @@
-use serde::Deserialize;
+use crate::llm_error::LlmError;
+use serde::{Deserialize, Serialize};
+use specta::Type;
 
 /// Custom response types for OpenAI-compatible APIs that may have
 /// non-standard fields (like Groq's `service_tier: "on_demand"`)
@@ -11,8 +13,8 @@ pub struct ChatCompletionResponse {
     pub created: u64,
     pub model: String,
     pub choices: Vec<ChatChoice>,
-    #[serde(skip)]
-    pub usage: Option<serde_json::Value>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
     #[serde(skip)]
     pub service_tier: Option<String>,
 }
@@ -27,9 +29,1468 @@ pub struct ChatChoice {
     pub logprobs: Option<serde_json::Value>,
 }
 
+/// One server-sent event of a streamed chat completion
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub choices: Vec<ChatChunkChoice>,
+    /// Only in the last chunk, when `stream_options.include_usage` is set
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatChunkChoice {
+    #[serde(default)]
+    pub delta: ChatChunkDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ChatChunkDelta {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// Parse one line of a streamed response. Returns `Ok(None)` for lines
+/// without data (comments, keep-alives, `event:` lines) and the `[DONE]` marker.
+pub fn parse_stream_line(line: &str) -> Result<Option<ChatCompletionChunk>, String> {
+    let data = match line.trim().strip_prefix("data:") {
+        Some(data) => data.trim(),
+        None => return Ok(None),
+    };
+    if data.is_empty() || data == "[DONE]" {
+        return Ok(None);
+    }
+    serde_json::from_str(data)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse stream chunk: {} - data: {}", e, data))
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 pub struct ChatMessage {
     pub role: String,
     pub content: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// A function call requested by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "default_tool_type")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON-encoded arguments, as produced by the model (may be malformed)
+    #[serde(default)]
+    pub arguments: String,
+}
+
+fn default_tool_type() -> String {
+    "function".to_string()
+}
+
+/// A tool the model may call, described by a JSON Schema for its arguments
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tool {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: FunctionDefinition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+impl Tool {
+    pub fn function(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        parameters: serde_json::Value,
+    ) -> Self {
+        Self {
+            kind: default_tool_type(),
+            function: FunctionDefinition {
+                name: name.into(),
+                description: description.into(),
+                parameters,
+            },
+        }
+    }
+}
+
+/// Whether the model may call the advertised tools; sent as a mode string
+/// such as `"auto"`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    Mode(String),
+}
+
+impl ToolChoice {
+    /// Let the model decide whether to call a tool
+    pub fn auto() -> Self {
+        ToolChoice::Mode("auto".to_string())
+    }
+}
+
+/// Token accounting returned alongside a completion.
+/// Providers add their own extras (e.g. Groq's `queue_time`), which are ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    #[serde(default)]
+    pub completion_tokens: u32,
+    #[serde(default)]
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+/// Audio payload for multimodal chat requests (base64-encoded)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InputAudio {
+    pub data: String,
+    pub format: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    InputAudio { input_audio: InputAudio },
+}
+
+/// Message content is either a plain string or a list of multimodal parts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatRequestMessage {
+    pub role: ChatRole,
+    pub content: MessageContent,
+    /// Calls made by the assistant, echoed back when continuing a tool conversation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// Id of the call a `tool` message answers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatRequestMessage {
+    pub fn text(role: ChatRole, content: impl Into<String>) -> Self {
+        Self::with_content(role, MessageContent::Text(content.into()))
+    }
+
+    pub fn parts(role: ChatRole, parts: Vec<ContentPart>) -> Self {
+        Self::with_content(role, MessageContent::Parts(parts))
+    }
+
+    fn with_content(role: ChatRole, content: MessageContent) -> Self {
+        Self {
+            role,
+            content,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    /// Assistant turn that requested `tool_calls`
+    pub fn assistant_tool_calls(content: Option<String>, tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls: Some(tool_calls),
+            ..Self::text(ChatRole::Assistant, content.unwrap_or_default())
+        }
+    }
+
+    /// Result of a tool call, sent back to the model
+    pub fn tool_result(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.into()),
+            ..Self::text(ChatRole::Tool, content)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+    pub strict: bool,
+}
+
+impl ResponseFormat {
+    pub fn json_schema(name: impl Into<String>, schema: serde_json::Value) -> Self {
+        ResponseFormat::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                name: name.into(),
+                schema,
+                strict: false,
+            },
+        }
+    }
+
+    /// Have the provider enforce the schema exactly; OpenAI then requires every
+    /// property to be listed as required and `additionalProperties: false`
+    pub fn strict(self, strict: bool) -> Self {
+        match self {
+            ResponseFormat::JsonSchema { mut json_schema } => {
+                json_schema.strict = strict;
+                ResponseFormat::JsonSchema { json_schema }
+            }
+        }
+    }
+
+    /// Instruction for providers without native structured output support
+    fn as_instruction(&self) -> String {
+        match self {
+            ResponseFormat::JsonSchema { json_schema } => format!(
+                "Respond only with a JSON object that matches this JSON Schema:\n{}",
+                json_schema.schema
+            ),
+        }
+    }
+}
+
+/// Parse a structured response, tolerating a surrounding Markdown code fence
+pub fn parse_structured_output(content: &str) -> Result<serde_json::Value, String> {
+    let trimmed = content.trim();
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(trimmed);
+
+    serde_json::from_str(unfenced.trim())
+        .map_err(|e| format!("Model did not return valid JSON: {}", e))
+}
+
+/// A structured response as text to type: strings as they are, lists one
+/// `- item` per line and objects one `Field: value` per line. An object with
+/// a single field is just that field's value.
+pub fn format_structured_output(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| format!("- {}", format_inline(item)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Object(fields) if fields.len() == 1 => {
+            format_structured_output(fields.values().next().unwrap_or(&Value::Null))
+        }
+        Value::Object(fields) => fields
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(name, value)| match value {
+                Value::Array(_) | Value::Object(_) => {
+                    format!(
+                        "{}:\n{}",
+                        field_label(name),
+                        format_structured_output(value)
+                    )
+                }
+                _ => format!("{}: {}", field_label(name), format_inline(value)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => value.to_string(),
+    }
+}
+
+/// `value` on one line, the values of an object separated by commas
+fn format_inline(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(format_inline)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Object(fields) => fields
+            .values()
+            .filter(|value| !value.is_null())
+            .map(format_inline)
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => value.to_string(),
+    }
+}
+
+/// `due_date` as `Due date`
+fn field_label(name: &str) -> String {
+    let name = name.replace(['_', '-'], " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+/// Request body for OpenAI-compatible `/chat/completions` endpoints.
+/// Use [`ChatCompletionRequest::builder`] to construct one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatRequestMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    /// Processing tier, e.g. Groq's `flex` or OpenAI's `priority`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
+    /// Provider-specific fields merged into the top level of the body, such as
+    /// OpenRouter's `provider` routing block. Only sent to OpenAI-compatible APIs.
+    #[serde(flatten)]
+    pub extra_body: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Body fields with a typed counterpart, which `extra_body` must not override
+const TYPED_REQUEST_FIELDS: &[&str] = &[
+    "model",
+    "messages",
+    "temperature",
+    "top_p",
+    "max_tokens",
+    "response_format",
+    "stop",
+    "tools",
+    "tool_choice",
+    "service_tier",
+];
+
+/// Parse user-supplied extra body fields (a JSON object) and check that they
+/// don't clash with the typed request fields
+pub fn parse_extra_body(text: &str) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("Extra body is not valid JSON: {}", e))?;
+    let extra_body = match value {
+        serde_json::Value::Object(map) => map,
+        _ => return Err("Extra body must be a JSON object".to_string()),
+    };
+    check_extra_body(&extra_body)?;
+    Ok(extra_body)
+}
+
+fn check_extra_body(extra_body: &serde_json::Map<String, serde_json::Value>) -> Result<(), String> {
+    match extra_body
+        .keys()
+        .find(|key| TYPED_REQUEST_FIELDS.contains(&key.as_str()))
+    {
+        Some(key) => Err(format!(
+            "Extra body must not set '{}', it has its own setting",
+            key
+        )),
+        None => Ok(()),
+    }
+}
+
+impl ChatCompletionRequest {
+    /// Tool calling is only translated for OpenAI-compatible endpoints
+    fn ensure_no_tools(&self, provider: &str) -> Result<(), String> {
+        let has_tool_messages = self
+            .messages
+            .iter()
+            .any(|message| message.role == ChatRole::Tool || message.tool_calls.is_some());
+        if self.tools.is_some() || has_tool_messages {
+            return Err(format!("Tool calling is not supported for {}", provider));
+        }
+        Ok(())
+    }
+
+    pub fn builder(model: impl Into<String>) -> ChatCompletionRequestBuilder {
+        ChatCompletionRequestBuilder {
+            request: ChatCompletionRequest {
+                model: model.into(),
+                messages: Vec::new(),
+                temperature: None,
+                top_p: None,
+                max_tokens: None,
+                response_format: None,
+                stop: None,
+                tools: None,
+                tool_choice: None,
+                service_tier: None,
+                extra_body: serde_json::Map::new(),
+            },
+        }
+    }
+
+    /// Fill in provider-level defaults the request doesn't set itself
+    pub fn with_provider_fields(
+        &self,
+        service_tier: Option<&str>,
+        extra_body: &serde_json::Map<String, serde_json::Value>,
+    ) -> ChatCompletionRequest {
+        let mut request = self.clone();
+        if request.service_tier.is_none() {
+            request.service_tier = service_tier.map(str::to_string);
+        }
+        for (key, value) in extra_body {
+            request
+                .extra_body
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        request
+    }
+}
+
+pub struct ChatCompletionRequestBuilder {
+    request: ChatCompletionRequest,
+}
+
+impl ChatCompletionRequestBuilder {
+    pub fn message(mut self, message: ChatRequestMessage) -> Self {
+        self.request.messages.push(message);
+        self
+    }
+
+    pub fn system(self, content: impl Into<String>) -> Self {
+        self.message(ChatRequestMessage::text(ChatRole::System, content))
+    }
+
+    pub fn user(self, content: impl Into<String>) -> Self {
+        self.message(ChatRequestMessage::text(ChatRole::User, content))
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.request.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.request.top_p = Some(top_p);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.request.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.request.response_format = Some(response_format);
+        self
+    }
+
+    pub fn stop(mut self, stop: Vec<String>) -> Self {
+        self.request.stop = if stop.is_empty() { None } else { Some(stop) };
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<Tool>) -> Self {
+        self.request.tools = if tools.is_empty() { None } else { Some(tools) };
+        self
+    }
+
+    pub fn tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.request.tool_choice = Some(tool_choice);
+        self
+    }
+
+    /// Validate and return the request
+    pub fn build(self) -> Result<ChatCompletionRequest, String> {
+        let request = self.request;
+
+        if request.model.trim().is_empty() {
+            return Err("Model must not be empty".to_string());
+        }
+        if request.messages.is_empty() {
+            return Err("At least one message is required".to_string());
+        }
+        if let Some(temperature) = request.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(format!(
+                    "Temperature must be between 0 and 2, got {}",
+                    temperature
+                ));
+            }
+        }
+        if let Some(top_p) = request.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err(format!("top_p must be between 0 and 1, got {}", top_p));
+            }
+        }
+        if request.max_tokens == Some(0) {
+            return Err("max_tokens must be greater than 0".to_string());
+        }
+        if request.stop.as_ref().is_some_and(|stop| stop.len() > 4) {
+            return Err("At most 4 stop sequences are allowed".to_string());
+        }
+        if request.tool_choice.is_some() && request.tools.is_none() {
+            return Err("tool_choice requires at least one tool".to_string());
+        }
+        if let Some(ResponseFormat::JsonSchema { json_schema }) = &request.response_format {
+            let valid_name = !json_schema.name.is_empty()
+                && json_schema.name.len() <= 64
+                && json_schema
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid_name {
+                return Err(format!("Invalid JSON schema name '{}'", json_schema.name));
+            }
+            if !json_schema.schema.is_object() {
+                return Err("JSON schema must be an object".to_string());
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+/// Anthropic requires `max_tokens`; used when the request leaves it unset
+pub const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Request body for Anthropic's `/messages` endpoint.
+/// System prompts live in a top-level field instead of the message list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnthropicMessagesRequest {
+    pub model: String,
+    pub max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnthropicMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl TryFrom<&ChatCompletionRequest> for AnthropicMessagesRequest {
+    type Error = String;
+
+    fn try_from(request: &ChatCompletionRequest) -> Result<Self, Self::Error> {
+        request.ensure_no_tools("Anthropic")?;
+
+        let mut system_parts = Vec::new();
+        let mut messages = Vec::new();
+
+        for message in &request.messages {
+            let text = match &message.content {
+                MessageContent::Text(text) => text.clone(),
+                MessageContent::Parts(parts) => {
+                    let mut texts = Vec::new();
+                    for part in parts {
+                        match part {
+                            ContentPart::Text { text } => texts.push(text.as_str()),
+                            ContentPart::InputAudio { .. } => {
+                                return Err("Anthropic models do not accept audio input".to_string())
+                            }
+                        }
+                    }
+                    texts.join("\n")
+                }
+            };
+
+            match message.role {
+                ChatRole::System => system_parts.push(text),
+                role => messages.push(AnthropicMessage {
+                    role,
+                    content: text,
+                }),
+            }
+        }
+
+        // Anthropic has no response_format, so ask for the shape in the system prompt
+        if let Some(instruction) = request
+            .response_format
+            .as_ref()
+            .map(ResponseFormat::as_instruction)
+        {
+            system_parts.push(instruction);
+        }
+
+        Ok(Self {
+            model: request.model.clone(),
+            max_tokens: request.max_tokens.unwrap_or(ANTHROPIC_DEFAULT_MAX_TOKENS),
+            system: if system_parts.is_empty() {
+                None
+            } else {
+                Some(system_parts.join("\n\n"))
+            },
+            messages,
+            temperature: request.temperature.map(|t| t.min(1.0)),
+            top_p: request.top_p,
+            stop_sequences: request.stop.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnthropicMessagesResponse {
+    pub content: Vec<AnthropicContentBlock>,
+    #[serde(default)]
+    pub usage: Option<AnthropicUsage>,
+}
+
+impl AnthropicMessagesResponse {
+    /// Concatenated text of all `text` content blocks
+    pub fn text(&self) -> Option<String> {
+        let text: String = self
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                AnthropicContentBlock::Text { text } => Some(text.as_str()),
+                AnthropicContentBlock::Other => None,
+            })
+            .collect();
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicContentBlock {
+    Text {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct AnthropicUsage {
+    #[serde(default)]
+    pub input_tokens: u32,
+    #[serde(default)]
+    pub output_tokens: u32,
+}
+
+impl From<AnthropicUsage> for Usage {
+    fn from(usage: AnthropicUsage) -> Self {
+        Usage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+            total_tokens: usage.input_tokens + usage.output_tokens,
+        }
+    }
+}
+
+/// Request body for Ollama's native `/api/chat` endpoint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OllamaChatRequest {
+    pub model: String,
+    pub messages: Vec<OllamaMessage>,
+    pub stream: bool,
+    /// Either `"json"` or a JSON Schema object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<OllamaOptions>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OllamaMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+}
+
+impl TryFrom<&ChatCompletionRequest> for OllamaChatRequest {
+    type Error = String;
+
+    fn try_from(request: &ChatCompletionRequest) -> Result<Self, Self::Error> {
+        request.ensure_no_tools("Ollama")?;
+
+        let mut messages = Vec::with_capacity(request.messages.len());
+        for message in &request.messages {
+            let content = match &message.content {
+                MessageContent::Text(text) => text.clone(),
+                MessageContent::Parts(parts) => {
+                    let mut texts = Vec::new();
+                    for part in parts {
+                        match part {
+                            ContentPart::Text { text } => texts.push(text.as_str()),
+                            ContentPart::InputAudio { .. } => {
+                                return Err("Ollama models do not accept audio input".to_string())
+                            }
+                        }
+                    }
+                    texts.join("\n")
+                }
+            };
+            messages.push(OllamaMessage {
+                role: message.role,
+                content,
+            });
+        }
+
+        let options = OllamaOptions {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            num_predict: request.max_tokens,
+            stop: request.stop.clone(),
+        };
+
+        Ok(Self {
+            model: request.model.clone(),
+            messages,
+            stream: false,
+            format: request
+                .response_format
+                .as_ref()
+                .map(|ResponseFormat::JsonSchema { json_schema }| json_schema.schema.clone()),
+            options: if options == OllamaOptions::default() {
+                None
+            } else {
+                Some(options)
+            },
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaChatResponse {
+    pub message: ChatMessage,
+    #[serde(default)]
+    pub prompt_eval_count: u32,
+    #[serde(default)]
+    pub eval_count: u32,
+}
+
+impl OllamaChatResponse {
+    pub fn usage(&self) -> Usage {
+        Usage {
+            prompt_tokens: self.prompt_eval_count,
+            completion_tokens: self.eval_count,
+            total_tokens: self.prompt_eval_count + self.eval_count,
+        }
+    }
+}
+
+/// Harm categories covered by the safety settings sent with Gemini requests
+pub const GEMINI_HARM_CATEGORIES: &[&str] = &[
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+/// Request body for Gemini's native `models/{model}:generateContent` endpoint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiGenerateContentRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<GeminiContent>,
+    pub contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<GeminiSafetySetting>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generation_config: Option<GeminiGenerationConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeminiContent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiPart {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_data: Option<GeminiInlineData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiInlineData {
+    pub mime_type: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GeminiSafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiGenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<serde_json::Value>,
+}
+
+impl GeminiPart {
+    fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            inline_data: None,
+        }
+    }
+}
+
+impl GeminiGenerateContentRequest {
+    /// Convert an OpenAI-style request, applying `safety_threshold` to every harm category
+    pub fn from_chat_request(
+        request: &ChatCompletionRequest,
+        safety_threshold: &str,
+    ) -> Result<Self, String> {
+        request.ensure_no_tools("Gemini")?;
+
+        let mut system_parts = Vec::new();
+        let mut contents = Vec::new();
+
+        for message in &request.messages {
+            let parts = match &message.content {
+                MessageContent::Text(text) => vec![GeminiPart::text(text.clone())],
+                MessageContent::Parts(parts) => parts
+                    .iter()
+                    .map(|part| match part {
+                        ContentPart::Text { text } => GeminiPart::text(text.clone()),
+                        ContentPart::InputAudio { input_audio } => GeminiPart {
+                            text: None,
+                            inline_data: Some(GeminiInlineData {
+                                mime_type: format!("audio/{}", input_audio.format),
+                                data: input_audio.data.clone(),
+                            }),
+                        },
+                    })
+                    .collect(),
+            };
+
+            match message.role {
+                ChatRole::System => system_parts.extend(parts),
+                ChatRole::User | ChatRole::Tool => contents.push(GeminiContent {
+                    role: Some("user".to_string()),
+                    parts,
+                }),
+                ChatRole::Assistant => contents.push(GeminiContent {
+                    role: Some("model".to_string()),
+                    parts,
+                }),
+            }
+        }
+
+        let generation_config = GeminiGenerationConfig {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            max_output_tokens: request.max_tokens,
+            stop_sequences: request.stop.clone(),
+            response_mime_type: request
+                .response_format
+                .as_ref()
+                .map(|_| "application/json".to_string()),
+            response_schema: request
+                .response_format
+                .as_ref()
+                .map(|ResponseFormat::JsonSchema { json_schema }| json_schema.schema.clone()),
+        };
+
+        Ok(Self {
+            system_instruction: if system_parts.is_empty() {
+                None
+            } else {
+                Some(GeminiContent {
+                    role: None,
+                    parts: system_parts,
+                })
+            },
+            contents,
+            safety_settings: GEMINI_HARM_CATEGORIES
+                .iter()
+                .map(|category| GeminiSafetySetting {
+                    category: category.to_string(),
+                    threshold: safety_threshold.to_string(),
+                })
+                .collect(),
+            generation_config: if generation_config == GeminiGenerationConfig::default() {
+                None
+            } else {
+                Some(generation_config)
+            },
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiGenerateContentResponse {
+    #[serde(default)]
+    pub candidates: Vec<GeminiCandidate>,
+    #[serde(default)]
+    pub usage_metadata: Option<GeminiUsageMetadata>,
+    #[serde(default)]
+    pub prompt_feedback: Option<GeminiPromptFeedback>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiCandidate {
+    #[serde(default)]
+    pub content: Option<GeminiContent>,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiPromptFeedback {
+    pub block_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiUsageMetadata {
+    #[serde(default)]
+    pub prompt_token_count: u32,
+    #[serde(default)]
+    pub candidates_token_count: u32,
+    #[serde(default)]
+    pub total_token_count: u32,
+}
+
+impl From<GeminiUsageMetadata> for Usage {
+    fn from(usage: GeminiUsageMetadata) -> Self {
+        Usage {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+            total_tokens: usage.total_token_count,
+        }
+    }
+}
+
+impl GeminiGenerateContentResponse {
+    /// Text of the first candidate, or the reason the prompt was blocked
+    pub fn text(&self) -> Result<String, LlmError> {
+        if let Some(reason) = self
+            .prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.block_reason.as_ref())
+        {
+            return Err(LlmError::content_filter(format!(
+                "Prompt was blocked by Gemini: {}",
+                reason
+            )));
+        }
+
+        let candidate = self
+            .candidates
+            .first()
+            .ok_or_else(|| LlmError::parse("No candidates in response"))?;
+
+        let text: String = candidate
+            .content
+            .iter()
+            .flat_map(|content| content.parts.iter())
+            .filter_map(|part| part.text.as_deref())
+            .collect();
+
+        if text.is_empty() {
+            let finish_reason = candidate.finish_reason.as_deref().unwrap_or("unknown");
+            let message = format!("No content in response (finish reason: {})", finish_reason);
+            if finish_reason == "SAFETY" {
+                Err(LlmError::content_filter(message))
+            } else {
+                Err(LlmError::parse(message))
+            }
+        } else {
+            Ok(text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_line() {
+        let chunk =
+            parse_stream_line(r#"data: {"choices":[{"index":0,"delta":{"content":"Hel"}}]}"#)
+                .unwrap()
+                .unwrap();
+        assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("Hel"));
+
+        let last = parse_stream_line(
+            r#"data: {"choices":[],"usage":{"prompt_tokens":9,"completion_tokens":2,"total_tokens":11}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(last.usage.unwrap().total_tokens, 11);
+
+        assert!(parse_stream_line("data: [DONE]").unwrap().is_none());
+        assert!(parse_stream_line(": keep-alive").unwrap().is_none());
+        assert!(parse_stream_line("data: {oops").is_err());
+    }
+
+    #[test]
+    fn test_usage_is_parsed() {
+        let body = r#"{
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "llama-3.3-70b-versatile",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Hello." },
+                "finish_reason": "stop"
+            }],
+            "usage": {
+                "queue_time": 0.02,
+                "prompt_tokens": 42,
+                "completion_tokens": 3,
+                "total_tokens": 45
+            },
+            "service_tier": "on_demand"
+        }"#;
+
+        let parsed: ChatCompletionResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            parsed.usage,
+            Some(Usage {
+                prompt_tokens: 42,
+                completion_tokens: 3,
+                total_tokens: 45,
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_usage_is_none() {
+        let body = r#"{
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4o-mini",
+            "choices": []
+        }"#;
+
+        let parsed: ChatCompletionResponse = serde_json::from_str(body).unwrap();
+        assert!(parsed.usage.is_none());
+    }
+
+    #[test]
+    fn test_builder_serializes_optional_fields() {
+        let request = ChatCompletionRequest::builder("gpt-4o-mini")
+            .system("You are terse.")
+            .user("Hello")
+            .temperature(0.2)
+            .top_p(0.9)
+            .max_tokens(64)
+            .stop(vec!["\n\n".to_string()])
+            .response_format(ResponseFormat::json_schema(
+                "reply",
+                serde_json::json!({ "type": "object" }),
+            ))
+            .build()
+            .unwrap();
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "model": "gpt-4o-mini",
+                "messages": [
+                    { "role": "system", "content": "You are terse." },
+                    { "role": "user", "content": "Hello" }
+                ],
+                "temperature": 0.2f32,
+                "top_p": 0.9f32,
+                "max_tokens": 64,
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": "reply", "schema": { "type": "object" }, "strict": false }
+                },
+                "stop": ["\n\n"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_builder_omits_unset_fields() {
+        let request = ChatCompletionRequest::builder("m")
+            .user("hi")
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "model": "m",
+                "messages": [{ "role": "user", "content": "hi" }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_provider_specific_fields() {
+        let extra_body =
+            parse_extra_body(r#"{ "provider": { "order": ["groq"], "allow_fallbacks": false } }"#)
+                .unwrap();
+        let mut request = ChatCompletionRequest::builder("m")
+            .user("hi")
+            .build()
+            .unwrap();
+        request.service_tier = Some("flex".to_string());
+        let request = request.with_provider_fields(Some("auto"), &extra_body);
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["service_tier"], "flex");
+        assert_eq!(value["provider"]["order"][0], "groq");
+
+        assert!(parse_extra_body(r#"{ "model": "other" }"#).is_err());
+        assert!(parse_extra_body("[1, 2]").is_err());
+        assert!(parse_extra_body(r#"{ "temperature": 1 }"#).is_err());
+    }
+
+    #[test]
+    fn test_multimodal_content_parts() {
+        let message = ChatRequestMessage::parts(
+            ChatRole::User,
+            vec![
+                ContentPart::Text {
+                    text: "Transcribe".to_string(),
+                },
+                ContentPart::InputAudio {
+                    input_audio: InputAudio {
+                        data: "AAAA".to_string(),
+                        format: "wav".to_string(),
+                    },
+                },
+            ],
+        );
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": "Transcribe" },
+                    { "type": "input_audio", "input_audio": { "data": "AAAA", "format": "wav" } }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_values() {
+        assert!(ChatCompletionRequest::builder("m").build().is_err());
+        assert!(ChatCompletionRequest::builder("m")
+            .user("hi")
+            .temperature(3.0)
+            .build()
+            .is_err());
+        assert!(ChatCompletionRequest::builder("m")
+            .user("hi")
+            .top_p(1.5)
+            .build()
+            .is_err());
+        assert!(ChatCompletionRequest::builder("m")
+            .user("hi")
+            .max_tokens(0)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_anthropic_request_hoists_system_prompt() {
+        let request = ChatCompletionRequest::builder("claude-3-5-haiku-latest")
+            .system("You are terse.")
+            .user("Hello")
+            .build()
+            .unwrap();
+
+        let anthropic = AnthropicMessagesRequest::try_from(&request).unwrap();
+        let value = serde_json::to_value(&anthropic).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "model": "claude-3-5-haiku-latest",
+                "max_tokens": ANTHROPIC_DEFAULT_MAX_TOKENS,
+                "system": "You are terse.",
+                "messages": [{ "role": "user", "content": "Hello" }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_anthropic_response_is_parsed() {
+        let body = r#"{
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-haiku-latest",
+            "content": [
+                { "type": "thinking", "thinking": "..." },
+                { "type": "text", "text": "Hello." }
+            ],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 12, "output_tokens": 3 }
+        }"#;
+
+        let parsed: AnthropicMessagesResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(parsed.text().as_deref(), Some("Hello."));
+        assert_eq!(
+            Usage::from(parsed.usage.unwrap()),
+            Usage {
+                prompt_tokens: 12,
+                completion_tokens: 3,
+                total_tokens: 15,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ollama_request_maps_options() {
+        let request = ChatCompletionRequest::builder("llama3.2")
+            .user("Hello")
+            .temperature(0.3)
+            .max_tokens(128)
+            .response_format(ResponseFormat::json_schema(
+                "reply",
+                serde_json::json!({ "type": "object" }),
+            ))
+            .build()
+            .unwrap();
+
+        let ollama = OllamaChatRequest::try_from(&request).unwrap();
+        let value = serde_json::to_value(&ollama).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "model": "llama3.2",
+                "messages": [{ "role": "user", "content": "Hello" }],
+                "stream": false,
+                "format": { "type": "object" },
+                "options": { "temperature": 0.3f32, "num_predict": 128 }
+            })
+        );
+    }
+
+    #[test]
+    fn test_gemini_request_conversion() {
+        let request = ChatCompletionRequest::builder("gemini-2.5-flash")
+            .system("You are terse.")
+            .user("Hello")
+            .max_tokens(256)
+            .build()
+            .unwrap();
+
+        let gemini =
+            GeminiGenerateContentRequest::from_chat_request(&request, "BLOCK_NONE").unwrap();
+        let value = serde_json::to_value(&gemini).unwrap();
+        assert_eq!(
+            value["systemInstruction"],
+            serde_json::json!({ "parts": [{ "text": "You are terse." }] })
+        );
+        assert_eq!(
+            value["contents"],
+            serde_json::json!([{ "role": "user", "parts": [{ "text": "Hello" }] }])
+        );
+        assert_eq!(
+            value["generationConfig"],
+            serde_json::json!({ "maxOutputTokens": 256 })
+        );
+        assert_eq!(
+            value["safetySettings"].as_array().unwrap().len(),
+            GEMINI_HARM_CATEGORIES.len()
+        );
+    }
+
+    #[test]
+    fn test_gemini_blocked_prompt_is_error() {
+        let body = r#"{ "promptFeedback": { "blockReason": "SAFETY" } }"#;
+        let parsed: GeminiGenerateContentResponse = serde_json::from_str(body).unwrap();
+        let error = parsed.text().unwrap_err();
+        assert!(matches!(error, LlmError::ContentFilter { .. }));
+        assert!(error.to_string().contains("SAFETY"));
+    }
+
+    #[test]
+    fn test_tool_calls_round_trip() {
+        let body = r#"{
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "insert_date", "arguments": "{}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }"#;
+        let parsed: ChatCompletionResponse = serde_json::from_str(body).unwrap();
+        let tool_calls = parsed.choices[0].message.tool_calls.clone();
+        assert_eq!(tool_calls[0].function.name, "insert_date");
+
+        let request = ChatCompletionRequest::builder("gpt-4o-mini")
+            .user("What's the date?")
+            .message(ChatRequestMessage::assistant_tool_calls(None, tool_calls))
+            .message(ChatRequestMessage::tool_result("call_1", "2024-01-01"))
+            .tools(vec![Tool::function(
+                "insert_date",
+                "Insert today's date",
+                serde_json::json!({ "type": "object", "properties": {} }),
+            )])
+            .tool_choice(ToolChoice::auto())
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["messages"][1]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(
+            value["messages"][2],
+            serde_json::json!({ "role": "tool", "content": "2024-01-01", "tool_call_id": "call_1" })
+        );
+        assert_eq!(value["tools"][0]["function"]["name"], "insert_date");
+        assert_eq!(value["tool_choice"], "auto");
+
+        assert!(AnthropicMessagesRequest::try_from(&request).is_err());
+    }
+
+    #[test]
+    fn test_json_schema_response_format() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "items": { "type": "array", "items": { "type": "string" } } },
+            "required": ["items"]
+        });
+        let request = ChatCompletionRequest::builder("gpt-4o-mini")
+            .user("Buy milk and call Sam")
+            .response_format(
+                ResponseFormat::json_schema("action_items", schema.clone()).strict(true),
+            )
+            .build()
+            .unwrap();
+
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value["response_format"],
+            serde_json::json!({
+                "type": "json_schema",
+                "json_schema": { "name": "action_items", "schema": schema, "strict": true }
+            })
+        );
+
+        let ollama = OllamaChatRequest::try_from(&request).unwrap();
+        assert_eq!(ollama.format, Some(schema.clone()));
+        let anthropic = AnthropicMessagesRequest::try_from(&request).unwrap();
+        assert!(anthropic.system.unwrap().contains("JSON Schema"));
+
+        assert!(ChatCompletionRequest::builder("m")
+            .user("hi")
+            .response_format(ResponseFormat::json_schema("bad name", schema))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_structured_output() {
+        let expected = serde_json::json!({ "items": ["Buy milk"] });
+        assert_eq!(
+            parse_structured_output("{\"items\": [\"Buy milk\"]}").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_structured_output("```json\n{\"items\": [\"Buy milk\"]}\n```").unwrap(),
+            expected
+        );
+        assert!(parse_structured_output("Sure! Here you go").is_err());
+    }
+
+    #[test]
+    fn test_format_structured_output() {
+        let items = serde_json::json!({ "items": ["Buy milk", "Call Sam"] });
+        assert_eq!(format_structured_output(&items), "- Buy milk\n- Call Sam");
+
+        let email = serde_json::json!({
+            "attendees": [{ "name": "Sam", "role": "host" }],
+            "due_date": null,
+            "subject": "Lunch",
+            "urgent": false
+        });
+        assert_eq!(
+            format_structured_output(&email),
+            "Attendees:\n- Sam, host\nSubject: Lunch\nUrgent: false"
+        );
+    }
 }
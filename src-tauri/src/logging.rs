@@ -0,0 +1,289 @@
+//! Logging through `tracing`. The `log` macros used across the app are
+//! forwarded to it, so both end up in the same places: the console, filtered
+//! by `RUST_LOG`, and JSON lines in daily log files, filtered by the log
+//! level setting with per-module overrides that apply as soon as they're
+//! saved.
+
+use crate::pipeline;
+use crate::portable;
+use crate::settings::AppSettings;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Map, Value};
+use std::fmt::Debug;
+use std::io::Write;
+use tauri::AppHandle;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Days of log files kept
+const MAX_LOG_FILES: usize = 7;
+
+/// Our own modules log under this target, e.g. `babbl_app_lib::input_hook`
+const CRATE_TARGET: &str = env!("CARGO_CRATE_NAME");
+
+/// Target of messages that carry what was said, which diagnostic bundles
+/// leave out
+pub const TRANSCRIPT_TARGET: &str = "babbl::transcript";
+
+static FILE_FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+/// Fields of an event, with the message apart
+#[derive(Default)]
+struct Fields {
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl Fields {
+    fn insert(&mut self, field: &Field, value: Value) {
+        match field.name() {
+            "message" => {
+                self.message = match value {
+                    Value::String(message) => message,
+                    other => other.to_string(),
+                }
+            }
+            // Where a `log` record came from, already in the metadata
+            name if name.starts_with("log.") => {}
+            name => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.insert(field, Value::String(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+}
+
+/// One line of a log file
+fn json_line(level: &str, target: &str, run_id: Option<String>, fields: Fields) -> String {
+    let mut line = json!({
+        "time": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "level": level,
+        "target": target,
+        "message": fields.message,
+    });
+    if let Some(run_id) = run_id {
+        line["run_id"] = run_id.into();
+    }
+    if !fields.fields.is_empty() {
+        line["fields"] = Value::Object(fields.fields);
+    }
+    line.to_string()
+}
+
+/// Writes each event as a JSON line
+struct JsonLines {
+    writer: RollingFileAppender,
+}
+
+impl<S: Subscriber> Layer<S> for JsonLines {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let line = json_line(
+            metadata.level().as_str(),
+            metadata.target(),
+            pipeline::run_id(),
+            fields,
+        );
+        // In one write, so lines from different threads don't interleave
+        let _ = self
+            .writer
+            .make_writer()
+            .write_all(format!("{}\n", line).as_bytes());
+    }
+}
+
+/// Whether `name` can be given its own level, e.g. `input_hook`,
+/// `managers::audio` or `reqwest`
+pub fn is_module_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// Filter directives for the log files. A module without a path is looked
+/// up both among ours and as a crate.
+fn file_directives(settings: &AppSettings) -> String {
+    let mut directives = vec![tracing::Level::from(settings.log_level).to_string()];
+    let mut modules: Vec<_> = settings
+        .log_module_levels
+        .iter()
+        .filter(|(module, _)| is_module_name(module))
+        .collect();
+    modules.sort_by_key(|(module, _)| *module);
+    for (module, level) in modules {
+        let level = tracing::Level::from(*level);
+        directives.push(format!("{}={}", module, level));
+        if !module.starts_with(CRATE_TARGET) {
+            directives.push(format!("{}::{}={}", CRATE_TARGET, module, level));
+        }
+    }
+    directives.join(",")
+}
+
+fn file_filter(settings: &AppSettings) -> EnvFilter {
+    EnvFilter::try_new(file_directives(settings)).unwrap_or_else(|e| {
+        log::warn!("Ignoring the per-module log levels: {}", e);
+        EnvFilter::new(tracing::Level::from(settings.log_level).to_string())
+    })
+}
+
+/// Console filter from `RUST_LOG`, info by default; the error is for an
+/// invalid `RUST_LOG`, logged once logging is up
+fn console_filter() -> (EnvFilter, Option<String>) {
+    match std::env::var("RUST_LOG") {
+        Ok(spec) if !spec.trim().is_empty() => match EnvFilter::try_new(&spec) {
+            Ok(filter) => (filter, None),
+            Err(e) => (
+                EnvFilter::new("info"),
+                Some(format!(
+                    "Ignoring invalid RUST_LOG value '{}': {}. Falling back to info-level console logging",
+                    spec, e
+                )),
+            ),
+        },
+        _ => (EnvFilter::new("info"), None),
+    }
+}
+
+/// Start logging; before this, log messages go nowhere
+pub fn init(app: &AppHandle, settings: &AppSettings) {
+    let (console_filter, console_error) = console_filter();
+    let console = tracing_subscriber::fmt::layer().with_filter(console_filter);
+
+    let (filter, handle) = reload::Layer::new(file_filter(settings));
+    let files = portable::app_log_dir(app)
+        .map_err(|e| e.to_string())
+        .and_then(|dir| {
+            std::fs::create_dir_all(&dir).map_err(|e| format!("{:?}: {}", dir, e))?;
+            RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix("babbl")
+                .filename_suffix("log")
+                .max_log_files(MAX_LOG_FILES)
+                .build(&dir)
+                .map_err(|e| format!("{:?}: {}", dir, e))
+        });
+    let (files, files_error) = match files {
+        Ok(writer) => (Some(JsonLines { writer }.with_filter(filter)), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let subscriber = Registry::default().with(files).with(console);
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        eprintln!("Failed to set up logging: {}", e);
+        return;
+    }
+    if let Err(e) = tracing_log::LogTracer::init() {
+        eprintln!("Failed to forward log messages: {}", e);
+    }
+
+    if let Some(e) = console_error {
+        log::warn!("{}", e);
+    }
+    match files_error {
+        Some(e) => log::warn!(
+            "Not logging to files, the log directory isn't usable: {}",
+            e
+        ),
+        None => {
+            let _ = FILE_FILTER.set(handle);
+        }
+    }
+}
+
+/// Apply changed log levels to the log files
+pub fn apply(settings: &AppSettings) {
+    if let Some(handle) = FILE_FILTER.get() {
+        if let Err(e) = handle.reload(file_filter(settings)) {
+            log::warn!("Failed to change the log levels: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{get_default_settings, LogLevel};
+
+    #[test]
+    fn test_file_directives() {
+        let mut settings = get_default_settings();
+        settings.log_level = LogLevel::Info;
+        assert_eq!(file_directives(&settings), "INFO");
+
+        settings
+            .log_module_levels
+            .insert("input_hook".to_string(), LogLevel::Trace);
+        settings
+            .log_module_levels
+            .insert("reqwest".to_string(), LogLevel::Warn);
+        settings
+            .log_module_levels
+            .insert("bad name!".to_string(), LogLevel::Debug);
+        let directives = file_directives(&settings);
+        assert_eq!(
+            directives,
+            format!(
+                "INFO,input_hook=TRACE,{0}::input_hook=TRACE,reqwest=WARN,{0}::reqwest=WARN",
+                CRATE_TARGET
+            )
+        );
+        assert!(EnvFilter::try_new(directives).is_ok());
+    }
+
+    #[test]
+    fn test_json_line() {
+        let mut fields = Fields {
+            message: "Recording started".to_string(),
+            fields: Map::new(),
+        };
+        fields
+            .fields
+            .insert("binding".to_string(), "transcribe".into());
+        let line: Value = serde_json::from_str(&json_line(
+            "DEBUG",
+            "babbl_app_lib::actions",
+            Some("run".to_string()),
+            fields,
+        ))
+        .unwrap();
+        assert_eq!(line["level"], "DEBUG");
+        assert_eq!(line["message"], "Recording started");
+        assert_eq!(line["run_id"], "run");
+        assert_eq!(line["fields"]["binding"], "transcribe");
+    }
+}
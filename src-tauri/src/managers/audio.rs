@@ -1,10 +1,17 @@
-use crate::audio_toolkit::{list_input_devices, vad::SmoothedVad, AudioRecorder, SileroVad};
+use crate::audio_toolkit::{
+    list_input_devices, vad::SmoothedVad, AudioRecorder, BufferUsage, SileroVad,
+};
 use crate::helpers::clamshell;
+use crate::microphone_permission;
+use crate::notifications::{self, NotificationKind};
+use crate::recovery;
 use crate::settings::{get_settings, AppSettings};
+use crate::settings_validation::SettingsError;
 use crate::utils;
 use log::{debug, error, info};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tauri::Manager;
 
 fn set_mute(mute: bool) {
@@ -112,6 +119,19 @@ pub enum MicrophoneMode {
     OnDemand,
 }
 
+/// Mode the microphone runs in per `settings`, always on as well while the
+/// wake word is listened for; in low-power mode it's only open while
+/// recording
+pub fn microphone_mode(settings: &AppSettings) -> MicrophoneMode {
+    if (settings.always_on_microphone || settings.wake_word_enabled)
+        && !crate::power::is_low_power()
+    {
+        MicrophoneMode::AlwaysOn
+    } else {
+        MicrophoneMode::OnDemand
+    }
+}
+
 /* ──────────────────────────────────────────────────────────────── */
 
 fn create_audio_recorder(
@@ -122,8 +142,8 @@ fn create_audio_recorder(
         .map_err(|e| anyhow::anyhow!("Failed to create SileroVad: {}", e))?;
     let smoothed_vad = SmoothedVad::new(Box::new(silero), 15, 15, 2);
 
-    // Recorder with VAD plus a spectrum-level callback that forwards updates to
-    // the frontend.
+    // Recorder with VAD plus spectrum-level and waveform callbacks that forward
+    // updates to the frontend, and what's said while idle to the wake word.
     let recorder = AudioRecorder::new()
         .map_err(|e| anyhow::anyhow!("Failed to create AudioRecorder: {}", e))?
         .with_vad(Box::new(smoothed_vad))
@@ -132,7 +152,18 @@ fn create_audio_recorder(
             move |levels| {
                 utils::emit_levels(&app_handle, &levels);
             }
-        });
+        })
+        .with_waveform_callback({
+            let app_handle = app_handle.clone();
+            move |peaks| {
+                utils::emit_waveform(&app_handle, &peaks);
+            }
+        })
+        .with_utterance_callback(
+            crate::wake_word::listening_flag(),
+            crate::wake_word::MAX_UTTERANCE_SAMPLES,
+            crate::wake_word::offer,
+        );
 
     Ok(recorder)
 }
@@ -149,6 +180,8 @@ pub struct AudioRecordingManager {
     is_open: Arc<Mutex<bool>>,
     is_recording: Arc<Mutex<bool>>,
     did_mute: Arc<Mutex<bool>>,
+    /// File the current recording is spooled to, see [`crate::recovery`]
+    spool: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl AudioRecordingManager {
@@ -156,11 +189,7 @@ impl AudioRecordingManager {
 
     pub fn new(app: &tauri::AppHandle) -> Result<Self, anyhow::Error> {
         let settings = get_settings(app);
-        let mode = if settings.always_on_microphone {
-            MicrophoneMode::AlwaysOn
-        } else {
-            MicrophoneMode::OnDemand
-        };
+        let mode = microphone_mode(&settings);
 
         let manager = Self {
             state: Arc::new(Mutex::new(RecordingState::Idle)),
@@ -171,11 +200,25 @@ impl AudioRecordingManager {
             is_open: Arc::new(Mutex::new(false)),
             is_recording: Arc::new(Mutex::new(false)),
             did_mute: Arc::new(Mutex::new(false)),
+            spool: Arc::new(Mutex::new(None)),
         };
 
-        // Always-on?  Open immediately.
+        // Always-on?  Open immediately, or once the audio devices had time
+        // to show up after login.
         if matches!(mode, MicrophoneMode::AlwaysOn) {
-            manager.start_microphone_stream()?;
+            let delay = crate::autostart::microphone_delay(&settings);
+            if delay.is_zero() {
+                manager.start_microphone_stream()?;
+            } else {
+                info!("Opening the microphone in {:?}, after login", delay);
+                let delayed = manager.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(delay);
+                    if let Err(e) = delayed.start_microphone_stream() {
+                        error!("Failed to open microphone stream: {e}");
+                    }
+                });
+            }
         }
 
         Ok(manager)
@@ -210,6 +253,28 @@ impl AudioRecordingManager {
         }
     }
 
+    fn create_recorder(&self) -> Result<AudioRecorder, anyhow::Error> {
+        let vad_path = self
+            .app_handle
+            .path()
+            .resolve(
+                "resources/models/silero_vad_v4.onnx",
+                tauri::path::BaseDirectory::Resource,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to resolve VAD path: {}", e))?;
+        create_audio_recorder(vad_path.to_str().unwrap(), &self.app_handle)
+    }
+
+    /// Create the recorder and load its VAD model ahead of the first
+    /// recording, without opening the microphone
+    pub fn prepare(&self) -> Result<(), anyhow::Error> {
+        let mut recorder_opt = self.recorder.lock().unwrap();
+        if recorder_opt.is_none() {
+            *recorder_opt = Some(self.create_recorder()?);
+        }
+        Ok(())
+    }
+
     /* ---------- microphone life-cycle -------------------------------------- */
 
     /// Applies mute if mute_while_recording is enabled and stream is open
@@ -247,21 +312,10 @@ impl AudioRecordingManager {
         let mut did_mute_guard = self.did_mute.lock().unwrap();
         *did_mute_guard = false;
 
-        let vad_path = self
-            .app_handle
-            .path()
-            .resolve(
-                "resources/models/silero_vad_v4.onnx",
-                tauri::path::BaseDirectory::Resource,
-            )
-            .map_err(|e| anyhow::anyhow!("Failed to resolve VAD path: {}", e))?;
         let mut recorder_opt = self.recorder.lock().unwrap();
 
         if recorder_opt.is_none() {
-            *recorder_opt = Some(create_audio_recorder(
-                vad_path.to_str().unwrap(),
-                &self.app_handle,
-            )?);
+            *recorder_opt = Some(self.create_recorder()?);
         }
 
         // Get the selected device from settings, considering clamshell mode
@@ -306,6 +360,27 @@ impl AudioRecordingManager {
         debug!("Microphone stream stopped");
     }
 
+    /// Whether the open microphone stream still delivers samples; `None`
+    /// while it's closed
+    pub fn stream_alive(&self) -> Option<bool> {
+        let open_flag = self.is_open.lock().unwrap();
+        if !*open_flag {
+            return None;
+        }
+        let recorder_opt = self.recorder.lock().unwrap();
+        Some(recorder_opt.as_ref().is_some_and(AudioRecorder::is_alive))
+    }
+
+    /// Reopen a stream that stopped delivering samples, unless a recording
+    /// is still waiting on it
+    pub fn restart_microphone_stream(&self) -> Result<(), anyhow::Error> {
+        if *self.is_recording.lock().unwrap() {
+            return Err(anyhow::anyhow!("A recording is using the stream"));
+        }
+        self.stop_microphone_stream();
+        self.start_microphone_stream()
+    }
+
     /* ---------- mode switching --------------------------------------------- */
 
     pub fn update_mode(&self, new_mode: MicrophoneMode) -> Result<(), anyhow::Error> {
@@ -333,19 +408,55 @@ impl AudioRecordingManager {
     /* ---------- recording --------------------------------------------------- */
 
     pub fn try_start_recording(&self, binding_id: &str) -> bool {
+        self.start_recording(binding_id, true)
+    }
+
+    /// Start a recording for `binding_id` that's read a segment at a time
+    /// with [`Self::take_segment`]. It isn't spooled for recovery, it can run
+    /// for hours and its segments are kept as they're taken.
+    pub fn try_start_segmented_recording(&self, binding_id: &str) -> bool {
+        self.start_recording(binding_id, false)
+    }
+
+    fn start_recording(&self, binding_id: &str, spooled: bool) -> bool {
         let mut state = self.state.lock().unwrap();
 
         if let RecordingState::Idle = *state {
-            // Ensure microphone is open in on-demand mode
-            if matches!(*self.mode.lock().unwrap(), MicrophoneMode::OnDemand) {
+            if !microphone_permission::ensure(&self.app_handle) {
+                return false;
+            }
+
+            // Ensure microphone is open in on-demand mode, or while an
+            // always-on one waits out the delay after login
+            if matches!(*self.mode.lock().unwrap(), MicrophoneMode::OnDemand)
+                || !*self.is_open.lock().unwrap()
+            {
                 if let Err(e) = self.start_microphone_stream() {
                     error!("Failed to open microphone stream: {e}");
+                    notifications::notify(
+                        &self.app_handle,
+                        NotificationKind::PermissionProblem,
+                        "Couldn't open the microphone",
+                        &format!("Check that Babbl may use the microphone. {}", e),
+                    );
                     return false;
                 }
             }
 
-            if let Some(rec) = self.recorder.lock().unwrap().as_ref() {
-                if rec.start().is_ok() {
+            if let Some(rec) = self.recorder.lock().unwrap().as_mut() {
+                let settings = get_settings(&self.app_handle);
+                rec.set_memory_limit(settings.recording_memory_limit_mb as usize * 1024 * 1024);
+                let spool = if spooled {
+                    recovery::new_spool(&self.app_handle)
+                } else {
+                    None
+                };
+                let started = match spool.clone() {
+                    Some(spool) => rec.start_spooled(spool),
+                    None => rec.start(),
+                };
+                if started.is_ok() {
+                    *self.spool.lock().unwrap() = spool;
                     *self.is_recording.lock().unwrap() = true;
                     *state = RecordingState::Recording {
                         binding_id: binding_id.to_string(),
@@ -361,6 +472,44 @@ impl AudioRecordingManager {
         }
     }
 
+    /// What `binding_id` recorded since it started or the last segment was
+    /// taken; `None` when it isn't recording
+    pub fn take_segment(&self, binding_id: &str) -> Option<Vec<f32>> {
+        let state = self.state.lock().unwrap();
+        match *state {
+            RecordingState::Recording {
+                binding_id: ref active,
+            } if active == binding_id => {}
+            _ => return None,
+        }
+        let recorder_opt = self.recorder.lock().unwrap();
+        match recorder_opt.as_ref().map(AudioRecorder::take) {
+            Some(Ok(samples)) => Some(samples),
+            Some(Err(e)) => {
+                error!("take() failed: {e}");
+                Some(Vec::new())
+            }
+            None => None,
+        }
+    }
+
+    /// How much of the current recording is in memory and spilled to disk
+    pub fn buffer_usage(&self) -> BufferUsage {
+        self.recorder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(AudioRecorder::buffer_usage)
+            .unwrap_or_default()
+    }
+
+    /// Delete the spool of the recording that just stopped
+    fn discard_spool(&self) {
+        if let Some(spool) = self.spool.lock().unwrap().take() {
+            recovery::discard(&spool);
+        }
+    }
+
     pub fn update_selected_device(&self) -> Result<(), anyhow::Error> {
         // If currently open, restart the microphone stream to use the new device
         if *self.is_open.lock().unwrap() {
@@ -392,6 +541,10 @@ impl AudioRecordingManager {
                     error!("Recorder not available");
                     Vec::new()
                 };
+                self.discard_spool();
+                if microphone_permission::is_blocked_signal(&samples) {
+                    microphone_permission::report_blocked_signal(&self.app_handle);
+                }
 
                 *self.is_recording.lock().unwrap() = false;
 
@@ -414,6 +567,15 @@ impl AudioRecordingManager {
             _ => None,
         }
     }
+    /// Time since speech was last heard in the current recording
+    pub fn silent_for(&self) -> Option<Duration> {
+        if !self.is_recording() {
+            return None;
+        }
+        let recorder_opt = self.recorder.lock().unwrap();
+        recorder_opt.as_ref().map(AudioRecorder::silent_for)
+    }
+
     pub fn is_recording(&self) -> bool {
         matches!(
             *self.state.lock().unwrap(),
@@ -423,7 +585,18 @@ impl AudioRecordingManager {
 
     /// Cancel any ongoing recording without returning audio samples
     pub fn cancel_recording(&self) {
+        self.abort_recording(false);
+    }
+
+    /// Stop any ongoing recording without transcribing it, keeping its spool
+    /// for [`crate::recovery`] to offer at the next launch
+    pub fn persist_recording(&self) -> Option<PathBuf> {
+        self.abort_recording(true)
+    }
+
+    fn abort_recording(&self, keep_spool: bool) -> Option<PathBuf> {
         let mut state = self.state.lock().unwrap();
+        let mut kept = None;
 
         if let RecordingState::Recording { .. } = *state {
             *state = RecordingState::Idle;
@@ -432,6 +605,11 @@ impl AudioRecordingManager {
             if let Some(rec) = self.recorder.lock().unwrap().as_ref() {
                 let _ = rec.stop(); // Discard the result
             }
+            if keep_spool {
+                kept = self.spool.lock().unwrap().take();
+            } else {
+                self.discard_spool();
+            }
 
             *self.is_recording.lock().unwrap() = false;
 
@@ -440,5 +618,40 @@ impl AudioRecordingManager {
                 self.stop_microphone_stream();
             }
         }
+        kept
+    }
+}
+
+/// Switch the microphone mode and reopen the stream on another device when
+/// those settings change
+pub fn on_settings_changed(
+    app: &tauri::AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    let rm = app.state::<Arc<AudioRecordingManager>>();
+    let mut errors = Vec::new();
+
+    if previous.always_on_microphone != next.always_on_microphone
+        || previous.wake_word_enabled != next.wake_word_enabled
+    {
+        if let Err(e) = rm.update_mode(microphone_mode(next)) {
+            errors.push(SettingsError::new(
+                "always_on_microphone",
+                format!("Failed to update microphone mode: {}", e),
+            ));
+        }
+    }
+
+    if previous.selected_microphone != next.selected_microphone
+        || previous.clamshell_microphone != next.clamshell_microphone
+    {
+        if let Err(e) = rm.update_selected_device() {
+            errors.push(SettingsError::new(
+                "selected_microphone",
+                format!("Failed to update selected device: {}", e),
+            ));
+        }
     }
+    errors
 }
@@ -0,0 +1,106 @@
+use crate::settings::AppSettings;
+use crate::settings_validation::SettingsError;
+use log::debug;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Upper bound on remembered turns, whatever the settings ask for
+const MAX_STORED_TURNS: usize = 20;
+
+/// One post-processed dictation: the prompt that was sent and the model's answer
+#[derive(Clone, Debug)]
+pub struct ConversationTurn {
+    pub prompt: String,
+    pub output: String,
+    at: Instant,
+}
+
+/// Recent dictations of the current session, replayed as chat history so that
+/// follow-ups ("actually make that Tuesday") can edit the previous output.
+#[derive(Default)]
+pub struct ConversationManager {
+    turns: Mutex<VecDeque<ConversationTurn>>,
+}
+
+impl ConversationManager {
+    pub fn record(&self, prompt: String, output: String) {
+        let mut turns = self.turns.lock().unwrap();
+        turns.push_back(ConversationTurn {
+            prompt,
+            output,
+            at: Instant::now(),
+        });
+        while turns.len() > MAX_STORED_TURNS {
+            turns.pop_front();
+        }
+    }
+
+    /// The last `max_turns` turns not older than `window`, oldest first
+    pub fn context(&self, max_turns: usize, window: Duration) -> Vec<ConversationTurn> {
+        self.context_at(Instant::now(), max_turns, window)
+    }
+
+    fn context_at(
+        &self,
+        now: Instant,
+        max_turns: usize,
+        window: Duration,
+    ) -> Vec<ConversationTurn> {
+        let mut turns = self.turns.lock().unwrap();
+        // Expired turns can never come back into the window
+        turns.retain(|turn| now.saturating_duration_since(turn.at) <= window);
+        let skip = turns.len().saturating_sub(max_turns);
+        turns.iter().skip(skip).cloned().collect()
+    }
+
+    /// Start a new session
+    pub fn reset(&self) {
+        debug!("Resetting conversation session");
+        self.turns.lock().unwrap().clear();
+    }
+}
+
+/// Turning context off or on starts a fresh session
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.conversation_context_enabled != next.conversation_context_enabled {
+        app.state::<Arc<ConversationManager>>().reset();
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_limits_turns_and_age() {
+        let manager = ConversationManager::default();
+        for i in 0..4 {
+            manager.record(format!("prompt {}", i), format!("output {}", i));
+        }
+
+        let recent = manager.context(2, Duration::from_secs(60));
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].output, "output 2");
+        assert_eq!(recent[1].output, "output 3");
+
+        let later = Instant::now() + Duration::from_secs(120);
+        assert!(manager
+            .context_at(later, 5, Duration::from_secs(60))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_reset_clears_session() {
+        let manager = ConversationManager::default();
+        manager.record("prompt".to_string(), "output".to_string());
+        manager.reset();
+        assert!(manager.context(5, Duration::from_secs(60)).is_empty());
+    }
+}
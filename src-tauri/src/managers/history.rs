@@ -6,10 +6,18 @@ use rusqlite_migration::{Migrations, M};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
 
 use crate::audio_toolkit::save_wav_file;
+use crate::history_crypto::{self, HistoryKey};
+use crate::settings::AppSettings;
+use crate::settings_validation::SettingsError;
 
 /// Database migrations for transcription history.
 /// Each migration is applied in order. The library tracks which migrations
@@ -31,8 +39,71 @@ static MIGRATIONS: &[M] = &[
     ),
     M::up("ALTER TABLE transcription_history ADD COLUMN post_processed_text TEXT;"),
     M::up("ALTER TABLE transcription_history ADD COLUMN post_process_prompt TEXT;"),
+    // Full-text index over both texts, kept in sync by triggers
+    M::up(
+        "CREATE VIRTUAL TABLE transcription_history_fts USING fts5(
+            transcription_text,
+            post_processed_text,
+            content='transcription_history',
+            content_rowid='id'
+        );
+        CREATE TRIGGER transcription_history_fts_insert AFTER INSERT ON transcription_history BEGIN
+            INSERT INTO transcription_history_fts (rowid, transcription_text, post_processed_text)
+            VALUES (new.id, new.transcription_text, new.post_processed_text);
+        END;
+        CREATE TRIGGER transcription_history_fts_delete AFTER DELETE ON transcription_history BEGIN
+            INSERT INTO transcription_history_fts (transcription_history_fts, rowid, transcription_text, post_processed_text)
+            VALUES ('delete', old.id, old.transcription_text, old.post_processed_text);
+        END;
+        CREATE TRIGGER transcription_history_fts_update AFTER UPDATE OF transcription_text, post_processed_text ON transcription_history BEGIN
+            INSERT INTO transcription_history_fts (transcription_history_fts, rowid, transcription_text, post_processed_text)
+            VALUES ('delete', old.id, old.transcription_text, old.post_processed_text);
+            INSERT INTO transcription_history_fts (rowid, transcription_text, post_processed_text)
+            VALUES (new.id, new.transcription_text, new.post_processed_text);
+        END;
+        INSERT INTO transcription_history_fts (transcription_history_fts) VALUES ('rebuild');",
+    ),
+    // Post-processed outputs replaced by a re-run, removed with their entry
+    M::up(
+        "CREATE TABLE transcription_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            history_id INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            post_processed_text TEXT,
+            post_process_prompt TEXT
+        );
+        CREATE INDEX transcription_revisions_history_id ON transcription_revisions (history_id);
+        CREATE TRIGGER transcription_revisions_delete AFTER DELETE ON transcription_history BEGIN
+            DELETE FROM transcription_revisions WHERE history_id = old.id;
+        END;",
+    ),
+    M::up(
+        "ALTER TABLE transcription_history ADD COLUMN action_id TEXT;
+        ALTER TABLE transcription_history ADD COLUMN duration_ms INTEGER;",
+    ),
+    // Set when retention removed the recording but kept the text
+    M::up("ALTER TABLE transcription_history ADD COLUMN audio_deleted BOOLEAN NOT NULL DEFAULT 0;"),
+    M::up(
+        "ALTER TABLE transcription_history ADD COLUMN app_name TEXT;
+        CREATE INDEX transcription_history_timestamp_id ON transcription_history (timestamp, id);",
+    ),
+    M::up("ALTER TABLE transcription_history ADD COLUMN run_id TEXT;"),
 ];
 
+/// Sample rate recordings are saved at
+const SAMPLE_RATE: i64 = 16000;
+
+/// How often the retention policy is applied while the app runs
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Most results returned by a history search
+const SEARCH_RESULT_LIMIT: usize = 100;
+
+/// Entries per history page when the caller doesn't say
+const DEFAULT_PAGE_SIZE: u32 = 50;
+/// Most entries per history page
+const MAX_PAGE_SIZE: u32 = 500;
+
 #[derive(Clone, Debug, Serialize, Deserialize, Type)]
 pub struct HistoryEntry {
     pub id: i64,
@@ -43,18 +114,371 @@ pub struct HistoryEntry {
     pub transcription_text: String,
     pub post_processed_text: Option<String>,
     pub post_process_prompt: Option<String>,
+    /// Shortcut action that produced the entry; unknown for older entries
+    pub action_id: Option<String>,
+    /// Length of the recording; unknown for older entries
+    pub duration_ms: Option<i64>,
+    /// The recording was removed by the retention policy
+    pub audio_deleted: bool,
+    /// Process name of the app dictated into; unknown for older entries
+    pub app_name: Option<String>,
+    /// Id of the dictation, as in its events and log lines; unknown for
+    /// older entries
+    pub run_id: Option<String>,
+}
+
+/// Entry about to be saved
+struct NewEntry {
+    file_name: String,
+    timestamp: i64,
+    transcription_text: String,
+    post_processed_text: Option<String>,
+    post_process_prompt: Option<String>,
+    action_id: String,
+    duration_ms: i64,
+    app_name: Option<String>,
+    run_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryStatus {
+    Saved,
+    /// Has post-processed text
+    PostProcessed,
+    /// Only the raw transcript
+    Raw,
+}
+
+/// Conditions every listed entry has to meet; unset ones match everything
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Type)]
+pub struct HistoryFilter {
+    #[serde(default)]
+    pub range: HistoryRange,
+    #[serde(default)]
+    pub app_name: Option<String>,
+    #[serde(default)]
+    pub action_id: Option<String>,
+    #[serde(default)]
+    pub status: Option<HistoryStatus>,
+    /// Entries with an unknown length count as 0
+    #[serde(default)]
+    pub min_duration_ms: Option<i64>,
+}
+
+/// Position after the last entry of a page, newest first
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub struct HistoryCursor {
+    pub timestamp: i64,
+    pub id: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    /// Cursor for the next page; None on the last page
+    pub next_cursor: Option<HistoryCursor>,
+}
+
+/// Entries by timestamp in Unix seconds; `end` is exclusive and a missing
+/// bound is open
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Type)]
+pub struct HistoryRange {
+    #[serde(default)]
+    pub start: Option<i64>,
+    #[serde(default)]
+    pub end: Option<i64>,
+}
+
+/// Earlier post-processed output of a history entry, kept when it was
+/// re-processed
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct HistoryRevision {
+    pub id: i64,
+    pub history_id: i64,
+    /// When this output was replaced
+    pub timestamp: i64,
+    pub post_processed_text: Option<String>,
+    pub post_process_prompt: Option<String>,
+}
+
+/// History entry matching a search, with the matching text highlighted
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct HistorySearchResult {
+    pub entry: HistoryEntry,
+    /// Excerpt of the best matching text as HTML, matches wrapped in `<mark>`
+    /// tags and everything else escaped
+    pub snippet: String,
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get("id")?,
+        file_name: row.get("file_name")?,
+        timestamp: row.get("timestamp")?,
+        saved: row.get("saved")?,
+        title: row.get("title")?,
+        transcription_text: row.get("transcription_text")?,
+        post_processed_text: row.get("post_processed_text")?,
+        post_process_prompt: row.get("post_process_prompt")?,
+        action_id: row.get("action_id")?,
+        duration_ms: row.get("duration_ms")?,
+        audio_deleted: row.get("audio_deleted")?,
+        app_name: row.get("app_name")?,
+        run_id: row.get("run_id")?,
+    })
+}
+
+/// Up to `limit` entries matching `filter` after `cursor`, newest first
+fn query_page(
+    conn: &Connection,
+    filter: &HistoryFilter,
+    cursor: Option<HistoryCursor>,
+    limit: u32,
+) -> Result<HistoryPage> {
+    let status = match filter.status {
+        Some(HistoryStatus::Saved) => "saved",
+        Some(HistoryStatus::PostProcessed) => "post_processed",
+        Some(HistoryStatus::Raw) => "raw",
+        None => "",
+    };
+    let cursor = cursor.unwrap_or(HistoryCursor {
+        timestamp: i64::MAX,
+        id: i64::MAX,
+    });
+
+    let mut stmt = conn.prepare(
+        "SELECT id, file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt, action_id, duration_ms, audio_deleted, app_name, run_id
+         FROM transcription_history
+         WHERE timestamp >= ?1 AND timestamp < ?2
+           AND (?3 IS NULL OR app_name = ?3)
+           AND (?4 IS NULL OR action_id = ?4)
+           AND (?5 IS NULL OR COALESCE(duration_ms, 0) >= ?5)
+           AND (?6 = ''
+                OR (?6 = 'saved' AND saved = 1)
+                OR (?6 = 'post_processed' AND post_processed_text IS NOT NULL)
+                OR (?6 = 'raw' AND post_processed_text IS NULL))
+           AND (timestamp < ?7 OR (timestamp = ?7 AND id < ?8))
+         ORDER BY timestamp DESC, id DESC
+         LIMIT ?9",
+    )?;
+
+    // One extra row tells whether there's another page
+    let rows = stmt.query_map(
+        params![
+            filter.range.start.unwrap_or(i64::MIN),
+            filter.range.end.unwrap_or(i64::MAX),
+            filter.app_name,
+            filter.action_id,
+            filter.min_duration_ms,
+            status,
+            cursor.timestamp,
+            cursor.id,
+            i64::from(limit) + 1
+        ],
+        entry_from_row,
+    )?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row?);
+    }
+
+    let next_cursor = if entries.len() > limit as usize {
+        entries.truncate(limit as usize);
+        entries.last().map(|entry| HistoryCursor {
+            timestamp: entry.timestamp,
+            id: entry.id,
+        })
+    } else {
+        None
+    };
+    Ok(HistoryPage {
+        entries,
+        next_cursor,
+    })
+}
+
+/// Indices of the oldest unsaved recordings to delete so the total of
+/// `recordings` (saved flag and size, oldest first) fits in `limit_bytes`
+fn oldest_over_limit(recordings: &[(bool, u64)], limit_bytes: u64) -> Vec<usize> {
+    let mut total: u64 = recordings.iter().map(|(_, size)| size).sum();
+    let mut indices = Vec::new();
+    for (index, (saved, size)) in recordings.iter().enumerate() {
+        if total <= limit_bytes {
+            break;
+        }
+        if !saved {
+            total -= size;
+            indices.push(index);
+        }
+    }
+    indices
+}
+
+/// FTS5 query matching every word of `query` as a prefix, or None when
+/// there's nothing to search for. Words are quoted so user input can't be
+/// read as FTS syntax.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Put around matches by SQLite, they can't be in a transcription
+const MATCH_START: &str = "\u{2}";
+const MATCH_END: &str = "\u{3}";
+
+/// A snippet with its match markers as HTML, the text in between escaped
+fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+    html.replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+/// Entries matching `query` in the raw or post-processed text, best first
+fn search_entries(conn: &Connection, query: &str) -> Result<Vec<HistorySearchResult>> {
+    let fts_query = match fts_query(query) {
+        Some(fts_query) => fts_query,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT h.id, h.file_name, h.timestamp, h.saved, h.title, h.transcription_text, h.post_processed_text, h.post_process_prompt, h.action_id, h.duration_ms, h.audio_deleted, h.app_name, h.run_id,
+                snippet(transcription_history_fts, -1, ?3, ?4, '…', 16) AS snippet
+         FROM transcription_history_fts
+         JOIN transcription_history h ON h.id = transcription_history_fts.rowid
+         WHERE transcription_history_fts MATCH ?1
+         ORDER BY bm25(transcription_history_fts), h.timestamp DESC
+         LIMIT ?2",
+    )?;
+
+    let parameters = params![
+        fts_query,
+        SEARCH_RESULT_LIMIT as i64,
+        MATCH_START,
+        MATCH_END
+    ];
+    let rows = stmt.query_map(parameters, |row| {
+        Ok(HistorySearchResult {
+            entry: entry_from_row(row)?,
+            snippet: highlight(&row.get::<_, String>("snippet")?),
+        })
+    })?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
 }
 
 pub struct HistoryManager {
     app_handle: AppHandle,
     recordings_dir: PathBuf,
     db_path: PathBuf,
+    /// Set while the database and recordings are encrypted
+    key: Mutex<Option<HistoryKey>>,
+    /// Saves underway, waited for on shutdown
+    pending_saves: AtomicUsize,
+    /// Shared by every write to the database, taken whole while it's
+    /// encrypted or decrypted
+    conversion: RwLock<()>,
+}
+
+/// Counts a save as pending while it lives
+struct PendingSave<'a>(&'a AtomicUsize);
+
+impl<'a> PendingSave<'a> {
+    fn new(pending: &'a AtomicUsize) -> Self {
+        pending.fetch_add(1, Ordering::SeqCst);
+        Self(pending)
+    }
+}
+
+impl Drop for PendingSave<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Connection for writing, keeping the database from being converted until
+/// it's closed
+struct WriteConnection<'a> {
+    conn: Connection,
+    _converting: RwLockReadGuard<'a, ()>,
+}
+
+impl Deref for WriteConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl DerefMut for WriteConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+}
+
+/// Start of every plain SQLite database
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// Whether the database at `path` is encrypted, i.e. holds at least a header
+/// but doesn't start like a plain one. SQLite creates an empty file before
+/// writing anything, so a shorter one is plain.
+fn is_encrypted_database(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(SQLITE_HEADER.len());
+    fs::File::open(path)
+        .and_then(|file| {
+            file.take(SQLITE_HEADER.len() as u64)
+                .read_to_end(&mut header)
+        })
+        .is_ok_and(|_| header.len() == SQLITE_HEADER.len() && header != SQLITE_HEADER)
+}
+
+/// Copy the database `conn` has open (including `main`) to a new file at
+/// `target`, encrypted with `key` or in plain text for an empty key
+fn export_database(conn: &Connection, target: &Path, key: &str) -> Result<()> {
+    if target.exists() {
+        fs::remove_file(target)?;
+    }
+    let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let target = target.display().to_string().replace('\'', "''");
+    conn.execute_batch(&format!(
+        "ATTACH DATABASE '{}' AS export KEY {};",
+        target, key
+    ))?;
+    conn.query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()))?;
+    conn.execute_batch(&format!(
+        "PRAGMA export.user_version = {}; DETACH DATABASE export;",
+        version
+    ))?;
+    Ok(())
 }
 
 impl HistoryManager {
     pub fn new(app_handle: &AppHandle) -> Result<Self> {
         // Create recordings directory in app data dir
-        let app_data_dir = app_handle.path().app_data_dir()?;
+        let app_data_dir = crate::portable::app_data_dir(app_handle)?;
         let recordings_dir = app_data_dir.join("recordings");
         let db_path = app_data_dir.join("history.db");
 
@@ -64,10 +488,25 @@ impl HistoryManager {
             debug!("Created recordings directory: {:?}", recordings_dir);
         }
 
+        let key = if crate::settings::get_settings(app_handle).history_encryption_enabled {
+            match history_crypto::load_or_create_key() {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    error!("History encryption is on but the key is unavailable: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         let manager = Self {
             app_handle: app_handle.clone(),
             recordings_dir,
             db_path,
+            key: Mutex::new(key),
+            pending_saves: AtomicUsize::new(0),
+            conversion: RwLock::new(()),
         };
 
         // Initialize database and run migrations synchronously
@@ -79,7 +518,7 @@ impl HistoryManager {
     fn init_database(&self) -> Result<()> {
         info!("Initializing database at {:?}", self.db_path);
 
-        let mut conn = Connection::open(&self.db_path)?;
+        let mut conn = self.get_connection()?;
 
         // Handle migration from tauri-plugin-sql to rusqlite_migration
         // tauri-plugin-sql used _sqlx_migrations table, rusqlite_migration uses user_version pragma
@@ -173,7 +612,110 @@ impl HistoryManager {
     }
 
     fn get_connection(&self) -> Result<Connection> {
-        Ok(Connection::open(&self.db_path)?)
+        let conn = Connection::open(&self.db_path)?;
+        if let Some(key) = self.key() {
+            conn.execute_batch(&format!("PRAGMA key = {};", key.sqlcipher_key()))?;
+        }
+        Ok(conn)
+    }
+
+    /// Connection for a write, which waits while the database is converted
+    fn write_connection(&self) -> Result<WriteConnection<'_>> {
+        let converting = self.conversion.read().unwrap_or_else(|e| e.into_inner());
+        Ok(WriteConnection {
+            conn: self.get_connection()?,
+            _converting: converting,
+        })
+    }
+
+    fn key(&self) -> Option<HistoryKey> {
+        self.key.lock().unwrap().clone()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.key().is_some()
+    }
+
+    /// Encrypt or decrypt the database and all recordings. Does nothing for
+    /// the database when it's already in the requested state. Fails unless
+    /// every file ends up that way. Writes wait until it's done, so none is
+    /// lost when the converted copy replaces the database.
+    pub fn set_encryption(&self, enabled: bool) -> Result<()> {
+        let _converting = self.conversion.write().unwrap_or_else(|e| e.into_inner());
+        let database_encrypted = self.key().is_some() || is_encrypted_database(&self.db_path);
+        let key = match self.key() {
+            Some(key) => key,
+            // Also needed to decrypt files the key wasn't loaded for
+            None if enabled || database_encrypted || self.has_encrypted_recordings() => {
+                history_crypto::load_or_create_key().map_err(anyhow::Error::msg)?
+            }
+            // Never encrypted, nothing to decrypt
+            None => return Ok(()),
+        };
+        if database_encrypted {
+            *self.key.lock().unwrap() = Some(key.clone());
+        }
+
+        if enabled != database_encrypted {
+            let tmp = self.db_path.with_extension("db.tmp");
+            {
+                let conn = self.get_connection()?;
+                let target_key = if enabled {
+                    key.sqlcipher_key()
+                } else {
+                    "''".to_string()
+                };
+                export_database(&conn, &tmp, &target_key)?;
+            }
+            fs::rename(&tmp, &self.db_path)?;
+            *self.key.lock().unwrap() = if enabled { Some(key.clone()) } else { None };
+            info!(
+                "History database {}",
+                if enabled { "encrypted" } else { "decrypted" }
+            );
+        }
+
+        // Also finishes recordings an interrupted run left behind
+        self.convert_recordings(&key, enabled)
+    }
+
+    fn recordings(&self) -> Result<Vec<PathBuf>> {
+        Ok(fs::read_dir(&self.recordings_dir)?
+            .flatten()
+            .map(|file| file.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "wav"))
+            .collect())
+    }
+
+    fn has_encrypted_recordings(&self) -> bool {
+        self.recordings()
+            .unwrap_or_default()
+            .iter()
+            .any(|path| history_crypto::is_encrypted_file(path))
+    }
+
+    /// Convert every recording it can, then fail if any is left behind
+    fn convert_recordings(&self, key: &HistoryKey, encrypt: bool) -> Result<()> {
+        let mut failed = 0;
+        for path in self.recordings()? {
+            let result = if encrypt {
+                history_crypto::encrypt_file(key, &path)
+            } else {
+                history_crypto::decrypt_file(key, &path)
+            };
+            if let Err(e) = result {
+                error!("{}", e);
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!(
+                "{} recording(s) couldn't be {}",
+                failed,
+                if encrypt { "encrypted" } else { "decrypted" }
+            );
+        }
+        Ok(())
     }
 
     /// Save a transcription to history (both database and WAV file)
@@ -183,24 +725,44 @@ impl HistoryManager {
         transcription_text: String,
         post_processed_text: Option<String>,
         post_process_prompt: Option<String>,
+        action_id: String,
+        app_name: Option<String>,
+        run_id: Option<String>,
     ) -> Result<()> {
+        let _pending = PendingSave::new(&self.pending_saves);
+        if !crate::settings::get_settings(&self.app_handle).history_enabled {
+            debug!("History is turned off, not saving the transcription");
+            return Ok(());
+        }
+
         let timestamp = Utc::now().timestamp();
         let file_name = format!("babbl-{}.wav", timestamp);
-        let title = self.format_timestamp_title(timestamp);
+        let duration_ms = audio_samples.len() as i64 * 1000 / SAMPLE_RATE;
 
-        // Save WAV file
+        // Save WAV file, encrypted without touching the disk in plain text
         let file_path = self.recordings_dir.join(&file_name);
-        save_wav_file(file_path, &audio_samples).await?;
+        match self.key() {
+            Some(key) => {
+                let wav = crate::actions::convert_samples_to_wav(&audio_samples)
+                    .and_then(|wav| history_crypto::encrypt(&key, &wav))
+                    .map_err(anyhow::Error::msg)?;
+                fs::write(file_path, wav)?;
+            }
+            None => save_wav_file(file_path, &audio_samples).await?,
+        }
 
         // Save to database
-        self.save_to_database(
+        self.save_to_database(&NewEntry {
             file_name,
             timestamp,
-            title,
             transcription_text,
             post_processed_text,
             post_process_prompt,
-        )?;
+            action_id,
+            duration_ms,
+            app_name,
+            run_id,
+        })?;
 
         // Clean up old entries
         self.cleanup_old_entries()?;
@@ -213,19 +775,29 @@ impl HistoryManager {
         Ok(())
     }
 
-    fn save_to_database(
-        &self,
-        file_name: String,
-        timestamp: i64,
-        title: String,
-        transcription_text: String,
-        post_processed_text: Option<String>,
-        post_process_prompt: Option<String>,
-    ) -> Result<()> {
-        let conn = self.get_connection()?;
+    /// Whether a save is still writing its recording or entry
+    pub fn has_pending_saves(&self) -> bool {
+        self.pending_saves.load(Ordering::SeqCst) > 0
+    }
+
+    fn save_to_database(&self, entry: &NewEntry) -> Result<()> {
+        let title = self.format_timestamp_title(entry.timestamp);
+        let conn = self.write_connection()?;
         conn.execute(
-            "INSERT INTO transcription_history (file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![file_name, timestamp, false, title, transcription_text, post_processed_text, post_process_prompt],
+            "INSERT INTO transcription_history (file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt, action_id, duration_ms, app_name, run_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                entry.file_name,
+                entry.timestamp,
+                false,
+                title,
+                entry.transcription_text,
+                entry.post_processed_text,
+                entry.post_process_prompt,
+                entry.action_id,
+                entry.duration_ms,
+                entry.app_name,
+                entry.run_id
+            ],
         )?;
 
         debug!("Saved transcription to database");
@@ -233,23 +805,53 @@ impl HistoryManager {
     }
 
     pub fn cleanup_old_entries(&self) -> Result<()> {
-        let retention_period = crate::settings::get_recording_retention_period(&self.app_handle);
+        let settings = crate::settings::get_settings(&self.app_handle);
 
-        match retention_period {
+        match settings.recording_retention_period {
             crate::settings::RecordingRetentionPeriod::Never => {
                 // Don't delete anything
-                return Ok(());
             }
             crate::settings::RecordingRetentionPeriod::PreserveLimit => {
                 // Use the old count-based logic with history_limit
-                let limit = crate::settings::get_history_limit(&self.app_handle);
-                return self.cleanup_by_count(limit);
+                self.cleanup_by_count(settings.history_limit, settings.purge_audio_only)?;
             }
-            _ => {
+            retention_period => {
                 // Use time-based logic
-                return self.cleanup_by_time(retention_period);
+                self.cleanup_by_time(
+                    retention_period,
+                    settings.history_retention_days,
+                    settings.purge_audio_only,
+                )?;
             }
         }
+
+        if let Some(limit_mb) = settings.recording_storage_limit_mb {
+            self.enforce_storage_limit(limit_mb.saturating_mul(1024 * 1024))?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove expired entries, or only their recordings when `purge_audio_only`
+    fn expire_entries(
+        &self,
+        entries: &[(i64, String, bool)],
+        purge_audio_only: bool,
+    ) -> Result<usize> {
+        if purge_audio_only {
+            let with_audio: Vec<(i64, String)> = entries
+                .iter()
+                .filter(|(_, _, audio_deleted)| !audio_deleted)
+                .map(|(id, file_name, _)| (*id, file_name.clone()))
+                .collect();
+            return self.delete_audio_files(&with_audio);
+        }
+
+        let entries: Vec<(i64, String)> = entries
+            .iter()
+            .map(|(id, file_name, _)| (*id, file_name.clone()))
+            .collect();
+        self.delete_entries_and_files(&entries)
     }
 
     fn delete_entries_and_files(&self, entries: &[(i64, String)]) -> Result<usize> {
@@ -257,7 +859,7 @@ impl HistoryManager {
             return Ok(0);
         }
 
-        let conn = self.get_connection()?;
+        let conn = self.write_connection()?;
         let mut deleted_count = 0;
 
         for (id, file_name) in entries {
@@ -282,26 +884,58 @@ impl HistoryManager {
         Ok(deleted_count)
     }
 
-    fn cleanup_by_count(&self, limit: usize) -> Result<()> {
+    /// Delete the recordings of `entries` and mark them as gone, keeping the text
+    fn delete_audio_files(&self, entries: &[(i64, String)]) -> Result<usize> {
+        if entries.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.write_connection()?;
+        let mut deleted_count = 0;
+
+        for (id, file_name) in entries {
+            let file_path = self.recordings_dir.join(file_name);
+            if file_path.exists() {
+                if let Err(e) = fs::remove_file(&file_path) {
+                    error!("Failed to delete WAV file {}: {}", file_name, e);
+                    continue;
+                }
+                debug!("Deleted old WAV file: {}", file_name);
+                deleted_count += 1;
+            }
+            conn.execute(
+                "UPDATE transcription_history SET audio_deleted = 1 WHERE id = ?1",
+                params![id],
+            )?;
+        }
+
+        Ok(deleted_count)
+    }
+
+    fn cleanup_by_count(&self, limit: usize, purge_audio_only: bool) -> Result<()> {
         let conn = self.get_connection()?;
 
         // Get all entries that are not saved, ordered by timestamp desc
         let mut stmt = conn.prepare(
-            "SELECT id, file_name FROM transcription_history WHERE saved = 0 ORDER BY timestamp DESC"
+            "SELECT id, file_name, audio_deleted FROM transcription_history WHERE saved = 0 ORDER BY timestamp DESC"
         )?;
 
         let rows = stmt.query_map([], |row| {
-            Ok((row.get::<_, i64>("id")?, row.get::<_, String>("file_name")?))
+            Ok((
+                row.get::<_, i64>("id")?,
+                row.get::<_, String>("file_name")?,
+                row.get::<_, bool>("audio_deleted")?,
+            ))
         })?;
 
-        let mut entries: Vec<(i64, String)> = Vec::new();
+        let mut entries: Vec<(i64, String, bool)> = Vec::new();
         for row in rows {
             entries.push(row?);
         }
 
         if entries.len() > limit {
             let entries_to_delete = &entries[limit..];
-            let deleted_count = self.delete_entries_and_files(entries_to_delete)?;
+            let deleted_count = self.expire_entries(entries_to_delete, purge_audio_only)?;
 
             if deleted_count > 0 {
                 debug!("Cleaned up {} old history entries by count", deleted_count);
@@ -314,6 +948,8 @@ impl HistoryManager {
     fn cleanup_by_time(
         &self,
         retention_period: crate::settings::RecordingRetentionPeriod,
+        retention_days: u32,
+        purge_audio_only: bool,
     ) -> Result<()> {
         let conn = self.get_connection()?;
 
@@ -323,24 +959,31 @@ impl HistoryManager {
             crate::settings::RecordingRetentionPeriod::Days3 => now - (3 * 24 * 60 * 60), // 3 days in seconds
             crate::settings::RecordingRetentionPeriod::Weeks2 => now - (2 * 7 * 24 * 60 * 60), // 2 weeks in seconds
             crate::settings::RecordingRetentionPeriod::Months3 => now - (3 * 30 * 24 * 60 * 60), // 3 months in seconds (approximate)
+            crate::settings::RecordingRetentionPeriod::CustomDays => {
+                now - i64::from(retention_days) * 24 * 60 * 60
+            }
             _ => unreachable!("Should not reach here"),
         };
 
         // Get all unsaved entries older than the cutoff timestamp
         let mut stmt = conn.prepare(
-            "SELECT id, file_name FROM transcription_history WHERE saved = 0 AND timestamp < ?1",
+            "SELECT id, file_name, audio_deleted FROM transcription_history WHERE saved = 0 AND timestamp < ?1",
         )?;
 
         let rows = stmt.query_map(params![cutoff_timestamp], |row| {
-            Ok((row.get::<_, i64>("id")?, row.get::<_, String>("file_name")?))
+            Ok((
+                row.get::<_, i64>("id")?,
+                row.get::<_, String>("file_name")?,
+                row.get::<_, bool>("audio_deleted")?,
+            ))
         })?;
 
-        let mut entries_to_delete: Vec<(i64, String)> = Vec::new();
+        let mut entries_to_delete: Vec<(i64, String, bool)> = Vec::new();
         for row in rows {
             entries_to_delete.push(row?);
         }
 
-        let deleted_count = self.delete_entries_and_files(&entries_to_delete)?;
+        let deleted_count = self.expire_entries(&entries_to_delete, purge_audio_only)?;
 
         if deleted_count > 0 {
             debug!(
@@ -352,25 +995,59 @@ impl HistoryManager {
         Ok(())
     }
 
-    pub async fn get_history_entries(&self) -> Result<Vec<HistoryEntry>> {
+    /// Delete the oldest unsaved recordings until all of them fit in
+    /// `limit_bytes`. The text of those entries is kept.
+    fn enforce_storage_limit(&self, limit_bytes: u64) -> Result<()> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT id, file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt FROM transcription_history ORDER BY timestamp DESC"
+            "SELECT id, file_name, saved FROM transcription_history WHERE audio_deleted = 0 ORDER BY timestamp ASC",
         )?;
 
         let rows = stmt.query_map([], |row| {
-            Ok(HistoryEntry {
-                id: row.get("id")?,
-                file_name: row.get("file_name")?,
-                timestamp: row.get("timestamp")?,
-                saved: row.get("saved")?,
-                title: row.get("title")?,
-                transcription_text: row.get("transcription_text")?,
-                post_processed_text: row.get("post_processed_text")?,
-                post_process_prompt: row.get("post_process_prompt")?,
-            })
+            Ok((
+                row.get::<_, i64>("id")?,
+                row.get::<_, String>("file_name")?,
+                row.get::<_, bool>("saved")?,
+            ))
         })?;
 
+        let mut recordings: Vec<(i64, String, bool, u64)> = Vec::new();
+        for row in rows {
+            let (id, file_name, saved) = row?;
+            let size = fs::metadata(self.recordings_dir.join(&file_name))
+                .map(|meta| meta.len())
+                .unwrap_or(0);
+            recordings.push((id, file_name, saved, size));
+        }
+
+        let sizes: Vec<(bool, u64)> = recordings
+            .iter()
+            .map(|(_, _, saved, size)| (*saved, *size))
+            .collect();
+        let to_delete: Vec<(i64, String)> = oldest_over_limit(&sizes, limit_bytes)
+            .into_iter()
+            .map(|index| (recordings[index].0, recordings[index].1.clone()))
+            .collect();
+
+        let deleted_count = self.delete_audio_files(&to_delete)?;
+        if deleted_count > 0 {
+            debug!(
+                "Deleted {} recordings to stay within the storage limit",
+                deleted_count
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_history_entries(&self) -> Result<Vec<HistoryEntry>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt, action_id, duration_ms, audio_deleted, app_name, run_id FROM transcription_history ORDER BY timestamp DESC"
+        )?;
+
+        let rows = stmt.query_map([], entry_from_row)?;
+
         let mut entries = Vec::new();
         for row in rows {
             entries.push(row?);
@@ -379,8 +1056,52 @@ impl HistoryManager {
         Ok(entries)
     }
 
-    pub async fn toggle_saved_status(&self, id: i64) -> Result<()> {
+    /// Entries from `start` (inclusive) to `end` (exclusive), oldest first
+    pub async fn get_entries_between(
+        &self,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> Result<Vec<HistoryEntry>> {
         let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt, action_id, duration_ms, audio_deleted, app_name, run_id
+             FROM transcription_history
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             ORDER BY timestamp ASC",
+        )?;
+
+        let rows = stmt.query_map(
+            params![start.unwrap_or(i64::MIN), end.unwrap_or(i64::MAX)],
+            entry_from_row,
+        )?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+
+        Ok(entries)
+    }
+
+    /// One page of entries matching `filter`, newest first
+    pub async fn get_history_page(
+        &self,
+        filter: &HistoryFilter,
+        cursor: Option<HistoryCursor>,
+        limit: Option<u32>,
+    ) -> Result<HistoryPage> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let conn = self.get_connection()?;
+        query_page(&conn, filter, cursor, limit)
+    }
+
+    pub async fn search_history(&self, query: &str) -> Result<Vec<HistorySearchResult>> {
+        let conn = self.get_connection()?;
+        search_entries(&conn, query)
+    }
+
+    pub async fn toggle_saved_status(&self, id: i64) -> Result<()> {
+        let conn = self.write_connection()?;
 
         // Get current saved status
         let current_saved: bool = conn.query_row(
@@ -406,40 +1127,103 @@ impl HistoryManager {
         Ok(())
     }
 
+    /// Replace the post-processed output of entry `id`, keeping the current one
+    /// as a revision
+    pub async fn add_revision(
+        &self,
+        id: i64,
+        post_processed_text: String,
+        post_process_prompt: String,
+    ) -> Result<()> {
+        let mut conn = self.write_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO transcription_revisions (history_id, timestamp, post_processed_text, post_process_prompt)
+             SELECT id, ?2, post_processed_text, post_process_prompt FROM transcription_history WHERE id = ?1",
+            params![id, Utc::now().timestamp()],
+        )?;
+        let updated = tx.execute(
+            "UPDATE transcription_history SET post_processed_text = ?1, post_process_prompt = ?2 WHERE id = ?3",
+            params![post_processed_text, post_process_prompt, id],
+        )?;
+        if updated == 0 {
+            return Err(anyhow::anyhow!("History entry {} not found", id));
+        }
+        tx.commit()?;
+
+        debug!("Saved new revision for history entry {}", id);
+
+        // Emit history updated event
+        if let Err(e) = self.app_handle.emit("history-updated", ()) {
+            error!("Failed to emit history-updated event: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// Earlier outputs of entry `id`, newest first
+    pub async fn get_revisions(&self, id: i64) -> Result<Vec<HistoryRevision>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, history_id, timestamp, post_processed_text, post_process_prompt
+             FROM transcription_revisions WHERE history_id = ?1 ORDER BY id DESC",
+        )?;
+
+        let rows = stmt.query_map([id], |row| {
+            Ok(HistoryRevision {
+                id: row.get("id")?,
+                history_id: row.get("history_id")?,
+                timestamp: row.get("timestamp")?,
+                post_processed_text: row.get("post_processed_text")?,
+                post_process_prompt: row.get("post_process_prompt")?,
+            })
+        })?;
+
+        let mut revisions = Vec::new();
+        for row in rows {
+            revisions.push(row?);
+        }
+
+        Ok(revisions)
+    }
+
     pub fn get_audio_file_path(&self, file_name: &str) -> PathBuf {
         self.recordings_dir.join(file_name)
     }
 
+    /// WAV data of the recording `file_name`, decrypted when needed
+    pub fn read_audio(&self, file_name: &str) -> Result<Vec<u8>> {
+        let data = fs::read(self.get_audio_file_path(file_name))?;
+        if !history_crypto::is_encrypted(&data) {
+            return Ok(data);
+        }
+        let key = self
+            .key()
+            .ok_or_else(|| anyhow::anyhow!("Recording is encrypted but the key is unavailable"))?;
+        history_crypto::decrypt(&key, &data).map_err(anyhow::Error::msg)
+    }
+
+    pub fn recordings_dir(&self) -> &Path {
+        &self.recordings_dir
+    }
+
     pub async fn get_entry_by_id(&self, id: i64) -> Result<Option<HistoryEntry>> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT id, file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt
+            "SELECT id, file_name, timestamp, saved, title, transcription_text, post_processed_text, post_process_prompt, action_id, duration_ms, audio_deleted, app_name, run_id
              FROM transcription_history WHERE id = ?1",
         )?;
 
-        let entry = stmt
-            .query_row([id], |row| {
-                Ok(HistoryEntry {
-                    id: row.get("id")?,
-                    file_name: row.get("file_name")?,
-                    timestamp: row.get("timestamp")?,
-                    saved: row.get("saved")?,
-                    title: row.get("title")?,
-                    transcription_text: row.get("transcription_text")?,
-                    post_processed_text: row.get("post_processed_text")?,
-                    post_process_prompt: row.get("post_process_prompt")?,
-                })
-            })
-            .optional()?;
+        let entry = stmt.query_row([id], entry_from_row).optional()?;
 
         Ok(entry)
     }
 
     pub async fn delete_entry(&self, id: i64) -> Result<()> {
-        let conn = self.get_connection()?;
-
         // Get the entry to find the file name
-        if let Some(entry) = self.get_entry_by_id(id).await? {
+        let entry = self.get_entry_by_id(id).await?;
+        let conn = self.write_connection()?;
+        if let Some(entry) = entry {
             // Delete the audio file first
             let file_path = self.get_audio_file_path(&entry.file_name);
             if file_path.exists() {
@@ -476,3 +1260,173 @@ impl HistoryManager {
         }
     }
 }
+
+/// Apply a lower limit or a shorter retention period right away, and
+/// encrypt or decrypt when that was toggled
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    let manager = app.state::<Arc<HistoryManager>>();
+    let mut errors = Vec::new();
+
+    // Already done when the toggle changed it
+    if previous.history_encryption_enabled != next.history_encryption_enabled
+        && manager.is_encrypted() != next.history_encryption_enabled
+    {
+        if let Err(e) = manager.set_encryption(next.history_encryption_enabled) {
+            error!("Failed to change history encryption: {}", e);
+            errors.push(SettingsError::new(
+                "history_encryption_enabled",
+                e.to_string(),
+            ));
+            // Put the toggle back to the state the files are in
+            let mut settings = next.clone();
+            settings.history_encryption_enabled = previous.history_encryption_enabled;
+            if let Err(e) = crate::settings::write_settings(app, settings) {
+                error!("Failed to put the history encryption setting back: {}", e);
+            }
+        }
+    }
+
+    if let Err(e) = manager.cleanup_old_entries() {
+        error!("Failed to clean up history: {}", e);
+    }
+    errors
+}
+
+/// Apply the retention policy periodically, so time-based limits also hold
+/// when nothing new is dictated
+pub fn start_maintenance(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(crate::power::scaled(MAINTENANCE_INTERVAL));
+        if let Err(e) = app.state::<Arc<HistoryManager>>().cleanup_old_entries() {
+            error!("History maintenance failed: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_over_limit() {
+        let recordings = [(false, 40), (true, 40), (false, 40), (false, 40)];
+        assert!(oldest_over_limit(&recordings, 200).is_empty());
+        // Saved recordings are kept even when they're the oldest
+        assert_eq!(oldest_over_limit(&recordings, 90), vec![0, 2]);
+        assert_eq!(oldest_over_limit(&recordings, 0), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn test_is_encrypted_database() {
+        let path = std::env::temp_dir().join(format!("babbl-history-{}.db", std::process::id()));
+        assert!(!is_encrypted_database(&path));
+        // Created but not written to yet
+        fs::write(&path, b"").unwrap();
+        assert!(!is_encrypted_database(&path));
+        fs::write(&path, b"SQLite format 3\0 and pages").unwrap();
+        assert!(!is_encrypted_database(&path));
+        fs::write(&path, [0x5a; 64]).unwrap();
+        assert!(is_encrypted_database(&path));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_query_page() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        Migrations::new(MIGRATIONS.to_vec())
+            .to_latest(&mut conn)
+            .unwrap();
+        for i in 0..5 {
+            conn.execute(
+                "INSERT INTO transcription_history (file_name, timestamp, title, transcription_text, action_id, duration_ms, app_name) VALUES (?1, ?2, '', 'text', ?3, ?4, ?5)",
+                params![
+                    format!("{}.wav", i),
+                    // Two entries share a timestamp to exercise the id tiebreak
+                    i.min(3),
+                    if i % 2 == 0 { "transcribe" } else { "translate" },
+                    i * 1000,
+                    "code"
+                ],
+            )
+            .unwrap();
+        }
+
+        let filter = HistoryFilter::default();
+        let first = query_page(&conn, &filter, None, 2).unwrap();
+        assert_eq!(first.entries.len(), 2);
+        let second = query_page(&conn, &filter, first.next_cursor, 2).unwrap();
+        let third = query_page(&conn, &filter, second.next_cursor, 2).unwrap();
+        assert_eq!(third.entries.len(), 1);
+        assert_eq!(third.next_cursor, None);
+        let ids: Vec<i64> = [first.entries, second.entries, third.entries]
+            .concat()
+            .iter()
+            .map(|entry| entry.id)
+            .collect();
+        assert_eq!(ids, vec![5, 4, 3, 2, 1]);
+
+        let filter = HistoryFilter {
+            action_id: Some("transcribe".to_string()),
+            min_duration_ms: Some(1000),
+            ..HistoryFilter::default()
+        };
+        let page = query_page(&conn, &filter, None, 10).unwrap();
+        let files: Vec<&str> = page.entries.iter().map(|e| e.file_name.as_str()).collect();
+        assert_eq!(files, vec!["4.wav", "2.wav"]);
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("   "), None);
+        assert_eq!(
+            fts_query("Q3 \"budget"),
+            Some("\"Q3\"* \"\"\"budget\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn test_search_entries() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        Migrations::new(MIGRATIONS.to_vec())
+            .to_latest(&mut conn)
+            .unwrap();
+        let insert = "INSERT INTO transcription_history (file_name, timestamp, title, transcription_text, post_processed_text) VALUES (?1, ?2, '', ?3, ?4)";
+        conn.execute(insert, params!["a.wav", 1, "lunch plans", None::<String>])
+            .unwrap();
+        conn.execute(
+            insert,
+            params![
+                "b.wav",
+                2,
+                "um the <b>q3</b> budget",
+                Some("The Q3 budget is final.")
+            ],
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE transcription_history SET post_processed_text = 'Budget review' WHERE file_name = 'a.wav'",
+            [],
+        )
+        .unwrap();
+
+        let results = search_entries(&conn, "budg").unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].entry.file_name, "b.wav");
+        assert_eq!(
+            results[0].snippet,
+            "um the &lt;b&gt;q3&lt;/b&gt; <mark>budget</mark>"
+        );
+
+        conn.execute(
+            "DELETE FROM transcription_history WHERE file_name = 'b.wav'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(search_entries(&conn, "q3").unwrap().len(), 0);
+    }
+}
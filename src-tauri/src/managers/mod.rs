@@ -1,4 +1,6 @@
 pub mod audio;
+pub mod conversation;
 pub mod history;
 pub mod model;
 pub mod transcription;
+pub mod usage;
@@ -1,3 +1,4 @@
+use crate::progress::{self, Operation, OperationKind};
 use crate::settings::{get_settings, write_settings};
 use anyhow::Result;
 use flate2::read::GzDecoder;
@@ -54,9 +55,7 @@ pub struct ModelManager {
 impl ModelManager {
     pub fn new(app_handle: &AppHandle) -> Result<Self> {
         // Create models directory in app data
-        let models_dir = app_handle
-            .path()
-            .app_data_dir()
+        let models_dir = crate::portable::app_data_dir(app_handle)
             .map_err(|e| anyhow::anyhow!("Failed to get app data dir: {}", e))?
             .join("models");
 
@@ -302,7 +301,7 @@ impl ModelManager {
                 // Update settings with the selected model
                 let mut updated_settings = settings;
                 updated_settings.selected_model = available_model.id.clone();
-                write_settings(&self.app_handle, updated_settings);
+                write_settings(&self.app_handle, updated_settings).map_err(anyhow::Error::msg)?;
 
                 info!("Successfully auto-selected model: {}", available_model.id);
             }
@@ -348,6 +347,10 @@ impl ModelManager {
             0
         };
 
+        // Uses the proxy and CA settings, so downloads work on corporate networks
+        let client =
+            crate::http::client(&get_settings(&self.app_handle)).map_err(|e| anyhow::anyhow!(e))?;
+
         // Mark as downloading
         {
             let mut models = self.available_models.lock().unwrap();
@@ -355,9 +358,13 @@ impl ModelManager {
                 model.is_downloading = true;
             }
         }
+        let mut operation = Operation::start(
+            &self.app_handle,
+            OperationKind::ModelDownload,
+            Some(model_id),
+        );
 
-        // Create HTTP client with range request for resuming
-        let client = reqwest::Client::new();
+        // Create request with range header for resuming
         let mut request = client.get(&url);
 
         if resume_from > 0 {
@@ -438,6 +445,18 @@ impl ModelManager {
 
         // Download with progress
         while let Some(chunk) = stream.next().await {
+            if operation.is_cancelled() {
+                // The partial file is kept so the download can be resumed later
+                file.flush()?;
+                {
+                    let mut models = self.available_models.lock().unwrap();
+                    if let Some(model) = models.get_mut(model_id) {
+                        model.is_downloading = false;
+                    }
+                }
+                return Err(anyhow::anyhow!("Download of {} cancelled", model_id));
+            }
+
             let chunk = chunk.map_err(|e| {
                 // Mark as not downloading on error
                 {
@@ -467,6 +486,7 @@ impl ModelManager {
             };
 
             let _ = self.app_handle.emit("model-download-progress", &progress);
+            operation.report("downloading", (total_size > 0).then_some(percentage));
         }
 
         file.flush()?;
@@ -496,6 +516,7 @@ impl ModelManager {
         if model_info.is_directory {
             // Emit extraction started event
             let _ = self.app_handle.emit("model-extraction-started", model_id);
+            operation.report("extracting", None);
             info!("Extracting archive for directory-based model: {}", model_id);
 
             // Use a temporary extraction directory to ensure atomic operations
@@ -577,6 +598,7 @@ impl ModelManager {
         }
 
         // Emit completion event
+        operation.complete();
         let _ = self.app_handle.emit("model-download-complete", model_id);
 
         info!(
@@ -710,9 +732,9 @@ impl ModelManager {
             }
         }
 
-        // Note: The actual download cancellation would need to be handled
-        // by the download task itself. This just updates the state.
-        // The partial file is kept so the download can be resumed later.
+        // The download task stops at its next chunk and keeps the partial
+        // file, so the download can be resumed later
+        progress::cancel_all(OperationKind::ModelDownload, Some(model_id));
 
         // Update download status to reflect current state
         self.update_download_status()?;
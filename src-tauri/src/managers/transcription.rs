@@ -1,14 +1,17 @@
 use crate::audio_toolkit::apply_custom_words;
 use crate::managers::model::{EngineType, ModelManager};
-use crate::settings::{get_settings, ModelUnloadTimeout};
+use crate::progress::{Operation, OperationKind};
+use crate::settings::{get_settings, AppSettings, ModelUnloadTimeout};
+use crate::settings_validation::SettingsError;
 use anyhow::Result;
 use log::{debug, error, info, warn};
 use serde::Serialize;
+use std::ops::Range;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
 use std::time::{Duration, SystemTime};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use transcribe_rs::{
     engines::{
         parakeet::{
@@ -19,6 +22,15 @@ use transcribe_rs::{
     TranscriptionEngine,
 };
 
+/// Recordings longer than this (16 kHz samples) are transcribed in chunks,
+/// reporting progress between them
+const LONG_AUDIO_SAMPLES: usize = 16000 * 120;
+/// Length a long recording's chunks aim for
+const CHUNK_SAMPLES: usize = 16000 * 60;
+/// How far before a chunk's end to look for a pause to cut at
+const SPLIT_SEARCH_SAMPLES: usize = 16000 * 5;
+const SPLIT_WINDOW_SAMPLES: usize = 16000 / 10;
+
 #[derive(Clone, Debug, Serialize)]
 pub struct ModelStateEvent {
     pub event_type: String,
@@ -288,7 +300,7 @@ impl TranscriptionManager {
         let self_clone = self.clone();
         thread::spawn(move || {
             let settings = get_settings(&self_clone.app_handle);
-            if let Err(e) = self_clone.load_model(&settings.selected_model) {
+            if let Err(e) = self_clone.load_model(crate::power::model_for(&settings)) {
                 error!("Failed to load model: {}", e);
             }
             let mut is_loading = self_clone.is_loading.lock().unwrap();
@@ -302,7 +314,8 @@ impl TranscriptionManager {
         current_model.clone()
     }
 
-    pub fn transcribe(&self, audio: Vec<f32>) -> Result<String> {
+    /// Transcribe `audio` in `language`, a language code or "auto"
+    pub fn transcribe(&self, audio: Vec<f32>, language: &str) -> Result<String> {
         // Update last activity timestamp
         self.last_activity.store(
             SystemTime::now()
@@ -339,7 +352,7 @@ impl TranscriptionManager {
         let settings = get_settings(&self.app_handle);
 
         // Perform transcription with the appropriate engine
-        let result = {
+        let text = {
             let mut engine_guard = self.engine.lock().unwrap();
             let engine = engine_guard.as_mut().ok_or_else(|| {
                 anyhow::anyhow!(
@@ -347,55 +360,51 @@ impl TranscriptionManager {
                 )
             })?;
 
-            match engine {
-                LoadedEngine::Whisper(whisper_engine) => {
-                    // Normalize language code for Whisper
-                    // Convert zh-Hans and zh-Hant to zh since Whisper uses ISO 639-1 codes
-                    let whisper_language = if settings.selected_language == "auto" {
-                        None
-                    } else {
-                        let normalized = if settings.selected_language == "zh-Hans"
-                            || settings.selected_language == "zh-Hant"
-                        {
-                            "zh".to_string()
-                        } else {
-                            settings.selected_language.clone()
-                        };
-                        Some(normalized)
-                    };
-
-                    let params = WhisperInferenceParams {
-                        language: whisper_language,
-                        translate: settings.translate_to_english,
-                        ..Default::default()
-                    };
-
-                    whisper_engine
-                        .transcribe_samples(audio, Some(params))
-                        .map_err(|e| anyhow::anyhow!("Whisper transcription failed: {}", e))?
-                }
-                LoadedEngine::Parakeet(parakeet_engine) => {
-                    let params = ParakeetInferenceParams {
-                        timestamp_granularity: TimestampGranularity::Segment,
-                        ..Default::default()
-                    };
-
-                    parakeet_engine
-                        .transcribe_samples(audio, Some(params))
-                        .map_err(|e| anyhow::anyhow!("Parakeet transcription failed: {}", e))?
+            if audio.len() <= LONG_AUDIO_SAMPLES {
+                transcribe_samples(engine, audio, language, settings.translate_to_english)?
+            } else {
+                let ranges = chunk_ranges(
+                    &audio,
+                    CHUNK_SAMPLES,
+                    SPLIT_SEARCH_SAMPLES,
+                    SPLIT_WINDOW_SAMPLES,
+                );
+                debug!("Transcribing long recording in {} chunks", ranges.len());
+                let mut operation =
+                    Operation::start(&self.app_handle, OperationKind::Transcription, None);
+                let mut parts = Vec::with_capacity(ranges.len());
+                for (index, range) in ranges.iter().enumerate() {
+                    if operation.is_cancelled() {
+                        return Err(anyhow::anyhow!("Transcription cancelled"));
+                    }
+                    operation.report(
+                        "transcribing",
+                        Some(index as f64 / ranges.len() as f64 * 100.0),
+                    );
+                    let part = transcribe_samples(
+                        engine,
+                        audio[range.clone()].to_vec(),
+                        language,
+                        settings.translate_to_english,
+                    )?;
+                    parts.push(part.trim().to_string());
+                    crate::pipeline::partial(&self.app_handle, &parts.join(" "));
                 }
+                operation.complete();
+                parts.retain(|part| !part.is_empty());
+                parts.join(" ")
             }
         };
 
         // Apply word correction if custom words are configured
         let corrected_result = if !settings.custom_words.is_empty() {
             apply_custom_words(
-                &result.text,
+                &text,
                 &settings.custom_words,
                 settings.word_correction_threshold,
             )
         } else {
-            result.text
+            text
         };
 
         let et = std::time::Instant::now();
@@ -415,7 +424,10 @@ impl TranscriptionManager {
         if final_result.is_empty() {
             info!("Transcription result is empty");
         } else {
-            info!("Transcription result: {}", final_result);
+            info!(
+                target: crate::logging::TRANSCRIPT_TARGET,
+                "Transcription result: {}", final_result
+            );
         }
 
         // Check if we should immediately unload the model after transcription
@@ -430,6 +442,106 @@ impl TranscriptionManager {
     }
 }
 
+fn transcribe_samples(
+    engine: &mut LoadedEngine,
+    audio: Vec<f32>,
+    language: &str,
+    translate: bool,
+) -> Result<String> {
+    let result = match engine {
+        LoadedEngine::Whisper(whisper_engine) => {
+            // Normalize language code for Whisper
+            // Convert zh-Hans and zh-Hant to zh since Whisper uses ISO 639-1 codes
+            let whisper_language = if language == "auto" {
+                None
+            } else {
+                let normalized = if language == "zh-Hans" || language == "zh-Hant" {
+                    "zh".to_string()
+                } else {
+                    language.to_string()
+                };
+                Some(normalized)
+            };
+
+            let params = WhisperInferenceParams {
+                language: whisper_language,
+                translate,
+                ..Default::default()
+            };
+
+            whisper_engine
+                .transcribe_samples(audio, Some(params))
+                .map_err(|e| anyhow::anyhow!("Whisper transcription failed: {}", e))?
+        }
+        LoadedEngine::Parakeet(parakeet_engine) => {
+            let params = ParakeetInferenceParams {
+                timestamp_granularity: TimestampGranularity::Segment,
+                ..Default::default()
+            };
+
+            parakeet_engine
+                .transcribe_samples(audio, Some(params))
+                .map_err(|e| anyhow::anyhow!("Parakeet transcription failed: {}", e))?
+        }
+    };
+    Ok(result.text)
+}
+
+/// Split `audio` into chunks of at most `chunk_len` samples, each cut in the
+/// quietest `window` of the last `search_len` samples so words stay whole
+fn chunk_ranges(
+    audio: &[f32],
+    chunk_len: usize,
+    search_len: usize,
+    window: usize,
+) -> Vec<Range<usize>> {
+    let window = window.max(1);
+    let energy = |pos: usize| -> f32 { audio[pos..pos + window].iter().map(|s| s * s).sum() };
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while audio.len() - start > chunk_len {
+        let target = start + chunk_len;
+        let search_start = target.saturating_sub(search_len).max(start + window);
+        let cut = (search_start..=target.saturating_sub(window))
+            .step_by(window)
+            .min_by(|&a, &b| energy(a).total_cmp(&energy(b)))
+            .map_or(target, |pos| pos + window / 2);
+        ranges.push(start..cut);
+        start = cut;
+    }
+    ranges.push(start..audio.len());
+    ranges
+}
+
+/// Swap in the model `settings` call for when another one is loaded, so the
+/// next dictation doesn't wait for it
+pub fn switch_model(app: &AppHandle, settings: &AppSettings) {
+    let tm = app.state::<Arc<TranscriptionManager>>();
+    // Nothing is loaded, or whoever selected the model loaded it already
+    match tm.get_current_model() {
+        Some(current) if current != crate::power::model_for(settings) => {
+            if let Err(e) = tm.unload_model() {
+                warn!("Failed to unload model '{}': {}", current, e);
+            }
+            tm.initiate_model_load();
+        }
+        _ => {}
+    }
+}
+
+/// Swap in a newly selected model when another one is loaded
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.selected_model != next.selected_model {
+        switch_model(app, next);
+    }
+    Vec::new()
+}
+
 impl Drop for TranscriptionManager {
     fn drop(&mut self) {
         debug!("Shutting down TranscriptionManager");
@@ -447,3 +559,22 @@ impl Drop for TranscriptionManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_ranges() {
+        assert_eq!(chunk_ranges(&[0.5; 30], 40, 10, 2), vec![0..30]);
+
+        // A pause at 34..36 is where the first chunk ends
+        let mut audio = vec![0.5; 100];
+        audio[34] = 0.0;
+        audio[35] = 0.0;
+        assert_eq!(
+            chunk_ranges(&audio, 40, 10, 2),
+            vec![0..35, 35..66, 66..100]
+        );
+    }
+}
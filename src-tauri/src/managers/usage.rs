@@ -0,0 +1,354 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use log::{debug, info};
+use rusqlite::{params, Connection};
+use rusqlite_migration::{Migrations, M};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use crate::llm_types::Usage;
+use crate::pricing;
+use crate::settings::ModelPrice;
+
+/// Database migrations for LLM token usage counters.
+/// Counters are aggregated per local day, provider, and model.
+static MIGRATIONS: &[M] = &[
+    M::up(
+        "CREATE TABLE IF NOT EXISTS llm_usage_daily (
+            day TEXT NOT NULL,
+            provider_id TEXT NOT NULL,
+            model TEXT NOT NULL,
+            request_count INTEGER NOT NULL DEFAULT 0,
+            prompt_tokens INTEGER NOT NULL DEFAULT 0,
+            completion_tokens INTEGER NOT NULL DEFAULT 0,
+            total_tokens INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (day, provider_id, model)
+        );",
+    ),
+    // Failed requests; request_count only counts successful ones
+    M::up("ALTER TABLE llm_usage_daily ADD COLUMN error_count INTEGER NOT NULL DEFAULT 0;"),
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct DailyUsage {
+    pub day: String,
+    pub provider_id: String,
+    pub model: String,
+    pub request_count: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+pub struct UsageStats {
+    pub daily: Vec<DailyUsage>,
+    pub request_count: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct PeriodCost {
+    /// Day (`YYYY-MM-DD`), week start (Monday) or provider id
+    pub key: String,
+    pub cost_usd: f64,
+}
+
+/// Estimated spend, derived from the token counters and the pricing table
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+pub struct CostStats {
+    pub total_usd: f64,
+    pub daily: Vec<PeriodCost>,
+    pub weekly: Vec<PeriodCost>,
+    pub by_provider: Vec<PeriodCost>,
+    /// Spend since the first of the current month, compared against the budget
+    pub month_to_date_usd: f64,
+    pub monthly_budget_usd: Option<f64>,
+    /// Models with usage but no known price; their tokens are not counted
+    pub unpriced_models: Vec<String>,
+}
+
+/// Successful and failed LLM requests of one provider
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct ProviderErrors {
+    pub provider_id: String,
+    pub request_count: i64,
+    pub error_count: i64,
+    /// Failed share of all requests, 0 to 1
+    pub error_rate: f64,
+}
+
+pub struct UsageManager {
+    db_path: PathBuf,
+    /// Month (`YYYY-MM`) for which the over-budget warning was already sent
+    budget_warned_month: Mutex<Option<String>>,
+}
+
+impl UsageManager {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = crate::portable::app_data_dir(app_handle)?;
+        let db_path = app_data_dir.join("usage.db");
+
+        let manager = Self {
+            db_path,
+            budget_warned_month: Mutex::new(None),
+        };
+        manager.init_database()?;
+
+        Ok(manager)
+    }
+
+    fn init_database(&self) -> Result<()> {
+        info!("Initializing usage database at {:?}", self.db_path);
+
+        let mut conn = Connection::open(&self.db_path)?;
+        let migrations = Migrations::new(MIGRATIONS.to_vec());
+
+        #[cfg(debug_assertions)]
+        migrations.validate().expect("Invalid usage migrations");
+
+        migrations.to_latest(&mut conn)?;
+        Ok(())
+    }
+
+    fn get_connection(&self) -> Result<Connection> {
+        Ok(Connection::open(&self.db_path)?)
+    }
+
+    /// Add the usage of a single completion to today's counters
+    pub fn record(&self, provider_id: &str, model: &str, usage: &Usage) -> Result<()> {
+        let day = Local::now().format("%Y-%m-%d").to_string();
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO llm_usage_daily (day, provider_id, model, request_count, prompt_tokens, completion_tokens, total_tokens)
+             VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6)
+             ON CONFLICT(day, provider_id, model) DO UPDATE SET
+                request_count = request_count + 1,
+                prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                completion_tokens = completion_tokens + excluded.completion_tokens,
+                total_tokens = total_tokens + excluded.total_tokens",
+            params![
+                day,
+                provider_id,
+                model,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+                usage.total_tokens
+            ],
+        )?;
+
+        debug!(
+            "Recorded LLM usage for {}/{}: {} tokens",
+            provider_id, model, usage.total_tokens
+        );
+        Ok(())
+    }
+
+    /// Count a failed completion in today's counters
+    pub fn record_error(&self, provider_id: &str, model: &str) -> Result<()> {
+        let day = Local::now().format("%Y-%m-%d").to_string();
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO llm_usage_daily (day, provider_id, model, error_count)
+             VALUES (?1, ?2, ?3, 1)
+             ON CONFLICT(day, provider_id, model) DO UPDATE SET
+                error_count = error_count + 1",
+            params![day, provider_id, model],
+        )?;
+
+        debug!("Recorded LLM error for {}/{}", provider_id, model);
+        Ok(())
+    }
+
+    /// Requests and failures per provider between the local days `since` and
+    /// `until` (`YYYY-MM-DD`, inclusive)
+    pub fn get_provider_errors(&self, since: &str, until: &str) -> Result<Vec<ProviderErrors>> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT provider_id, SUM(request_count) AS request_count, SUM(error_count) AS error_count
+             FROM llm_usage_daily WHERE day >= ?1 AND day <= ?2
+             GROUP BY provider_id ORDER BY provider_id",
+        )?;
+
+        let rows = stmt.query_map(params![since, until], |row| {
+            let request_count: i64 = row.get("request_count")?;
+            let error_count: i64 = row.get("error_count")?;
+            let total = request_count + error_count;
+            Ok(ProviderErrors {
+                provider_id: row.get("provider_id")?,
+                request_count,
+                error_count,
+                error_rate: if total > 0 {
+                    error_count as f64 / total as f64
+                } else {
+                    0.0
+                },
+            })
+        })?;
+
+        let mut providers = Vec::new();
+        for row in rows {
+            providers.push(row?);
+        }
+
+        Ok(providers)
+    }
+
+    /// Get the per-day counters for the last `days` days (all history when `None`)
+    pub fn get_stats(&self, days: Option<u32>) -> Result<UsageStats> {
+        let since = days
+            .map(|d| {
+                (Local::now() - Duration::days(d.saturating_sub(1) as i64))
+                    .format("%Y-%m-%d")
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT day, provider_id, model, request_count, prompt_tokens, completion_tokens, total_tokens
+             FROM llm_usage_daily WHERE day >= ?1 ORDER BY day DESC, provider_id, model",
+        )?;
+
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(DailyUsage {
+                day: row.get("day")?,
+                provider_id: row.get("provider_id")?,
+                model: row.get("model")?,
+                request_count: row.get("request_count")?,
+                prompt_tokens: row.get("prompt_tokens")?,
+                completion_tokens: row.get("completion_tokens")?,
+                total_tokens: row.get("total_tokens")?,
+            })
+        })?;
+
+        let mut stats = UsageStats::default();
+        for row in rows {
+            let entry = row?;
+            stats.request_count += entry.request_count;
+            stats.prompt_tokens += entry.prompt_tokens;
+            stats.completion_tokens += entry.completion_tokens;
+            stats.total_tokens += entry.total_tokens;
+            stats.daily.push(entry);
+        }
+
+        Ok(stats)
+    }
+
+    /// Estimated spend over the last `days` days (all history when `None`)
+    pub fn get_cost_stats(
+        &self,
+        days: Option<u32>,
+        custom_prices: &HashMap<String, ModelPrice>,
+        monthly_budget_usd: Option<f64>,
+    ) -> Result<CostStats> {
+        let usage = self.get_stats(days)?;
+
+        let mut daily: BTreeMap<String, f64> = BTreeMap::new();
+        let mut weekly: BTreeMap<String, f64> = BTreeMap::new();
+        let mut by_provider: BTreeMap<String, f64> = BTreeMap::new();
+        let mut stats = CostStats {
+            monthly_budget_usd,
+            ..CostStats::default()
+        };
+
+        for entry in &usage.daily {
+            let price = pricing::price_for_model(&entry.provider_id, &entry.model, custom_prices);
+            let price = match price {
+                Some(price) => price,
+                None => {
+                    if !stats.unpriced_models.contains(&entry.model) {
+                        stats.unpriced_models.push(entry.model.clone());
+                    }
+                    continue;
+                }
+            };
+            let cost = pricing::estimate_cost(&price, entry.prompt_tokens, entry.completion_tokens);
+
+            stats.total_usd += cost;
+            *daily.entry(entry.day.clone()).or_default() += cost;
+            *weekly.entry(week_start(&entry.day)).or_default() += cost;
+            *by_provider.entry(entry.provider_id.clone()).or_default() += cost;
+        }
+
+        let to_costs = |map: BTreeMap<String, f64>| {
+            map.into_iter()
+                .rev()
+                .map(|(key, cost_usd)| PeriodCost { key, cost_usd })
+                .collect()
+        };
+        stats.daily = to_costs(daily);
+        stats.weekly = to_costs(weekly);
+        stats.by_provider = to_costs(by_provider);
+        stats
+            .by_provider
+            .sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+        stats.month_to_date_usd = self.month_to_date_cost(custom_prices)?;
+        Ok(stats)
+    }
+
+    /// Estimated spend since the first day of the current month
+    pub fn month_to_date_cost(&self, custom_prices: &HashMap<String, ModelPrice>) -> Result<f64> {
+        let today = Local::now().date_naive();
+        let usage = self.get_stats(Some(today.day()))?;
+        Ok(usage
+            .daily
+            .iter()
+            .filter_map(|entry| {
+                pricing::price_for_model(&entry.provider_id, &entry.model, custom_prices).map(
+                    |price| {
+                        pricing::estimate_cost(&price, entry.prompt_tokens, entry.completion_tokens)
+                    },
+                )
+            })
+            .sum())
+    }
+
+    /// Returns true the first time per month the budget is found exceeded,
+    /// so the warning isn't repeated after every dictation
+    pub fn should_warn_budget(&self, spent_usd: f64, budget_usd: f64) -> bool {
+        if spent_usd <= budget_usd {
+            return false;
+        }
+        let month = Local::now().format("%Y-%m").to_string();
+        let mut warned = self.budget_warned_month.lock().unwrap();
+        if warned.as_deref() == Some(month.as_str()) {
+            return false;
+        }
+        *warned = Some(month);
+        true
+    }
+}
+
+/// Monday of the week containing `day` (`YYYY-MM-DD`); unparsable days are kept as-is
+fn week_start(day: &str) -> String {
+    match NaiveDate::parse_from_str(day, "%Y-%m-%d") {
+        Ok(date) => {
+            let offset = date.weekday().num_days_from_monday() as i64;
+            (date - Duration::days(offset))
+                .format("%Y-%m-%d")
+                .to_string()
+        }
+        Err(_) => day.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_week_start() {
+        // 2024-05-15 was a Wednesday
+        assert_eq!(week_start("2024-05-15"), "2024-05-13");
+        assert_eq!(week_start("2024-05-13"), "2024-05-13");
+        assert_eq!(week_start("2024-05-19"), "2024-05-13");
+        assert_eq!(week_start("garbage"), "garbage");
+    }
+}
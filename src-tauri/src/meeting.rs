@@ -0,0 +1,291 @@
+//! Meeting transcription: a recording that runs until it's stopped, cut into
+//! segments at pauses in the speech. Each segment is transcribed as soon as
+//! it's cut and appended with its time into the meeting to a Markdown
+//! document, and saved to history under the meeting's run id so its entries
+//! stay together.
+//!
+//! Unlike a dictation nothing is pasted or post-processed, and the pipeline
+//! stays idle, so the overlay and integrations don't take it for one. Only
+//! the microphone is recorded, and speakers aren't told apart since none of
+//! the transcription models label them.
+
+use crate::actions;
+use crate::managers::audio::AudioRecordingManager;
+use crate::managers::history::HistoryManager;
+use crate::managers::transcription::TranscriptionManager;
+use crate::note;
+use crate::pipeline;
+use crate::settings::{self, AppSettings};
+use crate::tray::{self, TrayIconState};
+use chrono::{DateTime, Local};
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Action id the meeting records and is kept in history under
+const ACTION_ID: &str = "meeting";
+
+/// Silence after which the speech so far becomes a segment
+const PAUSE: Duration = Duration::from_millis(800);
+
+/// Less speech waits for the next pause, it transcribes poorly on its own
+const MIN_SEGMENT: Duration = Duration::from_secs(3);
+
+/// Speech without a pause is cut here anyway
+const MAX_SEGMENT: Duration = Duration::from_secs(30);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct MeetingSession {
+    /// Run id of the meeting's history entries
+    pub id: String,
+    /// Unix milliseconds
+    pub started_at: i64,
+    /// Markdown document the transcript is written to
+    pub document: String,
+}
+
+/// Payload of the `meeting-segment` event
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct MeetingSegment {
+    pub session_id: String,
+    /// Time into the meeting the segment started at
+    pub offset_ms: u64,
+    pub text: String,
+}
+
+/// Meeting being transcribed; taken out when it's asked to stop, while its
+/// thread still transcribes the last segment
+static ACTIVE: Lazy<Mutex<Option<MeetingSession>>> = Lazy::new(|| Mutex::new(None));
+
+/// `offset` as `HH:MM:SS`
+fn timestamp(offset: Duration) -> String {
+    let secs = offset.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Whether `buffered` speech followed by `silence` makes a segment
+fn should_cut(buffered: Duration, silence: Duration) -> bool {
+    buffered >= MAX_SEGMENT || (buffered >= MIN_SEGMENT && silence >= PAUSE)
+}
+
+/// Speech held by the recording; the VAD leaves out the silence
+fn buffered(rm: &AudioRecordingManager) -> Duration {
+    let usage = rm.buffer_usage();
+    let samples = (usage.memory_bytes + usage.spilled_bytes) / std::mem::size_of::<f32>();
+    Duration::from_secs_f64(
+        samples as f64 / crate::audio_toolkit::constants::WHISPER_SAMPLE_RATE as f64,
+    )
+}
+
+fn document_path(
+    app: &AppHandle,
+    settings: &AppSettings,
+    at: DateTime<Local>,
+) -> Result<PathBuf, String> {
+    let dir = match settings
+        .meeting_notes_dir
+        .as_deref()
+        .filter(|dir| !dir.trim().is_empty())
+    {
+        Some(dir) => note::resolve_note_path(dir, at.date_naive()),
+        None => crate::portable::app_data_dir(app)
+            .map_err(|e| format!("Failed to find the app data folder: {}", e))?
+            .join("meetings"),
+    };
+    Ok(dir.join(format!("Meeting {}.md", at.format("%Y-%m-%d %H-%M"))))
+}
+
+fn is_active(session_id: &str) -> bool {
+    ACTIVE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|session| session.id == session_id)
+}
+
+fn emit_state(app: &AppHandle) {
+    if let Err(e) = app.emit("meeting-state", current()) {
+        warn!("Failed to emit meeting state: {}", e);
+    }
+    if !pipeline::is_busy() {
+        tray::update_tray_menu(app, &TrayIconState::Idle);
+    }
+}
+
+/// Transcribe a segment that started `offset` into the meeting and add it to
+/// the document and history
+fn keep_segment(app: &AppHandle, session: &MeetingSession, offset: Duration, samples: Vec<f32>) {
+    if samples.is_empty() {
+        return;
+    }
+    let settings = settings::get_settings(app);
+    let tm = app.state::<Arc<TranscriptionManager>>();
+    let text = match tauri::async_runtime::block_on(actions::transcribe_audio(
+        &settings,
+        &tm,
+        samples.clone(),
+    )) {
+        Ok(text) if !text.trim().is_empty() => text.trim().to_string(),
+        Ok(_) => return,
+        Err(e) => {
+            error!("Failed to transcribe a meeting segment: {}", e);
+            return;
+        }
+    };
+
+    let entry = format!("**[{}]** {}\n", timestamp(offset), text);
+    if let Err(e) = note::append_entry(Path::new(&session.document), &entry) {
+        error!("{}", e);
+    }
+    if settings.meeting_save_to_history {
+        let hm = app.state::<Arc<HistoryManager>>();
+        if let Err(e) = tauri::async_runtime::block_on(hm.save_transcription(
+            samples,
+            text.clone(),
+            None,
+            None,
+            ACTION_ID.to_string(),
+            None,
+            Some(session.id.clone()),
+        )) {
+            error!("Failed to save a meeting segment: {}", e);
+        }
+    }
+    let segment = MeetingSegment {
+        session_id: session.id.clone(),
+        offset_ms: offset.as_millis() as u64,
+        text,
+    };
+    if let Err(e) = app.emit("meeting-segment", segment) {
+        warn!("Failed to emit meeting segment: {}", e);
+    }
+}
+
+fn run(app: AppHandle, session: MeetingSession, started: Instant) {
+    let rm = app.state::<Arc<AudioRecordingManager>>();
+    // When the speech now in the recording began
+    let mut segment_start: Option<Duration> = None;
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        if buffered(&rm).is_zero() {
+            segment_start = None;
+        } else if segment_start.is_none() {
+            segment_start = Some(started.elapsed());
+        }
+        let offset = segment_start.unwrap_or_else(|| started.elapsed());
+
+        if !is_active(&session.id) {
+            let samples = rm.stop_recording(ACTION_ID).unwrap_or_default();
+            keep_segment(&app, &session, offset, samples);
+            break;
+        }
+        let silence = match rm.silent_for() {
+            Some(silence) => silence,
+            // Cancelled along with everything else
+            None => {
+                ACTIVE.lock().unwrap().take();
+                emit_state(&app);
+                break;
+            }
+        };
+        if should_cut(buffered(&rm), silence) {
+            if let Some(samples) = rm.take_segment(ACTION_ID) {
+                segment_start = None;
+                keep_segment(&app, &session, offset, samples);
+            }
+        }
+    }
+    info!("Meeting transcript written to '{}'", session.document);
+}
+
+/// The meeting being transcribed, if any
+pub fn current() -> Option<MeetingSession> {
+    ACTIVE.lock().unwrap().clone()
+}
+
+/// Start recording and transcribing a meeting
+pub fn start(app: &AppHandle) -> Result<MeetingSession, String> {
+    let mut active = ACTIVE.lock().unwrap();
+    if active.is_some() {
+        return Err("A meeting is already being transcribed".to_string());
+    }
+    if pipeline::is_busy() {
+        return Err("Finish the dictation first".to_string());
+    }
+    let settings = settings::get_settings(app);
+    let now = Local::now();
+    let document = document_path(app, &settings, now)?;
+
+    let rm = app.state::<Arc<AudioRecordingManager>>();
+    if !rm.try_start_segmented_recording(ACTION_ID) {
+        return Err("Couldn't start recording, another one may still be running".to_string());
+    }
+    let heading = format!("# Meeting, {}\n", now.format("%Y-%m-%d %H:%M"));
+    if let Err(e) = note::append_entry(&document, &heading) {
+        rm.cancel_recording();
+        return Err(e);
+    }
+    if !settings.use_online_provider {
+        app.state::<Arc<TranscriptionManager>>()
+            .initiate_model_load();
+    }
+
+    let session = MeetingSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        started_at: now.timestamp_millis(),
+        document: document.display().to_string(),
+    };
+    *active = Some(session.clone());
+    drop(active);
+    info!("Transcribing a meeting to '{}'", session.document);
+    emit_state(app);
+
+    let started = Instant::now();
+    let thread_app = app.clone();
+    let thread_session = session.clone();
+    std::thread::spawn(move || run(thread_app, thread_session, started));
+    Ok(session)
+}
+
+/// Stop the meeting; its last segment is still transcribed
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    let session = ACTIVE
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("No meeting is being transcribed")?;
+    debug!("Stopping meeting {}", session.id);
+    emit_state(app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(Duration::from_millis(59_900)), "00:00:59");
+        assert_eq!(
+            timestamp(Duration::from_secs(3 * 3600 + 25 * 60 + 7)),
+            "03:25:07"
+        );
+    }
+
+    #[test]
+    fn test_should_cut() {
+        let secs = Duration::from_secs;
+        assert!(should_cut(secs(5), secs(1)));
+        assert!(!should_cut(secs(5), Duration::from_millis(300)));
+        // Too little to transcribe on its own yet
+        assert!(!should_cut(secs(1), secs(2)));
+        assert!(should_cut(MAX_SEGMENT, Duration::ZERO));
+    }
+}
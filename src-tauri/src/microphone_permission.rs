@@ -0,0 +1,156 @@
+//! Microphone access. macOS hands an app without access a stream of zeros
+//! instead of an error, so the authorization is looked at before recording:
+//! the system prompt is shown at the first recording attempt (or from the
+//! onboarding), and once access was refused the user is told how to turn it
+//! back on instead of getting empty transcriptions.
+
+use crate::notifications::{self, NotificationKind};
+use log::{info, warn};
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter};
+
+#[cfg(target_os = "macos")]
+const PRIVACY_SETTINGS_URL: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone";
+
+#[cfg(target_os = "windows")]
+const PRIVACY_SETTINGS_URL: &str = "ms-settings:privacy-microphone";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum MicrophoneAccess {
+    /// Not asked yet; the system prompt shows when requested
+    NotDetermined,
+    Granted,
+    /// Refused by the user, only the system settings can turn it back on
+    Denied,
+    /// Blocked by a profile or parental controls
+    Restricted,
+    /// Can't be looked at on this platform
+    Unknown,
+}
+
+impl MicrophoneAccess {
+    /// Message on how to get access back, when recording can't work
+    pub fn problem(self) -> Option<&'static str> {
+        match self {
+            Self::Denied => Some(
+                "Babbl isn't allowed to use the microphone. Turn it on in System Settings > \
+                 Privacy & Security > Microphone.",
+            ),
+            Self::Restricted => Some(
+                "Microphone access is blocked on this Mac, likely by a profile or parental \
+                 controls.",
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn status() -> MicrophoneAccess {
+    use objc2_av_foundation::{AVAuthorizationStatus, AVCaptureDevice, AVMediaTypeAudio};
+
+    let media_type = match unsafe { AVMediaTypeAudio } {
+        Some(media_type) => media_type,
+        None => return MicrophoneAccess::Unknown,
+    };
+    match unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) } {
+        AVAuthorizationStatus::NotDetermined => MicrophoneAccess::NotDetermined,
+        AVAuthorizationStatus::Authorized => MicrophoneAccess::Granted,
+        AVAuthorizationStatus::Denied => MicrophoneAccess::Denied,
+        AVAuthorizationStatus::Restricted => MicrophoneAccess::Restricted,
+        _ => MicrophoneAccess::Unknown,
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn status() -> MicrophoneAccess {
+    MicrophoneAccess::Unknown
+}
+
+/// Show the system prompt for microphone access, if it wasn't answered yet
+pub fn request() {
+    #[cfg(target_os = "macos")]
+    tauri::async_runtime::spawn(async {
+        if let Err(e) = tauri_plugin_macos_permissions::request_microphone_permission().await {
+            warn!("Failed to request microphone access: {}", e);
+        }
+    });
+}
+
+/// Open the system settings page where microphone access is turned on
+pub fn open_settings(app: &AppHandle) -> Result<(), String> {
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        use tauri_plugin_opener::OpenerExt;
+
+        app.opener()
+            .open_url(PRIVACY_SETTINGS_URL, None::<String>)
+            .map_err(|e| format!("Failed to open the privacy settings: {}", e))
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = app;
+        Err("There are no microphone privacy settings on this platform".to_string())
+    }
+}
+
+fn report(app: &AppHandle, access: MicrophoneAccess, title: &str, message: &str) {
+    warn!("{}, microphone access is {:?}", title, access);
+    notifications::notify(app, NotificationKind::PermissionProblem, title, message);
+    if let Err(e) = app.emit("microphone-access", access) {
+        warn!("Failed to emit microphone access: {}", e);
+    }
+}
+
+/// Whether a recording may start now. Asking for access or being refused it
+/// drops this attempt; the next one records once access is given.
+pub fn ensure(app: &AppHandle) -> bool {
+    let access = status();
+    if access == MicrophoneAccess::NotDetermined {
+        info!("Asking for microphone access");
+        request();
+        if let Err(e) = app.emit("microphone-access", access) {
+            warn!("Failed to emit microphone access: {}", e);
+        }
+        return false;
+    }
+    match access.problem() {
+        Some(message) => {
+            report(app, access, "Microphone access is off", message);
+            false
+        }
+        None => true,
+    }
+}
+
+/// Whether `samples` are digital silence, what a blocked microphone records;
+/// real microphones always pick up some noise
+pub fn is_blocked_signal(samples: &[f32]) -> bool {
+    !samples.is_empty() && samples.iter().all(|sample| *sample == 0.0)
+}
+
+/// Tell the user a recording came back as digital silence
+pub fn report_blocked_signal(app: &AppHandle) {
+    let access = status();
+    let message = access.problem().unwrap_or(
+        "The microphone recorded nothing at all. Check that Babbl may use it and that it \
+         isn't muted.",
+    );
+    report(app, access, "The microphone recorded nothing", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_blocked_signal() {
+        assert!(is_blocked_signal(&[0.0; 1600]));
+        assert!(!is_blocked_signal(&[0.0, 0.0, 0.0001, 0.0]));
+        assert!(!is_blocked_signal(&[]));
+    }
+}
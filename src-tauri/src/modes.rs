@@ -0,0 +1,79 @@
+use crate::settings::ActionConfig;
+
+/// A post-processing mode that ships with the app. Each mode is its own
+/// action id, so it can be bound to a separate shortcut.
+pub struct BuiltinMode {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub system_prompt: &'static str,
+    pub prompt_template: &'static str,
+}
+
+const PRESERVE_MEANING: &str = "You rewrite dictated text. Keep the speaker's meaning, facts, names and numbers intact. Output only the rewritten text with no preamble, quotes or explanations.";
+
+pub const BUILTIN_MODES: &[BuiltinMode] = &[
+    BuiltinMode {
+        id: "mode_grammar_fix",
+        name: "Grammar Fix",
+        description: "Transcribes and fixes grammar, spelling and punctuation.",
+        system_prompt: PRESERVE_MEANING,
+        prompt_template: "Fix grammar, spelling and punctuation in the following transcript. Remove filler words (um, uh, like) and false starts, but do not change the wording otherwise.\n\nTranscript:\n${output}",
+    },
+    BuiltinMode {
+        id: "mode_formalize",
+        name: "Formalize",
+        description: "Transcribes and rewrites in a professional tone.",
+        system_prompt: PRESERVE_MEANING,
+        prompt_template: "Rewrite the following transcript in a clear, polite and professional tone suitable for work email. Use complete sentences and avoid slang.\n\nTranscript:\n${output}",
+    },
+    BuiltinMode {
+        id: "mode_casualize",
+        name: "Casualize",
+        description: "Transcribes and rewrites in a relaxed, friendly tone.",
+        system_prompt: PRESERVE_MEANING,
+        prompt_template: "Rewrite the following transcript in a relaxed, friendly tone suitable for a chat message. Keep it short and natural.\n\nTranscript:\n${output}",
+    },
+    BuiltinMode {
+        id: "mode_summarize",
+        name: "Summarize",
+        description: "Transcribes and condenses into a short summary.",
+        system_prompt: "You summarize dictated text. Output only the summary with no preamble.",
+        prompt_template: "Summarize the following transcript in at most three sentences, keeping every decision, date and number.\n\nTranscript:\n${output}",
+    },
+    BuiltinMode {
+        id: "mode_bullet_points",
+        name: "Bullet Points",
+        description: "Transcribes and turns the content into a bulleted list.",
+        system_prompt: "You turn dictated text into concise bullet points. Output only the list, one item per line starting with \"- \".",
+        prompt_template: "Convert the following transcript into bullet points. Merge duplicate ideas and keep the original order.\n\nTranscript:\n${output}",
+    },
+    BuiltinMode {
+        id: "mode_translate",
+        name: "Translate",
+        description: "Transcribes and translates into your target language.",
+        system_prompt: "You are a professional translator. Output only the translation with no preamble or notes.",
+        prompt_template: "Translate the following transcript into ${language}. Preserve formatting, names and numbers.\n\nTranscript:\n${output}",
+    },
+    BuiltinMode {
+        id: "mode_edit_selection",
+        name: "Edit Selection",
+        description: "Rewrites the highlighted text the way you say.",
+        system_prompt: "You edit text following a spoken instruction. Change only what the instruction asks for. Output only the edited text with no preamble, quotes or explanations.",
+        prompt_template: "Edit the following text as instructed. If there is no text, write it from the instruction instead.\n\nInstruction:\n${output}\n\nText:\n${selection}",
+    },
+];
+
+pub fn builtin_mode(id: &str) -> Option<&'static BuiltinMode> {
+    BUILTIN_MODES.iter().find(|mode| mode.id == id)
+}
+
+impl BuiltinMode {
+    pub fn action_config(&self) -> ActionConfig {
+        ActionConfig {
+            system_prompt: Some(self.system_prompt.to_string()),
+            prompt_template: Some(self.prompt_template.to_string()),
+            ..ActionConfig::default()
+        }
+    }
+}
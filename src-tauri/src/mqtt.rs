@@ -0,0 +1,280 @@
+//! Optional MQTT bridge for home automation. Under the configured prefix it
+//! publishes `availability` (`online`/`offline`), the pipeline `state` (both
+//! retained) and each final `transcript`, and takes `start`, `stop`,
+//! `toggle` or `cancel` on `command`, optionally followed by an action id.
+//!
+//! A host written as `mqtts://broker` connects over TLS, checked against the
+//! system's root certificates.
+
+use crate::control_api;
+use crate::pipeline::{self, PipelineState};
+use crate::settings::{self, AppSettings};
+use crate::settings_validation::SettingsError;
+use crate::utils::cancel_current_operation;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS, Transport};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Listener};
+
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Wait between attempts while the broker can't be reached
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Bumped whenever the bridge stops, which ends its thread
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Connected client and its topic prefix
+static CLIENT: Lazy<Mutex<Option<(Client, String)>>> = Lazy::new(|| Mutex::new(None));
+
+static PUBLISH_TRANSCRIPTS: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Start,
+    Stop,
+    Toggle,
+    Cancel,
+}
+
+/// Settings the connection depends on
+#[derive(PartialEq)]
+struct Config {
+    host: String,
+    port: u16,
+    tls: bool,
+    username: Option<String>,
+    password: Option<String>,
+    prefix: String,
+}
+
+impl Config {
+    fn of(settings: &AppSettings) -> Option<Self> {
+        // An unknown scheme is left in the host, connecting then fails
+        let (host, tls) = parse_host(&settings.mqtt_host).unwrap_or((&settings.mqtt_host, false));
+        settings.mqtt_enabled.then(|| Self {
+            host: host.to_string(),
+            port: settings.mqtt_port,
+            tls,
+            username: settings.mqtt_username.clone(),
+            password: settings.mqtt_password.clone(),
+            prefix: settings.mqtt_topic_prefix.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+/// Broker host and whether it's reached over TLS, from a host that may start
+/// with `mqtt://` or `mqtts://`; `None` for other schemes
+pub fn parse_host(host: &str) -> Option<(&str, bool)> {
+    let (host, tls) = match host.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("mqtts") => (rest, true),
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("mqtt") => (rest, false),
+        Some(_) => return None,
+        None => (host, false),
+    };
+    Some((host.trim_end_matches('/'), tls))
+}
+
+/// Command and optional action id of a command message, e.g. `start mode_email`
+fn parse_command(payload: &str) -> Option<(Command, Option<String>)> {
+    let mut words = payload.split_whitespace();
+    let command = match words.next()?.to_lowercase().as_str() {
+        "start" => Command::Start,
+        "stop" => Command::Stop,
+        "toggle" => Command::Toggle,
+        "cancel" => Command::Cancel,
+        _ => return None,
+    };
+    Some((command, words.next().map(str::to_string)))
+}
+
+fn run_command(app: &AppHandle, payload: &str) {
+    let (command, binding_id) = match parse_command(payload) {
+        Some(parsed) => parsed,
+        None => {
+            warn!("Ignoring unknown MQTT command '{}'", payload);
+            return;
+        }
+    };
+    debug!("MQTT command: {:?} {:?}", command, binding_id);
+    let active = match command {
+        Command::Start => true,
+        Command::Stop => false,
+        Command::Toggle => pipeline::current() != PipelineState::Recording,
+        Command::Cancel => {
+            cancel_current_operation(app);
+            return;
+        }
+    };
+
+    let binding_id = binding_id.unwrap_or_else(|| "transcribe".to_string());
+    if !settings::get_settings(app)
+        .bindings
+        .contains_key(&binding_id)
+    {
+        warn!("MQTT command for unknown action '{}'", binding_id);
+        return;
+    }
+    if let Err(e) = control_api::set_recording(app, &binding_id, active) {
+        warn!("MQTT command failed: {}", e);
+    }
+}
+
+/// Publish `payload` under the prefix without waiting; dropped when not
+/// connected
+fn publish(topic: &str, payload: String, retain: bool) {
+    if let Some((client, prefix)) = CLIENT.lock().unwrap().as_ref() {
+        let topic = format!("{}/{}", prefix, topic);
+        if let Err(e) = client.try_publish(&topic, QoS::AtLeastOnce, retain, payload) {
+            debug!("Failed to publish to {}: {}", topic, e);
+        }
+    }
+}
+
+/// Name of a state as in the `pipeline-state` event
+fn state_name(state: &Value) -> Option<String> {
+    state.as_str().map(str::to_string)
+}
+
+/// Publish pipeline states and final transcripts; the listeners stay for the
+/// app's lifetime and do nothing while the bridge is off
+pub fn init(app: &AppHandle) {
+    app.listen_any("pipeline-state", |event| {
+        let state = serde_json::from_str::<Value>(event.payload())
+            .ok()
+            .and_then(|event| state_name(&event["state"]));
+        if let Some(state) = state {
+            publish("state", state, true);
+        }
+    });
+    app.listen_any("transcript-final", |event| {
+        if PUBLISH_TRANSCRIPTS.load(Ordering::Relaxed) {
+            publish("transcript", event.payload().to_string(), false);
+        }
+    });
+}
+
+fn start(app: &AppHandle, config: Config) {
+    let client_id = format!("babbl-{}", std::process::id());
+    let mut options = MqttOptions::new(client_id, config.host.clone(), config.port);
+    options.set_keep_alive(KEEP_ALIVE);
+    if config.tls {
+        options.set_transport(Transport::tls_with_default_config());
+    }
+    if let Some(username) = config.username.as_deref().filter(|u| !u.is_empty()) {
+        options.set_credentials(username, config.password.as_deref().unwrap_or_default());
+    }
+    let availability = format!("{}/availability", config.prefix);
+    options.set_last_will(LastWill::new(
+        &availability,
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+
+    let (client, mut connection) = Client::new(options, 10);
+    *CLIENT.lock().unwrap() = Some((client.clone(), config.prefix.clone()));
+
+    let generation = GENERATION.load(Ordering::Relaxed);
+    let command_topic = format!("{}/command", config.prefix);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // Reconnects on the next iteration after an error
+        for notification in connection.iter() {
+            if GENERATION.load(Ordering::Relaxed) != generation {
+                break;
+            }
+            match notification {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    info!("Connected to MQTT broker {}:{}", config.host, config.port);
+                    let _ = client.try_subscribe(&command_topic, QoS::AtLeastOnce);
+                    let _ = client.try_publish(&availability, QoS::AtLeastOnce, true, "online");
+                    let state = serde_json::to_value(pipeline::current()).unwrap_or_default();
+                    if let Some(state) = state_name(&state) {
+                        publish("state", state, true);
+                    }
+                }
+                Ok(Event::Incoming(Packet::Publish(message))) if message.topic == command_topic => {
+                    run_command(&app, &String::from_utf8_lossy(&message.payload));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(
+                        "MQTT connection to {}:{} failed: {}",
+                        config.host, config.port, e
+                    );
+                    std::thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        }
+        debug!("MQTT bridge stopped");
+    });
+}
+
+fn stop() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    if let Some((client, prefix)) = CLIENT.lock().unwrap().take() {
+        // The last will only covers connections that drop
+        let topic = format!("{}/availability", prefix);
+        let _ = client.try_publish(&topic, QoS::AtLeastOnce, true, "offline");
+        let _ = client.try_disconnect();
+    }
+}
+
+/// Connect or disconnect per the settings
+pub fn sync(app: &AppHandle) {
+    stop();
+    let settings = settings::get_settings(app);
+    PUBLISH_TRANSCRIPTS.store(settings.mqtt_publish_transcripts, Ordering::Relaxed);
+    if let Some(config) = Config::of(&settings) {
+        start(app, config);
+    }
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if Config::of(previous) != Config::of(next)
+        || previous.mqtt_publish_transcripts != next.mqtt_publish_transcripts
+    {
+        sync(app);
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host() {
+        assert_eq!(parse_host("broker.local"), Some(("broker.local", false)));
+        assert_eq!(
+            parse_host("mqtt://broker.local"),
+            Some(("broker.local", false))
+        );
+        assert_eq!(
+            parse_host("MQTTS://broker.local/"),
+            Some(("broker.local", true))
+        );
+        assert_eq!(parse_host("https://broker.local"), None);
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("start"), Some((Command::Start, None)));
+        assert_eq!(
+            parse_command(" Toggle mode_email\n"),
+            Some((Command::Toggle, Some("mode_email".to_string())))
+        );
+        assert_eq!(parse_command("cancel"), Some((Command::Cancel, None)));
+        assert_eq!(parse_command("reboot"), None);
+        assert_eq!(parse_command(""), None);
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Local, NaiveDate};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Placeholder in the note path replaced with the current date, for one file per day
+const DATE_PLACEHOLDER: &str = "{date}";
+
+/// Path of the note for `date`; `{date}` becomes `YYYY-MM-DD` and a leading
+/// `~` the home directory
+pub fn resolve_note_path(template: &str, date: NaiveDate) -> PathBuf {
+    let path = template
+        .trim()
+        .replace(DATE_PLACEHOLDER, &date.format("%Y-%m-%d").to_string());
+    match path.strip_prefix("~/") {
+        Some(rest) => match std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+            Some(home) => PathBuf::from(home).join(rest),
+            None => PathBuf::from(path),
+        },
+        None => PathBuf::from(path),
+    }
+}
+
+/// Markdown entry for one transcript: a timestamp heading, then the text
+pub fn format_note_entry(text: &str, at: DateTime<Local>) -> String {
+    format!("## {}\n\n{}\n", at.format("%Y-%m-%d %H:%M"), text.trim())
+}
+
+/// Append `text` with a timestamp to the note at `template`, creating the file
+/// and its directory as needed. Returns the path written to.
+pub fn append_to_note(template: &str, text: &str) -> Result<PathBuf, String> {
+    if template.trim().is_empty() {
+        return Err("No note file configured".to_string());
+    }
+    let now = Local::now();
+    let path = resolve_note_path(template, now.date_naive());
+    append_entry(&path, &format_note_entry(text, now))?;
+    Ok(path)
+}
+
+/// Append `entry` to the Markdown file at `path`, after a blank line,
+/// creating the file and its directory as needed
+pub fn append_entry(path: &Path, entry: &str) -> Result<(), String> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+
+    // Keep a blank line between entries, also after text the user wrote
+    let separator = match file.metadata().map(|meta| meta.len()).unwrap_or(0) {
+        0 => "",
+        len => {
+            let mut tail = [0u8; 2];
+            let read = file
+                .seek(SeekFrom::Start(len.saturating_sub(2)))
+                .and_then(|_| file.read(&mut tail))
+                .unwrap_or(0);
+            match &tail[..read] {
+                b"\n\n" => "",
+                [.., b'\n'] => "\n",
+                _ => "\n\n",
+            }
+        }
+    };
+
+    file.write_all(format!("{}{}", separator, entry).as_bytes())
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_resolve_note_path() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(
+            resolve_note_path("/notes/daily/{date}.md", date),
+            PathBuf::from("/notes/daily/2026-03-09.md")
+        );
+        assert_eq!(
+            resolve_note_path(" /notes/journal.md ", date),
+            PathBuf::from("/notes/journal.md")
+        );
+    }
+
+    #[test]
+    fn test_format_note_entry() {
+        let at = Local.with_ymd_and_hms(2026, 3, 9, 8, 5, 0).unwrap();
+        assert_eq!(
+            format_note_entry("  Call the bank.\n", at),
+            "## 2026-03-09 08:05\n\nCall the bank.\n"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! OS notifications for outcomes the user would otherwise only find in the
+//! log. Desktop notifications can't report clicks, so the section a click
+//! should open is handed to the main window when it's focused shortly after.
+
+use crate::settings::{self, AppSettings};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// How long after a notification focusing the main window counts as its click
+const CLICK_WINDOW: Duration = Duration::from_secs(30);
+
+/// Notification body length before it's cut off
+const BODY_CHARS: usize = 160;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Finished while another app than the one dictated into was focused
+    TranscriptionComplete,
+    /// Transcription or post-processing provider failed
+    ProviderError,
+    /// Microphone or input control was refused, or a link wasn't allowed
+    PermissionProblem,
+    /// Asked for by a notify step of an action chain
+    ActionResult,
+    /// A recording was left unfinished when Babbl last stopped
+    RecordingRecovered,
+}
+
+impl NotificationKind {
+    /// Settings section of the main window that deals with it
+    fn target(self) -> &'static str {
+        match self {
+            Self::TranscriptionComplete => "history",
+            Self::ProviderError => "postprocessing",
+            Self::PermissionProblem => "general",
+            Self::ActionResult => "history",
+            Self::RecordingRecovered => "history",
+        }
+    }
+}
+
+/// Payload of the `notification` and `notification-clicked` events
+#[derive(Clone, Serialize, Type)]
+pub struct NotificationEvent {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    /// Settings section to open
+    pub target: String,
+}
+
+static LAST: Lazy<Mutex<Option<(NotificationEvent, Instant)>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn is_enabled(settings: &AppSettings, kind: NotificationKind) -> bool {
+    match kind {
+        NotificationKind::TranscriptionComplete => settings.notify_transcription_complete,
+        NotificationKind::ProviderError => settings.notify_provider_errors,
+        NotificationKind::PermissionProblem => settings.notify_permission_problems,
+        // The chain was set up to notify
+        NotificationKind::ActionResult => true,
+        // The audio is lost for good if it's overlooked
+        NotificationKind::RecordingRecovered => true,
+    }
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(BODY_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Show a notification, unless its kind is turned off
+pub fn notify(app: &AppHandle, kind: NotificationKind, title: &str, body: &str) {
+    if !is_enabled(&settings::get_settings(app), kind) {
+        return;
+    }
+
+    let event = NotificationEvent {
+        kind,
+        title: title.to_string(),
+        body: truncate(body),
+        target: kind.target().to_string(),
+    };
+    debug!("Notifying {:?}: {}", kind, event.title);
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(&event.title)
+        .body(&event.body)
+        .show()
+    {
+        warn!("Failed to show notification: {}", e);
+    }
+    if let Err(e) = app.emit("notification", event.clone()) {
+        warn!("Failed to emit notification event: {}", e);
+    }
+    *LAST.lock().unwrap() = Some((event, Instant::now()));
+}
+
+/// Notify that the text is ready when the user has switched away from
+/// `target_app`, the app focused when the recording stopped
+pub fn notify_if_focus_moved(app: &AppHandle, target_app: Option<&str>, text: &str) {
+    let target_app = match target_app {
+        Some(target_app) => target_app,
+        None => return,
+    };
+    let focused = crate::app_overrides::focused_process_name()
+        .map(|name| crate::app_overrides::normalize_process_name(&name));
+    if focused.is_none_or(|focused| focused == target_app) {
+        return;
+    }
+    notify(
+        app,
+        NotificationKind::TranscriptionComplete,
+        "Transcription complete",
+        text,
+    );
+}
+
+/// Tell the main window which section to open when it's focused right after a
+/// notification, which is how a click on one shows up
+pub fn on_main_window_focused(app: &AppHandle) {
+    let last = LAST.lock().unwrap().take();
+    if let Some((event, at)) = last {
+        if at.elapsed() <= CLICK_WINDOW {
+            if let Err(e) = app.emit("notification-clicked", event) {
+                warn!("Failed to emit notification click: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("  short  "), "short");
+        let long = "é".repeat(BODY_CHARS + 5);
+        assert_eq!(truncate(&long).chars().count(), BODY_CHARS + 1);
+    }
+}
@@ -0,0 +1,215 @@
+//! The Notion output target: each result becomes a page in a database, its
+//! first line as the title and the text as the body. It needs the token of
+//! an internal integration that the database is shared with.
+
+use crate::output::ActionOutput;
+use crate::settings::{ActionConfig, AppSettings};
+use log::{debug, error};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const API_URL: &str = "https://api.notion.com/v1";
+
+const API_VERSION: &str = "2022-06-28";
+
+/// Like webhooks, Notion is given up on when it doesn't answer in time
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const TITLE_CHARS: usize = 80;
+
+/// Longest text Notion takes in one rich text object
+const TEXT_CHARS: usize = 2000;
+
+/// Most blocks a page can be created with
+const MAX_BLOCKS: usize = 100;
+
+/// Id of a database given by its id or the link to it
+pub fn database_id(input: &str) -> Option<String> {
+    let input = input.trim();
+    let path = input.split(['?', '#']).next().unwrap_or_default();
+    let last = path.rsplit('/').next().unwrap_or_default();
+    let hex: String = last.chars().filter(|c| *c != '-').collect();
+    // Links end in `<title>-<id>`
+    let id = hex.get(hex.len().saturating_sub(32)..)?;
+    (id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit())).then(|| id.to_lowercase())
+}
+
+/// First line of `text`, shortened to a title
+fn title(text: &str) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("Babbl");
+    match line.char_indices().nth(TITLE_CHARS) {
+        Some((end, _)) => format!("{}…", line[..end].trim_end()),
+        None => line.to_string(),
+    }
+}
+
+/// Paragraphs of `text`, each split into pieces Notion accepts
+fn paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    for paragraph in text.split("\n\n").map(str::trim) {
+        let chars: Vec<char> = paragraph.chars().collect();
+        paragraphs.extend(
+            chars
+                .chunks(TEXT_CHARS)
+                .map(|chunk| chunk.iter().collect::<String>()),
+        );
+    }
+    paragraphs.retain(|paragraph| !paragraph.is_empty());
+    paragraphs.truncate(MAX_BLOCKS);
+    paragraphs
+}
+
+fn page(database_id: &str, title_property: &str, text: &str) -> Value {
+    let children: Vec<Value> = paragraphs(text)
+        .into_iter()
+        .map(|paragraph| {
+            json!({
+                "object": "block",
+                "type": "paragraph",
+                "paragraph": { "rich_text": [{ "type": "text", "text": { "content": paragraph } }] },
+            })
+        })
+        .collect();
+    json!({
+        "parent": { "database_id": database_id },
+        "properties": {
+            title_property: { "title": [{ "type": "text", "text": { "content": title(text) } }] },
+        },
+        "children": children,
+    })
+}
+
+/// Name of the database's title column, which differs between databases
+async fn title_property(
+    client: &reqwest::Client,
+    token: &str,
+    database_id: &str,
+) -> Result<String, String> {
+    let database: Value = client
+        .get(format!("{}/databases/{}", API_URL, database_id))
+        .bearer_auth(token)
+        .header("Notion-Version", API_VERSION)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to read the Notion database: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to read the Notion database: {}", e))?;
+    database["properties"]
+        .as_object()
+        .and_then(|properties| {
+            properties
+                .iter()
+                .find(|(_, property)| property["type"] == "title")
+        })
+        .map(|(name, _)| name.clone())
+        .ok_or_else(|| "The Notion database has no title column".to_string())
+}
+
+async fn create_page(
+    client: reqwest::Client,
+    token: String,
+    database_id: String,
+    text: String,
+) -> Result<(), String> {
+    let title_property = title_property(&client, &token, &database_id).await?;
+    let response = client
+        .post(format!("{}/pages", API_URL))
+        .bearer_auth(&token)
+        .header("Notion-Version", API_VERSION)
+        .json(&page(&database_id, &title_property, &text))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach Notion: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        return Err(format!(
+            "Notion refused the page ({}): {}",
+            status,
+            body["message"].as_str().unwrap_or("no reason given")
+        ));
+    }
+    Ok(())
+}
+
+/// Create a page for `output` in the action's database in the background
+pub fn spawn(settings: &AppSettings, config: &ActionConfig, output: &ActionOutput) {
+    let token = config
+        .notion_token
+        .clone()
+        .filter(|token| !token.trim().is_empty());
+    let database_id = config.notion_database_id.as_deref().and_then(database_id);
+    let (token, database_id) = match (token, database_id) {
+        (Some(token), Some(database_id)) => (token, database_id),
+        _ => {
+            error!(
+                "Action '{}' has a Notion target but no token or database",
+                output.binding_id
+            );
+            return;
+        }
+    };
+    let client = match crate::http::client_builder(settings)
+        .and_then(|builder| builder.timeout(TIMEOUT).build().map_err(|e| e.to_string()))
+    {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to build Notion client: {}", e);
+            return;
+        }
+    };
+    let text = output.text.clone();
+    tauri::async_runtime::spawn(async move {
+        match create_page(client, token, database_id.clone(), text).await {
+            Ok(()) => debug!("Added the result to Notion database {}", database_id),
+            Err(e) => error!("{}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_database_id() {
+        let id = "1f2e3d4c5b6a79881f2e3d4c5b6a7988";
+        assert_eq!(database_id(id).as_deref(), Some(id));
+        assert_eq!(
+            database_id("1f2e3d4c-5b6a-7988-1f2e-3d4c5b6a7988").as_deref(),
+            Some(id)
+        );
+        assert_eq!(
+            database_id(&format!(
+                "https://www.notion.so/team/Voice-Notes-{}?v=abc",
+                id
+            ))
+            .as_deref(),
+            Some(id)
+        );
+        assert_eq!(database_id("Voice Notes"), None);
+    }
+
+    #[test]
+    fn test_page() {
+        let text = format!("Standup notes\n\n{}", "a".repeat(TEXT_CHARS + 1));
+        let page = page("db", "Name", &text);
+        assert_eq!(
+            page["properties"]["Name"]["title"][0]["text"]["content"],
+            "Standup notes"
+        );
+        let children = page["children"].as_array().unwrap();
+        assert_eq!(children.len(), 3);
+        assert_eq!(
+            children[2]["paragraph"]["rich_text"][0]["text"]["content"],
+            "a"
+        );
+        assert_eq!(title(&"b".repeat(100)).chars().count(), TITLE_CHARS + 1);
+    }
+}
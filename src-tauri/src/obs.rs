@@ -0,0 +1,373 @@
+//! Live captions in OBS. While on, Babbl connects to obs-websocket (v5, built
+//! into OBS 28 and later) and keeps the text of a text source in sync with
+//! the dictation: the partial transcript while recording and the final one
+//! afterwards, cleared again after a while. Overlays that want more control
+//! can use the WebSocket server's `transcript-partial` and `transcript-final`
+//! events from a browser source instead.
+
+use crate::settings::{self, AppSettings};
+use crate::settings_validation::SettingsError;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener};
+use tungstenite::{Message, WebSocket};
+
+/// Captions keep the end of longer transcripts, about two lines on screen
+const CAPTION_CHARS: usize = 120;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long OBS gets to answer a message
+const REPLY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Wait between attempts while OBS isn't running
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// obs-websocket opcodes
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+
+/// Bumped whenever the integration stops, which ends its thread
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Captions for the running connection thread
+static CAPTIONS: Lazy<Mutex<Option<Sender<Caption>>>> = Lazy::new(|| Mutex::new(None));
+
+enum Caption {
+    /// Shown until the next caption
+    Partial(String),
+    /// Shown for the configured time, then cleared
+    Final(String),
+}
+
+/// Settings the connection depends on
+#[derive(Clone, PartialEq)]
+struct Config {
+    host: String,
+    port: u16,
+    password: Option<String>,
+    text_source: String,
+    clear_after: Duration,
+}
+
+impl Config {
+    fn of(settings: &AppSettings) -> Option<Self> {
+        settings.obs_enabled.then(|| Self {
+            host: settings.obs_host.clone(),
+            port: settings.obs_port,
+            password: settings.obs_password.clone(),
+            text_source: settings.obs_text_source.clone(),
+            clear_after: Duration::from_secs(settings.obs_clear_after_secs as u64),
+        })
+    }
+}
+
+/// The `authentication` string obs-websocket expects for a password
+fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    BASE64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+/// The end of `text` that fits a caption, starting at a word
+fn caption_tail(text: &str, max_chars: usize) -> &str {
+    let text = text.trim();
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text;
+    }
+    let start = text
+        .char_indices()
+        .nth(count - max_chars)
+        .map_or(0, |(index, _)| index);
+    let tail = &text[start..];
+    if text[..start].ends_with(char::is_whitespace) {
+        return tail;
+    }
+    match tail.find(char::is_whitespace) {
+        Some(index) => tail[index..].trim_start(),
+        None => tail,
+    }
+}
+
+struct Connection {
+    socket: WebSocket<TcpStream>,
+    next_request_id: u64,
+}
+
+impl Connection {
+    fn send(&mut self, op: u64, data: Value) -> Result<(), String> {
+        let text = json!({ "op": op, "d": data }).to_string();
+        self.socket
+            .send(Message::Text(text.into()))
+            .map_err(|e| format!("Failed to send to OBS: {}", e))
+    }
+
+    /// The next message with opcode `op`, skipping others
+    fn receive(&mut self, op: u64) -> Result<Value, String> {
+        loop {
+            let text = match self.socket.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => return Err("OBS closed the connection".to_string()),
+                Ok(_) => continue,
+                Err(e) => return Err(format!("Lost the connection to OBS: {}", e)),
+            };
+            let message: Value = serde_json::from_str(text.as_str())
+                .map_err(|e| format!("Unexpected message from OBS: {}", e))?;
+            if message["op"].as_u64() == Some(op) {
+                return Ok(message["d"].clone());
+            }
+        }
+    }
+
+    /// Connect and identify, without subscribing to any OBS events
+    fn open(config: &Config) -> Result<Self, String> {
+        let address = (config.host.as_str(), config.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}: {}", config.host, e))?
+            .next()
+            .ok_or_else(|| format!("Failed to resolve {}", config.host))?;
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(|e| {
+            format!(
+                "Failed to connect to OBS at {}:{}: {}",
+                config.host, config.port, e
+            )
+        })?;
+        stream
+            .set_read_timeout(Some(REPLY_TIMEOUT))
+            .map_err(|e| format!("Failed to configure the OBS connection: {}", e))?;
+        let url = format!("ws://{}:{}", config.host, config.port);
+        let (socket, _) = tungstenite::client(url, stream)
+            .map_err(|e| format!("Failed to connect to obs-websocket: {}", e))?;
+
+        let mut connection = Self {
+            socket,
+            next_request_id: 0,
+        };
+        let hello = connection.receive(OP_HELLO)?;
+        let mut identify = json!({ "rpcVersion": 1, "eventSubscriptions": 0 });
+        if let Some(auth) = hello.get("authentication") {
+            let password = config
+                .password
+                .as_deref()
+                .ok_or("OBS asks for a password, set it in the settings")?;
+            identify["authentication"] = json!(auth_response(
+                password,
+                auth["salt"].as_str().unwrap_or_default(),
+                auth["challenge"].as_str().unwrap_or_default(),
+            ));
+        }
+        connection.send(OP_IDENTIFY, identify)?;
+        // OBS closes the connection on a wrong password
+        connection
+            .receive(OP_IDENTIFIED)
+            .map_err(|e| format!("OBS refused the connection, check the password ({})", e))?;
+        Ok(connection)
+    }
+
+    fn request(&mut self, request_type: &str, request_data: Value) -> Result<Value, String> {
+        self.next_request_id += 1;
+        let request_id = self.next_request_id.to_string();
+        self.send(
+            OP_REQUEST,
+            json!({
+                "requestType": request_type,
+                "requestId": request_id,
+                "requestData": request_data,
+            }),
+        )?;
+        loop {
+            let response = self.receive(OP_REQUEST_RESPONSE)?;
+            if response["requestId"].as_str() != Some(request_id.as_str()) {
+                continue;
+            }
+            let status = &response["requestStatus"];
+            if status["result"].as_bool() == Some(true) {
+                return Ok(response["responseData"].clone());
+            }
+            return Err(format!(
+                "OBS couldn't run {}: {}",
+                request_type,
+                status["comment"].as_str().unwrap_or("no reason given")
+            ));
+        }
+    }
+
+    fn set_text(&mut self, source: &str, text: &str) -> Result<(), String> {
+        self.request(
+            "SetInputSettings",
+            json!({ "inputName": source, "inputSettings": { "text": text } }),
+        )
+        .map(|_| ())
+    }
+
+    fn close(mut self) {
+        let _ = self.socket.close(None);
+        let _ = self.socket.flush();
+    }
+}
+
+/// Show captions until the integration stops, reconnecting while OBS isn't
+/// there. Captions that arrive while disconnected are dropped.
+fn run(config: Config, captions: Receiver<Caption>, generation: u64) {
+    let current = || GENERATION.load(Ordering::Relaxed) == generation;
+    while current() {
+        let mut connection = match Connection::open(&config) {
+            Ok(connection) => connection,
+            Err(e) => {
+                debug!("{}", e);
+                std::thread::sleep(RECONNECT_DELAY);
+                continue;
+            }
+        };
+        info!("Connected to OBS at {}:{}", config.host, config.port);
+
+        let mut clear_at: Option<Instant> = None;
+        let result = loop {
+            if !current() {
+                break Ok(());
+            }
+            let caption = match captions.recv_timeout(Duration::from_millis(250)) {
+                // Only the newest caption is worth showing
+                Ok(first) => captions.try_iter().last().unwrap_or(first),
+                Err(RecvTimeoutError::Timeout) => {
+                    if clear_at.is_some_and(|at| Instant::now() >= at) {
+                        clear_at = None;
+                        if let Err(e) = connection.set_text(&config.text_source, "") {
+                            break Err(e);
+                        }
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break Ok(()),
+            };
+            let text = match caption {
+                Caption::Partial(text) => {
+                    clear_at = None;
+                    text
+                }
+                Caption::Final(text) => {
+                    clear_at = (!config.clear_after.is_zero())
+                        .then(|| Instant::now() + config.clear_after);
+                    text
+                }
+            };
+            if let Err(e) = connection.set_text(&config.text_source, &text) {
+                break Err(e);
+            }
+        };
+        match result {
+            Ok(()) => {
+                let _ = connection.set_text(&config.text_source, "");
+                connection.close();
+            }
+            Err(e) => {
+                warn!("{}", e);
+                std::thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    }
+    debug!("OBS captions stopped");
+}
+
+fn show(caption: Caption) {
+    if let Some(captions) = CAPTIONS.lock().unwrap().as_ref() {
+        let _ = captions.send(caption);
+    }
+}
+
+/// The `text` field of an event payload, cut to caption length
+fn caption_text(payload: &str) -> Option<String> {
+    let event: Value = serde_json::from_str(payload).ok()?;
+    let text = caption_tail(event["text"].as_str()?, CAPTION_CHARS);
+    Some(text.to_string())
+}
+
+/// Pass transcripts on to the connection; the listeners stay for the app's
+/// lifetime and do nothing while the integration is off
+pub fn init(app: &AppHandle) {
+    app.listen_any("transcript-partial", |event| {
+        if let Some(text) = caption_text(event.payload()).filter(|text| !text.is_empty()) {
+            show(Caption::Partial(text));
+        }
+    });
+    app.listen_any("transcript-final", |event| {
+        if let Some(text) = caption_text(event.payload()) {
+            show(Caption::Final(text));
+        }
+    });
+}
+
+/// Connect or disconnect per the settings
+pub fn sync(app: &AppHandle) {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    CAPTIONS.lock().unwrap().take();
+    let config = match Config::of(&settings::get_settings(app)) {
+        Some(config) => config,
+        None => return,
+    };
+    let (sender, receiver) = mpsc::channel();
+    *CAPTIONS.lock().unwrap() = Some(sender);
+    let generation = GENERATION.load(Ordering::Relaxed);
+    std::thread::spawn(move || run(config, receiver, generation));
+}
+
+/// Connect with the current settings and show a test caption, for the
+/// settings page
+pub fn test_connection(app: &AppHandle) -> Result<(), String> {
+    let mut settings = settings::get_settings(app);
+    settings.obs_enabled = true;
+    let config = Config::of(&settings).ok_or("OBS captions are off")?;
+    let mut connection = Connection::open(&config)?;
+    let result = connection.set_text(&config.text_source, "Babbl captions are working");
+    connection.close();
+    result
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if Config::of(previous) != Config::of(next) {
+        sync(app);
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_response() {
+        // The example from the obs-websocket protocol documentation
+        assert_eq!(
+            auth_response(
+                "supersecretpassword",
+                "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=",
+                "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY="
+            ),
+            "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+        );
+    }
+
+    #[test]
+    fn test_caption_tail() {
+        assert_eq!(caption_tail("  short caption ", 20), "short caption");
+        assert_eq!(caption_tail("one two three four", 10), "three four");
+        assert_eq!(caption_tail("one two three four", 9), "four");
+        assert_eq!(caption_tail("abcdefghij", 4), "ghij");
+    }
+}
@@ -0,0 +1,110 @@
+//! The Obsidian output target: results are appended to a note in a vault,
+//! written straight to the file so Obsidian doesn't have to run. Each entry
+//! is rendered from a template with `{{text}}`, `{{transcription}}`,
+//! `{{date}}`, `{{time}}` and `{{action}}`, and the note path may contain
+//! `{date}` for one note per day.
+
+use crate::note;
+use crate::output::ActionOutput;
+use crate::settings::ActionConfig;
+use crate::template;
+use chrono::{DateTime, Local};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_NOTE_PATH: &str = "Babbl/{date}.md";
+
+pub const DEFAULT_TEMPLATE: &str = "## {{date}} {{time}}\n\n{{text}}\n";
+
+/// Entry for `output`, from `template`
+fn render(
+    entry_template: &str,
+    output: &ActionOutput,
+    action_name: &str,
+    at: DateTime<Local>,
+) -> String {
+    let mut entry = template::render(entry_template, "{{", "}}", |name| match name {
+        "date" => Some(at.format("%Y-%m-%d").to_string()),
+        "time" => Some(at.format("%H:%M").to_string()),
+        "action" => Some(action_name.to_string()),
+        "transcription" => Some(output.transcription.trim().to_string()),
+        "text" => Some(output.text.trim().to_string()),
+        _ => None,
+    });
+    if !entry.ends_with('\n') {
+        entry.push('\n');
+    }
+    entry
+}
+
+/// The note in `vault` for `note_path`; absolute paths and `~/` are kept
+fn note_in_vault(vault: &Path, note_path: &str, at: DateTime<Local>) -> PathBuf {
+    vault.join(note::resolve_note_path(note_path, at.date_naive()))
+}
+
+/// Append `output` to the action's note. Returns the path written to.
+pub fn append(
+    config: &ActionConfig,
+    output: &ActionOutput,
+    action_name: &str,
+) -> Result<PathBuf, String> {
+    let now = Local::now();
+    let vault = config
+        .obsidian_vault_path
+        .as_deref()
+        .filter(|vault| !vault.trim().is_empty())
+        .ok_or("No Obsidian vault configured")?;
+    let vault = note::resolve_note_path(vault, now.date_naive());
+    if !vault.is_dir() {
+        return Err(format!(
+            "The Obsidian vault '{}' doesn't exist",
+            vault.display()
+        ));
+    }
+    let note_path = config
+        .obsidian_note_path
+        .as_deref()
+        .filter(|path| !path.trim().is_empty())
+        .unwrap_or(DEFAULT_NOTE_PATH);
+    let path = note_in_vault(&vault, note_path, now);
+    let template = config
+        .obsidian_template
+        .as_deref()
+        .filter(|template| !template.trim().is_empty())
+        .unwrap_or(DEFAULT_TEMPLATE);
+    note::append_entry(&path, &render(template, output, action_name, now))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render() {
+        let output = ActionOutput {
+            binding_id: "mode_summarize".to_string(),
+            text: "Ship on Friday. {{date}}".to_string(),
+            transcription: "so we ship on friday {{text}}".to_string(),
+            timestamp: 0,
+            run_id: None,
+        };
+        let at = Local.with_ymd_and_hms(2026, 3, 9, 8, 5, 0).unwrap();
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &output, "Summarize", at),
+            "## 2026-03-09 08:05\n\nShip on Friday. {{date}}\n"
+        );
+        assert_eq!(
+            render("- {{action}}: {{transcription}}", &output, "Summarize", at),
+            "- Summarize: so we ship on friday {{text}}\n"
+        );
+        assert_eq!(
+            render("{{text}} ({{unknown}})", &output, "Summarize", at),
+            "Ship on Friday. {{date}} ({{unknown}})\n"
+        );
+        assert_eq!(
+            note_in_vault(Path::new("/vault"), DEFAULT_NOTE_PATH, at),
+            PathBuf::from("/vault/Babbl/2026-03-09.md")
+        );
+    }
+}
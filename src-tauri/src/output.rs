@@ -0,0 +1,269 @@
+use crate::note;
+use crate::notion;
+use crate::obsidian;
+use crate::settings::{ActionConfig, AppSettings, OutputMode, OutputTarget};
+use hmac::{Hmac, Mac};
+use log::{debug, error};
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use sha2::Sha256;
+use specta::Type;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Webhooks that don't answer in time are given up on; the other targets
+/// have already received the result
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Unix time the request was signed at; part of the signed message so
+/// receivers can reject replays
+const TIMESTAMP_HEADER: &str = "X-Babbl-Timestamp";
+/// `sha256=` and the hex HMAC of `<timestamp>.<body>` with the webhook secret
+const SIGNATURE_HEADER: &str = "X-Babbl-Signature";
+
+/// Result of an action, as shown in the popup and sent to webhooks
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct ActionOutput {
+    pub binding_id: String,
+    /// Final text, after post-processing
+    pub text: String,
+    pub transcription: String,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    /// Dictation it came from, as in the `pipeline-state` event
+    pub run_id: Option<String>,
+}
+
+/// Where a result sent to a webhook came from
+#[derive(Serialize, Debug, Clone)]
+struct WebhookMetadata {
+    app_version: String,
+    /// Name of the action as shown in the settings
+    action_name: Option<String>,
+    model: String,
+    language: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct WebhookPayload {
+    #[serde(flatten)]
+    output: ActionOutput,
+    metadata: WebhookMetadata,
+}
+
+/// Targets of an action without duplicates. Actions without their own
+/// targets follow the global output mode.
+pub fn targets_for(settings: &AppSettings, binding_id: &str) -> Vec<OutputTarget> {
+    let configured = settings
+        .action_config(binding_id)
+        .map(|config| config.output_targets)
+        .unwrap_or_default();
+    resolve_targets(&configured, settings.output_mode)
+}
+
+fn resolve_targets(configured: &[OutputTarget], output_mode: OutputMode) -> Vec<OutputTarget> {
+    if configured.is_empty() {
+        return vec![match output_mode {
+            OutputMode::Inject => OutputTarget::Inject,
+            OutputMode::AppendToNote => OutputTarget::File,
+        }];
+    }
+    let mut targets = Vec::new();
+    for target in configured {
+        if !targets.contains(target) {
+            targets.push(*target);
+        }
+    }
+    targets
+}
+
+/// Send `output` to every target except `Inject`, which has to run on the
+/// main thread and is left to the caller. With `Inject` also selected, the
+/// clipboard target is handled by the paste so the clipboard isn't restored.
+pub fn deliver(
+    app: &AppHandle,
+    settings: &AppSettings,
+    output: &ActionOutput,
+    targets: &[OutputTarget],
+) {
+    for target in targets {
+        match target {
+            OutputTarget::Inject => {}
+            OutputTarget::Clipboard => {
+                if !targets.contains(&OutputTarget::Inject) {
+                    if let Err(e) = app.clipboard().write_text(&output.text) {
+                        error!("Failed to copy result to clipboard: {}", e);
+                    }
+                }
+            }
+            OutputTarget::Popup => {
+                crate::show_main_window(app);
+                if let Err(e) = app.emit("output-popup", output.clone()) {
+                    error!("Failed to emit output popup: {}", e);
+                }
+            }
+            OutputTarget::File => {
+                let path = settings
+                    .action_config(&output.binding_id)
+                    .and_then(|config| config.output_file_path)
+                    .or_else(|| settings.note_file_path.clone())
+                    .unwrap_or_default();
+                match note::append_to_note(&path, &output.text) {
+                    Ok(path) => debug!("Appended result to {}", path.display()),
+                    Err(e) => error!("Failed to write result to file: {}", e),
+                }
+            }
+            OutputTarget::Webhook => match settings.action_config(&output.binding_id) {
+                Some(config) if config.webhook_url.is_some() => {
+                    spawn_webhook(app, settings, config, output.clone())
+                }
+                _ => error!(
+                    "Action '{}' has a webhook target but no webhook URL",
+                    output.binding_id
+                ),
+            },
+            OutputTarget::Obsidian => {
+                let config = settings
+                    .action_config(&output.binding_id)
+                    .unwrap_or_default();
+                let action_name = settings
+                    .bindings
+                    .get(&output.binding_id)
+                    .map_or(output.binding_id.as_str(), |binding| binding.name.as_str());
+                match obsidian::append(&config, output, action_name) {
+                    Ok(path) => debug!("Appended result to {}", path.display()),
+                    Err(e) => error!("Failed to write result to Obsidian: {}", e),
+                }
+            }
+            OutputTarget::Notion => notion::spawn(
+                settings,
+                &settings
+                    .action_config(&output.binding_id)
+                    .unwrap_or_default(),
+                output,
+            ),
+        }
+    }
+}
+
+/// Hex HMAC-SHA256 of `message`
+fn hmac_hex(secret: &str, message: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// POST `output` with some metadata to the action's webhook in the background
+/// so a slow endpoint doesn't hold up the paste
+fn spawn_webhook(
+    app: &AppHandle,
+    settings: &AppSettings,
+    config: ActionConfig,
+    output: ActionOutput,
+) {
+    let url = config.webhook_url.unwrap_or_default();
+    let client = match crate::http::client_builder(settings).and_then(|builder| {
+        builder
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())
+    }) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to build webhook client: {}", e);
+            return;
+        }
+    };
+
+    let payload = WebhookPayload {
+        metadata: WebhookMetadata {
+            app_version: app.package_info().version.to_string(),
+            action_name: settings
+                .bindings
+                .get(&output.binding_id)
+                .map(|binding| binding.name.clone()),
+            model: settings.selected_model.clone(),
+            language: settings.selected_language.clone(),
+        },
+        output,
+    };
+    // Serialized up front, the signature has to cover the exact bytes sent
+    let body = match serde_json::to_vec(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to serialize webhook payload: {}", e);
+            return;
+        }
+    };
+
+    let mut request = client.post(&url).header(CONTENT_TYPE, "application/json");
+    for (name, value) in &config.webhook_headers {
+        request = request.header(name, value);
+    }
+    if let Some(secret) = config.webhook_secret.as_deref().filter(|s| !s.is_empty()) {
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut message = format!("{}.", timestamp).into_bytes();
+        message.extend_from_slice(&body);
+        request = request
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                format!("sha256={}", hmac_hex(secret, &message)),
+            );
+    }
+    let request = request.body(body);
+
+    tauri::async_runtime::spawn(async move {
+        let result = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(response) => debug!("Webhook {} answered {}", url, response.status()),
+            Err(e) => error!("Failed to send result to webhook {}: {}", url, e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_targets() {
+        assert_eq!(
+            resolve_targets(&[], OutputMode::Inject),
+            vec![OutputTarget::Inject]
+        );
+        assert_eq!(
+            resolve_targets(&[], OutputMode::AppendToNote),
+            vec![OutputTarget::File]
+        );
+        assert_eq!(
+            resolve_targets(
+                &[
+                    OutputTarget::Webhook,
+                    OutputTarget::Clipboard,
+                    OutputTarget::Webhook
+                ],
+                OutputMode::Inject
+            ),
+            vec![OutputTarget::Webhook, OutputTarget::Clipboard]
+        );
+    }
+
+    #[test]
+    fn test_hmac_hex() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hmac_hex("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
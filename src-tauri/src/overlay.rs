@@ -1,6 +1,12 @@
 use crate::input;
+use crate::power;
 use crate::settings;
-use crate::settings::OverlayPosition;
+use crate::settings::{AppSettings, OverlayAnchor, OverlayPosition};
+use crate::settings_validation::SettingsError;
+use log::warn;
+use serde::Serialize;
+use specta::Type;
+use std::sync::atomic::{AtomicU32, Ordering};
 use tauri::{AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize};
 
 #[cfg(not(target_os = "macos"))]
@@ -39,6 +45,12 @@ const OVERLAY_BOTTOM_OFFSET: f64 = 15.0;
 #[cfg(any(target_os = "windows", target_os = "linux"))]
 const OVERLAY_BOTTOM_OFFSET: f64 = 40.0;
 
+/// Gap between the cursor and the overlay in the cursor position
+const OVERLAY_CURSOR_OFFSET: f64 = 16.0;
+
+/// Gap between the overlay and the side of the screen in a corner
+const OVERLAY_SIDE_OFFSET: f64 = 16.0;
+
 /// Forces a window to be topmost using Win32 API (Windows only)
 /// This is more reliable than Tauri's set_always_on_top which can be overridden
 #[cfg(target_os = "windows")]
@@ -69,12 +81,29 @@ fn force_overlay_topmost(overlay_window: &tauri::webview::WebviewWindow) {
     });
 }
 
-fn get_monitor_with_cursor(app_handle: &AppHandle) -> Option<tauri::Monitor> {
-    if let Some(mouse_location) = input::get_cursor_position(app_handle) {
+/// The cursor in the coordinates of `monitor`'s position and size: enigo
+/// reports points on macOS, which Tauri scales per monitor, and pixels
+/// elsewhere
+fn cursor_on(monitor: &tauri::Monitor, cursor: (i32, i32)) -> (f64, f64) {
+    let (x, y) = (cursor.0 as f64, cursor.1 as f64);
+    if cfg!(target_os = "macos") {
+        let scale = monitor.scale_factor();
+        (x * scale, y * scale)
+    } else {
+        (x, y)
+    }
+}
+
+/// The monitor the cursor is on, or the primary one
+pub fn monitor_with_cursor(app_handle: &AppHandle) -> Option<tauri::Monitor> {
+    if let Some(cursor) = input::get_cursor_position(app_handle) {
         if let Ok(monitors) = app_handle.available_monitors() {
             for monitor in monitors {
-                let is_within =
-                    is_mouse_within_monitor(mouse_location, monitor.position(), monitor.size());
+                let is_within = is_mouse_within_monitor(
+                    cursor_on(&monitor, cursor),
+                    monitor.position(),
+                    monitor.size(),
+                );
                 if is_within {
                     return Some(monitor);
                 }
@@ -86,7 +115,7 @@ fn get_monitor_with_cursor(app_handle: &AppHandle) -> Option<tauri::Monitor> {
 }
 
 fn is_mouse_within_monitor(
-    mouse_pos: (i32, i32),
+    mouse_pos: (f64, f64),
     monitor_pos: &PhysicalPosition<i32>,
     monitor_size: &PhysicalSize<u32>,
 ) -> bool {
@@ -100,48 +129,135 @@ fn is_mouse_within_monitor(
         height: monitor_height,
     } = *monitor_size;
 
-    mouse_x >= monitor_x
-        && mouse_x < (monitor_x + monitor_width as i32)
-        && mouse_y >= monitor_y
-        && mouse_y < (monitor_y + monitor_height as i32)
+    mouse_x >= monitor_x as f64
+        && mouse_x < monitor_x as f64 + monitor_width as f64
+        && mouse_y >= monitor_y as f64
+        && mouse_y < monitor_y as f64 + monitor_height as f64
 }
 
-fn calculate_overlay_position(app_handle: &AppHandle) -> Option<(f64, f64)> {
-    if let Some(monitor) = get_monitor_with_cursor(app_handle) {
-        let work_area = monitor.work_area();
-        let scale = monitor.scale_factor();
-        let work_area_width = work_area.size.width as f64 / scale;
-        let work_area_height = work_area.size.height as f64 / scale;
-        let work_area_x = work_area.position.x as f64 / scale;
-        let work_area_y = work_area.position.y as f64 / scale;
-
-        let settings = settings::get_settings(app_handle);
-
-        let x = work_area_x + (work_area_width - OVERLAY_WIDTH) / 2.0;
-        let y = match settings.overlay_position {
-            OverlayPosition::Top => work_area_y + OVERLAY_TOP_OFFSET,
-            OverlayPosition::Bottom | OverlayPosition::None => {
-                // don't subtract the overlay height it puts it too far up
-                work_area_y + work_area_height - OVERLAY_BOTTOM_OFFSET
-            }
-        };
+/// Below and right of the cursor, flipped to the other side where the overlay
+/// would leave the work area. All values in logical pixels; the area is
+/// `(x, y, width, height)`.
+fn position_near_cursor(cursor: (f64, f64), area: (f64, f64, f64, f64)) -> (f64, f64) {
+    let (cursor_x, cursor_y) = cursor;
+    let (area_x, area_y, area_width, area_height) = area;
 
-        return Some((x, y));
+    let mut x = cursor_x + OVERLAY_CURSOR_OFFSET;
+    if x + OVERLAY_WIDTH > area_x + area_width {
+        x = cursor_x - OVERLAY_CURSOR_OFFSET - OVERLAY_WIDTH;
+    }
+    let mut y = cursor_y + OVERLAY_CURSOR_OFFSET;
+    if y + OVERLAY_HEIGHT > area_y + area_height {
+        y = cursor_y - OVERLAY_CURSOR_OFFSET - OVERLAY_HEIGHT;
     }
-    None
+    (x.max(area_x), y.max(area_y))
+}
+
+/// Top left corner of the overlay at `anchor` of the work area, in logical
+/// pixels like the area
+fn anchored_position(anchor: OverlayAnchor, area: (f64, f64, f64, f64)) -> (f64, f64) {
+    let (area_x, area_y, area_width, area_height) = area;
+    let x = match anchor {
+        OverlayAnchor::TopLeft | OverlayAnchor::BottomLeft => area_x + OVERLAY_SIDE_OFFSET,
+        OverlayAnchor::TopCenter | OverlayAnchor::BottomCenter => {
+            area_x + (area_width - OVERLAY_WIDTH) / 2.0
+        }
+        OverlayAnchor::TopRight | OverlayAnchor::BottomRight => {
+            area_x + area_width - OVERLAY_WIDTH - OVERLAY_SIDE_OFFSET
+        }
+    };
+    let y = match anchor {
+        OverlayAnchor::TopLeft | OverlayAnchor::TopCenter | OverlayAnchor::TopRight => {
+            area_y + OVERLAY_TOP_OFFSET
+        }
+        // don't subtract the overlay height it puts it too far up
+        OverlayAnchor::BottomLeft | OverlayAnchor::BottomCenter | OverlayAnchor::BottomRight => {
+            area_y + area_height - OVERLAY_BOTTOM_OFFSET
+        }
+    };
+    (x, y)
+}
+
+/// Where the overlay goes on the monitor with the cursor. Worked out in that
+/// monitor's logical pixels and returned in physical ones, so it lands right
+/// on a monitor scaled differently than the one the overlay was on.
+fn calculate_overlay_position(app_handle: &AppHandle) -> Option<PhysicalPosition<i32>> {
+    let monitor = monitor_with_cursor(app_handle)?;
+    let work_area = monitor.work_area();
+    let scale = monitor.scale_factor();
+    let area = (
+        work_area.position.x as f64 / scale,
+        work_area.position.y as f64 / scale,
+        work_area.size.width as f64 / scale,
+        work_area.size.height as f64 / scale,
+    );
+
+    let settings = settings::get_settings(app_handle);
+    let cursor = input::get_cursor_position(app_handle);
+    let (x, y) = match (settings.overlay_position, cursor) {
+        (OverlayPosition::Cursor, Some(cursor)) => {
+            let (cursor_x, cursor_y) = cursor_on(&monitor, cursor);
+            position_near_cursor((cursor_x / scale, cursor_y / scale), area)
+        }
+        (position, _) => {
+            let anchor = monitor
+                .name()
+                .and_then(|name| settings.overlay_display_anchors.get(name))
+                .copied()
+                .unwrap_or(match position {
+                    OverlayPosition::Top => OverlayAnchor::TopCenter,
+                    _ => OverlayAnchor::BottomCenter,
+                });
+            anchored_position(anchor, area)
+        }
+    };
+    Some(PhysicalPosition::new(
+        (x * scale).round() as i32,
+        (y * scale).round() as i32,
+    ))
+}
+
+/// A monitor, for picking the overlay's corner per display
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct DisplayInfo {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    /// Where the overlay goes on it, `None` to follow `overlay_position`
+    pub anchor: Option<OverlayAnchor>,
+}
+
+pub fn displays(app_handle: &AppHandle) -> Result<Vec<DisplayInfo>, String> {
+    let settings = settings::get_settings(app_handle);
+    let monitors = app_handle
+        .available_monitors()
+        .map_err(|e| format!("Failed to list displays: {}", e))?;
+    Ok(monitors
+        .iter()
+        .filter_map(|monitor| {
+            let name = monitor.name()?.clone();
+            Some(DisplayInfo {
+                width: monitor.size().width,
+                height: monitor.size().height,
+                scale_factor: monitor.scale_factor(),
+                anchor: settings.overlay_display_anchors.get(&name).copied(),
+                name,
+            })
+        })
+        .collect())
 }
 
 /// Creates the recording overlay window and keeps it hidden by default
 #[cfg(not(target_os = "macos"))]
 pub fn create_recording_overlay(app_handle: &AppHandle) {
-    if let Some((x, y)) = calculate_overlay_position(app_handle) {
+    if let Some(position) = calculate_overlay_position(app_handle) {
         match WebviewWindowBuilder::new(
             app_handle,
             "recording_overlay",
             tauri::WebviewUrl::App("src/overlay/index.html".into()),
         )
         .title("Recording")
-        .position(x, y)
         .resizable(false)
         .inner_size(OVERLAY_WIDTH, OVERLAY_HEIGHT)
         .shadow(false)
@@ -157,8 +273,10 @@ pub fn create_recording_overlay(app_handle: &AppHandle) {
         .visible(false)
         .build()
         {
-            Ok(_window) => {
+            Ok(window) => {
                 debug!("Recording overlay window created successfully (hidden)");
+                let _ = window.set_position(tauri::Position::Physical(position));
+                apply_click_through(app_handle);
             }
             Err(e) => {
                 debug!("Failed to create recording overlay window: {}", e);
@@ -170,13 +288,13 @@ pub fn create_recording_overlay(app_handle: &AppHandle) {
 /// Creates the recording overlay panel and keeps it hidden by default (macOS)
 #[cfg(target_os = "macos")]
 pub fn create_recording_overlay(app_handle: &AppHandle) {
-    if let Some((x, y)) = calculate_overlay_position(app_handle) {
+    if let Some(position) = calculate_overlay_position(app_handle) {
         // PanelBuilder creates a Tauri window then converts it to NSPanel.
         // The window remains registered, so get_webview_window() still works.
         match PanelBuilder::<_, RecordingOverlayPanel>::new(app_handle, "recording_overlay")
             .url(WebviewUrl::App("src/overlay/index.html".into()))
             .title("Recording")
-            .position(tauri::Position::Logical(tauri::LogicalPosition { x, y }))
+            .position(tauri::Position::Physical(position))
             .level(PanelLevel::Status)
             .size(tauri::Size::Logical(tauri::LogicalSize {
                 width: OVERLAY_WIDTH,
@@ -196,6 +314,7 @@ pub fn create_recording_overlay(app_handle: &AppHandle) {
         {
             Ok(panel) => {
                 let _ = panel.hide();
+                apply_click_through(app_handle);
             }
             Err(e) => {
                 log::error!("Failed to create recording overlay panel: {}", e);
@@ -204,6 +323,17 @@ pub fn create_recording_overlay(app_handle: &AppHandle) {
     }
 }
 
+/// Let clicks through the overlay, or catch them so its cancel button works,
+/// per the settings
+fn apply_click_through(app_handle: &AppHandle) {
+    if let Some(overlay_window) = app_handle.get_webview_window("recording_overlay") {
+        let click_through = settings::get_settings(app_handle).overlay_click_through;
+        if let Err(e) = overlay_window.set_ignore_cursor_events(click_through) {
+            warn!("Failed to set overlay click-through: {}", e);
+        }
+    }
+}
+
 /// Shows the recording overlay window with fade-in animation
 pub fn show_recording_overlay(app_handle: &AppHandle) {
     // Check if overlay should be shown based on position setting
@@ -214,9 +344,8 @@ pub fn show_recording_overlay(app_handle: &AppHandle) {
 
     if let Some(overlay_window) = app_handle.get_webview_window("recording_overlay") {
         // Update position before showing to prevent flicker from position changes
-        if let Some((x, y)) = calculate_overlay_position(app_handle) {
-            let _ = overlay_window
-                .set_position(tauri::Position::Logical(tauri::LogicalPosition { x, y }));
+        if let Some(position) = calculate_overlay_position(app_handle) {
+            let _ = overlay_window.set_position(tauri::Position::Physical(position));
         }
 
         let _ = overlay_window.show();
@@ -232,13 +361,27 @@ pub fn show_recording_overlay(app_handle: &AppHandle) {
 
 /// Shows the transcribing overlay window
 pub fn show_transcribing_overlay(app_handle: &AppHandle) {
+    show_overlay_state(app_handle, "transcribing");
+}
+
+/// Shows the overlay while the result is typed or pasted
+pub fn show_injecting_overlay(app_handle: &AppHandle) {
+    show_overlay_state(app_handle, "injecting");
+}
+
+/// Switch the visible overlay to a processing state
+fn show_overlay_state(app_handle: &AppHandle, state: &str) {
     // Check if overlay should be shown based on position setting
     let settings = settings::get_settings(app_handle);
     if settings.overlay_position == OverlayPosition::None {
         return;
     }
 
-    update_overlay_position(app_handle);
+    // The cursor position stays where recording started, so the overlay
+    // doesn't chase the mouse while the text is on its way
+    if settings.overlay_position != OverlayPosition::Cursor {
+        update_overlay_position(app_handle);
+    }
 
     if let Some(overlay_window) = app_handle.get_webview_window("recording_overlay") {
         let _ = overlay_window.show();
@@ -247,21 +390,31 @@ pub fn show_transcribing_overlay(app_handle: &AppHandle) {
         #[cfg(target_os = "windows")]
         force_overlay_topmost(&overlay_window);
 
-        // Emit event to switch to transcribing state
-        let _ = overlay_window.emit("show-overlay", "transcribing");
+        // Emit event to switch to the new state
+        let _ = overlay_window.emit("show-overlay", state);
     }
 }
 
 /// Updates the overlay window position based on current settings
 pub fn update_overlay_position(app_handle: &AppHandle) {
     if let Some(overlay_window) = app_handle.get_webview_window("recording_overlay") {
-        if let Some((x, y)) = calculate_overlay_position(app_handle) {
-            let _ = overlay_window
-                .set_position(tauri::Position::Logical(tauri::LogicalPosition { x, y }));
+        if let Some(position) = calculate_overlay_position(app_handle) {
+            let _ = overlay_window.set_position(tauri::Position::Physical(position));
         }
     }
 }
 
+/// Move the overlay and update its click-through when the settings change
+pub fn on_settings_changed(
+    app_handle: &AppHandle,
+    _previous: &AppSettings,
+    _next: &AppSettings,
+) -> Vec<SettingsError> {
+    update_overlay_position(app_handle);
+    apply_click_through(app_handle);
+    Vec::new()
+}
+
 /// Hides the recording overlay window with fade-out animation
 pub fn hide_recording_overlay(app_handle: &AppHandle) {
     // Always hide the overlay regardless of settings - if setting was changed while recording,
@@ -278,7 +431,31 @@ pub fn hide_recording_overlay(app_handle: &AppHandle) {
     }
 }
 
+/// Live waveform peaks for the overlay, on their own event so listeners that
+/// only want the levels aren't woken 30 times a second
+/// In low-power mode only every this many level and waveform frames are
+/// sent, so the webviews redraw less often
+const LOW_POWER_FRAME_STEP: u32 = 3;
+
+static WAVEFORM_FRAMES: AtomicU32 = AtomicU32::new(0);
+
+static LEVEL_FRAMES: AtomicU32 = AtomicU32::new(0);
+
+fn skip_frame(frames: &AtomicU32) -> bool {
+    power::is_low_power() && frames.fetch_add(1, Ordering::Relaxed) % LOW_POWER_FRAME_STEP != 0
+}
+
+pub fn emit_waveform(app_handle: &AppHandle, peaks: &[f32]) {
+    if skip_frame(&WAVEFORM_FRAMES) {
+        return;
+    }
+    let _ = app_handle.emit("audio-waveform", peaks);
+}
+
 pub fn emit_levels(app_handle: &AppHandle, levels: &Vec<f32>) {
+    if skip_frame(&LEVEL_FRAMES) {
+        return;
+    }
     // emit levels to main app
     let _ = app_handle.emit("mic-level", levels);
 
@@ -287,3 +464,40 @@ pub fn emit_levels(app_handle: &AppHandle, levels: &Vec<f32>) {
         let _ = overlay_window.emit("mic-level", levels);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_near_cursor() {
+        let area = (0.0, 0.0, 1000.0, 800.0);
+        assert_eq!(position_near_cursor((100.0, 100.0), area), (116.0, 116.0));
+        // Flipped left and up in the bottom right corner
+        assert_eq!(
+            position_near_cursor((990.0, 790.0), area),
+            (990.0 - 16.0 - OVERLAY_WIDTH, 790.0 - 16.0 - OVERLAY_HEIGHT)
+        );
+    }
+
+    #[test]
+    fn test_anchored_position() {
+        // A second display right of the first
+        let area = (1920.0, 0.0, 1280.0, 720.0);
+        assert_eq!(
+            anchored_position(OverlayAnchor::TopLeft, area),
+            (1920.0 + OVERLAY_SIDE_OFFSET, OVERLAY_TOP_OFFSET)
+        );
+        assert_eq!(
+            anchored_position(OverlayAnchor::BottomCenter, area),
+            (
+                1920.0 + (1280.0 - OVERLAY_WIDTH) / 2.0,
+                720.0 - OVERLAY_BOTTOM_OFFSET
+            )
+        );
+        assert_eq!(
+            anchored_position(OverlayAnchor::BottomRight, area).0,
+            3200.0 - OVERLAY_WIDTH - OVERLAY_SIDE_OFFSET
+        );
+    }
+}
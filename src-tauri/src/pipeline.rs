@@ -0,0 +1,256 @@
+//! Where the dictation pipeline is. Every step goes through [`transition`],
+//! which updates the tray and overlay and emits one `pipeline-state` event
+//! for the frontend. Each dictation gets a run id when it starts, which its
+//! events, log lines, outputs and history entry carry.
+
+use crate::app_overrides;
+use crate::overlay;
+use crate::scripting::{self, HookContext};
+use crate::settings;
+use crate::tray::{self, TrayIconState};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineState {
+    Idle,
+    Recording,
+    Transcribing,
+    PostProcessing,
+    /// Typing or pasting the result into the focused app
+    Injecting,
+    Done,
+    Error,
+}
+
+impl PipelineState {
+    /// Whether a dictation is under way
+    pub fn is_busy(self) -> bool {
+        !matches!(self, Self::Idle | Self::Done | Self::Error)
+    }
+
+    fn can_move_to(self, next: PipelineState) -> bool {
+        use PipelineState::*;
+
+        match (self, next) {
+            // Cancelling
+            (_, Idle) => true,
+            (Idle | Done | Error, Recording) => true,
+            (Recording, Transcribing) => true,
+            (Transcribing, PostProcessing | Injecting) => true,
+            (PostProcessing, Injecting) => true,
+            (Recording | Transcribing | PostProcessing | Injecting, Done | Error) => true,
+            _ => false,
+        }
+    }
+
+    fn tray_icon(self) -> TrayIconState {
+        match self {
+            Self::Recording => TrayIconState::Recording,
+            Self::Transcribing | Self::PostProcessing | Self::Injecting => {
+                TrayIconState::Transcribing
+            }
+            Self::Idle | Self::Done | Self::Error => TrayIconState::Idle,
+        }
+    }
+}
+
+/// Payload of the `pipeline-state` event
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct PipelineEvent {
+    pub state: PipelineState,
+    pub previous: PipelineState,
+    /// Action of the dictation, None when idle
+    pub binding_id: Option<String>,
+    /// Id of the dictation, None when idle
+    pub run_id: Option<String>,
+    /// Set with the error state
+    pub error: Option<String>,
+}
+
+/// Payload of the `transcript-partial` event
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct PartialTranscript {
+    pub state: PipelineState,
+    pub run_id: Option<String>,
+    /// Everything produced so far, not just what's new
+    pub text: String,
+}
+
+struct Current {
+    state: PipelineState,
+    binding_id: Option<String>,
+}
+
+static CURRENT: Lazy<Mutex<Current>> = Lazy::new(|| {
+    Mutex::new(Current {
+        state: PipelineState::Idle,
+        binding_id: None,
+    })
+});
+
+/// Apart from `CURRENT` since every log line reads it, also those logged
+/// while `CURRENT` is locked
+static RUN_ID: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn current() -> PipelineState {
+    CURRENT.lock().unwrap().state
+}
+
+/// Id of the latest dictation; it stays after the dictation ends so late
+/// work like saving the history can still be tied to it
+pub fn run_id() -> Option<String> {
+    RUN_ID.lock().unwrap().clone()
+}
+
+pub fn is_busy() -> bool {
+    current().is_busy()
+}
+
+/// Tray icon for the current state, e.g. to redraw it in a new theme
+pub fn tray_icon_state() -> TrayIconState {
+    current().tray_icon()
+}
+
+fn apply(app: &AppHandle, state: PipelineState) {
+    match state {
+        PipelineState::Recording => overlay::show_recording_overlay(app),
+        PipelineState::Transcribing | PipelineState::PostProcessing => {
+            overlay::show_transcribing_overlay(app)
+        }
+        PipelineState::Injecting => overlay::show_injecting_overlay(app),
+        PipelineState::Idle | PipelineState::Done | PipelineState::Error => {
+            overlay::hide_recording_overlay(app)
+        }
+    }
+    tray::change_tray_icon(app, state.tray_icon());
+}
+
+/// Move to `next` when that's possible from `expected` states only, or from
+/// any state the machine allows with `expected` None. Transitions that don't
+/// fit are dropped, so a step finishing after a cancel doesn't bring the
+/// overlay back. Returns whether the state changed.
+fn move_to(
+    app: &AppHandle,
+    expected: Option<PipelineState>,
+    next: PipelineState,
+    binding_id: Option<&str>,
+    error: Option<&str>,
+) -> bool {
+    let event = {
+        let mut current = CURRENT.lock().unwrap();
+        let previous = current.state;
+        if expected.is_some_and(|expected| expected != previous) || !previous.can_move_to(next) {
+            debug!("Ignoring pipeline transition {:?} -> {:?}", previous, next);
+            return false;
+        }
+
+        current.state = next;
+        if let Some(binding_id) = binding_id {
+            current.binding_id = Some(binding_id.to_string());
+            *RUN_ID.lock().unwrap() = Some(uuid::Uuid::new_v4().to_string());
+        } else if next == PipelineState::Idle {
+            current.binding_id = None;
+            *RUN_ID.lock().unwrap() = None;
+        }
+        PipelineEvent {
+            state: next,
+            previous,
+            binding_id: current.binding_id.clone(),
+            run_id: run_id(),
+            error: error.map(str::to_string),
+        }
+    };
+
+    debug!("Pipeline {:?} -> {:?}", event.previous, event.state);
+    apply(app, next);
+    if let Err(e) = app.emit("pipeline-state", event) {
+        warn!("Failed to emit pipeline state: {}", e);
+    }
+    true
+}
+
+/// Start a dictation for action `binding_id`
+pub fn start(app: &AppHandle, binding_id: &str) {
+    move_to(app, None, PipelineState::Recording, Some(binding_id), None);
+}
+
+pub fn transition(app: &AppHandle, next: PipelineState) {
+    move_to(app, None, next, None, None);
+}
+
+/// Move to `next` only while in `from`; for steps shared with work outside a
+/// dictation, like reprocessing a history entry
+pub fn advance(app: &AppHandle, from: PipelineState, next: PipelineState) {
+    move_to(app, Some(from), next, None, None);
+}
+
+pub fn finish(app: &AppHandle) {
+    transition(app, PipelineState::Done);
+}
+
+pub fn fail(app: &AppHandle, error: &str) {
+    if !move_to(app, None, PipelineState::Error, None, Some(error)) {
+        return;
+    }
+    let binding_id = CURRENT.lock().unwrap().binding_id.clone();
+    let focused_app = app_overrides::focused_process_name()
+        .map(|name| app_overrides::normalize_process_name(&name));
+    let context = HookContext {
+        binding_id: binding_id.as_deref().unwrap_or_default(),
+        app: focused_app.as_deref(),
+    };
+    scripting::on_error(&settings::get_settings(app), &context, error);
+}
+
+/// Back to idle from wherever, e.g. after a cancel
+pub fn reset(app: &AppHandle) {
+    transition(app, PipelineState::Idle);
+}
+
+/// Share text that's still being produced, like the chunks of a long
+/// transcription or a streamed LLM response
+pub fn partial(app: &AppHandle, text: &str) {
+    let event = PartialTranscript {
+        state: current(),
+        run_id: run_id(),
+        text: text.to_string(),
+    };
+    if let Err(e) = app.emit("transcript-partial", event) {
+        warn!("Failed to emit partial transcript: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PipelineState::*;
+
+    #[test]
+    fn test_can_move_to() {
+        let happy_path = [
+            Idle,
+            Recording,
+            Transcribing,
+            PostProcessing,
+            Injecting,
+            Done,
+        ];
+        for pair in happy_path.windows(2) {
+            assert!(pair[0].can_move_to(pair[1]), "{:?}", pair);
+        }
+        assert!(Done.can_move_to(Recording));
+        assert!(Transcribing.can_move_to(Error));
+        assert!(Injecting.can_move_to(Idle));
+
+        // A step finishing after a cancel
+        assert!(!Idle.can_move_to(Injecting));
+        assert!(!Idle.can_move_to(Done));
+        assert!(!Recording.can_move_to(PostProcessing));
+    }
+}
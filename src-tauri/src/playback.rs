@@ -0,0 +1,81 @@
+use crate::audio_feedback;
+use crate::settings;
+use log::{debug, error};
+use once_cell::sync::Lazy;
+use rodio::{Decoder, Sink, Source};
+use serde::Serialize;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// History entry being played and its sink, so a new playback or stop can
+/// end it
+static CURRENT: Lazy<Mutex<Option<(i64, Arc<Sink>)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Payload of the `history-playback` event
+#[derive(Clone, Serialize)]
+struct PlaybackEvent {
+    id: i64,
+    playing: bool,
+}
+
+fn emit_playback(app: &AppHandle, id: i64, playing: bool) {
+    if let Err(e) = app.emit("history-playback", PlaybackEvent { id, playing }) {
+        error!("Failed to emit history playback event: {}", e);
+    }
+}
+
+/// Play the WAV data of history entry `id` from `start`, on the output device
+/// picked in the settings. Stops whatever was playing before.
+pub fn play(app: &AppHandle, id: i64, wav: Vec<u8>, start: Duration) {
+    stop(app);
+
+    let app = app.clone();
+    thread::spawn(move || {
+        // The stream has to live on this thread until playback ends
+        let selected_device = settings::get_settings(&app).selected_output_device;
+        let stream = match audio_feedback::open_output_stream(selected_device) {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("Failed to open audio output for playback: {}", e);
+                return;
+            }
+        };
+        let source = match Decoder::new(Cursor::new(wav)) {
+            Ok(source) => source,
+            Err(e) => {
+                error!("Failed to decode recording of entry {}: {}", id, e);
+                return;
+            }
+        };
+
+        let sink = Arc::new(Sink::connect_new(stream.mixer()));
+        sink.append(source.skip_duration(start));
+        *CURRENT.lock().unwrap() = Some((id, sink.clone()));
+        debug!("Playing recording of entry {} from {:?}", id, start);
+        emit_playback(&app, id, true);
+
+        sink.sleep_until_end();
+
+        let mut current = CURRENT.lock().unwrap();
+        if current
+            .as_ref()
+            .is_some_and(|(_, playing)| Arc::ptr_eq(playing, &sink))
+        {
+            *current = None;
+            drop(current);
+            emit_playback(&app, id, false);
+        }
+    });
+}
+
+/// Stop the recording that's playing, if any
+pub fn stop(app: &AppHandle) {
+    let current = CURRENT.lock().unwrap().take();
+    if let Some((id, sink)) = current {
+        sink.stop();
+        emit_playback(app, id, false);
+    }
+}
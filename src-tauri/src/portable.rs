@@ -0,0 +1,104 @@
+use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Marker file next to the executable that turns on portable mode
+const PORTABLE_FLAG_FILE: &str = "portable.flag";
+/// Command line flag that turns on portable mode
+const PORTABLE_ARG: &str = "--portable";
+/// Directory next to the executable that holds all data in portable mode
+const DATA_DIR_NAME: &str = "data";
+
+/// Data directory in portable mode, decided once at startup
+static PORTABLE_DATA_DIR: Lazy<Option<PathBuf>> = Lazy::new(detect);
+
+/// Directory the app is shipped in: the executable's directory, the
+/// directory containing the `.app` bundle on macOS
+fn install_dir(exe: &Path) -> Option<PathBuf> {
+    let bundle = exe.ancestors().find(|dir| {
+        dir.extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("app"))
+    });
+    bundle.unwrap_or(exe).parent().map(Path::to_path_buf)
+}
+
+fn detect() -> Option<PathBuf> {
+    // An AppImage runs from a temporary mount; the flag sits next to the image
+    let exe = match std::env::var_os("APPIMAGE") {
+        Some(appimage) if cfg!(target_os = "linux") => PathBuf::from(appimage),
+        _ => std::env::current_exe().ok()?,
+    };
+    let dir = install_dir(&exe)?;
+    let requested = std::env::args().any(|arg| arg == PORTABLE_ARG);
+    if !requested && !dir.join(PORTABLE_FLAG_FILE).is_file() {
+        return None;
+    }
+    Some(dir.join(DATA_DIR_NAME))
+}
+
+/// Data directory used instead of the OS app data directory, when running
+/// portable
+pub fn data_dir() -> Option<&'static Path> {
+    PORTABLE_DATA_DIR.as_deref()
+}
+
+/// Arguments to start the app with from login, so a portable or headless
+/// copy started with the command line flag stays that way
+pub fn launch_args() -> Vec<&'static str> {
+    let mut args = Vec::new();
+    if data_dir().is_some() {
+        args.push(PORTABLE_ARG);
+    }
+    if crate::headless::is_headless() {
+        args.push(crate::headless::HEADLESS_ARG);
+    }
+    args
+}
+
+/// Where settings, history, recordings and models are kept
+pub fn app_data_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
+    match data_dir() {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => app.path().app_data_dir(),
+    }
+}
+
+/// Log directory in portable mode
+pub fn log_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("logs"))
+}
+
+/// Where log files are written
+pub fn app_log_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
+    match log_dir() {
+        Some(dir) => Ok(dir),
+        None => app.path().app_log_dir(),
+    }
+}
+
+/// Path to open a store file at. Relative paths are resolved against the OS
+/// app data directory by the store plugin, so portable stores get an
+/// absolute one.
+pub fn store_path(file_name: &str) -> PathBuf {
+    match data_dir() {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_dir() {
+        assert_eq!(
+            install_dir(Path::new("/media/usb/babbl/babbl")),
+            Some(PathBuf::from("/media/usb/babbl"))
+        );
+        assert_eq!(
+            install_dir(Path::new("/Volumes/USB/Babbl.app/Contents/MacOS/babbl")),
+            Some(PathBuf::from("/Volumes/USB"))
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Low-power mode. On battery or in the system's power saver (or always or
+//! never, per the settings) Babbl lightens up: the low-power model is
+//! transcribed with instead of the selected one, an always-on microphone is
+//! only opened while recording, the overlay and widget redraw less often and
+//! background polling slows down.
+
+use crate::managers::audio::{self, AudioRecordingManager};
+use crate::managers::transcription;
+use crate::pipeline;
+use crate::settings::{self, AppSettings, LowPowerMode};
+use crate::settings_validation::SettingsError;
+use log::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the power source is looked at
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How much longer background polling waits in low-power mode
+const POLLING_FACTOR: u32 = 4;
+
+static LOW_POWER: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PowerState {
+    pub on_battery: bool,
+    /// The system's power saver or low power mode is on
+    pub power_saver: bool,
+}
+
+#[cfg(target_os = "linux")]
+fn detect() -> PowerState {
+    let read = |path: &std::path::Path| {
+        std::fs::read_to_string(path)
+            .map(|text| text.trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut on_mains = false;
+    let mut has_battery = false;
+    if let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") {
+        for supply in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            match read(&supply.join("type")).as_str() {
+                "Mains" | "USB" => on_mains |= read(&supply.join("online")) == "1",
+                // Mice and headsets report their batteries as devices
+                "Battery" => has_battery |= read(&supply.join("scope")) != "Device",
+                _ => {}
+            }
+        }
+    }
+    PowerState {
+        on_battery: has_battery && !on_mains,
+        power_saver: read(std::path::Path::new("/sys/firmware/acpi/platform_profile"))
+            == "low-power",
+    }
+}
+
+/// Power state from the output of `pmset -g batt` and `pmset -g`
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_pmset(battery: &str, settings: &str) -> PowerState {
+    PowerState {
+        on_battery: battery.contains("'Battery Power'"),
+        power_saver: settings.lines().any(|line| {
+            let mut words = line.split_whitespace();
+            words.next() == Some("lowpowermode") && words.next() == Some("1")
+        }),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> PowerState {
+    let pmset = |args: &[&str]| {
+        std::process::Command::new("pmset")
+            .args(args)
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+            .unwrap_or_default()
+    };
+    parse_pmset(&pmset(&["-g", "batt"]), &pmset(&["-g"]))
+}
+
+#[cfg(windows)]
+fn detect() -> PowerState {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    if let Err(e) = unsafe { GetSystemPowerStatus(&mut status) } {
+        debug!("Failed to get the power status: {}", e);
+        return PowerState::default();
+    }
+    PowerState {
+        // 255 when unknown, like on desktops
+        on_battery: status.ACLineStatus == 0,
+        power_saver: status.SystemStatusFlag == 1,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn detect() -> PowerState {
+    PowerState::default()
+}
+
+fn wants_low_power(mode: LowPowerMode, state: PowerState) -> bool {
+    match mode {
+        LowPowerMode::Auto => state.on_battery || state.power_saver,
+        LowPowerMode::Always => true,
+        LowPowerMode::Never => false,
+    }
+}
+
+pub fn is_low_power() -> bool {
+    LOW_POWER.load(Ordering::Relaxed)
+}
+
+/// `interval` for background polling, longer in low-power mode
+pub fn scaled(interval: Duration) -> Duration {
+    if is_low_power() {
+        interval * POLLING_FACTOR
+    } else {
+        interval
+    }
+}
+
+/// Local model to transcribe with
+pub fn model_for(settings: &AppSettings) -> &str {
+    match &settings.low_power_model {
+        Some(model) if is_low_power() => model,
+        _ => &settings.selected_model,
+    }
+}
+
+/// Enter or leave low-power mode per the power source and `settings`
+fn update(app: &AppHandle, settings: &AppSettings) {
+    let state = detect();
+    let low_power = wants_low_power(settings.low_power_mode, state);
+    if LOW_POWER.swap(low_power, Ordering::Relaxed) != low_power {
+        info!(
+            "{} low-power mode ({:?})",
+            if low_power { "Entering" } else { "Leaving" },
+            state
+        );
+        let rm = app.state::<Arc<AudioRecordingManager>>();
+        if let Err(e) = rm.update_mode(audio::microphone_mode(settings)) {
+            warn!("Failed to switch the microphone mode: {}", e);
+        }
+        crate::wake_word::sync(app);
+        if let Err(e) = app.emit("low-power-changed", low_power) {
+            warn!("Failed to emit low-power change: {}", e);
+        }
+    }
+    // Not under a dictation using the loaded model; retried at the next check
+    if !pipeline::is_busy() {
+        transcription::switch_model(app, settings);
+    }
+}
+
+/// Look at the power source now, before the managers pick their behavior,
+/// and then periodically
+pub fn init(app: &AppHandle) {
+    let state = detect();
+    let low_power = wants_low_power(settings::get_settings(app).low_power_mode, state);
+    LOW_POWER.store(low_power, Ordering::Relaxed);
+    debug!("Power: {:?}, low-power mode: {}", state, low_power);
+
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        update(&app, &settings::get_settings(&app));
+    });
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.low_power_mode != next.low_power_mode
+        || previous.low_power_model != next.low_power_model
+    {
+        update(app, next);
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pmset() {
+        let battery =
+            "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1)\t81%; discharging";
+        let settings = "System-wide power settings:\nCurrently in use:\n lowpowermode         1\n sleep                1";
+        assert_eq!(
+            parse_pmset(battery, settings),
+            PowerState {
+                on_battery: true,
+                power_saver: true
+            }
+        );
+        assert_eq!(
+            parse_pmset("Now drawing from 'AC Power'", " lowpowermode 0"),
+            PowerState::default()
+        );
+    }
+
+    #[test]
+    fn test_wants_low_power() {
+        let on_battery = PowerState {
+            on_battery: true,
+            power_saver: false,
+        };
+        assert!(wants_low_power(LowPowerMode::Auto, on_battery));
+        assert!(!wants_low_power(LowPowerMode::Auto, PowerState::default()));
+        assert!(!wants_low_power(LowPowerMode::Never, on_battery));
+        assert!(wants_low_power(LowPowerMode::Always, PowerState::default()));
+    }
+}
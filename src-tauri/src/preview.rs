@@ -0,0 +1,178 @@
+use crate::settings::PasteMethod;
+use crate::utils;
+use log::{debug, error};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, WebviewWindowBuilder};
+
+const PREVIEW_WINDOW_LABEL: &str = "preview";
+const PREVIEW_WIDTH: f64 = 460.0;
+const PREVIEW_HEIGHT: f64 = 260.0;
+
+/// Time for focus to return to the previous app after the preview closes,
+/// so the paste doesn't land in the preview itself
+const FOCUS_RETURN_DELAY: Duration = Duration::from_millis(150);
+
+static NEXT_PREVIEW_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Text waiting for the user to accept, edit or discard it
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct PendingPreview {
+    pub id: u64,
+    pub binding_id: String,
+    pub text: String,
+    #[serde(skip)]
+    keep_on_clipboard: bool,
+    #[serde(skip)]
+    paste_method: PasteMethod,
+}
+
+/// Only the latest text is pending; a new dictation replaces an unanswered one
+static PENDING: Lazy<Mutex<Option<PendingPreview>>> = Lazy::new(|| Mutex::new(None));
+
+fn preview_window(app: &AppHandle) -> Result<tauri::WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(PREVIEW_WINDOW_LABEL) {
+        return Ok(window);
+    }
+    WebviewWindowBuilder::new(
+        app,
+        PREVIEW_WINDOW_LABEL,
+        tauri::WebviewUrl::App("src/preview/index.html".into()),
+    )
+    .title("Preview")
+    .inner_size(PREVIEW_WIDTH, PREVIEW_HEIGHT)
+    .center()
+    .resizable(true)
+    .maximizable(false)
+    .minimizable(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .visible(false)
+    .build()
+    .map_err(|e| format!("Failed to create preview window: {}", e))
+}
+
+/// Center the window on the display with the cursor, keeping its logical
+/// size in that display's scale
+fn place_on_cursor_display(app: &AppHandle, window: &tauri::WebviewWindow) {
+    let monitor = match crate::overlay::monitor_with_cursor(app) {
+        Some(monitor) => monitor,
+        None => return,
+    };
+    let (width, height) = match (window.inner_size(), window.scale_factor()) {
+        (Ok(size), Ok(current_scale)) => (
+            size.width as f64 / current_scale,
+            size.height as f64 / current_scale,
+        ),
+        _ => (PREVIEW_WIDTH, PREVIEW_HEIGHT),
+    };
+    let scale = monitor.scale_factor();
+    let size = PhysicalSize::new(
+        (width * scale).round() as u32,
+        (height * scale).round() as u32,
+    );
+    let area = monitor.work_area();
+    let position = PhysicalPosition::new(
+        area.position.x + (area.size.width as i32 - size.width as i32).max(0) / 2,
+        area.position.y + (area.size.height as i32 - size.height as i32).max(0) / 2,
+    );
+    // Moved first, so the size is taken in the new display's scale
+    if let Err(e) = window
+        .set_position(tauri::Position::Physical(position))
+        .and_then(|_| window.set_size(tauri::Size::Physical(size)))
+    {
+        debug!("Failed to place the preview window: {}", e);
+    }
+}
+
+/// Show `text` in the preview window instead of injecting it. It's pasted
+/// once the user accepts it, with their edits.
+pub fn request(
+    app: &AppHandle,
+    binding_id: &str,
+    text: String,
+    keep_on_clipboard: bool,
+    paste_method: PasteMethod,
+) -> Result<(), String> {
+    let preview = PendingPreview {
+        id: NEXT_PREVIEW_ID.fetch_add(1, Ordering::Relaxed),
+        binding_id: binding_id.to_string(),
+        text,
+        keep_on_clipboard,
+        paste_method,
+    };
+    if let Some(replaced) = PENDING.lock().unwrap().replace(preview.clone()) {
+        debug!("Preview {} replaced by {}", replaced.id, preview.id);
+    }
+
+    let window = preview_window(app)?;
+    place_on_cursor_display(app, &window);
+    window
+        .show()
+        .and_then(|_| window.set_focus())
+        .map_err(|e| format!("Failed to show preview window: {}", e))?;
+    window
+        .emit("show-preview", preview)
+        .map_err(|e| format!("Failed to emit preview: {}", e))
+}
+
+pub fn pending() -> Option<PendingPreview> {
+    PENDING.lock().unwrap().clone()
+}
+
+fn take(id: u64) -> Result<PendingPreview, String> {
+    let mut pending = PENDING.lock().unwrap();
+    match pending.as_ref() {
+        Some(preview) if preview.id == id => Ok(pending.take().unwrap()),
+        _ => Err(format!("Preview {} is no longer pending", id)),
+    }
+}
+
+fn hide_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(PREVIEW_WINDOW_LABEL) {
+        if let Err(e) = window.hide() {
+            error!("Failed to hide preview window: {}", e);
+        }
+    }
+}
+
+/// Paste `text`, the previewed text as the user left it
+pub fn accept(app: &AppHandle, id: u64, text: String) -> Result<(), String> {
+    let preview = take(id)?;
+    hide_window(app);
+
+    let app_clone = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(FOCUS_RETURN_DELAY);
+        let handle = app_clone.clone();
+        let result = app_clone.run_on_main_thread(move || {
+            let result = utils::paste_text(
+                text,
+                handle,
+                preview.keep_on_clipboard,
+                preview.paste_method,
+            );
+            if let Err(e) = result {
+                error!("Failed to paste previewed text: {}", e);
+            }
+        });
+        if let Err(e) = result {
+            error!("Failed to run paste on main thread: {:?}", e);
+        }
+    });
+    Ok(())
+}
+
+pub fn discard(app: &AppHandle, id: u64) -> Result<(), String> {
+    let preview = take(id)?;
+    debug!(
+        "Discarded preview {} of '{}'",
+        preview.id, preview.binding_id
+    );
+    hide_window(app);
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use crate::settings::{ModelPrice, APPLE_INTELLIGENCE_PROVIDER_ID, LLAMA_CPP_PROVIDER_ID};
+use std::collections::HashMap;
+
+/// Providers that run on the user's machine and cost nothing per token
+const LOCAL_PROVIDERS: &[&str] = &[
+    "ollama",
+    APPLE_INTELLIGENCE_PROVIDER_ID,
+    LLAMA_CPP_PROVIDER_ID,
+];
+
+/// List prices in USD per million tokens (input, output), matched against the
+/// lowercased model name by prefix. More specific entries come first.
+/// Estimates only; providers change prices and apply discounts we can't see.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+    ("o4-mini", 1.10, 4.40),
+    ("o3-mini", 1.10, 4.40),
+    ("o3", 2.00, 8.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("gpt-oss-20b", 0.10, 0.50),
+    ("gpt-oss-120b", 0.15, 0.75),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-haiku-4", 1.00, 5.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-opus-4", 15.00, 75.00),
+    ("gemini-2.5-flash-lite", 0.10, 0.40),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("gemini-2.0-flash-lite", 0.075, 0.30),
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-1.5-flash", 0.075, 0.30),
+    ("llama-3.1-8b-instant", 0.05, 0.08),
+    ("llama-3.3-70b-versatile", 0.59, 0.79),
+];
+
+/// Price of a model: a user-configured price first, then the built-in table.
+/// `None` means the model is unknown and its usage can't be priced.
+pub fn price_for_model(
+    provider_id: &str,
+    model: &str,
+    custom_prices: &HashMap<String, ModelPrice>,
+) -> Option<ModelPrice> {
+    if let Some(price) = custom_prices.get(model) {
+        return Some(*price);
+    }
+    if LOCAL_PROVIDERS.contains(&provider_id) {
+        return Some(ModelPrice::default());
+    }
+
+    let model = model.to_lowercase();
+    // Strip provider prefixes such as "openai/" (OpenRouter) or "models/" (Gemini)
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    PRICES
+        .iter()
+        .find(|(prefix, _, _)| name.starts_with(prefix))
+        .map(|(_, input, output)| ModelPrice {
+            input_per_million: *input,
+            output_per_million: *output,
+        })
+}
+
+/// Estimated cost in USD of the given token counts
+pub fn estimate_cost(price: &ModelPrice, prompt_tokens: i64, completion_tokens: i64) -> f64 {
+    (prompt_tokens as f64 * price.input_per_million
+        + completion_tokens as f64 * price.output_per_million)
+        / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_lookup() {
+        let none = HashMap::new();
+        let mini = price_for_model("openai", "gpt-4o-mini-2024-07-18", &none).unwrap();
+        assert_eq!(mini.input_per_million, 0.15);
+
+        let routed = price_for_model("openrouter", "openai/gpt-4o", &none).unwrap();
+        assert_eq!(routed.output_per_million, 10.00);
+
+        assert_eq!(
+            price_for_model("ollama", "llama3.2", &none),
+            Some(ModelPrice::default())
+        );
+        assert_eq!(price_for_model("custom", "my-finetune", &none), None);
+
+        let mut custom = HashMap::new();
+        custom.insert(
+            "my-finetune".to_string(),
+            ModelPrice {
+                input_per_million: 1.0,
+                output_per_million: 2.0,
+            },
+        );
+        assert!(price_for_model("custom", "my-finetune", &custom).is_some());
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let price = ModelPrice {
+            input_per_million: 2.0,
+            output_per_million: 8.0,
+        };
+        let cost = estimate_cost(&price, 500_000, 250_000);
+        assert!((cost - 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::managers::audio::AudioRecordingManager;
+use crate::settings::{self, AppSettings, SettingsProfile, ShortcutBinding};
+use crate::shortcut;
+use log::{error, info};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Binding id of the action that switches to the next profile
+pub const CYCLE_PROFILE_ACTION_ID: &str = "cycle_profile";
+
+/// Prefix of the tray menu item ids that switch profiles
+pub const TRAY_MENU_PREFIX: &str = "profile:";
+
+/// Snapshot of the profile-specific parts of `settings`
+pub fn capture(settings: &AppSettings, id: String, name: String) -> SettingsProfile {
+    SettingsProfile {
+        id,
+        name,
+        bindings: settings
+            .bindings
+            .iter()
+            .map(|(id, binding)| (id.clone(), binding.current_binding.clone()))
+            .collect(),
+        post_process_enabled: settings.post_process_enabled,
+        post_process_provider_id: settings.post_process_provider_id.clone(),
+        post_process_models: settings.post_process_models.clone(),
+        post_process_prompts: settings.post_process_prompts.clone(),
+        post_process_selected_prompt_id: settings.post_process_selected_prompt_id.clone(),
+        action_configs: settings.action_configs.clone(),
+        use_online_provider: settings.use_online_provider,
+        online_provider_id: settings.online_provider_id.clone(),
+        online_provider_models: settings.online_provider_models.clone(),
+    }
+}
+
+/// Actions added after the profile was saved keep their current shortcut
+fn apply(settings: &mut AppSettings, profile: &SettingsProfile) {
+    for (id, shortcut) in &profile.bindings {
+        if let Some(binding) = settings.bindings.get_mut(id) {
+            binding.current_binding = shortcut.clone();
+        }
+    }
+    settings.post_process_enabled = profile.post_process_enabled;
+    settings.post_process_provider_id = profile.post_process_provider_id.clone();
+    settings.post_process_models = profile.post_process_models.clone();
+    settings.post_process_prompts = profile.post_process_prompts.clone();
+    settings.post_process_selected_prompt_id = profile.post_process_selected_prompt_id.clone();
+    settings.action_configs = profile.action_configs.clone();
+    settings.use_online_provider = profile.use_online_provider;
+    settings.online_provider_id = profile.online_provider_id.clone();
+    settings.online_provider_models = profile.online_provider_models.clone();
+}
+
+/// Save the current settings back to the active profile
+pub fn save_active(settings: &mut AppSettings) {
+    let active = match &settings.active_profile_id {
+        Some(id) => id.clone(),
+        None => return,
+    };
+    if let Some(index) = settings.profiles.iter().position(|p| p.id == active) {
+        let name = settings.profiles[index].name.clone();
+        settings.profiles[index] = capture(settings, active, name);
+    }
+}
+
+/// Profile after `active`, wrapping around; the first one when none is active
+pub fn next_profile_id(profiles: &[SettingsProfile], active: Option<&str>) -> Option<String> {
+    let position = active.and_then(|id| profiles.iter().position(|p| p.id == id));
+    let next = position.map_or(0, |index| (index + 1) % profiles.len());
+    profiles.get(next).map(|profile| profile.id.clone())
+}
+
+/// `settings` switched to the profile `id`, once `swap` has taken the
+/// shortcuts from the current bindings to the profile's; untouched when
+/// the profile is missing or `swap` fails
+fn switch(
+    settings: &mut AppSettings,
+    id: &str,
+    swap: impl FnOnce(
+        &HashMap<String, ShortcutBinding>,
+        &HashMap<String, ShortcutBinding>,
+    ) -> Result<(), String>,
+) -> Result<SettingsProfile, String> {
+    let profile = settings
+        .profiles
+        .iter()
+        .find(|p| p.id == id)
+        .cloned()
+        .ok_or_else(|| format!("Profile '{}' not found", id))?;
+
+    let mut next = settings.clone();
+    save_active(&mut next);
+    apply(&mut next, &profile);
+    swap(&settings.bindings, &next.bindings)?;
+    next.active_profile_id = Some(profile.id.clone());
+    *settings = next;
+    Ok(profile)
+}
+
+/// Switch to the profile `id`. Edits made since the last switch are saved to
+/// the active profile first. Shortcuts are swapped as a set before anything
+/// is saved: if one of the profile's shortcuts can't be registered, nothing
+/// changes.
+pub fn switch_profile(app: &AppHandle, id: &str) -> Result<(), String> {
+    if app.state::<Arc<AudioRecordingManager>>().is_recording() {
+        return Err("Can't switch profiles while recording".to_string());
+    }
+
+    let mut settings = settings::get_settings(app);
+    let profile = switch(&mut settings, id, |previous, next| {
+        shortcut::replace_bindings(app, previous, next)
+    })?;
+    // Switched even when a setting had to be reset, which is reported after
+    let saved = settings::write_settings(app, settings);
+    info!("Switched to profile '{}'", profile.name);
+
+    if let Err(e) = app.emit("profile-changed", &profile.id) {
+        error!("Failed to emit profile-changed: {}", e);
+    }
+    saved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_next_profile_id() {
+        let settings = settings::get_default_settings();
+        let profiles: Vec<SettingsProfile> = ["work", "personal"]
+            .iter()
+            .map(|id| capture(&settings, id.to_string(), id.to_string()))
+            .collect();
+
+        assert_eq!(next_profile_id(&profiles, None).as_deref(), Some("work"));
+        assert_eq!(
+            next_profile_id(&profiles, Some("work")).as_deref(),
+            Some("personal")
+        );
+        assert_eq!(
+            next_profile_id(&profiles, Some("personal")).as_deref(),
+            Some("work")
+        );
+        assert_eq!(next_profile_id(&[], None), None);
+    }
+
+    #[test]
+    fn test_switch_keeps_everything_on_a_conflicting_shortcut() {
+        let mut settings = settings::get_default_settings();
+        let mut work = capture(&settings, "work".to_string(), "Work".to_string());
+        work.post_process_enabled = !settings.post_process_enabled;
+        work.post_process_provider_id = "anthropic".to_string();
+        for (index, shortcut) in work.bindings.values_mut().enumerate() {
+            *shortcut = format!("ctrl+alt+f{}", index + 1);
+        }
+        let conflicting = work.bindings["transcribe"].clone();
+        settings.profiles.push(work);
+        let before = serde_json::to_value(&settings).unwrap();
+
+        // Shortcuts held by other apps can't be registered
+        let mut registered: Vec<String> = settings
+            .bindings
+            .values()
+            .filter(|b| b.id != "cancel" && !b.current_binding.is_empty())
+            .map(|b| b.current_binding.clone())
+            .collect();
+        registered.sort();
+        let held = RefCell::new(registered.clone());
+        let result = switch(&mut settings, "work", |previous, next| {
+            shortcut::swap_bindings(
+                previous,
+                next,
+                |b| {
+                    if b.current_binding == conflicting {
+                        return Err("Already in use".to_string());
+                    }
+                    held.borrow_mut().push(b.current_binding);
+                    Ok(())
+                },
+                |b| {
+                    held.borrow_mut()
+                        .retain(|shortcut| *shortcut != b.current_binding);
+                    Ok(())
+                },
+            )
+        });
+
+        assert!(result.is_err());
+        assert_eq!(serde_json::to_value(&settings).unwrap(), before);
+        let mut held = held.into_inner();
+        held.sort();
+        assert_eq!(held, registered);
+
+        assert!(switch(&mut settings, "work", |_, _| Ok(())).is_ok());
+        assert_eq!(settings.active_profile_id.as_deref(), Some("work"));
+        assert_eq!(settings.post_process_provider_id, "anthropic");
+    }
+}
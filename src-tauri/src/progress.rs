@@ -0,0 +1,203 @@
+//! Progress of work that can take a while, like model downloads or long local
+//! transcriptions. Each run gets an operation id and reports its stage and
+//! percentage as `operation-progress`; `cancel` with that id asks it to stop.
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    ModelDownload,
+    /// Local transcription of a recording long enough to be split up
+    Transcription,
+    HistoryExport,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Payload of the `operation-progress` event
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct ProgressEvent {
+    pub operation_id: String,
+    pub kind: OperationKind,
+    /// What it works on, e.g. the model id
+    pub subject: Option<String>,
+    /// Current step, e.g. `downloading` or `extracting`
+    pub stage: String,
+    /// 0 to 100, None while the total isn't known
+    pub percentage: Option<f64>,
+    pub status: OperationStatus,
+}
+
+struct Running {
+    kind: OperationKind,
+    subject: Option<String>,
+    cancelled: Arc<AtomicBool>,
+}
+
+static RUNNING: Lazy<Mutex<HashMap<String, Running>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A running operation. Dropping it before `complete` reports it as failed,
+/// or cancelled when that was asked for.
+pub struct Operation {
+    app: AppHandle,
+    id: String,
+    kind: OperationKind,
+    subject: Option<String>,
+    cancelled: Arc<AtomicBool>,
+    stage: String,
+    percentage: Option<f64>,
+    ended: bool,
+}
+
+/// Whether a report differs enough from the last one to be sent; downloads
+/// report every chunk
+fn is_news(last: (&str, Option<f64>), next: (&str, Option<f64>)) -> bool {
+    let whole = |percentage: Option<f64>| percentage.map(|p| p.floor() as i64);
+    last.0 != next.0 || whole(last.1) != whole(next.1)
+}
+
+impl Operation {
+    pub fn start(app: &AppHandle, kind: OperationKind, subject: Option<&str>) -> Self {
+        let id = format!("op-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        RUNNING.lock().unwrap().insert(
+            id.clone(),
+            Running {
+                kind,
+                subject: subject.map(str::to_string),
+                cancelled: Arc::clone(&cancelled),
+            },
+        );
+        debug!("Started operation {} ({:?} {:?})", id, kind, subject);
+
+        let operation = Self {
+            app: app.clone(),
+            id,
+            kind,
+            subject: subject.map(str::to_string),
+            cancelled,
+            stage: "starting".to_string(),
+            percentage: None,
+            ended: false,
+        };
+        operation.emit(OperationStatus::Running);
+        operation
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn report(&mut self, stage: &str, percentage: Option<f64>) {
+        let percentage = percentage.map(|p| p.clamp(0.0, 100.0));
+        if !is_news((&self.stage, self.percentage), (stage, percentage)) {
+            return;
+        }
+        self.stage = stage.to_string();
+        self.percentage = percentage;
+        self.emit(OperationStatus::Running);
+    }
+
+    pub fn complete(mut self) {
+        self.percentage = Some(100.0);
+        self.end(OperationStatus::Completed);
+    }
+
+    fn end(&mut self, status: OperationStatus) {
+        self.ended = true;
+        RUNNING.lock().unwrap().remove(&self.id);
+        debug!("Operation {} ended: {:?}", self.id, status);
+        self.emit(status);
+    }
+
+    fn emit(&self, status: OperationStatus) {
+        let event = ProgressEvent {
+            operation_id: self.id.clone(),
+            kind: self.kind,
+            subject: self.subject.clone(),
+            stage: self.stage.clone(),
+            percentage: self.percentage,
+            status,
+        };
+        if let Err(e) = self.app.emit("operation-progress", event) {
+            warn!("Failed to emit operation progress: {}", e);
+        }
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if !self.ended {
+            let status = if self.is_cancelled() {
+                OperationStatus::Cancelled
+            } else {
+                OperationStatus::Failed
+            };
+            self.end(status);
+        }
+    }
+}
+
+/// Ask operation `id` to stop; it does at its next checkpoint
+pub fn cancel(id: &str) -> Result<(), String> {
+    match RUNNING.lock().unwrap().get(id) {
+        Some(running) => {
+            running.cancelled.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        None => Err(format!("No running operation '{}'", id)),
+    }
+}
+
+/// Ask every operation of `kind` to stop, only those on `subject` when given
+pub fn cancel_all(kind: OperationKind, subject: Option<&str>) {
+    for running in RUNNING.lock().unwrap().values() {
+        if running.kind == kind && (subject.is_none() || running.subject.as_deref() == subject) {
+            running.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_news() {
+        assert!(!is_news(
+            ("downloading", Some(41.2)),
+            ("downloading", Some(41.9))
+        ));
+        assert!(is_news(
+            ("downloading", Some(41.9)),
+            ("downloading", Some(42.0))
+        ));
+        assert!(is_news(
+            ("downloading", Some(100.0)),
+            ("extracting", Some(100.0))
+        ));
+        assert!(is_news(("starting", None), ("downloading", None)));
+        assert!(!is_news(("downloading", None), ("downloading", None)));
+    }
+}
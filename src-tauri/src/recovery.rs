@@ -0,0 +1,201 @@
+//! Recordings that survive a crash. While recording, the audio is also
+//! spooled to a file that's deleted once the recording stops; a spool still
+//! there at the next start is from a recording that never finished, which
+//! the user can transcribe into the history or discard.
+
+use crate::actions;
+use crate::managers::history::HistoryManager;
+use crate::managers::transcription::TranscriptionManager;
+use crate::notifications::{self, NotificationKind};
+use crate::portable;
+use crate::settings;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+const SPOOL_DIR: &str = "spool";
+
+const SPOOL_PREFIX: &str = "recording-";
+
+/// Spools from before this time can't belong to a recording of this run
+static LAUNCHED_AT: Lazy<i64> = Lazy::new(|| chrono::Utc::now().timestamp_millis());
+
+/// A recording left behind by a crash
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct OrphanedRecording {
+    pub file_name: String,
+    /// When it started, in milliseconds since the epoch
+    pub started_at: i64,
+    /// How much of it was saved
+    pub duration_ms: i64,
+}
+
+fn spool_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    portable::app_data_dir(app)
+        .map(|dir| dir.join(SPOOL_DIR))
+        .map_err(|e| format!("Failed to get the app data directory: {}", e))
+}
+
+/// File to spool a new recording to; None when the directory can't be made
+pub fn new_spool(app: &AppHandle) -> Option<PathBuf> {
+    let dir = match spool_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn!("{}", e);
+            return None;
+        }
+    };
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        warn!("Failed to create the spool directory {:?}: {}", dir, e);
+        return None;
+    }
+    let started_at = chrono::Utc::now().timestamp_millis();
+    Some(dir.join(format!("{}{}.wav", SPOOL_PREFIX, started_at)))
+}
+
+/// Delete the spool of a recording that stopped normally
+pub fn discard(spool: &Path) {
+    if let Err(e) = std::fs::remove_file(spool) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to delete audio spool {:?}: {}", spool, e);
+        }
+    }
+}
+
+/// Start time encoded in a spool's file name
+fn started_at(file_name: &str) -> Option<i64> {
+    file_name
+        .strip_prefix(SPOOL_PREFIX)?
+        .strip_suffix(".wav")?
+        .parse()
+        .ok()
+}
+
+/// Spools in `dir` started before `before`, oldest first; ones without any
+/// audio are deleted
+fn scan(dir: &Path, before: i64) -> Vec<OrphanedRecording> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut orphans: Vec<OrphanedRecording> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let started_at = started_at(&file_name).filter(|at| *at < before)?;
+            let duration_ms = hound::WavReader::open(entry.path())
+                .map(|reader| {
+                    reader.duration() as i64 * 1000 / i64::from(reader.spec().sample_rate)
+                })
+                .unwrap_or(0);
+            if duration_ms == 0 {
+                debug!("Deleting empty audio spool {}", file_name);
+                discard(&entry.path());
+                return None;
+            }
+            Some(OrphanedRecording {
+                file_name,
+                started_at,
+                duration_ms,
+            })
+        })
+        .collect();
+    orphans.sort_by_key(|orphan| orphan.started_at);
+    orphans
+}
+
+/// Recordings of earlier runs that didn't finish
+pub fn orphans(app: &AppHandle) -> Vec<OrphanedRecording> {
+    spool_dir(app)
+        .map(|dir| scan(&dir, *LAUNCHED_AT))
+        .unwrap_or_default()
+}
+
+/// Path of the orphan named `file_name`, checking it's one
+fn orphan_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    if !orphans(app)
+        .iter()
+        .any(|orphan| orphan.file_name == file_name)
+    {
+        return Err(format!("No unfinished recording '{}'", file_name));
+    }
+    Ok(spool_dir(app)?.join(file_name))
+}
+
+/// Offer to recover the recordings a crash left behind
+pub fn init(app: &AppHandle) {
+    Lazy::force(&LAUNCHED_AT);
+    let found = orphans(app);
+    if found.is_empty() {
+        return;
+    }
+    info!("Found {} unfinished recording(s)", found.len());
+    let body = if found.len() == 1 {
+        "Babbl stopped while recording. Open the history to transcribe or discard it.".to_string()
+    } else {
+        format!(
+            "Babbl stopped during {} recordings. Open the history to transcribe or discard them.",
+            found.len()
+        )
+    };
+    notifications::notify(
+        app,
+        NotificationKind::RecordingRecovered,
+        "Unfinished recording found",
+        &body,
+    );
+}
+
+/// Transcribe an orphan into the history and delete its spool
+pub async fn recover(app: &AppHandle, file_name: &str) -> Result<String, String> {
+    let path = orphan_path(app, file_name)?;
+    let samples = crate::audio_toolkit::read_wav_file(&path)
+        .map_err(|e| format!("Failed to read '{}': {}", file_name, e))?;
+    let settings = settings::get_settings(app);
+    let tm = app.state::<Arc<TranscriptionManager>>();
+    let text = actions::transcribe_audio(&settings, &tm, samples.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let hm = app.state::<Arc<HistoryManager>>();
+    hm.save_transcription(
+        samples,
+        text.clone(),
+        None,
+        None,
+        "transcribe".to_string(),
+        None,
+        None,
+    )
+    .await
+    .map_err(|e| format!("Failed to save the recovered transcription: {}", e))?;
+    discard(&path);
+    info!("Recovered unfinished recording {}", file_name);
+    Ok(text)
+}
+
+/// Delete an orphan without transcribing it
+pub fn discard_orphan(app: &AppHandle, file_name: &str) -> Result<(), String> {
+    let path = orphan_path(app, file_name)?;
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete '{}': {}", file_name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_started_at() {
+        assert_eq!(
+            started_at("recording-1760000000000.wav"),
+            Some(1760000000000)
+        );
+        assert_eq!(started_at("recording-.wav"), None);
+        assert_eq!(started_at("babbl-1760000000.wav"), None);
+        assert_eq!(started_at("recording-1760000000000.wav.tmp"), None);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::llm_error::LlmError;
+use chrono::{DateTime, Utc};
+use log::warn;
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+/// Longest `Retry-After` we are willing to wait before trying again
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Retry policy for outbound HTTP calls to LLM and STT providers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(20),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn with_max_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// Exponential backoff before retry number `attempt` (1-based), without jitter
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Backoff with jitter in the upper half of the window, so parallel
+    /// clients don't retry in lockstep
+    fn backoff_with_jitter(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff(attempt);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// Rate limits, request timeouts and transient server errors are worth retrying
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+}
+
+fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect()
+}
+
+/// Parse a `Retry-After` header given either as delta-seconds or an HTTP date
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&Utc) - now;
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Delay requested by a response's `Retry-After` header, if any
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, Utc::now()))
+}
+
+/// Send a request, retrying transient failures according to `policy`.
+///
+/// `make_request` is called once per attempt because multipart bodies can't be cloned.
+/// The last response is returned as-is when retries are exhausted, so callers keep
+/// their own status handling.
+pub async fn send_with_retry<F>(
+    policy: &RetryPolicy,
+    label: &str,
+    mut make_request: F,
+) -> Result<Response, LlmError>
+where
+    F: FnMut() -> Result<RequestBuilder, String>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = make_request()?.send().await;
+
+        let (reason, delay) = match &result {
+            Ok(response) if is_retryable_status(response.status()) => (
+                format!("status {}", response.status()),
+                retry_after(response)
+                    .map(|d| d.min(MAX_RETRY_AFTER))
+                    .unwrap_or_else(|| policy.backoff_with_jitter(attempt)),
+            ),
+            Err(e) if is_retryable_error(e) => (e.to_string(), policy.backoff_with_jitter(attempt)),
+            _ => return result.map_err(LlmError::from),
+        };
+
+        if attempt >= policy.max_attempts {
+            warn!(
+                "{}: giving up after {} attempt(s), last failure: {}",
+                label, attempt, reason
+            );
+            return result.map_err(LlmError::from);
+        }
+
+        warn!(
+            "{}: attempt {}/{} failed ({}), retrying in {:?}",
+            label, attempt, policy.max_attempts, reason, delay
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(30), policy.max_delay);
+
+        let jittered = policy.backoff_with_jitter(3);
+        assert!(jittered >= Duration::from_secs(1) && jittered <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::NOT_IMPLEMENTED));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(parse_retry_after("7", now), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2024 12:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Mon, 01 Jan 2024 11:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
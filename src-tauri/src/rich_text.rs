@@ -0,0 +1,124 @@
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+fn parser(markdown: &str) -> Parser<'_> {
+    Parser::new_ext(
+        markdown,
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES,
+    )
+}
+
+/// Markdown constructs needed before text is converted; a transcript can
+/// have one by chance, like a sentence that starts with "- "
+const MIN_CONSTRUCTS: usize = 2;
+
+/// Whether `text` uses Markdown formatting worth converting. Plain sentences
+/// are pasted as they are, so apps keep their own font and style.
+pub fn looks_like_markdown(text: &str) -> bool {
+    let block_markup = text
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            line.starts_with("# ")
+                || line.starts_with("## ")
+                || line.starts_with("### ")
+                || line.starts_with("- ")
+                || line.starts_with("* ")
+                || line.starts_with("> ")
+                || line.split_once(". ").is_some_and(|(number, _)| {
+                    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+                })
+        })
+        .count();
+    // Only paired markers count
+    let bold = text.matches("**").count() / 2;
+    let code = text.matches('`').count() / 2;
+    let links = text.matches("](").count().min(text.matches('[').count());
+    block_markup + bold + code + links >= MIN_CONSTRUCTS
+}
+
+/// HTML fragment for the clipboard
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut output = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut output, parser(markdown));
+    output
+}
+
+/// The same text without Markdown syntax, for apps that only read plain text
+pub fn markdown_to_plain_text(markdown: &str) -> String {
+    let mut output = String::with_capacity(markdown.len());
+    let mut list_numbers: Vec<Option<u64>> = Vec::new();
+    let mut links: Vec<(CowStr, usize)> = Vec::new();
+
+    for event in parser(markdown) {
+        match event {
+            Event::Start(Tag::List(start)) => list_numbers.push(start),
+            Event::End(TagEnd::List(_)) => {
+                list_numbers.pop();
+            }
+            Event::Start(Tag::Item) => {
+                output.push_str(&"  ".repeat(list_numbers.len().saturating_sub(1)));
+                match list_numbers.last_mut() {
+                    Some(Some(number)) => {
+                        output.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => output.push_str("• "),
+                }
+            }
+            // Kept as `text (url)`, where the text isn't the URL already
+            Event::Start(Tag::Link { dest_url, .. }) => links.push((dest_url, output.len())),
+            Event::End(TagEnd::Link) => {
+                if let Some((url, start)) = links.pop() {
+                    if output[start..] != *url {
+                        output.push_str(&format!(" ({})", url));
+                    }
+                }
+            }
+            Event::Text(text) | Event::Code(text) => output.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => output.push('\n'),
+            Event::End(TagEnd::Paragraph) | Event::End(TagEnd::Heading(_)) => {
+                output.push_str("\n\n")
+            }
+            Event::End(TagEnd::Item) | Event::End(TagEnd::TableRow) if !output.ends_with('\n') => {
+                output.push('\n')
+            }
+            Event::End(TagEnd::TableCell) => output.push('\t'),
+            _ => {}
+        }
+    }
+
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_markdown() {
+        assert!(looks_like_markdown("Agenda:\n- budget\n- hiring"));
+        assert!(looks_like_markdown("It's **important**, see `notes.md`."));
+        assert!(looks_like_markdown("1. First\n2. Second"));
+        assert!(!looks_like_markdown("Meet me at 5. Bring the slides."));
+        assert!(!looks_like_markdown("Just a plain sentence."));
+        // A single construct is likely chance
+        assert!(!looks_like_markdown("- that's what she said"));
+        assert!(!looks_like_markdown("Rename it to __init__ and `run`."));
+    }
+
+    #[test]
+    fn test_markdown_conversion() {
+        let markdown = "Notes with **bold**:\n\n- one\n- two";
+        let html = markdown_to_html(markdown);
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(html.contains("<li>one</li>"));
+        assert_eq!(
+            markdown_to_plain_text(markdown),
+            "Notes with bold:\n\n• one\n• two"
+        );
+        assert_eq!(
+            markdown_to_plain_text("See [the docs](https://example.com) or <https://example.com>."),
+            "See the docs (https://example.com) or https://example.com."
+        );
+    }
+}
@@ -0,0 +1,281 @@
+//! Optional Rhai script with hooks for custom text rules. The script may
+//! define any of:
+//!
+//! - `on_transcript(text, ctx)`: runs on the raw transcription, before
+//!   post-processing. Returning an empty string drops the dictation.
+//! - `before_inject(text, ctx)`: runs on the final text when it's about to be
+//!   typed or pasted. Returning `false` vetoes the injection; the other
+//!   outputs still get the text.
+//! - `on_error(message, ctx)`: runs when a dictation fails.
+//!
+//! The text hooks return the new text, or nothing to keep it. `ctx` has the
+//! `binding_id` of the action and the focused `app`, if known. The script is
+//! compiled again whenever the file changes.
+
+use crate::settings::AppSettings;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Keeps a runaway loop from holding up the pipeline
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+const MAX_CALL_LEVELS: usize = 32;
+
+/// Bytes; far above any transcript, and keeps a string doubled in a loop
+/// from taking all the memory before the operation limit is hit
+const MAX_STRING_SIZE: usize = 1 << 20;
+
+const MAX_ARRAY_SIZE: usize = 10_000;
+
+const MAX_MAP_SIZE: usize = 10_000;
+
+static ENGINE: Lazy<Engine> = Lazy::new(|| {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_ARRAY_SIZE);
+    engine.set_max_map_size(MAX_MAP_SIZE);
+    // Scripts see the transcript, so what they print may well be part of it
+    engine.on_print(|text| {
+        info!(
+            target: crate::logging::TRANSCRIPT_TARGET,
+            "Hook script: {}", text
+        )
+    });
+    engine.on_debug(|text, _source, position| {
+        debug!(
+            target: crate::logging::TRANSCRIPT_TARGET,
+            "Hook script {}: {}", position, text
+        )
+    });
+    engine
+});
+
+/// Last compiled script, None inside when it didn't compile
+struct Compiled {
+    path: String,
+    modified: Option<SystemTime>,
+    ast: Option<Arc<AST>>,
+}
+
+static COMPILED: Lazy<Mutex<Option<Compiled>>> = Lazy::new(|| Mutex::new(None));
+
+/// Where a hook runs
+pub struct HookContext<'a> {
+    pub binding_id: &'a str,
+    /// Process name of the focused app
+    pub app: Option<&'a str>,
+}
+
+impl HookContext<'_> {
+    fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        map.insert("binding_id".into(), self.binding_id.to_string().into());
+        let app = self.app.map_or(Dynamic::UNIT, |app| app.to_string().into());
+        map.insert("app".into(), app);
+        map
+    }
+}
+
+/// What a text hook asked for
+#[derive(Debug, PartialEq)]
+pub enum HookResult {
+    Keep,
+    Replace(String),
+    Veto,
+}
+
+/// Compile the script at `path`, e.g. to check it before it's saved
+pub fn load(path: &str) -> Result<AST, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read hook script '{}': {}", path, e))?;
+    ENGINE
+        .compile(source)
+        .map_err(|e| format!("Hook script '{}' doesn't compile: {}", path, e))
+}
+
+/// The configured script, compiled again when the file changed
+fn script(settings: &AppSettings) -> Option<Arc<AST>> {
+    let path = settings.hook_script_path.as_deref()?;
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    let mut compiled = COMPILED.lock().unwrap();
+    let current = compiled
+        .as_ref()
+        .filter(|compiled| compiled.path == path && compiled.modified == modified);
+    if let Some(current) = current {
+        return current.ast.clone();
+    }
+
+    let ast = match load(path) {
+        Ok(ast) => Some(Arc::new(ast)),
+        Err(e) => {
+            // Once per change of the file, not on every dictation
+            warn!("{}", e);
+            None
+        }
+    };
+    *compiled = Some(Compiled {
+        path: path.to_string(),
+        modified,
+        ast: ast.clone(),
+    });
+    ast
+}
+
+fn defines(ast: &AST, hook: &str) -> bool {
+    ast.iter_functions()
+        .any(|function| function.name == hook && function.params.len() == 2)
+}
+
+/// Whether the configured script defines `hook`
+pub fn has_hook(settings: &AppSettings, hook: &str) -> bool {
+    script(settings).is_some_and(|ast| defines(&ast, hook))
+}
+
+/// Run `hook` with `value` and the context; None when the script doesn't
+/// define it or it failed
+fn call(ast: &AST, hook: &str, value: &str, context: &HookContext) -> Option<Dynamic> {
+    if !defines(ast, hook) {
+        return None;
+    }
+    let args = (value.to_string(), context.to_map());
+    match ENGINE.call_fn::<Dynamic>(&mut Scope::new(), ast, hook, args) {
+        Ok(result) => Some(result),
+        Err(e) => {
+            warn!("Hook '{}' failed: {}", hook, e);
+            None
+        }
+    }
+}
+
+fn text_result(hook: &str, value: Dynamic, may_veto: bool) -> HookResult {
+    if value.is_unit() {
+        return HookResult::Keep;
+    }
+    if let Ok(veto) = value.as_bool() {
+        return match (veto, may_veto) {
+            (false, true) => HookResult::Veto,
+            _ => HookResult::Keep,
+        };
+    }
+    match value.into_string() {
+        Ok(text) => HookResult::Replace(text),
+        Err(kind) => {
+            warn!("Hook '{}' returned a {}, not text", hook, kind);
+            HookResult::Keep
+        }
+    }
+}
+
+/// Pass a fresh transcription through `on_transcript`
+pub fn on_transcript(settings: &AppSettings, context: &HookContext, text: String) -> String {
+    let result = script(settings)
+        .and_then(|ast| call(&ast, "on_transcript", &text, context))
+        .map_or(HookResult::Keep, |value| {
+            text_result("on_transcript", value, false)
+        });
+    match result {
+        HookResult::Replace(replaced) => {
+            debug!(
+                target: crate::logging::TRANSCRIPT_TARGET,
+                "on_transcript changed '{}' to '{}'", text, replaced
+            );
+            replaced
+        }
+        HookResult::Keep | HookResult::Veto => text,
+    }
+}
+
+/// Ask `before_inject` about the text that's about to be injected
+pub fn before_inject(settings: &AppSettings, context: &HookContext, text: &str) -> HookResult {
+    script(settings)
+        .and_then(|ast| call(&ast, "before_inject", text, context))
+        .map_or(HookResult::Keep, |value| {
+            text_result("before_inject", value, true)
+        })
+}
+
+/// Tell `on_error` that a dictation failed
+pub fn on_error(settings: &AppSettings, context: &HookContext, message: &str) {
+    if let Some(ast) = script(settings) {
+        call(&ast, "on_error", message, context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_hooks() {
+        let ast = ENGINE
+            .compile(
+                r#"
+                fn on_transcript(text, ctx) {
+                    if ctx.app == "slack" { text.to_lower() }
+                }
+                fn before_inject(text, ctx) {
+                    if text.contains("password") { return false; }
+                    text + "!"
+                }
+                "#,
+            )
+            .unwrap();
+        let context = HookContext {
+            binding_id: "transcribe",
+            app: Some("slack"),
+        };
+        let run = |hook: &str, text: &str, may_veto: bool| {
+            text_result(hook, call(&ast, hook, text, &context).unwrap(), may_veto)
+        };
+
+        assert_eq!(
+            run("on_transcript", "Hello", false),
+            HookResult::Replace("hello".to_string())
+        );
+        assert_eq!(
+            run("before_inject", "Hi", true),
+            HookResult::Replace("Hi!".to_string())
+        );
+        assert_eq!(run("before_inject", "my password", true), HookResult::Veto);
+        assert_eq!(run("before_inject", "my password", false), HookResult::Keep);
+        assert!(call(&ast, "on_error", "boom", &context).is_none());
+
+        let other_app = HookContext {
+            binding_id: "transcribe",
+            app: None,
+        };
+        let value = call(&ast, "on_transcript", "Hello", &other_app).unwrap();
+        assert_eq!(text_result("on_transcript", value, false), HookResult::Keep);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let ast = ENGINE
+            .compile(
+                r#"
+                fn on_transcript(text, ctx) {
+                    loop { text += text; }
+                }
+                fn before_inject(text, ctx) {
+                    let list = [];
+                    loop { list.push(text); }
+                }
+                "#,
+            )
+            .unwrap();
+        let context = HookContext {
+            binding_id: "transcribe",
+            app: None,
+        };
+        assert!(call(&ast, "on_transcript", "Hello", &context).is_none());
+        assert!(call(&ast, "before_inject", "Hello", &context).is_none());
+    }
+}
@@ -0,0 +1,162 @@
+//! Secure keyboard entry on macOS. While an app has it on (Terminal's Secure
+//! Keyboard Entry, a password manager, a password field left focused), no
+//! other app sees key events, so Babbl's shortcuts seem to stop working at
+//! random. Babbl looks for it periodically and tells the user which app holds
+//! it.
+
+use crate::notifications::{self, NotificationKind};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Type)]
+pub struct SecureInput {
+    /// Process that turned it on, when macOS tells
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+/// Secure input as last seen, `None` while it's off
+static CURRENT: Lazy<Mutex<Option<SecureInput>>> = Lazy::new(|| Mutex::new(None));
+
+#[cfg(target_os = "macos")]
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn IsSecureEventInputEnabled() -> bool;
+}
+
+/// Pid of the process holding secure input, from the output of
+/// `ioreg -l -w 0 -d 1 -c IORegistryEntry`
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_holder_pid(ioreg: &str) -> Option<u32> {
+    const KEY: &str = "\"kCGSSessionSecureInputPID\"=";
+    let start = ioreg.find(KEY)? + KEY.len();
+    let digits: String = ioreg[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok().filter(|pid| *pid != 0)
+}
+
+#[cfg(target_os = "macos")]
+fn holder() -> SecureInput {
+    use std::process::Command;
+
+    let run = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let pid = run(
+        "ioreg",
+        &["-l", "-w", "0", "-d", "1", "-c", "IORegistryEntry"],
+    )
+    .as_deref()
+    .and_then(parse_holder_pid);
+    // The executable path; its file name is the app's name
+    let process = pid
+        .and_then(|pid| run("ps", &["-p", &pid.to_string(), "-o", "comm="]))
+        .and_then(|command| {
+            std::path::Path::new(&command)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .filter(|name| !name.is_empty());
+    SecureInput { pid, process }
+}
+
+/// Whether secure input is on, and who holds it
+#[cfg(target_os = "macos")]
+fn detect() -> Option<SecureInput> {
+    unsafe { IsSecureEventInputEnabled() }.then(holder)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn detect() -> Option<SecureInput> {
+    None
+}
+
+/// Secure input as of the last check
+pub fn current() -> Option<SecureInput> {
+    CURRENT.lock().unwrap().clone()
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn update(app: &AppHandle) {
+    let next = detect();
+    let changed = {
+        let mut current = CURRENT.lock().unwrap();
+        let changed = *current != next;
+        *current = next.clone();
+        changed
+    };
+    if !changed {
+        return;
+    }
+    match &next {
+        Some(secure_input) => {
+            let holder = secure_input
+                .process
+                .clone()
+                .unwrap_or_else(|| "Another app".to_string());
+            warn!("Secure input is on, held by {:?}", secure_input);
+            notifications::notify(
+                app,
+                NotificationKind::PermissionProblem,
+                "Shortcuts are blocked",
+                &format!(
+                    "{} turned on secure keyboard entry, which keeps Babbl from seeing \
+                     its shortcuts. Turn it off there, or close the password field it's \
+                     asking for.",
+                    holder
+                ),
+            );
+        }
+        None => info!("Secure input is off again"),
+    }
+    if let Err(e) = app.emit("secure-input-changed", next) {
+        warn!("Failed to emit secure input change: {}", e);
+    }
+}
+
+/// Look for secure input periodically (macOS)
+pub fn init(app: &AppHandle) {
+    #[cfg(target_os = "macos")]
+    {
+        let app = app.clone();
+        std::thread::spawn(move || loop {
+            update(&app);
+            std::thread::sleep(crate::power::scaled(CHECK_INTERVAL));
+        });
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_holder_pid() {
+        let ioreg = r#"+-o Root  <class IORegistryEntry, id 0x100000100, retain 28>
+    {
+      "IOConsoleUsers" = ({"kCGSSessionOnConsoleKey"=Yes,"kCGSSessionSecureInputPID"=7301,"kCGSSessionUserIDKey"=501})
+    }"#;
+        assert_eq!(parse_holder_pid(ioreg), Some(7301));
+        assert_eq!(
+            parse_holder_pid(r#"({"kCGSSessionSecureInputPID"=0})"#),
+            None
+        );
+        assert_eq!(parse_holder_pid(r#"({"kCGSSessionUserIDKey"=501})"#), None);
+    }
+}
@@ -0,0 +1,355 @@
+//! The text highlighted in the focused app, for prompts that use
+//! `${selection}`: "rewrite this" style actions then get the highlighted
+//! text next to the spoken instruction, and pasting the result replaces it.
+//!
+//! It's read through accessibility when a recording starts, while the app
+//! still has focus and its selection, and only for actions whose prompt asks
+//! for it. macOS asks the focused element for `AXSelectedText`, Windows the
+//! text pattern of the focused UI Automation element. On Linux the
+//! highlighted text is the primary selection, which every toolkit fills
+//! while far from every app exposes AT-SPI text; it's read with `wl-paste`,
+//! `xclip` or `xsel`. It outlives the highlight there, so text selected
+//! earlier can show up with nothing highlighted now.
+//!
+//! Smart spacing reads the few characters before the cursor the same way,
+//! from the focused element's value and selected range, so nothing is typed
+//! into the app to find them.
+
+use crate::settings::AppSettings;
+use log::debug;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+pub const PLACEHOLDER: &str = "${selection}";
+
+/// Longer selections are cut, they'd crowd out the instruction
+const MAX_CHARS: usize = 20_000;
+
+/// Selection read when the current dictation started
+static CAPTURED: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::ffi::c_void;
+
+    type CFTypeRef = *const c_void;
+
+    const UTF8: u32 = 0x0800_0100;
+
+    /// `kAXValueCFRangeType`
+    const CF_RANGE_TYPE: u32 = 4;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CFRange {
+        location: isize,
+        length: isize,
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXUIElementCreateSystemWide() -> CFTypeRef;
+        fn AXUIElementCopyAttributeValue(
+            element: CFTypeRef,
+            attribute: CFTypeRef,
+            value: *mut CFTypeRef,
+        ) -> i32;
+        fn AXValueGetValue(value: CFTypeRef, value_type: u32, value_ptr: *mut c_void) -> bool;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithBytes(
+            allocator: CFTypeRef,
+            bytes: *const u8,
+            length: isize,
+            encoding: u32,
+            external: bool,
+        ) -> CFTypeRef;
+        fn CFStringGetTypeID() -> usize;
+        fn CFGetTypeID(value: CFTypeRef) -> usize;
+        fn CFStringGetLength(string: CFTypeRef) -> isize;
+        fn CFStringGetMaximumSizeForEncoding(length: isize, encoding: u32) -> isize;
+        fn CFStringGetCString(
+            string: CFTypeRef,
+            buffer: *mut u8,
+            size: isize,
+            encoding: u32,
+        ) -> bool;
+        fn CFRelease(value: CFTypeRef);
+    }
+
+    unsafe fn attribute(element: CFTypeRef, name: &str) -> Option<CFTypeRef> {
+        let name = CFStringCreateWithBytes(
+            std::ptr::null(),
+            name.as_ptr(),
+            name.len() as isize,
+            UTF8,
+            false,
+        );
+        let mut value: CFTypeRef = std::ptr::null();
+        let error = AXUIElementCopyAttributeValue(element, name, &mut value);
+        CFRelease(name);
+        (error == 0 && !value.is_null()).then_some(value)
+    }
+
+    unsafe fn to_string(string: CFTypeRef) -> Option<String> {
+        if CFGetTypeID(string) != CFStringGetTypeID() {
+            return None;
+        }
+        let size = CFStringGetMaximumSizeForEncoding(CFStringGetLength(string), UTF8) + 1;
+        let mut buffer = vec![0u8; size as usize];
+        if !CFStringGetCString(string, buffer.as_mut_ptr(), size, UTF8) {
+            return None;
+        }
+        let end = buffer.iter().position(|byte| *byte == 0)?;
+        String::from_utf8(buffer[..end].to_vec()).ok()
+    }
+
+    unsafe fn string_attribute(element: CFTypeRef, name: &str) -> Option<String> {
+        let value = attribute(element, name)?;
+        let text = to_string(value);
+        CFRelease(value);
+        text
+    }
+
+    /// `read` applied to the focused element
+    unsafe fn with_focused<T>(read: impl FnOnce(CFTypeRef) -> Option<T>) -> Option<T> {
+        let system = AXUIElementCreateSystemWide();
+        let focused = attribute(system, "AXFocusedUIElement");
+        CFRelease(system);
+        let focused = focused?;
+        let result = read(focused);
+        CFRelease(focused);
+        result
+    }
+
+    pub fn selected_text() -> Option<String> {
+        unsafe { with_focused(|element| string_attribute(element, "AXSelectedText")) }
+    }
+
+    pub fn text_before_cursor() -> Option<String> {
+        unsafe {
+            with_focused(|element| {
+                let range = attribute(element, "AXSelectedTextRange")?;
+                let mut selected = CFRange::default();
+                let is_range = AXValueGetValue(
+                    range,
+                    CF_RANGE_TYPE,
+                    &mut selected as *mut CFRange as *mut c_void,
+                );
+                CFRelease(range);
+                if !is_range {
+                    return None;
+                }
+                // The range counts UTF-16 code units
+                let value = string_attribute(element, "AXValue")?;
+                let before: Vec<u16> = value
+                    .encode_utf16()
+                    .take(selected.location.max(0) as usize)
+                    .collect();
+                Some(String::from_utf16_lossy(&before))
+            })
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn read() -> Option<String> {
+    macos::selected_text()
+}
+
+#[cfg(target_os = "macos")]
+fn read_before_cursor(_max_chars: usize) -> Option<String> {
+    macos::text_before_cursor()
+}
+
+/// Text pattern of the focused UI Automation element; elements without
+/// text, like buttons, have none
+#[cfg(target_os = "windows")]
+unsafe fn focused_text_pattern(
+) -> Option<windows::Win32::UI::Accessibility::IUIAutomationTextPattern> {
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{CUIAutomation, IUIAutomation, UIA_TextPatternId};
+
+    let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    let automation: IUIAutomation =
+        CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+    let focused = automation.GetFocusedElement().ok()?;
+    focused.GetCurrentPatternAs(UIA_TextPatternId).ok()
+}
+
+#[cfg(target_os = "windows")]
+fn read() -> Option<String> {
+    unsafe {
+        let pattern = focused_text_pattern()?;
+        let ranges = pattern.GetSelection().ok()?;
+        let mut text = String::new();
+        for index in 0..ranges.Length().ok()? {
+            let range = ranges.GetElement(index).ok()?;
+            text.push_str(&range.GetText(-1).ok()?.to_string());
+        }
+        Some(text)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_before_cursor(max_chars: usize) -> Option<String> {
+    use windows::Win32::UI::Accessibility::{
+        TextPatternRangeEndpoint_End, TextPatternRangeEndpoint_Start, TextUnit_Character,
+    };
+
+    unsafe {
+        let pattern = focused_text_pattern()?;
+        let ranges = pattern.GetSelection().ok()?;
+        if ranges.Length().ok()? == 0 {
+            return None;
+        }
+        // The cursor is an empty selection; take the characters before its start
+        let selection = ranges.GetElement(0).ok()?;
+        let before = selection.Clone().ok()?;
+        before
+            .MoveEndpointByRange(
+                TextPatternRangeEndpoint_End,
+                &selection,
+                TextPatternRangeEndpoint_Start,
+            )
+            .ok()?;
+        before
+            .MoveEndpointByUnit(
+                TextPatternRangeEndpoint_Start,
+                TextUnit_Character,
+                -(max_chars as i32),
+            )
+            .ok()?;
+        Some(before.GetText(-1).ok()?.to_string())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read() -> Option<String> {
+    use std::process::Command;
+
+    let tools: &[(&str, &[&str])] = if crate::utils::is_wayland() {
+        &[("wl-paste", &["--primary", "--no-newline"])]
+    } else {
+        &[
+            ("xclip", &["-o", "-selection", "primary"]),
+            ("xsel", &["--primary", "--output"]),
+        ]
+    };
+    tools.iter().find_map(|(program, args)| {
+        let output = Command::new(program).args(*args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn read() -> Option<String> {
+    None
+}
+
+/// The primary selection says nothing about the cursor
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn read_before_cursor(_max_chars: usize) -> Option<String> {
+    None
+}
+
+/// `text` cut to at most `max_chars` characters
+fn truncate(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    }
+}
+
+/// The last `max_chars` characters of `text`
+fn last_chars(text: &str, max_chars: usize) -> &str {
+    let skipped = text.chars().count().saturating_sub(max_chars);
+    match text.char_indices().nth(skipped) {
+        Some((index, _)) => &text[index..],
+        None => "",
+    }
+}
+
+/// Whether the prompts action `binding_id` runs with ask for the selection
+pub fn is_used(settings: &AppSettings, binding_id: &str) -> bool {
+    let uses = |template: &str| template.contains(PLACEHOLDER);
+    let config = settings.action_config(binding_id);
+    // Same precedence as the post-processing: steps, the action's template,
+    // then the selected prompt
+    if let Some(config) = config.as_ref().filter(|config| !config.steps.is_empty()) {
+        return config.steps.iter().any(|step| uses(&step.prompt_template));
+    }
+    if let Some(template) = config.and_then(|config| config.prompt_template) {
+        return uses(&template);
+    }
+    settings
+        .post_process_selected_prompt_id
+        .as_ref()
+        .and_then(|id| {
+            settings
+                .post_process_prompts
+                .iter()
+                .find(|prompt| &prompt.id == id)
+        })
+        .is_some_and(|prompt| uses(&prompt.prompt))
+}
+
+/// Read the selection for a dictation of `binding_id` that's starting, in
+/// the background, when its prompt uses it
+pub fn capture(settings: &AppSettings, binding_id: &str) {
+    *CAPTURED.lock().unwrap() = None;
+    if !settings.post_process_enabled || !is_used(settings, binding_id) {
+        return;
+    }
+    std::thread::spawn(|| {
+        let selection = read()
+            .map(|text| truncate(text.trim(), MAX_CHARS).to_string())
+            .filter(|text| !text.is_empty());
+        debug!(
+            "Read {} selected characters",
+            selection.as_ref().map_or(0, |text| text.chars().count())
+        );
+        *CAPTURED.lock().unwrap() = selection;
+    });
+}
+
+/// The selection read when this dictation started, empty when nothing was
+/// highlighted
+pub fn captured() -> String {
+    CAPTURED.lock().unwrap().clone().unwrap_or_default()
+}
+
+/// Up to `max_chars` characters before the cursor in the focused field, read
+/// through accessibility so nothing is typed into the app. `None` where it
+/// isn't exposed: on Linux, in terminals and in apps without accessible
+/// text.
+pub fn text_before_cursor(max_chars: usize) -> Option<String> {
+    read_before_cursor(max_chars).map(|text| last_chars(&text, max_chars).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("highlighted", 20), "highlighted");
+        assert_eq!(truncate("highlighted", 4), "high");
+        assert_eq!(truncate("größer", 3), "grö");
+    }
+
+    #[test]
+    fn test_last_chars() {
+        assert_eq!(last_chars("Hello, ", 2), ", ");
+        assert_eq!(last_chars("süß", 2), "üß");
+        assert_eq!(last_chars("a", 2), "a");
+        assert_eq!(last_chars("abc", 0), "");
+    }
+}
@@ -1,13 +1,22 @@
+use crate::env_overrides;
+use crate::portable;
+use crate::settings_events;
+use crate::settings_validation;
 use log::{debug, warn};
+use once_cell::sync::Lazy;
 use serde::de::{self, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
 use specta::Type;
 use std::collections::HashMap;
-use tauri::AppHandle;
-use tauri_plugin_store::StoreExt;
+use std::sync::RwLock;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::{Store, StoreExt};
 
 pub const APPLE_INTELLIGENCE_PROVIDER_ID: &str = "apple_intelligence";
 pub const APPLE_INTELLIGENCE_DEFAULT_MODEL_ID: &str = "Apple Intelligence";
+pub const LLAMA_CPP_PROVIDER_ID: &str = "llama_cpp";
+pub const LLAMA_CPP_DEFAULT_MODEL_ID: &str = "Local GGUF model";
 
 #[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
 #[serde(rename_all = "lowercase")]
@@ -19,7 +28,8 @@ pub enum LogLevel {
     Error,
 }
 
-// Custom deserializer to handle both old numeric format (1-5) and new string format ("trace", "debug", etc.)
+// Custom deserializer so levels match in any case; the old numeric format is
+// converted by `migrate_numeric_log_level`
 impl<'de> Deserialize<'de> for LogLevel {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -31,7 +41,7 @@ impl<'de> Deserialize<'de> for LogLevel {
             type Value = LogLevel;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a string or integer representing log level")
+                formatter.write_str("a string representing log level")
             }
 
             fn visit_str<E: de::Error>(self, value: &str) -> Result<LogLevel, E> {
@@ -47,31 +57,20 @@ impl<'de> Deserialize<'de> for LogLevel {
                     )),
                 }
             }
-
-            fn visit_u64<E: de::Error>(self, value: u64) -> Result<LogLevel, E> {
-                match value {
-                    1 => Ok(LogLevel::Trace),
-                    2 => Ok(LogLevel::Debug),
-                    3 => Ok(LogLevel::Info),
-                    4 => Ok(LogLevel::Warn),
-                    5 => Ok(LogLevel::Error),
-                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(value), &"1-5")),
-                }
-            }
         }
 
-        deserializer.deserialize_any(LogLevelVisitor)
+        deserializer.deserialize_str(LogLevelVisitor)
     }
 }
 
-impl From<LogLevel> for tauri_plugin_log::LogLevel {
+impl From<LogLevel> for tracing::Level {
     fn from(level: LogLevel) -> Self {
         match level {
-            LogLevel::Trace => tauri_plugin_log::LogLevel::Trace,
-            LogLevel::Debug => tauri_plugin_log::LogLevel::Debug,
-            LogLevel::Info => tauri_plugin_log::LogLevel::Info,
-            LogLevel::Warn => tauri_plugin_log::LogLevel::Warn,
-            LogLevel::Error => tauri_plugin_log::LogLevel::Error,
+            LogLevel::Trace => tracing::Level::TRACE,
+            LogLevel::Debug => tracing::Level::DEBUG,
+            LogLevel::Info => tracing::Level::INFO,
+            LogLevel::Warn => tracing::Level::WARN,
+            LogLevel::Error => tracing::Level::ERROR,
         }
     }
 }
@@ -92,6 +91,223 @@ pub struct LLMPrompt {
     pub prompt: String,
 }
 
+/// Stored text inserted when its trigger phrase is spoken
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+pub struct Snippet {
+    /// Spoken phrase, matched ignoring case and punctuation
+    pub trigger: String,
+    pub text: String,
+}
+
+/// Named set of shortcuts, prompts and provider choices that can be switched
+/// to in one go. API keys are shared by all profiles.
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+pub struct SettingsProfile {
+    pub id: String,
+    pub name: String,
+    /// Shortcut per binding id
+    pub bindings: HashMap<String, String>,
+    pub post_process_enabled: bool,
+    pub post_process_provider_id: String,
+    pub post_process_models: HashMap<String, String>,
+    pub post_process_prompts: Vec<LLMPrompt>,
+    pub post_process_selected_prompt_id: Option<String>,
+    pub action_configs: HashMap<String, ActionConfig>,
+    pub use_online_provider: bool,
+    pub online_provider_id: String,
+    pub online_provider_models: HashMap<String, String>,
+}
+
+/// Settings that change while a particular app is focused
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Type)]
+pub struct AppOverride {
+    /// Transcription language code, or "auto"
+    #[serde(default)]
+    pub selected_language: Option<String>,
+    #[serde(default)]
+    pub post_process_enabled: Option<bool>,
+    /// Prompt used for post-processing instead of the selected one
+    #[serde(default)]
+    pub post_process_prompt_id: Option<String>,
+    #[serde(default)]
+    pub paste_method: Option<PasteMethod>,
+}
+
+/// One LLM call in a multi-step action pipeline
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Type)]
+pub struct PipelineStep {
+    pub name: String,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// `${output}` is the previous step's result (the transcription for the first step),
+    /// `${transcription}` the original text and `${step.<name>}` an earlier step's result
+    pub prompt_template: String,
+}
+
+/// Per-action overrides, keyed by the binding id of the action
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Type)]
+pub struct ActionConfig {
+    /// Paste the raw transcription without running the LLM
+    #[serde(default)]
+    pub skip_post_process: bool,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// User prompt template; `${output}` is replaced with the transcription
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+    /// Let the model call registered tools (OpenAI-compatible providers only)
+    #[serde(default)]
+    pub tools_enabled: bool,
+    /// JSON Schema (as JSON text) the response must follow; the parsed result
+    /// is emitted as a `structured-output` event and its fields are typed
+    /// instead of the JSON
+    #[serde(default)]
+    pub output_schema: Option<String>,
+    /// Have the provider enforce `output_schema` exactly, which OpenAI only
+    /// accepts for schemas that require every property
+    #[serde(default)]
+    pub output_schema_strict: bool,
+    /// Ordered LLM steps; when set, they replace the single prompt above
+    #[serde(default)]
+    pub steps: Vec<PipelineStep>,
+    /// Sampling overrides; unset values leave the provider's defaults in place
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub stop: Vec<String>,
+    /// Where the result goes, all at once; empty follows `output_mode`
+    #[serde(default)]
+    pub output_targets: Vec<OutputTarget>,
+    /// File for `OutputTarget::File`; falls back to `note_file_path`
+    #[serde(default)]
+    pub output_file_path: Option<String>,
+    /// URL that receives the result as JSON for `OutputTarget::Webhook`
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Extra headers sent to the webhook, e.g. an API key
+    #[serde(default)]
+    pub webhook_headers: HashMap<String, String>,
+    /// Key that signs webhook requests with HMAC-SHA256 when set
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+    /// Vault folder for `OutputTarget::Obsidian`
+    #[serde(default)]
+    pub obsidian_vault_path: Option<String>,
+    /// Note in the vault, `{date}` for one per day; `Babbl/{date}.md` when unset
+    #[serde(default)]
+    pub obsidian_note_path: Option<String>,
+    /// Entry template with `{{text}}`, `{{transcription}}`, `{{date}}`,
+    /// `{{time}}` and `{{action}}`
+    #[serde(default)]
+    pub obsidian_template: Option<String>,
+    /// Database for `OutputTarget::Notion`, its id or link
+    #[serde(default)]
+    pub notion_database_id: Option<String>,
+    /// Token of a Notion integration the database is shared with
+    #[serde(default)]
+    pub notion_token: Option<String>,
+    /// Show the text for review before it's injected
+    #[serde(default)]
+    pub confirm_before_inject: bool,
+    /// Command the final text is piped to before it's delivered
+    #[serde(default)]
+    pub shell_command: Option<ShellCommand>,
+    /// Steps the final text goes through, in order, before it's delivered
+    #[serde(default)]
+    pub chain: Vec<ChainStep>,
+    /// Seconds the action may take after the recording stops, overriding
+    /// `action_timeout_secs`; zero means no limit
+    #[serde(default)]
+    pub timeout_secs: Option<u32>,
+    /// What its shortcut does while an earlier run is still processing
+    #[serde(default)]
+    pub concurrency: ConcurrencyPolicy,
+}
+
+/// What happens when an action is started while an earlier run of it hasn't
+/// delivered its result yet
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrencyPolicy {
+    /// Both runs go on and deliver whenever they're done
+    Parallel,
+    /// The new run records right away but delivers after the earlier one
+    Queue,
+    /// The new run doesn't start
+    Ignore,
+    /// The earlier run is cancelled
+    Restart,
+}
+
+/// What a chain does when one of its steps fails
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum StepErrorPolicy {
+    /// End the chain without delivering the text
+    Stop,
+    /// Go on with the text from before the step
+    Continue,
+}
+
+/// Work done by one chain step; each gets the text the previous one produced
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChainStepKind {
+    /// Post-process with another action's prompt, e.g. `mode_summarize`
+    Action {
+        action_id: String,
+    },
+    Shell {
+        command: ShellCommand,
+    },
+    /// Send the text on and keep going; `inject` waits for the end of the chain
+    Output {
+        targets: Vec<OutputTarget>,
+    },
+    /// Show the text in a notification
+    Notify {
+        title: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+pub struct ChainStep {
+    pub step: ChainStepKind,
+    #[serde(default)]
+    pub on_error: StepErrorPolicy,
+}
+
+/// Command an action pipes its text to on stdin, run with the platform shell
+/// (`sh -c`, or `cmd /C` on Windows)
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+pub struct ShellCommand {
+    pub command: String,
+    /// Directory it runs in; the app's own when unset
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Added to the environment, next to `BABBL_ACTION_ID` and
+    /// `BABBL_TRANSCRIPTION`
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// The command is killed after this long
+    #[serde(default = "default_shell_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Continue with what it prints instead of the text it was given
+    #[serde(default)]
+    pub capture_output: bool,
+}
+
+/// Token price in USD per million tokens
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Type)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Type)]
 pub struct PostProcessProvider {
     pub id: String,
@@ -101,6 +317,9 @@ pub struct PostProcessProvider {
     pub allow_base_url_edit: bool,
     #[serde(default)]
     pub models_endpoint: Option<String>,
+    /// API version query parameter, for providers that version by URL (Azure)
+    #[serde(default)]
+    pub api_version: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
@@ -109,6 +328,27 @@ pub enum OverlayPosition {
     None,
     Top,
     Bottom,
+    /// Next to the mouse cursor
+    Cursor,
+}
+
+/// Where on a display the overlay goes, overriding `overlay_position` there
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayAnchor {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+/// Top left corner of the widget in logical screen coordinates
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+pub struct WidgetPosition {
+    pub x: f64,
+    pub y: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
@@ -124,6 +364,37 @@ pub enum ModelUnloadTimeout {
     Sec5, // Debug mode only
 }
 
+/// When the models and connections a dictation needs are readied, see
+/// [`crate::warmup`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmUp {
+    Off,
+    /// As soon as Babbl starts
+    Launch,
+    /// Once Babbl has sat idle for a bit after starting
+    Idle,
+}
+
+/// When Babbl switches to lighter behavior, see [`crate::power`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum LowPowerMode {
+    /// On battery or in the system's power saver
+    Auto,
+    Always,
+    Never,
+}
+
+/// Releases Babbl updates to, see [`crate::updater`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    Stable,
+    /// Pre-releases, ahead of stable
+    Beta,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
 #[serde(rename_all = "snake_case")]
 pub enum PasteMethod {
@@ -132,6 +403,9 @@ pub enum PasteMethod {
     None,
     ShiftInsert,
     CtrlShiftV,
+    /// Type the text key by key at `typing_chars_per_second`, for apps that
+    /// don't accept paste (terminals, remote desktops)
+    Keystrokes,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
@@ -141,6 +415,75 @@ pub enum ClipboardHandling {
     CopyToClipboard,
 }
 
+/// Where finished transcripts go
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Paste or type into the focused app
+    Inject,
+    /// Append to the Markdown file at `note_file_path`
+    AppendToNote,
+}
+
+/// One destination of an action's result
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputTarget {
+    /// Paste or type into the focused app
+    Inject,
+    /// Leave the result on the clipboard without pasting it
+    Clipboard,
+    /// Show the result in the app window
+    Popup,
+    /// Append to a Markdown file, like `OutputMode::AppendToNote`
+    File,
+    /// POST the result to `webhook_url`
+    Webhook,
+    /// Append to a note in the Obsidian vault at `obsidian_vault_path`
+    Obsidian,
+    /// Add a page to the Notion database `notion_database_id`
+    Notion,
+}
+
+/// How the undo action removes the last injected text
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoMethod {
+    /// One backspace per injected character
+    Backspace,
+    /// Ctrl+Z / Cmd+Z, for apps that treat the paste as a single edit
+    PlatformUndo,
+}
+
+/// How transcripts that exceed the model's context window are shortened
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum LongTranscriptStrategy {
+    Truncate,
+    Summarize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum GeminiSafetyThreshold {
+    BlockNone,
+    BlockOnlyHigh,
+    BlockMediumAndAbove,
+    BlockLowAndAbove,
+}
+
+impl GeminiSafetyThreshold {
+    /// Threshold name expected by the Gemini API
+    pub fn as_api_str(&self) -> &'static str {
+        match self {
+            GeminiSafetyThreshold::BlockNone => "BLOCK_NONE",
+            GeminiSafetyThreshold::BlockOnlyHigh => "BLOCK_ONLY_HIGH",
+            GeminiSafetyThreshold::BlockMediumAndAbove => "BLOCK_MEDIUM_AND_ABOVE",
+            GeminiSafetyThreshold::BlockLowAndAbove => "BLOCK_LOW_AND_ABOVE",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
 #[serde(rename_all = "snake_case")]
 pub enum RecordingRetentionPeriod {
@@ -149,6 +492,8 @@ pub enum RecordingRetentionPeriod {
     Days3,
     Weeks2,
     Months3,
+    /// Keep `history_retention_days` days
+    CustomDays,
 }
 
 impl Default for ModelUnloadTimeout {
@@ -157,6 +502,24 @@ impl Default for ModelUnloadTimeout {
     }
 }
 
+impl Default for WarmUp {
+    fn default() -> Self {
+        WarmUp::Idle
+    }
+}
+
+impl Default for LowPowerMode {
+    fn default() -> Self {
+        LowPowerMode::Auto
+    }
+}
+
+impl Default for UpdateChannel {
+    fn default() -> Self {
+        UpdateChannel::Stable
+    }
+}
+
 impl Default for PasteMethod {
     fn default() -> Self {
         // Default to CtrlV for macOS and Windows, Direct for Linux
@@ -173,6 +536,30 @@ impl Default for ClipboardHandling {
     }
 }
 
+impl Default for StepErrorPolicy {
+    fn default() -> Self {
+        StepErrorPolicy::Stop
+    }
+}
+
+impl Default for ConcurrencyPolicy {
+    fn default() -> Self {
+        ConcurrencyPolicy::Parallel
+    }
+}
+
+impl Default for OutputMode {
+    fn default() -> Self {
+        OutputMode::Inject
+    }
+}
+
+impl Default for UndoMethod {
+    fn default() -> Self {
+        UndoMethod::Backspace
+    }
+}
+
 impl ModelUnloadTimeout {
     pub fn to_minutes(self) -> Option<u64> {
         match self {
@@ -225,6 +612,9 @@ impl SoundTheme {
 
 #[derive(Serialize, Deserialize, Debug, Clone, Type)]
 pub struct AppSettings {
+    /// Layout version of the stored settings, see [`migrate_settings`]
+    #[serde(default)]
+    pub schema_version: u32,
     pub bindings: HashMap<String, ShortcutBinding>,
     pub push_to_talk: bool,
     pub audio_feedback: bool,
@@ -236,8 +626,17 @@ pub struct AppSettings {
     pub start_hidden: bool,
     #[serde(default = "default_autostart_enabled")]
     pub autostart_enabled: bool,
+    /// Stay in the tray when started at login, even if `start_hidden` is off
+    #[serde(default = "default_autostart_minimized")]
+    pub autostart_minimized: bool,
+    /// Seconds to wait after a start at login before opening the microphone,
+    /// so the audio devices are there
+    #[serde(default = "default_autostart_delay_secs")]
+    pub autostart_delay_secs: u32,
     #[serde(default = "default_update_checks_enabled")]
     pub update_checks_enabled: bool,
+    #[serde(default)]
+    pub update_channel: UpdateChannel,
     #[serde(default = "default_model")]
     pub selected_model: String,
     #[serde(default = "default_always_on_microphone")]
@@ -254,24 +653,95 @@ pub struct AppSettings {
     pub selected_language: String,
     #[serde(default = "default_overlay_position")]
     pub overlay_position: OverlayPosition,
+    /// Clicks go through the overlay to the window below it
+    #[serde(default = "default_overlay_click_through")]
+    pub overlay_click_through: bool,
+    /// Overlay corner per display, by display name
+    #[serde(default)]
+    pub overlay_display_anchors: HashMap<String, OverlayAnchor>,
+    /// Show the floating widget with a record button
+    #[serde(default)]
+    pub widget_enabled: bool,
+    /// Where the widget was dragged to, None until it's moved
+    #[serde(default)]
+    pub widget_position: Option<WidgetPosition>,
     #[serde(default = "default_debug_mode")]
     pub debug_mode: bool,
+    /// Notify when a transcription finishes after switching away from the app
+    #[serde(default = "default_notify")]
+    pub notify_transcription_complete: bool,
+    #[serde(default = "default_notify")]
+    pub notify_provider_errors: bool,
+    /// Notify when the microphone or input control is refused
+    #[serde(default = "default_notify")]
+    pub notify_permission_problems: bool,
     #[serde(default = "default_log_level")]
     pub log_level: LogLevel,
+    /// Levels for single modules in the log files, e.g. `input_hook`,
+    /// overriding `log_level`
+    #[serde(default)]
+    pub log_module_levels: HashMap<String, LogLevel>,
     #[serde(default)]
     pub custom_words: Vec<String>,
     #[serde(default)]
+    pub snippets: Vec<Snippet>,
+    #[serde(default)]
+    pub profiles: Vec<SettingsProfile>,
+    /// Profile the current settings belong to; edits are saved back to it on switch
+    #[serde(default)]
+    pub active_profile_id: Option<String>,
+    /// Overrides keyed by the process name of the focused app, e.g. `code`
+    #[serde(default)]
+    pub app_overrides: HashMap<String, AppOverride>,
+    #[serde(default)]
     pub model_unload_timeout: ModelUnloadTimeout,
+    #[serde(default)]
+    pub warm_up: WarmUp,
+    #[serde(default)]
+    pub low_power_mode: LowPowerMode,
+    /// Local model used instead of the selected one in low-power mode
+    #[serde(default)]
+    pub low_power_model: Option<String>,
     #[serde(default = "default_word_correction_threshold")]
     pub word_correction_threshold: f64,
     #[serde(default = "default_history_limit")]
     pub history_limit: usize,
     #[serde(default = "default_recording_retention_period")]
     pub recording_retention_period: RecordingRetentionPeriod,
+    /// Days kept with the custom retention period
+    #[serde(default = "default_history_retention_days")]
+    pub history_retention_days: u32,
+    /// Off stops saving new dictations and their audio
+    #[serde(default = "default_history_enabled")]
+    pub history_enabled: bool,
+    /// Expired entries lose their recording but keep their text
+    #[serde(default)]
+    pub purge_audio_only: bool,
+    /// Total size of kept recordings; the oldest unsaved ones are removed above it
+    #[serde(default)]
+    pub recording_storage_limit_mb: Option<u64>,
+    /// Keep the history database and recordings encrypted with a key from the
+    /// OS keyring
+    #[serde(default)]
+    pub history_encryption_enabled: bool,
+    /// Folder meeting transcripts are written to, `meetings` in the app data
+    /// folder when unset
+    #[serde(default)]
+    pub meeting_notes_dir: Option<String>,
+    /// Also keep each segment of a meeting in history, with its audio
+    #[serde(default = "default_meeting_save_to_history")]
+    pub meeting_save_to_history: bool,
     #[serde(default)]
     pub paste_method: PasteMethod,
     #[serde(default)]
     pub clipboard_handling: ClipboardHandling,
+    #[serde(default)]
+    pub undo_method: UndoMethod,
+    #[serde(default)]
+    pub output_mode: OutputMode,
+    /// Markdown file for `OutputMode::AppendToNote`; `{date}` starts a file per day
+    #[serde(default)]
+    pub note_file_path: Option<String>,
     #[serde(default = "default_post_process_enabled")]
     pub post_process_enabled: bool,
     #[serde(default = "default_post_process_provider_id")]
@@ -280,16 +750,155 @@ pub struct AppSettings {
     pub post_process_providers: Vec<PostProcessProvider>,
     #[serde(default = "default_post_process_api_keys")]
     pub post_process_api_keys: HashMap<String, String>,
+    /// Keys tried in order when the main key is rejected or rate limited
+    #[serde(default)]
+    pub post_process_fallback_api_keys: HashMap<String, Vec<String>>,
     #[serde(default = "default_post_process_models")]
     pub post_process_models: HashMap<String, String>,
     #[serde(default = "default_post_process_prompts")]
     pub post_process_prompts: Vec<LLMPrompt>,
+    #[serde(default = "default_gemini_safety_threshold")]
+    pub gemini_safety_threshold: GeminiSafetyThreshold,
     #[serde(default)]
     pub post_process_selected_prompt_id: Option<String>,
     #[serde(default)]
+    pub action_configs: HashMap<String, ActionConfig>,
+    #[serde(default = "default_translate_target_language")]
+    pub translate_target_language: String,
+    #[serde(default = "default_long_transcript_strategy")]
+    pub long_transcript_strategy: LongTranscriptStrategy,
+    #[serde(default)]
     pub mute_while_recording: bool,
+    /// Megabytes of a recording held in memory; the rest waits on disk until
+    /// the recording stops
+    #[serde(default = "default_recording_memory_limit_mb")]
+    pub recording_memory_limit_mb: u32,
+    /// Start a dictation when the wake phrase is said
+    #[serde(default)]
+    pub wake_word_enabled: bool,
+    /// Name of the phrase; it's recognized by the recordings made of it
+    #[serde(default = "default_wake_word_phrase")]
+    pub wake_word_phrase: String,
+    /// 0 to 1, how far what's heard may be from the recordings of the phrase
+    #[serde(default = "default_wake_word_sensitivity")]
+    pub wake_word_sensitivity: f32,
     #[serde(default)]
     pub append_trailing_space: bool,
+    #[serde(default = "default_typing_chars_per_second")]
+    pub typing_chars_per_second: u32,
+    /// Put back what was on the clipboard after a clipboard paste
+    #[serde(default = "default_restore_clipboard")]
+    pub restore_clipboard: bool,
+    #[serde(default = "default_clipboard_restore_delay_ms")]
+    pub clipboard_restore_delay_ms: u64,
+    /// Paste Markdown output as rich text (HTML) so formatting survives in
+    /// mail and document editors
+    #[serde(default)]
+    pub markdown_rich_text: bool,
+    /// Add a space and adjust capitalization to fit the text before the
+    /// cursor, where the app exposes it through accessibility
+    #[serde(default)]
+    pub smart_spacing: bool,
+    /// Type post-processed text while the LLM is still generating it,
+    /// correcting revised words with backspaces
+    #[serde(default)]
+    pub streaming_injection: bool,
+    #[serde(default = "default_network_max_attempts")]
+    pub network_max_attempts: u32,
+    /// Seconds an action may take from the end of the recording until its
+    /// result is delivered before it's cancelled; zero means no limit
+    #[serde(default)]
+    pub action_timeout_secs: u32,
+    /// Proxy for all outgoing requests (http, https, socks5 or socks5h URL)
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// PEM bundle of extra CAs to trust, e.g. for TLS-inspecting corporate proxies
+    #[serde(default)]
+    pub custom_ca_path: Option<String>,
+    /// Let `babbl://` links start a recording or transcribe a file; any web
+    /// page can open one
+    #[serde(default)]
+    pub allow_link_actions: bool,
+    /// Serve the local HTTP control API on 127.0.0.1
+    #[serde(default)]
+    pub control_api_enabled: bool,
+    #[serde(default = "default_control_api_port")]
+    pub control_api_port: u16,
+    /// Token every control API and WebSocket client needs; created when
+    /// either is first enabled
+    #[serde(default)]
+    pub control_api_token: Option<String>,
+    /// Serve events and accept control messages over WebSocket on 127.0.0.1
+    #[serde(default)]
+    pub websocket_enabled: bool,
+    #[serde(default = "default_websocket_port")]
+    pub websocket_port: u16,
+    /// Publish states and transcripts to an MQTT broker and take commands
+    #[serde(default)]
+    pub mqtt_enabled: bool,
+    #[serde(default = "default_mqtt_host")]
+    pub mqtt_host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub mqtt_port: u16,
+    #[serde(default)]
+    pub mqtt_username: Option<String>,
+    #[serde(default)]
+    pub mqtt_password: Option<String>,
+    /// Topics are `<prefix>/state`, `<prefix>/transcript` and so on
+    #[serde(default = "default_mqtt_topic_prefix")]
+    pub mqtt_topic_prefix: String,
+    /// Off unless asked for, every subscriber on the broker can read them
+    #[serde(default)]
+    pub mqtt_publish_transcripts: bool,
+    /// Show live captions in an OBS text source through obs-websocket
+    #[serde(default)]
+    pub obs_enabled: bool,
+    #[serde(default = "default_obs_host")]
+    pub obs_host: String,
+    #[serde(default = "default_obs_port")]
+    pub obs_port: u16,
+    #[serde(default)]
+    pub obs_password: Option<String>,
+    /// Name of the text source the captions go to
+    #[serde(default = "default_obs_text_source")]
+    pub obs_text_source: String,
+    /// Clear the caption this long after the final transcript, 0 keeps it
+    #[serde(default = "default_obs_clear_after_secs")]
+    pub obs_clear_after_secs: u32,
+    /// Mute Teams and Zoom calls while recording
+    #[serde(default)]
+    pub call_mute_enabled: bool,
+    /// Token Teams handed out when it allowed Babbl to control meetings
+    #[serde(default)]
+    pub call_mute_teams_token: Option<String>,
+    /// Rhai script with hooks that can change the text or veto injection
+    #[serde(default)]
+    pub hook_script_path: Option<String>,
+    /// Send recent dictations along as chat history so follow-ups can edit them
+    #[serde(default)]
+    pub conversation_context_enabled: bool,
+    #[serde(default = "default_conversation_context_turns")]
+    pub conversation_context_turns: u32,
+    #[serde(default = "default_conversation_context_window_minutes")]
+    pub conversation_context_window_minutes: u32,
+    /// Prices for models missing from the built-in table, keyed by model name
+    #[serde(default)]
+    pub custom_model_prices: HashMap<String, ModelPrice>,
+    /// Soft LLM spending limit per calendar month; exceeding it only warns
+    #[serde(default)]
+    pub monthly_llm_budget_usd: Option<f64>,
+    /// Service tier per provider id, e.g. `flex` for Groq
+    #[serde(default)]
+    pub post_process_service_tiers: HashMap<String, String>,
+    /// Extra request body fields per provider id, as JSON object text
+    #[serde(default)]
+    pub post_process_extra_body: HashMap<String, String>,
+    /// GGUF file used by the embedded llama.cpp provider
+    #[serde(default)]
+    pub llama_cpp_model_path: Option<String>,
+    /// Model layers offloaded to the GPU; 0 runs on the CPU only
+    #[serde(default)]
+    pub llama_cpp_gpu_layers: u32,
     // Online provider settings
     #[serde(default)]
     pub use_online_provider: bool,
@@ -297,6 +906,8 @@ pub struct AppSettings {
     pub online_provider_id: String,
     #[serde(default = "default_online_provider_api_keys")]
     pub online_provider_api_keys: HashMap<String, String>,
+    #[serde(default)]
+    pub online_provider_fallback_api_keys: HashMap<String, Vec<String>>,
     #[serde(default = "default_online_provider_models")]
     pub online_provider_models: HashMap<String, String>,
     #[serde(default)]
@@ -325,6 +936,14 @@ fn default_autostart_enabled() -> bool {
     false
 }
 
+fn default_autostart_minimized() -> bool {
+    true
+}
+
+fn default_autostart_delay_secs() -> u32 {
+    0
+}
+
 fn default_update_checks_enabled() -> bool {
     true
 }
@@ -340,6 +959,18 @@ fn default_overlay_position() -> OverlayPosition {
     return OverlayPosition::Bottom;
 }
 
+fn default_overlay_click_through() -> bool {
+    true
+}
+
+fn default_notify() -> bool {
+    true
+}
+
+fn default_shell_timeout_secs() -> u64 {
+    30
+}
+
 fn default_debug_mode() -> bool {
     false
 }
@@ -356,10 +987,35 @@ fn default_history_limit() -> usize {
     5
 }
 
+/// About 17 minutes of audio
+fn default_recording_memory_limit_mb() -> u32 {
+    64
+}
+
+fn default_wake_word_phrase() -> String {
+    crate::wake_word::DEFAULT_PHRASE.to_string()
+}
+
+fn default_wake_word_sensitivity() -> f32 {
+    0.5
+}
+
 fn default_recording_retention_period() -> RecordingRetentionPeriod {
     RecordingRetentionPeriod::PreserveLimit
 }
 
+fn default_history_retention_days() -> u32 {
+    30
+}
+
+fn default_history_enabled() -> bool {
+    true
+}
+
+fn default_meeting_save_to_history() -> bool {
+    true
+}
+
 fn default_audio_feedback_volume() -> f32 {
     1.0
 }
@@ -372,6 +1028,78 @@ fn default_post_process_enabled() -> bool {
     false
 }
 
+fn default_gemini_safety_threshold() -> GeminiSafetyThreshold {
+    GeminiSafetyThreshold::BlockOnlyHigh
+}
+
+fn default_long_transcript_strategy() -> LongTranscriptStrategy {
+    LongTranscriptStrategy::Truncate
+}
+
+fn default_translate_target_language() -> String {
+    "English".to_string()
+}
+
+fn default_restore_clipboard() -> bool {
+    true
+}
+
+fn default_clipboard_restore_delay_ms() -> u64 {
+    300
+}
+
+fn default_typing_chars_per_second() -> u32 {
+    100
+}
+
+fn default_network_max_attempts() -> u32 {
+    3
+}
+
+fn default_control_api_port() -> u16 {
+    47_810
+}
+
+fn default_websocket_port() -> u16 {
+    47_811
+}
+
+fn default_mqtt_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_topic_prefix() -> String {
+    "babbl".to_string()
+}
+
+fn default_obs_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_obs_port() -> u16 {
+    4455
+}
+
+fn default_obs_text_source() -> String {
+    "Babbl Captions".to_string()
+}
+
+fn default_obs_clear_after_secs() -> u32 {
+    5
+}
+
+fn default_conversation_context_turns() -> u32 {
+    3
+}
+
+fn default_conversation_context_window_minutes() -> u32 {
+    5
+}
+
 fn default_online_provider_id() -> String {
     "openai".to_string()
 }
@@ -409,6 +1137,7 @@ fn default_post_process_providers() -> Vec<PostProcessProvider> {
             base_url: "https://api.openai.com/v1".to_string(),
             allow_base_url_edit: false,
             models_endpoint: Some("/models".to_string()),
+            api_version: None,
         },
         PostProcessProvider {
             id: "openrouter".to_string(),
@@ -416,13 +1145,23 @@ fn default_post_process_providers() -> Vec<PostProcessProvider> {
             base_url: "https://openrouter.ai/api/v1".to_string(),
             allow_base_url_edit: false,
             models_endpoint: Some("/models".to_string()),
+            api_version: None,
         },
         PostProcessProvider {
             id: "gemini".to_string(),
             label: "Gemini".to_string(),
-            base_url: "https://generativelanguage.googleapis.com/v1beta/openai".to_string(),
+            base_url: "https://generativelanguage.googleapis.com/v1beta".to_string(),
+            allow_base_url_edit: false,
+            models_endpoint: Some("/models".to_string()),
+            api_version: None,
+        },
+        PostProcessProvider {
+            id: "anthropic".to_string(),
+            label: "Anthropic".to_string(),
+            base_url: "https://api.anthropic.com/v1".to_string(),
             allow_base_url_edit: false,
             models_endpoint: Some("/models".to_string()),
+            api_version: None,
         },
         PostProcessProvider {
             id: "groq".to_string(),
@@ -430,6 +1169,7 @@ fn default_post_process_providers() -> Vec<PostProcessProvider> {
             base_url: "https://api.groq.com/openai/v1".to_string(),
             allow_base_url_edit: false,
             models_endpoint: Some("/models".to_string()),
+            api_version: None,
         },
         PostProcessProvider {
             id: "cerebras".to_string(),
@@ -437,6 +1177,24 @@ fn default_post_process_providers() -> Vec<PostProcessProvider> {
             base_url: "https://api.cerebras.ai/v1".to_string(),
             allow_base_url_edit: false,
             models_endpoint: Some("/models".to_string()),
+            api_version: None,
+        },
+        PostProcessProvider {
+            id: "azure".to_string(),
+            label: "Azure OpenAI".to_string(),
+            base_url: "https://your-resource.openai.azure.com".to_string(),
+            allow_base_url_edit: true,
+            // Deployments can't be listed with an API key, the model is the deployment name
+            models_endpoint: None,
+            api_version: Some(crate::llm_client::AZURE_DEFAULT_API_VERSION.to_string()),
+        },
+        PostProcessProvider {
+            id: "ollama".to_string(),
+            label: "Ollama".to_string(),
+            base_url: "http://localhost:11434".to_string(),
+            allow_base_url_edit: true,
+            models_endpoint: Some("/api/tags".to_string()),
+            api_version: None,
         },
         PostProcessProvider {
             id: "custom".to_string(),
@@ -444,6 +1202,7 @@ fn default_post_process_providers() -> Vec<PostProcessProvider> {
             base_url: "http://localhost:11434/v1".to_string(),
             allow_base_url_edit: true,
             models_endpoint: Some("/models".to_string()),
+            api_version: None,
         },
     ];
 
@@ -456,10 +1215,21 @@ fn default_post_process_providers() -> Vec<PostProcessProvider> {
                 base_url: "apple-intelligence://local".to_string(),
                 allow_base_url_edit: false,
                 models_endpoint: None,
+                api_version: None,
             });
         }
     }
 
+    #[cfg(feature = "llama-cpp")]
+    providers.push(PostProcessProvider {
+        id: LLAMA_CPP_PROVIDER_ID.to_string(),
+        label: "Local (llama.cpp)".to_string(),
+        base_url: "llama-cpp://local".to_string(),
+        allow_base_url_edit: false,
+        models_endpoint: None,
+        api_version: None,
+    });
+
     providers
 }
 
@@ -475,6 +1245,9 @@ fn default_model_for_provider(provider_id: &str) -> String {
     if provider_id == APPLE_INTELLIGENCE_PROVIDER_ID {
         return APPLE_INTELLIGENCE_DEFAULT_MODEL_ID.to_string();
     }
+    if provider_id == LLAMA_CPP_PROVIDER_ID {
+        return LLAMA_CPP_DEFAULT_MODEL_ID.to_string();
+    }
     String::new()
 }
 
@@ -512,13 +1285,22 @@ fn default_post_process_prompts() -> Vec<LLMPrompt> {
 fn ensure_post_process_defaults(settings: &mut AppSettings) -> bool {
     let mut changed = false;
     for provider in default_post_process_providers() {
-        if settings
-            .post_process_providers
-            .iter()
-            .all(|existing| existing.id != provider.id)
-        {
-            settings.post_process_providers.push(provider.clone());
-            changed = true;
+        match settings.post_process_provider_mut(&provider.id) {
+            Some(existing) => {
+                // Endpoints of built-in providers aren't user editable, so keep them current
+                if !provider.allow_base_url_edit
+                    && (existing.base_url != provider.base_url
+                        || existing.models_endpoint != provider.models_endpoint)
+                {
+                    existing.base_url = provider.base_url.clone();
+                    existing.models_endpoint = provider.models_endpoint.clone();
+                    changed = true;
+                }
+            }
+            None => {
+                settings.post_process_providers.push(provider.clone());
+                changed = true;
+            }
         }
 
         if !settings.post_process_api_keys.contains_key(&provider.id) {
@@ -550,6 +1332,63 @@ fn ensure_post_process_defaults(settings: &mut AppSettings) -> bool {
 
 pub const SETTINGS_STORE_PATH: &str = "settings_store.json";
 
+/// Upgrades stored settings from the schema version at its index to the next.
+/// Append one whenever a key is renamed or restructured, so old config files
+/// keep their values instead of failing to parse and falling back to defaults.
+const SETTINGS_MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[migrate_numeric_log_level];
+
+pub const SETTINGS_SCHEMA_VERSION: u32 = SETTINGS_MIGRATIONS.len() as u32;
+
+/// 0 to 1: log levels were stored as numbers from 1 (trace) to 5 (error)
+fn migrate_numeric_log_level(settings: &mut Map<String, Value>) {
+    const NAMES: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+    let name = settings
+        .get("log_level")
+        .and_then(Value::as_u64)
+        .and_then(|level| level.checked_sub(1))
+        .and_then(|index| NAMES.get(index as usize));
+    if let Some(name) = name {
+        settings.insert("log_level".to_string(), Value::from(*name));
+    }
+}
+
+/// Runs the migrations the stored settings `value` still needs and stamps it
+/// with the current schema version. Returns whether anything changed.
+pub fn migrate_settings(value: &mut Value) -> bool {
+    let settings = match value.as_object_mut() {
+        Some(settings) => settings,
+        None => return false,
+    };
+    // Settings from before versioning have no version
+    let version = settings
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version >= SETTINGS_SCHEMA_VERSION as u64 {
+        if version > SETTINGS_SCHEMA_VERSION as u64 {
+            warn!(
+                "Settings were saved by a newer version (schema {}), loading them as they are",
+                version
+            );
+        }
+        return false;
+    }
+
+    for (index, migration) in SETTINGS_MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(version as usize)
+    {
+        debug!("Migrating settings from schema {} to {}", index, index + 1);
+        migration(settings);
+    }
+    settings.insert(
+        "schema_version".to_string(),
+        Value::from(SETTINGS_SCHEMA_VERSION),
+    );
+    true
+}
+
 pub fn get_default_settings() -> AppSettings {
     #[cfg(target_os = "windows")]
     let default_shortcut = "ctrl+space";
@@ -581,8 +1420,53 @@ pub fn get_default_settings() -> AppSettings {
             current_binding: "escape".to_string(),
         },
     );
+    bindings.insert(
+        crate::voice_command::VOICE_COMMAND_ACTION_ID.to_string(),
+        ShortcutBinding {
+            id: crate::voice_command::VOICE_COMMAND_ACTION_ID.to_string(),
+            name: "Voice Command".to_string(),
+            description: "Speak a command such as \"switch to email mode\" or \"open history\"."
+                .to_string(),
+            default_binding: String::new(),
+            current_binding: String::new(),
+        },
+    );
+    bindings.insert(
+        crate::clipboard::UNDO_INJECTION_ACTION_ID.to_string(),
+        ShortcutBinding {
+            id: crate::clipboard::UNDO_INJECTION_ACTION_ID.to_string(),
+            name: "Undo Last Dictation".to_string(),
+            description: "Removes the text that was last pasted or typed.".to_string(),
+            default_binding: String::new(),
+            current_binding: String::new(),
+        },
+    );
+    bindings.insert(
+        crate::profiles::CYCLE_PROFILE_ACTION_ID.to_string(),
+        ShortcutBinding {
+            id: crate::profiles::CYCLE_PROFILE_ACTION_ID.to_string(),
+            name: "Switch Profile".to_string(),
+            description: "Switches to the next settings profile.".to_string(),
+            default_binding: String::new(),
+            current_binding: String::new(),
+        },
+    );
+    // Built-in post-processing modes start unbound
+    for mode in crate::modes::BUILTIN_MODES {
+        bindings.insert(
+            mode.id.to_string(),
+            ShortcutBinding {
+                id: mode.id.to_string(),
+                name: mode.name.to_string(),
+                description: mode.description.to_string(),
+                default_binding: String::new(),
+                current_binding: String::new(),
+            },
+        );
+    }
 
     AppSettings {
+        schema_version: SETTINGS_SCHEMA_VERSION,
         bindings,
         push_to_talk: true,
         audio_feedback: false,
@@ -590,7 +1474,10 @@ pub fn get_default_settings() -> AppSettings {
         sound_theme: default_sound_theme(),
         start_hidden: default_start_hidden(),
         autostart_enabled: default_autostart_enabled(),
+        autostart_minimized: default_autostart_minimized(),
+        autostart_delay_secs: default_autostart_delay_secs(),
         update_checks_enabled: default_update_checks_enabled(),
+        update_channel: UpdateChannel::default(),
         selected_model: "".to_string(),
         always_on_microphone: false,
         selected_microphone: None,
@@ -599,35 +1486,170 @@ pub fn get_default_settings() -> AppSettings {
         translate_to_english: false,
         selected_language: "auto".to_string(),
         overlay_position: default_overlay_position(),
+        overlay_click_through: default_overlay_click_through(),
+        overlay_display_anchors: HashMap::new(),
+        widget_enabled: false,
+        widget_position: None,
         debug_mode: false,
+        notify_transcription_complete: default_notify(),
+        notify_provider_errors: default_notify(),
+        notify_permission_problems: default_notify(),
         log_level: default_log_level(),
+        log_module_levels: HashMap::new(),
         custom_words: Vec::new(),
+        snippets: Vec::new(),
+        profiles: Vec::new(),
+        active_profile_id: None,
+        app_overrides: HashMap::new(),
         model_unload_timeout: ModelUnloadTimeout::Never,
+        warm_up: WarmUp::default(),
+        low_power_mode: LowPowerMode::default(),
+        low_power_model: None,
         word_correction_threshold: default_word_correction_threshold(),
         history_limit: default_history_limit(),
         recording_retention_period: default_recording_retention_period(),
+        history_retention_days: default_history_retention_days(),
+        history_enabled: default_history_enabled(),
+        purge_audio_only: false,
+        recording_storage_limit_mb: None,
+        history_encryption_enabled: false,
+        meeting_notes_dir: None,
+        meeting_save_to_history: default_meeting_save_to_history(),
         paste_method: PasteMethod::default(),
         clipboard_handling: ClipboardHandling::default(),
+        undo_method: UndoMethod::default(),
+        output_mode: OutputMode::default(),
+        note_file_path: None,
         post_process_enabled: default_post_process_enabled(),
         post_process_provider_id: default_post_process_provider_id(),
         post_process_providers: default_post_process_providers(),
         post_process_api_keys: default_post_process_api_keys(),
+        post_process_fallback_api_keys: HashMap::new(),
         post_process_models: default_post_process_models(),
         post_process_prompts: default_post_process_prompts(),
+        gemini_safety_threshold: default_gemini_safety_threshold(),
         post_process_selected_prompt_id: None,
+        action_configs: HashMap::new(),
+        translate_target_language: default_translate_target_language(),
+        long_transcript_strategy: default_long_transcript_strategy(),
         mute_while_recording: false,
+        recording_memory_limit_mb: default_recording_memory_limit_mb(),
+        wake_word_enabled: false,
+        wake_word_phrase: default_wake_word_phrase(),
+        wake_word_sensitivity: default_wake_word_sensitivity(),
         append_trailing_space: false,
+        typing_chars_per_second: default_typing_chars_per_second(),
+        restore_clipboard: default_restore_clipboard(),
+        clipboard_restore_delay_ms: default_clipboard_restore_delay_ms(),
+        markdown_rich_text: false,
+        smart_spacing: false,
+        streaming_injection: false,
+        network_max_attempts: default_network_max_attempts(),
+        action_timeout_secs: 0,
+        proxy_url: None,
+        custom_ca_path: None,
+        allow_link_actions: false,
+        control_api_enabled: false,
+        control_api_port: default_control_api_port(),
+        control_api_token: None,
+        websocket_enabled: false,
+        websocket_port: default_websocket_port(),
+        mqtt_enabled: false,
+        mqtt_host: default_mqtt_host(),
+        mqtt_port: default_mqtt_port(),
+        mqtt_username: None,
+        mqtt_password: None,
+        mqtt_topic_prefix: default_mqtt_topic_prefix(),
+        mqtt_publish_transcripts: false,
+        obs_enabled: false,
+        obs_host: default_obs_host(),
+        obs_port: default_obs_port(),
+        obs_password: None,
+        obs_text_source: default_obs_text_source(),
+        obs_clear_after_secs: default_obs_clear_after_secs(),
+        call_mute_enabled: false,
+        call_mute_teams_token: None,
+        hook_script_path: None,
+        conversation_context_enabled: false,
+        conversation_context_turns: default_conversation_context_turns(),
+        conversation_context_window_minutes: default_conversation_context_window_minutes(),
+        custom_model_prices: HashMap::new(),
+        monthly_llm_budget_usd: None,
+        post_process_service_tiers: HashMap::new(),
+        post_process_extra_body: HashMap::new(),
+        llama_cpp_model_path: None,
+        llama_cpp_gpu_layers: 0,
         // Online provider defaults
         use_online_provider: false,
         online_provider_id: default_online_provider_id(),
         online_provider_api_keys: default_online_provider_api_keys(),
+        online_provider_fallback_api_keys: HashMap::new(),
         online_provider_models: default_online_provider_models(),
         online_provider_custom_prompt: None,
         app_language: default_app_language(),
     }
 }
 
+/// `settings` with the fields of `section` back at their defaults and
+/// everything else kept. Credentials stay, and so do the shortcuts and
+/// configs of custom actions, which have no defaults to go back to.
+pub fn reset_section(settings: &AppSettings, section: &str) -> Result<AppSettings, String> {
+    let section = match serde_json::from_value(Value::String(section.to_string())) {
+        Ok(section) if section != settings_events::SettingsSection::Other => section,
+        _ => return Err(format!("Invalid settings section '{}'", section)),
+    };
+
+    let defaults = serde_json::to_value(get_default_settings()).map_err(|e| e.to_string())?;
+    let mut value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    let fields = defaults.as_object().into_iter().flatten();
+    for (field, default) in fields {
+        if settings_events::SettingsSection::of(field) != section
+            || crate::diagnostics::is_secret_field(field)
+        {
+            continue;
+        }
+        let mut reset = default.clone();
+        let current = value[field]
+            .as_object()
+            .filter(|_| field == "bindings" || field == "action_configs");
+        if let (Some(reset), Some(current)) = (reset.as_object_mut(), current) {
+            let custom = current
+                .iter()
+                .filter(|(id, _)| id.starts_with(crate::actions::CUSTOM_ACTION_PREFIX));
+            reset.extend(custom.map(|(id, entry)| (id.clone(), entry.clone())));
+        }
+        value[field] = reset;
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// `config` with the fields it leaves at their defaults taken from `base`
+fn merge_action_config(config: &ActionConfig, base: &ActionConfig) -> ActionConfig {
+    let defaults = serde_json::to_value(ActionConfig::default()).unwrap_or_default();
+    let mut merged = serde_json::to_value(base).unwrap_or_default();
+    if let (Ok(Value::Object(fields)), Some(merged_fields)) =
+        (serde_json::to_value(config), merged.as_object_mut())
+    {
+        for (name, value) in fields {
+            if value != defaults[name.as_str()] {
+                merged_fields.insert(name, value);
+            }
+        }
+    }
+    serde_json::from_value(merged).unwrap_or_else(|_| config.clone())
+}
+
 impl AppSettings {
+    /// Effective configuration of an action: the user's own, with what it
+    /// leaves unset taken from the built-in mode's
+    pub fn action_config(&self, action_id: &str) -> Option<ActionConfig> {
+        let builtin = crate::modes::builtin_mode(action_id).map(|mode| mode.action_config());
+        match (self.action_configs.get(action_id), builtin) {
+            (Some(config), Some(builtin)) => Some(merge_action_config(config, &builtin)),
+            (config, builtin) => config.cloned().or(builtin),
+        }
+    }
+
     pub fn active_post_process_provider(&self) -> Option<&PostProcessProvider> {
         self.post_process_providers
             .iter()
@@ -640,6 +1662,37 @@ impl AppSettings {
             .find(|provider| provider.id == provider_id)
     }
 
+    /// Main and fallback keys of an online transcription provider, in order
+    pub fn online_provider_keys(&self, provider_id: &str) -> Vec<String> {
+        let primary = self.online_provider_api_keys.get(provider_id).cloned();
+        let fallbacks = self
+            .online_provider_fallback_api_keys
+            .get(provider_id)
+            .cloned()
+            .unwrap_or_default();
+        crate::api_keys::dedup_keys(primary.into_iter().chain(fallbacks))
+    }
+
+    /// Service tier and extra body fields configured for a provider
+    pub fn provider_request_fields(
+        &self,
+        provider_id: &str,
+    ) -> (Option<String>, serde_json::Map<String, serde_json::Value>) {
+        let service_tier = self
+            .post_process_service_tiers
+            .get(provider_id)
+            .filter(|tier| !tier.trim().is_empty())
+            .cloned();
+        let extra_body = match self.post_process_extra_body.get(provider_id) {
+            Some(text) => crate::llm_types::parse_extra_body(text).unwrap_or_else(|e| {
+                warn!("Ignoring extra body of provider '{}': {}", provider_id, e);
+                serde_json::Map::new()
+            }),
+            None => serde_json::Map::new(),
+        };
+        (service_tier, extra_body)
+    }
+
     pub fn post_process_provider_mut(
         &mut self,
         provider_id: &str,
@@ -650,87 +1703,217 @@ impl AppSettings {
     }
 }
 
+/// The settings as last read from the store, so reading them on every
+/// shortcut press doesn't parse the store each time. Each change to the
+/// stored settings bumps the generation and drops them; a read that started
+/// before a change doesn't cache what it read.
+struct CachedSettings {
+    generation: u64,
+    settings: Option<AppSettings>,
+}
+
+static CACHE: Lazy<RwLock<CachedSettings>> = Lazy::new(|| {
+    RwLock::new(CachedSettings {
+        generation: 0,
+        settings: None,
+    })
+});
+
+/// Drop the cached settings; call after setting them in the store
+pub fn forget_cached() {
+    // A panic elsewhere can't leave the cache half-written
+    let mut cache = CACHE.write().unwrap_or_else(|e| e.into_inner());
+    cache.generation += 1;
+    cache.settings = None;
+}
+
+/// Write `settings` to the store, keeping the stored values of fields that
+/// are overridden from the environment
+fn store_settings<R: Runtime>(store: &Store<R>, settings: &AppSettings) {
+    let mut value = serde_json::to_value(settings).unwrap();
+    env_overrides::restore(&mut value, store.get("settings").as_ref());
+    store.set("settings", value);
+    forget_cached();
+}
+
+/// Fill in the post-processing defaults of settings read from the store,
+/// handing them to `store` when that changed them, and then apply the
+/// environment overrides with `apply_env`. The overrides come last: the
+/// defaults pass resets the endpoints of built-in providers, and they must
+/// not be stored.
+fn finish_loading(
+    mut settings: AppSettings,
+    store: impl FnOnce(&AppSettings),
+    apply_env: impl FnOnce(&mut Value),
+) -> AppSettings {
+    if ensure_post_process_defaults(&mut settings) {
+        store(&settings);
+    }
+
+    let mut value = match serde_json::to_value(&settings) {
+        Ok(value) => value,
+        Err(_) => return settings,
+    };
+    apply_env(&mut value);
+    serde_json::from_value(value).unwrap_or_else(|e| {
+        warn!("Ignoring the environment overrides: {}", e);
+        settings
+    })
+}
+
 pub fn load_or_create_app_settings(app: &AppHandle) -> AppSettings {
     // Initialize store
     let store = app
-        .store(SETTINGS_STORE_PATH)
+        .store(portable::store_path(SETTINGS_STORE_PATH))
         .expect("Failed to initialize store");
 
-    let mut settings = if let Some(settings_value) = store.get("settings") {
-        // Parse the entire settings object
-        match serde_json::from_value::<AppSettings>(settings_value) {
-            Ok(mut settings) => {
-                debug!("Found existing settings: {:?}", settings);
-                let default_settings = get_default_settings();
-                let mut updated = false;
-
-                // Merge default bindings into existing settings
-                for (key, value) in default_settings.bindings {
-                    if !settings.bindings.contains_key(&key) {
-                        debug!("Adding missing binding: {}", key);
-                        settings.bindings.insert(key, value);
-                        updated = true;
-                    }
-                }
-
-                if updated {
-                    debug!("Settings updated with new bindings");
-                    store.set("settings", serde_json::to_value(&settings).unwrap());
-                }
+    let settings = if let Some(mut settings_value) = store.get("settings") {
+        if migrate_settings(&mut settings_value) {
+            store.set("settings", settings_value.clone());
+            forget_cached();
+        }
 
-                settings
-            }
-            Err(e) => {
-                warn!("Failed to parse settings: {}", e);
-                // Fall back to default settings if parsing fails
-                let default_settings = get_default_settings();
-                store.set("settings", serde_json::to_value(&default_settings).unwrap());
-                default_settings
+        // Fields that don't parse fall back to their defaults one by one
+        let default_settings = get_default_settings();
+        let mut errors = settings_validation::unknown_keys(&settings_value, &default_settings);
+        let (mut settings, parse_errors) =
+            settings_validation::parse_lenient(&settings_value, &default_settings);
+        let mut updated = !errors.is_empty() || !parse_errors.is_empty();
+        if !parse_errors.is_empty() {
+            // Keep the rejected values so they can be recovered by hand
+            store.set("settings_backup", settings_value);
+            errors.extend(parse_errors);
+        }
+        debug!("Found existing settings: {:?}", settings);
+
+        // Merge default bindings into existing settings
+        for (key, value) in default_settings.bindings.clone() {
+            if !settings.bindings.contains_key(&key) {
+                debug!("Adding missing binding: {}", key);
+                settings.bindings.insert(key, value);
+                updated = true;
             }
         }
+
+        let invalid = settings_validation::sanitize(&mut settings, &default_settings);
+        updated |= !invalid.is_empty();
+        errors.extend(invalid);
+
+        if updated {
+            debug!("Settings updated on load");
+            store_settings(&store, &settings);
+        }
+        settings_validation::report(app, errors);
+
+        settings
     } else {
         let default_settings = get_default_settings();
-        store.set("settings", serde_json::to_value(&default_settings).unwrap());
+        store_settings(&store, &default_settings);
         default_settings
     };
 
-    if ensure_post_process_defaults(&mut settings) {
-        store.set("settings", serde_json::to_value(&settings).unwrap());
-    }
-
-    settings
+    finish_loading(
+        settings,
+        |settings| store_settings(&store, settings),
+        env_overrides::apply,
+    )
 }
 
 pub fn get_settings(app: &AppHandle) -> AppSettings {
+    let generation = {
+        let cache = CACHE.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(settings) = &cache.settings {
+            return settings.clone();
+        }
+        cache.generation
+    };
+
     let store = app
-        .store(SETTINGS_STORE_PATH)
+        .store(portable::store_path(SETTINGS_STORE_PATH))
         .expect("Failed to initialize store");
 
-    let mut settings = if let Some(settings_value) = store.get("settings") {
-        serde_json::from_value::<AppSettings>(settings_value).unwrap_or_else(|_| {
-            let default_settings = get_default_settings();
-            store.set("settings", serde_json::to_value(&default_settings).unwrap());
-            default_settings
-        })
+    let settings = if let Some(mut settings_value) = store.get("settings") {
+        if migrate_settings(&mut settings_value) {
+            store.set("settings", settings_value.clone());
+        }
+
+        let (settings, errors) =
+            settings_validation::parse_lenient(&settings_value, &get_default_settings());
+        if !errors.is_empty() {
+            store.set("settings_backup", settings_value);
+            store_settings(&store, &settings);
+            settings_validation::report(app, errors);
+        }
+        settings
     } else {
         let default_settings = get_default_settings();
-        store.set("settings", serde_json::to_value(&default_settings).unwrap());
+        store_settings(&store, &default_settings);
         default_settings
     };
 
-    if ensure_post_process_defaults(&mut settings) {
-        store.set("settings", serde_json::to_value(&settings).unwrap());
-    }
+    let settings = finish_loading(
+        settings,
+        |settings| store_settings(&store, settings),
+        env_overrides::apply,
+    );
 
+    let mut cache = CACHE.write().unwrap_or_else(|e| e.into_inner());
+    if cache.generation == generation {
+        cache.settings = Some(settings.clone());
+    }
     settings
 }
 
-pub fn write_settings(app: &AppHandle, settings: AppSettings) {
+/// Store `settings`. Fails with the fields that had to be reset or were
+/// rejected on the way; everything else is stored all the same.
+pub fn write_settings(app: &AppHandle, settings: AppSettings) -> Result<(), String> {
+    let previous = get_settings(app);
+    let errors = commit_settings(app, &previous, settings);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; "))
+}
+
+/// Store `settings` and let the subsystems subscribed to the sections that
+/// changed since `previous` reconfigure themselves. Returns the problems
+/// that weren't reported already.
+pub fn commit_settings(
+    app: &AppHandle,
+    previous: &AppSettings,
+    mut settings: AppSettings,
+) -> Vec<settings_validation::SettingsError> {
+    let store = app
+        .store(portable::store_path(SETTINGS_STORE_PATH))
+        .expect("Failed to initialize store");
+
+    let mut errors = settings_validation::sanitize(&mut settings, &get_default_settings());
+    store_settings(&store, &settings);
+    errors.extend(settings_events::publish(app, previous, &settings));
+    let reported = settings_validation::last_errors();
+    let new_errors = errors
+        .iter()
+        .filter(|error| !reported.contains(error))
+        .cloned()
+        .collect();
+    settings_validation::report(app, errors);
+    new_errors
+}
+
+/// Put `bindings` back into the stored settings without notifying the
+/// subscribers, for shortcuts that couldn't be registered
+pub fn restore_bindings(app: &AppHandle, bindings: HashMap<String, ShortcutBinding>) {
     let store = app
-        .store(SETTINGS_STORE_PATH)
+        .store(portable::store_path(SETTINGS_STORE_PATH))
         .expect("Failed to initialize store");
 
-    store.set("settings", serde_json::to_value(&settings).unwrap());
+    let mut settings = get_settings(app);
+    settings.bindings.extend(bindings);
+    store_settings(&store, &settings);
 }
 
 pub fn get_bindings(app: &AppHandle) -> HashMap<String, ShortcutBinding> {
@@ -747,12 +1930,114 @@ pub fn get_stored_binding(app: &AppHandle, id: &str) -> ShortcutBinding {
     binding
 }
 
-pub fn get_history_limit(app: &AppHandle) -> usize {
-    let settings = get_settings(app);
-    settings.history_limit
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_settings() {
+        let mut old = serde_json::json!({ "log_level": 2, "push_to_talk": true });
+        // Numbers no longer parse as a level, only the migration keeps them
+        assert!(serde_json::from_value::<LogLevel>(old["log_level"].clone()).is_err());
+        assert!(migrate_settings(&mut old));
+        assert_eq!(
+            serde_json::from_value::<LogLevel>(old["log_level"].clone()).unwrap(),
+            LogLevel::Debug
+        );
+        assert_eq!(old["push_to_talk"], true);
+        assert_eq!(old["schema_version"], SETTINGS_SCHEMA_VERSION);
 
-pub fn get_recording_retention_period(app: &AppHandle) -> RecordingRetentionPeriod {
-    let settings = get_settings(app);
-    settings.recording_retention_period
+        // Already current
+        assert!(!migrate_settings(&mut old));
+
+        // Newer than this build
+        let mut newer = serde_json::json!({ "schema_version": SETTINGS_SCHEMA_VERSION + 1 });
+        assert!(!migrate_settings(&mut newer));
+    }
+
+    #[test]
+    fn test_env_overrides_survive_the_defaults() {
+        let env: HashMap<String, String> = [
+            ("BABBL_OPENAI_BASE_URL", "https://proxy.example.com/v1"),
+            ("BABBL_LLM_PROVIDER", "groq"),
+            ("BABBL_LLM_BASE_URL", "http://localhost:8080/v1"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let mut stored = 0;
+        let settings = finish_loading(
+            get_default_settings(),
+            |_| stored += 1,
+            |value| env_overrides::apply_with(value, &env),
+        );
+
+        let base_url = |id: &str| settings.post_process_provider(id).unwrap().base_url.clone();
+        assert_eq!(base_url("openai"), "https://proxy.example.com/v1");
+        assert_eq!(base_url("groq"), "http://localhost:8080/v1");
+        assert_eq!(settings.post_process_provider_id, "groq");
+        // Complete settings aren't written back on every read
+        assert_eq!(stored, 0);
+    }
+
+    #[test]
+    fn test_reset_section() {
+        let defaults = get_default_settings();
+        let mut settings = defaults.clone();
+        settings.post_process_enabled = !defaults.post_process_enabled;
+        settings
+            .post_process_api_keys
+            .insert("openai".to_string(), "sk-test".to_string());
+        settings.audio_feedback = !defaults.audio_feedback;
+
+        let reset = reset_section(&settings, "providers").unwrap();
+        assert_eq!(reset.post_process_enabled, defaults.post_process_enabled);
+        assert_eq!(reset.post_process_api_keys["openai"], "sk-test");
+        assert_eq!(reset.audio_feedback, settings.audio_feedback);
+
+        let mut custom = settings.bindings["transcribe"].clone();
+        custom.id = "custom_email".to_string();
+        settings.bindings.insert(custom.id.clone(), custom);
+        settings
+            .bindings
+            .get_mut("transcribe")
+            .unwrap()
+            .current_binding = "ctrl+f9".to_string();
+        settings
+            .action_configs
+            .insert("custom_email".to_string(), ActionConfig::default());
+        settings.push_to_talk = !defaults.push_to_talk;
+        let reset = reset_section(&settings, "shortcuts").unwrap();
+        assert_eq!(
+            reset.bindings["transcribe"].current_binding,
+            defaults.bindings["transcribe"].current_binding
+        );
+        assert!(reset.bindings.contains_key("custom_email"));
+        assert_eq!(reset.push_to_talk, defaults.push_to_talk);
+        let reset = reset_section(&settings, "providers").unwrap();
+        assert!(reset.action_configs.contains_key("custom_email"));
+
+        assert!(reset_section(&settings, "other").is_err());
+        assert!(reset_section(&settings, "everything").is_err());
+    }
+
+    #[test]
+    fn test_action_config_over_builtin_mode() {
+        let mut settings = get_default_settings();
+        settings.action_configs.insert(
+            "mode_summarize".to_string(),
+            ActionConfig {
+                temperature: Some(0.2),
+                ..ActionConfig::default()
+            },
+        );
+
+        let builtin = crate::modes::builtin_mode("mode_summarize")
+            .unwrap()
+            .action_config();
+        let config = settings.action_config("mode_summarize").unwrap();
+        assert_eq!(config.temperature, Some(0.2));
+        assert_eq!(config.prompt_template, builtin.prompt_template);
+        assert_eq!(config.system_prompt, builtin.system_prompt);
+    }
 }
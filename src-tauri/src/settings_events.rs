@@ -0,0 +1,195 @@
+use crate::settings::AppSettings;
+use crate::settings_validation::SettingsError;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// Group of settings that a subsystem reconfigures itself from, and that is
+/// reset to its defaults as a whole
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsSection {
+    Shortcuts,
+    Audio,
+    Providers,
+    Output,
+    Overlay,
+    History,
+    Conversation,
+    Profiles,
+    System,
+    Other,
+}
+
+impl SettingsSection {
+    /// Section of a top-level settings field
+    pub fn of(field: &str) -> Self {
+        match field {
+            "bindings" | "push_to_talk" => Self::Shortcuts,
+            "always_on_microphone"
+            | "selected_microphone"
+            | "clamshell_microphone"
+            | "selected_output_device"
+            | "audio_feedback"
+            | "audio_feedback_volume"
+            | "sound_theme"
+            | "mute_while_recording"
+            | "recording_memory_limit_mb" => Self::Audio,
+            field if field.starts_with("wake_word_") => Self::Audio,
+            "selected_model"
+            | "model_unload_timeout"
+            | "warm_up"
+            | "use_online_provider"
+            | "gemini_safety_threshold"
+            | "proxy_url"
+            | "custom_ca_path"
+            | "network_max_attempts"
+            | "action_configs"
+            | "translate_target_language"
+            | "long_transcript_strategy" => Self::Providers,
+            field
+                if field.starts_with("post_process_")
+                    || field.starts_with("online_provider_")
+                    || field.starts_with("llama_cpp_") =>
+            {
+                Self::Providers
+            }
+            "paste_method"
+            | "clipboard_handling"
+            | "undo_method"
+            | "output_mode"
+            | "note_file_path"
+            | "append_trailing_space"
+            | "typing_chars_per_second"
+            | "restore_clipboard"
+            | "clipboard_restore_delay_ms"
+            | "markdown_rich_text"
+            | "smart_spacing"
+            | "streaming_injection" => Self::Output,
+            "overlay_position"
+            | "overlay_click_through"
+            | "overlay_display_anchors"
+            | "widget_enabled"
+            | "widget_position" => Self::Overlay,
+            "history_limit"
+            | "recording_retention_period"
+            | "history_retention_days"
+            | "history_enabled"
+            | "purge_audio_only"
+            | "recording_storage_limit_mb"
+            | "history_encryption_enabled" => Self::History,
+            field if field.starts_with("meeting_") => Self::History,
+            field if field.starts_with("conversation_context_") => Self::Conversation,
+            field if field.starts_with("mqtt_") => Self::System,
+            field if field.starts_with("obs_") => Self::System,
+            field if field.starts_with("call_mute_") => Self::System,
+            "profiles" | "active_profile_id" => Self::Profiles,
+            "autostart_enabled"
+            | "autostart_minimized"
+            | "autostart_delay_secs"
+            | "low_power_mode"
+            | "low_power_model"
+            | "start_hidden"
+            | "update_checks_enabled"
+            | "update_channel"
+            | "debug_mode"
+            | "log_level"
+            | "log_module_levels"
+            | "notify_transcription_complete"
+            | "notify_provider_errors"
+            | "notify_permission_problems"
+            | "app_language"
+            | "allow_link_actions"
+            | "control_api_enabled"
+            | "control_api_port"
+            | "control_api_token"
+            | "websocket_enabled"
+            | "websocket_port" => Self::System,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Payload of the `settings-changed` event
+#[derive(Serialize, Debug, Clone)]
+pub struct SettingsChanged {
+    pub section: SettingsSection,
+}
+
+/// Called with the settings before and after a save, once they're stored.
+/// Returns the fields it had to reject.
+pub type Subscriber = fn(&AppHandle, &AppSettings, &AppSettings) -> Vec<SettingsError>;
+
+static SUBSCRIBERS: Mutex<Vec<(SettingsSection, Subscriber)>> = Mutex::new(Vec::new());
+
+/// Call `subscriber` after every save that changes a setting in `section`
+pub fn subscribe(section: SettingsSection, subscriber: Subscriber) {
+    SUBSCRIBERS.lock().unwrap().push((section, subscriber));
+}
+
+/// Sections with a field that differs between `previous` and `next`, in
+/// declaration order
+pub fn changed_sections(previous: &AppSettings, next: &AppSettings) -> Vec<SettingsSection> {
+    let (previous, next) = match (serde_json::to_value(previous), serde_json::to_value(next)) {
+        (Ok(Value::Object(previous)), Ok(Value::Object(next))) => (previous, next),
+        _ => return Vec::new(),
+    };
+    let mut sections: Vec<SettingsSection> = next
+        .iter()
+        .filter(|(field, value)| previous.get(*field) != Some(*value))
+        .map(|(field, _)| SettingsSection::of(field))
+        .collect();
+    sections.sort();
+    sections.dedup();
+    sections
+}
+
+/// Run the subscribers of the sections that changed, then tell the frontend
+pub fn publish(app: &AppHandle, previous: &AppSettings, next: &AppSettings) -> Vec<SettingsError> {
+    let sections = changed_sections(previous, next);
+    // Copied out so a subscriber can save settings itself
+    let subscribers = SUBSCRIBERS.lock().unwrap().clone();
+
+    let mut errors = Vec::new();
+    for section in sections {
+        debug!("Settings changed: {:?}", section);
+        for (_, subscriber) in subscribers.iter().filter(|(s, _)| *s == section) {
+            errors.extend(subscriber(app, previous, next));
+        }
+        if let Err(e) = app.emit("settings-changed", SettingsChanged { section }) {
+            error!("Failed to emit settings-changed: {}", e);
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::get_default_settings;
+
+    #[test]
+    fn test_changed_sections() {
+        let previous = get_default_settings();
+        assert!(changed_sections(&previous, &previous).is_empty());
+
+        let mut next = previous.clone();
+        next.post_process_enabled = !next.post_process_enabled;
+        next.always_on_microphone = !next.always_on_microphone;
+        next.history_limit += 1;
+        next.push_to_talk = !next.push_to_talk;
+        next.schema_version += 1;
+        assert_eq!(
+            changed_sections(&previous, &next),
+            [
+                SettingsSection::Shortcuts,
+                SettingsSection::Audio,
+                SettingsSection::Providers,
+                SettingsSection::History,
+                SettingsSection::Other
+            ]
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use crate::cancellation::MAX_ACTION_TIMEOUT_SECS;
+use crate::settings::AppSettings;
+use crate::shortcut;
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use specta::Type;
+use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// A settings field that was rejected or needs attention
+#[derive(Serialize, Debug, Clone, PartialEq, Type)]
+pub struct SettingsError {
+    /// Dotted path of the field, e.g. `bindings.transcribe.current_binding`
+    pub path: String,
+    pub message: String,
+}
+
+impl SettingsError {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Problems found by the last validation, for windows opened after the event
+static LAST_ERRORS: Lazy<Mutex<Vec<SettingsError>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Keys of the stored settings object that no setting reads
+pub fn unknown_keys(value: &Value, defaults: &AppSettings) -> Vec<SettingsError> {
+    let known = serde_json::to_value(defaults).unwrap_or_default();
+    let (stored, known) = match (value.as_object(), known.as_object()) {
+        (Some(stored), Some(known)) => (stored, known),
+        _ => return Vec::new(),
+    };
+    stored
+        .keys()
+        .filter(|key| !known.contains_key(*key))
+        .map(|key| SettingsError::new(key.clone(), "Unknown setting, ignored"))
+        .collect()
+}
+
+/// Parse stored settings, replacing fields that don't parse with their
+/// defaults instead of discarding the whole file
+pub fn parse_lenient(value: &Value, defaults: &AppSettings) -> (AppSettings, Vec<SettingsError>) {
+    if let Ok(settings) = serde_json::from_value::<AppSettings>(value.clone()) {
+        return (settings, Vec::new());
+    }
+
+    let stored = match value.as_object() {
+        Some(stored) => stored,
+        None => {
+            let error = SettingsError::new("", "Settings are not a JSON object, using defaults");
+            return (defaults.clone(), vec![error]);
+        }
+    };
+    let mut merged = match serde_json::to_value(defaults) {
+        Ok(Value::Object(merged)) => merged,
+        _ => return (defaults.clone(), Vec::new()),
+    };
+
+    // Take the stored fields one by one, keeping the default where one fails
+    let mut errors = Vec::new();
+    for (key, field) in stored {
+        if !merged.contains_key(key) {
+            continue;
+        }
+        let mut candidate = merged.clone();
+        candidate.insert(key.clone(), field.clone());
+        match serde_json::from_value::<AppSettings>(Value::Object(candidate)) {
+            Ok(_) => {
+                merged.insert(key.clone(), field.clone());
+            }
+            Err(e) => errors.push(SettingsError::new(
+                key.clone(),
+                format!("{}; reset to the default", e),
+            )),
+        }
+    }
+
+    match serde_json::from_value::<AppSettings>(Value::Object(merged)) {
+        Ok(settings) => (settings, errors),
+        Err(e) => {
+            errors.push(SettingsError::new("", format!("{}; using defaults", e)));
+            (defaults.clone(), errors)
+        }
+    }
+}
+
+fn check_range<T: PartialOrd + Copy + Display>(
+    errors: &mut Vec<SettingsError>,
+    path: &str,
+    value: &mut T,
+    range: RangeInclusive<T>,
+    default: T,
+) {
+    if !range.contains(value) {
+        errors.push(SettingsError::new(
+            path,
+            format!(
+                "{} is outside {}..={}; reset to {}",
+                value,
+                range.start(),
+                range.end(),
+                default
+            ),
+        ));
+        *value = default;
+    }
+}
+
+/// Reset out-of-range values and unusable shortcuts to their defaults, and
+/// report settings that leave a feature unable to run
+pub fn sanitize(settings: &mut AppSettings, defaults: &AppSettings) -> Vec<SettingsError> {
+    let mut errors = Vec::new();
+
+    check_range(
+        &mut errors,
+        "audio_feedback_volume",
+        &mut settings.audio_feedback_volume,
+        0.0..=1.0,
+        defaults.audio_feedback_volume,
+    );
+    check_range(
+        &mut errors,
+        "wake_word_sensitivity",
+        &mut settings.wake_word_sensitivity,
+        0.0..=1.0,
+        defaults.wake_word_sensitivity,
+    );
+    if crate::snippets::trigger_words(&settings.wake_word_phrase).is_empty() {
+        errors.push(SettingsError::new(
+            "wake_word_phrase",
+            format!(
+                "Wake phrase must have a word; reset to '{}'",
+                defaults.wake_word_phrase
+            ),
+        ));
+        settings.wake_word_phrase = defaults.wake_word_phrase.clone();
+    }
+    check_range(
+        &mut errors,
+        "word_correction_threshold",
+        &mut settings.word_correction_threshold,
+        0.0..=1.0,
+        defaults.word_correction_threshold,
+    );
+    check_range(
+        &mut errors,
+        "typing_chars_per_second",
+        &mut settings.typing_chars_per_second,
+        1..=1000,
+        defaults.typing_chars_per_second,
+    );
+    check_range(
+        &mut errors,
+        "clipboard_restore_delay_ms",
+        &mut settings.clipboard_restore_delay_ms,
+        50..=5000,
+        defaults.clipboard_restore_delay_ms,
+    );
+    check_range(
+        &mut errors,
+        "recording_memory_limit_mb",
+        &mut settings.recording_memory_limit_mb,
+        8..=4096,
+        defaults.recording_memory_limit_mb,
+    );
+    check_range(
+        &mut errors,
+        "autostart_delay_secs",
+        &mut settings.autostart_delay_secs,
+        0..=crate::autostart::MAX_DELAY_SECS,
+        defaults.autostart_delay_secs,
+    );
+    check_range(
+        &mut errors,
+        "network_max_attempts",
+        &mut settings.network_max_attempts,
+        1..=10,
+        defaults.network_max_attempts,
+    );
+    check_range(
+        &mut errors,
+        "action_timeout_secs",
+        &mut settings.action_timeout_secs,
+        0..=MAX_ACTION_TIMEOUT_SECS,
+        defaults.action_timeout_secs,
+    );
+    check_range(
+        &mut errors,
+        "conversation_context_turns",
+        &mut settings.conversation_context_turns,
+        1..=10,
+        defaults.conversation_context_turns,
+    );
+    check_range(
+        &mut errors,
+        "conversation_context_window_minutes",
+        &mut settings.conversation_context_window_minutes,
+        1..=120,
+        defaults.conversation_context_window_minutes,
+    );
+    check_range(
+        &mut errors,
+        "control_api_port",
+        &mut settings.control_api_port,
+        1024..=65535,
+        defaults.control_api_port,
+    );
+    check_range(
+        &mut errors,
+        "websocket_port",
+        &mut settings.websocket_port,
+        1024..=65535,
+        defaults.websocket_port,
+    );
+    check_range(
+        &mut errors,
+        "mqtt_port",
+        &mut settings.mqtt_port,
+        1..=65535,
+        defaults.mqtt_port,
+    );
+    if settings
+        .mqtt_topic_prefix
+        .trim_matches('/')
+        .trim()
+        .is_empty()
+    {
+        errors.push(SettingsError::new(
+            "mqtt_topic_prefix",
+            format!(
+                "Topic prefix must not be empty; reset to '{}'",
+                defaults.mqtt_topic_prefix
+            ),
+        ));
+        settings.mqtt_topic_prefix = defaults.mqtt_topic_prefix.clone();
+    }
+    check_range(
+        &mut errors,
+        "obs_port",
+        &mut settings.obs_port,
+        1..=65535,
+        defaults.obs_port,
+    );
+    check_range(
+        &mut errors,
+        "obs_clear_after_secs",
+        &mut settings.obs_clear_after_secs,
+        0..=600,
+        defaults.obs_clear_after_secs,
+    );
+    check_range(
+        &mut errors,
+        "history_retention_days",
+        &mut settings.history_retention_days,
+        1..=3650,
+        defaults.history_retention_days,
+    );
+    if settings.recording_storage_limit_mb == Some(0) {
+        errors.push(SettingsError::new(
+            "recording_storage_limit_mb",
+            "Storage limit must be greater than 0; removed",
+        ));
+        settings.recording_storage_limit_mb = None;
+    }
+    if settings
+        .monthly_llm_budget_usd
+        .is_some_and(|budget| budget.is_nan() || budget <= 0.0)
+    {
+        errors.push(SettingsError::new(
+            "monthly_llm_budget_usd",
+            "Budget must be greater than 0; removed",
+        ));
+        settings.monthly_llm_budget_usd = None;
+    }
+
+    for (id, binding) in settings.bindings.iter_mut() {
+        if binding.current_binding.is_empty() {
+            continue;
+        }
+        if let Err(e) = shortcut::check_shortcut_string(&binding.current_binding) {
+            let default = defaults
+                .bindings
+                .get(id)
+                .map(|default| default.default_binding.clone())
+                .unwrap_or_default();
+            errors.push(SettingsError::new(
+                format!("bindings.{}.current_binding", id),
+                format!("{}; reset to '{}'", e, default),
+            ));
+            binding.current_binding = default;
+        }
+    }
+
+    // Reported only; the user has to pick a model
+    if settings.post_process_enabled {
+        let provider_id = &settings.post_process_provider_id;
+        let has_model = settings
+            .post_process_models
+            .get(provider_id)
+            .is_some_and(|model| !model.trim().is_empty());
+        if !has_model {
+            errors.push(SettingsError::new(
+                format!("post_process_models.{}", provider_id),
+                "Post-processing is enabled but no model is selected",
+            ));
+        }
+    }
+    if settings.use_online_provider {
+        let provider_id = &settings.online_provider_id;
+        let has_model = settings
+            .online_provider_models
+            .get(provider_id)
+            .is_some_and(|model| !model.trim().is_empty());
+        if !has_model {
+            errors.push(SettingsError::new(
+                format!("online_provider_models.{}", provider_id),
+                "Online transcription is enabled but no model is selected",
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Log the problems and tell the frontend which fields were rejected. An
+/// empty list is only sent when it clears earlier problems.
+pub fn report(app: &AppHandle, errors: Vec<SettingsError>) {
+    let mut last = LAST_ERRORS.lock().unwrap();
+    if *last == errors {
+        return;
+    }
+    for error in &errors {
+        warn!("Invalid setting '{}': {}", error.path, error.message);
+    }
+    if let Err(e) = app.emit("settings-invalid", &errors) {
+        warn!("Failed to emit settings-invalid: {}", e);
+    }
+    *last = errors;
+}
+
+pub fn last_errors() -> Vec<SettingsError> {
+    LAST_ERRORS.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::get_default_settings;
+
+    #[test]
+    fn test_parse_lenient() {
+        let defaults = get_default_settings();
+        let mut value = serde_json::to_value(&defaults).unwrap();
+        value["push_to_talk"] = Value::from(!defaults.push_to_talk);
+        value["history_limit"] = Value::from("lots");
+        value["no_such_setting"] = Value::from(1);
+
+        let (settings, errors) = parse_lenient(&value, &defaults);
+        assert_eq!(settings.push_to_talk, !defaults.push_to_talk);
+        assert_eq!(settings.history_limit, defaults.history_limit);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "history_limit");
+
+        let unknown = unknown_keys(&value, &defaults);
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].path, "no_such_setting");
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let defaults = get_default_settings();
+        let mut settings = defaults.clone();
+        settings.audio_feedback_volume = 3.0;
+        settings.network_max_attempts = 0;
+        settings.post_process_enabled = false;
+        settings.use_online_provider = false;
+
+        let errors = sanitize(&mut settings, &defaults);
+        let paths: Vec<&str> = errors.iter().map(|error| error.path.as_str()).collect();
+        assert_eq!(paths, ["audio_feedback_volume", "network_max_attempts"]);
+        assert_eq!(
+            settings.audio_feedback_volume,
+            defaults.audio_feedback_volume
+        );
+        assert_eq!(settings.network_max_attempts, defaults.network_max_attempts);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::portable;
+use crate::settings::{self, SETTINGS_STORE_PATH};
+use log::{debug, error, info, warn};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+/// Editors write a file in several steps; wait for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Keeps the watcher alive for the lifetime of the app
+pub struct SettingsWatcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+/// Settings as last read from the file, so our own saves and stale events
+/// aren't mistaken for external edits
+static LAST_FILE_SETTINGS: Mutex<Option<Value>> = Mutex::new(None);
+
+fn read_file_settings(path: &Path) -> Option<Value> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<Value>(&contents) {
+        Ok(mut file) => file.get_mut("settings").map(Value::take),
+        Err(e) => {
+            // Most likely saved halfway; the next event brings the rest
+            debug!("Settings file isn't valid JSON yet: {}", e);
+            None
+        }
+    }
+}
+
+/// Watch the settings file and apply edits made outside the app (by hand
+/// or by a dotfile sync) without a restart
+pub fn start(app: &AppHandle) {
+    let path: PathBuf = match portable::app_data_dir(app) {
+        Ok(dir) => dir.join(SETTINGS_STORE_PATH),
+        Err(e) => {
+            warn!("Not watching settings, no app data directory: {}", e);
+            return;
+        }
+    };
+    let dir = match path.parent() {
+        Some(dir) => dir.to_path_buf(),
+        None => return,
+    };
+    *LAST_FILE_SETTINGS.lock().unwrap() = read_file_settings(&path);
+
+    let app_handle = app.clone();
+    let watched = path.clone();
+    let debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+        Ok(events) => {
+            if events
+                .iter()
+                .any(|event| event.path.file_name() == watched.file_name())
+            {
+                reload(&app_handle, &watched);
+            }
+        }
+        Err(e) => warn!("Settings watcher error: {}", e),
+    });
+    let mut debouncer = match debouncer {
+        Ok(debouncer) => debouncer,
+        Err(e) => {
+            warn!("Failed to create settings watcher: {}", e);
+            return;
+        }
+    };
+    // Watch the directory; editors often replace the file instead of writing into it
+    if let Err(e) = debouncer.watcher().watch(&dir, RecursiveMode::NonRecursive) {
+        warn!("Failed to watch {:?}: {}", dir, e);
+        return;
+    }
+    debug!("Watching {:?} for settings changes", path);
+    app.manage(SettingsWatcher {
+        _debouncer: debouncer,
+    });
+}
+
+fn reload(app: &AppHandle, path: &Path) {
+    let file_settings = match read_file_settings(path) {
+        Some(file_settings) => file_settings,
+        None => return,
+    };
+    let store = match app.store(portable::store_path(SETTINGS_STORE_PATH)) {
+        Ok(store) => store,
+        Err(e) => {
+            error!("Failed to open settings store: {}", e);
+            return;
+        }
+    };
+
+    {
+        let mut last = LAST_FILE_SETTINGS.lock().unwrap();
+        let unchanged = last.as_ref() == Some(&file_settings);
+        // Our own save, or one of ours that's still pending made it stale
+        let ours = store.get("settings").as_ref() == Some(&file_settings);
+        *last = Some(file_settings.clone());
+        if unchanged || ours {
+            return;
+        }
+    }
+
+    info!("Settings file changed outside the app, applying it");
+    let previous = settings::get_settings(app);
+    store.set("settings", file_settings);
+    settings::forget_cached();
+    // Saved again through the usual path so subsystems pick up the changes
+    let next = settings::get_settings(app);
+    settings::commit_settings(app, &previous, next);
+}
@@ -0,0 +1,124 @@
+use crate::settings::ShellCommand;
+use log::debug;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Longest part of stderr quoted in an error
+const STDERR_CHARS: usize = 300;
+
+/// Longest time a command may run, whatever its settings say
+pub const MAX_TIMEOUT_SECS: u64 = 600;
+
+fn shell(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// What a command printed, without the trailing newline most commands add
+fn clean_output(stdout: &[u8]) -> String {
+    String::from_utf8_lossy(stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string()
+}
+
+/// Pipe `text` to `command` and return what it printed on stdout. Fails when
+/// it can't be started, exits with an error or runs out of time.
+pub async fn run(
+    command: &ShellCommand,
+    action_id: &str,
+    transcription: &str,
+    text: &str,
+) -> Result<String, String> {
+    let mut shell = shell(&command.command);
+    shell
+        .envs(&command.env)
+        .env("BABBL_ACTION_ID", action_id)
+        .env("BABBL_TRANSCRIPTION", transcription)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(dir) = command.working_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        shell.current_dir(dir);
+    }
+
+    let mut child = shell
+        .spawn()
+        .map_err(|e| format!("Failed to start '{}': {}", command.command, e))?;
+    // Written alongside reading the output, as a command may fill the stdout
+    // pipe before it has read all of its input
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = text.as_bytes().to_vec();
+        tauri::async_runtime::spawn(async move {
+            // Commands that don't read their input close the pipe early
+            if let Err(e) = stdin.write_all(&input).await {
+                debug!("Command didn't take its input: {}", e);
+            }
+        });
+    }
+    let finished = child.wait_with_output();
+
+    // Dropping the child on a timeout kills it
+    let timeout = Duration::from_secs(command.timeout_secs.clamp(1, MAX_TIMEOUT_SECS));
+    let output = tokio::time::timeout(timeout, finished)
+        .await
+        .map_err(|_| {
+            format!(
+                "'{}' didn't finish within {} seconds",
+                command.command,
+                timeout.as_secs()
+            )
+        })?
+        .map_err(|e| format!("Failed to run '{}': {}", command.command, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr: String = stderr.trim().chars().take(STDERR_CHARS).collect();
+        return Err(format!(
+            "'{}' failed ({}): {}",
+            command.command, output.status, stderr
+        ));
+    }
+    Ok(clean_output(&output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_output() {
+        assert_eq!(clean_output(b"done\n"), "done");
+        assert_eq!(clean_output(b"two\nlines\r\n\r\n"), "two\nlines");
+        assert_eq!(clean_output(b"  indented"), "  indented");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_run_large_input() {
+        let command = ShellCommand {
+            command: "cat".to_string(),
+            working_dir: None,
+            env: Default::default(),
+            timeout_secs: 10,
+            capture_output: true,
+        };
+        // Far more than a pipe holds
+        let text = "dictated words ".repeat(100_000);
+        let output =
+            tauri::async_runtime::block_on(run(&command, "test", "dictated", &text)).unwrap();
+        assert_eq!(output, text);
+    }
+}
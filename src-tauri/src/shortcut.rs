@@ -1,21 +1,37 @@
-use log::{error, warn};
+use log::{debug, error, warn};
+use once_cell::sync::Lazy;
 use serde::Serialize;
 use specta::Type;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager};
-use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
-use crate::actions::ACTION_MAP;
+use crate::actions::{action_for, admit};
+use crate::cancellation::MAX_ACTION_TIMEOUT_SECS;
 use crate::input_hook;
 use crate::managers::audio::AudioRecordingManager;
+use crate::notifications::NotificationKind;
 use crate::settings::ShortcutBinding;
 use crate::settings::{
-    self, get_settings, ClipboardHandling, LLMPrompt, OverlayPosition, PasteMethod, SoundTheme,
-    APPLE_INTELLIGENCE_PROVIDER_ID,
+    self, get_settings, AppSettings, ClipboardHandling, GeminiSafetyThreshold, LLMPrompt,
+    LongTranscriptStrategy, OutputMode, OverlayAnchor, OverlayPosition, PasteMethod, SoundTheme,
+    UndoMethod, UpdateChannel, APPLE_INTELLIGENCE_PROVIDER_ID, LLAMA_CPP_PROVIDER_ID,
 };
+use crate::settings_validation::SettingsError;
+use crate::throttle;
 use crate::ManagedToggleState;
 
+/// Shortcut registered for each binding id
+static REGISTERED: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Bindings unregistered while the user edits them
+static SUSPENDED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// All shortcuts ignored until resumed; not persisted, so a restart resumes them
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
 pub fn init_shortcuts(app: &AppHandle) {
     let default_bindings = settings::get_default_settings().bindings;
     let user_settings = settings::load_or_create_app_settings(app);
@@ -31,6 +47,11 @@ pub fn init_shortcuts(app: &AppHandle) {
             .cloned()
             .unwrap_or(default_binding);
 
+        // Unbound actions (e.g. built-in modes) have nothing to register
+        if binding.current_binding.is_empty() {
+            continue;
+        }
+
         if let Err(e) = register_shortcut(app, binding) {
             error!("Failed to register shortcut {} during init: {}", id, e);
         }
@@ -66,54 +87,32 @@ pub fn change_binding(
             });
         }
     };
-    // If this is the cancel binding, just update the settings and return
-    // It's managed dynamically, so we don't register/unregister here
-    if id == "cancel" {
-        if let Some(mut b) = settings.bindings.get(&id).cloned() {
-            b.current_binding = binding;
-            settings.bindings.insert(id.clone(), b.clone());
-            settings::write_settings(&app, settings);
-            return Ok(BindingResponse {
-                success: true,
-                binding: Some(b.clone()),
-                error: None,
-            });
+    // The cancel binding is registered while recording only, and an empty
+    // binding leaves the action unbound (the default for built-in modes)
+    if id != "cancel" && !binding.is_empty() {
+        if let Err(e) = validate_shortcut_string(&binding) {
+            warn!("change_binding validation error: {}", e);
+            return Err(e);
         }
     }
 
-    // Unregister the existing binding
-    if let Err(e) = unregister_shortcut(&app, binding_to_modify.clone()) {
-        let error_msg = format!("Failed to unregister shortcut: {}", e);
-        error!("change_binding error: {}", error_msg);
-    }
-
-    // Validate the new shortcut before we touch the current registration
-    if let Err(e) = validate_shortcut_string(&binding) {
-        warn!("change_binding validation error: {}", e);
-        return Err(e);
-    }
-
     // Create an updated binding
     let mut updated_binding = binding_to_modify;
     updated_binding.current_binding = binding;
+    settings
+        .bindings
+        .insert(id.clone(), updated_binding.clone());
 
-    // Register the new binding
-    if let Err(e) = register_shortcut(&app, updated_binding.clone()) {
-        let error_msg = format!("Failed to register shortcut: {}", e);
-        error!("change_binding error: {}", error_msg);
+    // Saving registers the shortcut; one that can't be registered is put back
+    if let Err(e) = settings::write_settings(&app, settings) {
+        error!("change_binding error: {}", e);
         return Ok(BindingResponse {
             success: false,
             binding: None,
-            error: Some(error_msg),
+            error: Some(e),
         });
     }
 
-    // Update the binding in the settings
-    settings.bindings.insert(id, updated_binding.clone());
-
-    // Save the settings
-    settings::write_settings(&app, settings);
-
     // Return the updated binding
     Ok(BindingResponse {
         success: true,
@@ -139,9 +138,7 @@ pub fn change_ptt_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     // cancel any ongoing recordings or actions
     settings.push_to_talk = enabled;
 
-    settings::write_settings(&app, settings);
-
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -149,8 +146,7 @@ pub fn change_ptt_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
 pub fn change_audio_feedback_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.audio_feedback = enabled;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -158,8 +154,7 @@ pub fn change_audio_feedback_setting(app: AppHandle, enabled: bool) -> Result<()
 pub fn change_audio_feedback_volume_setting(app: AppHandle, volume: f32) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.audio_feedback_volume = volume;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -176,8 +171,7 @@ pub fn change_sound_theme_setting(app: AppHandle, theme: String) -> Result<(), S
         }
     };
     settings.sound_theme = parsed;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -185,8 +179,7 @@ pub fn change_sound_theme_setting(app: AppHandle, theme: String) -> Result<(), S
 pub fn change_translate_to_english_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.translate_to_english = enabled;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -194,8 +187,7 @@ pub fn change_translate_to_english_setting(app: AppHandle, enabled: bool) -> Res
 pub fn change_selected_language_setting(app: AppHandle, language: String) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.selected_language = language;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -206,18 +198,234 @@ pub fn change_overlay_position_setting(app: AppHandle, position: String) -> Resu
         "none" => OverlayPosition::None,
         "top" => OverlayPosition::Top,
         "bottom" => OverlayPosition::Bottom,
+        "cursor" => OverlayPosition::Cursor,
         other => {
             warn!("Invalid overlay position '{}', defaulting to bottom", other);
             OverlayPosition::Bottom
         }
     };
     settings.overlay_position = parsed;
-    settings::write_settings(&app, settings);
+    settings::write_settings(&app, settings)
+}
 
-    // Update overlay position without recreating window
-    crate::utils::update_overlay_position(&app);
+/// Put the overlay at `anchor` on `display`, or back at `overlay_position`
+/// without one
+#[tauri::command]
+#[specta::specta]
+pub fn change_overlay_display_anchor_setting(
+    app: AppHandle,
+    display: String,
+    anchor: Option<OverlayAnchor>,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    match anchor {
+        Some(anchor) => settings.overlay_display_anchors.insert(display, anchor),
+        None => settings.overlay_display_anchors.remove(&display),
+    };
+    settings::write_settings(&app, settings)
+}
 
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub fn change_overlay_click_through_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.overlay_click_through = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_widget_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.widget_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_allow_link_actions_setting(app: AppHandle, allowed: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.allow_link_actions = allowed;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_control_api_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.control_api_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_control_api_port_setting(app: AppHandle, port: u16) -> Result<(), String> {
+    if port < 1024 {
+        return Err("The control API needs a port from 1024 up".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.control_api_port = port;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_websocket_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.websocket_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_websocket_port_setting(app: AppHandle, port: u16) -> Result<(), String> {
+    if port < 1024 {
+        return Err("The WebSocket server needs a port from 1024 up".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.websocket_port = port;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_hook_script_path_setting(app: AppHandle, path: Option<String>) -> Result<(), String> {
+    let path = path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    if let Some(path) = &path {
+        crate::scripting::load(path)?;
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.hook_script_path = path;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_mqtt_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.mqtt_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_mqtt_broker_setting(
+    app: AppHandle,
+    host: String,
+    port: u16,
+    username: Option<String>,
+    password: Option<String>,
+    topic_prefix: String,
+) -> Result<(), String> {
+    let host = host.trim();
+    if crate::mqtt::parse_host(host).is_none_or(|(host, _)| host.is_empty()) {
+        return Err("The MQTT broker must be a host name or an mqtts:// URL".to_string());
+    }
+    if port == 0 {
+        return Err("Invalid MQTT broker port".to_string());
+    }
+    let topic_prefix = topic_prefix.trim().trim_matches('/');
+    if topic_prefix.is_empty() {
+        return Err("The MQTT topic prefix must not be empty".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.mqtt_host = host.to_string();
+    settings.mqtt_port = port;
+    settings.mqtt_username = username.filter(|username| !username.is_empty());
+    settings.mqtt_password = password.filter(|password| !password.is_empty());
+    settings.mqtt_topic_prefix = topic_prefix.to_string();
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_call_mute_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.call_mute_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_obs_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.obs_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_obs_connection_setting(
+    app: AppHandle,
+    host: String,
+    port: u16,
+    password: Option<String>,
+    text_source: String,
+) -> Result<(), String> {
+    let host = host.trim();
+    if host.is_empty() {
+        return Err("The OBS host must not be empty".to_string());
+    }
+    if port == 0 {
+        return Err("Invalid OBS port".to_string());
+    }
+    let text_source = text_source.trim();
+    if text_source.is_empty() {
+        return Err("The OBS text source must not be empty".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.obs_host = host.to_string();
+    settings.obs_port = port;
+    settings.obs_password = password.filter(|password| !password.is_empty());
+    settings.obs_text_source = text_source.to_string();
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_obs_clear_after_setting(app: AppHandle, secs: u32) -> Result<(), String> {
+    if secs > 600 {
+        return Err("Captions can stay up to 600 seconds".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.obs_clear_after_secs = secs;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_mqtt_publish_transcripts_setting(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.mqtt_publish_transcripts = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_notification_setting(
+    app: AppHandle,
+    kind: NotificationKind,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    match kind {
+        NotificationKind::TranscriptionComplete => settings.notify_transcription_complete = enabled,
+        NotificationKind::ProviderError => settings.notify_provider_errors = enabled,
+        NotificationKind::PermissionProblem => settings.notify_permission_problems = enabled,
+        NotificationKind::ActionResult => {
+            return Err("Chain notifications are set per step".to_string())
+        }
+        NotificationKind::RecordingRecovered => {
+            return Err("Unfinished recordings are always reported".to_string())
+        }
+    }
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -225,18 +433,7 @@ pub fn change_overlay_position_setting(app: AppHandle, position: String) -> Resu
 pub fn change_debug_mode_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.debug_mode = enabled;
-    settings::write_settings(&app, settings);
-
-    // Emit event to notify frontend of debug mode change
-    let _ = app.emit(
-        "settings-changed",
-        serde_json::json!({
-            "setting": "debug_mode",
-            "value": enabled
-        }),
-    );
-
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -244,18 +441,7 @@ pub fn change_debug_mode_setting(app: AppHandle, enabled: bool) -> Result<(), St
 pub fn change_start_hidden_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.start_hidden = enabled;
-    settings::write_settings(&app, settings);
-
-    // Notify frontend
-    let _ = app.emit(
-        "settings-changed",
-        serde_json::json!({
-            "setting": "start_hidden",
-            "value": enabled
-        }),
-    );
-
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -263,26 +449,29 @@ pub fn change_start_hidden_setting(app: AppHandle, enabled: bool) -> Result<(),
 pub fn change_autostart_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.autostart_enabled = enabled;
-    settings::write_settings(&app, settings);
-
-    // Apply the autostart setting immediately
-    let autostart_manager = app.autolaunch();
-    if enabled {
-        let _ = autostart_manager.enable();
-    } else {
-        let _ = autostart_manager.disable();
-    }
+    settings::write_settings(&app, settings)
+}
 
-    // Notify frontend
-    let _ = app.emit(
-        "settings-changed",
-        serde_json::json!({
-            "setting": "autostart_enabled",
-            "value": enabled
-        }),
-    );
+#[tauri::command]
+#[specta::specta]
+pub fn change_autostart_minimized_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.autostart_minimized = enabled;
+    settings::write_settings(&app, settings)
+}
 
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub fn change_autostart_delay_setting(app: AppHandle, delay_secs: u32) -> Result<(), String> {
+    if delay_secs > crate::autostart::MAX_DELAY_SECS {
+        return Err(format!(
+            "The delay can be at most {} seconds",
+            crate::autostart::MAX_DELAY_SECS
+        ));
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.autostart_delay_secs = delay_secs;
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -290,17 +479,15 @@ pub fn change_autostart_setting(app: AppHandle, enabled: bool) -> Result<(), Str
 pub fn change_update_checks_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.update_checks_enabled = enabled;
-    settings::write_settings(&app, settings);
-
-    let _ = app.emit(
-        "settings-changed",
-        serde_json::json!({
-            "setting": "update_checks_enabled",
-            "value": enabled
-        }),
-    );
+    settings::write_settings(&app, settings)
+}
 
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub fn change_update_channel_setting(app: AppHandle, channel: UpdateChannel) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.update_channel = channel;
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -308,8 +495,7 @@ pub fn change_update_checks_setting(app: AppHandle, enabled: bool) -> Result<(),
 pub fn update_custom_words(app: AppHandle, words: Vec<String>) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.custom_words = words;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -320,8 +506,7 @@ pub fn change_word_correction_threshold_setting(
 ) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.word_correction_threshold = threshold;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -334,14 +519,126 @@ pub fn change_paste_method_setting(app: AppHandle, method: String) -> Result<(),
         "none" => PasteMethod::None,
         "shift_insert" => PasteMethod::ShiftInsert,
         "ctrl_shift_v" => PasteMethod::CtrlShiftV,
+        "keystrokes" => PasteMethod::Keystrokes,
         other => {
             warn!("Invalid paste method '{}', defaulting to ctrl_v", other);
             PasteMethod::CtrlV
         }
     };
     settings.paste_method = parsed;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_typing_speed_setting(app: AppHandle, chars_per_second: u32) -> Result<(), String> {
+    if !(1..=1000).contains(&chars_per_second) {
+        return Err(format!(
+            "Typing speed must be between 1 and 1000 characters per second, got {}",
+            chars_per_second
+        ));
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.typing_chars_per_second = chars_per_second;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_output_mode_setting(app: AppHandle, mode: String) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    let parsed = match mode.as_str() {
+        "inject" => OutputMode::Inject,
+        "append_to_note" => OutputMode::AppendToNote,
+        other => {
+            warn!("Invalid output mode '{}', defaulting to inject", other);
+            OutputMode::Inject
+        }
+    };
+    settings.output_mode = parsed;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_note_file_path_setting(app: AppHandle, path: Option<String>) -> Result<(), String> {
+    let path = path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    if let Some(path) = &path {
+        let resolved = crate::note::resolve_note_path(path, chrono::Local::now().date_naive());
+        if !resolved.is_absolute() {
+            return Err("Note file path must be absolute".to_string());
+        }
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.note_file_path = path;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_undo_method_setting(app: AppHandle, method: String) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    let parsed = match method.as_str() {
+        "backspace" => UndoMethod::Backspace,
+        "platform_undo" => UndoMethod::PlatformUndo,
+        other => {
+            warn!("Invalid undo method '{}', defaulting to backspace", other);
+            UndoMethod::Backspace
+        }
+    };
+    settings.undo_method = parsed;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_restore_clipboard_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.restore_clipboard = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_clipboard_restore_delay_setting(app: AppHandle, delay_ms: u64) -> Result<(), String> {
+    if !(50..=5000).contains(&delay_ms) {
+        return Err(format!(
+            "Restore delay must be between 50 and 5000 ms, got {}",
+            delay_ms
+        ));
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.clipboard_restore_delay_ms = delay_ms;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_markdown_rich_text_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.markdown_rich_text = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_smart_spacing_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.smart_spacing = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_streaming_injection_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.streaming_injection = enabled;
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -360,8 +657,31 @@ pub fn change_clipboard_handling_setting(app: AppHandle, handling: String) -> Re
         }
     };
     settings.clipboard_handling = parsed;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_gemini_safety_threshold_setting(
+    app: AppHandle,
+    threshold: String,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    let parsed = match threshold.as_str() {
+        "block_none" => GeminiSafetyThreshold::BlockNone,
+        "block_only_high" => GeminiSafetyThreshold::BlockOnlyHigh,
+        "block_medium_and_above" => GeminiSafetyThreshold::BlockMediumAndAbove,
+        "block_low_and_above" => GeminiSafetyThreshold::BlockLowAndAbove,
+        other => {
+            warn!(
+                "Invalid Gemini safety threshold '{}', defaulting to block_only_high",
+                other
+            );
+            GeminiSafetyThreshold::BlockOnlyHigh
+        }
+    };
+    settings.gemini_safety_threshold = parsed;
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -369,8 +689,7 @@ pub fn change_clipboard_handling_setting(app: AppHandle, handling: String) -> Re
 pub fn change_post_process_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.post_process_enabled = enabled;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -390,16 +709,114 @@ pub fn change_post_process_base_url_setting(
         .post_process_provider_mut(&provider_id)
         .expect("Provider looked up above must exist");
 
-    if !provider.allow_base_url_edit {
-        return Err(format!(
-            "Provider '{}' does not allow editing the base URL",
-            label
-        ));
-    }
+    if !provider.allow_base_url_edit {
+        return Err(format!(
+            "Provider '{}' does not allow editing the base URL",
+            label
+        ));
+    }
+
+    provider.base_url = base_url;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_post_process_api_version_setting(
+    app: AppHandle,
+    provider_id: String,
+    api_version: Option<String>,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    let provider = settings
+        .post_process_provider_mut(&provider_id)
+        .ok_or_else(|| format!("Provider '{}' not found", provider_id))?;
+
+    provider.api_version = api_version
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty());
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_post_process_service_tier_setting(
+    app: AppHandle,
+    provider_id: String,
+    service_tier: Option<String>,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    validate_provider_exists(&settings, &provider_id)?;
+
+    match service_tier
+        .map(|tier| tier.trim().to_string())
+        .filter(|tier| !tier.is_empty())
+    {
+        Some(tier) => {
+            settings
+                .post_process_service_tiers
+                .insert(provider_id, tier);
+        }
+        None => {
+            settings.post_process_service_tiers.remove(&provider_id);
+        }
+    }
+    settings::write_settings(&app, settings)
+}
+
+/// Set provider-specific request fields as a JSON object, or clear them with `None`
+#[tauri::command]
+#[specta::specta]
+pub fn change_post_process_extra_body_setting(
+    app: AppHandle,
+    provider_id: String,
+    extra_body: Option<String>,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    validate_provider_exists(&settings, &provider_id)?;
+
+    match extra_body.filter(|text| !text.trim().is_empty()) {
+        Some(text) => {
+            crate::llm_types::parse_extra_body(&text)?;
+            settings.post_process_extra_body.insert(provider_id, text);
+        }
+        None => {
+            settings.post_process_extra_body.remove(&provider_id);
+        }
+    }
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_post_process_fallback_api_keys_setting(
+    app: AppHandle,
+    provider_id: String,
+    api_keys: Vec<String>,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    validate_provider_exists(&settings, &provider_id)?;
+
+    crate::api_keys::reset(&provider_id);
+    settings
+        .post_process_fallback_api_keys
+        .insert(provider_id, crate::api_keys::dedup_keys(api_keys));
+    settings::write_settings(&app, settings)
+}
 
-    provider.base_url = base_url;
-    settings::write_settings(&app, settings);
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub fn change_online_provider_fallback_api_keys_setting(
+    app: AppHandle,
+    provider_id: String,
+    api_keys: Vec<String>,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    crate::api_keys::reset(&provider_id);
+    settings
+        .online_provider_fallback_api_keys
+        .insert(provider_id, crate::api_keys::dedup_keys(api_keys));
+    settings::write_settings(&app, settings)
 }
 
 /// Generic helper to validate provider exists
@@ -427,22 +844,23 @@ pub fn change_post_process_api_key_setting(
     let mut settings = settings::get_settings(&app);
     validate_provider_exists(&settings, &provider_id)?;
     settings.post_process_api_keys.insert(provider_id, api_key);
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
 #[specta::specta]
-pub fn change_post_process_model_setting(
+pub async fn change_post_process_model_setting(
     app: AppHandle,
     provider_id: String,
     model: String,
 ) -> Result<(), String> {
+    validate_provider_exists(&settings::get_settings(&app), &provider_id)?;
+    crate::commands::llm_models::validate_llm_model(&app, &provider_id, &model).await?;
+
+    // Re-read, settings may have changed while the model list was fetched
     let mut settings = settings::get_settings(&app);
-    validate_provider_exists(&settings, &provider_id)?;
     settings.post_process_models.insert(provider_id, model);
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -451,8 +869,7 @@ pub fn set_post_process_provider(app: AppHandle, provider_id: String) -> Result<
     let mut settings = settings::get_settings(&app);
     validate_provider_exists(&settings, &provider_id)?;
     settings.post_process_provider_id = provider_id;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -474,7 +891,7 @@ pub fn add_post_process_prompt(
     };
 
     settings.post_process_prompts.push(new_prompt.clone());
-    settings::write_settings(&app, settings);
+    settings::write_settings(&app, settings)?;
 
     Ok(new_prompt)
 }
@@ -496,8 +913,7 @@ pub fn update_post_process_prompt(
     {
         existing_prompt.name = name;
         existing_prompt.prompt = prompt;
-        settings::write_settings(&app, settings);
-        Ok(())
+        settings::write_settings(&app, settings)
     } else {
         Err(format!("Prompt with id '{}' not found", id))
     }
@@ -527,8 +943,7 @@ pub fn delete_post_process_prompt(app: AppHandle, id: String) -> Result<(), Stri
             settings.post_process_prompts.first().map(|p| p.id.clone());
     }
 
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -558,6 +973,18 @@ pub async fn fetch_post_process_models(
         }
     }
 
+    if provider.id == LLAMA_CPP_PROVIDER_ID {
+        // The model is the configured GGUF file, there is nothing to list
+        return Ok(vec![settings::LLAMA_CPP_DEFAULT_MODEL_ID.to_string()]);
+    }
+
+    if provider.id == "azure" {
+        return Err(
+            "Azure OpenAI deployments can't be listed, enter the deployment name as the model"
+                .to_string(),
+        );
+    }
+
     // Get API key
     let api_key = settings
         .post_process_api_keys
@@ -566,7 +993,7 @@ pub async fn fetch_post_process_models(
         .unwrap_or_default();
 
     // Skip fetching if no API key for providers that typically need one
-    if api_key.trim().is_empty() && provider.id != "custom" {
+    if api_key.trim().is_empty() && !matches!(provider.id.as_str(), "custom" | "ollama") {
         return Err(format!(
             "API key is required for {}. Please add an API key to list available models.",
             provider.label
@@ -579,7 +1006,8 @@ pub async fn fetch_post_process_models(
     // return Ok(response.data.iter().map(|m| m.id.clone()).collect());
 
     // For now, use manual HTTP request to have more control over the endpoint
-    fetch_models_manual(provider, api_key).await
+    let http_client = crate::http::client_builder(&settings)?;
+    fetch_models_manual(provider, api_key, http_client).await
 }
 
 /// Fetch models using manual HTTP request
@@ -587,6 +1015,7 @@ pub async fn fetch_post_process_models(
 async fn fetch_models_manual(
     provider: &crate::settings::PostProcessProvider,
     api_key: String,
+    http_client: reqwest::ClientBuilder,
 ) -> Result<Vec<String>, String> {
     // Build the endpoint URL
     let base_url = provider.base_url.trim_end_matches('/');
@@ -621,6 +1050,16 @@ async fn fetch_models_manual(
             "anthropic-version",
             reqwest::header::HeaderValue::from_static("2023-06-01"),
         );
+    } else if provider.id == "gemini" {
+        // Gemini's native API has its own header; a key in the URL would end
+        // up in proxy and server logs
+        if !api_key.is_empty() {
+            headers.insert(
+                "x-goog-api-key",
+                reqwest::header::HeaderValue::from_str(&api_key)
+                    .map_err(|e| format!("Invalid API key: {}", e))?,
+            );
+        }
     } else if !api_key.is_empty() {
         headers.insert(
             "Authorization",
@@ -629,7 +1068,7 @@ async fn fetch_models_manual(
         );
     }
 
-    let http_client = reqwest::Client::builder()
+    let http_client = http_client
         .default_headers(headers)
         .build()
         .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
@@ -671,6 +1110,14 @@ async fn fetch_models_manual(
             }
         }
     }
+    // Handle Ollama and Gemini format: { models: [ { name: "..." }, ... ] }
+    else if let Some(data) = parsed.get("models").and_then(|d| d.as_array()) {
+        for entry in data {
+            if let Some(name) = entry.get("name").and_then(|n| n.as_str()) {
+                models.push(name.trim_start_matches("models/").to_string());
+            }
+        }
+    }
     // Handle array format: [ "model1", "model2", ... ]
     else if let Some(array) = parsed.as_array() {
         for entry in array {
@@ -694,8 +1141,7 @@ pub fn set_post_process_selected_prompt(app: AppHandle, id: String) -> Result<()
     }
 
     settings.post_process_selected_prompt_id = Some(id);
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -703,9 +1149,51 @@ pub fn set_post_process_selected_prompt(app: AppHandle, id: String) -> Result<()
 pub fn change_mute_while_recording_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.mute_while_recording = enabled;
-    settings::write_settings(&app, settings);
+    settings::write_settings(&app, settings)
+}
 
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub fn change_recording_memory_limit_setting(app: AppHandle, limit_mb: u32) -> Result<(), String> {
+    if !(8..=4096).contains(&limit_mb) {
+        return Err("Recording memory limit must be between 8 and 4096 MB".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.recording_memory_limit_mb = limit_mb;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_wake_word_enabled_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.wake_word_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_wake_word_phrase_setting(app: AppHandle, phrase: String) -> Result<(), String> {
+    if crate::snippets::trigger_words(&phrase).is_empty() {
+        return Err("The wake phrase must have a word".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.wake_word_phrase = phrase.trim().to_string();
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_wake_word_sensitivity_setting(
+    app: AppHandle,
+    sensitivity: f32,
+) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&sensitivity) {
+        return Err("Wake word sensitivity must be between 0 and 1".to_string());
+    }
+    let mut settings = settings::get_settings(&app);
+    settings.wake_word_sensitivity = sensitivity;
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -713,9 +1201,199 @@ pub fn change_mute_while_recording_setting(app: AppHandle, enabled: bool) -> Res
 pub fn change_append_trailing_space_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.append_trailing_space = enabled;
-    settings::write_settings(&app, settings);
+    settings::write_settings(&app, settings)
+}
 
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub fn change_llama_cpp_model_path_setting(
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<(), String> {
+    let path = path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    if let Some(path) = &path {
+        let file = std::path::Path::new(path);
+        if !file.is_file() {
+            return Err(format!("Model file '{}' does not exist", path));
+        }
+        let is_gguf = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"));
+        if !is_gguf {
+            return Err("Model file must be a .gguf file".to_string());
+        }
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.llama_cpp_model_path = path;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_llama_cpp_gpu_layers_setting(app: AppHandle, layers: u32) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.llama_cpp_gpu_layers = layers;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_proxy_url_setting(app: AppHandle, proxy_url: Option<String>) -> Result<(), String> {
+    let proxy_url = proxy_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &proxy_url {
+        crate::http::parse_proxy(url)?;
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.proxy_url = proxy_url;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_custom_ca_path_setting(app: AppHandle, path: Option<String>) -> Result<(), String> {
+    let path = path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    if let Some(path) = &path {
+        crate::http::load_ca_bundle(path)?;
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.custom_ca_path = path;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_action_timeout_setting(app: AppHandle, secs: u32) -> Result<(), String> {
+    if secs > MAX_ACTION_TIMEOUT_SECS {
+        return Err(format!(
+            "The action timeout must be at most {} seconds",
+            MAX_ACTION_TIMEOUT_SECS
+        ));
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.action_timeout_secs = secs;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_network_max_attempts_setting(app: AppHandle, attempts: u32) -> Result<(), String> {
+    if !(1..=10).contains(&attempts) {
+        return Err(format!(
+            "Max attempts must be between 1 and 10, got {}",
+            attempts
+        ));
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.network_max_attempts = attempts;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_conversation_context_enabled_setting(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    settings.conversation_context_enabled = enabled;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_conversation_context_turns_setting(app: AppHandle, turns: u32) -> Result<(), String> {
+    if !(1..=10).contains(&turns) {
+        return Err(format!(
+            "Context turns must be between 1 and 10, got {}",
+            turns
+        ));
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.conversation_context_turns = turns;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_conversation_context_window_setting(
+    app: AppHandle,
+    minutes: u32,
+) -> Result<(), String> {
+    if !(1..=120).contains(&minutes) {
+        return Err(format!(
+            "Context window must be between 1 and 120 minutes, got {}",
+            minutes
+        ));
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.conversation_context_window_minutes = minutes;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_monthly_llm_budget_setting(
+    app: AppHandle,
+    budget_usd: Option<f64>,
+) -> Result<(), String> {
+    if budget_usd.is_some_and(|budget| budget <= 0.0) {
+        return Err("Budget must be greater than 0".to_string());
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.monthly_llm_budget_usd = budget_usd;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_long_transcript_strategy_setting(
+    app: AppHandle,
+    strategy: String,
+) -> Result<(), String> {
+    let mut settings = settings::get_settings(&app);
+    let parsed = match strategy.as_str() {
+        "truncate" => LongTranscriptStrategy::Truncate,
+        "summarize" => LongTranscriptStrategy::Summarize,
+        other => {
+            warn!(
+                "Invalid long transcript strategy '{}', defaulting to truncate",
+                other
+            );
+            LongTranscriptStrategy::Truncate
+        }
+    };
+    settings.long_transcript_strategy = parsed;
+    settings::write_settings(&app, settings)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn change_translate_target_language_setting(
+    app: AppHandle,
+    language: String,
+) -> Result<(), String> {
+    let language = language.trim();
+    if language.is_empty() {
+        return Err("Target language must not be empty".to_string());
+    }
+
+    let mut settings = settings::get_settings(&app);
+    settings.translate_target_language = language.to_string();
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -723,9 +1401,7 @@ pub fn change_append_trailing_space_setting(app: AppHandle, enabled: bool) -> Re
 pub fn change_app_language_setting(app: AppHandle, language: String) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.app_language = language;
-    settings::write_settings(&app, settings);
-
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 // ============================================================================
@@ -750,8 +1426,7 @@ fn validate_online_provider_id(provider_id: &str) -> Result<(), String> {
 pub fn change_use_online_provider_setting(app: AppHandle, enabled: bool) -> Result<(), String> {
     let mut settings = settings::get_settings(&app);
     settings.use_online_provider = enabled;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -763,8 +1438,7 @@ pub fn change_online_provider_id_setting(
     validate_online_provider_id(&provider_id)?;
     let mut settings = settings::get_settings(&app);
     settings.online_provider_id = provider_id;
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -777,8 +1451,7 @@ pub fn change_online_provider_api_key_setting(
     validate_online_provider_id(&provider_id)?;
     let mut settings = settings::get_settings(&app);
     settings.online_provider_api_keys.insert(provider_id, api_key);
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
 }
 
 #[tauri::command]
@@ -791,8 +1464,18 @@ pub fn change_online_provider_model_setting(
     validate_online_provider_id(&provider_id)?;
     let mut settings = settings::get_settings(&app);
     settings.online_provider_models.insert(provider_id, model);
-    settings::write_settings(&app, settings);
-    Ok(())
+    settings::write_settings(&app, settings)
+}
+
+/// Whether `raw` is a shortcut that could be registered, keyboard or mouse
+pub fn check_shortcut_string(raw: &str) -> Result<(), String> {
+    validate_shortcut_string(raw)?;
+    if input_hook::contains_mouse_button(raw) {
+        return Ok(());
+    }
+    raw.parse::<Shortcut>()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to parse shortcut '{}': {}", raw, e))
 }
 
 /// Determine whether a shortcut string contains at least one non-modifier key.
@@ -819,6 +1502,8 @@ fn validate_shortcut_string(raw: &str) -> Result<(), String> {
 #[specta::specta]
 pub fn suspend_binding(app: AppHandle, id: String) -> Result<(), String> {
     if let Some(b) = settings::get_bindings(&app).get(&id).cloned() {
+        SUSPENDED.lock().unwrap().insert(id.clone());
+
         // Check if this is a mouse shortcut
         if input_hook::contains_mouse_button(&b.current_binding) {
             input_hook::suspend_mouse_shortcut(&id);
@@ -838,12 +1523,18 @@ pub fn suspend_binding(app: AppHandle, id: String) -> Result<(), String> {
 #[specta::specta]
 pub fn resume_binding(app: AppHandle, id: String) -> Result<(), String> {
     if let Some(b) = settings::get_bindings(&app).get(&id).cloned() {
+        SUSPENDED.lock().unwrap().remove(&id);
+
         // Check if this is a mouse shortcut
         if input_hook::contains_mouse_button(&b.current_binding) {
             input_hook::resume_mouse_shortcut(&id);
             return Ok(());
         }
-        
+
+        // Already registered when the binding was changed while suspended
+        if REGISTERED.lock().unwrap().get(&id) == Some(&b.current_binding) {
+            return Ok(());
+        }
         if let Err(e) = register_shortcut(&app, b) {
             error!("resume_binding error for id '{}': {}", id, e);
             return Err(e);
@@ -852,6 +1543,35 @@ pub fn resume_binding(app: AppHandle, id: String) -> Result<(), String> {
     Ok(())
 }
 
+pub fn shortcuts_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+/// Whether a shortcut event should be dropped. A recording already under way
+/// can still be finished or cancelled.
+pub fn ignore_shortcut(app: &AppHandle) -> bool {
+    shortcuts_paused() && !app.state::<Arc<AudioRecordingManager>>().is_recording()
+}
+
+/// Ignore or handle all shortcuts again, e.g. while gaming or presenting
+#[tauri::command]
+#[specta::specta]
+pub fn set_shortcuts_paused(app: AppHandle, paused: bool) {
+    PAUSED.store(paused, Ordering::Relaxed);
+    if let Err(e) = app.emit("shortcuts-paused", paused) {
+        warn!("Failed to emit shortcuts-paused: {}", e);
+    }
+    if !crate::pipeline::is_busy() {
+        crate::tray::update_tray_menu(&app, &crate::tray::TrayIconState::Idle);
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_shortcuts_paused() -> bool {
+    shortcuts_paused()
+}
+
 pub fn register_cancel_shortcut(app: &AppHandle) {
     // Cancel shortcut is disabled on Linux due to instability with dynamic shortcut registration
     #[cfg(target_os = "linux")]
@@ -906,7 +1626,12 @@ pub fn register_shortcut(app: &AppHandle, binding: ShortcutBinding) -> Result<()
     // Check if this shortcut contains mouse buttons
     if input_hook::contains_mouse_button(&binding.current_binding) {
         // Route to input_hook module for mouse-containing shortcuts
-        return input_hook::register_mouse_shortcut(&binding.id, &binding.current_binding);
+        input_hook::register_mouse_shortcut(&binding.id, &binding.current_binding)?;
+        REGISTERED
+            .lock()
+            .unwrap()
+            .insert(binding.id, binding.current_binding);
+        return Ok(());
     }
 
     // Parse shortcut and return error if it fails (keyboard-only shortcuts)
@@ -935,24 +1660,52 @@ pub fn register_shortcut(app: &AppHandle, binding: ShortcutBinding) -> Result<()
     app.global_shortcut()
         .on_shortcut(shortcut, move |ah, scut, event| {
             if scut == &shortcut {
+                if ignore_shortcut(ah) {
+                    return;
+                }
                 let shortcut_string = scut.into_string();
-                let settings = get_settings(ah);
 
-                if let Some(action) = ACTION_MAP.get(&binding_id_for_closure) {
+                if let Some(action) = action_for(&binding_id_for_closure) {
                     if binding_id_for_closure == "cancel" {
                         let audio_manager = ah.state::<Arc<AudioRecordingManager>>();
                         if audio_manager.is_recording() && event.state == ShortcutState::Pressed {
                             action.start(ah, &binding_id_for_closure, &shortcut_string);
                         }
                         return;
-                    } else if settings.push_to_talk {
-                        if event.state == ShortcutState::Pressed {
+                    } else if binding_id_for_closure == crate::clipboard::UNDO_INJECTION_ACTION_ID
+                        || binding_id_for_closure == crate::profiles::CYCLE_PROFILE_ACTION_ID
+                    {
+                        // One-shot; on release so the shortcut's keys don't mix with the undo
+                        if event.state == ShortcutState::Released {
                             action.start(ah, &binding_id_for_closure, &shortcut_string);
-                        } else if event.state == ShortcutState::Released {
+                        }
+                        return;
+                    } else if get_settings(ah).push_to_talk {
+                        let toggle_state_manager = ah.state::<ManagedToggleState>();
+                        let mut states = toggle_state_manager
+                            .lock()
+                            .expect("Failed to lock toggle state manager");
+                        let is_currently_active = states
+                            .active_toggles
+                            .entry(binding_id_for_closure.clone())
+                            .or_insert(false);
+
+                        // A press that wasn't admitted has nothing to stop on release
+                        if event.state == ShortcutState::Pressed {
+                            if !*is_currently_active && admit(ah, &binding_id_for_closure) {
+                                action.start(ah, &binding_id_for_closure, &shortcut_string);
+                                *is_currently_active = true;
+                            }
+                        } else if event.state == ShortcutState::Released && *is_currently_active {
                             action.stop(ah, &binding_id_for_closure, &shortcut_string);
+                            *is_currently_active = false;
                         }
                     } else {
                         if event.state == ShortcutState::Pressed {
+                            if throttle::is_bounce(&binding_id_for_closure) {
+                                debug!("Ignoring a bounce of '{}'", binding_id_for_closure);
+                                return;
+                            }
                             let toggle_state_manager = ah.state::<ManagedToggleState>();
 
                             let mut states = toggle_state_manager.lock().expect("Failed to lock toggle state manager");
@@ -968,7 +1721,7 @@ pub fn register_shortcut(app: &AppHandle, binding: ShortcutBinding) -> Result<()
                                     &shortcut_string,
                                 );
                                 *is_currently_active = false; // Update state to inactive
-                            } else {
+                            } else if admit(ah, &binding_id_for_closure) {
                                 action.start(ah, &binding_id_for_closure, &shortcut_string);
                                 *is_currently_active = true; // Update state to active
                             }
@@ -976,7 +1729,7 @@ pub fn register_shortcut(app: &AppHandle, binding: ShortcutBinding) -> Result<()
                     }
                 } else {
                     warn!(
-                        "No action defined in ACTION_MAP for shortcut ID '{}'. Shortcut: '{}', State: {:?}",
+                        "No action defined for shortcut ID '{}'. Shortcut: '{}', State: {:?}",
                         binding_id_for_closure, shortcut_string, event.state
                     );
                 }
@@ -988,14 +1741,187 @@ pub fn register_shortcut(app: &AppHandle, binding: ShortcutBinding) -> Result<()
             error_msg
         })?;
 
+    REGISTERED
+        .lock()
+        .unwrap()
+        .insert(binding.id, binding.current_binding);
+    Ok(())
+}
+
+fn with_shortcut(binding: &ShortcutBinding, shortcut: &str) -> ShortcutBinding {
+    ShortcutBinding {
+        current_binding: shortcut.to_string(),
+        ..binding.clone()
+    }
+}
+
+/// Bindings of `next` whose shortcut differs from `previous`, with the
+/// previous shortcut; cancel is registered while recording only
+fn changed_bindings<'a>(
+    previous: &HashMap<String, ShortcutBinding>,
+    next: &'a HashMap<String, ShortcutBinding>,
+) -> Vec<(&'a ShortcutBinding, String)> {
+    next.values()
+        .filter(|binding| binding.id != "cancel")
+        .filter_map(|binding| {
+            let current = previous
+                .get(&binding.id)
+                .map(|old| old.current_binding.clone())
+                .unwrap_or_default();
+            (current != binding.current_binding).then_some((binding, current))
+        })
+        .collect()
+}
+
+/// Swap the shortcuts that differ between `previous` and `next` as a set
+/// through `register` and `unregister`. When one of `next` fails to
+/// register, the `previous` shortcuts are put back.
+pub(crate) fn swap_bindings(
+    previous: &HashMap<String, ShortcutBinding>,
+    next: &HashMap<String, ShortcutBinding>,
+    mut register: impl FnMut(ShortcutBinding) -> Result<(), String>,
+    mut unregister: impl FnMut(ShortcutBinding) -> Result<(), String>,
+) -> Result<(), String> {
+    let changes = changed_bindings(previous, next);
+    for (binding, current) in &changes {
+        if let Err(e) = unregister(with_shortcut(binding, current)) {
+            warn!("Failed to unregister shortcut '{}': {}", binding.id, e);
+        }
+    }
+
+    let mut registered = Vec::new();
+    // An empty shortcut leaves the action unbound
+    for (binding, _) in changes
+        .iter()
+        .filter(|(b, _)| !b.current_binding.is_empty())
+    {
+        if let Err(e) = register((*binding).clone()) {
+            for binding in registered {
+                let _ = unregister(binding);
+            }
+            for (binding, current) in changes.iter().filter(|(_, c)| !c.is_empty()) {
+                if let Err(e) = register(with_shortcut(binding, current)) {
+                    error!("Failed to restore shortcut '{}': {}", binding.id, e);
+                }
+            }
+            return Err(format!(
+                "Failed to register shortcut for '{}': {}",
+                binding.name, e
+            ));
+        }
+        registered.push((*binding).clone());
+    }
     Ok(())
 }
 
+/// Swap the registered shortcuts from `previous` to `next` as a set, for
+/// changes that must apply whole or not at all, like switching profiles.
+/// The settings subscriber then finds the shortcuts already registered.
+pub fn replace_bindings(
+    app: &AppHandle,
+    previous: &HashMap<String, ShortcutBinding>,
+    next: &HashMap<String, ShortcutBinding>,
+) -> Result<(), String> {
+    swap_bindings(
+        previous,
+        next,
+        |binding| register_shortcut(app, binding),
+        |binding| unregister_shortcut(app, binding),
+    )
+}
+
+/// Bring the registered shortcuts in line with the saved bindings. A
+/// binding whose shortcut can't be registered keeps its previous one, in
+/// the registration and in the settings; changes that must apply whole go
+/// through `replace_bindings` before they're saved.
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    let registered = REGISTERED.lock().unwrap().clone();
+    let mut changes = Vec::new();
+    for (id, binding) in &next.bindings {
+        // Cancel is registered while recording only
+        if id == "cancel" {
+            continue;
+        }
+        let current = registered.get(id).cloned().unwrap_or_default();
+        if current == binding.current_binding {
+            continue;
+        }
+        // A binding being edited stays unregistered until it's changed
+        let edited = previous
+            .bindings
+            .get(id)
+            .is_none_or(|old| old.current_binding != binding.current_binding);
+        let mut suspended = SUSPENDED.lock().unwrap();
+        if suspended.contains(id) {
+            if !edited {
+                continue;
+            }
+            suspended.remove(id);
+        }
+        changes.push((binding, current));
+    }
+
+    // Bindings that are gone, like deleted custom actions
+    for (id, binding) in &previous.bindings {
+        if next.bindings.contains_key(id) {
+            continue;
+        }
+        if let Some(current) = registered.get(id) {
+            if let Err(e) = unregister_shortcut(app, with_shortcut(binding, current)) {
+                warn!("Failed to unregister shortcut '{}': {}", id, e);
+            }
+        }
+    }
+
+    // Free the old shortcuts first so two bindings can swap theirs
+    for (binding, current) in &changes {
+        if let Err(e) = unregister_shortcut(app, with_shortcut(binding, current)) {
+            warn!("Failed to unregister shortcut '{}': {}", binding.id, e);
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut reverted = HashMap::new();
+    for (binding, current) in changes {
+        if binding.current_binding.is_empty() {
+            continue;
+        }
+        if let Err(e) = register_shortcut(app, binding.clone()) {
+            let previous_binding = with_shortcut(binding, &current);
+            if !current.is_empty() {
+                if let Err(e) = register_shortcut(app, previous_binding.clone()) {
+                    error!("Failed to restore shortcut '{}': {}", binding.id, e);
+                }
+            }
+            errors.push(SettingsError::new(
+                format!("bindings.{}.current_binding", binding.id),
+                format!("{}; kept '{}'", e, current),
+            ));
+            reverted.insert(binding.id.clone(), previous_binding);
+        }
+    }
+    if !reverted.is_empty() {
+        settings::restore_bindings(app, reverted);
+    }
+    errors
+}
+
 pub fn unregister_shortcut(app: &AppHandle, binding: ShortcutBinding) -> Result<(), String> {
+    // Unbound actions were never registered
+    if binding.current_binding.is_empty() {
+        return Ok(());
+    }
+
     // Check if this shortcut contains mouse buttons
     if input_hook::contains_mouse_button(&binding.current_binding) {
         // Route to input_hook module for mouse-containing shortcuts
-        return input_hook::unregister_mouse_shortcut(&binding.id);
+        input_hook::unregister_mouse_shortcut(&binding.id)?;
+        REGISTERED.lock().unwrap().remove(&binding.id);
+        return Ok(());
     }
     
     let shortcut = match binding.current_binding.parse::<Shortcut>() {
@@ -1019,6 +1945,7 @@ pub fn unregister_shortcut(app: &AppHandle, binding: ShortcutBinding) -> Result<
         error_msg
     })?;
 
+    REGISTERED.lock().unwrap().remove(&binding.id);
     Ok(())
 }
 
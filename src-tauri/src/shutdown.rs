@@ -0,0 +1,128 @@
+//! Orderly shutdown. Quitting first stops new dictations from starting,
+//! keeps a recording still underway for recovery at the next launch, lets a
+//! transcription already past the recording finish and its history entry be
+//! written, puts back a clipboard a paste borrowed and releases the
+//! microphone and the shortcuts, all within `DEADLINE`, before the process
+//! actually exits.
+
+use crate::clipboard;
+use crate::managers::audio::AudioRecordingManager;
+use crate::managers::history::HistoryManager;
+use crate::pipeline;
+use crate::updater;
+use log::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, ExitRequestApi, Manager};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+/// How long quitting may wait on work in flight
+const DEADLINE: Duration = Duration::from_secs(8);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Set once the sequence ran, letting the exit it requests through
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Babbl is quitting; no new dictation starts then
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+/// Wait until `done` or `deadline`; whether it got done in time
+fn wait_until(deadline: Instant, mut done: impl FnMut() -> bool) -> bool {
+    loop {
+        if done() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn run(app: &AppHandle) {
+    let started = Instant::now();
+    let deadline = started + DEADLINE;
+    info!("Shutting down");
+
+    if let Err(e) = app.global_shortcut().unregister_all() {
+        warn!("Failed to unregister the shortcuts: {}", e);
+    }
+
+    let rm = app.state::<Arc<AudioRecordingManager>>();
+    if rm.is_recording() {
+        match rm.persist_recording() {
+            Some(spool) => info!("Kept the recording underway for recovery: {:?}", spool),
+            None => warn!("Dropped the recording underway, it wasn't spooled"),
+        }
+        pipeline::reset(app);
+    }
+    if !wait_until(deadline, || !pipeline::is_busy()) {
+        warn!(
+            "Quitting while the dictation was still {:?}",
+            pipeline::current()
+        );
+    }
+
+    let hm = app.state::<Arc<HistoryManager>>();
+    if !wait_until(deadline, || !hm.has_pending_saves()) {
+        warn!("Quitting before the history was saved");
+    }
+
+    clipboard::restore_pending(app);
+    rm.stop_microphone_stream();
+
+    debug!("Shut down in {:?}", started.elapsed());
+}
+
+/// Handle `RunEvent::ExitRequested`: hold the exit back and run the
+/// shutdown, then exit for real
+pub fn on_exit_requested(app: &AppHandle, code: Option<i32>, api: &ExitRequestApi) {
+    if FINISHED.load(Ordering::SeqCst) {
+        return;
+    }
+    api.prevent_exit();
+    // The last window closed, which doesn't quit an app living in the tray
+    if code.is_none() {
+        return;
+    }
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // Exit even when a step panics
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run(&app)));
+        FINISHED.store(true, Ordering::SeqCst);
+        match code {
+            Some(tauri::RESTART_EXIT_CODE) => {
+                updater::install_pending();
+                app.request_restart()
+            }
+            code => app.exit(code.unwrap_or(0)),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_until() {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut polls = 0;
+        assert!(wait_until(deadline, || {
+            polls += 1;
+            polls == 3
+        }));
+        assert_eq!(polls, 3);
+
+        assert!(!wait_until(Instant::now(), || false));
+    }
+}
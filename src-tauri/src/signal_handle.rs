@@ -1,5 +1,7 @@
 #[cfg(unix)]
-use crate::actions::ACTION_MAP;
+use crate::actions::{action_for, admit};
+#[cfg(unix)]
+use crate::throttle;
 #[cfg(unix)]
 use crate::ManagedToggleState;
 #[cfg(unix)]
@@ -28,8 +30,12 @@ pub fn setup_signal_handler(app_handle: AppHandle, mut signals: Signals) {
 
                     let binding_id = "transcribe";
                     let shortcut_string = "SIGUSR2";
+                    if throttle::is_bounce(binding_id) {
+                        debug!("SIGUSR2: Ignoring, too soon after the last one");
+                        continue;
+                    }
 
-                    if let Some(action) = ACTION_MAP.get(binding_id) {
+                    if let Some(action) = action_for(binding_id) {
                         let toggle_state_manager =
                             app_handle_for_signal.state::<ManagedToggleState>();
 
@@ -51,14 +57,14 @@ pub fn setup_signal_handler(app_handle: AppHandle, mut signals: Signals) {
                             action.stop(&app_handle_for_signal, binding_id, shortcut_string);
                             *is_currently_active = false; // Update state to inactive
                             debug!("SIGUSR2: Transcription stopped");
-                        } else {
+                        } else if admit(&app_handle_for_signal, binding_id) {
                             debug!("SIGUSR2: Starting transcription (currently inactive)");
                             action.start(&app_handle_for_signal, binding_id, shortcut_string);
                             *is_currently_active = true; // Update state to active
                             info!("SIGUSR2: Transcription started");
                         }
                     } else {
-                        warn!("No action defined in ACTION_MAP for binding ID '{binding_id}'");
+                        warn!("No action defined for binding ID '{binding_id}'");
                     }
                 }
                 _ => unreachable!(),
@@ -0,0 +1,130 @@
+use crate::settings::Snippet;
+
+/// Lowercase letters and digits of a word, so "Address," matches "address"
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Normalized words of a trigger phrase
+pub fn trigger_words(trigger: &str) -> Vec<String> {
+    trigger
+        .split_whitespace()
+        .map(normalize_word)
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Punctuation that ends a spoken phrase
+const PHRASE_ENDS: &[char] = &['.', ',', '!', '?', ';', ':'];
+
+/// Replace spoken trigger phrases in `text` with their snippet text. Matching
+/// ignores case and punctuation; punctuation right after a trigger is dropped
+/// along with it. A trigger only counts as a phrase of its own, at the start
+/// of the text or after a sentence and up to punctuation or the end, so
+/// "sign off" doesn't fire inside "please sign off the report". Longer
+/// triggers win when they overlap.
+pub fn expand_snippets(text: &str, snippets: &[Snippet]) -> String {
+    let mut triggers: Vec<(Vec<String>, &str)> = snippets
+        .iter()
+        .map(|snippet| (trigger_words(&snippet.trigger), snippet.text.as_str()))
+        .filter(|(words, _)| !words.is_empty())
+        .collect();
+    if triggers.is_empty() {
+        return text.to_string();
+    }
+    triggers.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+
+    // Byte range and normalized form of every whitespace separated word
+    let mut words: Vec<(usize, usize, String)> = Vec::new();
+    let mut start = None;
+    for (index, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(index),
+            (true, Some(word_start)) => {
+                words.push((word_start, index, normalize_word(&text[word_start..index])));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut copied_to = 0;
+    let mut i = 0;
+    while i < words.len() {
+        let starts_phrase = i == 0 || {
+            let (start, end, _) = words[i - 1];
+            text[start..end].ends_with(['.', '!', '?', ':'])
+        };
+        let matched = triggers.iter().find(|(trigger, _)| {
+            let end = i + trigger.len();
+            starts_phrase
+                && end <= words.len()
+                && trigger
+                    .iter()
+                    .zip(&words[i..end])
+                    .all(|(expected, (_, _, word))| expected == word)
+                && (end == words.len() || {
+                    let (start, end, _) = words[end - 1];
+                    text[start..end].ends_with(PHRASE_ENDS)
+                })
+        });
+        match matched {
+            Some((trigger, replacement)) => {
+                let (phrase_start, _, _) = words[i];
+                let (_, phrase_end, _) = words[i + trigger.len() - 1];
+                output.push_str(&text[copied_to..phrase_start]);
+                output.push_str(replacement);
+                copied_to = phrase_end;
+                i += trigger.len();
+            }
+            None => i += 1,
+        }
+    }
+    output.push_str(&text[copied_to..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(trigger: &str, text: &str) -> Snippet {
+        Snippet {
+            trigger: trigger.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_expand_snippets() {
+        let snippets = vec![
+            snippet("insert my address", "221B Baker Street\nLondon"),
+            snippet("sign off", "Cheers"),
+            snippet("sign off formal", "Kind regards,\nAda"),
+        ];
+
+        assert_eq!(
+            expand_snippets("Insert my address.", &snippets),
+            "221B Baker Street\nLondon"
+        );
+        assert_eq!(
+            expand_snippets("Thanks again. Sign off, formal!", &snippets),
+            "Thanks again. Kind regards,\nAda"
+        );
+        assert_eq!(
+            expand_snippets("Please sign off the report. Sign off.", &snippets),
+            "Please sign off the report. Cheers"
+        );
+        assert_eq!(
+            expand_snippets("Nothing to expand here.", &snippets),
+            "Nothing to expand here."
+        );
+    }
+}
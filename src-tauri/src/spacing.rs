@@ -0,0 +1,94 @@
+/// The next word follows these without a space
+const OPENERS: &[char] = &['(', '[', '{', '"', '\'', '“', '‘', '/', '-', '@', '#'];
+
+/// A word after these starts a new sentence
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', '…'];
+
+/// Punctuation that attaches to the word before it
+const ATTACHING: &[char] = &[',', '.', ';', ':', '!', '?', ')', ']', '}', '…'];
+
+fn capitalize_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Lowercase the first word unless it's "I", an acronym or another word
+/// that's capitalized on its own
+fn lowercase_first_word(text: &str) -> String {
+    let word = text
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or("");
+    let keep = word == "I"
+        || text.starts_with("I'")
+        || text.starts_with("I’")
+        || word.chars().skip(1).any(char::is_uppercase);
+    if keep {
+        return text.to_string();
+    }
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Fit `text` to the characters `before` the cursor: a space is added when it
+/// would otherwise run into the previous word, and the first word is only
+/// capitalized at the start of a sentence. Empty `before` means the start of
+/// the field.
+pub fn fit_to_context(text: &str, before: &str) -> String {
+    let trimmed = text.trim_start();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+
+    let last = before.chars().last();
+    let last_visible = before.chars().rev().find(|c| !c.is_whitespace());
+    let sentence_start =
+        before.ends_with('\n') || last_visible.is_none_or(|c| SENTENCE_ENDS.contains(&c));
+    let needs_space = last.is_some_and(|c| {
+        !c.is_whitespace() && !OPENERS.contains(&c) && !trimmed.starts_with(ATTACHING)
+    });
+
+    let body = if sentence_start {
+        capitalize_first(trimmed)
+    } else {
+        lowercase_first_word(trimmed)
+    };
+    if needs_space {
+        format!(" {}", body)
+    } else {
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_to_context() {
+        // Start of the field and after a finished sentence
+        assert_eq!(fit_to_context("hello there.", ""), "Hello there.");
+        assert_eq!(fit_to_context("See you.", "d."), " See you.");
+        assert_eq!(fit_to_context("see you.", ". "), "See you.");
+        assert_eq!(fit_to_context("Next line", "x\n"), "Next line");
+
+        // Continuing a sentence
+        assert_eq!(
+            fit_to_context("And then we left.", "rk"),
+            " and then we left."
+        );
+        assert_eq!(fit_to_context("And then", "k "), "and then");
+        assert_eq!(fit_to_context("I think so", "nd"), " I think so");
+        assert_eq!(fit_to_context("NASA called", "nd"), " NASA called");
+
+        // No space after openers or before attaching punctuation
+        assert_eq!(fit_to_context("Quote", " \""), "quote");
+        assert_eq!(fit_to_context(", right?", "ok"), ", right?");
+    }
+}
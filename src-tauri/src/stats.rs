@@ -0,0 +1,161 @@
+use crate::managers::history::HistoryEntry;
+use crate::managers::usage::ProviderErrors;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+
+/// Typing speed the time saved is measured against, in words per minute
+const TYPING_WPM: f64 = 40.0;
+/// Speaking speed assumed for entries without a recorded duration
+const DEFAULT_SPEAKING_WPM: f64 = 150.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct DailyWords {
+    /// Local day, `YYYY-MM-DD`
+    pub day: String,
+    pub entry_count: i64,
+    pub word_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct ActionCount {
+    /// Shortcut action id, `unknown` for entries saved before actions were recorded
+    pub action_id: String,
+    pub count: i64,
+}
+
+/// Aggregates over the history for the stats screen
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+pub struct DictationStats {
+    pub entry_count: i64,
+    /// Words of the final (post-processed when available) text
+    pub word_count: i64,
+    /// Oldest day first, days without dictations left out
+    pub daily: Vec<DailyWords>,
+    /// Minutes of recorded audio, for entries that know their length
+    pub recorded_minutes: f64,
+    /// Spoken words per recorded minute
+    pub speaking_wpm: Option<f64>,
+    /// Time typing the final text would have taken, minus the time spent speaking
+    pub time_saved_minutes: f64,
+    /// Most used first
+    pub actions: Vec<ActionCount>,
+    /// LLM requests and failures per provider
+    pub providers: Vec<ProviderErrors>,
+}
+
+fn word_count(text: &str) -> i64 {
+    text.split_whitespace().count() as i64
+}
+
+fn local_day(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Aggregate `entries`; provider error rates come from the usage counters
+/// and are filled in by the caller
+pub fn compute(entries: &[HistoryEntry]) -> DictationStats {
+    let mut stats = DictationStats::default();
+    let mut daily: BTreeMap<String, DailyWords> = BTreeMap::new();
+    let mut actions: HashMap<String, i64> = HashMap::new();
+    let mut timed_words = 0;
+    let mut timed_minutes = 0.0;
+    let mut speaking_minutes = 0.0;
+
+    for entry in entries {
+        let spoken_words = word_count(&entry.transcription_text);
+        let words = match entry.post_processed_text.as_deref() {
+            Some(text) if !text.trim().is_empty() => word_count(text),
+            _ => spoken_words,
+        };
+        stats.entry_count += 1;
+        stats.word_count += words;
+
+        let day = local_day(entry.timestamp);
+        let day_stats = daily.entry(day.clone()).or_insert(DailyWords {
+            day,
+            entry_count: 0,
+            word_count: 0,
+        });
+        day_stats.entry_count += 1;
+        day_stats.word_count += words;
+
+        let action_id = entry.action_id.as_deref().unwrap_or("unknown");
+        *actions.entry(action_id.to_string()).or_default() += 1;
+
+        match entry.duration_ms.filter(|duration| *duration > 0) {
+            Some(duration) => {
+                let minutes = duration as f64 / 60_000.0;
+                timed_words += spoken_words;
+                timed_minutes += minutes;
+                speaking_minutes += minutes;
+            }
+            None => speaking_minutes += spoken_words as f64 / DEFAULT_SPEAKING_WPM,
+        }
+    }
+
+    stats.daily = daily.into_values().collect();
+    stats.recorded_minutes = timed_minutes;
+    if timed_minutes > 0.0 {
+        stats.speaking_wpm = Some(timed_words as f64 / timed_minutes);
+    }
+    stats.time_saved_minutes = (stats.word_count as f64 / TYPING_WPM - speaking_minutes).max(0.0);
+
+    let mut actions: Vec<ActionCount> = actions
+        .into_iter()
+        .map(|(action_id, count)| ActionCount { action_id, count })
+        .collect();
+    actions.sort_by(|a, b| b.count.cmp(&a.count).then(a.action_id.cmp(&b.action_id)));
+    stats.actions = actions;
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(raw: &str, processed: Option<&str>, action: &str, duration_ms: i64) -> HistoryEntry {
+        HistoryEntry {
+            id: 0,
+            file_name: String::new(),
+            timestamp: 0,
+            saved: false,
+            title: String::new(),
+            transcription_text: raw.to_string(),
+            post_processed_text: processed.map(str::to_string),
+            post_process_prompt: None,
+            action_id: Some(action.to_string()),
+            duration_ms: Some(duration_ms),
+            audio_deleted: false,
+            app_name: None,
+            run_id: None,
+        }
+    }
+
+    #[test]
+    fn test_compute() {
+        let entries = [
+            entry("one two three four", None, "transcribe", 1_000),
+            entry("um five six", Some("Five six."), "transcribe", 1_000),
+            entry("seven", None, "translate", 0),
+        ];
+        let stats = compute(&entries);
+        assert_eq!(stats.entry_count, 3);
+        assert_eq!(stats.word_count, 4 + 2 + 1);
+        assert_eq!(stats.daily.len(), 1);
+        assert_eq!(stats.daily[0].word_count, 7);
+        // 7 spoken words in two seconds of timed audio
+        assert_eq!(stats.speaking_wpm, Some(210.0));
+        assert_eq!(stats.actions[0].action_id, "transcribe");
+        assert_eq!(stats.actions[0].count, 2);
+        assert!(stats.time_saved_minutes > 0.0);
+
+        assert_eq!(compute(&[]).speaking_wpm, None);
+    }
+}
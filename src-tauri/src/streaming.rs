@@ -0,0 +1,140 @@
+use crate::clipboard;
+use crate::input::{self, EnigoState};
+use crate::settings::get_settings;
+use log::{debug, error};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+
+#[cfg(target_os = "linux")]
+use crate::utils::is_wayland;
+#[cfg(target_os = "linux")]
+use crate::wayland;
+
+/// Part of `text` that's unlikely to change: everything up to the last
+/// whitespace, so half-received words aren't typed and then corrected
+pub fn settled_prefix(text: &str) -> &str {
+    match text.rfind(char::is_whitespace) {
+        Some(index) => &text[..index],
+        None => "",
+    }
+}
+
+/// Backspaces and text that turn `injected` into `target`: whatever follows
+/// their common prefix is removed and retyped. The prefix is the same bytes
+/// in both, so its length is a char boundary of `target` too.
+pub fn edit_between<'a>(injected: &str, target: &'a str) -> (usize, &'a str) {
+    let common = injected
+        .char_indices()
+        .zip(target.chars())
+        .find(|((_, injected_char), target_char)| injected_char != target_char)
+        .map_or(injected.len().min(target.len()), |((index, _), _)| index);
+    // CRLF line breaks are removed with a single backspace
+    let backspaces = injected[common..].chars().filter(|c| *c != '\r').count();
+    (backspaces, &target[common..])
+}
+
+/// Types text into the focused app while it's still being generated.
+/// Revised text is corrected by backspacing to where it changed.
+pub struct StreamingInjection {
+    app: AppHandle,
+    /// What the focused app has received so far
+    injected: Arc<Mutex<String>>,
+}
+
+impl StreamingInjection {
+    pub fn new(app: &AppHandle) -> Self {
+        Self {
+            app: app.clone(),
+            injected: Arc::new(Mutex::new(String::new())),
+        }
+    }
+
+    /// Type the settled part of the text received so far
+    pub fn update(&self, text: &str) {
+        self.inject(settled_prefix(text).to_string());
+    }
+
+    /// Bring the injected text in line with the final `text`
+    pub fn finish(&self, text: &str) {
+        let text = if get_settings(&self.app).append_trailing_space {
+            format!("{} ", text)
+        } else {
+            text.to_string()
+        };
+        self.inject(text);
+        let injected = Arc::clone(&self.injected);
+        let result = self.app.run_on_main_thread(move || {
+            let injected = injected.lock().unwrap();
+            debug!("Streamed {} characters", injected.chars().count());
+            clipboard::record_injection(&injected);
+        });
+        if let Err(e) = result {
+            error!("Failed to run on main thread: {:?}", e);
+        }
+    }
+
+    /// Queue the edit on the main thread, which runs them in order
+    fn inject(&self, target: String) {
+        let app = self.app.clone();
+        let injected = Arc::clone(&self.injected);
+        let result = self.app.run_on_main_thread(move || {
+            let mut injected = injected.lock().unwrap();
+            let (backspaces, insert) = edit_between(&injected, &target);
+            if backspaces == 0 && insert.is_empty() {
+                return;
+            }
+            match apply_edit(&app, backspaces, insert) {
+                Ok(()) => *injected = target,
+                Err(e) => error!("Failed to inject streamed text: {}", e),
+            }
+        });
+        if let Err(e) = result {
+            error!("Failed to run streaming injection on main thread: {:?}", e);
+        }
+    }
+}
+
+fn apply_edit(app: &AppHandle, backspaces: usize, insert: &str) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    if is_wayland()
+        && wayland::send_backspaces(backspaces)?
+        && (insert.is_empty() || wayland::type_text(insert, None)?)
+    {
+        return Ok(());
+    }
+
+    let enigo_state = app
+        .try_state::<EnigoState>()
+        .ok_or("Enigo state not initialized")?;
+    let mut enigo = enigo_state
+        .0
+        .lock()
+        .map_err(|e| format!("Failed to lock Enigo: {}", e))?;
+    input::send_backspaces(&mut enigo, backspaces)?;
+    if !insert.is_empty() {
+        input::paste_text_direct(&mut enigo, insert)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settled_prefix() {
+        assert_eq!(settled_prefix("Hello wor"), "Hello");
+        assert_eq!(settled_prefix("Hello world. "), "Hello world.");
+        assert_eq!(settled_prefix("Hel"), "");
+    }
+
+    #[test]
+    fn test_edit_between() {
+        assert_eq!(edit_between("", "Hello"), (0, "Hello"));
+        assert_eq!(edit_between("Hello", "Hello world"), (0, " world"));
+        // A revised word is backspaced and retyped
+        assert_eq!(edit_between("Hello wrld", "Hello world"), (3, "orld"));
+        assert_eq!(edit_between("Grüße, Tom", "Grüße, Tim"), (2, "im"));
+        assert_eq!(edit_between("same", "same"), (0, ""));
+    }
+}
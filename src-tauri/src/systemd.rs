@@ -0,0 +1,217 @@
+//! Background startup on Linux as a systemd user service, an alternative to
+//! the XDG autostart entry that restarts Babbl when it crashes. The unit is
+//! bound to `graphical-session.target`, which desktops only reach once they
+//! imported `DISPLAY`, `WAYLAND_DISPLAY` and friends into the user manager;
+//! installing also imports them from the current session, for window
+//! managers that don't. Managed from the settings or with
+//! `--install-service` / `--uninstall-service`.
+
+use crate::autostart;
+use log::{info, warn};
+use serde::Serialize;
+use specta::Type;
+use std::path::PathBuf;
+use std::process::Command;
+
+pub const INSTALL_ARG: &str = "--install-service";
+pub const UNINSTALL_ARG: &str = "--uninstall-service";
+
+const UNIT_NAME: &str = "babbl.service";
+
+/// Session variables the input hook, the tray and the windows need
+const SESSION_VARIABLES: [&str; 6] = [
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XAUTHORITY",
+    "XDG_SESSION_TYPE",
+    "XDG_CURRENT_DESKTOP",
+    "DBUS_SESSION_BUS_ADDRESS",
+];
+
+#[derive(Serialize, Debug, Clone, Type)]
+pub struct ServiceStatus {
+    /// Whether systemd user services can be used here
+    pub supported: bool,
+    pub installed: bool,
+    pub enabled: bool,
+    pub unit_path: Option<String>,
+}
+
+fn unit_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("systemd").join("user").join(UNIT_NAME))
+}
+
+/// `arg` as one word of an `ExecStart=` line
+fn quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+fn unit_contents(exe: &str, args: &[&str]) -> String {
+    let command = std::iter::once(exe)
+        .chain(args.iter().copied())
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "[Unit]\n\
+         Description=Babbl speech to text\n\
+         PartOf=graphical-session.target\n\
+         After=graphical-session.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=graphical-session.target\n",
+        command
+    )
+}
+
+/// The executable to start, the image itself for an AppImage
+fn executable() -> Result<String, String> {
+    let exe = match std::env::var_os("APPIMAGE") {
+        Some(appimage) => PathBuf::from(appimage),
+        None => std::env::current_exe()
+            .map_err(|e| format!("Failed to find the Babbl executable: {}", e))?,
+    };
+    exe.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("The Babbl executable path isn't UTF-8: {:?}", exe))
+}
+
+fn systemctl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run systemctl: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() {
+        Ok(stdout)
+    } else {
+        Err(format!(
+            "systemctl --user {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn ensure_supported() -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("systemd services are only available on Linux".to_string());
+    }
+    systemctl(&["--version"])
+        .map(|_| ())
+        .map_err(|e| format!("systemd user services aren't available: {}", e))
+}
+
+/// Write and enable the unit; it starts Babbl from the next login
+pub fn install() -> Result<PathBuf, String> {
+    ensure_supported()?;
+    let path = unit_path().ok_or("Failed to find the systemd user directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = unit_contents(&executable()?, &autostart::launch_args());
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let present: Vec<&str> = SESSION_VARIABLES
+        .iter()
+        .copied()
+        .filter(|name| std::env::var_os(name).is_some())
+        .collect();
+    if !present.is_empty() {
+        let mut args = vec!["import-environment"];
+        args.extend(present);
+        if let Err(e) = systemctl(&args) {
+            warn!("{}", e);
+        }
+    }
+    systemctl(&["daemon-reload"])?;
+    // Not started now, that would be a second Babbl next to this one
+    systemctl(&["enable", UNIT_NAME])?;
+    info!("Installed the systemd user service at {}", path.display());
+    Ok(path)
+}
+
+/// Disable and remove the unit. A Babbl the service runs keeps running.
+pub fn uninstall() -> Result<(), String> {
+    ensure_supported()?;
+    let path = unit_path().ok_or("Failed to find the systemd user directory")?;
+    if !path.exists() {
+        return Ok(());
+    }
+    if let Err(e) = systemctl(&["disable", UNIT_NAME]) {
+        warn!("{}", e);
+    }
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    systemctl(&["daemon-reload"])?;
+    info!("Removed the systemd user service");
+    Ok(())
+}
+
+pub fn status() -> ServiceStatus {
+    let path = unit_path();
+    let installed = path.as_ref().is_some_and(|path| path.exists());
+    ServiceStatus {
+        supported: ensure_supported().is_ok(),
+        installed,
+        enabled: installed
+            && systemctl(&["is-enabled", UNIT_NAME]).is_ok_and(|out| out == "enabled"),
+        unit_path: path.map(|path| path.display().to_string()),
+    }
+}
+
+/// Handle `--install-service` or `--uninstall-service` and return the exit
+/// code, before anything else starts; `None` for a regular launch
+pub fn run_cli() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = if args.iter().any(|arg| arg == INSTALL_ARG) {
+        install().map(|path| format!("Installed {}", path.display()))
+    } else if args.iter().any(|arg| arg == UNINSTALL_ARG) {
+        uninstall().map(|()| format!("Removed {}", UNIT_NAME))
+    } else {
+        return None;
+    };
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_contents() {
+        assert_eq!(quote("/opt/Babbl 1.0/babbl"), "\"/opt/Babbl 1.0/babbl\"");
+        assert_eq!(quote("100%$\"x\""), "\"100%%$$\\\"x\\\"\"");
+
+        let unit = unit_contents("/usr/bin/babbl", &["--autostart"]);
+        assert!(unit.contains("ExecStart=\"/usr/bin/babbl\" \"--autostart\"\n"));
+        assert!(unit.contains("WantedBy=graphical-session.target\n"));
+    }
+}
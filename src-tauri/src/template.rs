@@ -0,0 +1,57 @@
+//! Filling of prompt and note templates in a single pass: a value put in is
+//! never searched for placeholders again, so dictated text that looks like
+//! one stays as it is. Names without a value are left as written.
+
+/// Fill every `<open>name<close>` in `template` with `value(name)`
+pub fn render(
+    template: &str,
+    open: &str,
+    close: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(open) {
+        let after_open = &rest[start + open.len()..];
+        let end = match after_open.find(close) {
+            Some(end) => end,
+            None => break,
+        };
+        match value(&after_open[..end]) {
+            Some(value) => {
+                rendered.push_str(&rest[..start]);
+                rendered.push_str(&value);
+                rest = &after_open[end + close.len()..];
+            }
+            // Kept, but what follows the opening may still be a placeholder
+            None => {
+                rendered.push_str(&rest[..start + open.len()]);
+                rest = after_open;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let value = |name: &str| match name {
+            "text" => Some("say {{date}}".to_string()),
+            "date" => Some("2026-03-09".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            render("{{date}}: {{text}}", "{{", "}}", value),
+            "2026-03-09: say {{date}}"
+        );
+        assert_eq!(
+            render("{{unknown}} {{ {{date}} {{", "{{", "}}", value),
+            "{{unknown}} {{ 2026-03-09 {{"
+        );
+    }
+}
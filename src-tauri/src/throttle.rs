@@ -0,0 +1,83 @@
+//! Backpressure on triggers, so a bouncing key or a runaway script can't pile
+//! up dictations: a toggle pressed again right after it was handled is a
+//! bounce and dropped, collapsing the start/stop pair it would make, and a
+//! dictation only starts some time after the previous one did.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Toggle presses of one binding closer together than this are bounces
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Least time between two dictations starting
+const MIN_START_INTERVAL: Duration = Duration::from_millis(300);
+
+#[derive(Default)]
+struct Throttle {
+    /// When each binding's toggle was last handled
+    toggles: HashMap<String, Instant>,
+    last_start: Option<Instant>,
+}
+
+impl Throttle {
+    /// Whether a toggle of `binding_id` at `now` is a bounce; if not, it's
+    /// recorded as handled
+    fn is_bounce(&mut self, binding_id: &str, now: Instant) -> bool {
+        let bounced = self
+            .toggles
+            .get(binding_id)
+            .is_some_and(|last| now.saturating_duration_since(*last) < DEBOUNCE);
+        if !bounced {
+            self.toggles.insert(binding_id.to_string(), now);
+        }
+        bounced
+    }
+
+    fn start_too_soon(&self, now: Instant) -> bool {
+        self.last_start
+            .is_some_and(|last| now.saturating_duration_since(last) < MIN_START_INTERVAL)
+    }
+}
+
+static THROTTLE: Lazy<Mutex<Throttle>> = Lazy::new(|| Mutex::new(Throttle::default()));
+
+/// Whether a toggle-mode press of `binding_id` came too soon after the last
+/// one to be meant; drop it if so
+pub fn is_bounce(binding_id: &str) -> bool {
+    THROTTLE
+        .lock()
+        .unwrap()
+        .is_bounce(binding_id, Instant::now())
+}
+
+/// Whether a dictation starting now would follow the last one too closely
+pub fn start_too_soon() -> bool {
+    THROTTLE.lock().unwrap().start_too_soon(Instant::now())
+}
+
+/// Note that a dictation starts now
+pub fn record_start() {
+    THROTTLE.lock().unwrap().last_start = Some(Instant::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = Throttle::default();
+        let now = Instant::now();
+        assert!(!throttle.is_bounce("transcribe", now));
+        assert!(throttle.is_bounce("transcribe", now + Duration::from_millis(20)));
+        assert!(!throttle.is_bounce("mode_email", now + Duration::from_millis(20)));
+        assert!(!throttle.is_bounce("transcribe", now + DEBOUNCE));
+
+        assert!(!throttle.start_too_soon(now));
+        throttle.last_start = Some(now);
+        assert!(throttle.start_too_soon(now + Duration::from_millis(100)));
+        assert!(!throttle.start_too_soon(now + MIN_START_INTERVAL));
+    }
+}
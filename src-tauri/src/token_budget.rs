@@ -0,0 +1,123 @@
+/// Tokens kept free for the model's answer when budgeting a prompt
+pub const RESERVED_OUTPUT_TOKENS: usize = 2048;
+
+/// Context window assumed for models we don't recognise
+const DEFAULT_CONTEXT_WINDOW: usize = 8_192;
+
+/// Known context windows, matched against the lowercased model name by prefix.
+/// More specific entries come first.
+const CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-5", 400_000),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4", 200_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5", 16_385),
+    ("claude", 200_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini", 1_048_576),
+    ("llama-3.1", 131_072),
+    ("llama-3.2", 131_072),
+    ("llama-3.3", 131_072),
+    ("llama3.1", 131_072),
+    ("llama3.2", 131_072),
+    ("llama3.3", 131_072),
+    ("llama-4", 131_072),
+    ("qwen", 32_768),
+    ("mixtral", 32_768),
+    ("mistral", 32_768),
+    ("gemma", 8_192),
+];
+
+/// Rough token estimate (~4 characters per token for English text)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Context window of a model, falling back to a conservative default
+pub fn context_window_for_model(model: &str) -> usize {
+    let model = model.to_lowercase();
+    // Strip provider prefixes such as "openai/" (OpenRouter) or "models/" (Gemini)
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    CONTEXT_WINDOWS
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, window)| *window)
+        .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+}
+
+/// Tokens left for the transcript after the prompt overhead and the reserved output
+pub fn transcript_budget(model: &str, overhead_tokens: usize) -> usize {
+    context_window_for_model(model)
+        .saturating_sub(RESERVED_OUTPUT_TOKENS)
+        .saturating_sub(overhead_tokens)
+        .max(256)
+}
+
+/// Byte index at which `text` should be cut to stay within `max_tokens`,
+/// moved back to the last whitespace so words aren't split
+fn cut_index(text: &str, max_tokens: usize) -> usize {
+    let max_chars = max_tokens * 4;
+    let hard_cut = match text.char_indices().nth(max_chars) {
+        Some((index, _)) => index,
+        None => return text.len(),
+    };
+    match text[..hard_cut].rfind(char::is_whitespace) {
+        Some(index) if index > 0 => index,
+        _ => hard_cut,
+    }
+}
+
+/// Keep the beginning of `text` that fits into `max_tokens`
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    text[..cut_index(text, max_tokens)].trim_end().to_string()
+}
+
+/// Split `text` into consecutive chunks of at most `max_tokens` each
+pub fn chunk_text(text: &str, max_tokens: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let index = cut_index(rest, max_tokens.max(1));
+        chunks.push(rest[..index].trim().to_string());
+        rest = rest[index..].trim_start();
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_window_lookup() {
+        assert_eq!(context_window_for_model("gpt-4o-mini"), 128_000);
+        assert_eq!(context_window_for_model("openai/gpt-4o"), 128_000);
+        assert_eq!(context_window_for_model("llama-3.3-70b-versatile"), 131_072);
+        assert_eq!(context_window_for_model("claude-3-5-haiku-latest"), 200_000);
+        assert_eq!(context_window_for_model("gpt-4"), 8_192);
+        assert_eq!(
+            context_window_for_model("some-unknown-model"),
+            DEFAULT_CONTEXT_WINDOW
+        );
+    }
+
+    #[test]
+    fn test_truncate_respects_word_boundaries() {
+        let text = "alpha beta gamma delta";
+        assert_eq!(truncate_to_tokens(text, 3), "alpha beta");
+        assert_eq!(truncate_to_tokens(text, 100), text);
+    }
+
+    #[test]
+    fn test_chunks_cover_all_words() {
+        let text = "one two three four five six seven eight nine ten";
+        let chunks = chunk_text(text, 3);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| estimate_tokens(chunk) <= 3));
+        assert_eq!(chunks.join(" "), text);
+    }
+}
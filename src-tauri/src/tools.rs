@@ -0,0 +1,148 @@
+use crate::llm_client::{ChatCompletionOutput, LlmClient};
+use crate::llm_error::LlmError;
+use crate::llm_types::{ChatCompletionRequest, ChatRequestMessage, Tool, ToolCall, Usage};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::AppHandle;
+
+/// Upper bound on model <-> tool round trips for a single request
+const MAX_TOOL_ROUNDS: usize = 4;
+
+pub type ToolHandler = Arc<dyn Fn(&AppHandle, &Value) -> Result<String, String> + Send + Sync>;
+
+struct RegisteredTool {
+    definition: Tool,
+    handler: ToolHandler,
+}
+
+/// Tools the model is allowed to call. Calls to anything not registered here are refused.
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: HashMap<String, RegisteredTool>,
+}
+
+impl ToolRegistry {
+    pub fn register(&mut self, definition: Tool, handler: ToolHandler) {
+        self.tools.insert(
+            definition.function.name.clone(),
+            RegisteredTool {
+                definition,
+                handler,
+            },
+        );
+    }
+
+    /// Tool definitions to advertise in a request, sorted by name
+    pub fn definitions(&self) -> Vec<Tool> {
+        let mut definitions: Vec<Tool> = self
+            .tools
+            .values()
+            .map(|tool| tool.definition.clone())
+            .collect();
+        definitions.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+        definitions
+    }
+
+    /// Run a tool call. Failures are reported back to the model rather than aborting.
+    pub fn dispatch(&self, app: &AppHandle, call: &ToolCall) -> String {
+        let tool = match self.tools.get(&call.function.name) {
+            Some(tool) => tool,
+            None => {
+                warn!("Model requested unknown tool '{}'", call.function.name);
+                return format!("Error: unknown tool '{}'", call.function.name);
+            }
+        };
+
+        let arguments = if call.function.arguments.trim().is_empty() {
+            json!({})
+        } else {
+            match serde_json::from_str::<Value>(&call.function.arguments) {
+                Ok(arguments) => arguments,
+                Err(e) => return format!("Error: invalid arguments: {}", e),
+            }
+        };
+
+        debug!(
+            "Dispatching tool '{}' with arguments {}",
+            call.function.name, arguments
+        );
+        match (tool.handler)(app, &arguments) {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("Tool '{}' failed: {}", call.function.name, e);
+                format!("Error: {}", e)
+            }
+        }
+    }
+}
+
+pub static TOOL_REGISTRY: Lazy<ToolRegistry> = Lazy::new(|| {
+    let mut registry = ToolRegistry::default();
+    registry.register(
+        Tool::function(
+            "open_settings",
+            "Open the Babbl settings window.",
+            json!({ "type": "object", "properties": {} }),
+        ),
+        Arc::new(|app, _| {
+            let handle = app.clone();
+            app.run_on_main_thread(move || crate::show_main_window(&handle))
+                .map_err(|e| format!("Failed to open settings: {}", e))?;
+            Ok("Settings window opened.".to_string())
+        }),
+    );
+    registry.register(
+        Tool::function(
+            "insert_date",
+            "Get today's local date to insert into the text.",
+            json!({ "type": "object", "properties": {} }),
+        ),
+        Arc::new(|_, _| Ok(chrono::Local::now().format("%Y-%m-%d (%A)").to_string())),
+    );
+    registry
+});
+
+/// Run a chat completion advertising `registry`'s tools, executing requested tool calls
+/// until the model answers with text
+pub async fn complete_with_tools(
+    client: &LlmClient,
+    app: &AppHandle,
+    mut request: ChatCompletionRequest,
+    registry: &ToolRegistry,
+) -> Result<ChatCompletionOutput, LlmError> {
+    let mut total_usage: Option<Usage> = None;
+    for _ in 0..MAX_TOOL_ROUNDS {
+        let mut output = client.send_chat_request(&request).await?;
+
+        if let Some(usage) = output.usage {
+            let total = total_usage.get_or_insert_with(Usage::default);
+            total.prompt_tokens += usage.prompt_tokens;
+            total.completion_tokens += usage.completion_tokens;
+            total.total_tokens += usage.total_tokens;
+        }
+
+        if output.tool_calls.is_empty() {
+            output.usage = total_usage;
+            return Ok(output);
+        }
+
+        let content = Some(output.content).filter(|content| !content.is_empty());
+        let assistant_message =
+            ChatRequestMessage::assistant_tool_calls(content, output.tool_calls.clone());
+        request.messages.push(assistant_message);
+        for call in &output.tool_calls {
+            let result = registry.dispatch(app, call);
+            request
+                .messages
+                .push(ChatRequestMessage::tool_result(call.id.clone(), result));
+        }
+    }
+
+    Err(LlmError::from(format!(
+        "Model was still calling tools after {} rounds",
+        MAX_TOOL_ROUNDS
+    )))
+}
@@ -1,8 +1,16 @@
-use crate::settings;
+use crate::audio_toolkit::list_input_devices;
+use crate::profiles;
+use crate::settings::{self, AppSettings};
+use crate::settings_validation::SettingsError;
+use crate::shortcut;
 use tauri::image::Image;
-use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
 use tauri::tray::TrayIcon;
-use tauri::{AppHandle, Manager, Theme};
+use tauri::{AppHandle, Manager, Theme, Wry};
+
+/// Prefix of the microphone menu item ids, followed by the device name or
+/// `default`
+pub const MICROPHONE_MENU_PREFIX: &str = "microphone:";
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum TrayIconState {
@@ -91,6 +99,20 @@ pub fn change_tray_icon(app: &AppHandle, icon: TrayIconState) {
     update_tray_menu(app, &icon);
 }
 
+/// Rebuild the menu, which lists the profiles and microphones and shows
+/// whether update checks are on
+pub fn on_settings_changed(
+    app: &AppHandle,
+    _previous: &AppSettings,
+    _next: &AppSettings,
+) -> Vec<SettingsError> {
+    // The idle menu is rebuilt anyway when the dictation ends
+    if !crate::pipeline::is_busy() {
+        update_tray_menu(app, &TrayIconState::Idle);
+    }
+    Vec::new()
+}
+
 pub fn update_tray_menu(app: &AppHandle, state: &TrayIconState) {
     let settings = settings::get_settings(app);
 
@@ -117,9 +139,114 @@ pub fn update_tray_menu(app: &AppHandle, state: &TrayIconState) {
     let quit_i = MenuItem::with_id(app, "quit", "Quit", true, quit_accelerator)
         .expect("failed to create quit item");
     let separator = || PredefinedMenuItem::separator(app).expect("failed to create separator");
+    let pause_i = CheckMenuItem::with_id(
+        app,
+        "pause_shortcuts",
+        "Pause Shortcuts",
+        true,
+        shortcut::shortcuts_paused(),
+        None::<&str>,
+    )
+    .expect("failed to create pause shortcuts item");
+    let meeting_i = MenuItem::with_id(
+        app,
+        "meeting",
+        if crate::meeting::current().is_some() {
+            "Stop Meeting Transcription"
+        } else {
+            "Transcribe Meeting"
+        },
+        true,
+        None::<&str>,
+    )
+    .expect("failed to create meeting item");
+    let wake_word_label = if crate::wake_word::is_listening() {
+        format!("Listening for \"{}\"", settings.wake_word_phrase)
+    } else {
+        format!("Listen for \"{}\"", settings.wake_word_phrase)
+    };
+    let wake_word_i = CheckMenuItem::with_id(
+        app,
+        "wake_word",
+        &wake_word_label,
+        true,
+        settings.wake_word_enabled,
+        None::<&str>,
+    )
+    .expect("failed to create wake word item");
+    let copy_last_i = MenuItem::with_id(
+        app,
+        "copy_last_transcript",
+        "Copy Last Transcript",
+        true,
+        None::<&str>,
+    )
+    .expect("failed to create copy last transcript item");
+
+    // Microphone picker, "Default" first
+    let mut microphones = vec![("default".to_string(), "Default".to_string())];
+    match list_input_devices() {
+        Ok(devices) => {
+            microphones.extend(devices.into_iter().map(|d| (d.name.clone(), d.name)));
+        }
+        Err(e) => log::warn!("Failed to list microphones for the tray: {}", e),
+    }
+    let selected_microphone = settings.selected_microphone.as_deref().unwrap_or("default");
+    let microphone_items: Vec<CheckMenuItem<Wry>> = microphones
+        .iter()
+        .map(|(id, name)| {
+            CheckMenuItem::with_id(
+                app,
+                format!("{}{}", MICROPHONE_MENU_PREFIX, id),
+                name,
+                true,
+                id == selected_microphone,
+                None::<&str>,
+            )
+            .expect("failed to create microphone item")
+        })
+        .collect();
+    let microphone_refs: Vec<&dyn IsMenuItem<Wry>> = microphone_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    let microphone_menu = Submenu::with_items(app, "Microphone", true, &microphone_refs)
+        .expect("failed to create microphone menu");
+
+    // Profile switcher, with a check mark on the active profile
+    let profile_items: Vec<CheckMenuItem<Wry>> = settings
+        .profiles
+        .iter()
+        .map(|profile| {
+            CheckMenuItem::with_id(
+                app,
+                format!("{}{}", profiles::TRAY_MENU_PREFIX, profile.id),
+                &profile.name,
+                true,
+                settings.active_profile_id.as_deref() == Some(profile.id.as_str()),
+                None::<&str>,
+            )
+            .expect("failed to create profile item")
+        })
+        .collect();
+    let profile_refs: Vec<&dyn IsMenuItem<Wry>> = profile_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    let profiles_menu = (!profile_refs.is_empty()).then(|| {
+        Submenu::with_items(app, "Profile", true, &profile_refs)
+            .expect("failed to create profile menu")
+    });
 
     let menu = match state {
         TrayIconState::Recording | TrayIconState::Transcribing => {
+            let status = if *state == TrayIconState::Recording {
+                "Recording..."
+            } else {
+                "Transcribing..."
+            };
+            let status_i = MenuItem::with_id(app, "status", status, false, None::<&str>)
+                .expect("failed to create status item");
             let cancel_i = MenuItem::with_id(app, "cancel", "Cancel", true, None::<&str>)
                 .expect("failed to create cancel item");
             Menu::with_items(
@@ -127,6 +254,7 @@ pub fn update_tray_menu(app: &AppHandle, state: &TrayIconState) {
                 &[
                     &version_i,
                     &separator(),
+                    &status_i,
                     &cancel_i,
                     &separator(),
                     &settings_i,
@@ -137,18 +265,30 @@ pub fn update_tray_menu(app: &AppHandle, state: &TrayIconState) {
             )
             .expect("failed to create menu")
         }
-        TrayIconState::Idle => Menu::with_items(
-            app,
-            &[
+        TrayIconState::Idle => {
+            let (top_separator, quick_separator, bottom_separator) =
+                (separator(), separator(), separator());
+            let mut items: Vec<&dyn IsMenuItem<Wry>> = vec![
                 &version_i,
-                &separator(),
-                &settings_i,
+                &top_separator,
+                &copy_last_i,
+                &meeting_i,
+                &pause_i,
+                &wake_word_i,
+                &microphone_menu,
+            ];
+            if let Some(profiles_menu) = &profiles_menu {
+                items.push(profiles_menu);
+            }
+            items.push(&quick_separator);
+            items.extend([
+                &settings_i as &dyn IsMenuItem<Wry>,
                 &check_updates_i,
-                &separator(),
+                &bottom_separator,
                 &quit_i,
-            ],
-        )
-        .expect("failed to create menu"),
+            ]);
+            Menu::with_items(app, &items).expect("failed to create menu")
+        }
     };
 
     let tray = app.state::<TrayIcon>();
@@ -0,0 +1,250 @@
+//! Updates. Babbl checks the release channel picked in the settings in the
+//! background and downloads a new version right away; the updater plugin
+//! verifies its signature against the public key in `tauri.conf.json`
+//! before it's kept. Installing waits for "restart to update", which lets
+//! a dictation underway finish and then restarts through the orderly
+//! shutdown, installing the update on the way out. Updates are downloaded
+//! whole, the updater has no delta format.
+
+use crate::pipeline;
+use crate::settings::{self, AppSettings, UpdateChannel};
+use crate::settings_validation::SettingsError;
+use log::{debug, error, info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use specta::Type;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// Where beta releases are published; stable ones come from the endpoints
+/// in `tauri.conf.json`
+const BETA_ENDPOINT: &str =
+    "https://github.com/avijitbhuin21/Babbl/releases/download/beta/latest.json";
+
+/// Wait after launch before the first check, to keep it off the startup
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How often a pending restart looks whether the dictation is done
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub channel: UpdateChannel,
+    /// Downloaded and verified, ready to install on restart
+    pub ready: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct DownloadProgress {
+    pub version: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+struct Downloaded {
+    update: Update,
+    channel: UpdateChannel,
+    bytes: Vec<u8>,
+}
+
+#[derive(Default)]
+struct UpdaterState {
+    /// Version being downloaded
+    downloading: Option<String>,
+    ready: Option<Downloaded>,
+}
+
+static STATE: Lazy<Mutex<UpdaterState>> = Lazy::new(|| Mutex::new(UpdaterState::default()));
+
+static RESTART_PENDING: AtomicBool = AtomicBool::new(false);
+
+fn info(update: &Update, channel: UpdateChannel, ready: bool) -> UpdateInfo {
+    UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        channel,
+        ready,
+    }
+}
+
+async fn find_update(app: &AppHandle, channel: UpdateChannel) -> Result<Option<Update>, String> {
+    let mut builder = app.updater_builder();
+    if channel == UpdateChannel::Beta {
+        let url = Url::parse(BETA_ENDPOINT).map_err(|e| e.to_string())?;
+        builder = builder.endpoints(vec![url]).map_err(|e| e.to_string())?;
+    }
+    let updater = builder.build().map_err(|e| e.to_string())?;
+    updater.check().await.map_err(|e| e.to_string())
+}
+
+async fn download(app: AppHandle, update: Update, channel: UpdateChannel) {
+    let version = update.version.clone();
+    info!("Downloading update {} ({:?})", version, channel);
+    let mut downloaded = 0u64;
+    let result = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app.emit(
+                    "update-download-progress",
+                    DownloadProgress {
+                        version: version.clone(),
+                        downloaded,
+                        total,
+                    },
+                );
+            },
+            || debug!("Downloaded update {}", version),
+        )
+        .await;
+
+    let mut state = STATE.lock().unwrap();
+    state.downloading = None;
+    match result {
+        Ok(bytes) => {
+            // The channel changed while downloading
+            if settings::get_settings(&app).update_channel != channel {
+                return;
+            }
+            let ready = info(&update, channel, true);
+            state.ready = Some(Downloaded {
+                update,
+                channel,
+                bytes,
+            });
+            drop(state);
+            info!("Update {} is ready to install", ready.version);
+            if let Err(e) = app.emit("update-ready", ready) {
+                warn!("Failed to emit update ready: {}", e);
+            }
+        }
+        Err(e) => {
+            drop(state);
+            warn!("Failed to download update {}: {}", version, e);
+            let _ = app.emit("update-download-failed", e.to_string());
+        }
+    }
+}
+
+/// Check the selected channel for an update and start downloading it in the
+/// background when there's a new one
+pub async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let channel = settings::get_settings(app).update_channel;
+    let update = match find_update(app, channel).await? {
+        Some(update) => update,
+        None => {
+            debug!("No update on the {:?} channel", channel);
+            return Ok(None);
+        }
+    };
+
+    let mut state = STATE.lock().unwrap();
+    if let Some(ready) = &state.ready {
+        if ready.update.version == update.version && ready.channel == channel {
+            return Ok(Some(info(&ready.update, channel, true)));
+        }
+    }
+    if state.downloading.as_deref() != Some(update.version.as_str()) {
+        state.downloading = Some(update.version.clone());
+        tauri::async_runtime::spawn(download(app.clone(), update.clone(), channel));
+    }
+    Ok(Some(info(&update, channel, false)))
+}
+
+/// The update downloaded and ready to install, if any
+pub fn pending() -> Option<UpdateInfo> {
+    let state = STATE.lock().unwrap();
+    state
+        .ready
+        .as_ref()
+        .map(|ready| info(&ready.update, ready.channel, true))
+}
+
+/// Restart into the downloaded update once no dictation is underway
+pub fn restart_to_update(app: &AppHandle) -> Result<(), String> {
+    if STATE.lock().unwrap().ready.is_none() {
+        return Err("No update is ready to install".to_string());
+    }
+    if RESTART_PENDING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if pipeline::is_busy() {
+            info!("Restarting to update once the dictation is done");
+            let _ = app.emit("update-restart-pending", ());
+            while pipeline::is_busy() {
+                std::thread::sleep(IDLE_POLL_INTERVAL);
+            }
+        }
+        // Goes through the shutdown, which calls `install_pending`
+        app.request_restart();
+    });
+    Ok(())
+}
+
+/// Install the downloaded update when a restart into it was asked for; run
+/// by the shutdown, once the work in flight is done
+pub fn install_pending() {
+    if !RESTART_PENDING.load(Ordering::SeqCst) {
+        return;
+    }
+    let ready = match STATE.lock().unwrap().ready.take() {
+        Some(ready) => ready,
+        None => return,
+    };
+    info!("Installing update {}", ready.update.version);
+    // On Windows the installer takes over and exits Babbl
+    if let Err(e) = ready.update.install(&ready.bytes) {
+        error!("Failed to install update {}: {}", ready.update.version, e);
+    }
+}
+
+/// Check for updates periodically while update checks are on
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        loop {
+            if settings::get_settings(&app).update_checks_enabled {
+                if let Err(e) = check(&app).await {
+                    warn!("Failed to check for updates: {}", e);
+                }
+            }
+            tokio::time::sleep(crate::power::scaled(CHECK_INTERVAL)).await;
+        }
+    });
+}
+
+/// Drop an update downloaded from the previous channel and look at the new
+/// one
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.update_channel == next.update_channel {
+        return Vec::new();
+    }
+    if !RESTART_PENDING.load(Ordering::SeqCst) {
+        STATE.lock().unwrap().ready = None;
+    }
+    if next.update_checks_enabled {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = check(&app).await {
+                warn!("Failed to check for updates: {}", e);
+            }
+        });
+    }
+    Vec::new()
+}
@@ -31,10 +31,12 @@ pub fn cancel_current_operation(app: &AppHandle) {
     // Cancel any ongoing recording
     let audio_manager = app.state::<Arc<AudioRecordingManager>>();
     audio_manager.cancel_recording();
+    crate::progress::cancel_all(crate::progress::OperationKind::Transcription, None);
+    // Drops provider requests and keeps the result from being injected
+    crate::cancellation::cancel_all();
 
-    // Update tray icon and hide overlay
-    change_tray_icon(app, crate::tray::TrayIconState::Idle);
-    hide_recording_overlay(app);
+    // Hides the overlay and resets the tray icon
+    crate::pipeline::reset(app);
 
     info!("Operation cancellation completed - returned to idle state");
 }
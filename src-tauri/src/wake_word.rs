@@ -0,0 +1,400 @@
+//! Hands-free dictation: saying the wake phrase, "hey babbl" unless it's
+//! changed, starts a recording with the transcribe action as if its shortcut
+//! was pressed, and a couple of seconds without speech end it.
+//!
+//! There's no keyword model. The phrase is recorded a few times to enroll
+//! it, and while nothing is recorded the microphone stays open and its VAD
+//! cuts what's said into utterances. The short ones are compared to the
+//! recordings by their MFCCs, aligned with dynamic time warping so a phrase
+//! said faster or slower still lines up. That's cheap enough to run on
+//! every utterance and nothing leaves the machine, but it only knows the
+//! voice and phrase it was enrolled with; the phrase setting is its name.
+//! Listening pauses in low-power mode, which closes the microphone.
+
+use crate::audio_toolkit::audio::{mfcc, MfccFrame};
+use crate::audio_toolkit::constants::WHISPER_SAMPLE_RATE;
+use crate::control_api;
+use crate::managers::audio::AudioRecordingManager;
+use crate::pipeline;
+use crate::settings::{self, AppSettings};
+use crate::settings_validation::SettingsError;
+use crate::tray::{self, TrayIconState};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const DEFAULT_PHRASE: &str = "hey babbl";
+
+/// Action the wake phrase starts
+const BINDING_ID: &str = "transcribe";
+
+/// Binding id recordings of the phrase are made under
+const ENROLL_ID: &str = "wake_word_enrollment";
+
+/// Recordings of the phrase needed before it's listened for
+pub const MIN_SAMPLES: usize = 3;
+
+/// Longer utterances are conversation, not the phrase
+pub const MAX_UTTERANCE_SAMPLES: usize = 4 * WHISPER_SAMPLE_RATE as usize;
+
+/// Shorter ones are clicks and coughs; the VAD's pre-roll and hangover alone
+/// make up almost a second
+const MIN_UTTERANCE_SAMPLES: usize = WHISPER_SAMPLE_RATE as usize;
+
+/// MFCC frames, 10 ms each, a recording of the phrase needs
+const MIN_SAMPLE_FRAMES: usize = 20;
+
+/// What's heard and a recording this many times longer than the other can't
+/// be the same phrase
+const MAX_LENGTH_RATIO: f32 = 2.0;
+
+/// Silence that ends a recording of the phrase once it was said
+const ENROLL_END_SILENCE: Duration = Duration::from_millis(700);
+
+/// Recording of the phrase stops here if it's still going
+const ENROLL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Silence that ends a dictation the wake phrase started
+const END_AFTER_SILENCE: Duration = Duration::from_secs(2);
+
+const SILENCE_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Read by the recorder, which only hands over utterances while it's set
+static LISTENING: Lazy<Arc<AtomicBool>> = Lazy::new(|| Arc::new(AtomicBool::new(false)));
+
+static UTTERANCES: Lazy<Mutex<Option<Sender<Vec<f32>>>>> = Lazy::new(|| Mutex::new(None));
+
+/// MFCCs of the recordings of the phrase
+static TEMPLATES: Lazy<Mutex<Vec<Vec<MfccFrame>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn frame_distance(a: &MfccFrame, b: &MfccFrame) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Average distance between the frames of `a` and `b` along the alignment
+/// that matches them most closely
+fn dtw_distance(a: &[MfccFrame], b: &[MfccFrame]) -> f32 {
+    let (shorter, longer) = (a.len().min(b.len()), a.len().max(b.len()));
+    if shorter == 0 || longer as f32 > shorter as f32 * MAX_LENGTH_RATIO {
+        return f32::INFINITY;
+    }
+    // Cost of the best path to each frame of `b`, one frame of `a` at a time
+    let mut previous = vec![f32::INFINITY; b.len() + 1];
+    let mut current = vec![f32::INFINITY; b.len() + 1];
+    previous[0] = 0.0;
+    for frame in a {
+        current[0] = f32::INFINITY;
+        for (j, other) in b.iter().enumerate() {
+            let best = previous[j].min(previous[j + 1]).min(current[j]);
+            current[j + 1] = best + frame_distance(frame, other);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()] / (a.len() + b.len()) as f32
+}
+
+/// How far the recordings of the phrase are from each other on average
+fn spread(templates: &[Vec<MfccFrame>]) -> f32 {
+    let distances: Vec<f32> = templates
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| templates[i + 1..].iter().map(move |b| dtw_distance(a, b)))
+        .filter(|distance| distance.is_finite())
+        .collect();
+    distances.iter().sum::<f32>() / distances.len().max(1) as f32
+}
+
+/// Distance what's heard may be from the closest recording, relative to the
+/// recordings' spread: from 0.8 times it at `sensitivity` 0 to 1.4 at 1
+fn max_distance(spread: f32, sensitivity: f32) -> f32 {
+    spread * (0.8 + 0.6 * sensitivity.clamp(0.0, 1.0))
+}
+
+/// Whether `heard` is the phrase in `templates`
+fn matches(heard: &[MfccFrame], templates: &[Vec<MfccFrame>], sensitivity: f32) -> bool {
+    if templates.len() < MIN_SAMPLES {
+        return false;
+    }
+    let max = max_distance(spread(templates), sensitivity);
+    templates
+        .iter()
+        .any(|template| dtw_distance(heard, template) <= max)
+}
+
+fn samples_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    crate::portable::app_data_dir(app)
+        .map(|dir| dir.join("wake_word"))
+        .map_err(|e| format!("Failed to find the app data folder: {}", e))
+}
+
+/// Read the recordings of the phrase into [`TEMPLATES`]
+fn load_templates(app: &AppHandle) {
+    let files = samples_dir(app)
+        .and_then(|dir| std::fs::read_dir(dir).map_err(|e| e.to_string()))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let templates: Vec<Vec<MfccFrame>> = files
+        .iter()
+        .filter_map(|path| match crate::audio_toolkit::read_wav_file(path) {
+            Ok(samples) => Some(mfcc(&samples, WHISPER_SAMPLE_RATE)),
+            Err(e) => {
+                warn!(
+                    "Failed to read wake phrase recording '{}': {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        })
+        .collect();
+    debug!("Loaded {} recordings of the wake phrase", templates.len());
+    *TEMPLATES.lock().unwrap() = templates;
+}
+
+/// Read the recordings of the phrase made before
+pub fn init(app: &AppHandle) {
+    load_templates(app);
+}
+
+/// How many recordings of the phrase there are
+pub fn sample_count() -> usize {
+    TEMPLATES.lock().unwrap().len()
+}
+
+/// Record the phrase once, until a pause after it, and keep it to recognize
+/// the phrase by. Returns how many recordings there are now.
+pub fn record_sample(app: &AppHandle) -> Result<usize, String> {
+    if pipeline::is_busy() || crate::meeting::current().is_some() {
+        return Err("Finish the dictation first".to_string());
+    }
+    let rm = app.state::<Arc<AudioRecordingManager>>();
+    if !rm.try_start_recording(ENROLL_ID) {
+        return Err("Couldn't start recording, another one may still be running".to_string());
+    }
+    let started = Instant::now();
+    while started.elapsed() < ENROLL_TIMEOUT {
+        std::thread::sleep(SILENCE_CHECK_INTERVAL);
+        // The VAD only lets speech into the recording
+        let heard = rm.buffer_usage().memory_bytes > 0;
+        if heard
+            && rm
+                .silent_for()
+                .is_some_and(|silence| silence >= ENROLL_END_SILENCE)
+        {
+            break;
+        }
+    }
+    let samples = rm.stop_recording(ENROLL_ID).unwrap_or_default();
+
+    if samples.len() > MAX_UTTERANCE_SAMPLES {
+        return Err("That was too long for a wake phrase".to_string());
+    }
+    if mfcc(&samples, WHISPER_SAMPLE_RATE).len() < MIN_SAMPLE_FRAMES {
+        return Err("Nothing was heard, say the wake phrase after starting".to_string());
+    }
+    let dir = samples_dir(app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.wav", chrono::Utc::now().timestamp_millis()));
+    tauri::async_runtime::block_on(crate::audio_toolkit::save_wav_file(&path, &samples))
+        .map_err(|e| format!("Failed to save the recording: {}", e))?;
+
+    load_templates(app);
+    sync(app);
+    Ok(sample_count())
+}
+
+/// Delete the recordings of the phrase; it isn't listened for until it's
+/// recorded again
+pub fn clear_samples(app: &AppHandle) -> Result<(), String> {
+    let dir = samples_dir(app)?;
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to delete '{}': {}", dir.display(), e))?;
+    }
+    load_templates(app);
+    sync(app);
+    Ok(())
+}
+
+/// Shared with the recorder, see [`LISTENING`]
+pub fn listening_flag() -> Arc<AtomicBool> {
+    LISTENING.clone()
+}
+
+/// Whether the wake phrase is listened for right now
+pub fn is_listening() -> bool {
+    LISTENING.load(Ordering::Relaxed)
+}
+
+/// Called by the recorder with an utterance heard while idle
+pub fn offer(samples: Vec<f32>) {
+    if let Some(sender) = UTTERANCES.lock().unwrap().as_ref() {
+        let _ = sender.send(samples);
+    }
+}
+
+/// Stop the dictation once nothing was said for a while, unless it ends
+/// before that
+fn end_after_silence(app: &AppHandle) {
+    let rm = app.state::<Arc<AudioRecordingManager>>();
+    loop {
+        std::thread::sleep(SILENCE_CHECK_INTERVAL);
+        match rm.silent_for() {
+            Some(silence) if silence >= END_AFTER_SILENCE => break,
+            Some(_) => {}
+            // Stopped or cancelled
+            None => return,
+        }
+    }
+    debug!(
+        "Ending the dictation after {:?} of silence",
+        END_AFTER_SILENCE
+    );
+    if let Err(e) = control_api::set_recording(app, BINDING_ID, false) {
+        warn!(
+            "Failed to end the dictation started by the wake word: {}",
+            e
+        );
+    }
+}
+
+/// Start a dictation when `samples` are the wake phrase
+fn handle(app: &AppHandle, samples: Vec<f32>) {
+    if !is_listening() || samples.len() < MIN_UTTERANCE_SAMPLES || pipeline::is_busy() {
+        return;
+    }
+    let settings = settings::get_settings(app);
+    let heard = mfcc(&samples, WHISPER_SAMPLE_RATE);
+    if !matches(
+        &heard,
+        &TEMPLATES.lock().unwrap(),
+        settings.wake_word_sensitivity,
+    ) {
+        return;
+    }
+    info!("Heard the wake phrase, starting a dictation");
+    match control_api::set_recording(app, BINDING_ID, true) {
+        Ok(true) => end_after_silence(app),
+        Ok(false) => {}
+        Err(e) => warn!("Failed to start a dictation on the wake word: {}", e),
+    }
+}
+
+fn run(app: AppHandle, utterances: Receiver<Vec<f32>>) {
+    while let Ok(mut samples) = utterances.recv() {
+        // Only the latest is worth checking after a dictation
+        while let Ok(newer) = utterances.try_recv() {
+            samples = newer;
+        }
+        handle(&app, samples);
+    }
+}
+
+/// Listen for the wake phrase per the settings, the power mode and whether
+/// it was recorded, and show whether it is
+pub fn sync(app: &AppHandle) {
+    let settings = settings::get_settings(app);
+    let recorded = sample_count() >= MIN_SAMPLES;
+    if settings.wake_word_enabled && !recorded {
+        warn!(
+            "Record the wake phrase {} times before it can be listened for",
+            MIN_SAMPLES
+        );
+    }
+    let listening = settings.wake_word_enabled && recorded && !crate::power::is_low_power();
+    if LISTENING.swap(listening, Ordering::Relaxed) != listening {
+        info!(
+            "{} listening for '{}'",
+            if listening { "Started" } else { "Stopped" },
+            settings.wake_word_phrase
+        );
+        if let Err(e) = app.emit("wake-word-listening", listening) {
+            warn!("Failed to emit wake word state: {}", e);
+        }
+        if !pipeline::is_busy() {
+            tray::update_tray_menu(app, &TrayIconState::Idle);
+        }
+    }
+
+    // Dropping the sender ends the thread
+    UTTERANCES.lock().unwrap().take();
+    if !listening {
+        return;
+    }
+    let (sender, receiver) = mpsc::channel();
+    *UTTERANCES.lock().unwrap() = Some(sender);
+    let app = app.clone();
+    std::thread::spawn(move || run(app, receiver));
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    // The sensitivity is read for every utterance
+    if previous.wake_word_enabled != next.wake_word_enabled {
+        sync(app);
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A made-up word: `tones` one after the other, stretched by `tempo`
+    fn word(tones: &[f32], tempo: f32) -> Vec<MfccFrame> {
+        let rate = WHISPER_SAMPLE_RATE as f32;
+        let per_tone = (0.2 * tempo * rate) as usize;
+        let samples: Vec<f32> = tones
+            .iter()
+            .flat_map(|hz| {
+                (0..per_tone)
+                    .map(move |i| 0.3 * (std::f32::consts::TAU * hz * i as f32 / rate).sin())
+            })
+            .collect();
+        mfcc(&samples, WHISPER_SAMPLE_RATE)
+    }
+
+    #[test]
+    fn test_matches() {
+        let phrase = [300.0, 800.0, 2000.0];
+        let templates: Vec<_> = [0.9, 1.0, 1.15]
+            .iter()
+            .map(|tempo| word(&phrase, *tempo))
+            .collect();
+
+        assert!(matches(&word(&phrase, 1.05), &templates, 0.5));
+        // Same sounds in another order
+        assert!(!matches(
+            &word(&[2000.0, 800.0, 300.0], 1.0),
+            &templates,
+            1.0
+        ));
+        assert!(!matches(
+            &word(&[500.0, 1200.0, 3000.0], 1.0),
+            &templates,
+            1.0
+        ));
+        // Much longer than any recording
+        assert!(!matches(&word(&phrase, 3.0), &templates, 1.0));
+        // Too few recordings to go by
+        assert!(!matches(&word(&phrase, 1.0), &templates[..2], 1.0));
+    }
+}
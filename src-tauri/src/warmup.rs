@@ -0,0 +1,153 @@
+//! Warm-up, so the first dictation after launch is as quick as the ones
+//! after it: the local transcription and LLM models are loaded, the recorder
+//! and its VAD model are readied and connections to the online providers are
+//! opened, either at launch or once Babbl has sat idle for a bit. Connections
+//! are opened again whenever a recording starts, while the user is still
+//! speaking, in case the idle ones were closed in the meantime.
+
+use crate::actions;
+use crate::http;
+use crate::managers::audio::AudioRecordingManager;
+use crate::managers::transcription::TranscriptionManager;
+use crate::pipeline;
+use crate::settings::{
+    self, AppSettings, WarmUp, APPLE_INTELLIGENCE_PROVIDER_ID, LLAMA_CPP_PROVIDER_ID,
+};
+use log::{debug, info, warn};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// How long after launch `WarmUp::Idle` waits for Babbl to settle
+const IDLE_DELAY: Duration = Duration::from_secs(20);
+
+/// URLs of the online providers the next dictation will talk to
+fn preconnect_urls(settings: &AppSettings) -> Vec<String> {
+    let mut urls = Vec::new();
+    if settings.use_online_provider {
+        if let Some(base_url) = actions::online_provider_base_url(&settings.online_provider_id) {
+            urls.push(base_url.to_string());
+        }
+    }
+    if settings.post_process_enabled {
+        let provider = settings
+            .active_post_process_provider()
+            .filter(|provider| {
+                provider.id != LLAMA_CPP_PROVIDER_ID
+                    && provider.id != APPLE_INTELLIGENCE_PROVIDER_ID
+            })
+            .map(|provider| provider.base_url.trim().trim_end_matches('/'))
+            .filter(|base_url| !base_url.is_empty());
+        if let Some(base_url) = provider {
+            urls.push(base_url.to_string());
+        }
+    }
+    urls.dedup();
+    urls
+}
+
+/// Open connections to the online providers the next dictation will use,
+/// in the background
+pub fn preconnect(app: &AppHandle) {
+    let settings = settings::get_settings(app);
+    if settings.warm_up == WarmUp::Off {
+        return;
+    }
+    let urls = preconnect_urls(&settings);
+    if urls.is_empty() {
+        return;
+    }
+    let client = match http::shared_client(&settings) {
+        Ok(client) => client,
+        Err(e) => {
+            debug!("Not connecting ahead of time: {}", e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        for url in urls {
+            http::preconnect(&client, &url).await;
+        }
+    });
+}
+
+#[cfg(feature = "llama-cpp")]
+fn preload_llm(settings: &AppSettings) {
+    if !settings.post_process_enabled || settings.post_process_provider_id != LLAMA_CPP_PROVIDER_ID
+    {
+        return;
+    }
+    if let Some(model_path) = &settings.llama_cpp_model_path {
+        if let Err(e) = crate::llama_cpp::preload(model_path, settings.llama_cpp_gpu_layers) {
+            warn!("Failed to preload the local LLM: {}", e);
+        }
+    }
+}
+
+/// Ready what the next dictation needs. Blocks while the recorder and the
+/// local LLM load; call it from a background thread.
+fn warm_up(app: &AppHandle) {
+    let settings = settings::get_settings(app);
+    let started = Instant::now();
+    info!("Warming up");
+
+    preconnect(app);
+    if !settings.use_online_provider {
+        // Loads on its own thread
+        app.state::<Arc<TranscriptionManager>>()
+            .initiate_model_load();
+    }
+    if let Err(e) = app.state::<Arc<AudioRecordingManager>>().prepare() {
+        warn!("Failed to prepare the recorder: {}", e);
+    }
+    #[cfg(feature = "llama-cpp")]
+    preload_llm(&settings);
+
+    debug!("Warmed up in {:?}", started.elapsed());
+}
+
+/// Warm up per the setting; call once the managers are managed
+pub fn init(app: &AppHandle) {
+    let warm_up_at = settings::get_settings(app).warm_up;
+    if warm_up_at == WarmUp::Off {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if warm_up_at == WarmUp::Idle {
+            std::thread::sleep(IDLE_DELAY);
+            // A dictation already underway loads what it needs itself
+            if pipeline::is_busy() {
+                debug!("Skipping the warm-up, a dictation is underway");
+                return;
+            }
+        }
+        warm_up(&app);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::get_default_settings;
+
+    #[test]
+    fn test_preconnect_urls() {
+        let mut settings = get_default_settings();
+        settings.use_online_provider = false;
+        settings.post_process_enabled = false;
+        assert!(preconnect_urls(&settings).is_empty());
+
+        settings.use_online_provider = true;
+        settings.online_provider_id = "groq".to_string();
+        assert_eq!(
+            preconnect_urls(&settings),
+            vec!["https://api.groq.com/openai/v1".to_string()]
+        );
+
+        settings.use_online_provider = false;
+        settings.post_process_enabled = true;
+        settings.post_process_provider_id = LLAMA_CPP_PROVIDER_ID.to_string();
+        assert!(preconnect_urls(&settings).is_empty());
+    }
+}
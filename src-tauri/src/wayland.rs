@@ -0,0 +1,281 @@
+use crate::settings::PasteMethod;
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// External tool that sends key events on Wayland, where the X11 events
+/// sent by enigo only reach XWayland windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputTool {
+    /// Uses the wlr virtual-keyboard protocol (Sway, Hyprland, river, ...)
+    Wtype,
+    /// Writes to uinput, so it works on every compositor including GNOME
+    Dotool,
+    /// Also uinput, through the `ydotoold` daemon
+    Ydotool,
+}
+
+/// Linux input event codes used with ydotool
+const KEY_LEFTCTRL: u16 = 29;
+const KEY_LEFTSHIFT: u16 = 42;
+const KEY_V: u16 = 47;
+const KEY_INSERT: u16 = 110;
+const KEY_BACKSPACE: u16 = 14;
+
+/// Detected once; installing a tool takes effect after a restart
+static INPUT_TOOL: Lazy<Option<InputTool>> = Lazy::new(|| {
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    let tool = detect_input_tool(&desktop, command_exists);
+    info!("Wayland input tool for desktop '{}': {:?}", desktop, tool);
+    tool
+});
+
+fn command_exists(name: &str) -> bool {
+    Command::new("which")
+        .arg(name)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Whether the compositor of `desktop` (`XDG_CURRENT_DESKTOP`) implements the
+/// virtual-keyboard protocol wtype needs. GNOME's Mutter doesn't, and wtype
+/// fails there.
+pub fn supports_virtual_keyboard(desktop: &str) -> bool {
+    !desktop
+        .split(':')
+        .any(|name| name.eq_ignore_ascii_case("gnome"))
+}
+
+fn detect_input_tool(desktop: &str, exists: impl Fn(&str) -> bool) -> Option<InputTool> {
+    if supports_virtual_keyboard(desktop) && exists("wtype") {
+        Some(InputTool::Wtype)
+    } else if exists("dotool") {
+        Some(InputTool::Dotool)
+    } else if exists("ydotool") {
+        Some(InputTool::Ydotool)
+    } else {
+        None
+    }
+}
+
+pub fn input_tool() -> Option<InputTool> {
+    *INPUT_TOOL
+}
+
+fn run(mut command: Command, stdin: Option<&str>) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait for {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed: {}", program, stderr.trim()));
+    }
+    Ok(())
+}
+
+/// Sends the keystroke of a clipboard paste method. Returns `Ok(false)` when
+/// no input tool is installed.
+pub fn send_paste_keys(paste_method: &PasteMethod) -> Result<bool, String> {
+    let tool = match input_tool() {
+        Some(tool) => tool,
+        None => return Ok(false),
+    };
+    let (wtype_args, dotool_keys, ydotool_codes): (&[&str], &str, &[u16]) = match paste_method {
+        PasteMethod::CtrlV => (&["-M", "ctrl", "-k", "v"], "ctrl+v", &[KEY_LEFTCTRL, KEY_V]),
+        PasteMethod::ShiftInsert => (
+            &["-M", "shift", "-k", "Insert"],
+            "shift+insert",
+            &[KEY_LEFTSHIFT, KEY_INSERT],
+        ),
+        PasteMethod::CtrlShiftV => (
+            &["-M", "ctrl", "-M", "shift", "-k", "v"],
+            "ctrl+shift+v",
+            &[KEY_LEFTCTRL, KEY_LEFTSHIFT, KEY_V],
+        ),
+        _ => return Err("Unsupported paste method".into()),
+    };
+
+    match tool {
+        InputTool::Wtype => {
+            let mut command = Command::new("wtype");
+            command.args(wtype_args);
+            run(command, None)?
+        }
+        InputTool::Dotool => run(
+            Command::new("dotool"),
+            Some(&format!("key {}\n", dotool_keys)),
+        )?,
+        InputTool::Ydotool => {
+            let mut command = Command::new("ydotool");
+            command.arg("key").args(ydotool_key_sequence(ydotool_codes));
+            run(command, None)?
+        }
+    }
+    debug!("Sent paste keys with {:?}", tool);
+    Ok(true)
+}
+
+/// Presses Backspace `count` times. Returns `Ok(false)` when no input tool
+/// is installed.
+pub fn send_backspaces(count: usize) -> Result<bool, String> {
+    let tool = match input_tool() {
+        Some(tool) => tool,
+        None => return Ok(false),
+    };
+    if count == 0 {
+        return Ok(true);
+    }
+
+    match tool {
+        InputTool::Wtype => {
+            let mut command = Command::new("wtype");
+            for _ in 0..count {
+                command.args(["-k", "BackSpace"]);
+            }
+            run(command, None)?
+        }
+        InputTool::Dotool => run(
+            Command::new("dotool"),
+            Some(&"key backspace\n".repeat(count)),
+        )?,
+        InputTool::Ydotool => {
+            let mut command = Command::new("ydotool");
+            command.arg("key");
+            for _ in 0..count {
+                command.args(ydotool_key_sequence(&[KEY_BACKSPACE]));
+            }
+            run(command, None)?
+        }
+    }
+    debug!("Sent {} backspaces with {:?}", count, tool);
+    Ok(true)
+}
+
+/// Press every key in order, then release them in reverse
+fn ydotool_key_sequence(codes: &[u16]) -> Vec<String> {
+    let presses = codes.iter().map(|code| format!("{}:1", code));
+    let releases = codes.iter().rev().map(|code| format!("{}:0", code));
+    presses.chain(releases).collect()
+}
+
+/// dotool script typing `text`. `type` takes one line, so line breaks are
+/// sent as Enter presses.
+fn dotool_script(text: &str, delay: Option<Duration>) -> String {
+    let mut script = String::new();
+    if let Some(delay) = delay {
+        script.push_str(&format!("typedelay {}\n", delay.as_millis()));
+    }
+    for (index, line) in text.replace('\r', "").split('\n').enumerate() {
+        if index > 0 {
+            script.push_str("key enter\n");
+        }
+        if !line.is_empty() {
+            script.push_str(&format!("type {}\n", line));
+        }
+    }
+    script
+}
+
+/// Types `text`, pausing `delay` between characters when given. Returns
+/// `Ok(false)` when no input tool is installed.
+pub fn type_text(text: &str, delay: Option<Duration>) -> Result<bool, String> {
+    let tool = match input_tool() {
+        Some(tool) => tool,
+        None => return Ok(false),
+    };
+    let delay_ms = delay.unwrap_or_default().as_millis().to_string();
+
+    match tool {
+        InputTool::Wtype => {
+            let mut command = Command::new("wtype");
+            command.args(["-d", &delay_ms, "--", text]);
+            run(command, None)?
+        }
+        InputTool::Dotool => run(Command::new("dotool"), Some(&dotool_script(text, delay)))?,
+        InputTool::Ydotool => {
+            let mut command = Command::new("ydotool");
+            command.args(["type", "--key-delay", &delay_ms, "--file", "-"]);
+            run(command, Some(text))?
+        }
+    }
+    debug!("Typed {} characters with {:?}", text.chars().count(), tool);
+    Ok(true)
+}
+
+/// Puts `text` on the clipboard with `wl-copy`, for compositors where the
+/// clipboard plugin can't reach the Wayland clipboard. Returns `Ok(false)`
+/// when wl-copy isn't installed.
+pub fn copy_text(text: &str) -> Result<bool, String> {
+    if !command_exists("wl-copy") {
+        return Ok(false);
+    }
+    // wl-copy forks to serve the clipboard and the fork keeps inherited
+    // pipes open, so its output isn't captured
+    let mut child = Command::new("wl-copy")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to execute wl-copy: {}", e))?;
+    if let Some(mut pipe) = child.stdin.take() {
+        pipe.write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to write to wl-copy: {}", e))?;
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for wl-copy: {}", e))?;
+    if !status.success() {
+        return Err(format!("wl-copy failed with {}", status));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_input_tool() {
+        let all = |_: &str| true;
+        assert_eq!(detect_input_tool("sway", all), Some(InputTool::Wtype));
+        assert_eq!(
+            detect_input_tool("ubuntu:GNOME", all),
+            Some(InputTool::Dotool)
+        );
+        assert_eq!(
+            detect_input_tool("GNOME", |name| name == "ydotool"),
+            Some(InputTool::Ydotool)
+        );
+        assert_eq!(detect_input_tool("KDE", |_| false), None);
+    }
+
+    #[test]
+    fn test_key_scripts() {
+        assert_eq!(
+            dotool_script("Dear team,\r\n\nThanks", Some(Duration::from_millis(10))),
+            "typedelay 10\ntype Dear team,\nkey enter\nkey enter\ntype Thanks\n"
+        );
+        assert_eq!(
+            ydotool_key_sequence(&[KEY_LEFTCTRL, KEY_V]),
+            vec!["29:1", "47:1", "47:0", "29:0"]
+        );
+    }
+}
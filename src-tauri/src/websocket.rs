@@ -0,0 +1,314 @@
+//! Optional WebSocket server on 127.0.0.1 for live captions and Stream Deck
+//! style controllers. Clients connect with `?token=<token>` (the control API
+//! token) and receive `{"event": ..., "data": ...}` messages for pipeline
+//! states, partial transcripts and final results. They can send
+//! `{"type": "start" | "stop" | "cancel" | "status"}` with an optional
+//! `binding_id` and get a `reply` back.
+
+use crate::control_api;
+use crate::pipeline;
+use crate::settings::{self, AppSettings};
+use crate::settings_validation::SettingsError;
+use crate::utils::cancel_current_operation;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Listener};
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::{Error, Message, WebSocket};
+
+/// App events passed on to clients under the same name
+const FORWARDED_EVENTS: &[&str] = &[
+    "pipeline-state",
+    "transcript-partial",
+    "transcript-final",
+    "operation-progress",
+];
+
+/// How long the server and client threads wait before checking for news
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Clients that don't finish the handshake in time are dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Bumped whenever the server stops, which ends its threads
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Taken out when the server stops so the port is free right away
+static LISTENER: Lazy<Mutex<Option<TcpListener>>> = Lazy::new(|| Mutex::new(None));
+
+static CLIENTS: Lazy<Mutex<Vec<Sender<String>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ControlMessage {
+    Start {
+        #[serde(default)]
+        binding_id: Option<String>,
+    },
+    Stop {
+        #[serde(default)]
+        binding_id: Option<String>,
+    },
+    Cancel,
+    Status,
+}
+
+/// A message for clients; `data` is JSON already
+fn message(event: &str, data: &str) -> String {
+    format!(r#"{{"event":"{}","data":{}}}"#, event, data)
+}
+
+/// The `token` parameter of a query string
+fn query_token(query: Option<&str>) -> Option<&str> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+}
+
+fn status() -> Value {
+    json!({
+        "state": pipeline::current(),
+        "busy": pipeline::is_busy(),
+    })
+}
+
+fn control(app: &AppHandle, text: &str) -> Result<Value, String> {
+    let message: ControlMessage = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    debug!("WebSocket control message: {:?}", message);
+    let (binding_id, active) = match message {
+        ControlMessage::Start { binding_id } => (binding_id, true),
+        ControlMessage::Stop { binding_id } => (binding_id, false),
+        ControlMessage::Cancel => {
+            cancel_current_operation(app);
+            return Ok(status());
+        }
+        ControlMessage::Status => return Ok(status()),
+    };
+
+    let binding_id = binding_id.unwrap_or_else(|| "transcribe".to_string());
+    if !settings::get_settings(app)
+        .bindings
+        .contains_key(&binding_id)
+    {
+        return Err(format!("No action '{}'", binding_id));
+    }
+    let changed = control_api::set_recording(app, &binding_id, active)?;
+    Ok(json!({ "binding_id": binding_id, "changed": changed }))
+}
+
+fn reply(app: &AppHandle, text: &str) -> String {
+    let data = match control(app, text) {
+        Ok(value) => json!({ "ok": true, "result": value }),
+        Err(e) => json!({ "ok": false, "error": e }),
+    };
+    message("reply", &data.to_string())
+}
+
+/// Send every client `event`, dropping those that are gone
+fn broadcast(event: &str, data: &str) {
+    let text = message(event, data);
+    CLIENTS
+        .lock()
+        .unwrap()
+        .retain(|client| client.send(text.clone()).is_ok());
+}
+
+/// Forward app events to clients; the listeners stay for the app's lifetime
+/// and cost nothing while nobody's connected
+pub fn init(app: &AppHandle) {
+    for event in FORWARDED_EVENTS {
+        app.listen_any(*event, move |payload| broadcast(event, payload.payload()));
+    }
+}
+
+fn accept(stream: TcpStream, token: &str) -> Result<WebSocket<TcpStream>, String> {
+    let check_token =
+        |request: &Request, response: Response| match query_token(request.uri().query()) {
+            Some(given) if control_api::token_matches(given, token) => Ok(response),
+            _ => {
+                let mut rejection = ErrorResponse::new(Some("Missing or wrong token".into()));
+                *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(rejection)
+            }
+        };
+    tungstenite::accept_hdr(stream, check_token).map_err(|e| e.to_string())
+}
+
+/// Pass broadcasts to one client and answer its control messages until it
+/// leaves or the server stops
+fn serve_client(
+    app: &AppHandle,
+    mut socket: WebSocket<TcpStream>,
+    outgoing: Receiver<String>,
+    generation: u64,
+) {
+    let hello = message("status", &status().to_string());
+    if let Err(e) = socket.send(Message::Text(hello.into())) {
+        debug!("WebSocket client left: {}", e);
+        return;
+    }
+
+    while GENERATION.load(Ordering::Relaxed) == generation {
+        for text in outgoing.try_iter() {
+            if let Err(e) = socket.send(Message::Text(text.into())) {
+                debug!("WebSocket client left: {}", e);
+                return;
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let answer = reply(app, text.as_str());
+                if let Err(e) = socket.send(Message::Text(answer.into())) {
+                    debug!("WebSocket client left: {}", e);
+                    return;
+                }
+            }
+            Ok(Message::Close(_)) => return,
+            Ok(_) => {}
+            // The read timeout, time to check for broadcasts again
+            Err(Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => {
+                debug!("WebSocket client left: {}", e);
+                return;
+            }
+        }
+    }
+    let _ = socket.close(None);
+    let _ = socket.flush();
+}
+
+fn start(app: &AppHandle, port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to start WebSocket server on port {}: {}", port, e))?;
+    // Polled, so the thread notices when the server is stopped
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure WebSocket server: {}", e))?;
+
+    *LISTENER.lock().unwrap() = Some(listener);
+
+    let generation = GENERATION.load(Ordering::Relaxed);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while GENERATION.load(Ordering::Relaxed) == generation {
+            let accepted = match LISTENER.lock().unwrap().as_ref() {
+                Some(listener) => listener.accept(),
+                None => break,
+            };
+            let stream = match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(crate::power::scaled(POLL_INTERVAL));
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to accept WebSocket client: {}", e);
+                    continue;
+                }
+            };
+
+            let app = app.clone();
+            std::thread::spawn(move || {
+                let configured = stream
+                    .set_nonblocking(false)
+                    .and_then(|_| stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)));
+                if let Err(e) = configured {
+                    warn!("Failed to configure WebSocket client: {}", e);
+                    return;
+                }
+                let token = settings::get_settings(&app)
+                    .control_api_token
+                    .unwrap_or_default();
+                let socket = match accept(stream, &token) {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        debug!("Rejected WebSocket client: {}", e);
+                        return;
+                    }
+                };
+                // Reads time out so broadcasts aren't held up by a quiet client
+                if let Err(e) = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+                    warn!("Failed to configure WebSocket client: {}", e);
+                    return;
+                }
+
+                let (sender, receiver) = mpsc::channel();
+                CLIENTS.lock().unwrap().push(sender);
+                serve_client(&app, socket, receiver, generation);
+            });
+        }
+    });
+    info!("WebSocket server listening on 127.0.0.1:{}", port);
+    Ok(())
+}
+
+/// Start or stop the server per the settings
+pub fn sync(app: &AppHandle) -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    LISTENER.lock().unwrap().take();
+    CLIENTS.lock().unwrap().clear();
+    let settings = settings::get_settings(app);
+    if !settings.websocket_enabled {
+        return Ok(());
+    }
+    control_api::ensure_token(app);
+    start(app, settings.websocket_port)
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.websocket_enabled == next.websocket_enabled
+        && previous.websocket_port == next.websocket_port
+    {
+        return Vec::new();
+    }
+    match sync(app) {
+        Ok(()) => Vec::new(),
+        Err(e) => {
+            warn!("{}", e);
+            vec![SettingsError::new("websocket_port", e)]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_token() {
+        assert_eq!(query_token(Some("token=abc")), Some("abc"));
+        assert_eq!(query_token(Some("v=1&token=abc")), Some("abc"));
+        assert_eq!(query_token(Some("v=1")), None);
+        assert_eq!(query_token(None), None);
+    }
+
+    #[test]
+    fn test_control_message() {
+        assert_eq!(
+            serde_json::from_str::<ControlMessage>(r#"{"type":"start"}"#).unwrap(),
+            ControlMessage::Start { binding_id: None }
+        );
+        assert_eq!(
+            serde_json::from_str::<ControlMessage>(r#"{"type":"stop","binding_id":"x"}"#).unwrap(),
+            ControlMessage::Stop {
+                binding_id: Some("x".to_string())
+            }
+        );
+        assert!(serde_json::from_str::<ControlMessage>(r#"{"type":"reboot"}"#).is_err());
+    }
+}
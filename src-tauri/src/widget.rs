@@ -0,0 +1,226 @@
+//! Optional floating widget with a record button, a level meter and the last
+//! transcript, for clicking instead of using shortcuts. It can be dragged
+//! anywhere and comes back where it was left.
+
+use crate::actions::{action_for, admit};
+use crate::settings::{self, AppSettings, WidgetPosition};
+use crate::settings_validation::SettingsError;
+use crate::throttle;
+use crate::ManagedToggleState;
+use log::{debug, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, PhysicalPosition, WebviewWindowBuilder};
+
+const WIDGET_WINDOW_LABEL: &str = "widget";
+const WIDGET_WIDTH: f64 = 240.0;
+const WIDGET_HEIGHT: f64 = 64.0;
+/// Gap to the bottom right corner of the work area before it's been moved
+const WIDGET_MARGIN: f64 = 24.0;
+
+/// Time without moves after which a drag counts as finished and is saved
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Bumped on every move, so only the last one of a drag is saved
+static MOVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the widget's title bar would be on `area` (x, y, width, height)
+fn is_within(position: WidgetPosition, area: (f64, f64, f64, f64)) -> bool {
+    let (x, y, width, height) = area;
+    position.x + WIDGET_WIDTH > x
+        && position.x < x + width
+        && position.y >= y
+        && position.y + WIDGET_HEIGHT / 2.0 <= y + height
+}
+
+/// Logical work areas of all monitors
+fn work_areas(app: &AppHandle) -> Vec<(f64, f64, f64, f64)> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|monitor| {
+            let work_area = monitor.work_area();
+            let scale = monitor.scale_factor();
+            (
+                work_area.position.x as f64 / scale,
+                work_area.position.y as f64 / scale,
+                work_area.size.width as f64 / scale,
+                work_area.size.height as f64 / scale,
+            )
+        })
+        .collect()
+}
+
+/// The saved position when it's still on a screen, e.g. after a monitor was
+/// unplugged, otherwise the corner of the primary monitor
+fn initial_position(app: &AppHandle, settings: &AppSettings) -> Option<(f64, f64)> {
+    if let Some(position) = settings.widget_position {
+        if work_areas(app)
+            .into_iter()
+            .any(|area| is_within(position, area))
+        {
+            return Some((position.x, position.y));
+        }
+        debug!("Saved widget position {:?} is off screen", position);
+    }
+
+    let monitor = app.primary_monitor().ok().flatten()?;
+    let work_area = monitor.work_area();
+    let scale = monitor.scale_factor();
+    Some((
+        (work_area.position.x as f64 + work_area.size.width as f64) / scale
+            - WIDGET_WIDTH
+            - WIDGET_MARGIN,
+        (work_area.position.y as f64 + work_area.size.height as f64) / scale
+            - WIDGET_HEIGHT
+            - WIDGET_MARGIN,
+    ))
+}
+
+fn widget_window(app: &AppHandle) -> Result<tauri::WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(WIDGET_WINDOW_LABEL) {
+        return Ok(window);
+    }
+    let mut builder = WebviewWindowBuilder::new(
+        app,
+        WIDGET_WINDOW_LABEL,
+        tauri::WebviewUrl::App("src/widget/index.html".into()),
+    )
+    .title("Babbl")
+    .inner_size(WIDGET_WIDTH, WIDGET_HEIGHT)
+    .resizable(false)
+    .maximizable(false)
+    .minimizable(false)
+    .closable(false)
+    .decorations(false)
+    .transparent(true)
+    .shadow(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .accept_first_mouse(true)
+    // Clicks mustn't take focus from the app the text is pasted into
+    .focusable(false)
+    .focused(false)
+    .visible(false);
+    if let Some((x, y)) = initial_position(app, &settings::get_settings(app)) {
+        builder = builder.position(x, y);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create widget window: {}", e))
+}
+
+/// Show or hide the widget per the settings
+pub fn sync(app: &AppHandle) {
+    if settings::get_settings(app).widget_enabled {
+        match widget_window(app) {
+            Ok(window) => {
+                if let Err(e) = window.show() {
+                    warn!("Failed to show widget: {}", e);
+                }
+            }
+            Err(e) => warn!("{}", e),
+        }
+    } else if let Some(window) = app.get_webview_window(WIDGET_WINDOW_LABEL) {
+        if let Err(e) = window.hide() {
+            warn!("Failed to hide widget: {}", e);
+        }
+    }
+}
+
+pub fn on_settings_changed(
+    app: &AppHandle,
+    previous: &AppSettings,
+    next: &AppSettings,
+) -> Vec<SettingsError> {
+    if previous.widget_enabled != next.widget_enabled {
+        sync(app);
+    }
+    Vec::new()
+}
+
+/// Save where the widget was dragged to once the drag is over
+pub fn on_moved(window: &tauri::Window, position: PhysicalPosition<i32>) {
+    let scale = match window.scale_factor() {
+        Ok(scale) => scale,
+        Err(e) => {
+            warn!("Failed to get widget scale factor: {}", e);
+            return;
+        }
+    };
+    let position = position.to_logical::<f64>(scale);
+    let position = WidgetPosition {
+        x: position.x,
+        y: position.y,
+    };
+
+    let generation = MOVE_GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    let app = window.app_handle().clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(SAVE_DELAY);
+        if MOVE_GENERATION.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        let mut settings = settings::get_settings(&app);
+        if settings.widget_position != Some(position) {
+            debug!("Widget moved to {:?}", position);
+            settings.widget_position = Some(position);
+            if let Err(e) = settings::write_settings(&app, settings) {
+                warn!("Failed to save the widget position: {}", e);
+            }
+        }
+    });
+}
+
+/// Start or stop a dictation with the transcribe action, like its shortcut
+/// in toggle mode
+pub fn toggle_recording(app: &AppHandle) -> Result<(), String> {
+    let binding_id = "transcribe";
+    let action =
+        action_for(binding_id).ok_or_else(|| format!("No action defined for '{}'", binding_id))?;
+    if throttle::is_bounce(binding_id) {
+        debug!("Ignoring a double click on the widget");
+        return Ok(());
+    }
+
+    let toggle_state_manager = app.state::<ManagedToggleState>();
+    let mut states = toggle_state_manager
+        .lock()
+        .map_err(|e| format!("Failed to lock toggle state manager: {}", e))?;
+    let is_currently_active = states
+        .active_toggles
+        .entry(binding_id.to_string())
+        .or_insert(false);
+
+    if *is_currently_active {
+        action.stop(app, binding_id, "widget");
+    } else if admit(app, binding_id) {
+        action.start(app, binding_id, "widget");
+    } else {
+        return Err(format!("'{}' is still running", binding_id));
+    }
+    *is_currently_active = !*is_currently_active;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_within() {
+        let area = (0.0, 0.0, 1920.0, 1040.0);
+        assert!(is_within(WidgetPosition { x: 100.0, y: 900.0 }, area));
+        // Mostly off the left edge is fine, it can still be dragged back
+        assert!(is_within(WidgetPosition { x: -200.0, y: 10.0 }, area));
+        assert!(!is_within(WidgetPosition { x: 2000.0, y: 10.0 }, area));
+        assert!(!is_within(WidgetPosition { x: 100.0, y: -5.0 }, area));
+        assert!(!is_within(
+            WidgetPosition {
+                x: 100.0,
+                y: 1020.0
+            },
+            area
+        ));
+    }
+}
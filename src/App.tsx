@@ -1,4 +1,5 @@
 import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { Toaster } from "sonner";
 import "./App.css";
 import AccessibilityPermissions from "./components/AccessibilityPermissions";
@@ -24,6 +25,23 @@ function App() {
     checkOnboardingStatus();
   }, []);
 
+  // babbl://settings/<section> links
+  useEffect(() => {
+    const openSection = (section: string | null) => {
+      if (section && section in SECTIONS_CONFIG) {
+        setCurrentSection(section as SidebarSection);
+      }
+    };
+    commands.takeRequestedSection().then(openSection);
+    const unlisten = listen<string>("open-section", (event) => {
+      commands.takeRequestedSection();
+      openSection(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Handle keyboard shortcuts for debug mode toggle
   useEffect(() => {
     const handleKeyDown = (event: KeyboardEvent) => {
@@ -69,6 +69,22 @@ async changeAutostartSetting(enabled: boolean) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async changeAutostartMinimizedSetting(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_autostart_minimized_setting", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async changeAutostartDelaySetting(delaySecs: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_autostart_delay_setting", { delaySecs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async changeTranslateToEnglishSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("change_translate_to_english_setting", { enabled }) };
@@ -93,556 +109,1733 @@ async changeOverlayPositionSetting(position: string) : Promise<Result<null, stri
     else return { status: "error", error: e  as any };
 }
 },
-async changeDebugModeSetting(enabled: boolean) : Promise<Result<null, string>> {
+/**
+ * Put the overlay at `anchor` on `display`, or back at `overlay_position`
+ * without one
+ */
+async changeOverlayDisplayAnchorSetting(display: string, anchor: OverlayAnchor | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_debug_mode_setting", { enabled }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_overlay_display_anchor_setting", { display, anchor }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeWordCorrectionThresholdSetting(threshold: number) : Promise<Result<null, string>> {
+async changeOverlayClickThroughSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_word_correction_threshold_setting", { threshold }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_overlay_click_through_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changePasteMethodSetting(method: string) : Promise<Result<null, string>> {
+async changeWidgetEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_paste_method_setting", { method }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_widget_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeClipboardHandlingSetting(handling: string) : Promise<Result<null, string>> {
+async changeAllowLinkActionsSetting(allowed: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_clipboard_handling_setting", { handling }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_allow_link_actions_setting", { allowed }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changePostProcessEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
+async changeControlApiEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_post_process_enabled_setting", { enabled }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_control_api_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changePostProcessBaseUrlSetting(providerId: string, baseUrl: string) : Promise<Result<null, string>> {
+async changeControlApiPortSetting(port: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_post_process_base_url_setting", { providerId, baseUrl }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_control_api_port_setting", { port }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changePostProcessApiKeySetting(providerId: string, apiKey: string) : Promise<Result<null, string>> {
+async changeWebsocketEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_post_process_api_key_setting", { providerId, apiKey }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_websocket_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changePostProcessModelSetting(providerId: string, model: string) : Promise<Result<null, string>> {
+async changeWebsocketPortSetting(port: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_post_process_model_setting", { providerId, model }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_websocket_port_setting", { port }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async setPostProcessProvider(providerId: string) : Promise<Result<null, string>> {
+async changeMqttEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_post_process_provider", { providerId }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_mqtt_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async fetchPostProcessModels(providerId: string) : Promise<Result<string[], string>> {
+async changeMqttBrokerSetting(host: string, port: number, username: string | null, password: string | null, topicPrefix: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("fetch_post_process_models", { providerId }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_mqtt_broker_setting", { host, port, username, password, topicPrefix }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async addPostProcessPrompt(name: string, prompt: string) : Promise<Result<LLMPrompt, string>> {
+async changeMqttPublishTranscriptsSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("add_post_process_prompt", { name, prompt }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_mqtt_publish_transcripts_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async updatePostProcessPrompt(id: string, name: string, prompt: string) : Promise<Result<null, string>> {
+async changeCallMuteEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("update_post_process_prompt", { id, name, prompt }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_call_mute_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async deletePostProcessPrompt(id: string) : Promise<Result<null, string>> {
+async changeObsEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("delete_post_process_prompt", { id }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_obs_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async setPostProcessSelectedPrompt(id: string) : Promise<Result<null, string>> {
+async changeObsConnectionSetting(host: string, port: number, password: string | null, textSource: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_post_process_selected_prompt", { id }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_obs_connection_setting", { host, port, password, textSource }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async updateCustomWords(words: string[]) : Promise<Result<null, string>> {
+async changeObsClearAfterSetting(secs: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("update_custom_words", { words }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_obs_clear_after_setting", { secs }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
 /**
- * Temporarily unregister a binding while the user is editing it in the UI.
- * This avoids firing the action while keys are being recorded.
+ * Ignore or handle all shortcuts again, e.g. while gaming or presenting
  */
-async suspendBinding(id: string) : Promise<Result<null, string>> {
+async setShortcutsPaused(paused: boolean) : Promise<void> {
+    await TAURI_INVOKE("set_shortcuts_paused", { paused });
+},
+async getShortcutsPaused() : Promise<boolean> {
+    return await TAURI_INVOKE("get_shortcuts_paused");
+},
+async changeDebugModeSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("suspend_binding", { id }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_debug_mode_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-/**
- * Re-register the binding after the user has finished editing.
- */
-async resumeBinding(id: string) : Promise<Result<null, string>> {
+async changeNotificationSetting(kind: NotificationKind, enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("resume_binding", { id }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_notification_setting", { kind, enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeMuteWhileRecordingSetting(enabled: boolean) : Promise<Result<null, string>> {
+async changeWordCorrectionThresholdSetting(threshold: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_mute_while_recording_setting", { enabled }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_word_correction_threshold_setting", { threshold }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeAppendTrailingSpaceSetting(enabled: boolean) : Promise<Result<null, string>> {
+async changePasteMethodSetting(method: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_append_trailing_space_setting", { enabled }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_paste_method_setting", { method }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeAppLanguageSetting(language: string) : Promise<Result<null, string>> {
+async changeTypingSpeedSetting(charsPerSecond: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_app_language_setting", { language }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_typing_speed_setting", { charsPerSecond }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeUpdateChecksSetting(enabled: boolean) : Promise<Result<null, string>> {
+async changeUndoMethodSetting(method: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_update_checks_setting", { enabled }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_undo_method_setting", { method }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeUseOnlineProviderSetting(enabled: boolean) : Promise<Result<null, string>> {
+async changeOutputModeSetting(mode: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_use_online_provider_setting", { enabled }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_output_mode_setting", { mode }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeOnlineProviderIdSetting(providerId: string) : Promise<Result<null, string>> {
+async changeNoteFilePathSetting(path: string | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_online_provider_id_setting", { providerId }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_note_file_path_setting", { path }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeOnlineProviderApiKeySetting(providerId: string, apiKey: string) : Promise<Result<null, string>> {
+async changeRestoreClipboardSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_online_provider_api_key_setting", { providerId, apiKey }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_restore_clipboard_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async changeOnlineProviderModelSetting(providerId: string, model: string) : Promise<Result<null, string>> {
+async changeClipboardRestoreDelaySetting(delayMs: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("change_online_provider_model_setting", { providerId, model }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_clipboard_restore_delay_setting", { delayMs }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async triggerUpdateCheck() : Promise<Result<null, string>> {
+async changeMarkdownRichTextSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("trigger_update_check") };
+    return { status: "ok", data: await TAURI_INVOKE("change_markdown_rich_text_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async cancelOperation() : Promise<void> {
-    await TAURI_INVOKE("cancel_operation");
+async changeSmartSpacingSetting(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_smart_spacing_setting", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 },
-async getAppDirPath() : Promise<Result<string, string>> {
+async changeStreamingInjectionSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_app_dir_path") };
+    return { status: "ok", data: await TAURI_INVOKE("change_streaming_injection_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getAppSettings() : Promise<Result<AppSettings, string>> {
+async changeClipboardHandlingSetting(handling: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_app_settings") };
+    return { status: "ok", data: await TAURI_INVOKE("change_clipboard_handling_setting", { handling }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getDefaultSettings() : Promise<Result<AppSettings, string>> {
+async changeGeminiSafetyThresholdSetting(threshold: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_default_settings") };
+    return { status: "ok", data: await TAURI_INVOKE("change_gemini_safety_threshold_setting", { threshold }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getLogDirPath() : Promise<Result<string, string>> {
+async changePostProcessEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_log_dir_path") };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async setLogLevel(level: LogLevel) : Promise<Result<null, string>> {
+async changePostProcessBaseUrlSetting(providerId: string, baseUrl: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_log_level", { level }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_base_url_setting", { providerId, baseUrl }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async openRecordingsFolder() : Promise<Result<null, string>> {
+async changePostProcessApiVersionSetting(providerId: string, apiVersion: string | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("open_recordings_folder") };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_api_version_setting", { providerId, apiVersion }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async openLogDir() : Promise<Result<null, string>> {
+async changePostProcessFallbackApiKeysSetting(providerId: string, apiKeys: string[]) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("open_log_dir") };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_fallback_api_keys_setting", { providerId, apiKeys }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async openAppDataDir() : Promise<Result<null, string>> {
+async changeOnlineProviderFallbackApiKeysSetting(providerId: string, apiKeys: string[]) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("open_app_data_dir") };
+    return { status: "ok", data: await TAURI_INVOKE("change_online_provider_fallback_api_keys_setting", { providerId, apiKeys }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getAvailableModels() : Promise<Result<ModelInfo[], string>> {
+async changePostProcessServiceTierSetting(providerId: string, serviceTier: string | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_available_models") };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_service_tier_setting", { providerId, serviceTier }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getModelInfo(modelId: string) : Promise<Result<ModelInfo | null, string>> {
+/**
+ * Set provider-specific request fields as a JSON object, or clear them with `None`
+ */
+async changePostProcessExtraBodySetting(providerId: string, extraBody: string | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_model_info", { modelId }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_extra_body_setting", { providerId, extraBody }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async downloadModel(modelId: string) : Promise<Result<null, string>> {
+async changePostProcessApiKeySetting(providerId: string, apiKey: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("download_model", { modelId }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_api_key_setting", { providerId, apiKey }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async deleteModel(modelId: string) : Promise<Result<null, string>> {
+async changePostProcessModelSetting(providerId: string, model: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("delete_model", { modelId }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_post_process_model_setting", { providerId, model }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async cancelDownload(modelId: string) : Promise<Result<null, string>> {
+async setPostProcessProvider(providerId: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("cancel_download", { modelId }) };
+    return { status: "ok", data: await TAURI_INVOKE("set_post_process_provider", { providerId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async setActiveModel(modelId: string) : Promise<Result<null, string>> {
+async fetchPostProcessModels(providerId: string) : Promise<Result<string[], string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_active_model", { modelId }) };
+    return { status: "ok", data: await TAURI_INVOKE("fetch_post_process_models", { providerId }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getCurrentModel() : Promise<Result<string, string>> {
+async addPostProcessPrompt(name: string, prompt: string) : Promise<Result<LLMPrompt, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_current_model") };
+    return { status: "ok", data: await TAURI_INVOKE("add_post_process_prompt", { name, prompt }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getTranscriptionModelStatus() : Promise<Result<string | null, string>> {
+async updatePostProcessPrompt(id: string, name: string, prompt: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_transcription_model_status") };
+    return { status: "ok", data: await TAURI_INVOKE("update_post_process_prompt", { id, name, prompt }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async isModelLoading() : Promise<Result<boolean, string>> {
+async deletePostProcessPrompt(id: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("is_model_loading") };
+    return { status: "ok", data: await TAURI_INVOKE("delete_post_process_prompt", { id }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async hasAnyModelsAvailable() : Promise<Result<boolean, string>> {
+async setPostProcessSelectedPrompt(id: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("has_any_models_available") };
+    return { status: "ok", data: await TAURI_INVOKE("set_post_process_selected_prompt", { id }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async hasAnyModelsOrDownloads() : Promise<Result<boolean, string>> {
+async updateCustomWords(words: string[]) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("has_any_models_or_downloads") };
+    return { status: "ok", data: await TAURI_INVOKE("update_custom_words", { words }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getRecommendedFirstModel() : Promise<Result<string, string>> {
+/**
+ * Temporarily unregister a binding while the user is editing it in the UI.
+ * This avoids firing the action while keys are being recorded.
+ */
+async suspendBinding(id: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_recommended_first_model") };
+    return { status: "ok", data: await TAURI_INVOKE("suspend_binding", { id }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async updateMicrophoneMode(alwaysOn: boolean) : Promise<Result<null, string>> {
+/**
+ * Re-register the binding after the user has finished editing.
+ */
+async resumeBinding(id: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("update_microphone_mode", { alwaysOn }) };
+    return { status: "ok", data: await TAURI_INVOKE("resume_binding", { id }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getMicrophoneMode() : Promise<Result<boolean, string>> {
+async changeMuteWhileRecordingSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_microphone_mode") };
+    return { status: "ok", data: await TAURI_INVOKE("change_mute_while_recording_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getAvailableMicrophones() : Promise<Result<AudioDevice[], string>> {
+async changeRecordingMemoryLimitSetting(limitMb: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_available_microphones") };
+    return { status: "ok", data: await TAURI_INVOKE("change_recording_memory_limit_setting", { limitMb }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async setSelectedMicrophone(deviceName: string) : Promise<Result<null, string>> {
+async changeWakeWordEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_selected_microphone", { deviceName }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_wake_word_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getSelectedMicrophone() : Promise<Result<string, string>> {
+async changeWakeWordPhraseSetting(phrase: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_selected_microphone") };
+    return { status: "ok", data: await TAURI_INVOKE("change_wake_word_phrase_setting", { phrase }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getAvailableOutputDevices() : Promise<Result<AudioDevice[], string>> {
+async changeWakeWordSensitivitySetting(sensitivity: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_available_output_devices") };
+    return { status: "ok", data: await TAURI_INVOKE("change_wake_word_sensitivity_setting", { sensitivity }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async setSelectedOutputDevice(deviceName: string) : Promise<Result<null, string>> {
+async changeAppendTrailingSpaceSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_selected_output_device", { deviceName }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_append_trailing_space_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getSelectedOutputDevice() : Promise<Result<string, string>> {
+async changeLlamaCppModelPathSetting(path: string | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_selected_output_device") };
+    return { status: "ok", data: await TAURI_INVOKE("change_llama_cpp_model_path_setting", { path }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async playTestSound(soundType: string) : Promise<void> {
-    await TAURI_INVOKE("play_test_sound", { soundType });
+async changeLlamaCppGpuLayersSetting(layers: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_llama_cpp_gpu_layers_setting", { layers }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 },
-async checkCustomSounds() : Promise<CustomSounds> {
-    return await TAURI_INVOKE("check_custom_sounds");
+async changeNetworkMaxAttemptsSetting(attempts: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_network_max_attempts_setting", { attempts }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 },
-async setClamshellMicrophone(deviceName: string) : Promise<Result<null, string>> {
+async changeActionTimeoutSetting(secs: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("set_clamshell_microphone", { deviceName }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_action_timeout_setting", { secs }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getClamshellMicrophone() : Promise<Result<string, string>> {
+async changeProxyUrlSetting(proxyUrl: string | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_clamshell_microphone") };
+    return { status: "ok", data: await TAURI_INVOKE("change_proxy_url_setting", { proxyUrl }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async isRecording() : Promise<boolean> {
-    return await TAURI_INVOKE("is_recording");
+async changeCustomCaPathSetting(path: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_custom_ca_path_setting", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 },
-async setModelUnloadTimeout(timeout: ModelUnloadTimeout) : Promise<void> {
-    await TAURI_INVOKE("set_model_unload_timeout", { timeout });
+async changeHookScriptPathSetting(path: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_hook_script_path_setting", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 },
-async getModelLoadStatus() : Promise<Result<ModelLoadStatus, string>> {
+async changeConversationContextEnabledSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_model_load_status") };
+    return { status: "ok", data: await TAURI_INVOKE("change_conversation_context_enabled_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async unloadModelManually() : Promise<Result<null, string>> {
+async changeConversationContextTurnsSetting(turns: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("unload_model_manually") };
+    return { status: "ok", data: await TAURI_INVOKE("change_conversation_context_turns_setting", { turns }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getHistoryEntries() : Promise<Result<HistoryEntry[], string>> {
+async changeConversationContextWindowSetting(minutes: number) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_history_entries") };
+    return { status: "ok", data: await TAURI_INVOKE("change_conversation_context_window_setting", { minutes }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async toggleHistoryEntrySaved(id: number) : Promise<Result<null, string>> {
+async changeMonthlyLlmBudgetSetting(budgetUsd: number | null) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("toggle_history_entry_saved", { id }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_monthly_llm_budget_setting", { budgetUsd }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async getAudioFilePath(fileName: string) : Promise<Result<string, string>> {
+async changeTranslateTargetLanguageSetting(language: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("get_audio_file_path", { fileName }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_translate_target_language_setting", { language }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async deleteHistoryEntry(id: number) : Promise<Result<null, string>> {
+async changeLongTranscriptStrategySetting(strategy: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("delete_history_entry", { id }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_long_transcript_strategy_setting", { strategy }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async updateHistoryLimit(limit: number) : Promise<Result<null, string>> {
+async changeAppLanguageSetting(language: string) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("update_history_limit", { limit }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_app_language_setting", { language }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async updateRecordingRetentionPeriod(period: string) : Promise<Result<null, string>> {
+async changeUpdateChecksSetting(enabled: boolean) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("update_recording_retention_period", { period }) };
+    return { status: "ok", data: await TAURI_INVOKE("change_update_checks_setting", { enabled }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-/**
- * Stub implementation for non-macOS platforms
- * Always returns false since laptop detection is macOS-specific
- */
-async isLaptop() : Promise<Result<boolean, string>> {
+async changeUpdateChannelSetting(channel: UpdateChannel) : Promise<Result<null, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("is_laptop") };
+    return { status: "ok", data: await TAURI_INVOKE("change_update_channel_setting", { channel }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
+},
+async changeUseOnlineProviderSetting(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_use_online_provider_setting", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
 }
+},
+async changeOnlineProviderIdSetting(providerId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_online_provider_id_setting", { providerId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
 }
-
-/** user-defined events **/
-
+},
+async changeOnlineProviderApiKeySetting(providerId: string, apiKey: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_online_provider_api_key_setting", { providerId, apiKey }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async changeOnlineProviderModelSetting(providerId: string, model: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("change_online_provider_model_setting", { providerId, model }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async triggerUpdateCheck() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("trigger_update_check") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async cancelOperation() : Promise<void> {
+    await TAURI_INVOKE("cancel_operation");
+},
+/**
+ * Stop a model download, long transcription or export by the id its
+ * `operation-progress` events carry
+ */
+async cancelProgressOperation(operationId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_progress_operation", { operationId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAppDirPath() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_app_dir_path") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAppSettings() : Promise<Result<AppSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_app_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Settings fields rejected or flagged by the last validation
+ */
+async getSettingsErrors() : Promise<Result<SettingsError[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_settings_errors") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getDefaultSettings() : Promise<Result<AppSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_default_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Restore the defaults of one settings section, e.g. `shortcuts` or
+ * `output`, keeping all other settings
+ */
+async resetSettingsSection(name: string) : Promise<Result<AppSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reset_settings_section", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Replace the control API token, locking out clients with the old one
+ */
+async regenerateControlApiToken() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("regenerate_control_api_token") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getLogDirPath() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_log_dir_path") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setLogLevel(level: LogLevel) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_log_level", { level }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Log `module` at `level` in the log files, or at the global level again
+ * without one. `module` is one of Babbl's, like `input_hook`, or a crate.
+ */
+async setLogModuleLevel(module: string, level: LogLevel | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_log_module_level", { module, level }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Zip the recent logs, settings, system info, audio devices and permission
+ * states into `path` for a bug report, without secrets or transcripts
+ */
+async createDiagnosticBundle(path: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_diagnostic_bundle", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Time each stage of a dictation with every configured provider, on the
+ * WAV file at `sample_path` or on synthetic audio
+ */
+async runBenchmark(samplePath: string | null) : Promise<Result<BenchmarkReport, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("run_benchmark", { samplePath }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Panics and unrecoverable errors not dismissed yet, for the error dialog
+ */
+async getFatalErrors() : Promise<FatalError[]> {
+    return await TAURI_INVOKE("get_fatal_errors");
+},
+/**
+ * Open a GitHub issue prefilled with the details of the error with `id`
+ */
+async reportFatalError(id: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("report_fatal_error", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async dismissFatalErrors() : Promise<void> {
+    await TAURI_INVOKE("dismiss_fatal_errors");
+},
+/**
+ * Health of the input listener, microphone stream and control API, from
+ * the watchdog's last check
+ */
+async getHealth() : Promise<HealthStatus> {
+    return await TAURI_INVOKE("get_health");
+},
+/**
+ * The displays, with the overlay corner picked for each
+ */
+async getDisplays() : Promise<Result<DisplayInfo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_displays") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The app keeping the shortcuts from Babbl with secure keyboard entry
+ * (macOS), if any
+ */
+async getSecureInput() : Promise<SecureInput | null> {
+    return await TAURI_INVOKE("get_secure_input");
+},
+/**
+ * Connect to OBS with the saved settings and show a test caption
+ */
+async testObsConnection() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("test_obs_connection") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The settings section a `babbl://settings/...` link asked for before the
+ * window was ready, once
+ */
+async takeRequestedSection() : Promise<string | null> {
+    return await TAURI_INVOKE("take_requested_section");
+},
+/**
+ * Record and transcribe a meeting until it's stopped
+ */
+async startMeetingTranscription() : Promise<Result<MeetingSession, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("start_meeting_transcription") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopMeetingTranscription() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("stop_meeting_transcription") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The meeting being transcribed, if any
+ */
+async getMeetingSession() : Promise<MeetingSession | null> {
+    return await TAURI_INVOKE("get_meeting_session");
+},
+/**
+ * Record the wake phrase once, until a pause after it; returns how many
+ * recordings of it there are
+ */
+async recordWakeWordSample() : Promise<Result<number, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("record_wake_word_sample") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async clearWakeWordSamples() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("clear_wake_word_samples") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * How many recordings of the wake phrase there are; it's listened for
+ * from three on
+ */
+async getWakeWordSampleCount() : Promise<number> {
+    return await TAURI_INVOKE("get_wake_word_sample_count");
+},
+/**
+ * Whether Babbl starts at login, and how
+ */
+async getAutostartStatus() : Promise<AutostartStatus> {
+    return await TAURI_INVOKE("get_autostart_status");
+},
+async getServiceStatus() : Promise<ServiceStatus> {
+    return await TAURI_INVOKE("get_service_status");
+},
+/**
+ * Start Babbl from a systemd user service at login (Linux), in place of the
+ * autostart entry
+ */
+async installService() : Promise<Result<ServiceStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("install_service") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async uninstallService() : Promise<Result<ServiceStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("uninstall_service") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Check the selected update channel, downloading a new version in the
+ * background
+ */
+async checkForUpdate() : Promise<Result<UpdateInfo | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("check_for_update") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getPendingUpdate() : Promise<UpdateInfo | null> {
+    return await TAURI_INVOKE("get_pending_update");
+},
+/**
+ * Restart into the downloaded update, after the dictation underway
+ */
+async restartToUpdate() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("restart_to_update") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async openRecordingsFolder() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("open_recordings_folder") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async openLogDir() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("open_log_dir") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async openAppDataDir() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("open_app_data_dir") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAvailableModels() : Promise<Result<ModelInfo[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_available_models") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getModelInfo(modelId: string) : Promise<Result<ModelInfo | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_model_info", { modelId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async downloadModel(modelId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("download_model", { modelId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteModel(modelId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_model", { modelId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async cancelDownload(modelId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_download", { modelId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setActiveModel(modelId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_active_model", { modelId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getCurrentModel() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_current_model") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getTranscriptionModelStatus() : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_transcription_model_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async isModelLoading() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("is_model_loading") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async hasAnyModelsAvailable() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("has_any_models_available") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async hasAnyModelsOrDownloads() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("has_any_models_or_downloads") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getRecommendedFirstModel() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_recommended_first_model") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * List the models a post-processing provider offers. Results are cached for a
+ * few minutes; pass `refresh` to bypass the cache.
+ */
+async listLlmModels(providerId: string, refresh: boolean | null) : Promise<Result<string[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_llm_models", { providerId, refresh }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Send a minimal chat request with the stored key and model of a provider.
+ * `model` overrides the saved model, e.g. to test a selection before saving it.
+ */
+async checkLlmConnection(providerId: string, model: string | null) : Promise<Result<ConnectionCheck, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("check_llm_connection", { providerId, model }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateMicrophoneMode(alwaysOn: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_microphone_mode", { alwaysOn }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getMicrophoneMode() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_microphone_mode") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAvailableMicrophones() : Promise<Result<AudioDevice[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_available_microphones") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setSelectedMicrophone(deviceName: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_selected_microphone", { deviceName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSelectedMicrophone() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_selected_microphone") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAvailableOutputDevices() : Promise<Result<AudioDevice[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_available_output_devices") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setSelectedOutputDevice(deviceName: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_selected_output_device", { deviceName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSelectedOutputDevice() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_selected_output_device") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async playTestSound(soundType: string) : Promise<void> {
+    await TAURI_INVOKE("play_test_sound", { soundType });
+},
+async checkCustomSounds() : Promise<CustomSounds> {
+    return await TAURI_INVOKE("check_custom_sounds");
+},
+async setClamshellMicrophone(deviceName: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_clamshell_microphone", { deviceName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getClamshellMicrophone() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_clamshell_microphone") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async isRecording() : Promise<boolean> {
+    return await TAURI_INVOKE("is_recording");
+},
+async getMicrophoneAccess() : Promise<MicrophoneAccess> {
+    return await TAURI_INVOKE("get_microphone_access");
+},
+/**
+ * Open the system settings page that turns microphone access back on
+ */
+async openMicrophoneSettings() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("open_microphone_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setModelUnloadTimeout(timeout: ModelUnloadTimeout) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_model_unload_timeout", { timeout }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setWarmUp(warmUp: WarmUp) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_warm_up", { warmUp }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setLowPowerMode(mode: LowPowerMode, model: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_low_power_mode", { mode, model }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getModelLoadStatus() : Promise<Result<ModelLoadStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_model_load_status") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unloadModelManually() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unload_model_manually") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getHistoryEntries() : Promise<Result<HistoryEntry[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_history_entries") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Entries matching `filter`, newest first, `limit` at a time. Pass the
+ * returned cursor back to get the next page.
+ */
+async getHistoryPage(filter: HistoryFilter | null, cursor: HistoryCursor | null, limit: number | null) : Promise<Result<HistoryPage, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_history_page", { filter, cursor, limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Status of everything dictation needs, for the first-run wizard. With
+ * `validate_api_key` the post-processing key is checked with a test request.
+ */
+async getSetupStatus(validateApiKey: boolean) : Promise<Result<Capability[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_setup_status", { validateApiKey }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Show the OS prompt or settings page for a capability
+ */
+async requestCapability(id: CapabilityId) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("request_capability", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Paste a known phrase with the configured paste method. The wizard focuses
+ * one of its own fields first and compares its content with the returned
+ * text.
+ */
+async runInjectionTest() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("run_injection_test") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The newest entry's final text, None while the history is empty
+ */
+async getLastTranscript() : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_last_transcript") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Copy the newest entry's final text to the clipboard
+ */
+async copyLastTranscript() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("copy_last_transcript") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async searchHistory(query: string) : Promise<Result<HistorySearchResult[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("search_history", { query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Write the entries in `range` to `path` as Markdown, CSV or JSON.
+ * Returns how many entries were exported.
+ */
+async exportHistory(range: HistoryRange, format: ExportFormat, path: string, options: ExportOptions | null) : Promise<Result<number, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_history", { range, format, path, options }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Recordings left unfinished when Babbl last stopped
+ */
+async listOrphanedRecordings() : Promise<OrphanedRecording[]> {
+    return await TAURI_INVOKE("list_orphaned_recordings");
+},
+/**
+ * Transcribe an unfinished recording into the history; returns the text
+ */
+async recoverOrphanedRecording(fileName: string) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("recover_orphaned_recording", { fileName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async discardOrphanedRecording(fileName: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("discard_orphaned_recording", { fileName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Run an entry's raw transcript through post-processing again, with action
+ * `binding_id` (the default transcribe action when omitted) and optionally
+ * another prompt. The new output replaces the entry's, the old one is kept as
+ * a revision.
+ */
+async reprocessHistoryEntry(id: number, bindingId: string | null, promptId: string | null) : Promise<Result<HistoryEntry, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reprocess_history_entry", { id, bindingId, promptId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getHistoryRevisions(id: number) : Promise<Result<HistoryRevision[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_history_revisions", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Words, speaking speed, time saved, action use and provider error rates for
+ * the entries in `range`
+ */
+async getStats(range: HistoryRange) : Promise<Result<DictationStats, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_stats", { range }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async toggleHistoryEntrySaved(id: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_history_entry_saved", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAudioFilePath(fileName: string) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_audio_file_path", { fileName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Play the recording of entry `id` in the app, from `start_ms` into it
+ */
+async playHistoryAudio(id: number, startMs: number | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("play_history_audio", { id, startMs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async stopHistoryAudio() : Promise<void> {
+    await TAURI_INVOKE("stop_history_audio");
+},
+async deleteHistoryEntry(id: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_history_entry", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateHistoryLimit(limit: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_history_limit", { limit }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateRecordingRetentionPeriod(period: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_recording_retention_period", { period }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateHistoryRetentionDays(days: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_history_retention_days", { days }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateHistoryEnabled(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_history_enabled", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updatePurgeAudioOnly(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_purge_audio_only", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateRecordingStorageLimit(limitMb: number | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_recording_storage_limit", { limitMb }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateMeetingNotesDir(dir: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_meeting_notes_dir", { dir }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateMeetingSaveToHistory(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_meeting_save_to_history", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Encrypt or decrypt the history database and recordings; the setting only
+ * changes once all of them are
+ */
+async updateHistoryEncryption(enabled: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_history_encryption", { enabled }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getUsageStats(days: number | null) : Promise<Result<UsageStats, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_usage_stats", { days }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Estimated spend per day, week and provider for the last `days` days
+ */
+async getCostStats(days: number | null) : Promise<Result<CostStats, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_cost_stats", { days }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Requests, failures and cooldowns per API key since the app started
+ */
+async getApiKeyUsage(providerId: string | null) : Promise<ApiKeyUsage[]> {
+    return await TAURI_INVOKE("get_api_key_usage", { providerId });
+},
+/**
+ * Set the price of a model missing from the built-in table; `None` removes it
+ */
+async setCustomModelPrice(model: string, price: ModelPrice | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_custom_model_price", { model, price }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Forget earlier dictations so the next one starts a new conversation
+ */
+async resetConversation() : Promise<void> {
+    await TAURI_INVOKE("reset_conversation");
+},
+async getActionConfigs() : Promise<Result<Partial<{ [key in string]: ActionConfig }>, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_action_configs") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Create or replace the prompt configuration of an action
+ */
+async setActionConfig(actionId: string, config: ActionConfig) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_action_config", { actionId, config }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Remove an action's configuration so it falls back to the global prompt
+ */
+async deleteActionConfig(actionId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_action_config", { actionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Define a new action with its own configuration, e.g. one that pipes its
+ * dictation to a shell command. It starts without a shortcut. Returns its id.
+ */
+async createCustomAction(name: string, config: ActionConfig) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_custom_action", { name, config }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Rename a custom action. Its id follows the name, and whatever refers to
+ * the old id is moved along. Returns the new id.
+ */
+async renameCustomAction(actionId: string, name: string) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("rename_custom_action", { actionId, name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Remove a custom action with its shortcut and configuration, unless
+ * another action's chain still runs it
+ */
+async deleteCustomAction(actionId: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_custom_action", { actionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Text waiting in the preview window, for when the window opens after the
+ * `show-preview` event was sent
+ */
+async getPendingPreview() : Promise<PendingPreview | null> {
+    return await TAURI_INVOKE("get_pending_preview");
+},
+async acceptPreview(id: number, text: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("accept_preview", { id, text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async discardPreview(id: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("discard_preview", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The widget's record button
+ */
+async toggleWidgetRecording() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_widget_recording") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getSnippets() : Promise<Result<Snippet[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_snippets") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Add a snippet, or replace the one with the same trigger phrase
+ */
+async setSnippet(trigger: string, text: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_snippet", { trigger, text }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteSnippet(trigger: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_snippet", { trigger }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getProfiles() : Promise<Result<SettingsProfile[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_profiles") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Save the current shortcuts, prompts and providers as a profile, replacing
+ * the one with the same name. The saved profile becomes the active one.
+ */
+async saveProfile(name: string) : Promise<Result<SettingsProfile, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("save_profile", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete a profile; the current settings stay as they are
+ */
+async deleteProfile(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_profile", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async switchProfile(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("switch_profile", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getAppOverrides() : Promise<Result<Partial<{ [key in string]: AppOverride }>, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_app_overrides") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Add or replace the override for the app with process `process_name`
+ */
+async setAppOverride(processName: string, appOverride: AppOverride) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_app_override", { processName, appOverride }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteAppOverride(processName: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_app_override", { processName }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stub implementation for non-macOS platforms
+ * Always returns false since laptop detection is macOS-specific
+ */
+async isLaptop() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("is_laptop") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+}
+}
+
+/** user-defined events **/
+
 
 
 /** user-defined constants **/
@@ -651,24 +1844,1006 @@ async isLaptop() : Promise<Result<boolean, string>> {
 
 /** user-defined types **/
 
-export type AppSettings = { bindings: Partial<{ [key in string]: ShortcutBinding }>; push_to_talk: boolean; audio_feedback: boolean; audio_feedback_volume?: number; sound_theme?: SoundTheme; start_hidden?: boolean; autostart_enabled?: boolean; update_checks_enabled?: boolean; selected_model?: string; always_on_microphone?: boolean; selected_microphone?: string | null; clamshell_microphone?: string | null; selected_output_device?: string | null; translate_to_english?: boolean; selected_language?: string; overlay_position?: OverlayPosition; debug_mode?: boolean; log_level?: LogLevel; custom_words?: string[]; model_unload_timeout?: ModelUnloadTimeout; word_correction_threshold?: number; history_limit?: number; recording_retention_period?: RecordingRetentionPeriod; paste_method?: PasteMethod; clipboard_handling?: ClipboardHandling; post_process_enabled?: boolean; post_process_provider_id?: string; post_process_providers?: PostProcessProvider[]; post_process_api_keys?: Partial<{ [key in string]: string }>; post_process_models?: Partial<{ [key in string]: string }>; post_process_prompts?: LLMPrompt[]; post_process_selected_prompt_id?: string | null; mute_while_recording?: boolean; append_trailing_space?: boolean; use_online_provider?: boolean; online_provider_id?: string; online_provider_api_keys?: Partial<{ [key in string]: string }>; online_provider_models?: Partial<{ [key in string]: string }>; online_provider_custom_prompt?: string | null; app_language?: string }
+/**
+ * Per-action overrides, keyed by the binding id of the action
+ */
+export type ActionConfig = { 
+/**
+ * Paste the raw transcription without running the LLM
+ */
+skip_post_process?: boolean; system_prompt?: string | null; 
+/**
+ * User prompt template; `${output}` is replaced with the transcription
+ */
+prompt_template?: string | null; 
+/**
+ * Let the model call registered tools (OpenAI-compatible providers only)
+ */
+tools_enabled?: boolean; 
+/**
+ * JSON Schema (as JSON text) the response must follow; the parsed result
+ * is emitted as a `structured-output` event and its fields are typed
+ * instead of the JSON
+ */
+output_schema?: string | null; 
+/**
+ * Have the provider enforce `output_schema` exactly, which OpenAI only
+ * accepts for schemas that require every property
+ */
+output_schema_strict?: boolean; 
+/**
+ * Ordered LLM steps; when set, they replace the single prompt above
+ */
+steps?: PipelineStep[]; 
+/**
+ * Sampling overrides; unset values leave the provider's defaults in place
+ */
+temperature?: number | null; top_p?: number | null; max_tokens?: number | null; stop?: string[]; 
+/**
+ * Where the result goes, all at once; empty follows `output_mode`
+ */
+output_targets?: OutputTarget[]; 
+/**
+ * File for `OutputTarget::File`; falls back to `note_file_path`
+ */
+output_file_path?: string | null; 
+/**
+ * URL that receives the result as JSON for `OutputTarget::Webhook`
+ */
+webhook_url?: string | null; 
+/**
+ * Extra headers sent to the webhook, e.g. an API key
+ */
+webhook_headers?: Partial<{ [key in string]: string }>; 
+/**
+ * Key that signs webhook requests with HMAC-SHA256 when set
+ */
+webhook_secret?: string | null; 
+/**
+ * Vault folder for `OutputTarget::Obsidian`
+ */
+obsidian_vault_path?: string | null; 
+/**
+ * Note in the vault, `{date}` for one per day; `Babbl/{date}.md` when unset
+ */
+obsidian_note_path?: string | null; 
+/**
+ * Entry template with `{{text}}`, `{{transcription}}`, `{{date}}`,
+ * `{{time}}` and `{{action}}`
+ */
+obsidian_template?: string | null; 
+/**
+ * Database for `OutputTarget::Notion`, its id or link
+ */
+notion_database_id?: string | null; 
+/**
+ * Token of a Notion integration the database is shared with
+ */
+notion_token?: string | null; 
+/**
+ * Show the text for review before it's injected
+ */
+confirm_before_inject?: boolean; 
+/**
+ * Command the final text is piped to before it's delivered
+ */
+shell_command?: ShellCommand | null; 
+/**
+ * Steps the final text goes through, in order, before it's delivered
+ */
+chain?: ChainStep[]; 
+/**
+ * Seconds the action may take after the recording stops, overriding
+ * `action_timeout_secs`; zero means no limit
+ */
+timeout_secs?: number | null; 
+/**
+ * What its shortcut does while an earlier run is still processing
+ */
+concurrency?: ConcurrencyPolicy }
+export type ActionCount = { 
+/**
+ * Shortcut action id, `unknown` for entries saved before actions were recorded
+ */
+action_id: string; count: number }
+/**
+ * Per-key usage since the app started
+ */
+export type ApiKeyUsage = { provider_id: string; 
+/**
+ * Last characters of the key, enough to tell keys apart
+ */
+key_hint: string; requests: number; failures: number; rate_limited: number; 
+/**
+ * Authentication or quota failures
+ */
+rejected: number; 
+/**
+ * Seconds until the key is tried first again, if it's cooling down
+ */
+cooldown_secs: number | null }
+/**
+ * Settings that change while a particular app is focused
+ */
+export type AppOverride = { 
+/**
+ * Transcription language code, or "auto"
+ */
+selected_language?: string | null; post_process_enabled?: boolean | null; 
+/**
+ * Prompt used for post-processing instead of the selected one
+ */
+post_process_prompt_id?: string | null; paste_method?: PasteMethod | null }
+export type AppSettings = { 
+/**
+ * Layout version of the stored settings, see [`migrate_settings`]
+ */
+schema_version?: number; bindings: Partial<{ [key in string]: ShortcutBinding }>; push_to_talk: boolean; audio_feedback: boolean; audio_feedback_volume?: number; sound_theme?: SoundTheme; start_hidden?: boolean; autostart_enabled?: boolean; 
+/**
+ * Stay in the tray when started at login, even if `start_hidden` is off
+ */
+autostart_minimized?: boolean; 
+/**
+ * Seconds to wait after a start at login before opening the microphone,
+ * so the audio devices are there
+ */
+autostart_delay_secs?: number; update_checks_enabled?: boolean; update_channel?: UpdateChannel; selected_model?: string; always_on_microphone?: boolean; selected_microphone?: string | null; clamshell_microphone?: string | null; selected_output_device?: string | null; translate_to_english?: boolean; selected_language?: string; overlay_position?: OverlayPosition; 
+/**
+ * Clicks go through the overlay to the window below it
+ */
+overlay_click_through?: boolean; 
+/**
+ * Overlay corner per display, by display name
+ */
+overlay_display_anchors?: Partial<{ [key in string]: OverlayAnchor }>; 
+/**
+ * Show the floating widget with a record button
+ */
+widget_enabled?: boolean; 
+/**
+ * Where the widget was dragged to, None until it's moved
+ */
+widget_position?: WidgetPosition | null; debug_mode?: boolean; 
+/**
+ * Notify when a transcription finishes after switching away from the app
+ */
+notify_transcription_complete?: boolean; notify_provider_errors?: boolean; 
+/**
+ * Notify when the microphone or input control is refused
+ */
+notify_permission_problems?: boolean; log_level?: LogLevel; 
+/**
+ * Levels for single modules in the log files, e.g. `input_hook`,
+ * overriding `log_level`
+ */
+log_module_levels?: Partial<{ [key in string]: LogLevel }>; custom_words?: string[]; snippets?: Snippet[]; profiles?: SettingsProfile[]; 
+/**
+ * Profile the current settings belong to; edits are saved back to it on switch
+ */
+active_profile_id?: string | null; 
+/**
+ * Overrides keyed by the process name of the focused app, e.g. `code`
+ */
+app_overrides?: Partial<{ [key in string]: AppOverride }>; model_unload_timeout?: ModelUnloadTimeout; warm_up?: WarmUp; low_power_mode?: LowPowerMode; 
+/**
+ * Local model used instead of the selected one in low-power mode
+ */
+low_power_model?: string | null; word_correction_threshold?: number; history_limit?: number; recording_retention_period?: RecordingRetentionPeriod; 
+/**
+ * Days kept with the custom retention period
+ */
+history_retention_days?: number; 
+/**
+ * Off stops saving new dictations and their audio
+ */
+history_enabled?: boolean; 
+/**
+ * Expired entries lose their recording but keep their text
+ */
+purge_audio_only?: boolean; 
+/**
+ * Total size of kept recordings; the oldest unsaved ones are removed above it
+ */
+recording_storage_limit_mb?: number | null; 
+/**
+ * Keep the history database and recordings encrypted with a key from the
+ * OS keyring
+ */
+history_encryption_enabled?: boolean; 
+/**
+ * Folder meeting transcripts are written to, `meetings` in the app data
+ * folder when unset
+ */
+meeting_notes_dir?: string | null; 
+/**
+ * Also keep each segment of a meeting in history, with its audio
+ */
+meeting_save_to_history?: boolean; paste_method?: PasteMethod; clipboard_handling?: ClipboardHandling; undo_method?: UndoMethod; output_mode?: OutputMode; 
+/**
+ * Markdown file for `OutputMode::AppendToNote`; `{date}` starts a file per day
+ */
+note_file_path?: string | null; post_process_enabled?: boolean; post_process_provider_id?: string; post_process_providers?: PostProcessProvider[]; post_process_api_keys?: Partial<{ [key in string]: string }>; 
+/**
+ * Keys tried in order when the main key is rejected or rate limited
+ */
+post_process_fallback_api_keys?: Partial<{ [key in string]: string[] }>; post_process_models?: Partial<{ [key in string]: string }>; post_process_prompts?: LLMPrompt[]; gemini_safety_threshold?: GeminiSafetyThreshold; post_process_selected_prompt_id?: string | null; action_configs?: Partial<{ [key in string]: ActionConfig }>; translate_target_language?: string; long_transcript_strategy?: LongTranscriptStrategy; mute_while_recording?: boolean; 
+/**
+ * Megabytes of a recording held in memory; the rest waits on disk until
+ * the recording stops
+ */
+recording_memory_limit_mb?: number; 
+/**
+ * Start a dictation when the wake phrase is said
+ */
+wake_word_enabled?: boolean; 
+/**
+ * Name of the phrase; it's recognized by the recordings made of it
+ */
+wake_word_phrase?: string; 
+/**
+ * 0 to 1, how far what's heard may be from the recordings of the phrase
+ */
+wake_word_sensitivity?: number; append_trailing_space?: boolean; typing_chars_per_second?: number; 
+/**
+ * Put back what was on the clipboard after a clipboard paste
+ */
+restore_clipboard?: boolean; clipboard_restore_delay_ms?: number; 
+/**
+ * Paste Markdown output as rich text (HTML) so formatting survives in
+ * mail and document editors
+ */
+markdown_rich_text?: boolean; 
+/**
+ * Add a space and adjust capitalization to fit the text before the
+ * cursor, where the app exposes it through accessibility
+ */
+smart_spacing?: boolean; 
+/**
+ * Type post-processed text while the LLM is still generating it,
+ * correcting revised words with backspaces
+ */
+streaming_injection?: boolean; network_max_attempts?: number; 
+/**
+ * Seconds an action may take from the end of the recording until its
+ * result is delivered before it's cancelled; zero means no limit
+ */
+action_timeout_secs?: number; 
+/**
+ * Proxy for all outgoing requests (http, https, socks5 or socks5h URL)
+ */
+proxy_url?: string | null; 
+/**
+ * PEM bundle of extra CAs to trust, e.g. for TLS-inspecting corporate proxies
+ */
+custom_ca_path?: string | null; 
+/**
+ * Let `babbl://` links start a recording or transcribe a file; any web
+ * page can open one
+ */
+allow_link_actions?: boolean; 
+/**
+ * Serve the local HTTP control API on 127.0.0.1
+ */
+control_api_enabled?: boolean; control_api_port?: number; 
+/**
+ * Token every control API and WebSocket client needs; created when
+ * either is first enabled
+ */
+control_api_token?: string | null; 
+/**
+ * Serve events and accept control messages over WebSocket on 127.0.0.1
+ */
+websocket_enabled?: boolean; websocket_port?: number; 
+/**
+ * Publish states and transcripts to an MQTT broker and take commands
+ */
+mqtt_enabled?: boolean; mqtt_host?: string; mqtt_port?: number; mqtt_username?: string | null; mqtt_password?: string | null; 
+/**
+ * Topics are `<prefix>/state`, `<prefix>/transcript` and so on
+ */
+mqtt_topic_prefix?: string; 
+/**
+ * Off unless asked for, every subscriber on the broker can read them
+ */
+mqtt_publish_transcripts?: boolean; 
+/**
+ * Show live captions in an OBS text source through obs-websocket
+ */
+obs_enabled?: boolean; obs_host?: string; obs_port?: number; obs_password?: string | null; 
+/**
+ * Name of the text source the captions go to
+ */
+obs_text_source?: string; 
+/**
+ * Clear the caption this long after the final transcript, 0 keeps it
+ */
+obs_clear_after_secs?: number; 
+/**
+ * Mute Teams and Zoom calls while recording
+ */
+call_mute_enabled?: boolean; 
+/**
+ * Token Teams handed out when it allowed Babbl to control meetings
+ */
+call_mute_teams_token?: string | null; 
+/**
+ * Rhai script with hooks that can change the text or veto injection
+ */
+hook_script_path?: string | null; 
+/**
+ * Send recent dictations along as chat history so follow-ups can edit them
+ */
+conversation_context_enabled?: boolean; conversation_context_turns?: number; conversation_context_window_minutes?: number; 
+/**
+ * Prices for models missing from the built-in table, keyed by model name
+ */
+custom_model_prices?: Partial<{ [key in string]: ModelPrice }>; 
+/**
+ * Soft LLM spending limit per calendar month; exceeding it only warns
+ */
+monthly_llm_budget_usd?: number | null; 
+/**
+ * Service tier per provider id, e.g. `flex` for Groq
+ */
+post_process_service_tiers?: Partial<{ [key in string]: string }>; 
+/**
+ * Extra request body fields per provider id, as JSON object text
+ */
+post_process_extra_body?: Partial<{ [key in string]: string }>; 
+/**
+ * GGUF file used by the embedded llama.cpp provider
+ */
+llama_cpp_model_path?: string | null; 
+/**
+ * Model layers offloaded to the GPU; 0 runs on the CPU only
+ */
+llama_cpp_gpu_layers?: number; use_online_provider?: boolean; online_provider_id?: string; online_provider_api_keys?: Partial<{ [key in string]: string }>; online_provider_fallback_api_keys?: Partial<{ [key in string]: string[] }>; online_provider_models?: Partial<{ [key in string]: string }>; online_provider_custom_prompt?: string | null; app_language?: string }
 export type AudioDevice = { index: string; name: string; is_default: boolean }
+export type AutostartStatus = { 
+/**
+ * Whether the login entry is registered with the OS
+ */
+registered: boolean; enabled: boolean; minimized: boolean; delay_secs: number }
+export type BenchmarkReport = { audio_ms: number; 
+/**
+ * Whether the sample was generated rather than read from a file
+ */
+synthetic_audio: boolean; 
+/**
+ * Getting the recording out of its buffer once it stops
+ */
+capture_flush_ms: number; transcription: ProviderTiming[]; post_processing: ProviderTiming[]; 
+/**
+ * Putting the text on the clipboard; the keystroke and the target app
+ * aren't included
+ */
+inject_ms: number | null }
 export type BindingResponse = { success: boolean; binding: ShortcutBinding | null; error: string | null }
+export type Capability = { id: CapabilityId; status: CapabilityStatus; 
+/**
+ * What's wrong or what to do, ready to show
+ */
+detail: string | null; 
+/**
+ * `request_capability` can prompt for it
+ */
+can_request: boolean }
+/**
+ * Something the app needs before dictation works
+ */
+export type CapabilityId = "microphone_permission" | 
+/**
+ * Needed to paste or type into other apps (macOS)
+ */
+"accessibility_permission" | 
+/**
+ * Needed for mouse button shortcuts (macOS)
+ */
+"input_monitoring_permission" | "audio_device" | 
+/**
+ * Keys of the providers the settings use
+ */
+"api_key" | 
+/**
+ * Whether pasting into a focused field works, see `run_injection_test`
+ */
+"injection"
+export type CapabilityStatus = "ready" | "needs_action" | 
+/**
+ * Not needed on this platform or with these settings
+ */
+"not_required" | 
+/**
+ * Can't be checked without the user, e.g. the injection test
+ */
+"unknown"
+export type ChainStep = { step: ChainStepKind; on_error?: StepErrorPolicy }
+/**
+ * Work done by one chain step; each gets the text the previous one produced
+ */
+export type ChainStepKind = 
+/**
+ * Post-process with another action's prompt, e.g. `mode_summarize`
+ */
+{ type: "action"; action_id: string } | { type: "shell"; command: ShellCommand } | 
+/**
+ * Send the text on and keep going; `inject` waits for the end of the chain
+ */
+{ type: "output"; targets: OutputTarget[] } | 
+/**
+ * Show the text in a notification
+ */
+{ type: "notify"; title: string }
 export type ClipboardHandling = "dont_modify" | "copy_to_clipboard"
+export type ComponentHealth = { state: ComponentState; 
+/**
+ * Restarts since launch
+ */
+restarts: number; 
+/**
+ * Restarts since it was last seen running
+ */
+restart_attempts: number; last_error: string | null }
+export type ComponentState = "running" | 
+/**
+ * Not meant to run with the current settings
+ */
+"disabled" | 
+/**
+ * Stopped and restarted, not confirmed running yet
+ */
+"restarting" | 
+/**
+ * Stopped, and restarting it didn't help
+ */
+"down"
+/**
+ * What happens when an action is started while an earlier run of it hasn't
+ * delivered its result yet
+ */
+export type ConcurrencyPolicy = 
+/**
+ * Both runs go on and deliver whenever they're done
+ */
+"parallel" | 
+/**
+ * The new run records right away but delivers after the earlier one
+ */
+"queue" | 
+/**
+ * The new run doesn't start
+ */
+"ignore" | 
+/**
+ * The earlier run is cancelled
+ */
+"restart"
+/**
+ * Result of a provider connection test
+ */
+export type ConnectionCheck = { ok: boolean; model: string; 
+/**
+ * Round trip of the test request, including the model's answer
+ */
+latency_ms: number; error: LlmError | null; 
+/**
+ * Short explanation and fix for `error`, ready to show
+ */
+user_message: string | null; remediation: string | null }
+/**
+ * Estimated spend, derived from the token counters and the pricing table
+ */
+export type CostStats = { total_usd: number; daily: PeriodCost[]; weekly: PeriodCost[]; by_provider: PeriodCost[]; 
+/**
+ * Spend since the first of the current month, compared against the budget
+ */
+month_to_date_usd: number; monthly_budget_usd: number | null; 
+/**
+ * Models with usage but no known price; their tokens are not counted
+ */
+unpriced_models: string[] }
 export type CustomSounds = { start: boolean; stop: boolean }
+export type DailyUsage = { day: string; provider_id: string; model: string; request_count: number; prompt_tokens: number; completion_tokens: number; total_tokens: number }
+export type DailyWords = { 
+/**
+ * Local day, `YYYY-MM-DD`
+ */
+day: string; entry_count: number; word_count: number }
+/**
+ * Aggregates over the history for the stats screen
+ */
+export type DictationStats = { entry_count: number; 
+/**
+ * Words of the final (post-processed when available) text
+ */
+word_count: number; 
+/**
+ * Oldest day first, days without dictations left out
+ */
+daily: DailyWords[]; 
+/**
+ * Minutes of recorded audio, for entries that know their length
+ */
+recorded_minutes: number; 
+/**
+ * Spoken words per recorded minute
+ */
+speaking_wpm: number | null; 
+/**
+ * Time typing the final text would have taken, minus the time spent speaking
+ */
+time_saved_minutes: number; 
+/**
+ * Most used first
+ */
+actions: ActionCount[]; 
+/**
+ * LLM requests and failures per provider
+ */
+providers: ProviderErrors[] }
+/**
+ * A monitor, for picking the overlay's corner per display
+ */
+export type DisplayInfo = { name: string; width: number; height: number; scale_factor: number; 
+/**
+ * Where the overlay goes on it, `None` to follow `overlay_position`
+ */
+anchor: OverlayAnchor | null }
 export type EngineType = "Whisper" | "Parakeet"
-export type HistoryEntry = { id: number; file_name: string; timestamp: number; saved: boolean; title: string; transcription_text: string; post_processed_text: string | null; post_process_prompt: string | null }
+export type ExportFormat = 
+/**
+ * One section per day, for reading
+ */
+"markdown" | 
+/**
+ * One row per entry, for spreadsheets
+ */
+"csv" | 
+/**
+ * Array of entries, for scripts
+ */
+"json"
+export type ExportOptions = { 
+/**
+ * Add the id, title, saved flag, raw transcript and prompt
+ */
+include_metadata?: boolean; 
+/**
+ * Add the path of each entry's recording
+ */
+include_audio?: boolean }
+export type FatalError = { id: number; kind: FatalErrorKind; message: string; 
+/**
+ * Source location of a panic
+ */
+location: string | null; thread: string | null; backtrace: string | null; run_id: string | null; 
+/**
+ * In milliseconds since the epoch
+ */
+occurred_at: number }
+export type FatalErrorKind = "panic" | "error"
+export type GeminiSafetyThreshold = "block_none" | "block_only_high" | "block_medium_and_above" | "block_low_and_above"
+export type HealthStatus = { 
+/**
+ * Whether nothing is down, for the indicator
+ */
+healthy: boolean; input_listener: ComponentHealth; audio_stream: ComponentHealth; control_api: ComponentHealth; 
+/**
+ * In milliseconds since the epoch, `None` before the first check
+ */
+checked_at: number | null }
+/**
+ * Position after the last entry of a page, newest first
+ */
+export type HistoryCursor = { timestamp: number; id: number }
+export type HistoryEntry = { id: number; file_name: string; timestamp: number; saved: boolean; title: string; transcription_text: string; post_processed_text: string | null; post_process_prompt: string | null; 
+/**
+ * Shortcut action that produced the entry; unknown for older entries
+ */
+action_id: string | null; 
+/**
+ * Length of the recording; unknown for older entries
+ */
+duration_ms: number | null; 
+/**
+ * The recording was removed by the retention policy
+ */
+audio_deleted: boolean; 
+/**
+ * Process name of the app dictated into; unknown for older entries
+ */
+app_name: string | null; 
+/**
+ * Id of the dictation, as in its events and log lines; unknown for
+ * older entries
+ */
+run_id: string | null }
+/**
+ * Conditions every listed entry has to meet; unset ones match everything
+ */
+export type HistoryFilter = { range?: HistoryRange; app_name?: string | null; action_id?: string | null; status?: HistoryStatus | null; 
+/**
+ * Entries with an unknown length count as 0
+ */
+min_duration_ms?: number | null }
+export type HistoryPage = { entries: HistoryEntry[]; 
+/**
+ * Cursor for the next page; None on the last page
+ */
+next_cursor: HistoryCursor | null }
+/**
+ * Entries by timestamp in Unix seconds; `end` is exclusive and a missing
+ * bound is open
+ */
+export type HistoryRange = { start?: number | null; end?: number | null }
+/**
+ * Earlier post-processed output of a history entry, kept when it was
+ * re-processed
+ */
+export type HistoryRevision = { id: number; history_id: number; 
+/**
+ * When this output was replaced
+ */
+timestamp: number; post_processed_text: string | null; post_process_prompt: string | null }
+/**
+ * History entry matching a search, with the matching text highlighted
+ */
+export type HistorySearchResult = { entry: HistoryEntry; 
+/**
+ * Excerpt of the best matching text as HTML, matches wrapped in `<mark>`
+ * tags and everything else escaped
+ */
+snippet: string }
+export type HistoryStatus = "saved" | 
+/**
+ * Has post-processed text
+ */
+"post_processed" | 
+/**
+ * Only the raw transcript
+ */
+"raw"
 export type LLMPrompt = { id: string; name: string; prompt: string }
+/**
+ * Classified failure of an LLM or cloud transcription request.
+ * 
+ * Serialized with a `kind` tag so the frontend can pick a message and a fix
+ * without parsing provider error bodies.
+ */
+export type LlmError = 
+/**
+ * The API key is missing, wrong or lacks access
+ */
+{ kind: "auth"; message: string } | 
+/**
+ * Too many requests; `retry_after_secs` is taken from the `Retry-After` header
+ */
+{ kind: "rate_limited"; retry_after_secs: number | null; message: string } | 
+/**
+ * The account is out of credits or over its billing quota
+ */
+{ kind: "quota"; message: string } | 
+/**
+ * The provider could not be reached
+ */
+{ kind: "network"; message: string } | 
+/**
+ * The configured model does not exist or isn't available to this key
+ */
+{ kind: "invalid_model"; message: string } | 
+/**
+ * The provider refused the prompt or the answer for safety reasons
+ */
+{ kind: "content_filter"; message: string } | 
+/**
+ * The response could not be understood
+ */
+{ kind: "parse"; message: string } | 
+/**
+ * Anything else, e.g. invalid requests or server errors
+ */
+{ kind: "other"; message: string }
 export type LogLevel = "trace" | "debug" | "info" | "warn" | "error"
+/**
+ * How transcripts that exceed the model's context window are shortened
+ */
+export type LongTranscriptStrategy = "truncate" | "summarize"
+/**
+ * When Babbl switches to lighter behavior, see [`crate::power`]
+ */
+export type LowPowerMode = 
+/**
+ * On battery or in the system's power saver
+ */
+"auto" | "always" | "never"
+export type MeetingSession = { 
+/**
+ * Run id of the meeting's history entries
+ */
+id: string; 
+/**
+ * Unix milliseconds
+ */
+started_at: number; 
+/**
+ * Markdown document the transcript is written to
+ */
+document: string }
+export type MicrophoneAccess = 
+/**
+ * Not asked yet; the system prompt shows when requested
+ */
+"not_determined" | "granted" | 
+/**
+ * Refused by the user, only the system settings can turn it back on
+ */
+"denied" | 
+/**
+ * Blocked by a profile or parental controls
+ */
+"restricted" | 
+/**
+ * Can't be looked at on this platform
+ */
+"unknown"
 export type ModelInfo = { id: string; name: string; description: string; filename: string; url: string | null; size_mb: number; is_downloaded: boolean; is_downloading: boolean; partial_size: number; is_directory: boolean; engine_type: EngineType; accuracy_score: number; speed_score: number }
 export type ModelLoadStatus = { is_loaded: boolean; current_model: string | null }
+/**
+ * Token price in USD per million tokens
+ */
+export type ModelPrice = { input_per_million: number; output_per_million: number }
 export type ModelUnloadTimeout = "never" | "immediately" | "min_2" | "min_5" | "min_10" | "min_15" | "hour_1" | "sec_5"
-export type OverlayPosition = "none" | "top" | "bottom"
-export type PasteMethod = "ctrl_v" | "direct" | "none" | "shift_insert" | "ctrl_shift_v"
-export type PostProcessProvider = { id: string; label: string; base_url: string; allow_base_url_edit?: boolean; models_endpoint?: string | null }
-export type RecordingRetentionPeriod = "never" | "preserve_limit" | "days_3" | "weeks_2" | "months_3"
+export type NotificationKind = 
+/**
+ * Finished while another app than the one dictated into was focused
+ */
+"transcription_complete" | 
+/**
+ * Transcription or post-processing provider failed
+ */
+"provider_error" | 
+/**
+ * Microphone or input control was refused, or a link wasn't allowed
+ */
+"permission_problem" | 
+/**
+ * Asked for by a notify step of an action chain
+ */
+"action_result" | 
+/**
+ * A recording was left unfinished when Babbl last stopped
+ */
+"recording_recovered"
+/**
+ * A recording left behind by a crash
+ */
+export type OrphanedRecording = { file_name: string; 
+/**
+ * When it started, in milliseconds since the epoch
+ */
+started_at: number; 
+/**
+ * How much of it was saved
+ */
+duration_ms: number }
+/**
+ * Where finished transcripts go
+ */
+export type OutputMode = 
+/**
+ * Paste or type into the focused app
+ */
+"inject" | 
+/**
+ * Append to the Markdown file at `note_file_path`
+ */
+"append_to_note"
+/**
+ * One destination of an action's result
+ */
+export type OutputTarget = 
+/**
+ * Paste or type into the focused app
+ */
+"inject" | 
+/**
+ * Leave the result on the clipboard without pasting it
+ */
+"clipboard" | 
+/**
+ * Show the result in the app window
+ */
+"popup" | 
+/**
+ * Append to a Markdown file, like `OutputMode::AppendToNote`
+ */
+"file" | 
+/**
+ * POST the result to `webhook_url`
+ */
+"webhook" | 
+/**
+ * Append to a note in the Obsidian vault at `obsidian_vault_path`
+ */
+"obsidian" | 
+/**
+ * Add a page to the Notion database `notion_database_id`
+ */
+"notion"
+/**
+ * Where on a display the overlay goes, overriding `overlay_position` there
+ */
+export type OverlayAnchor = "top_left" | "top_center" | "top_right" | "bottom_left" | "bottom_center" | "bottom_right"
+export type OverlayPosition = "none" | "top" | "bottom" | 
+/**
+ * Next to the mouse cursor
+ */
+"cursor"
+export type PasteMethod = "ctrl_v" | "direct" | "none" | "shift_insert" | "ctrl_shift_v" | 
+/**
+ * Type the text key by key at `typing_chars_per_second`, for apps that
+ * don't accept paste (terminals, remote desktops)
+ */
+"keystrokes"
+/**
+ * Text waiting for the user to accept, edit or discard it
+ */
+export type PendingPreview = { id: number; binding_id: string; text: string }
+export type PeriodCost = { 
+/**
+ * Day (`YYYY-MM-DD`), week start (Monday) or provider id
+ */
+key: string; cost_usd: number }
+/**
+ * One LLM call in a multi-step action pipeline
+ */
+export type PipelineStep = { name: string; system_prompt?: string | null; 
+/**
+ * `${output}` is the previous step's result (the transcription for the first step),
+ * `${transcription}` the original text and `${step.<name>}` an earlier step's result
+ */
+prompt_template: string }
+export type PostProcessProvider = { id: string; label: string; base_url: string; allow_base_url_edit?: boolean; models_endpoint?: string | null; 
+/**
+ * API version query parameter, for providers that version by URL (Azure)
+ */
+api_version?: string | null }
+/**
+ * Successful and failed LLM requests of one provider
+ */
+export type ProviderErrors = { provider_id: string; request_count: number; error_count: number; 
+/**
+ * Failed share of all requests, 0 to 1
+ */
+error_rate: number }
+export type ProviderTiming = { provider_id: string; model: string; 
+/**
+ * Estimated from a round trip to the provider; None for local models
+ */
+upload_ms: number | null; 
+/**
+ * Transcription, or post-processing, without the upload
+ */
+inference_ms: number | null; total_ms: number; output: string | null; error: string | null }
+export type RecordingRetentionPeriod = "never" | "preserve_limit" | "days_3" | "weeks_2" | "months_3" | 
+/**
+ * Keep `history_retention_days` days
+ */
+"custom_days"
+export type SecureInput = { 
+/**
+ * Process that turned it on, when macOS tells
+ */
+pid: number | null; process: string | null }
+export type ServiceStatus = { 
+/**
+ * Whether systemd user services can be used here
+ */
+supported: boolean; installed: boolean; enabled: boolean; unit_path: string | null }
+/**
+ * A settings field that was rejected or needs attention
+ */
+export type SettingsError = { 
+/**
+ * Dotted path of the field, e.g. `bindings.transcribe.current_binding`
+ */
+path: string; message: string }
+/**
+ * Named set of shortcuts, prompts and provider choices that can be switched
+ * to in one go. API keys are shared by all profiles.
+ */
+export type SettingsProfile = { id: string; name: string; 
+/**
+ * Shortcut per binding id
+ */
+bindings: Partial<{ [key in string]: string }>; post_process_enabled: boolean; post_process_provider_id: string; post_process_models: Partial<{ [key in string]: string }>; post_process_prompts: LLMPrompt[]; post_process_selected_prompt_id: string | null; action_configs: Partial<{ [key in string]: ActionConfig }>; use_online_provider: boolean; online_provider_id: string; online_provider_models: Partial<{ [key in string]: string }> }
+/**
+ * Command an action pipes its text to on stdin, run with the platform shell
+ * (`sh -c`, or `cmd /C` on Windows)
+ */
+export type ShellCommand = { command: string; 
+/**
+ * Directory it runs in; the app's own when unset
+ */
+working_dir?: string | null; 
+/**
+ * Added to the environment, next to `BABBL_ACTION_ID` and
+ * `BABBL_TRANSCRIPTION`
+ */
+env?: Partial<{ [key in string]: string }>; 
+/**
+ * The command is killed after this long
+ */
+timeout_secs?: number; 
+/**
+ * Continue with what it prints instead of the text it was given
+ */
+capture_output?: boolean }
 export type ShortcutBinding = { id: string; name: string; description: string; default_binding: string; current_binding: string }
+/**
+ * Stored text inserted when its trigger phrase is spoken
+ */
+export type Snippet = { 
+/**
+ * Spoken phrase, matched ignoring case and punctuation
+ */
+trigger: string; text: string }
 export type SoundTheme = "marimba" | "pop" | "custom"
+/**
+ * What a chain does when one of its steps fails
+ */
+export type StepErrorPolicy = 
+/**
+ * End the chain without delivering the text
+ */
+"stop" | 
+/**
+ * Go on with the text from before the step
+ */
+"continue"
+/**
+ * How the undo action removes the last injected text
+ */
+export type UndoMethod = 
+/**
+ * One backspace per injected character
+ */
+"backspace" | 
+/**
+ * Ctrl+Z / Cmd+Z, for apps that treat the paste as a single edit
+ */
+"platform_undo"
+/**
+ * Releases Babbl updates to, see [`crate::updater`]
+ */
+export type UpdateChannel = "stable" | 
+/**
+ * Pre-releases, ahead of stable
+ */
+"beta"
+export type UpdateInfo = { version: string; current_version: string; notes: string | null; channel: UpdateChannel; 
+/**
+ * Downloaded and verified, ready to install on restart
+ */
+ready: boolean }
+export type UsageStats = { daily: DailyUsage[]; request_count: number; prompt_tokens: number; completion_tokens: number; total_tokens: number }
+/**
+ * When the models and connections a dictation needs are readied, see
+ * [`crate::warmup`]
+ */
+export type WarmUp = "off" | 
+/**
+ * As soon as Babbl starts
+ */
+"launch" | 
+/**
+ * Once Babbl has sat idle for a bit after starting
+ */
+"idle"
+/**
+ * Top left corner of the widget in logical screen coordinates
+ */
+export type WidgetPosition = { x: number; y: number }
 
 /** tauri-specta globals **/
 
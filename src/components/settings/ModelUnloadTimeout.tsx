@@ -60,7 +60,8 @@ export const ModelUnloadTimeoutSetting: React.FC<ModelUnloadTimeoutProps> = ({
     const newTimeout = event.target.value as ModelUnloadTimeout;
 
     try {
-      await commands.setModelUnloadTimeout(newTimeout);
+      const result = await commands.setModelUnloadTimeout(newTimeout);
+      if (result.status === "error") throw new Error(result.error);
       updateSetting("model_unload_timeout", newTimeout);
     } catch (error) {
       console.error("Failed to update model unload timeout:", error);
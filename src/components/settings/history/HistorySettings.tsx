@@ -89,18 +89,9 @@ export const HistorySettings: React.FC = () => {
     }
   };
 
-  const getAudioUrl = async (fileName: string) => {
-    try {
-      const result = await commands.getAudioFilePath(fileName);
-      if (result.status === "ok") {
-        return convertFileSrc(`${result.data}`, "asset");
-      }
-      return null;
-    } catch (error) {
-      console.error("Failed to get audio file path:", error);
-      return null;
-    }
-  };
+  // Served by the backend, which decrypts encrypted recordings in memory
+  const getAudioUrl = async (fileName: string) =>
+    convertFileSrc(fileName, "history-audio");
 
   const deleteAudioEntry = async (id: number) => {
     try {
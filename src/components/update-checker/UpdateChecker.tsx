@@ -1,8 +1,7 @@
 import React, { useState, useEffect, useRef } from "react";
 import { useTranslation } from "react-i18next";
-import { check } from "@tauri-apps/plugin-updater";
-import { relaunch } from "@tauri-apps/plugin-process";
 import { listen } from "@tauri-apps/api/event";
+import { commands, type UpdateInfo } from "@/bindings";
 import { ProgressBar } from "../shared";
 import { useSettings } from "../../hooks/useSettings";
 
@@ -10,11 +9,17 @@ interface UpdateCheckerProps {
   className?: string;
 }
 
+interface DownloadProgress {
+  version: string;
+  downloaded: number;
+  total: number | null;
+}
+
 const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
   const { t } = useTranslation();
   // Update checking state
   const [isChecking, setIsChecking] = useState(false);
-  const [updateAvailable, setUpdateAvailable] = useState(false);
+  const [update, setUpdate] = useState<UpdateInfo | null>(null);
   const [isInstalling, setIsInstalling] = useState(false);
   const [downloadProgress, setDownloadProgress] = useState(0);
   const [showUpToDate, setShowUpToDate] = useState(false);
@@ -22,11 +27,10 @@ const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
   const { settings, isLoading } = useSettings();
   const settingsLoaded = !isLoading && settings !== null;
   const updateChecksEnabled = settings?.update_checks_enabled ?? false;
+  const updateAvailable = update !== null;
 
   const upToDateTimeoutRef = useRef<ReturnType<typeof setTimeout>>();
   const isManualCheckRef = useRef(false);
-  const downloadedBytesRef = useRef(0);
-  const contentLengthRef = useRef(0);
 
   useEffect(() => {
     // Wait for settings to load before doing anything
@@ -37,23 +41,46 @@ const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
         clearTimeout(upToDateTimeoutRef.current);
       }
       setIsChecking(false);
-      setUpdateAvailable(false);
+      setUpdate(null);
       setShowUpToDate(false);
       return;
     }
 
-    checkForUpdates();
+    // The backend checks in the background; pick up what it found already
+    commands
+      .getPendingUpdate()
+      .then((pending) => (pending ? setUpdate(pending) : checkForUpdates()))
+      .catch(console.error);
 
     // Listen for update check events
     const updateUnlisten = listen("check-for-updates", () => {
       handleManualUpdateCheck();
     });
+    const progressUnlisten = listen<DownloadProgress>(
+      "update-download-progress",
+      (event) => {
+        const { downloaded, total } = event.payload;
+        const progress = total ? Math.round((downloaded / total) * 100) : 0;
+        setDownloadProgress(Math.min(progress, 100));
+      },
+    );
+    const readyUnlisten = listen<UpdateInfo>("update-ready", (event) => {
+      setUpdate(event.payload);
+      setDownloadProgress(0);
+    });
+    const failedUnlisten = listen("update-download-failed", () => {
+      setUpdate(null);
+      setDownloadProgress(0);
+    });
 
     return () => {
       if (upToDateTimeoutRef.current) {
         clearTimeout(upToDateTimeoutRef.current);
       }
       updateUnlisten.then((fn) => fn());
+      progressUnlisten.then((fn) => fn());
+      readyUnlisten.then((fn) => fn());
+      failedUnlisten.then((fn) => fn());
     };
   }, [settingsLoaded, updateChecksEnabled]);
 
@@ -63,13 +90,15 @@ const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
 
     try {
       setIsChecking(true);
-      const update = await check();
+      const result = await commands.checkForUpdate();
+      if (result.status === "error") throw new Error(result.error);
+      const found = result.data;
 
-      if (update) {
-        setUpdateAvailable(true);
+      if (found) {
+        setUpdate(found);
         setShowUpToDate(false);
       } else {
-        setUpdateAvailable(false);
+        setUpdate(null);
 
         if (isManualCheckRef.current) {
           setShowUpToDate(true);
@@ -95,47 +124,16 @@ const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
     checkForUpdates();
   };
 
+  // Restarts once a dictation underway is done, installing on the way out
   const installUpdate = async () => {
-    if (!updateChecksEnabled) return;
+    if (!update?.ready) return;
     try {
       setIsInstalling(true);
-      setDownloadProgress(0);
-      downloadedBytesRef.current = 0;
-      contentLengthRef.current = 0;
-      const update = await check();
-
-      if (!update) {
-        console.log("No update available during install attempt");
-        return;
-      }
-
-      await update.downloadAndInstall((event) => {
-        switch (event.event) {
-          case "Started":
-            downloadedBytesRef.current = 0;
-            contentLengthRef.current = event.data.contentLength ?? 0;
-            break;
-          case "Progress":
-            downloadedBytesRef.current += event.data.chunkLength;
-            const progress =
-              contentLengthRef.current > 0
-                ? Math.round(
-                  (downloadedBytesRef.current / contentLengthRef.current) *
-                  100,
-                )
-                : 0;
-            setDownloadProgress(Math.min(progress, 100));
-            break;
-        }
-      });
-      await relaunch();
+      const result = await commands.restartToUpdate();
+      if (result.status === "error") throw new Error(result.error);
     } catch (error) {
       console.error("Failed to install update:", error);
-    } finally {
       setIsInstalling(false);
-      setDownloadProgress(0);
-      downloadedBytesRef.current = 0;
-      contentLengthRef.current = 0;
     }
   };
 
@@ -144,24 +142,23 @@ const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
     if (!updateChecksEnabled) {
       return t("footer.updateCheckingDisabled");
     }
-    if (isInstalling) {
-      return downloadProgress > 0 && downloadProgress < 100
+    if (isInstalling) return t("footer.installing");
+    if (update && !update.ready) {
+      return downloadProgress > 0
         ? t("footer.downloading", {
           progress: downloadProgress.toString().padStart(3),
         })
-        : downloadProgress === 100
-          ? t("footer.installing")
-          : t("footer.preparing");
+        : t("footer.preparing");
     }
     if (isChecking) return t("footer.checkingUpdates");
     if (showUpToDate) return t("footer.upToDate");
-    if (updateAvailable) return t("footer.updateAvailableShort");
+    if (updateAvailable) return t("footer.restartToUpdate");
     return t("footer.checkForUpdates");
   };
 
   const getUpdateStatusAction = () => {
     if (!updateChecksEnabled) return undefined;
-    if (updateAvailable && !isInstalling) return installUpdate;
+    if (update?.ready && !isInstalling) return installUpdate;
     if (!isChecking && !isInstalling && !updateAvailable)
       return handleManualUpdateCheck;
     return undefined;
@@ -169,7 +166,8 @@ const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
 
   const isUpdateDisabled = !updateChecksEnabled || isChecking || isInstalling;
   const isUpdateClickable =
-    !isUpdateDisabled && (updateAvailable || (!isChecking && !showUpToDate));
+    !isUpdateDisabled &&
+    (update?.ready || (!updateAvailable && !showUpToDate));
 
   return (
     <div className={`flex items-center gap-3 ${className}`}>
@@ -190,7 +188,7 @@ const UpdateChecker: React.FC<UpdateCheckerProps> = ({ className = "" }) => {
         </span>
       )}
 
-      {isInstalling && downloadProgress > 0 && downloadProgress < 100 && (
+      {update && !update.ready && downloadProgress > 0 && (
         <ProgressBar
           progress={[
             {
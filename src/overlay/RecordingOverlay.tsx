@@ -8,7 +8,7 @@ import { syncLanguageFromSettings } from "@/i18n";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import { resolveResource } from "@tauri-apps/api/path";
 
-type OverlayState = "recording" | "transcribing";
+type OverlayState = "recording" | "transcribing" | "injecting";
 
 const RecordingOverlay: React.FC = () => {
   const { t } = useTranslation();
@@ -104,6 +104,9 @@ const RecordingOverlay: React.FC = () => {
         {state === "transcribing" && (
           <div className="transcribing-text">{t("overlay.transcribing")}</div>
         )}
+        {state === "injecting" && (
+          <div className="transcribing-text">{t("overlay.injecting")}</div>
+        )}
       </div>
 
       <div className="overlay-right">
@@ -0,0 +1,93 @@
+import { listen } from "@tauri-apps/api/event";
+import React, { useEffect, useRef, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { commands, type PendingPreview } from "@/bindings";
+import { syncLanguageFromSettings } from "@/i18n";
+import "./PreviewPopup.css";
+
+const PreviewPopup: React.FC = () => {
+  const { t } = useTranslation();
+  const [preview, setPreview] = useState<PendingPreview | null>(null);
+  const [text, setText] = useState("");
+  const [isEditing, setIsEditing] = useState(false);
+  const textareaRef = useRef<HTMLTextAreaElement>(null);
+
+  const open = (pending: PendingPreview) => {
+    setPreview(pending);
+    setText(pending.text);
+    setIsEditing(false);
+  };
+
+  useEffect(() => {
+    // The first preview can be sent before this window has loaded
+    commands.getPendingPreview().then((pending) => {
+      if (pending) open(pending);
+    });
+
+    const unlisten = listen<PendingPreview>("show-preview", async (event) => {
+      await syncLanguageFromSettings();
+      open(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (isEditing) textareaRef.current?.focus();
+  }, [isEditing]);
+
+  const accept = () => {
+    if (!preview) return;
+    commands.acceptPreview(preview.id, text).then((result) => {
+      if (result.status === "error") console.error(result.error);
+    });
+    setPreview(null);
+  };
+
+  const discard = () => {
+    if (!preview) return;
+    commands.discardPreview(preview.id).then((result) => {
+      if (result.status === "error") console.error(result.error);
+    });
+    setPreview(null);
+  };
+
+  const onKeyDown = (event: React.KeyboardEvent) => {
+    if (event.key === "Escape") {
+      discard();
+    } else if (event.key === "Enter" && (event.ctrlKey || event.metaKey)) {
+      accept();
+    }
+  };
+
+  if (!preview) return null;
+
+  return (
+    <div className="preview-popup" onKeyDown={onKeyDown}>
+      <div className="preview-title">{t("preview.title")}</div>
+      <textarea
+        ref={textareaRef}
+        className="preview-text"
+        value={text}
+        readOnly={!isEditing}
+        onChange={(event) => setText(event.target.value)}
+      />
+      <div className="preview-buttons">
+        <button className="preview-discard" onClick={discard}>
+          {t("preview.discard")}
+        </button>
+        {!isEditing && (
+          <button onClick={() => setIsEditing(true)}>
+            {t("preview.edit")}
+          </button>
+        )}
+        <button className="preview-accept" onClick={accept} autoFocus>
+          {t("preview.accept")}
+        </button>
+      </div>
+    </div>
+  );
+};
+
+export default PreviewPopup;
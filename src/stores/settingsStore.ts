@@ -1,3 +1,4 @@
+import { listen } from "@tauri-apps/api/event";
 import { create } from "zustand";
 import { subscribeWithSelector } from "zustand/middleware";
 import type { AppSettings as Settings, AudioDevice } from "@/bindings";
@@ -522,6 +523,11 @@ export const useSettingsStore = create<SettingsStore>()(
         refreshOutputDevices(),
         checkCustomSounds(),
       ]);
+
+      // Sent for every saved section, including changes made outside this
+      // window: the settings file, the tray or a profile switch
+      await listen("settings-changed", () => refreshSettings());
+      await listen("profile-changed", () => refreshSettings());
     },
   })),
 );
@@ -0,0 +1,103 @@
+import { listen } from "@tauri-apps/api/event";
+import React, { useEffect, useRef, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { commands } from "@/bindings";
+import { syncLanguageFromSettings } from "@/i18n";
+import "./MiniWidget.css";
+
+type PipelineState =
+  | "idle"
+  | "recording"
+  | "transcribing"
+  | "post_processing"
+  | "injecting"
+  | "done"
+  | "error";
+
+interface PipelineEvent {
+  state: PipelineState;
+}
+
+const BUSY_STATES: PipelineState[] = [
+  "transcribing",
+  "post_processing",
+  "injecting",
+];
+
+const MiniWidget: React.FC = () => {
+  const { t } = useTranslation();
+  const [state, setState] = useState<PipelineState>("idle");
+  const [level, setLevel] = useState(0);
+  const [lastTranscript, setLastTranscript] = useState<string | null>(null);
+  const smoothedLevelRef = useRef(0);
+
+  const refreshTranscript = () => {
+    commands.getLastTranscript().then((result) => {
+      if (result.status === "ok") setLastTranscript(result.data);
+      else console.error(result.error);
+    });
+  };
+
+  useEffect(() => {
+    syncLanguageFromSettings();
+    refreshTranscript();
+
+    const unlistenState = listen<PipelineEvent>("pipeline-state", (event) => {
+      setState(event.payload.state);
+      if (event.payload.state !== "recording") {
+        smoothedLevelRef.current = 0;
+        setLevel(0);
+      }
+    });
+    const unlistenLevel = listen<number[]>("mic-level", (event) => {
+      const levels = event.payload;
+      const peak = levels.length ? Math.max(...levels) : 0;
+      smoothedLevelRef.current = smoothedLevelRef.current * 0.7 + peak * 0.3;
+      setLevel(smoothedLevelRef.current);
+    });
+    const unlistenHistory = listen("history-updated", refreshTranscript);
+
+    return () => {
+      unlistenState.then((fn) => fn());
+      unlistenLevel.then((fn) => fn());
+      unlistenHistory.then((fn) => fn());
+    };
+  }, []);
+
+  const isRecording = state === "recording";
+  const isBusy = BUSY_STATES.includes(state);
+
+  const toggleRecording = () => {
+    commands.toggleWidgetRecording().then((result) => {
+      if (result.status === "error") console.error(result.error);
+    });
+  };
+
+  return (
+    <div className="mini-widget" data-tauri-drag-region>
+      <button
+        className={`widget-record ${isRecording ? "recording" : ""}`}
+        onClick={toggleRecording}
+        disabled={isBusy}
+        title={isRecording ? t("widget.stop") : t("widget.record")}
+      >
+        <span className="widget-record-dot" />
+      </button>
+      <div className="widget-body" data-tauri-drag-region>
+        <div className="widget-meter" data-tauri-drag-region>
+          <div
+            className="widget-meter-fill"
+            style={{ width: `${Math.min(100, Math.pow(level, 0.7) * 100)}%` }}
+          />
+        </div>
+        <div className="widget-transcript" data-tauri-drag-region>
+          {isBusy
+            ? t("overlay.transcribing")
+            : lastTranscript || t("widget.noTranscript")}
+        </div>
+      </div>
+    </div>
+  );
+};
+
+export default MiniWidget;
@@ -23,6 +23,8 @@ export default defineConfig(async () => ({
       input: {
         main: resolve(__dirname, "index.html"),
         overlay: resolve(__dirname, "src/overlay/index.html"),
+        preview: resolve(__dirname, "src/preview/index.html"),
+        widget: resolve(__dirname, "src/widget/index.html"),
       },
     },
   },